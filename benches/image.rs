@@ -0,0 +1,43 @@
+//! Benchmark decoding of image data units
+//!
+//! Run with `cargo bench --bench image`
+
+use criterion::{criterion_group, criterion_main, Criterion, Throughput};
+use fits::HDU;
+
+/// Primary HDU bytes for a square image with the given BITPIX
+fn image_hdu(bitpix: i64, side: usize) -> Vec<u8> {
+    let cards = [
+        "SIMPLE  =                    T".to_string(),
+        format!("BITPIX  = {:>20}", bitpix),
+        "NAXIS   =                    2".to_string(),
+        format!("NAXIS1  = {:>20}", side),
+        format!("NAXIS2  = {:>20}", side),
+        "END".to_string(),
+    ];
+    let mut raw: Vec<u8> = cards
+        .iter()
+        .flat_map(|c| format!("{:<80}", c).into_bytes())
+        .collect();
+    raw.resize(2880, b' ');
+    let nbytes = side * side * (bitpix.unsigned_abs() as usize / 8);
+    raw.extend((0..nbytes).map(|i| (i % 251) as u8));
+    raw.resize(raw.len().div_ceil(2880) * 2880, 0);
+    raw
+}
+
+fn decode(c: &mut Criterion) {
+    let side = 2048;
+    let mut group = c.benchmark_group("image_from_bytes");
+    for bitpix in [16, 32, -32, -64] {
+        let raw = image_hdu(bitpix, side);
+        group.throughput(Throughput::Bytes(raw.len() as u64));
+        group.bench_function(format!("bitpix_{}", bitpix), |b| {
+            b.iter(|| HDU::from_bytes(&raw).unwrap())
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, decode);
+criterion_main!(benches);
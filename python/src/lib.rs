@@ -0,0 +1,438 @@
+//! Python bindings for the fits-rs library
+//!
+//! Build with `maturin develop` from this directory, then:
+//!
+//! ```python
+//! import fits_rs
+//! f = fits_rs.open("image.fits")
+//! print(f[0].header["NAXIS1"])
+//! pixels = f["SCI"].data           # numpy array, shape (NAXISn, ..., NAXIS1)
+//! table = f[2].to_pandas()         # pandas DataFrame
+//! ```
+
+use std::sync::Arc;
+
+use numpy::{PyArray1, PyArrayMethods};
+use pyo3::exceptions::{PyIOError, PyIndexError, PyKeyError, PyTypeError, PyValueError};
+use pyo3::prelude::*;
+use pyo3::types::{PyComplex, PyDict, PyList};
+
+use fits::{BinTable, BinTableValue, Bitpix, FITSError, HDUData, Image, KeywordValue, TFormType};
+
+/// Convert a library error to a Python exception
+fn py_error(e: FITSError) -> PyErr {
+    match e {
+        FITSError::Io(e) => PyIOError::new_err(e.to_string()),
+        FITSError::IndexOutOfRange(..) => PyIndexError::new_err(e.to_string()),
+        e => PyValueError::new_err(e.to_string()),
+    }
+}
+
+/// Python value of a header keyword
+fn keyword_value<'py>(py: Python<'py>, value: &KeywordValue) -> PyResult<Bound<'py, PyAny>> {
+    Ok(match value {
+        KeywordValue::None | KeywordValue::Undefined => py.None().into_bound(py),
+        KeywordValue::Bool(b) => b.into_pyobject(py)?.to_owned().into_any(),
+        KeywordValue::Int(i) => i.into_pyobject(py)?.into_any(),
+        KeywordValue::Float(f) => f.into_pyobject(py)?.into_any(),
+        KeywordValue::String(s) | KeywordValue::CommentText(s) => s.into_pyobject(py)?.into_any(),
+        KeywordValue::ComplexInt(r, i) => {
+            PyComplex::from_doubles(py, *r as f64, *i as f64).into_any()
+        }
+        KeywordValue::ComplexFloat(r, i) => PyComplex::from_doubles(py, *r, *i).into_any(),
+    })
+}
+
+/// Python value of a binary table cell
+fn cell_value<'py>(py: Python<'py>, value: &BinTableValue) -> PyResult<Bound<'py, PyAny>> {
+    Ok(match value {
+        BinTableValue::Null => py.None().into_bound(py),
+        BinTableValue::Logical(b) => b.into_pyobject(py)?.to_owned().into_any(),
+        BinTableValue::Bits(bits) => PyList::new(py, bits)?.into_any(),
+        BinTableValue::String(s) => s
+            .trim_end_matches([' ', '\0'])
+            .into_pyobject(py)?
+            .into_any(),
+        BinTableValue::Complex32(r, i) => {
+            PyComplex::from_doubles(py, *r as f64, *i as f64).into_any()
+        }
+        BinTableValue::Complex64(r, i) => PyComplex::from_doubles(py, *r, *i).into_any(),
+        BinTableValue::Array(values) => PyList::new(
+            py,
+            values
+                .iter()
+                .map(|v| cell_value(py, v))
+                .collect::<PyResult<Vec<_>>>()?,
+        )?
+        .into_any(),
+        BinTableValue::UInt64(v) => v.into_pyobject(py)?.into_any(),
+        BinTableValue::Int64(v) => v.into_pyobject(py)?.into_any(),
+        v => match v.as_f64() {
+            Some(f) if matches!(v, BinTableValue::Float32(_) | BinTableValue::Float64(_)) => {
+                f.into_pyobject(py)?.into_any()
+            }
+            Some(f) => (f as i64).into_pyobject(py)?.into_any(),
+            None => py.None().into_bound(py),
+        },
+    })
+}
+
+/// Typed numpy array of pixels, reshaped to the image axes
+macro_rules! typed_pixels {
+    ($py:expr, $image:expr, $t:ty, $shape:expr) => {
+        PyArray1::from_vec($py, $image.pixels_iter::<$t>().collect())
+            .reshape($shape)?
+            .into_any()
+    };
+}
+
+/// Image pixels as a numpy array
+///
+/// The shape is the reverse of the FITS axes, so that `data[y, x]`
+/// addresses pixel (x, y).  Pixels keep their stored type unless
+/// BSCALE, BZERO or BLANK apply, in which case the physical values are
+/// returned as float64 with NaN for undefined pixels
+fn image_array<'py>(py: Python<'py>, image: &Image) -> PyResult<Bound<'py, PyAny>> {
+    let shape: Vec<usize> = image.axes.iter().rev().copied().collect();
+    if image.blank.is_some() || image.bscale != 1.0 || image.bzero != 0.0 {
+        return Ok(PyArray1::from_vec(py, image.physical_pixels())
+            .reshape(shape)?
+            .into_any());
+    }
+    Ok(match image.pixeltype {
+        Bitpix::Int8 => typed_pixels!(py, image, u8, shape),
+        Bitpix::Int16 => typed_pixels!(py, image, i16, shape),
+        Bitpix::Int32 => typed_pixels!(py, image, i32, shape),
+        Bitpix::Int64 => typed_pixels!(py, image, i64, shape),
+        Bitpix::Uint16 => typed_pixels!(py, image, u16, shape),
+        Bitpix::Uint32 => typed_pixels!(py, image, u32, shape),
+        Bitpix::Uint64 => typed_pixels!(py, image, u64, shape),
+        Bitpix::Float32 => typed_pixels!(py, image, f32, shape),
+        Bitpix::Float64 => typed_pixels!(py, image, f64, shape),
+    })
+}
+
+/// Values of a binary table column
+///
+/// Numeric columns with one element per row are numpy arrays: float64
+/// (NaN for nulls) when scaled, null, or floating point, int64
+/// otherwise.  Logical columns are bool arrays; numeric columns with a
+/// fixed repeat count are 2-D float64 arrays.  Other columns are lists
+fn column<'py>(py: Python<'py>, table: &BinTable, col: usize) -> PyResult<Bound<'py, PyAny>> {
+    let cells = (0..table.nrows)
+        .map(|row| table.at_physical(row, col))
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(py_error)?;
+    let tform = &table.tform[col];
+    let numeric = !matches!(
+        tform.ftype,
+        TFormType::Logical
+            | TFormType::Bit
+            | TFormType::Char
+            | TFormType::Complex32
+            | TFormType::Complex64
+            | TFormType::ArrayD32(_)
+            | TFormType::ArrayD64(_)
+    );
+    let floats = |values: &[BinTableValue]| -> Vec<f64> {
+        values
+            .iter()
+            .map(|v| v.as_f64().unwrap_or(f64::NAN))
+            .collect()
+    };
+
+    if tform.ftype == TFormType::Logical && tform.repeat == 1 {
+        let values: Option<Vec<bool>> = cells
+            .iter()
+            .map(|v| match v {
+                BinTableValue::Logical(b) => Some(*b),
+                _ => None,
+            })
+            .collect();
+        if let Some(values) = values {
+            return Ok(PyArray1::from_vec(py, values).into_any());
+        }
+    }
+    if numeric && tform.repeat == 1 {
+        let integer = matches!(
+            tform.ftype,
+            TFormType::Byte | TFormType::Int16 | TFormType::Int32 | TFormType::Int64
+        ) && table.tscal[col].is_none()
+            && table.tzero[col].is_none();
+        if integer && !cells.contains(&BinTableValue::Null) {
+            let values: Vec<i64> = cells
+                .iter()
+                .map(|v| match v {
+                    // Exact, where 64-bit values may not be as f64
+                    BinTableValue::Int64(i) => *i,
+                    v => v.as_f64().map_or(0, |f| f as i64),
+                })
+                .collect();
+            return Ok(PyArray1::from_vec(py, values).into_any());
+        }
+        return Ok(PyArray1::from_vec(py, floats(&cells)).into_any());
+    }
+    if numeric && tform.repeat > 1 {
+        let values: Vec<f64> = cells
+            .iter()
+            .flat_map(|v| match v {
+                BinTableValue::Array(values) => floats(values),
+                v => floats(std::slice::from_ref(v)),
+            })
+            .collect();
+        return Ok(PyArray1::from_vec(py, values)
+            .reshape([table.nrows, tform.repeat])?
+            .into_any());
+    }
+    Ok(PyList::new(
+        py,
+        cells
+            .iter()
+            .map(|v| cell_value(py, v))
+            .collect::<PyResult<Vec<_>>>()?,
+    )?
+    .into_any())
+}
+
+/// Name of a table column, from TTYPEn
+fn column_name(table: &BinTable, col: usize) -> String {
+    table.ttype[col]
+        .clone()
+        .unwrap_or_else(|| format!("COL{}", col + 1))
+}
+
+/// Header of an HDU, with dictionary-style access to keyword values
+#[pyclass(name = "Header", module = "fits_rs", frozen)]
+struct PyHeader {
+    header: fits::Header,
+}
+
+#[pymethods]
+impl PyHeader {
+    fn __getitem__<'py>(&self, py: Python<'py>, name: &str) -> PyResult<Bound<'py, PyAny>> {
+        match self.header.value(&name.to_uppercase()) {
+            Some(value) => keyword_value(py, value),
+            None => Err(PyKeyError::new_err(name.to_string())),
+        }
+    }
+
+    fn __contains__(&self, name: &str) -> bool {
+        self.header.value(&name.to_uppercase()).is_some()
+    }
+
+    fn __len__(&self) -> usize {
+        self.header.iter().filter(|kw| kw.name != "END").count()
+    }
+
+    fn __str__(&self) -> String {
+        self.header
+            .to_bytes()
+            .chunks(80)
+            .map(|card| String::from_utf8_lossy(card).trim_end().to_string())
+            .take_while(|card| card != "END")
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    /// Value of a keyword, or `default` if absent
+    #[pyo3(signature = (name, default=None))]
+    fn get<'py>(
+        &self,
+        py: Python<'py>,
+        name: &str,
+        default: Option<Bound<'py, PyAny>>,
+    ) -> PyResult<Bound<'py, PyAny>> {
+        match self.header.value(&name.to_uppercase()) {
+            Some(value) => keyword_value(py, value),
+            None => Ok(default.unwrap_or_else(|| py.None().into_bound(py))),
+        }
+    }
+
+    /// Keyword names, in header order, without END
+    fn keys(&self) -> Vec<String> {
+        self.header
+            .iter()
+            .filter(|kw| kw.name != "END")
+            .map(|kw| kw.name.clone())
+            .collect()
+    }
+
+    /// Comment of a keyword
+    fn comment(&self, name: &str) -> PyResult<Option<String>> {
+        self.header
+            .iter()
+            .find(|kw| kw.name == name.to_uppercase())
+            .map(|kw| kw.comment.clone())
+            .ok_or_else(|| PyKeyError::new_err(name.to_string()))
+    }
+
+    /// Header as JSON text; see `Header::to_json`
+    fn to_json(&self) -> String {
+        self.header.to_json()
+    }
+}
+
+/// A header and data unit of an open file
+#[pyclass(name = "HDU", module = "fits_rs", frozen)]
+struct PyHDU {
+    file: Arc<fits::FITS>,
+    index: usize,
+}
+
+impl PyHDU {
+    fn hdu(&self) -> &fits::HDU {
+        &self.file.iter().as_slice()[self.index]
+    }
+}
+
+#[pymethods]
+impl PyHDU {
+    /// The header
+    #[getter]
+    fn header(&self) -> PyHeader {
+        PyHeader {
+            header: self.hdu().header.clone(),
+        }
+    }
+
+    /// Kind of data: "image", "bintable", "table", "none", or the
+    /// XTENSION of an uninterpreted extension
+    #[getter]
+    fn kind(&self) -> String {
+        match &self.hdu().data {
+            HDUData::None => "none".to_string(),
+            HDUData::Image(_) => "image".to_string(),
+            HDUData::BinTable(_) => "bintable".to_string(),
+            HDUData::Table(_) => "table".to_string(),
+            HDUData::Unknown { xtension, .. } => xtension.clone(),
+        }
+    }
+
+    /// Extension name, from EXTNAME
+    #[getter]
+    fn name(&self) -> Option<String> {
+        self.hdu().extname().map(str::to_string)
+    }
+
+    /// The data: a numpy array for images, a dictionary of columns for
+    /// binary tables, or None
+    #[getter]
+    fn data<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyAny>> {
+        match &self.hdu().data {
+            HDUData::Image(image) => image_array(py, image),
+            HDUData::BinTable(table) => {
+                let dict = PyDict::new(py);
+                for col in 0..table.ncols() {
+                    dict.set_item(column_name(table, col), column(py, table, col)?)?;
+                }
+                Ok(dict.into_any())
+            }
+            HDUData::Table(_) => Err(PyTypeError::new_err("ASCII tables are not supported")),
+            HDUData::None | HDUData::Unknown { .. } => Ok(py.None().into_bound(py)),
+        }
+    }
+
+    /// Binary table as a pandas DataFrame
+    ///
+    /// Multi-element columns hold one numpy array per row
+    fn to_pandas<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyAny>> {
+        let HDUData::BinTable(table) = &self.hdu().data else {
+            return Err(PyTypeError::new_err("HDU is not a binary table"));
+        };
+        let dict = PyDict::new(py);
+        for col in 0..table.ncols() {
+            let mut values = column(py, table, col)?;
+            if values
+                .getattr("ndim")
+                .is_ok_and(|n| n.extract::<usize>().ok() == Some(2))
+            {
+                values =
+                    PyList::new(py, values.try_iter()?.collect::<PyResult<Vec<_>>>()?)?.into_any();
+            }
+            dict.set_item(column_name(table, col), values)?;
+        }
+        py.import("pandas")?.getattr("DataFrame")?.call1((dict,))
+    }
+}
+
+/// An open FITS file
+#[pyclass(name = "FITS", module = "fits_rs", frozen)]
+struct PyFITS {
+    file: Arc<fits::FITS>,
+}
+
+#[pymethods]
+impl PyFITS {
+    #[new]
+    fn new(py: Python<'_>, path: &str) -> PyResult<Self> {
+        let file = py
+            .detach(|| fits::FITS::from_file(path))
+            .map_err(py_error)?;
+        Ok(PyFITS {
+            file: Arc::new(file),
+        })
+    }
+
+    fn __len__(&self) -> usize {
+        self.file.len()
+    }
+
+    /// HDU by position, or by extension name
+    fn __getitem__(&self, key: &Bound<'_, PyAny>) -> PyResult<PyHDU> {
+        let len = self.file.len() as isize;
+        let index = match key.extract::<isize>() {
+            Ok(i) if (-len..len).contains(&i) => i.rem_euclid(len) as usize,
+            Ok(i) => {
+                return Err(PyIndexError::new_err(format!(
+                    "HDU index {} out of range",
+                    i
+                )))
+            }
+            Err(_) => {
+                let name: String = key.extract()?;
+                self.file
+                    .iter()
+                    .position(|hdu| hdu.extname().is_some_and(|n| n.eq_ignore_ascii_case(&name)))
+                    .ok_or_else(|| PyKeyError::new_err(name))?
+            }
+        };
+        Ok(PyHDU {
+            file: self.file.clone(),
+            index,
+        })
+    }
+
+    fn __iter__(slf: PyRef<'_, Self>) -> PyResult<Bound<'_, PyAny>> {
+        let py = slf.py();
+        let hdus = (0..slf.file.len())
+            .map(|index| {
+                Bound::new(
+                    py,
+                    PyHDU {
+                        file: slf.file.clone(),
+                        index,
+                    },
+                )
+            })
+            .collect::<PyResult<Vec<_>>>()?;
+        PyList::new(py, hdus)?
+            .into_any()
+            .try_iter()
+            .map(Bound::into_any)
+    }
+}
+
+/// Open a FITS file
+#[pyfunction]
+fn open(py: Python<'_>, path: &str) -> PyResult<PyFITS> {
+    PyFITS::new(py, path)
+}
+
+#[pymodule]
+fn fits_rs(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<PyFITS>()?;
+    m.add_class::<PyHDU>()?;
+    m.add_class::<PyHeader>()?;
+    m.add_function(wrap_pyfunction!(open, m)?)?;
+    Ok(())
+}
@@ -0,0 +1,236 @@
+//! Angles written in sexagesimal notation, e.g. "12:34:56.7" or
+//! "-01d02m03s"
+
+use crate::FITSError;
+use crate::WCSError;
+
+/// An angle, stored in degrees
+///
+/// # Example
+///
+/// ```
+/// # use fits::Angle;
+/// # fn main() -> Result<(), fits::FITSError> {
+/// let ra = Angle::parse_hms("12:34:56.7")?;
+/// let dec = Angle::parse_dms("-01 02 03")?;
+/// assert_eq!(ra.to_hms(1), "12:34:56.7");
+/// println!("{} {}", ra.degrees(), dec.radians());
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, PartialOrd)]
+pub struct Angle {
+    degrees: f64,
+}
+
+/// Sign and fields of a sexagesimal string
+///
+/// Fields are separated by blanks, colons, or the unit letters and
+/// symbols `h m s d ° ' "`.  One to three fields are accepted
+fn split_fields(text: &str) -> Result<(bool, Vec<f64>), FITSError> {
+    let bad = || FITSError::from(WCSError::InvalidAngle(text.to_string()));
+    let trimmed = text.trim();
+    let (negative, rest) = match trimmed.strip_prefix('-') {
+        Some(rest) => (true, rest),
+        None => (false, trimmed.strip_prefix('+').unwrap_or(trimmed)),
+    };
+    let fields = rest
+        .split(|c: char| c.is_whitespace() || ":hmsd°'\"".contains(c))
+        .filter(|f| !f.is_empty())
+        .map(|f| f.parse::<f64>().map_err(|_| bad()))
+        .collect::<Result<Vec<_>, _>>()?;
+    if fields.is_empty() || fields.len() > 3 || fields.iter().any(|f| *f < 0.0) {
+        return Err(bad());
+    }
+    Ok((negative, fields))
+}
+
+/// Sexagesimal text of a non-negative value, rounded to `precision`
+/// decimals of the last field
+///
+/// # Returns
+///
+/// The whole units, minutes, and seconds, with seconds formatted
+///
+fn sexagesimal(value: f64, precision: usize) -> (u64, u64, String) {
+    let scale = 10u64.pow(precision as u32);
+    let ticks = (value * 3600.0 * scale as f64).round() as u64;
+    let units = ticks / (3600 * scale);
+    let minutes = ticks / (60 * scale) % 60;
+    let seconds = (ticks % (60 * scale)) as f64 / scale as f64;
+    let width = if precision > 0 { precision + 3 } else { 2 };
+    (
+        units,
+        minutes,
+        format!("{:0w$.p$}", seconds, w = width, p = precision),
+    )
+}
+
+impl Angle {
+    /// Angle from degrees
+    pub fn from_degrees(degrees: f64) -> Self {
+        Angle { degrees }
+    }
+
+    /// Angle from radians
+    pub fn from_radians(radians: f64) -> Self {
+        Angle {
+            degrees: radians.to_degrees(),
+        }
+    }
+
+    /// Angle from hours, minutes, and seconds of time
+    ///
+    /// # Arguments
+    ///
+    /// * `hours` - Hours; its sign (including -0.0) applies to the whole angle
+    /// * `minutes` - Minutes of time
+    /// * `seconds` - Seconds of time
+    ///
+    pub fn from_hms(hours: f64, minutes: f64, seconds: f64) -> Self {
+        let sign = if hours.is_sign_negative() { -1.0 } else { 1.0 };
+        Angle {
+            degrees: sign * 15.0 * (hours.abs() + minutes / 60.0 + seconds / 3600.0),
+        }
+    }
+
+    /// Angle from degrees, arcminutes, and arcseconds
+    ///
+    /// # Arguments
+    ///
+    /// * `degrees` - Degrees; its sign (including -0.0, for angles
+    ///   between -1 and 0 degrees) applies to the whole angle
+    /// * `arcmin` - Arcminutes
+    /// * `arcsec` - Arcseconds
+    ///
+    pub fn from_dms(degrees: f64, arcmin: f64, arcsec: f64) -> Self {
+        let sign = if degrees.is_sign_negative() {
+            -1.0
+        } else {
+            1.0
+        };
+        Angle {
+            degrees: sign * (degrees.abs() + arcmin / 60.0 + arcsec / 3600.0),
+        }
+    }
+
+    /// Parse an angle in hours, e.g. "12:34:56.7", "12h34m56.7s", or
+    /// "12 34 56.7"
+    ///
+    /// # Arguments
+    ///
+    /// * `text` - Hours, minutes, and seconds; trailing fields may be
+    ///   omitted, and the last field may have decimals
+    ///
+    /// # Returns
+    ///
+    /// The angle, or an error if the text is not sexagesimal
+    ///
+    pub fn parse_hms(text: &str) -> Result<Self, FITSError> {
+        let (negative, f) = split_fields(text)?;
+        let hours = if negative { -f[0] } else { f[0] };
+        Ok(Angle::from_hms(
+            hours,
+            f.get(1).copied().unwrap_or(0.0),
+            f.get(2).copied().unwrap_or(0.0),
+        ))
+    }
+
+    /// Parse an angle in degrees, e.g. "-01:02:03", "+45d30m00s", or
+    /// "12°34'56\""
+    ///
+    /// # Arguments
+    ///
+    /// * `text` - Degrees, arcminutes, and arcseconds; trailing fields
+    ///   may be omitted, and the last field may have decimals
+    ///
+    /// # Returns
+    ///
+    /// The angle, or an error if the text is not sexagesimal
+    ///
+    pub fn parse_dms(text: &str) -> Result<Self, FITSError> {
+        let (negative, f) = split_fields(text)?;
+        let degrees = if negative { -f[0] } else { f[0] };
+        Ok(Angle::from_dms(
+            degrees,
+            f.get(1).copied().unwrap_or(0.0),
+            f.get(2).copied().unwrap_or(0.0),
+        ))
+    }
+
+    /// Angle in degrees
+    pub fn degrees(&self) -> f64 {
+        self.degrees
+    }
+
+    /// Angle in radians
+    pub fn radians(&self) -> f64 {
+        self.degrees.to_radians()
+    }
+
+    /// Angle in hours
+    pub fn hours(&self) -> f64 {
+        self.degrees / 15.0
+    }
+
+    /// Format as hours, minutes, and seconds, "hh:mm:ss.s"
+    ///
+    /// The angle is wrapped to [0, 24) hours, as for a right ascension
+    ///
+    /// # Arguments
+    ///
+    /// * `precision` - Decimals of the seconds
+    ///
+    pub fn to_hms(&self, precision: usize) -> String {
+        let (hours, minutes, seconds) = sexagesimal(self.hours().rem_euclid(24.0), precision);
+        format!("{:02}:{:02}:{}", hours % 24, minutes, seconds)
+    }
+
+    /// Format as signed degrees, arcminutes, and arcseconds,
+    /// "+dd:mm:ss.s"
+    ///
+    /// # Arguments
+    ///
+    /// * `precision` - Decimals of the arcseconds
+    ///
+    pub fn to_dms(&self, precision: usize) -> String {
+        let (degrees, arcmin, arcsec) = sexagesimal(self.degrees.abs(), precision);
+        let sign = if self.degrees < 0.0 { '-' } else { '+' };
+        format!("{}{:02}:{:02}:{}", sign, degrees, arcmin, arcsec)
+    }
+}
+
+/// Signed degrees, arcminutes, and arcseconds, to 0.1 arcsecond
+impl std::fmt::Display for Angle {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}", self.to_dms(1))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_angle() {
+        let ra = Angle::parse_hms("12:34:56.7").unwrap();
+        assert!((ra.degrees() - 188.73625).abs() < 1e-10);
+        assert_eq!(ra.to_hms(1), "12:34:56.7");
+        assert_eq!(Angle::parse_hms("12h34m56.7s").unwrap(), ra);
+        assert_eq!(Angle::from_hms(12.0, 34.0, 56.7), ra);
+
+        let dec = Angle::parse_dms("-00 30 00").unwrap();
+        assert_eq!(dec.degrees(), -0.5);
+        assert_eq!(dec.to_dms(0), "-00:30:00");
+        assert_eq!(Angle::from_dms(-0.0, 30.0, 0.0), dec);
+        assert_eq!(
+            Angle::parse_dms("+45d30'15.25\"").unwrap().to_dms(2),
+            "+45:30:15.25"
+        );
+        assert_eq!(Angle::from_degrees(59.99999).to_dms(1), "+60:00:00.0");
+        assert_eq!(Angle::from_degrees(-15.0).to_hms(0), "23:00:00");
+        assert!((Angle::from_radians(std::f64::consts::PI).degrees() - 180.0).abs() < 1e-12);
+        assert!(Angle::parse_dms("12:ab").is_err());
+        assert!(Angle::parse_dms("1:2:3:4").is_err());
+    }
+}
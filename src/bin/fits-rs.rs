@@ -0,0 +1,212 @@
+//! Command-line tool for inspecting FITS files
+//!
+//! ```sh
+//! fits-rs header file.fits --hdu 2 --keyword DATE-OBS
+//! fits-rs info file.fits
+//! fits-rs diff a.fits b.fits --rtol 1e-6
+//! ```
+
+use std::io::{BufReader, Write};
+use std::process::ExitCode;
+
+use clap::{Parser, Subcommand};
+
+use fits::{DiffOptions, FITSError, FITSStream, FITSSummary, HDUSummary, Header, FITS};
+
+#[derive(Parser)]
+#[command(name = "fits-rs", version, about = "Inspect FITS files")]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Print header cards
+    Header {
+        /// FITS files to read
+        #[arg(required = true)]
+        files: Vec<String>,
+        /// HDU to print, by index (0 is the primary HDU) or EXTNAME;
+        /// may be repeated.  All HDUs are printed by default
+        #[arg(short = 'e', long = "hdu")]
+        hdus: Vec<String>,
+        /// Keyword to print; may be repeated.  All cards are printed
+        /// by default
+        #[arg(short, long = "keyword")]
+        keywords: Vec<String>,
+    },
+    /// List the HDUs of files: type, name, BITPIX, dimensions and size
+    Info {
+        /// FITS files to read
+        #[arg(required = true)]
+        files: Vec<String>,
+    },
+    /// Compare two files; exits with status 1 if they differ
+    Diff {
+        a: String,
+        b: String,
+        /// Relative tolerance for numbers
+        #[arg(long, default_value_t = 0.0)]
+        rtol: f64,
+        /// Absolute tolerance for numbers
+        #[arg(long, default_value_t = 0.0)]
+        atol: f64,
+        /// Keyword not to compare, in addition to CHECKSUM and
+        /// DATASUM; may be repeated
+        #[arg(short = 'k', long = "ignore-keyword")]
+        ignore_keywords: Vec<String>,
+    },
+}
+
+/// Does an HDU match one of the requested indices or extension names
+fn selected(hdus: &[String], index: usize, header: &Header) -> bool {
+    if hdus.is_empty() {
+        return true;
+    }
+    let extname = header.get::<String>("EXTNAME").ok();
+    hdus.iter().any(|h| match h.parse::<usize>() {
+        Ok(i) => i == index,
+        Err(_) => extname
+            .as_deref()
+            .is_some_and(|name| name.trim_end().eq_ignore_ascii_case(h)),
+    })
+}
+
+/// Print the selected header cards of the selected HDUs of a file
+///
+/// Headers are read with `FITSStream`, so data units are skipped
+/// rather than decoded
+///
+/// # Returns
+///
+/// Whether any HDU was selected
+///
+fn print_headers(
+    out: &mut impl Write,
+    file: &str,
+    hdus: &[String],
+    keywords: &[String],
+) -> Result<bool, FITSError> {
+    let mut stream = FITSStream::new(BufReader::new(std::fs::File::open(file)?));
+    let mut index = 0;
+    let mut found = false;
+    while let Some(header) = stream.next_header()? {
+        if selected(hdus, index, header) {
+            found = true;
+            writeln!(out, "# HDU {} in {}:", index, file)?;
+            for kw in header.iter() {
+                if !keywords.is_empty()
+                    && !keywords.iter().any(|k| k.eq_ignore_ascii_case(&kw.name))
+                {
+                    continue;
+                }
+                for card in kw.to_bytes().chunks(80) {
+                    writeln!(out, "{}", String::from_utf8_lossy(card).trim_end())?;
+                }
+            }
+            writeln!(out)?;
+        }
+        index += 1;
+    }
+    Ok(found)
+}
+
+/// Print a one-line summary of each HDU in a file
+///
+/// Like `print_headers`, only headers are read
+fn print_info(out: &mut impl Write, file: &str) -> Result<(), FITSError> {
+    let mut stream = FITSStream::new(BufReader::new(std::fs::File::open(file)?));
+    let mut summary = FITSSummary::default();
+    while let Some(header) = stream.next_header()? {
+        summary
+            .hdus
+            .push(HDUSummary::from_header(summary.hdus.len(), header));
+    }
+    writeln!(out, "Filename: {}", file)?;
+    writeln!(out, "{}", summary)?;
+    writeln!(out)?;
+    Ok(())
+}
+
+/// Report an error, unless it is a closed output pipe (e.g. `| head`)
+///
+/// # Returns
+///
+/// Whether processing should stop
+///
+fn report(file: &str, e: FITSError) -> bool {
+    if let FITSError::Io(e) = &e {
+        if e.kind() == std::io::ErrorKind::BrokenPipe {
+            return true;
+        }
+    }
+    eprintln!("{}: {}", file, e);
+    false
+}
+
+fn main() -> ExitCode {
+    let cli = Cli::parse();
+    let mut out = std::io::stdout().lock();
+    let mut status = ExitCode::SUCCESS;
+    match cli.command {
+        Command::Header {
+            files,
+            hdus,
+            keywords,
+        } => {
+            for file in &files {
+                match print_headers(&mut out, file, &hdus, &keywords) {
+                    Ok(true) => {}
+                    Ok(false) => {
+                        eprintln!("{}: no HDU matches {}", file, hdus.join(", "));
+                        status = ExitCode::FAILURE;
+                    }
+                    Err(e) => {
+                        status = ExitCode::FAILURE;
+                        if report(file, e) {
+                            break;
+                        }
+                    }
+                }
+            }
+        }
+        Command::Diff {
+            a,
+            b,
+            rtol,
+            atol,
+            ignore_keywords,
+        } => {
+            let mut options = DiffOptions {
+                rtol,
+                atol,
+                ..Default::default()
+            };
+            options.ignore_keywords.extend(ignore_keywords);
+            let read = |file: &str| FITS::from_file(file).map_err(|e| report(file, e));
+            let (fa, fb) = match (read(&a), read(&b)) {
+                (Ok(fa), Ok(fb)) => (fa, fb),
+                _ => return ExitCode::from(2),
+            };
+            let diff = fa.diff(&fb, &options);
+            if let Err(e) = writeln!(out, "{}", diff) {
+                report(&a, FITSError::from(e));
+            }
+            if !diff.is_identical() {
+                status = ExitCode::from(1);
+            }
+        }
+        Command::Info { files } => {
+            for file in &files {
+                if let Err(e) = print_info(&mut out, file) {
+                    status = ExitCode::FAILURE;
+                    if report(file, e) {
+                        break;
+                    }
+                }
+            }
+        }
+    }
+    status
+}
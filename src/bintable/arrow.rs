@@ -0,0 +1,144 @@
+//! Conversion of tables to Apache Arrow, enabled by the `arrow` feature
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use arrow_array::{
+    ArrayRef, BooleanArray, FixedSizeListArray, Float32Array, Float64Array, Int16Array, Int32Array,
+    Int64Array, Int8Array, ListArray, RecordBatch, StringArray, UInt16Array, UInt32Array,
+    UInt64Array, UInt8Array,
+};
+use arrow_buffer::OffsetBuffer;
+use arrow_schema::{ArrowError, DataType, Field, Schema};
+
+use super::export::{column_cells, column_layout, complex_parts, elements, ElementType, Layout};
+use super::BinTableValue;
+use crate::FITSError;
+use crate::TableLike;
+
+/// Convert an Arrow error
+fn arrow_error(e: ArrowError) -> FITSError {
+    FITSError::Arrow(e.to_string())
+}
+
+/// Arrow type of a single element; complex numbers are a fixed-size
+/// list of two floats, real part first
+fn data_type(etype: ElementType) -> DataType {
+    let complex = |t: DataType| DataType::FixedSizeList(Arc::new(Field::new("item", t, false)), 2);
+    match etype {
+        ElementType::Boolean => DataType::Boolean,
+        ElementType::String => DataType::Utf8,
+        ElementType::UInt8 => DataType::UInt8,
+        ElementType::Int8 => DataType::Int8,
+        ElementType::Int16 => DataType::Int16,
+        ElementType::UInt16 => DataType::UInt16,
+        ElementType::Int32 => DataType::Int32,
+        ElementType::UInt32 => DataType::UInt32,
+        ElementType::Int64 => DataType::Int64,
+        ElementType::UInt64 => DataType::UInt64,
+        ElementType::Float32 => DataType::Float32,
+        ElementType::Float64 => DataType::Float64,
+        ElementType::Complex32 => complex(DataType::Float32),
+        ElementType::Complex64 => complex(DataType::Float64),
+    }
+}
+
+/// Build a primitive Arrow array, with nulls where `$variant` does not match
+macro_rules! primitive {
+    ($array:ty, $values:expr, $variant:ident) => {
+        Arc::new(<$array>::from_iter($values.iter().map(|v| match v {
+            BinTableValue::$variant(x) => Some(*x),
+            _ => None,
+        }))) as ArrayRef
+    };
+}
+
+/// Build an Arrow array of elements of the given type
+fn element_array(dtype: &DataType, values: &[BinTableValue]) -> Result<ArrayRef, FITSError> {
+    Ok(match dtype {
+        DataType::Boolean => primitive!(BooleanArray, values, Logical),
+        DataType::UInt8 => primitive!(UInt8Array, values, Byte),
+        DataType::Int8 => primitive!(Int8Array, values, Int8),
+        DataType::Int16 => primitive!(Int16Array, values, Int16),
+        DataType::UInt16 => primitive!(UInt16Array, values, UInt16),
+        DataType::Int32 => primitive!(Int32Array, values, Int32),
+        DataType::UInt32 => primitive!(UInt32Array, values, UInt32),
+        DataType::Int64 => primitive!(Int64Array, values, Int64),
+        DataType::UInt64 => primitive!(UInt64Array, values, UInt64),
+        DataType::Float32 => primitive!(Float32Array, values, Float32),
+        DataType::Float64 => Arc::new(Float64Array::from_iter(
+            values.iter().map(BinTableValue::as_f64),
+        )),
+        DataType::Utf8 => Arc::new(StringArray::from_iter(values.iter().map(|v| match v {
+            BinTableValue::String(s) => Some(s.trim_end_matches([' ', '\0'])),
+            _ => None,
+        }))),
+        DataType::FixedSizeList(field, 2) => {
+            let parts: Vec<BinTableValue> = values.iter().flat_map(complex_parts).collect();
+            let parts = element_array(field.data_type(), &parts)?;
+            Arc::new(
+                FixedSizeListArray::try_new(field.clone(), 2, parts, None).map_err(arrow_error)?,
+            )
+        }
+        other => {
+            return Err(FITSError::Arrow(format!(
+                "unsupported element type {}",
+                other
+            )))
+        }
+    })
+}
+
+/// Convert a table to an Arrow record batch; see
+/// `TableLike::to_record_batch`
+pub(crate) fn record_batch<T: TableLike>(table: &T) -> Result<RecordBatch, FITSError> {
+    let mut fields = Vec::with_capacity(table.ncols());
+    let mut columns = Vec::with_capacity(table.ncols());
+    for (col, name) in table.column_names().into_iter().enumerate() {
+        let (etype, layout) = column_layout(table, col)?;
+        let etype = data_type(etype);
+        let cells = column_cells(table, col)?;
+
+        let item = Arc::new(Field::new("item", etype.clone(), true));
+        let (dtype, array): (DataType, ArrayRef) = match layout {
+            Layout::Scalar => (etype.clone(), element_array(&etype, &cells)?),
+            Layout::Fixed(repeat) => fixed_list(item, repeat, table.nrows(), cells)?,
+            Layout::Variable => {
+                let cells: Vec<Vec<BinTableValue>> = cells.into_iter().map(elements).collect();
+                let offsets = OffsetBuffer::from_lengths(cells.iter().map(Vec::len));
+                let values = element_array(&etype, &cells.concat())?;
+                (
+                    DataType::List(item.clone()),
+                    Arc::new(ListArray::try_new(item, offsets, values, None).map_err(arrow_error)?),
+                )
+            }
+        };
+
+        let mut field = Field::new(name, dtype, true);
+        if let Some(unit) = table.unit(col) {
+            field = field.with_metadata(HashMap::from([("unit".to_string(), unit.to_string())]));
+        }
+        fields.push(field);
+        columns.push(array);
+    }
+    RecordBatch::try_new(Arc::new(Schema::new(fields)), columns).map_err(arrow_error)
+}
+
+/// Fixed-size list column of `repeat` elements per row
+fn fixed_list(
+    item: Arc<Field>,
+    repeat: usize,
+    nrows: usize,
+    cells: Vec<BinTableValue>,
+) -> Result<(DataType, ArrayRef), FITSError> {
+    let values: Vec<BinTableValue> = cells.into_iter().flat_map(elements).collect();
+    let values = element_array(item.data_type(), &values)?;
+    let size = repeat as i32;
+    Ok((
+        DataType::FixedSizeList(item.clone(), size),
+        Arc::new(
+            FixedSizeListArray::try_new_with_length(item, size, values, None, nrows)
+                .map_err(arrow_error)?,
+        ),
+    ))
+}
@@ -0,0 +1,310 @@
+use super::BinTable;
+use crate::Bitpix;
+use crate::FITSError;
+use crate::Gti;
+use crate::HDUData;
+use crate::Header;
+use crate::Image;
+use crate::Keyword;
+use crate::KeywordValue;
+use crate::TableError;
+use crate::TableLike;
+use crate::WCS;
+use std::collections::HashMap;
+
+/// Keywords copied from an event table to its light curve, giving the
+/// time reference and the source of the data
+const LIGHTCURVE_KEYWORDS: [&str; 13] = [
+    "TELESCOP", "INSTRUME", "OBJECT", "TIMESYS", "TIMEREF", "TIMEUNIT", "MJDREF", "MJDREFI",
+    "MJDREFF", "TIMEZERO", "TSTART", "TSTOP", "DATE-OBS",
+];
+
+/// Binning of one axis: `n` bins of `width` starting at `lo`
+#[derive(Clone, Copy, Debug)]
+struct Axis {
+    lo: f64,
+    width: f64,
+    n: usize,
+}
+
+impl Axis {
+    /// Bin holding a value, or None if outside the axis
+    fn bin(&self, value: f64) -> Option<usize> {
+        let bin = ((value - self.lo) / self.width).floor();
+        match bin >= 0.0 && bin < self.n as f64 {
+            true => Some(bin as usize),
+            false => None,
+        }
+    }
+}
+
+impl BinTable {
+    /// Range of a pixel-coordinate column: TLMINn to TLMAXn if given,
+    /// else the smallest and largest finite values
+    fn column_limits(&self, col: usize, values: &[Option<f64>]) -> Option<(f64, f64)> {
+        let header = self.header();
+        let lo = header.get::<f64>(&format!("TLMIN{}", col + 1)).ok();
+        let hi = header.get::<f64>(&format!("TLMAX{}", col + 1)).ok();
+        let finite = || values.iter().flatten().copied().filter(|v| v.is_finite());
+        let lo = lo.or_else(|| finite().reduce(f64::min))?;
+        let hi = hi.or_else(|| finite().reduce(f64::max))?;
+        Some((lo, hi))
+    }
+
+    /// Bin an event list into an image
+    ///
+    /// Each axis spans the column's range, TLMINn to TLMAXn (or the
+    /// smallest to largest value), widened by half a unit at each end
+    /// so that integer pixel coordinates fall at bin centers; the range
+    /// is divided into `shape` equal bins.  Events outside the range
+    /// or with a null coordinate are skipped.
+    ///
+    /// If the columns carry a celestial coordinate description
+    /// (TCTYPn, TCRVLn, ...; see `wcs_for_column`), it is converted to
+    /// the image's pixel grid and attached as the image WCS
+    ///
+    /// # Arguments
+    ///
+    /// * `xcol` - Column of the first image axis, e.g. "X"
+    /// * `ycol` - Column of the second image axis, e.g. "Y"
+    /// * `shape` - Number of bins along each axis
+    /// * `weights` - Column whose values are summed in each bin; if
+    ///   None, events are counted
+    ///
+    /// # Returns
+    ///
+    /// A 32-bit integer image of counts, or a 64-bit float image of
+    /// summed weights
+    ///
+    pub fn bin_image(
+        &self,
+        xcol: &str,
+        ycol: &str,
+        shape: [usize; 2],
+        weights: Option<&str>,
+    ) -> Result<Image, FITSError> {
+        let cols = [self.column_index(xcol)?, self.column_index(ycol)?];
+        let values = [self.column::<f64>(xcol)?, self.column::<f64>(ycol)?];
+        let weights = weights.map(|w| self.column::<f64>(w)).transpose()?;
+
+        let mut axes = [Axis {
+            lo: 0.0,
+            width: 1.0,
+            n: 0,
+        }; 2];
+        for i in 0..2 {
+            let invalid = |why: &str| {
+                FITSError::from(TableError::InvalidBinning(format!(
+                    "column {}: {}",
+                    [xcol, ycol][i],
+                    why
+                )))
+            };
+            if shape[i] == 0 {
+                return Err(invalid("zero bins"));
+            }
+            let (lo, hi) = self
+                .column_limits(cols[i], &values[i])
+                .ok_or_else(|| invalid("no values"))?;
+            axes[i] = Axis {
+                lo: lo - 0.5,
+                width: (hi - lo + 1.0) / shape[i] as f64,
+                n: shape[i],
+            };
+        }
+
+        let mut sums = vec![0.0f64; shape[0] * shape[1]];
+        for row in 0..self.nrows() {
+            let (Some(x), Some(y)) = (values[0][row], values[1][row]) else {
+                continue;
+            };
+            let (Some(ix), Some(iy)) = (axes[0].bin(x), axes[1].bin(y)) else {
+                continue;
+            };
+            let weight = match &weights {
+                Some(w) => match w[row] {
+                    Some(w) if !w.is_nan() => w,
+                    _ => continue,
+                },
+                None => 1.0,
+            };
+            sums[iy * shape[0] + ix] += weight;
+        }
+
+        let (pixeltype, rawbytes) = match weights {
+            Some(_) => (Bitpix::Float64, bytemuck::cast_slice(&sums).to_vec()),
+            None => {
+                let counts: Vec<i32> = sums.iter().map(|c| *c as i32).collect();
+                (Bitpix::Int32, bytemuck::cast_slice(&counts).to_vec())
+            }
+        };
+        let wcs = match self.wcs_columns(cols[0]) == cols {
+            true => self
+                .wcs_for_column(cols[0])?
+                .map(|wcs| binned_wcs(wcs, &axes)),
+            false => None,
+        };
+        Ok(Image {
+            pixeltype,
+            axes: shape.to_vec(),
+            rawbytes,
+            bscale: 1.0,
+            bzero: 0.0,
+            blank: None,
+            bunit: None,
+            wcs,
+            alt_wcs: HashMap::new(),
+            native: true,
+        })
+    }
+
+    /// Bin the events of a time-tagged event table into a light curve
+    ///
+    /// Bins of `bin_width` start at the first GTI start, or at the
+    /// earliest event if no GTIs are given.  With GTIs, events outside
+    /// them are skipped, the exposure of each bin is the time it
+    /// overlaps the GTIs, and bins without exposure are dropped;
+    /// otherwise every bin has an exposure of `bin_width`
+    ///
+    /// # Arguments
+    ///
+    /// * `time_col` - Column of event times, e.g. "TIME"
+    /// * `bin_width` - Width of the bins, in the units of `time_col`
+    /// * `gti` - Good Time Intervals, if any
+    ///
+    /// # Returns
+    ///
+    /// Table (EXTNAME = 'RATE') with columns TIME (bin center), COUNTS,
+    /// RATE (counts per unit time), and ERROR (Poisson error of RATE).
+    /// Time reference keywords such as MJDREF and TIMESYS are copied
+    /// from this table
+    ///
+    pub fn bin_lightcurve(
+        &self,
+        time_col: &str,
+        bin_width: f64,
+        gti: Option<&Gti>,
+    ) -> Result<BinTable, FITSError> {
+        let invalid = |why: &str| FITSError::from(TableError::InvalidBinning(why.to_string()));
+        if !(bin_width > 0.0 && bin_width.is_finite()) {
+            return Err(invalid("bin width must be positive"));
+        }
+        let col = self.column_index(time_col)?;
+        let times: Vec<f64> = self
+            .column::<f64>(time_col)?
+            .into_iter()
+            .flatten()
+            .filter(|t| t.is_finite() && gti.is_none_or(|g| g.contains(*t)))
+            .collect();
+        let (start, stop) = match gti.map(|g| g.intervals()) {
+            Some([first, .., last]) => (first.0, last.1),
+            Some([only]) => *only,
+            _ => match (
+                times.iter().copied().reduce(f64::min),
+                times.iter().copied().reduce(f64::max),
+            ) {
+                (Some(lo), Some(hi)) => (lo, hi),
+                _ => return Err(invalid("no events to bin")),
+            },
+        };
+        let nbins = (((stop - start) / bin_width).ceil() as usize).max(1);
+
+        let mut counts = vec![0i32; nbins];
+        for t in times {
+            let bin = ((t - start) / bin_width).floor() as usize;
+            counts[bin.min(nbins - 1)] += 1;
+        }
+
+        let mut rawbytes = Vec::new();
+        let mut nrows = 0;
+        for (bin, count) in counts.into_iter().enumerate() {
+            let lo = start + bin as f64 * bin_width;
+            let exposure = match gti {
+                Some(gti) => gti
+                    .intersection(&Gti::new(&[(lo, lo + bin_width)]))
+                    .exposure(),
+                None => bin_width,
+            };
+            if exposure <= 0.0 {
+                continue;
+            }
+            rawbytes.extend((lo + 0.5 * bin_width).to_be_bytes());
+            rawbytes.extend(count.to_be_bytes());
+            rawbytes.extend((count as f64 / exposure).to_be_bytes());
+            rawbytes.extend(((count as f64).sqrt() / exposure).to_be_bytes());
+            nrows += 1;
+        }
+
+        let keyword = |name: &str, value: KeywordValue| Keyword {
+            name: name.to_string(),
+            value,
+            ..Default::default()
+        };
+        let string = |s: &str| KeywordValue::String(s.to_string());
+        let time_unit = self.unit(col).unwrap_or("s");
+        let mut keywords = vec![
+            keyword("XTENSION", string("BINTABLE")),
+            keyword("BITPIX", KeywordValue::Int(8)),
+            keyword("NAXIS", KeywordValue::Int(2)),
+            keyword("NAXIS1", KeywordValue::Int(28)),
+            keyword("NAXIS2", KeywordValue::Int(nrows)),
+            keyword("PCOUNT", KeywordValue::Int(0)),
+            keyword("GCOUNT", KeywordValue::Int(1)),
+            keyword("TFIELDS", KeywordValue::Int(4)),
+        ];
+        for (n, (name, tform, unit)) in [
+            ("TIME", "1D", time_unit.to_string()),
+            ("COUNTS", "1J", "count".to_string()),
+            ("RATE", "1D", format!("count/{}", time_unit)),
+            ("ERROR", "1D", format!("count/{}", time_unit)),
+        ]
+        .into_iter()
+        .enumerate()
+        {
+            keywords.push(keyword(&format!("TTYPE{}", n + 1), string(name)));
+            keywords.push(keyword(&format!("TFORM{}", n + 1), string(tform)));
+            keywords.push(keyword(&format!("TUNIT{}", n + 1), string(&unit)));
+        }
+        keywords.push(keyword("EXTNAME", string("RATE")));
+        for name in LIGHTCURVE_KEYWORDS {
+            if let Some(kw) = self.header().find(name) {
+                keywords.push(kw.clone());
+            }
+        }
+        keywords.push(keyword("TIMEDEL", KeywordValue::Float(bin_width)));
+        keywords.push(keyword("END", KeywordValue::None));
+
+        match BinTable::from_bytes(&Header::new(keywords), &rawbytes)? {
+            (HDUData::BinTable(table), _) => Ok(*table),
+            _ => unreachable!("BinTable::from_bytes returns a binary table"),
+        }
+    }
+}
+
+/// Convert a column WCS, whose pixel coordinates are column values, to
+/// the pixel grid of a binned image
+fn binned_wcs(mut wcs: WCS, axes: &[Axis; 2]) -> WCS {
+    // Bin p (zero-based) is centered on column value
+    // lo + (p + 0.5) * width, so the offset from the reference point in
+    // column units, v + 1 - crpix, is width * (p + 1 - crpix')
+    if let Some(crpix) = wcs.crpix.as_mut() {
+        for (c, axis) in crpix.iter_mut().zip(axes) {
+            *c = (*c - axis.lo - 1.0) / axis.width + 0.5;
+        }
+    }
+    if let Some(cd) = wcs.cd.as_mut() {
+        for (j, axis) in axes.iter().enumerate() {
+            cd.column_mut(j).scale_mut(axis.width);
+        }
+    } else if let Some(pc) = wcs.pc.as_mut() {
+        for (j, axis) in axes.iter().enumerate() {
+            pc.column_mut(j).scale_mut(axis.width);
+        }
+    } else {
+        let cdelt = wcs.cdelt.get_or_insert_with(|| vec![1.0; 2]);
+        for (c, axis) in cdelt.iter_mut().zip(axes) {
+            *c *= axis.width;
+        }
+    }
+    wcs
+}
@@ -0,0 +1,123 @@
+//! Adding and removing columns of a table
+
+use super::BinTable;
+use super::BinTableValue;
+use crate::FITSError;
+use crate::KeywordValue;
+use crate::TForm;
+use crate::TableError;
+
+impl BinTable {
+    /// Add a column after the last one
+    ///
+    /// Each row is re-laid out with the new field at its end, and
+    /// TTYPEn, TFORMn, TFIELDS, NAXIS1, PCOUNT, and THEAP (if present)
+    /// are updated in `header`, as `fits_insert_col` does in cfitsio
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - Name (TTYPEn) of the new column
+    /// * `tform` - Format (TFORMn) of the new column, e.g. "1J" or "1PE"
+    /// * `data` - Value of each row, as for `set`
+    ///
+    /// # Returns
+    ///
+    /// Nothing, or an error if a column named `name` already exists,
+    /// the format is invalid, or the values do not match the rows or
+    /// the format.  The table is unchanged on error
+    ///
+    pub fn add_column(
+        &mut self,
+        name: &str,
+        tform: &str,
+        data: &[BinTableValue],
+    ) -> Result<(), FITSError> {
+        if self.has_column(name) {
+            return Err(FITSError::from(TableError::DuplicateColumn(
+                name.to_string(),
+            )));
+        }
+        let tform = tform.parse::<TForm>()?;
+        if data.len() != self.nrows {
+            return Err(FITSError::from(TableError::ColumnLength(
+                data.len(),
+                self.nrows,
+            )));
+        }
+
+        // Describe the column first, so its values can be encoded
+        let (heap, header) = (self.heap.len(), self.header.clone());
+        let col = self.ncols();
+        let size = tform.size();
+        self.ttype.push(Some(name.to_string()));
+        self.tform.push(tform);
+        self.tunit.push(None);
+        self.tscal.push(None);
+        self.tzero.push(None);
+        self.tnull.push(None);
+        self.offsets.push(self.rowbytes);
+        let fields = match data
+            .iter()
+            .map(|v| self.encode_field(col, v))
+            .collect::<Result<Vec<_>, _>>()
+        {
+            Ok(fields) => fields,
+            Err(e) => {
+                self.ttype.pop();
+                self.tform.pop();
+                self.tunit.pop();
+                self.tscal.pop();
+                self.tzero.pop();
+                self.tnull.pop();
+                self.offsets.pop();
+                self.heap.truncate(heap);
+                self.header = header;
+                return Err(e);
+            }
+        };
+
+        let mut rawbytes = Vec::with_capacity(self.nrows * (self.rowbytes + size));
+        for (row, field) in fields.into_iter().enumerate() {
+            let start = row * self.rowbytes;
+            rawbytes.extend_from_slice(&self.rawbytes[start..start + self.rowbytes]);
+            rawbytes.extend(field);
+        }
+        // Keep the heap at the same position relative to the end of
+        // the main table, so descriptors still point to their arrays
+        self.theap = (self.theap + rawbytes.len()).saturating_sub(self.rawbytes.len());
+        self.rawbytes = rawbytes;
+        self.rowbytes += size;
+
+        self.header.set(
+            &format!("TTYPE{}", col + 1),
+            KeywordValue::String(name.to_string()),
+        );
+        self.header.set(
+            &format!("TFORM{}", col + 1),
+            KeywordValue::String(self.tform[col].to_string()),
+        );
+        self.set_layout_keywords();
+        Ok(())
+    }
+
+    /// Remove a column
+    ///
+    /// Each row is re-laid out without the field, and the keywords of
+    /// the following columns are renumbered, as `fits_delete_col` does
+    /// in cfitsio.  Heap arrays only used by the column are dropped
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - Name (TTYPEn) of the column, matched case-insensitively
+    ///
+    /// # Returns
+    ///
+    /// Nothing, or an error if no column is named `name`
+    ///
+    pub fn remove_column(&mut self, name: &str) -> Result<(), FITSError> {
+        let removed = self.column_index(name)?;
+        let cols: Vec<usize> = (0..self.ncols()).filter(|&c| c != removed).collect();
+        *self = self.subset(0..self.nrows, &cols)?;
+        Ok(())
+    }
+}
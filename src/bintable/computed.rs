@@ -0,0 +1,83 @@
+//! Columns computed from an expression over existing columns
+
+use super::expr::{Expression, Value};
+use super::BinTable;
+use super::BinTableValue;
+use crate::FITSError;
+use crate::TableError;
+
+impl BinTable {
+    /// Add a column computed from the other columns of each row
+    ///
+    /// The expression syntax is that of `select_rows`, e.g.
+    /// `"PHA * GAIN + OFFSET"`.  The type of the new column follows
+    /// from the values: numbers give a `D` column, with nulls stored as
+    /// NaN; booleans give an `L` column, with nulls stored as 0; strings
+    /// give an `nA` column as wide as the longest value.  TTYPEn,
+    /// TFORMn, TFIELDS, and NAXIS1 are updated in `header`
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - Name (TTYPEn) of the new column
+    /// * `expr` - Expression evaluated for each row
+    ///
+    /// # Returns
+    ///
+    /// Nothing, or an error if a column named `name` already exists,
+    /// the expression is invalid, or its values mix types
+    ///
+    pub fn add_computed_column(&mut self, name: &str, expr: &str) -> Result<(), FITSError> {
+        if self.has_column(name) {
+            return Err(FITSError::from(TableError::DuplicateColumn(
+                name.to_string(),
+            )));
+        }
+        let expr = Expression::parse(expr, self)?;
+        let values = (0..self.nrows)
+            .map(|row| expr.eval(self, row))
+            .collect::<Result<Vec<_>, _>>()?;
+        let (tform, cells) = column_values(&values)?;
+        self.add_column(name, &tform, &cells)
+    }
+}
+
+/// Choose a column format for computed values and convert them
+///
+/// # Returns
+///
+/// The format (TFORMn), and the value of each cell
+///
+fn column_values(values: &[Value]) -> Result<(String, Vec<BinTableValue>), FITSError> {
+    let kind = values
+        .iter()
+        .find(|v| **v != Value::Null)
+        .unwrap_or(&Value::Number(0.0));
+    let width = values
+        .iter()
+        .map(|v| match v {
+            Value::String(s) => s.len(),
+            _ => 0,
+        })
+        .max()
+        .unwrap_or(0)
+        .max(1);
+    let tform = match kind {
+        Value::Number(_) => "1D".to_string(),
+        Value::Bool(_) => "1L".to_string(),
+        _ => format!("{}A", width),
+    };
+    let cells = values
+        .iter()
+        .map(|v| match (kind, v) {
+            (_, Value::Null) => Ok(BinTableValue::Null),
+            (Value::Number(_), Value::Number(x)) => Ok(BinTableValue::Float64(*x)),
+            (Value::Bool(_), Value::Bool(b)) => Ok(BinTableValue::Logical(*b)),
+            (Value::String(_), Value::String(s)) => Ok(BinTableValue::String(s.clone())),
+            (kind, v) => Err(FITSError::from(TableError::InvalidExpression(format!(
+                "column values mix types: {:?} and {:?}",
+                kind, v
+            )))),
+        })
+        .collect::<Result<_, _>>()?;
+    Ok((tform, cells))
+}
@@ -0,0 +1,115 @@
+//! Vertical concatenation of tables with the same columns
+
+use super::BinTable;
+use crate::FITSError;
+use crate::KeywordValue;
+use crate::TableError;
+
+/// Are two optional strings equal, ignoring case and trailing blanks
+fn same_name(a: &Option<String>, b: &Option<String>) -> bool {
+    match (a, b) {
+        (Some(a), Some(b)) => a.trim_end().eq_ignore_ascii_case(b.trim_end()),
+        (a, b) => a.is_none() && b.is_none(),
+    }
+}
+
+impl BinTable {
+    /// Table with the rows of several tables, one after another
+    ///
+    /// All tables must have the same columns: the same number, names
+    /// (TTYPEn), formats (TFORMn), units (TUNITn), and scaling and null
+    /// values (TSCALn, TZEROn, TNULLn).  The heaps are merged, with
+    /// variable-length array descriptors re-based to the merged heap,
+    /// and the maximum array length of P/Q columns is the largest of
+    /// the tables.  The header is that of the first table, with NAXIS2,
+    /// PCOUNT, THEAP, and TFORMn updated
+    ///
+    /// # Arguments
+    ///
+    /// * `tables` - Tables to concatenate, in order
+    ///
+    /// # Returns
+    ///
+    /// The combined table, or an error if no tables are given or their
+    /// columns differ
+    ///
+    pub fn concat(tables: &[&BinTable]) -> Result<BinTable, FITSError> {
+        let incompatible =
+            |message: String| FITSError::from(TableError::IncompatibleTables(message));
+        let Some(first) = tables.first() else {
+            return Err(incompatible("no tables given".to_string()));
+        };
+        let mut tform = first.tform.clone();
+        for (n, table) in tables.iter().enumerate().skip(1) {
+            if table.ncols() != first.ncols() {
+                return Err(incompatible(format!(
+                    "table {} has {} columns, table 0 has {}",
+                    n,
+                    table.ncols(),
+                    first.ncols()
+                )));
+            }
+            for (col, merged) in tform.iter_mut().enumerate() {
+                let (a, b) = (&first.tform[col], &table.tform[col]);
+                let differs = if !same_name(&first.ttype[col], &table.ttype[col]) {
+                    Some("TTYPE")
+                } else if a.repeat != b.repeat || a.ftype != b.ftype {
+                    Some("TFORM")
+                } else if !same_name(&first.tunit[col], &table.tunit[col]) {
+                    Some("TUNIT")
+                } else if first.tscal[col] != table.tscal[col]
+                    || first.tzero[col] != table.tzero[col]
+                    || first.tnull[col] != table.tnull[col]
+                {
+                    Some("TSCAL, TZERO, or TNULL")
+                } else {
+                    None
+                };
+                if let Some(keyword) = differs {
+                    return Err(incompatible(format!(
+                        "{} of column {} differs between table 0 and table {}",
+                        keyword,
+                        col + 1,
+                        n
+                    )));
+                }
+                merged.maxlen = merged.maxlen.max(b.maxlen);
+            }
+        }
+
+        let cols: Vec<usize> = (0..first.ncols()).collect();
+        let mut rawbytes = Vec::new();
+        let mut heap = Vec::new();
+        let mut nrows = 0;
+        for table in tables {
+            nrows += table.copy_rows(0..table.nrows, &cols, &mut rawbytes, &mut heap)?;
+        }
+
+        let mut header = first.header.clone();
+        for (col, (new, old)) in tform.iter().zip(&first.tform).enumerate() {
+            if new != old {
+                header.set(
+                    &format!("TFORM{}", col + 1),
+                    KeywordValue::String(new.to_string()),
+                );
+            }
+        }
+        let mut table = BinTable {
+            nrows,
+            rowbytes: first.rowbytes,
+            ttype: first.ttype.clone(),
+            tform,
+            tunit: first.tunit.clone(),
+            tscal: first.tscal.clone(),
+            tzero: first.tzero.clone(),
+            tnull: first.tnull.clone(),
+            theap: rawbytes.len(),
+            rawbytes,
+            heap,
+            offsets: first.offsets.clone(),
+            header,
+        };
+        table.set_layout_keywords();
+        Ok(table)
+    }
+}
@@ -0,0 +1,204 @@
+//! Export of binary tables as delimited text (CSV, TSV)
+
+use std::io::Write;
+
+use super::BinTable;
+use super::BinTableValue;
+use super::TDisp;
+use crate::FITSError;
+use crate::TableLike;
+
+/// How fixed-size array cells are written
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ArrayStyle {
+    /// One column per element, named `NAME_1`, `NAME_2`, ...
+    Columns,
+    /// A single column holding a JSON array, e.g. `[1,2,3]`
+    Json,
+}
+
+/// Options for `BinTable::to_csv` and `Table::to_csv`
+#[derive(Clone, Debug)]
+pub struct CsvOptions {
+    /// Field separator
+    pub delimiter: char,
+    /// Write a first line with the column names
+    pub header: bool,
+    /// How fixed-size array cells are written; variable-length arrays
+    /// are always written as JSON arrays
+    pub arrays: ArrayStyle,
+    /// Text written for null values (TNULLn, and NaN floats)
+    pub null: String,
+    /// Apply TSCALn and TZEROn; see `BinTable::at_physical`
+    pub physical: bool,
+}
+
+impl Default for CsvOptions {
+    fn default() -> Self {
+        CsvOptions {
+            delimiter: ',',
+            header: true,
+            arrays: ArrayStyle::Columns,
+            null: String::new(),
+            physical: true,
+        }
+    }
+}
+
+impl CsvOptions {
+    /// Options for tab-separated output
+    pub fn tsv() -> Self {
+        CsvOptions {
+            delimiter: '\t',
+            ..Default::default()
+        }
+    }
+}
+
+/// Format a scalar value as text, or None if it is null
+///
+/// Numbers follow the TDISPn format of the column if given, without
+/// the padding to the field width
+fn scalar_text(value: &BinTableValue, tdisp: Option<&TDisp>) -> Option<String> {
+    match value {
+        BinTableValue::Null => None,
+        BinTableValue::Float32(v) if v.is_nan() => None,
+        BinTableValue::Float64(v) if v.is_nan() => None,
+        BinTableValue::Logical(b) => Some(if *b { "T" } else { "F" }.to_string()),
+        BinTableValue::String(s) => Some(s.trim_end_matches([' ', '\0']).to_string()),
+        value => match tdisp {
+            Some(tdisp) => Some(tdisp.format(value).trim().to_string()),
+            None => match value {
+                BinTableValue::Complex32(r, i) => Some(format!("({}, {})", r, i)),
+                BinTableValue::Complex64(r, i) => Some(format!("({}, {})", r, i)),
+                other => Some(other.to_string()),
+            },
+        },
+    }
+}
+
+/// Format a value as JSON
+fn json_text(value: &BinTableValue, tdisp: Option<&TDisp>) -> String {
+    match value {
+        BinTableValue::Array(values) => {
+            let items: Vec<String> = values.iter().map(|v| json_text(v, tdisp)).collect();
+            format!("[{}]", items.join(","))
+        }
+        BinTableValue::Logical(b) => b.to_string(),
+        BinTableValue::String(_) => match scalar_text(value, tdisp) {
+            Some(s) => format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\"")),
+            None => "null".to_string(),
+        },
+        BinTableValue::Complex32(r, i) => format!("[{},{}]", r, i),
+        BinTableValue::Complex64(r, i) => format!("[{},{}]", r, i),
+        _ => scalar_text(value, tdisp).unwrap_or_else(|| "null".to_string()),
+    }
+}
+
+/// Quote a field if it contains the delimiter, a quote, or a line break
+fn quote(field: &str, delimiter: char) -> String {
+    if field.contains([delimiter, '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+/// Number of text columns written for a table column
+fn csv_width<T: TableLike>(table: &T, col: usize, options: &CsvOptions) -> usize {
+    match (table.repeat(col), options.arrays) {
+        (None, _) => 1,
+        (Some(repeat), ArrayStyle::Columns) => repeat,
+        (Some(repeat), ArrayStyle::Json) => repeat.min(1),
+    }
+}
+
+/// Write an ASCII or binary table as delimited text; see
+/// `BinTable::to_csv`
+pub(crate) fn write_csv<T: TableLike, W: Write>(
+    table: &T,
+    mut writer: W,
+    options: &CsvOptions,
+) -> Result<(), FITSError> {
+    let delimiter = options.delimiter.to_string();
+    let tdisp: Vec<Option<TDisp>> = (0..table.ncols())
+        .map(|col| {
+            table
+                .header()
+                .get::<String>(&format!("TDISP{}", col + 1))
+                .ok()
+                .and_then(|t| t.parse().ok())
+        })
+        .collect();
+
+    if options.header {
+        let mut names = Vec::new();
+        for (col, name) in table.column_names().into_iter().enumerate() {
+            match csv_width(table, col, options) {
+                1 => names.push(quote(&name, options.delimiter)),
+                width => names.extend(
+                    (1..=width).map(|i| quote(&format!("{}_{}", name, i), options.delimiter)),
+                ),
+            }
+        }
+        writeln!(writer, "{}", names.join(&delimiter))?;
+    }
+
+    for row in 0..table.nrows() {
+        let mut fields = Vec::new();
+        for (col, tdisp) in tdisp.iter().enumerate() {
+            let tdisp = tdisp.as_ref();
+            let value = match options.physical {
+                true => table.at_physical(row, col)?,
+                false => table.at(row, col)?,
+            };
+            let width = csv_width(table, col, options);
+            let fixed = table.repeat(col).is_some();
+            let text =
+                |v: &BinTableValue| scalar_text(v, tdisp).unwrap_or_else(|| options.null.clone());
+            match value {
+                BinTableValue::Array(values) if fixed && options.arrays == ArrayStyle::Columns => {
+                    fields.extend(values.iter().map(|v| quote(&text(v), options.delimiter)))
+                }
+                BinTableValue::Array(_) => {
+                    fields.push(quote(&json_text(&value, tdisp), options.delimiter))
+                }
+                _ if width == 0 => {}
+                value => fields.push(quote(&text(&value), options.delimiter)),
+            }
+        }
+        writeln!(writer, "{}", fields.join(&delimiter))?;
+    }
+    Ok(())
+}
+
+impl BinTable {
+    /// Write the table as delimited text
+    ///
+    /// Columns are named from TTYPEn (`COLn` if absent).  Numbers are
+    /// formatted with TDISPn when present (see `TDisp::format`), without
+    /// padding.  Null values are written as `options.null`
+    ///
+    /// # Arguments
+    ///
+    /// * `writer` - Destination of the text
+    /// * `options` - Delimiter, array style and null text; see `CsvOptions`
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use fits::CsvOptions;
+    /// # use fits::{HDUData, TableLike, FITS};
+    /// # fn main() -> Result<(), fits::FITSError> {
+    /// # let fits = FITS::from_file("catalog.fits")?;
+    /// # let HDUData::BinTable(table) = &fits[1].data else { return Ok(()) };
+    /// let file = std::fs::File::create("catalog.csv")?;
+    /// table.to_csv(std::io::BufWriter::new(file), &CsvOptions::default())?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    pub fn to_csv<W: Write>(&self, writer: W, options: &CsvOptions) -> Result<(), FITSError> {
+        write_csv(self, writer, options)
+    }
+}
@@ -0,0 +1,290 @@
+//! Encoding of cell values into table bytes, for editing tables
+
+use super::BinTable;
+use super::BinTableValue;
+use crate::FITSError;
+use crate::KeywordValue;
+use crate::TFormType;
+use crate::TableError;
+
+/// Encode one element of a numeric, logical, or complex field
+///
+/// # Returns
+///
+/// The big-endian bytes, or the reason the value does not fit
+///
+fn encode_element(
+    ftype: &TFormType,
+    value: &BinTableValue,
+    tnull: Option<i64>,
+) -> Result<Vec<u8>, String> {
+    let mismatch = || format!("{:?} cannot be stored as {:?}", value, ftype);
+    let integer = || -> Result<i128, String> {
+        match value {
+            BinTableValue::Null => tnull
+                .map(|v| v as i128)
+                .ok_or_else(|| "null value without TNULL".to_string()),
+            v => v.as_integer().ok_or_else(mismatch),
+        }
+    };
+    let real = || match value {
+        BinTableValue::Null => Ok(f64::NAN),
+        v => v.as_f64().ok_or_else(mismatch),
+    };
+    let out_of_range = |_| format!("{:?} out of range for {:?}", value, ftype);
+    Ok(match ftype {
+        TFormType::Logical => match value {
+            BinTableValue::Logical(true) => vec![b'T'],
+            BinTableValue::Logical(false) => vec![b'F'],
+            BinTableValue::Null => vec![0],
+            _ => return Err(mismatch()),
+        },
+        TFormType::Byte => vec![u8::try_from(integer()?).map_err(out_of_range)?],
+        TFormType::Int16 => i16::try_from(integer()?)
+            .map_err(out_of_range)?
+            .to_be_bytes()
+            .to_vec(),
+        TFormType::Int32 => i32::try_from(integer()?)
+            .map_err(out_of_range)?
+            .to_be_bytes()
+            .to_vec(),
+        TFormType::Int64 => i64::try_from(integer()?)
+            .map_err(out_of_range)?
+            .to_be_bytes()
+            .to_vec(),
+        TFormType::Float32 => (real()? as f32).to_be_bytes().to_vec(),
+        TFormType::Float64 => real()?.to_be_bytes().to_vec(),
+        TFormType::Complex32 | TFormType::Complex64 => {
+            let (re, im) = match value {
+                BinTableValue::Complex32(re, im) => (*re as f64, *im as f64),
+                BinTableValue::Complex64(re, im) => (*re, *im),
+                BinTableValue::Null => (f64::NAN, f64::NAN),
+                v => (v.as_f64().ok_or_else(mismatch)?, 0.0),
+            };
+            match ftype {
+                TFormType::Complex32 => [(re as f32).to_be_bytes(), (im as f32).to_be_bytes()]
+                    .concat()
+                    .to_vec(),
+                _ => [re.to_be_bytes(), im.to_be_bytes()].concat().to_vec(),
+            }
+        }
+        _ => return Err(mismatch()),
+    })
+}
+
+/// Encode `repeat` elements of the given type, the inverse of `decode`
+///
+/// Strings shorter than the field are padded with blanks, and bit
+/// arrays shorter than the field with zeros.  With `repeat` = None
+/// (variable-length arrays), all elements of the value are encoded
+///
+/// # Returns
+///
+/// The bytes and number of elements, or the reason the value does not
+/// fit
+///
+fn encode(
+    ftype: &TFormType,
+    repeat: Option<usize>,
+    value: &BinTableValue,
+    tnull: Option<i64>,
+) -> Result<(Vec<u8>, usize), String> {
+    let too_long = |n: usize| match repeat {
+        Some(r) if n > r => Err(format!("{} elements do not fit in {}", n, r)),
+        _ => Ok(repeat.unwrap_or(n)),
+    };
+    match (ftype, value) {
+        (TFormType::Char, BinTableValue::String(s)) => {
+            let n = too_long(s.len())?;
+            let mut bytes = s.as_bytes().to_vec();
+            bytes.resize(n, b' ');
+            Ok((bytes, n))
+        }
+        (TFormType::Char, BinTableValue::Null) => {
+            let n = repeat.unwrap_or(0);
+            Ok((vec![b' '; n], n))
+        }
+        (TFormType::Bit, BinTableValue::Bits(bits)) => {
+            let n = too_long(bits.len())?;
+            let mut bytes = vec![0u8; n.div_ceil(8)];
+            for (i, _) in bits.iter().enumerate().filter(|(_, b)| **b) {
+                bytes[i / 8] |= 0x80 >> (i % 8);
+            }
+            Ok((bytes, n))
+        }
+        (_, BinTableValue::Array(values)) => {
+            if repeat.is_some_and(|r| r != values.len()) {
+                return Err(format!(
+                    "{} elements given for a field of {}",
+                    values.len(),
+                    repeat.unwrap_or(0)
+                ));
+            }
+            let bytes = values
+                .iter()
+                .map(|v| encode_element(ftype, v, tnull))
+                .collect::<Result<Vec<_>, _>>()?;
+            Ok((bytes.concat(), values.len()))
+        }
+        (_, value) if repeat.unwrap_or(1) == 1 => Ok((encode_element(ftype, value, tnull)?, 1)),
+        (_, value) => Err(format!(
+            "{:?} is not an array of {}",
+            value,
+            repeat.unwrap_or(0)
+        )),
+    }
+}
+
+/// Append a variable-length array descriptor to a row
+pub(super) fn push_descriptor(
+    row: &mut Vec<u8>,
+    ftype: &TFormType,
+    count: usize,
+    offset: usize,
+    col: usize,
+) -> Result<(), FITSError> {
+    match ftype {
+        TFormType::ArrayD32(_) => {
+            let too_large = || FITSError::from(TableError::HeapOutOfRange(col));
+            row.extend((u32::try_from(count).map_err(|_| too_large())?).to_be_bytes());
+            row.extend((u32::try_from(offset).map_err(|_| too_large())?).to_be_bytes());
+        }
+        _ => {
+            row.extend((count as u64).to_be_bytes());
+            row.extend((offset as u64).to_be_bytes());
+        }
+    }
+    Ok(())
+}
+
+impl BinTable {
+    /// Encode a value as the bytes of a field in the main table
+    ///
+    /// Variable-length arrays are appended to the heap, and the
+    /// returned descriptor points to them; PCOUNT, and TFORMn if the
+    /// array is longer than its maximum length, are updated in `header`
+    pub(super) fn encode_field(
+        &mut self,
+        col: usize,
+        value: &BinTableValue,
+    ) -> Result<Vec<u8>, FITSError> {
+        let invalid = |reason: String| FITSError::from(TableError::IncompatibleValue(col, reason));
+        let tform = &self.tform[col];
+        let etype = match &tform.ftype {
+            TFormType::ArrayD32(etype) | TFormType::ArrayD64(etype) => etype.as_ref(),
+            ftype => {
+                let (bytes, _) =
+                    encode(ftype, Some(tform.repeat), value, self.tnull[col]).map_err(invalid)?;
+                return Ok(bytes);
+            }
+        };
+
+        let (bytes, count) = encode(etype, None, value, self.tnull[col]).map_err(invalid)?;
+        // Heap offsets are relative to THEAP; see `heap_array`
+        let offset = (self.heap.len() + self.rawbytes.len())
+            .checked_sub(self.theap)
+            .ok_or(TableError::HeapOutOfRange(col))?;
+        let mut field = Vec::with_capacity(tform.size());
+        push_descriptor(&mut field, &tform.ftype, count, offset, col)?;
+        self.heap.extend(bytes);
+        self.header
+            .set("PCOUNT", KeywordValue::Int(self.heap.len() as i64));
+        if self.tform[col].maxlen.is_some_and(|m| m < count) {
+            self.tform[col].maxlen = Some(count);
+            self.header.set(
+                &format!("TFORM{}", col + 1),
+                KeywordValue::String(self.tform[col].to_string()),
+            );
+        }
+        Ok(field)
+    }
+
+    /// Set the value of a table cell
+    ///
+    /// The value is the stored value, as returned by `at`; it is not
+    /// scaled by TSCALn and TZEROn.  Integers are stored in integer
+    /// columns if they fit, and any number in real columns; `Null` is
+    /// stored as TNULLn in integer columns and NaN in real columns.
+    /// Vector fields take an `Array` with one value per element, and
+    /// strings are padded with blanks.  A new variable-length array is appended to the
+    /// heap; the heap bytes of the old one are left unused
+    ///
+    /// # Arguments
+    ///
+    /// * `row` - Row index (zero-based)
+    /// * `col` - Column index (zero-based)
+    /// * `value` - New value
+    ///
+    /// # Returns
+    ///
+    /// Nothing, or an error if the cell is out of range or the value
+    /// cannot be stored in the column
+    ///
+    pub fn set(&mut self, row: usize, col: usize, value: &BinTableValue) -> Result<(), FITSError> {
+        if row >= self.nrows {
+            return Err(FITSError::from(TableError::RowOutOfRange(row, self.nrows)));
+        }
+        if col >= self.ncols() {
+            return Err(FITSError::from(TableError::ColumnOutOfRange(
+                col,
+                self.ncols(),
+            )));
+        }
+        let field = self.encode_field(col, value)?;
+        let start = row * self.rowbytes + self.offsets[col];
+        self.rawbytes[start..start + field.len()].copy_from_slice(&field);
+        Ok(())
+    }
+
+    /// Append a row
+    ///
+    /// Values are given as for `set`, one per column.  NAXIS2, PCOUNT,
+    /// and THEAP (if present) are updated in `header`, so rows can be
+    /// accumulated one at a time and the table written when complete
+    ///
+    /// # Arguments
+    ///
+    /// * `values` - Value of each column
+    ///
+    /// # Returns
+    ///
+    /// Nothing, or an error if the number of values differs from the
+    /// number of columns or a value cannot be stored in its column.
+    /// The table is unchanged on error
+    ///
+    pub fn push_row(&mut self, values: &[BinTableValue]) -> Result<(), FITSError> {
+        if values.len() != self.ncols() {
+            return Err(FITSError::from(TableError::RowLength(
+                values.len(),
+                self.ncols(),
+            )));
+        }
+        // Only variable-length arrays change the heap and header
+        let saved = self
+            .tform
+            .iter()
+            .any(|t| matches!(t.ftype, TFormType::ArrayD32(_) | TFormType::ArrayD64(_)))
+            .then(|| (self.heap.len(), self.header.clone(), self.tform.clone()));
+        let mut row = Vec::with_capacity(self.rowbytes);
+        for (col, value) in values.iter().enumerate() {
+            match self.encode_field(col, value) {
+                Ok(field) => row.extend(field),
+                Err(e) => {
+                    if let Some((heap, header, tform)) = saved {
+                        self.heap.truncate(heap);
+                        self.header = header;
+                        self.tform = tform;
+                    }
+                    return Err(e);
+                }
+            }
+        }
+        self.rawbytes.extend(row);
+        // Keep the heap at the same position relative to the end of
+        // the main table, so descriptors still point to their arrays
+        self.theap += self.rowbytes;
+        self.nrows += 1;
+        self.set_layout_keywords();
+        Ok(())
+    }
+}
@@ -0,0 +1,116 @@
+//! Column layout shared by the Arrow and Polars conversions
+
+use super::BinTableValue;
+use super::TFormType;
+use crate::FITSError;
+use crate::TableError;
+use crate::TableLike;
+
+/// Type of a single element of a column, after scaling
+///
+/// Mirrors the values returned by `at_physical`: offset-binary integers
+/// become the matching unsigned (or signed byte) type, other scaled
+/// numbers become Float64, and scaled complex numbers Complex64
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum ElementType {
+    Boolean,
+    String,
+    UInt8,
+    Int8,
+    Int16,
+    UInt16,
+    Int32,
+    UInt32,
+    Int64,
+    UInt64,
+    Float32,
+    Float64,
+    Complex32,
+    Complex64,
+}
+
+/// How the elements of a column make up each cell
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Layout {
+    /// One element per cell; strings are a single element
+    Scalar,
+    /// The given number of elements per cell
+    Fixed(usize),
+    /// Variable-length arrays
+    Variable,
+}
+
+/// Element type of values of the given binary table type
+fn element_type(ftype: &TFormType, scaling: Option<(f64, f64)>) -> ElementType {
+    match (ftype, scaling) {
+        (TFormType::Logical | TFormType::Bit, _) => ElementType::Boolean,
+        (TFormType::Char, _) => ElementType::String,
+        (TFormType::ArrayD32(etype) | TFormType::ArrayD64(etype), _) => {
+            element_type(etype, scaling)
+        }
+        (TFormType::Byte, None) => ElementType::UInt8,
+        (TFormType::Int16, None) => ElementType::Int16,
+        (TFormType::Int32, None) => ElementType::Int32,
+        (TFormType::Int64, None) => ElementType::Int64,
+        (TFormType::Float32, None) => ElementType::Float32,
+        (TFormType::Float64, None) => ElementType::Float64,
+        (TFormType::Complex32, None) => ElementType::Complex32,
+        (TFormType::Complex64, None) => ElementType::Complex64,
+        (TFormType::Byte, Some((1.0, -128.0))) => ElementType::Int8,
+        (TFormType::Int16, Some((1.0, 32768.0))) => ElementType::UInt16,
+        (TFormType::Int32, Some((1.0, 2147483648.0))) => ElementType::UInt32,
+        (TFormType::Int64, Some((1.0, 9223372036854775808.0))) => ElementType::UInt64,
+        (TFormType::Complex32 | TFormType::Complex64, Some(_)) => ElementType::Complex64,
+        (_, Some(_)) => ElementType::Float64,
+    }
+}
+
+/// Element type and layout of a column
+///
+/// Character columns, including variable-length ones, are single
+/// strings; bit fields and repeat counts other than one are fixed-size
+pub(crate) fn column_layout<T: TableLike>(
+    table: &T,
+    col: usize,
+) -> Result<(ElementType, Layout), FITSError> {
+    let tform = table
+        .column_format(col)
+        .ok_or(TableError::ColumnOutOfRange(col, table.ncols()))?;
+    let etype = element_type(&tform.ftype, table.scaling(col));
+    let layout = match tform.ftype {
+        _ if etype == ElementType::String => Layout::Scalar,
+        TFormType::ArrayD32(_) | TFormType::ArrayD64(_) => Layout::Variable,
+        _ if tform.ftype == TFormType::Bit || tform.repeat != 1 => Layout::Fixed(tform.repeat),
+        _ => Layout::Scalar,
+    };
+    Ok((etype, layout))
+}
+
+/// Physical values of a column, one per row
+pub(crate) fn column_cells<T: TableLike>(
+    table: &T,
+    col: usize,
+) -> Result<Vec<BinTableValue>, FITSError> {
+    (0..table.nrows())
+        .map(|row| table.at_physical(row, col))
+        .collect()
+}
+
+/// Elements of a cell value; arrays and bit fields are split into
+/// their elements, other values are a single element
+pub(crate) fn elements(value: BinTableValue) -> Vec<BinTableValue> {
+    match value {
+        BinTableValue::Array(values) => values,
+        BinTableValue::Bits(bits) => bits.into_iter().map(BinTableValue::Logical).collect(),
+        value => vec![value],
+    }
+}
+
+/// Real and imaginary parts of a complex cell value, or two nulls
+pub(crate) fn complex_parts(value: &BinTableValue) -> [BinTableValue; 2] {
+    match *value {
+        BinTableValue::Complex32(r, i) => [BinTableValue::Float32(r), BinTableValue::Float32(i)],
+        BinTableValue::Complex64(r, i) => [BinTableValue::Float64(r), BinTableValue::Float64(i)],
+        _ => [BinTableValue::Null, BinTableValue::Null],
+    }
+}
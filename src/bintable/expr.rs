@@ -0,0 +1,729 @@
+//! Expressions over table rows, in the style of the cfitsio calculator
+//!
+//! An expression combines column values and constants with:
+//!
+//! * arithmetic: `+ - * / %` and `**` (or `^`) for powers
+//! * comparisons: `== != < <= > >=` (`=` is accepted for `==`)
+//! * boolean logic: `&& || !`
+//! * functions: `abs sqrt exp log log10 sin cos tan asin acos atan
+//!   atan2 floor ceil round min max isnull`
+//!
+//! Columns are referred to by TTYPEn name, case-insensitively, or as
+//! `$name$` if the name contains other characters.  Elements of vector
+//! columns are selected with a 1-based index, e.g. `FLUX[2]`.  `#ROW`
+//! is the 1-based row number, and `T`, `F`, `true` and `false` are
+//! boolean constants unless a column has that name.  Strings are
+//! quoted with `"` or `'`.
+//!
+//! Column values are physical (after TSCALn and TZEROn), and numbers
+//! are evaluated as 64-bit floats.  Null values (TNULLn, or NaN) make
+//! the result of arithmetic and comparisons null, so rows with nulls
+//! are not selected by a filter
+
+use super::BinTable;
+use crate::BinTableValue;
+use crate::FITSError;
+use crate::TFormType;
+use crate::TableError;
+
+/// Value of an expression for one row
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) enum Value {
+    Null,
+    Bool(bool),
+    Number(f64),
+    String(String),
+}
+
+impl Value {
+    fn type_name(&self) -> &'static str {
+        match self {
+            Value::Null => "null",
+            Value::Bool(_) => "boolean",
+            Value::Number(_) => "number",
+            Value::String(_) => "string",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum UnaryOp {
+    Neg,
+    Not,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum BinaryOp {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Rem,
+    Pow,
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    And,
+    Or,
+}
+
+impl BinaryOp {
+    fn symbol(&self) -> &'static str {
+        match self {
+            BinaryOp::Add => "+",
+            BinaryOp::Sub => "-",
+            BinaryOp::Mul => "*",
+            BinaryOp::Div => "/",
+            BinaryOp::Rem => "%",
+            BinaryOp::Pow => "**",
+            BinaryOp::Eq => "==",
+            BinaryOp::Ne => "!=",
+            BinaryOp::Lt => "<",
+            BinaryOp::Le => "<=",
+            BinaryOp::Gt => ">",
+            BinaryOp::Ge => ">=",
+            BinaryOp::And => "&&",
+            BinaryOp::Or => "||",
+        }
+    }
+}
+
+/// Names of the functions, with their number of arguments
+const FUNCTIONS: &[(&str, usize)] = &[
+    ("abs", 1),
+    ("sqrt", 1),
+    ("exp", 1),
+    ("log", 1),
+    ("log10", 1),
+    ("sin", 1),
+    ("cos", 1),
+    ("tan", 1),
+    ("asin", 1),
+    ("acos", 1),
+    ("atan", 1),
+    ("atan2", 2),
+    ("floor", 1),
+    ("ceil", 1),
+    ("round", 1),
+    ("min", 2),
+    ("max", 2),
+    ("isnull", 1),
+];
+
+#[derive(Clone, Debug, PartialEq)]
+enum Node {
+    Const(Value),
+    /// Column index, and 0-based element for vector columns
+    Column(usize, Option<usize>),
+    Row,
+    Unary(UnaryOp, Box<Node>),
+    Binary(BinaryOp, Box<Node>, Box<Node>),
+    Call(&'static str, Vec<Node>),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Token {
+    Number(f64),
+    String(String),
+    Name(String),
+    Row,
+    Op(&'static str),
+}
+
+/// Operators and punctuation, longest first so `**` is not read as `*`
+const OPERATORS: &[&str] = &[
+    "**", "&&", "||", "==", "!=", "<=", ">=", "<", ">", "=", "+", "-", "*", "/", "%", "^", "!",
+    "(", ")", "[", "]", ",",
+];
+
+/// Deepest nesting of parentheses, function calls, unary operators,
+/// and chained binary operators that is parsed; deeper expressions
+/// would overflow the stack when parsed or evaluated
+const MAX_DEPTH: usize = 256;
+
+fn invalid(message: String) -> TableError {
+    TableError::InvalidExpression(message)
+}
+
+/// Split expression text into tokens
+fn tokenize(text: &str) -> Result<Vec<Token>, TableError> {
+    let mut tokens = Vec::new();
+    let mut rest = text.trim_start();
+    while let Some(c) = rest.chars().next() {
+        let len = if c.is_ascii_digit()
+            || (c == '.' && rest[1..].starts_with(|c: char| c.is_ascii_digit()))
+        {
+            let mut len = rest
+                .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+                .unwrap_or(rest.len());
+            // Exponent, with optional sign
+            let tail = &rest[len..];
+            if tail.starts_with(['e', 'E']) {
+                let digits = tail[1..].trim_start_matches(['+', '-']);
+                if digits.starts_with(|c: char| c.is_ascii_digit()) {
+                    let exp = tail.len() - digits.len();
+                    len += exp
+                        + digits
+                            .find(|c: char| !c.is_ascii_digit())
+                            .unwrap_or(digits.len());
+                }
+            }
+            let value = rest[..len]
+                .parse::<f64>()
+                .map_err(|_| invalid(format!("invalid number \"{}\"", &rest[..len])))?;
+            tokens.push(Token::Number(value));
+            len
+        } else if c.is_ascii_alphabetic() || c == '_' {
+            let len = rest
+                .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+                .unwrap_or(rest.len());
+            tokens.push(Token::Name(rest[..len].to_string()));
+            len
+        } else if c == '$' {
+            let end = rest[1..]
+                .find('$')
+                .ok_or_else(|| invalid("unterminated $name$".to_string()))?;
+            tokens.push(Token::Name(rest[1..end + 1].to_string()));
+            end + 2
+        } else if c == '"' || c == '\'' {
+            let end = rest[1..]
+                .find(c)
+                .ok_or_else(|| invalid("unterminated string".to_string()))?;
+            tokens.push(Token::String(rest[1..end + 1].to_string()));
+            end + 2
+        } else if rest
+            .get(..4)
+            .is_some_and(|s| s.eq_ignore_ascii_case("#ROW"))
+        {
+            tokens.push(Token::Row);
+            4
+        } else {
+            let op = OPERATORS
+                .iter()
+                .find(|op| rest.starts_with(**op))
+                .ok_or_else(|| invalid(format!("unexpected character '{}'", c)))?;
+            tokens.push(Token::Op(op));
+            op.len()
+        };
+        rest = rest[len..].trim_start();
+    }
+    Ok(tokens)
+}
+
+/// Recursive-descent parser, from lowest to highest precedence:
+/// `||`, `&&`, comparisons, `+ -`, `* / %`, unary `- !`, `**`
+struct Parser<'a> {
+    tokens: Vec<Token>,
+    pos: usize,
+    depth: usize,
+    table: &'a BinTable,
+}
+
+impl Parser<'_> {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    /// Consume the next token if it is one of the operators
+    fn eat(&mut self, ops: &[&str]) -> Option<&'static str> {
+        match self.peek() {
+            Some(Token::Op(op)) if ops.contains(op) => {
+                let op = *op;
+                self.pos += 1;
+                Some(op)
+            }
+            _ => None,
+        }
+    }
+
+    fn expect(&mut self, op: &str) -> Result<(), TableError> {
+        match self.eat(&[op]) {
+            Some(_) => Ok(()),
+            None => Err(invalid(format!("expected '{}'", op))),
+        }
+    }
+
+    /// Go one level deeper, or fail past `MAX_DEPTH`
+    fn enter(&mut self) -> Result<(), TableError> {
+        if self.depth == MAX_DEPTH {
+            return Err(invalid(format!(
+                "expression nested more than {} levels deep",
+                MAX_DEPTH
+            )));
+        }
+        self.depth += 1;
+        Ok(())
+    }
+
+    /// Parse a nested part of the expression one level deeper
+    fn nested(
+        &mut self,
+        parse: fn(&mut Self) -> Result<Node, TableError>,
+    ) -> Result<Node, TableError> {
+        self.enter()?;
+        let node = parse(self);
+        self.depth -= 1;
+        node
+    }
+
+    fn binary(
+        &mut self,
+        ops: &[(&str, BinaryOp)],
+        next: fn(&mut Self) -> Result<Node, TableError>,
+    ) -> Result<Node, TableError> {
+        let symbols: Vec<&str> = ops.iter().map(|(s, _)| *s).collect();
+        let mut left = next(self)?;
+        // Each operator in a chain adds a level to the left operand
+        let mut links = 0;
+        while let Some(symbol) = self.eat(&symbols) {
+            self.enter()?;
+            links += 1;
+            let op = ops.iter().find(|(s, _)| *s == symbol).unwrap().1;
+            left = Node::Binary(op, Box::new(left), Box::new(next(self)?));
+        }
+        self.depth -= links;
+        Ok(left)
+    }
+
+    fn or(&mut self) -> Result<Node, TableError> {
+        self.binary(&[("||", BinaryOp::Or)], Self::and)
+    }
+
+    fn and(&mut self) -> Result<Node, TableError> {
+        self.binary(&[("&&", BinaryOp::And)], Self::comparison)
+    }
+
+    fn comparison(&mut self) -> Result<Node, TableError> {
+        self.binary(
+            &[
+                ("==", BinaryOp::Eq),
+                ("=", BinaryOp::Eq),
+                ("!=", BinaryOp::Ne),
+                ("<", BinaryOp::Lt),
+                ("<=", BinaryOp::Le),
+                (">", BinaryOp::Gt),
+                (">=", BinaryOp::Ge),
+            ],
+            Self::sum,
+        )
+    }
+
+    fn sum(&mut self) -> Result<Node, TableError> {
+        self.binary(&[("+", BinaryOp::Add), ("-", BinaryOp::Sub)], Self::product)
+    }
+
+    fn product(&mut self) -> Result<Node, TableError> {
+        self.binary(
+            &[
+                ("*", BinaryOp::Mul),
+                ("/", BinaryOp::Div),
+                ("%", BinaryOp::Rem),
+            ],
+            Self::unary,
+        )
+    }
+
+    fn unary(&mut self) -> Result<Node, TableError> {
+        match self.eat(&["-", "+", "!"]) {
+            Some("-") => Ok(Node::Unary(
+                UnaryOp::Neg,
+                Box::new(self.nested(Self::unary)?),
+            )),
+            Some("!") => Ok(Node::Unary(
+                UnaryOp::Not,
+                Box::new(self.nested(Self::unary)?),
+            )),
+            Some(_) => self.nested(Self::unary),
+            None => self.power(),
+        }
+    }
+
+    /// Powers are right-associative and bind tighter than unary minus
+    /// on their left, so `-2**2` is -4
+    fn power(&mut self) -> Result<Node, TableError> {
+        let base = self.atom()?;
+        match self.eat(&["**", "^"]) {
+            Some(_) => Ok(Node::Binary(
+                BinaryOp::Pow,
+                Box::new(base),
+                Box::new(self.nested(Self::unary)?),
+            )),
+            None => Ok(base),
+        }
+    }
+
+    fn atom(&mut self) -> Result<Node, TableError> {
+        let token = self
+            .peek()
+            .cloned()
+            .ok_or_else(|| invalid("unexpected end of expression".to_string()))?;
+        self.pos += 1;
+        match token {
+            Token::Number(v) => Ok(Node::Const(Value::Number(v))),
+            Token::String(s) => Ok(Node::Const(Value::String(s))),
+            Token::Row => Ok(Node::Row),
+            Token::Op("(") => {
+                let node = self.nested(Self::or)?;
+                self.expect(")")?;
+                Ok(node)
+            }
+            Token::Name(name) => self.name(&name),
+            Token::Op(op) => Err(invalid(format!("unexpected '{}'", op))),
+        }
+    }
+
+    /// Function call, column reference, or boolean constant
+    fn name(&mut self, name: &str) -> Result<Node, TableError> {
+        if self.eat(&["("]).is_some() {
+            let &(function, nargs) = FUNCTIONS
+                .iter()
+                .find(|(f, _)| f.eq_ignore_ascii_case(name))
+                .ok_or_else(|| invalid(format!("unknown function \"{}\"", name)))?;
+            let mut args = vec![self.nested(Self::or)?];
+            while self.eat(&[","]).is_some() {
+                args.push(self.nested(Self::or)?);
+            }
+            self.expect(")")?;
+            if args.len() != nargs {
+                return Err(invalid(format!(
+                    "{} takes {} arguments, got {}",
+                    function,
+                    nargs,
+                    args.len()
+                )));
+            }
+            return Ok(Node::Call(function, args));
+        }
+
+        let col = match self.table.find_column(name) {
+            Ok(col) => col,
+            Err(TableError::UnknownColumn(_)) => {
+                return match name {
+                    "T" | "true" => Ok(Node::Const(Value::Bool(true))),
+                    "F" | "false" => Ok(Node::Const(Value::Bool(false))),
+                    _ => Err(invalid(format!("unknown column \"{}\"", name))),
+                };
+            }
+            Err(e) => return Err(e),
+        };
+
+        let tform = &self.table.tform[col];
+        let element = match self.eat(&["["]) {
+            Some(_) => {
+                let index = match self.peek() {
+                    Some(Token::Number(v)) if v.fract() == 0.0 && *v >= 1.0 => *v as usize,
+                    _ => return Err(invalid(format!("invalid element index for {}", name))),
+                };
+                self.pos += 1;
+                self.expect("]")?;
+                let fixed = !matches!(tform.ftype, TFormType::ArrayD32(_) | TFormType::ArrayD64(_));
+                if tform.ftype == TFormType::Char || (fixed && index > tform.repeat) {
+                    return Err(invalid(format!(
+                        "element {} out of range for column {}",
+                        index, name
+                    )));
+                }
+                Some(index - 1)
+            }
+            None => {
+                let scalar = match tform.ftype {
+                    TFormType::Char => true,
+                    TFormType::ArrayD32(_) | TFormType::ArrayD64(_) => false,
+                    _ => tform.repeat == 1,
+                };
+                if !scalar {
+                    return Err(invalid(format!(
+                        "column {} is a vector; select an element with {}[n]",
+                        name, name
+                    )));
+                }
+                None
+            }
+        };
+        if matches!(tform.ftype, TFormType::Complex32 | TFormType::Complex64) {
+            return Err(invalid(format!("complex column {} is not supported", name)));
+        }
+        Ok(Node::Column(col, element))
+    }
+}
+
+/// Convert a physical cell value to an expression value
+fn cell_value(value: BinTableValue, element: Option<usize>) -> Value {
+    let value = match (value, element) {
+        (BinTableValue::Array(v), Some(i)) => v.into_iter().nth(i).unwrap_or(BinTableValue::Null),
+        (BinTableValue::Bits(v), Some(i)) => v
+            .get(i)
+            .map_or(BinTableValue::Null, |b| BinTableValue::Logical(*b)),
+        (BinTableValue::Bits(v), None) if v.len() == 1 => BinTableValue::Logical(v[0]),
+        (value, _) => value,
+    };
+    match value {
+        BinTableValue::Logical(b) => Value::Bool(b),
+        BinTableValue::String(s) => Value::String(s.trim_end().to_string()),
+        value => match value.as_f64() {
+            Some(v) if !v.is_nan() => Value::Number(v),
+            _ => Value::Null,
+        },
+    }
+}
+
+/// A parsed expression, bound to the columns of a table
+#[derive(Clone, Debug)]
+pub(crate) struct Expression {
+    root: Node,
+}
+
+impl Expression {
+    /// Parse an expression, resolving column names against a table
+    pub(crate) fn parse(text: &str, table: &BinTable) -> Result<Self, TableError> {
+        let mut parser = Parser {
+            tokens: tokenize(text)?,
+            pos: 0,
+            depth: 0,
+            table,
+        };
+        let root = parser.or()?;
+        if let Some(token) = parser.peek() {
+            return Err(invalid(format!("unexpected {:?}", token)));
+        }
+        Ok(Expression { root })
+    }
+
+    /// Evaluate the expression for one row of the table it was
+    /// parsed against
+    pub(crate) fn eval(&self, table: &BinTable, row: usize) -> Result<Value, FITSError> {
+        eval(&self.root, table, row)
+    }
+}
+
+fn eval(node: &Node, table: &BinTable, row: usize) -> Result<Value, FITSError> {
+    match node {
+        Node::Const(v) => Ok(v.clone()),
+        Node::Row => Ok(Value::Number((row + 1) as f64)),
+        Node::Column(col, element) => Ok(cell_value(table.at_physical(row, *col)?, *element)),
+        Node::Unary(op, arg) => match (op, eval(arg, table, row)?) {
+            (_, Value::Null) => Ok(Value::Null),
+            (UnaryOp::Neg, Value::Number(v)) => Ok(Value::Number(-v)),
+            (UnaryOp::Not, Value::Bool(b)) => Ok(Value::Bool(!b)),
+            (op, v) => Err(FITSError::from(invalid(format!(
+                "cannot apply {} to {}",
+                if *op == UnaryOp::Neg { "-" } else { "!" },
+                v.type_name()
+            )))),
+        },
+        Node::Binary(BinaryOp::And, a, b) => logical(false, a, b, table, row),
+        Node::Binary(BinaryOp::Or, a, b) => logical(true, a, b, table, row),
+        Node::Binary(op, a, b) => binary(*op, eval(a, table, row)?, eval(b, table, row)?),
+        Node::Call(function, args) => {
+            let args = args
+                .iter()
+                .map(|a| eval(a, table, row))
+                .collect::<Result<Vec<_>, _>>()?;
+            call(function, args)
+        }
+    }
+}
+
+/// Evaluate `&&` (when `short` is false) or `||` (when `short` is
+/// true); the right side is skipped once the left decides the result.
+/// A null operand gives null unless the other operand decides
+fn logical(
+    short: bool,
+    a: &Node,
+    b: &Node,
+    table: &BinTable,
+    row: usize,
+) -> Result<Value, FITSError> {
+    let boolean = |v: Value| match v {
+        Value::Bool(b) => Ok(Some(b)),
+        Value::Null => Ok(None),
+        v => Err(FITSError::from(invalid(format!(
+            "cannot apply {} to {}",
+            if short { "||" } else { "&&" },
+            v.type_name()
+        )))),
+    };
+    let left = boolean(eval(a, table, row)?)?;
+    if left == Some(short) {
+        return Ok(Value::Bool(short));
+    }
+    let right = boolean(eval(b, table, row)?)?;
+    Ok(match (left, right) {
+        (_, Some(r)) if r == short => Value::Bool(short),
+        (Some(_), Some(_)) => Value::Bool(!short),
+        _ => Value::Null,
+    })
+}
+
+fn binary(op: BinaryOp, a: Value, b: Value) -> Result<Value, FITSError> {
+    use std::cmp::Ordering;
+    let compare = |ord: Option<Ordering>| {
+        Value::Bool(match (op, ord) {
+            (_, None) => false,
+            (BinaryOp::Eq, Some(o)) => o == Ordering::Equal,
+            (BinaryOp::Ne, Some(o)) => o != Ordering::Equal,
+            (BinaryOp::Lt, Some(o)) => o == Ordering::Less,
+            (BinaryOp::Le, Some(o)) => o != Ordering::Greater,
+            (BinaryOp::Gt, Some(o)) => o == Ordering::Greater,
+            (BinaryOp::Ge, Some(o)) => o != Ordering::Less,
+            _ => unreachable!(),
+        })
+    };
+    let is_comparison = matches!(
+        op,
+        BinaryOp::Eq | BinaryOp::Ne | BinaryOp::Lt | BinaryOp::Le | BinaryOp::Gt | BinaryOp::Ge
+    );
+    match (a, b) {
+        (Value::Null, _) | (_, Value::Null) => Ok(Value::Null),
+        (Value::Number(x), Value::Number(y)) => Ok(match op {
+            BinaryOp::Add => Value::Number(x + y),
+            BinaryOp::Sub => Value::Number(x - y),
+            BinaryOp::Mul => Value::Number(x * y),
+            BinaryOp::Div => Value::Number(x / y),
+            BinaryOp::Rem => Value::Number(x % y),
+            BinaryOp::Pow => Value::Number(x.powf(y)),
+            _ => compare(x.partial_cmp(&y)),
+        }),
+        (Value::String(x), Value::String(y)) if is_comparison => Ok(compare(Some(x.cmp(&y)))),
+        (Value::Bool(x), Value::Bool(y)) if matches!(op, BinaryOp::Eq | BinaryOp::Ne) => {
+            Ok(compare(Some(x.cmp(&y))))
+        }
+        (a, b) => Err(FITSError::from(invalid(format!(
+            "cannot apply {} to {} and {}",
+            op.symbol(),
+            a.type_name(),
+            b.type_name()
+        )))),
+    }
+}
+
+fn call(function: &str, args: Vec<Value>) -> Result<Value, FITSError> {
+    if function == "isnull" {
+        return Ok(Value::Bool(args[0] == Value::Null));
+    }
+    let mut numbers = Vec::with_capacity(args.len());
+    for arg in args {
+        match arg {
+            Value::Number(v) => numbers.push(v),
+            Value::Null => return Ok(Value::Null),
+            v => {
+                return Err(FITSError::from(invalid(format!(
+                    "{} requires numbers, got {}",
+                    function,
+                    v.type_name()
+                ))))
+            }
+        }
+    }
+    let x = numbers[0];
+    Ok(Value::Number(match function {
+        "abs" => x.abs(),
+        "sqrt" => x.sqrt(),
+        "exp" => x.exp(),
+        "log" => x.ln(),
+        "log10" => x.log10(),
+        "sin" => x.sin(),
+        "cos" => x.cos(),
+        "tan" => x.tan(),
+        "asin" => x.asin(),
+        "acos" => x.acos(),
+        "atan" => x.atan(),
+        "atan2" => x.atan2(numbers[1]),
+        "floor" => x.floor(),
+        "ceil" => x.ceil(),
+        "round" => x.round(),
+        "min" => x.min(numbers[1]),
+        "max" => x.max(numbers[1]),
+        _ => unreachable!("function table and evaluation disagree"),
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bintable::tests::sample_table;
+
+    fn eval_rows(text: &str) -> Vec<Value> {
+        let table = sample_table();
+        let expr = Expression::parse(text, &table).unwrap();
+        (0..table.nrows)
+            .map(|row| expr.eval(&table, row).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn test_expression() {
+        use Value::*;
+        assert_eq!(eval_rows("1 + 2 * 3 - 2**3**0 / 4"), vec![Number(6.5); 2]);
+        assert_eq!(eval_rows("-2^2 + 7 % 4"), vec![Number(-1.0); 2]);
+        assert_eq!(
+            eval_rows("counts / 5 + #ROW"),
+            vec![Number(1.0), Number(13109.0)]
+        );
+        assert_eq!(eval_rows("FLUX[1] * 2"), vec![Number(22.0), Number(20.0)]);
+        assert_eq!(eval_rows("FLUX[2] > 10"), vec![Null, Bool(true)]);
+        assert_eq!(
+            eval_rows("isnull(FLUX[2]) || FLUX[2] > 10"),
+            vec![Bool(true); 2]
+        );
+        assert_eq!(eval_rows("FLUX[2] > 10 && F"), vec![Bool(false); 2]);
+        assert_eq!(eval_rows("NAME == 'wxyz'"), vec![Bool(false), Bool(true)]);
+        assert_eq!(eval_rows("$NAME$ < \"b\""), vec![Bool(true), Bool(false)]);
+        assert_eq!(
+            eval_rows("!(COUNTS >= 1.5e2) = true"),
+            vec![Bool(true), Bool(false)]
+        );
+        assert_eq!(
+            eval_rows("max(abs(-3), sqrt(16)) + round(0.6)"),
+            vec![Number(5.0); 2]
+        );
+
+        let table = sample_table();
+        for bad in [
+            "FLUX > 1",
+            "FLUX[3]",
+            "NAME[1]",
+            "ENERGY > 5",
+            "sqrt(1, 2)",
+            "nosuch(1)",
+            "(1 + 2",
+            "1 + 2)",
+            "COUNTS >",
+            "'abc",
+            "1 @ 2",
+        ] {
+            assert!(
+                matches!(
+                    Expression::parse(bad, &table),
+                    Err(TableError::InvalidExpression(_))
+                ),
+                "{}",
+                bad
+            );
+        }
+        let expr = Expression::parse("NAME + 1", &table).unwrap();
+        assert!(expr.eval(&table, 0).is_err());
+        let expr = Expression::parse("COUNTS && T", &table).unwrap();
+        assert!(expr.eval(&table, 0).is_err());
+    }
+    #[test]
+    fn test_nesting() {
+        let table = sample_table();
+        let nested = format!("{}1{}", "(".repeat(100), ")".repeat(100));
+        assert!(Expression::parse(&nested, &table).is_ok());
+        for deep in [
+            format!("{}1{}", "(".repeat(100000), ")".repeat(100000)),
+            format!("{}1", "-".repeat(100000)),
+            format!("{}1", "1 + ".repeat(100000)),
+            format!("{}1{}", "abs(".repeat(100000), ")".repeat(100000)),
+        ] {
+            assert!(matches!(
+                Expression::parse(&deep, &table),
+                Err(TableError::InvalidExpression(_))
+            ));
+        }
+    }
+}
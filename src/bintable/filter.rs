@@ -0,0 +1,63 @@
+//! Selection of table rows with an expression
+
+use super::expr::{Expression, Value};
+use super::BinTable;
+use crate::FITSError;
+use crate::TableError;
+
+impl BinTable {
+    /// Rows for which a boolean expression is true
+    ///
+    /// The syntax follows the cfitsio row filter: column names
+    /// (case-insensitive, or quoted as `$name$`), vector elements as
+    /// `FLUX[2]` (1-based), `#ROW` (1-based), numbers, quoted strings,
+    /// `T` and `F`, the operators `+ - * / % ** == != < <= > >= && || !`,
+    /// and the functions `abs sqrt exp log log10 sin cos tan asin acos
+    /// atan atan2 floor ceil round min max isnull`.  Column values are
+    /// physical (see `at_physical`).  Rows where the expression is null
+    /// (e.g. it uses a TNULLn value) are not selected
+    ///
+    /// # Arguments
+    ///
+    /// * `expr` - Boolean expression, e.g. `"ENERGY > 500 && GRADE <= 6"`
+    ///
+    /// # Returns
+    ///
+    /// Indices of the selected rows, in increasing order, or an error
+    /// if the expression is invalid or not boolean
+    ///
+    pub fn select_rows(&self, expr: &str) -> Result<Vec<usize>, FITSError> {
+        let expr = Expression::parse(expr, self)?;
+        let mut rows = Vec::new();
+        for row in 0..self.nrows {
+            match expr.eval(self, row)? {
+                Value::Bool(true) => rows.push(row),
+                Value::Bool(false) | Value::Null => {}
+                v => {
+                    return Err(FITSError::from(TableError::InvalidExpression(format!(
+                        "filter must be boolean, got {:?}",
+                        v
+                    ))))
+                }
+            }
+        }
+        Ok(rows)
+    }
+
+    /// Table of the rows for which a boolean expression is true
+    ///
+    /// Only the heap arrays of the selected rows are copied.  NAXIS2
+    /// and PCOUNT are updated in `header`
+    ///
+    /// # Arguments
+    ///
+    /// * `expr` - Boolean expression; see `select_rows`
+    ///
+    /// # Returns
+    ///
+    /// New table with the selected rows
+    ///
+    pub fn filter(&self, expr: &str) -> Result<BinTable, FITSError> {
+        self.take_rows(self.select_rows(expr)?)
+    }
+}
@@ -0,0 +1,1486 @@
+#[cfg(feature = "arrow")]
+mod arrow;
+mod binning;
+mod columns;
+mod computed;
+mod concat;
+mod csv;
+mod encode;
+#[cfg(any(feature = "arrow", feature = "polars"))]
+mod export;
+mod expr;
+mod filter;
+#[cfg(feature = "polars")]
+mod polars;
+mod select;
+mod sort;
+mod stats;
+mod strings;
+mod tdisp;
+mod tform;
+mod value;
+mod wcs;
+
+#[cfg(feature = "arrow")]
+pub(crate) use arrow::record_batch;
+pub(crate) use csv::write_csv;
+pub use csv::{ArrayStyle, CsvOptions};
+#[cfg(feature = "polars")]
+pub(crate) use polars::data_frame;
+pub use sort::SortOrder;
+pub use stats::ColumnStats;
+pub use strings::StringOptions;
+pub use tdisp::{TDisp, TDispType};
+pub use tform::TForm;
+pub use tform::TFormType;
+pub use value::BinTableValue;
+pub use value::FromTableValue;
+
+use crate::FITSError;
+use crate::FromKeywordValue;
+use crate::HDUData;
+use crate::Header;
+use crate::HeaderError;
+use crate::KeywordValue;
+use crate::TableError;
+
+/// Binary table extension (XTENSION = 'BINTABLE')
+///
+/// The main data table is held as raw big-endian bytes, with
+/// values decoded on access.  Column metadata is taken from the
+/// TTYPEn, TFORMn, TUNITn, TSCALn, TZEROn, and TNULLn keywords,
+/// stored as one entry per column.  Columns may also carry a world
+/// coordinate system; see `wcs_for_column`.
+///
+/// See Section 7.3 of FITS standard, version 4
+#[derive(Clone, Debug)]
+pub struct BinTable {
+    /// Number of rows (NAXIS2)
+    pub nrows: usize,
+    /// Number of bytes in each row (NAXIS1)
+    pub rowbytes: usize,
+    pub ttype: Vec<Option<String>>,
+    pub tform: Vec<TForm>,
+    pub tunit: Vec<Option<String>>,
+    pub tscal: Vec<Option<f64>>,
+    pub tzero: Vec<Option<f64>>,
+    pub tnull: Vec<Option<i64>>,
+    /// Raw bytes of the main data table
+    pub rawbytes: Vec<u8>,
+    /// Raw bytes of the supplemental data area (PCOUNT bytes)
+    /// following the main data table
+    pub heap: Vec<u8>,
+    /// Byte offset of the heap from the start of the data (THEAP)
+    pub theap: usize,
+    /// Byte offset of each column within a row
+    offsets: Vec<usize>,
+    /// Table header, for column WCS keywords
+    header: Header,
+}
+
+/// Get integer value of a mandatory keyword at a fixed position in the header
+fn mandatory_int(header: &Header, idx: usize, name: &str) -> Result<i64, HeaderError> {
+    let kw = header
+        .iter()
+        .nth(idx)
+        .ok_or(HeaderError::GenericError("not enough keywords".to_string()))?;
+    if kw.name != name {
+        return Err(HeaderError::InvalidKeywordPlacement(kw.name.clone(), idx));
+    }
+    match &kw.value {
+        KeywordValue::Int(value) => Ok(*value),
+        _ => Err(HeaderError::UnexpectedValueType(name.to_string())),
+    }
+}
+
+/// Mandatory keyword holding a size or count, which must not be negative
+fn mandatory_size(header: &Header, idx: usize, name: &str) -> Result<usize, HeaderError> {
+    let value = mandatory_int(header, idx, name)?;
+    usize::try_from(value)
+        .map_err(|_| HeaderError::GenericError(format!("Invalid {} value: {}", name, value)))
+}
+
+/// Values of an indexed column keyword family, e.g. TTYPEn, one per field
+///
+/// Fields without the keyword are None; indices beyond TFIELDS are ignored
+pub(crate) fn column_keywords<T: FromKeywordValue>(
+    header: &Header,
+    name: &str,
+    tfields: usize,
+) -> Result<Vec<Option<T>>, HeaderError> {
+    let mut values: Vec<Option<T>> = (0..tfields).map(|_| None).collect();
+    for (n, kw) in header.indexed(name) {
+        if n > tfields || values[n - 1].is_some() {
+            continue;
+        }
+        values[n - 1] = Some(
+            T::from_value(&kw.value)
+                .ok_or_else(|| HeaderError::UnexpectedValueType(kw.name.clone()))?,
+        );
+    }
+    Ok(values)
+}
+
+/// Decode a single element of the given type from big-endian bytes
+fn decode_element(ftype: &TFormType, b: &[u8]) -> BinTableValue {
+    match ftype {
+        TFormType::Logical => BinTableValue::Logical(b[0] == b'T'),
+        TFormType::Byte => BinTableValue::Byte(b[0]),
+        TFormType::Int16 => BinTableValue::Int16(i16::from_be_bytes([b[0], b[1]])),
+        TFormType::Int32 => BinTableValue::Int32(i32::from_be_bytes(b[0..4].try_into().unwrap())),
+        TFormType::Int64 => BinTableValue::Int64(i64::from_be_bytes(b[0..8].try_into().unwrap())),
+        TFormType::Float32 => {
+            BinTableValue::Float32(f32::from_be_bytes(b[0..4].try_into().unwrap()))
+        }
+        TFormType::Float64 => {
+            BinTableValue::Float64(f64::from_be_bytes(b[0..8].try_into().unwrap()))
+        }
+        TFormType::Complex32 => BinTableValue::Complex32(
+            f32::from_be_bytes(b[0..4].try_into().unwrap()),
+            f32::from_be_bytes(b[4..8].try_into().unwrap()),
+        ),
+        TFormType::Complex64 => BinTableValue::Complex64(
+            f64::from_be_bytes(b[0..8].try_into().unwrap()),
+            f64::from_be_bytes(b[8..16].try_into().unwrap()),
+        ),
+        // Bit, character, and array fields are handled by `decode`
+        _ => BinTableValue::Array(Vec::new()),
+    }
+}
+
+/// Decode `repeat` elements of the given type from big-endian bytes
+fn decode(ftype: &TFormType, repeat: usize, b: &[u8]) -> BinTableValue {
+    match ftype {
+        TFormType::Char => BinTableValue::String(decode_string(&b[0..repeat])),
+        TFormType::Bit => BinTableValue::Bits(
+            (0..repeat)
+                .map(|i| (b[i / 8] >> (7 - (i % 8))) & 0x01 == 1)
+                .collect(),
+        ),
+        _ => {
+            let sz = ftype.size();
+            if repeat == 1 {
+                decode_element(ftype, b)
+            } else {
+                BinTableValue::Array(
+                    (0..repeat)
+                        .map(|i| decode_element(ftype, &b[i * sz..(i + 1) * sz]))
+                        .collect(),
+                )
+            }
+        }
+    }
+}
+
+/// Decode a character field
+///
+/// An ASCII NUL ends the string; the bytes after it are ignored (see
+/// Section 7.3.3.1 of FITS standard, version 4)
+fn decode_string(b: &[u8]) -> String {
+    let end = b.iter().position(|&c| c == 0).unwrap_or(b.len());
+    String::from_utf8_lossy(&b[..end]).to_string()
+}
+
+/// Apply the linear transform `TZERO + TSCAL * stored` to a value
+///
+/// Integer columns that follow the offset-binary convention
+/// (TSCAL = 1, TZERO = 2^(bits-1), or TZERO = -128 for bytes) are
+/// returned exactly as the matching unsigned (or signed byte) type
+pub(crate) fn scale(value: BinTableValue, tscal: f64, tzero: f64) -> BinTableValue {
+    let offset_binary = tscal == 1.0;
+    match value {
+        BinTableValue::Byte(v) if offset_binary && tzero == -128.0 => {
+            BinTableValue::Int8((v ^ 0x80) as i8)
+        }
+        BinTableValue::Int16(v) if offset_binary && tzero == 32768.0 => {
+            BinTableValue::UInt16((v as u16) ^ 0x8000)
+        }
+        BinTableValue::Int32(v) if offset_binary && tzero == 2147483648.0 => {
+            BinTableValue::UInt32((v as u32) ^ 0x8000_0000)
+        }
+        BinTableValue::Int64(v) if offset_binary && tzero == 9223372036854775808.0 => {
+            BinTableValue::UInt64((v as u64) ^ 0x8000_0000_0000_0000)
+        }
+        BinTableValue::Byte(v) => BinTableValue::Float64(tzero + tscal * v as f64),
+        BinTableValue::Int16(v) => BinTableValue::Float64(tzero + tscal * v as f64),
+        BinTableValue::Int32(v) => BinTableValue::Float64(tzero + tscal * v as f64),
+        BinTableValue::Int64(v) => BinTableValue::Float64(tzero + tscal * v as f64),
+        BinTableValue::Float32(v) => BinTableValue::Float64(tzero + tscal * v as f64),
+        BinTableValue::Float64(v) => BinTableValue::Float64(tzero + tscal * v),
+        BinTableValue::Complex32(r, i) => {
+            BinTableValue::Complex64(tzero + tscal * r as f64, tzero + tscal * i as f64)
+        }
+        BinTableValue::Complex64(r, i) => {
+            BinTableValue::Complex64(tzero + tscal * r, tzero + tscal * i)
+        }
+        BinTableValue::Array(v) => {
+            BinTableValue::Array(v.into_iter().map(|x| scale(x, tscal, tzero)).collect())
+        }
+        other => other,
+    }
+}
+
+/// Replace integer values equal to the TNULLn sentinel with `BinTableValue::Null`
+fn mask_null(value: BinTableValue, tnull: i64) -> BinTableValue {
+    match value {
+        BinTableValue::Byte(v) if v as i64 == tnull => BinTableValue::Null,
+        BinTableValue::Int16(v) if v as i64 == tnull => BinTableValue::Null,
+        BinTableValue::Int32(v) if v as i64 == tnull => BinTableValue::Null,
+        BinTableValue::Int64(v) if v == tnull => BinTableValue::Null,
+        BinTableValue::Array(v) => {
+            BinTableValue::Array(v.into_iter().map(|x| mask_null(x, tnull)).collect())
+        }
+        other => other,
+    }
+}
+
+/// Element type, element count, and byte range in the heap of a
+/// variable-length array
+type HeapArray<'a> = (&'a TFormType, usize, std::ops::Range<usize>);
+
+impl BinTable {
+    /// Number of columns (TFIELDS)
+    pub fn ncols(&self) -> usize {
+        self.tform.len()
+    }
+
+    /// Header describing the table
+    ///
+    /// Kept consistent with the table when rows are selected (see
+    /// `filter`), so it can be used as the header of a new HDU
+    pub fn header(&self) -> &Header {
+        &self.header
+    }
+
+    /// Names of the columns
+    ///
+    /// # Returns
+    ///
+    /// The TTYPEn value of each column, without trailing blanks, or
+    /// "COLn" (1-based) for columns without a name
+    ///
+    pub fn column_names(&self) -> Vec<String> {
+        self.ttype
+            .iter()
+            .enumerate()
+            .map(|(col, t)| match t {
+                Some(t) => t.trim_end().to_string(),
+                None => format!("COL{}", col + 1),
+            })
+            .collect()
+    }
+
+    /// Index of the column with a given name
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - Column name (TTYPEn), matched case-insensitively and
+    ///   ignoring trailing blanks
+    ///
+    /// # Returns
+    ///
+    /// The zero-based column index, or an error if no column, or more
+    /// than one column, has the name
+    ///
+    pub fn column_index(&self, name: &str) -> Result<usize, FITSError> {
+        Ok(self.find_column(name)?)
+    }
+
+    /// Index of the column with a given name; see `column_index`
+    pub(super) fn find_column(&self, name: &str) -> Result<usize, TableError> {
+        let mut matches = self.ttype.iter().enumerate().filter(|(_, t)| {
+            t.as_deref()
+                .is_some_and(|t| t.trim_end().eq_ignore_ascii_case(name.trim_end()))
+        });
+        match (matches.next(), matches.next()) {
+            (Some((col, _)), None) => Ok(col),
+            (Some(_), Some(_)) => Err(TableError::AmbiguousColumn(name.to_string())),
+            (None, _) => Err(TableError::UnknownColumn(name.to_string())),
+        }
+    }
+
+    /// Is there a column with a given name, matched as for `column_index`
+    pub(super) fn has_column(&self, name: &str) -> bool {
+        !matches!(self.find_column(name), Err(TableError::UnknownColumn(_)))
+    }
+
+    /// Construct a binary table from raw bytes from the file
+    ///
+    /// Arguments:
+    ///
+    /// * `header` - Header information for the table
+    /// * `rawbytes` - Raw bytes starting from "data" portion of HDU
+    ///
+    /// Returns:
+    ///
+    /// * `HDUData` - Binary table data
+    /// * `usize` - Number of bytes consumed
+    ///
+    pub(crate) fn from_bytes(
+        header: &Header,
+        rawbytes: &[u8],
+    ) -> Result<(HDUData, usize), FITSError> {
+        // Section 7.3.1 of the fits standard 4.0 manual
+        if mandatory_int(header, 1, "BITPIX")? != 8 {
+            return Err(FITSError::from(HeaderError::GenericError(
+                "Invalid BITPIX value".to_string(),
+            )));
+        }
+        if mandatory_int(header, 2, "NAXIS")? != 2 {
+            return Err(FITSError::from(HeaderError::GenericError(
+                "Invalid NAXIS value".to_string(),
+            )));
+        }
+        let rowbytes = mandatory_size(header, 3, "NAXIS1")?;
+        let nrows = mandatory_size(header, 4, "NAXIS2")?;
+        let pcount = mandatory_size(header, 5, "PCOUNT")?;
+        if mandatory_int(header, 6, "GCOUNT")? != 1 {
+            return Err(FITSError::from(HeaderError::GenericError(
+                "Invalid GCOUNT value".to_string(),
+            )));
+        }
+        let tfields = mandatory_size(header, 7, "TFIELDS")?;
+        let too_large = || {
+            HeaderError::GenericError(format!(
+                "table of {} rows of {} bytes with PCOUNT {} is too large",
+                nrows, rowbytes, pcount
+            ))
+        };
+        let tablebytes = rowbytes.checked_mul(nrows).ok_or_else(too_large)?;
+        let nbytes = tablebytes.checked_add(pcount).ok_or_else(too_large)?;
+
+        let tforms = column_keywords::<String>(header, "TFORM", tfields)?;
+        let mut table = BinTable {
+            nrows,
+            rowbytes,
+            ttype: column_keywords(header, "TTYPE", tfields)?,
+            tform: Vec::with_capacity(tfields),
+            tunit: column_keywords(header, "TUNIT", tfields)?,
+            tscal: column_keywords(header, "TSCAL", tfields)?,
+            tzero: column_keywords(header, "TZERO", tfields)?,
+            tnull: column_keywords(header, "TNULL", tfields)?,
+            rawbytes: Vec::new(),
+            heap: Vec::new(),
+            theap: tablebytes,
+            offsets: Vec::with_capacity(tfields),
+            header: header.clone(),
+        };
+
+        let mut offset = 0;
+        for (i, tform) in tforms.into_iter().enumerate() {
+            let tform = tform
+                .ok_or(HeaderError::GenericError(format!("missing TFORM{}", i + 1)))?
+                .parse::<TForm>()?;
+            table.offsets.push(offset);
+            offset = tform
+                .size()
+                .checked_add(offset)
+                .ok_or_else(|| HeaderError::GenericError("row size is too large".to_string()))?;
+            table.tform.push(tform);
+        }
+        if offset != rowbytes {
+            return Err(FITSError::from(HeaderError::GenericError(format!(
+                "Sum of TFORM sizes ({}) does not match NAXIS1 ({})",
+                offset, rowbytes
+            ))));
+        }
+        if let Some(kw) = header.value("THEAP") {
+            match kw {
+                KeywordValue::Int(v) => {
+                    table.theap = usize::try_from(*v).map_err(|_| {
+                        HeaderError::GenericError(format!("Invalid THEAP value: {}", v))
+                    })?
+                }
+                _ => {
+                    return Err(FITSError::from(HeaderError::UnexpectedValueType(
+                        "THEAP".into(),
+                    )))
+                }
+            }
+        }
+
+        if rawbytes.len() < nbytes {
+            return Err(FITSError::from(HeaderError::GenericError(
+                "not enough data bytes for table".to_string(),
+            )));
+        }
+        table.rawbytes = rawbytes[0..tablebytes].to_vec();
+        table.heap = rawbytes[tablebytes..nbytes].to_vec();
+
+        Ok((HDUData::BinTable(Box::new(table)), nbytes))
+    }
+
+    /// Get value of a table cell, as stored in the file
+    ///
+    /// # Arguments
+    ///
+    /// * `row` - Row index (zero-based)
+    /// * `col` - Column index (zero-based)
+    ///
+    /// # Returns
+    ///
+    /// The cell value; fields with repeat count other than one
+    /// and variable-length arrays are returned as `BinTableValue::Array`,
+    /// and character fields as `BinTableValue::String`.
+    /// Integer values equal to the TNULLn value for the column are
+    /// returned as `BinTableValue::Null`
+    ///
+    pub fn at(&self, row: usize, col: usize) -> Result<BinTableValue, FITSError> {
+        let value = self.decode_cell(row, col)?;
+        match self.tnull[col] {
+            Some(tnull) => Ok(mask_null(value, tnull)),
+            None => Ok(value),
+        }
+    }
+
+    /// Locate the variable-length array a descriptor points to
+    ///
+    /// # Arguments
+    ///
+    /// * `col` - Column index
+    /// * `field` - Bytes of the field in the main table
+    ///
+    /// # Returns
+    ///
+    /// The element type, element count, and byte range in `heap`, or
+    /// None if the column does not hold variable-length arrays
+    ///
+    fn heap_array<'a>(
+        &'a self,
+        col: usize,
+        field: &[u8],
+    ) -> Result<Option<HeapArray<'a>>, FITSError> {
+        let (etype, count, heapoff) = match &self.tform[col].ftype {
+            TFormType::ArrayD32(etype) => (
+                etype,
+                u32::from_be_bytes(field[0..4].try_into().unwrap()) as usize,
+                u32::from_be_bytes(field[4..8].try_into().unwrap()) as usize,
+            ),
+            TFormType::ArrayD64(etype) => (
+                etype,
+                u64::from_be_bytes(field[0..8].try_into().unwrap()) as usize,
+                u64::from_be_bytes(field[8..16].try_into().unwrap()) as usize,
+            ),
+            _ => return Ok(None),
+        };
+        let out_of_range = || FITSError::from(TableError::HeapOutOfRange(col));
+        let nbytes = match etype.as_ref() {
+            TFormType::Bit => count.div_ceil(8),
+            t => count.checked_mul(t.size()).ok_or_else(out_of_range)?,
+        };
+        let start = self
+            .theap
+            .checked_add(heapoff)
+            .and_then(|offset| offset.checked_sub(self.rawbytes.len()))
+            .ok_or_else(out_of_range)?;
+        let end = start.checked_add(nbytes).ok_or_else(out_of_range)?;
+        if end > self.heap.len() {
+            return Err(out_of_range());
+        }
+        Ok(Some((etype.as_ref(), count, start..end)))
+    }
+
+    /// Decode table cell without null substitution
+    fn decode_cell(&self, row: usize, col: usize) -> Result<BinTableValue, FITSError> {
+        if row >= self.nrows {
+            return Err(FITSError::from(TableError::RowOutOfRange(row, self.nrows)));
+        }
+        if col >= self.ncols() {
+            return Err(FITSError::from(TableError::ColumnOutOfRange(
+                col,
+                self.ncols(),
+            )));
+        }
+        let tform = &self.tform[col];
+        let start = row * self.rowbytes + self.offsets[col];
+        let bytes = &self.rawbytes[start..start + tform.size()];
+
+        let Some((etype, count, range)) = self.heap_array(col, bytes)? else {
+            return Ok(decode(&tform.ftype, tform.repeat, bytes));
+        };
+        let bytes = &self.heap[range];
+        match etype {
+            TFormType::Char | TFormType::Bit => Ok(decode(etype, count, bytes)),
+            _ => Ok(BinTableValue::Array(
+                (0..count)
+                    .map(|i| decode_element(etype, &bytes[i * etype.size()..]))
+                    .collect(),
+            )),
+        }
+    }
+
+    /// Get physical value of a table cell
+    ///
+    /// Applies the TSCALn and TZEROn keywords to the stored value:
+    /// `physical = TZERO + TSCAL * stored`.  Numeric values are returned
+    /// as `BinTableValue::Float64` (or `Complex64`), except for integer columns
+    /// using the offset-binary convention (TSCAL = 1 and TZERO = 2^(bits-1)),
+    /// which are returned exactly as `UInt16`, `UInt32`, or `UInt64`
+    /// (or `Int8` for byte columns with TZERO = -128).
+    ///
+    /// Columns without TSCALn or TZEROn are returned unchanged, and null
+    /// values (see `at`) are never scaled.
+    ///
+    /// # Arguments
+    ///
+    /// * `row` - Row index (zero-based)
+    /// * `col` - Column index (zero-based)
+    ///
+    pub fn at_physical(&self, row: usize, col: usize) -> Result<BinTableValue, FITSError> {
+        let value = self.at(row, col)?;
+        if self.tscal[col].is_none() && self.tzero[col].is_none() {
+            return Ok(value);
+        }
+        Ok(scale(
+            value,
+            self.tscal[col].unwrap_or(1.0),
+            self.tzero[col].unwrap_or(0.0),
+        ))
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::testutil::kw;
+    use crate::TableLike;
+
+    /// Table with columns: 1I (TZERO=32768), 2J (TSCAL=0.5, TZERO=10, TNULL=-4), 4A
+    pub(crate) fn sample_table() -> BinTable {
+        let header = Header::new(vec![
+            kw("XTENSION", KeywordValue::String("BINTABLE".into())),
+            kw("BITPIX", KeywordValue::Int(8)),
+            kw("NAXIS", KeywordValue::Int(2)),
+            kw("NAXIS1", KeywordValue::Int(14)),
+            kw("NAXIS2", KeywordValue::Int(2)),
+            kw("PCOUNT", KeywordValue::Int(0)),
+            kw("GCOUNT", KeywordValue::Int(1)),
+            kw("TFIELDS", KeywordValue::Int(3)),
+            kw("TTYPE1", KeywordValue::String("COUNTS".into())),
+            kw("TFORM1", KeywordValue::String("1I".into())),
+            kw("TZERO1", KeywordValue::Int(32768)),
+            kw("TTYPE2", KeywordValue::String("FLUX".into())),
+            kw("TFORM2", KeywordValue::String("2J".into())),
+            kw("TSCAL2", KeywordValue::Float(0.5)),
+            kw("TZERO2", KeywordValue::Float(10.0)),
+            kw("TNULL2", KeywordValue::Int(-4)),
+            kw("TTYPE3", KeywordValue::String("NAME".into())),
+            kw("TFORM3", KeywordValue::String("4A".into())),
+            kw("END", KeywordValue::None),
+        ]);
+        let mut raw = Vec::new();
+        for (c, f, n) in [(-32768i16, [2i32, -4], b"ab  "), (32767, [0, 1], b"wxyz")] {
+            raw.extend_from_slice(&c.to_be_bytes());
+            raw.extend_from_slice(&f[0].to_be_bytes());
+            raw.extend_from_slice(&f[1].to_be_bytes());
+            raw.extend_from_slice(n);
+        }
+        match BinTable::from_bytes(&header, &raw).unwrap() {
+            (HDUData::BinTable(t), 28) => *t,
+            _ => panic!("expected binary table"),
+        }
+    }
+
+    #[test]
+    fn test_filter() {
+        let table = sample_table();
+        assert_eq!(table.select_rows("COUNTS > 100").unwrap(), vec![1]);
+        assert_eq!(table.select_rows("FLUX[2] < 100").unwrap(), vec![1]);
+        assert_eq!(
+            table.select_rows("NAME == 'ab' || FLUX[1] == 10").unwrap(),
+            vec![0, 1]
+        );
+        assert!(table.select_rows("COUNTS + 1").is_err());
+        assert!(table.select_rows("ENERGY > 500").is_err());
+
+        let filtered = table.filter("NAME != 'ab'").unwrap();
+        assert_eq!(filtered.nrows, 1);
+        assert_eq!(filtered.rawbytes.len(), 14);
+        assert_eq!(filtered.header().get::<i64>("NAXIS2").unwrap(), 1);
+        assert_eq!(
+            filtered.at(0, 2).unwrap(),
+            BinTableValue::String("wxyz".to_string())
+        );
+        assert_eq!(table.filter("F").unwrap().nrows, 0);
+    }
+
+    #[test]
+    fn test_select_columns() {
+        let table = sample_table();
+        let selected = table.select_columns(&["name", "FLUX"]).unwrap();
+        assert_eq!(selected.ncols(), 2);
+        assert_eq!(selected.rowbytes, 12);
+        assert_eq!(
+            selected.at(1, 0).unwrap(),
+            BinTableValue::String("wxyz".to_string())
+        );
+        assert_eq!(
+            selected.at_physical(0, 1).unwrap(),
+            BinTableValue::Array(vec![BinTableValue::Float64(11.0), BinTableValue::Null])
+        );
+        let header = selected.header();
+        assert_eq!(header.get::<String>("TTYPE1").unwrap(), "NAME");
+        assert_eq!(header.get::<String>("TFORM2").unwrap(), "2J");
+        assert_eq!(header.get::<i64>("TNULL2").unwrap(), -4);
+        assert_eq!(header.get::<f64>("TSCAL2").unwrap(), 0.5);
+        assert!(header.find("TZERO1").is_none());
+        assert!(header.find("TTYPE3").is_none());
+        assert_eq!(header.get::<i64>("TFIELDS").unwrap(), 2);
+        assert!(matches!(
+            table.select_columns(&["ENERGY"]),
+            Err(FITSError::Table(TableError::UnknownColumn(_)))
+        ));
+    }
+
+    /// Table with columns: 1J, 1PI (variable-length), with 3 rows whose
+    /// arrays have 2, 0, and 1 elements; the heap has 2 unused bytes
+    /// before the arrays
+    pub(super) fn vla_table() -> BinTable {
+        let header = Header::new(vec![
+            kw("XTENSION", KeywordValue::String("BINTABLE".into())),
+            kw("BITPIX", KeywordValue::Int(8)),
+            kw("NAXIS", KeywordValue::Int(2)),
+            kw("NAXIS1", KeywordValue::Int(12)),
+            kw("NAXIS2", KeywordValue::Int(3)),
+            kw("PCOUNT", KeywordValue::Int(8)),
+            kw("GCOUNT", KeywordValue::Int(1)),
+            kw("TFIELDS", KeywordValue::Int(2)),
+            kw("TTYPE1", KeywordValue::String("ID".into())),
+            kw("TFORM1", KeywordValue::String("1J".into())),
+            kw("TTYPE2", KeywordValue::String("HITS".into())),
+            kw("TFORM2", KeywordValue::String("1PI(2)".into())),
+            kw("END", KeywordValue::None),
+        ]);
+        let mut raw = Vec::new();
+        for (id, count, offset) in [(1i32, 2u32, 2u32), (2, 0, 0), (3, 1, 6)] {
+            raw.extend_from_slice(&id.to_be_bytes());
+            raw.extend_from_slice(&count.to_be_bytes());
+            raw.extend_from_slice(&offset.to_be_bytes());
+        }
+        raw.extend_from_slice(&[0, 0, 0, 10, 0, 20, 0, 30]);
+        match BinTable::from_bytes(&header, &raw).unwrap() {
+            (HDUData::BinTable(t), 44) => *t,
+            _ => panic!("expected binary table"),
+        }
+    }
+
+    #[test]
+    fn test_subset_heap() {
+        let table = vla_table();
+        let ids = table.select_columns(&["ID"]).unwrap();
+        assert!(ids.heap.is_empty());
+        assert_eq!(ids.header().get::<i64>("PCOUNT").unwrap(), 0);
+
+        let hits = table.select_columns(&["HITS", "ID"]).unwrap();
+        assert_eq!(hits.heap, vec![0, 10, 0, 20, 0, 30]);
+        assert_eq!(hits.header().get::<i64>("PCOUNT").unwrap(), 6);
+        assert_eq!(hits.header().get::<String>("TFORM1").unwrap(), "1PI(2)");
+        assert_eq!(
+            hits.at(2, 0).unwrap(),
+            BinTableValue::Array(vec![BinTableValue::Int16(30)])
+        );
+
+        let filtered = table.filter("ID != 1").unwrap();
+        assert_eq!(filtered.heap, vec![0, 30]);
+        assert_eq!(filtered.at(0, 1).unwrap(), BinTableValue::Array(vec![]));
+        assert_eq!(
+            filtered.at(1, 1).unwrap(),
+            BinTableValue::Array(vec![BinTableValue::Int16(30)])
+        );
+    }
+
+    #[test]
+    fn test_rows_range() {
+        let table = vla_table();
+        let chunk = table.rows_range(1..3).unwrap();
+        assert_eq!(chunk.nrows, 2);
+        assert_eq!(chunk.heap, vec![0, 30]);
+        assert_eq!(chunk.at(1, 0).unwrap(), BinTableValue::Int32(3));
+        assert_eq!(table.rows_range(3..3).unwrap().nrows, 0);
+        assert!(table.rows_range(2..4).is_err());
+        let (start, end) = (2, 1);
+        assert!(table.rows_range(start..end).is_err());
+        assert!(table.rows_range(4..4).is_err());
+
+        let masked = table.filter_rows(&[true, false, true]).unwrap();
+        assert_eq!(masked.header().get::<i64>("NAXIS2").unwrap(), 2);
+        assert_eq!(masked.heap, vec![0, 10, 0, 20, 0, 30]);
+        assert_eq!(
+            masked.at(0, 1).unwrap(),
+            BinTableValue::Array(vec![BinTableValue::Int16(10), BinTableValue::Int16(20)])
+        );
+        assert!(matches!(
+            table.filter_rows(&[true]),
+            Err(FITSError::Table(TableError::MaskLength(1, 3)))
+        ));
+
+        let fixed = sample_table().rows_range(1..2).unwrap();
+        assert_eq!(fixed.rawbytes, sample_table().rawbytes[14..]);
+    }
+
+    #[test]
+    fn test_sort_by() {
+        let mut table = vla_table();
+        table.sort_by(&["hits"], SortOrder::Descending).unwrap();
+        let ids: Vec<_> = (0..3).map(|row| table.at(row, 0).unwrap()).collect();
+        assert_eq!(
+            ids,
+            vec![
+                BinTableValue::Int32(3),
+                BinTableValue::Int32(1),
+                BinTableValue::Int32(2)
+            ]
+        );
+        assert_eq!(
+            table.at(1, 1).unwrap(),
+            BinTableValue::Array(vec![BinTableValue::Int16(10), BinTableValue::Int16(20)])
+        );
+        assert_eq!(table.heap, vla_table().heap);
+
+        // FLUX[1] is 11 and 10; nulls in FLUX[2] do not matter
+        let mut table = sample_table();
+        table.sort_by(&["FLUX"], SortOrder::Ascending).unwrap();
+        assert_eq!(
+            table.at(0, 2).unwrap(),
+            BinTableValue::String("wxyz".to_string())
+        );
+        table
+            .sort_by(&["NAME", "COUNTS"], SortOrder::Ascending)
+            .unwrap();
+        assert_eq!(
+            table.at(0, 2).unwrap(),
+            BinTableValue::String("ab  ".to_string())
+        );
+        assert!(table.sort_by(&["ENERGY"], SortOrder::Ascending).is_err());
+    }
+
+    #[test]
+    fn test_concat() {
+        let table = vla_table();
+        let tail = table.rows_range(2..3).unwrap();
+        let merged = BinTable::concat(&[&table, &tail]).unwrap();
+        assert_eq!(merged.nrows, 4);
+        assert_eq!(merged.header().get::<i64>("NAXIS2").unwrap(), 4);
+        assert_eq!(merged.heap, vec![0, 10, 0, 20, 0, 30, 0, 30]);
+        assert_eq!(merged.header().get::<i64>("PCOUNT").unwrap(), 8);
+        for row in [2, 3] {
+            assert_eq!(
+                merged.at(row, 1).unwrap(),
+                BinTableValue::Array(vec![BinTableValue::Int16(30)])
+            );
+        }
+
+        let sample = sample_table();
+        let merged = BinTable::concat(&[&sample, &sample, &sample]).unwrap();
+        assert_eq!(merged.rawbytes.len(), 6 * 14);
+        assert_eq!(
+            merged.at_physical(5, 0).unwrap(),
+            sample.at_physical(1, 0).unwrap()
+        );
+
+        assert!(BinTable::concat(&[]).is_err());
+        assert!(matches!(
+            BinTable::concat(&[&table, &sample]),
+            Err(FITSError::Table(TableError::IncompatibleTables(_)))
+        ));
+        let mut renamed = sample_table();
+        renamed.ttype[2] = Some("LABEL".to_string());
+        assert!(BinTable::concat(&[&sample, &renamed]).is_err());
+    }
+
+    #[test]
+    fn test_column_stats() {
+        let mut table = sample_table();
+        let stats = table.column_stats(1).unwrap();
+        assert_eq!((stats.nvalues, stats.nnull), (4, 1));
+        assert_eq!((stats.min, stats.max, stats.mean), (10.0, 11.0, 10.5));
+        assert!((stats.stddev - (0.5f64 / 3.0).sqrt()).abs() < 1e-12);
+        assert_eq!(table.column_stats(0).unwrap().max, 65535.0);
+        assert!(matches!(
+            table.column_stats(2),
+            Err(FITSError::Table(TableError::NotNumeric(2)))
+        ));
+        assert!(table.column_stats(3).is_err());
+
+        table.update_limits().unwrap();
+        let header = table.header();
+        assert_eq!(header.get::<i64>("TDMIN1").unwrap(), 0);
+        assert_eq!(header.get::<i64>("TDMAX1").unwrap(), 65535);
+        assert_eq!(header.get::<f64>("TDMAX2").unwrap(), 11.0);
+        assert!(header.find("TDMIN3").is_none());
+
+        let stats = vla_table().column_stats(1).unwrap();
+        assert_eq!((stats.nvalues, stats.min, stats.max), (3, 10.0, 30.0));
+    }
+
+    #[test]
+    fn test_at_string() {
+        let header = Header::new(vec![
+            kw("XTENSION", KeywordValue::String("BINTABLE".into())),
+            kw("BITPIX", KeywordValue::Int(8)),
+            kw("NAXIS", KeywordValue::Int(2)),
+            kw("NAXIS1", KeywordValue::Int(6)),
+            kw("NAXIS2", KeywordValue::Int(1)),
+            kw("PCOUNT", KeywordValue::Int(0)),
+            kw("GCOUNT", KeywordValue::Int(1)),
+            kw("TFIELDS", KeywordValue::Int(1)),
+            kw("TFORM1", KeywordValue::String("6A3".into())),
+            kw("END", KeywordValue::None),
+        ]);
+        let table = match BinTable::from_bytes(&header, b"ab\0cd ").unwrap() {
+            (HDUData::BinTable(t), _) => *t,
+            _ => panic!("expected binary table"),
+        };
+        let string = |s: &str| BinTableValue::String(s.to_string());
+        assert_eq!(table.at(0, 0).unwrap(), string("ab"));
+        let options = StringOptions {
+            trim: true,
+            split: true,
+        };
+        assert_eq!(
+            table.at_string(0, 0, &options).unwrap(),
+            BinTableValue::Array(vec![string("ab"), string("cd")])
+        );
+        assert_eq!(
+            table.at_string(0, 0, &StringOptions::default()).unwrap(),
+            string("ab")
+        );
+
+        let sample = sample_table();
+        assert_eq!(sample.at_string(0, 2, &options).unwrap(), string("ab"));
+        assert!(matches!(
+            sample.at_string(0, 0, &options),
+            Err(FITSError::Table(TableError::NotCharacter(0)))
+        ));
+    }
+
+    #[test]
+    fn test_set() {
+        let mut table = sample_table();
+        table.set(0, 0, &BinTableValue::Int16(5)).unwrap();
+        table
+            .set(
+                1,
+                1,
+                &BinTableValue::Array(vec![BinTableValue::Null, BinTableValue::Int64(3)]),
+            )
+            .unwrap();
+        table
+            .set(0, 2, &BinTableValue::String("q".to_string()))
+            .unwrap();
+        assert_eq!(table.at(0, 0).unwrap(), BinTableValue::Int16(5));
+        assert_eq!(
+            table.at(1, 1).unwrap(),
+            BinTableValue::Array(vec![BinTableValue::Null, BinTableValue::Int32(3)])
+        );
+        assert_eq!(
+            table.at(0, 2).unwrap(),
+            BinTableValue::String("q   ".to_string())
+        );
+
+        let invalid = |r: Result<(), FITSError>| {
+            matches!(r, Err(FITSError::Table(TableError::IncompatibleValue(..))))
+        };
+        assert!(invalid(table.set(0, 0, &BinTableValue::Int32(40000))));
+        assert!(invalid(table.set(0, 0, &BinTableValue::Null)));
+        assert!(invalid(table.set(0, 1, &BinTableValue::Int32(1))));
+        assert!(invalid(table.set(
+            0,
+            2,
+            &BinTableValue::String("toolong".into())
+        )));
+        assert!(table.set(2, 0, &BinTableValue::Int16(0)).is_err());
+
+        let mut table = vla_table();
+        let hits: Vec<_> = (1..=3).map(BinTableValue::Int16).collect();
+        table
+            .set(1, 1, &BinTableValue::Array(hits.clone()))
+            .unwrap();
+        assert_eq!(table.at(1, 1).unwrap(), BinTableValue::Array(hits));
+        assert_eq!(table.heap.len(), 14);
+        assert_eq!(table.header().get::<i64>("PCOUNT").unwrap(), 14);
+        assert_eq!(table.header().get::<String>("TFORM2").unwrap(), "1PI(3)");
+        assert_eq!(
+            table.at(2, 1).unwrap(),
+            BinTableValue::Array(vec![BinTableValue::Int16(30)])
+        );
+    }
+
+    #[test]
+    fn test_add_remove_column() {
+        let mut table = vla_table();
+        let tags = [vec![1u8], vec![], vec![2, 3]]
+            .map(|v| BinTableValue::Array(v.into_iter().map(BinTableValue::Byte).collect()));
+        table.add_column("TAGS", "1QB", &tags).unwrap();
+        assert_eq!(table.rowbytes, 28);
+        assert_eq!(table.header().get::<i64>("NAXIS1").unwrap(), 28);
+        assert_eq!(table.header().get::<String>("TFORM3").unwrap(), "1QB");
+        assert_eq!(table.header().get::<i64>("PCOUNT").unwrap(), 11);
+        assert_eq!(table.at(2, 2).unwrap(), tags[2]);
+        assert_eq!(
+            table.at(0, 1).unwrap(),
+            BinTableValue::Array(vec![BinTableValue::Int16(10), BinTableValue::Int16(20)])
+        );
+
+        assert!(matches!(
+            table.add_column("id", "1J", &tags),
+            Err(FITSError::Table(TableError::DuplicateColumn(_)))
+        ));
+        assert!(matches!(
+            table.add_column("X", "1J", &tags[..1]),
+            Err(FITSError::Table(TableError::ColumnLength(1, 3)))
+        ));
+        assert!(table.add_column("X", "1J", &tags).is_err());
+        assert_eq!(table.ncols(), 3);
+        assert_eq!(table.heap.len(), 11);
+
+        table.remove_column("hits").unwrap();
+        assert_eq!(table.ncols(), 2);
+        assert_eq!(table.rowbytes, 20);
+        assert_eq!(table.heap, vec![1, 2, 3]);
+        assert_eq!(table.header().get::<String>("TTYPE2").unwrap(), "TAGS");
+        assert_eq!(table.header().get::<String>("TFORM2").unwrap(), "1QB");
+        assert!(table.header().find("TTYPE3").is_none());
+        assert_eq!(table.at(2, 1).unwrap(), tags[2]);
+        assert!(table.remove_column("HITS").is_err());
+    }
+
+    #[test]
+    fn test_push_row() {
+        let mut table = vla_table();
+        let hits = BinTableValue::Array(vec![BinTableValue::Int16(40)]);
+        table
+            .push_row(&[BinTableValue::Int32(4), hits.clone()])
+            .unwrap();
+        assert_eq!(table.nrows, 4);
+        assert_eq!(table.header().get::<i64>("NAXIS2").unwrap(), 4);
+        assert_eq!(table.header().get::<i64>("PCOUNT").unwrap(), 10);
+        assert_eq!(table.at(3, 1).unwrap(), hits);
+        assert_eq!(
+            table.at(2, 1).unwrap(),
+            BinTableValue::Array(vec![BinTableValue::Int16(30)])
+        );
+
+        assert!(matches!(
+            table.push_row(&[BinTableValue::Int32(5)]),
+            Err(FITSError::Table(TableError::RowLength(1, 2)))
+        ));
+        let long = BinTableValue::Array(vec![BinTableValue::Int16(1); 3]);
+        assert!(table
+            .push_row(&[BinTableValue::String("x".into()), long])
+            .is_err());
+        assert_eq!((table.nrows, table.heap.len()), (4, 10));
+        assert_eq!(table.header().get::<String>("TFORM2").unwrap(), "1PI(2)");
+
+        let mut table = sample_table();
+        let flux = BinTableValue::Array(vec![BinTableValue::Int32(6); 2]);
+        table
+            .push_row(&[
+                BinTableValue::Int16(1),
+                flux,
+                BinTableValue::String("new".into()),
+            ])
+            .unwrap();
+        assert_eq!(table.rawbytes.len(), 3 * 14);
+        assert_eq!(
+            table.at_physical(2, 1).unwrap(),
+            BinTableValue::Array(vec![BinTableValue::Float64(13.0); 2])
+        );
+    }
+
+    #[test]
+    fn test_column_index() {
+        let mut table = sample_table();
+        assert_eq!(table.column_index("flux").unwrap(), 1);
+        assert_eq!(table.column_names(), vec!["COUNTS", "FLUX", "NAME"]);
+        table.ttype[0] = None;
+        table.ttype[2] = Some("Flux  ".to_string());
+        assert_eq!(table.column_names(), vec!["COL1", "FLUX", "Flux"]);
+        assert!(matches!(
+            table.column_index("FLUX"),
+            Err(FITSError::Table(TableError::AmbiguousColumn(_)))
+        ));
+        assert!(matches!(
+            table.column_index("COL1"),
+            Err(FITSError::Table(TableError::UnknownColumn(_)))
+        ));
+        assert!(table.select_rows("FLUX > 0").is_err());
+        assert!(table.add_column("flux", "1J", &[]).is_err());
+    }
+
+    #[test]
+    fn test_column_and_rows() {
+        let table = sample_table();
+        assert_eq!(
+            table.column::<i64>("COUNTS").unwrap(),
+            vec![Some(0), Some(65535)]
+        );
+        assert_eq!(
+            table.column::<String>("name").unwrap(),
+            vec![Some("ab  ".to_string()), Some("wxyz".to_string())]
+        );
+        assert!(matches!(
+            table.column::<f64>("FLUX"),
+            Err(FITSError::Table(TableError::InvalidType(0, 1, _)))
+        ));
+        let rows: Vec<_> = table.rows().map(Result::unwrap).collect();
+        assert_eq!(rows[1][0], BinTableValue::UInt16(65535));
+    }
+
+    #[test]
+    fn test_add_computed_column() {
+        let mut table = sample_table();
+        table
+            .add_computed_column("TOTAL", "FLUX[1] + FLUX[2] * 2")
+            .unwrap();
+        table.add_computed_column("BRIGHT", "COUNTS > 100").unwrap();
+        table.add_computed_column("LABEL", "NAME").unwrap();
+        assert_eq!(table.ncols(), 6);
+        assert_eq!(table.rowbytes, 14 + 8 + 1 + 4);
+        assert_eq!(table.rawbytes.len(), 2 * table.rowbytes);
+        assert_eq!(table.tform[3].to_string(), "1D");
+        assert_eq!(table.tform[5].to_string(), "4A");
+        assert_eq!(table.header().get::<i64>("NAXIS1").unwrap(), 27);
+        assert_eq!(table.header().get::<i64>("TFIELDS").unwrap(), 6);
+        assert_eq!(table.header().get::<String>("TTYPE5").unwrap(), "BRIGHT");
+        assert_eq!(table.header().get::<String>("TFORM5").unwrap(), "1L");
+
+        // Null in FLUX[2] gives NaN
+        assert!(matches!(table.at(0, 3).unwrap(), BinTableValue::Float64(v) if v.is_nan()));
+        assert_eq!(table.at(1, 3).unwrap(), BinTableValue::Float64(31.0));
+        assert_eq!(table.at(1, 4).unwrap(), BinTableValue::Logical(true));
+        assert_eq!(
+            table.at(0, 5).unwrap(),
+            BinTableValue::String("ab  ".to_string())
+        );
+        // Existing columns are unchanged
+        assert_eq!(
+            table.at(1, 2).unwrap(),
+            BinTableValue::String("wxyz".to_string())
+        );
+        assert_eq!(table.select_rows("TOTAL > 30 && BRIGHT").unwrap(), vec![1]);
+
+        assert!(table.add_computed_column("total", "1").is_err());
+        assert!(table.add_computed_column("X", "NOSUCH").is_err());
+    }
+
+    #[test]
+    fn test_to_csv() {
+        let table = sample_table();
+        let mut out = Vec::new();
+        table.to_csv(&mut out, &CsvOptions::default()).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "COUNTS,FLUX_1,FLUX_2,NAME\n0,11,,ab\n65535,10,10.5,wxyz\n"
+        );
+
+        let options = CsvOptions {
+            arrays: ArrayStyle::Json,
+            null: "NA".to_string(),
+            ..CsvOptions::tsv()
+        };
+        let mut out = Vec::new();
+        table.to_csv(&mut out, &options).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "COUNTS\tFLUX\tNAME\n0\t[11,null]\tab\n65535\t[10,10.5]\twxyz\n"
+        );
+    }
+
+    #[test]
+    fn test_tdisp() {
+        let format =
+            |tdisp: &str, value: BinTableValue| tdisp.parse::<TDisp>().unwrap().format(&value);
+        for tdisp in [
+            "A8", "L1", "I6.3", "Z4", "F8.3", "E12.4E3", "EN10.2", "ES9.2", "G10.3",
+        ] {
+            assert_eq!(tdisp.parse::<TDisp>().unwrap().to_string(), tdisp);
+        }
+        assert!("F8.2E2".parse::<TDisp>().is_err());
+        assert!("Q4".parse::<TDisp>().is_err());
+
+        assert_eq!(format("A6", BinTableValue::String("ab  ".into())), "ab    ");
+        assert_eq!(format("L2", BinTableValue::Logical(true)), " T");
+        assert_eq!(format("I6.3", BinTableValue::Int32(-7)), "  -007");
+        assert_eq!(format("B8", BinTableValue::Byte(5)), "     101");
+        assert_eq!(format("O4", BinTableValue::Int16(8)), "  10");
+        assert_eq!(format("Z4", BinTableValue::Int64(255)), "  FF");
+        assert_eq!(format("I4", BinTableValue::Float64(2.6)), "   3");
+        assert_eq!(format("F8.3", BinTableValue::Float32(1.5)), "   1.500");
+        assert_eq!(format("F3.1", BinTableValue::Float64(123.0)), "123.0");
+        assert_eq!(
+            format("E11.3", BinTableValue::Float64(-1234.5)),
+            " -1.234E+03"
+        );
+        assert_eq!(
+            format("D10.2E3", BinTableValue::Float64(0.05)),
+            " 5.00D-002"
+        );
+        assert_eq!(
+            format("EN10.2", BinTableValue::Float64(12345.0)),
+            " 12.35E+03"
+        );
+        assert_eq!(format("ES9.2", BinTableValue::Int32(12345)), " 1.23E+04");
+        assert_eq!(format("G8.3", BinTableValue::Float64(12.345)), "    12.3");
+        assert_eq!(format("G10.3", BinTableValue::Float64(1.0e6)), "  1.00E+06");
+        assert_eq!(format("F6.1", BinTableValue::Null), "      ");
+        assert_eq!(format("F6.1", BinTableValue::Float64(f64::NAN)), "      ");
+
+        let mut table = sample_table();
+        table
+            .header
+            .set("TDISP2", KeywordValue::String("F6.2".into()));
+        let mut out = Vec::new();
+        table.to_csv(&mut out, &CsvOptions::default()).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "COUNTS,FLUX_1,FLUX_2,NAME\n0,11.00,,ab\n65535,10.00,10.50,wxyz\n"
+        );
+    }
+
+    #[cfg(feature = "arrow")]
+    #[test]
+    fn test_to_record_batch() {
+        use arrow_array::cast::AsArray;
+        use arrow_array::types::{Float64Type, UInt16Type};
+        use arrow_array::Array;
+        use arrow_schema::DataType;
+
+        let batch = sample_table().to_record_batch().unwrap();
+        assert_eq!(batch.num_rows(), 2);
+        let schema = batch.schema();
+        assert_eq!(schema.field(0).name(), "COUNTS");
+        assert_eq!(schema.field(0).data_type(), &DataType::UInt16);
+        assert!(matches!(
+            schema.field(1).data_type(),
+            DataType::FixedSizeList(item, 2) if item.data_type() == &DataType::Float64
+        ));
+        assert_eq!(schema.field(2).data_type(), &DataType::Utf8);
+
+        let counts = batch.column(0).as_primitive::<UInt16Type>();
+        assert_eq!(counts.values(), &[0, 65535]);
+        let flux = batch.column(1).as_fixed_size_list();
+        let values = flux.values().as_primitive::<Float64Type>();
+        assert_eq!(values.len(), 4);
+        assert!(values.is_null(1));
+        assert_eq!(
+            (values.value(0), values.value(2), values.value(3)),
+            (11.0, 10.0, 10.5)
+        );
+        let names = batch.column(2).as_string::<i32>();
+        assert_eq!((names.value(0), names.value(1)), ("ab", "wxyz"));
+    }
+
+    #[cfg(feature = "polars")]
+    #[test]
+    fn test_to_polars() {
+        use ::polars::prelude::DataType;
+
+        let df = sample_table().to_polars().unwrap();
+        assert_eq!(df.shape(), (2, 3));
+        assert_eq!(df.get_column_names(), &["COUNTS", "FLUX", "NAME"]);
+
+        let counts = df.column("COUNTS").unwrap();
+        assert_eq!(counts.dtype(), &DataType::UInt16);
+        let counts: Vec<Option<u16>> = counts.u16().unwrap().into_iter().collect();
+        assert_eq!(counts, vec![Some(0), Some(65535)]);
+
+        let flux = df.column("FLUX").unwrap();
+        assert_eq!(flux.dtype(), &DataType::List(Box::new(DataType::Float64)));
+        let first = flux.list().unwrap().get_as_series(0).unwrap();
+        let first: Vec<Option<f64>> = first.f64().unwrap().into_iter().collect();
+        assert_eq!(first, vec![Some(11.0), None]);
+
+        let names: Vec<Option<&str>> = df
+            .column("NAME")
+            .unwrap()
+            .str()
+            .unwrap()
+            .into_iter()
+            .collect();
+        assert_eq!(names, vec![Some("ab"), Some("wxyz")]);
+    }
+
+    #[test]
+    fn test_tform() {
+        let t = "1PE(100)".parse::<TForm>().unwrap();
+        assert_eq!(t.ftype, TFormType::ArrayD32(Box::new(TFormType::Float32)));
+        assert_eq!(t.maxlen, Some(100));
+        assert_eq!(t.size(), 8);
+        assert_eq!("13X".parse::<TForm>().unwrap().size(), 2);
+        assert_eq!("D".parse::<TForm>().unwrap().repeat, 1);
+        assert!("3Z".parse::<TForm>().is_err());
+        let t = "20A10".parse::<TForm>().unwrap();
+        assert_eq!((t.repeat, t.width, t.size()), (20, Some(10), 20));
+        assert_eq!(t.to_string(), "20A10");
+    }
+
+    #[test]
+    fn test_at() {
+        let table = sample_table();
+        assert_eq!(table.at(0, 0).unwrap(), BinTableValue::Int16(-32768));
+        assert_eq!(
+            table.at(0, 1).unwrap(),
+            BinTableValue::Array(vec![BinTableValue::Int32(2), BinTableValue::Null])
+        );
+        assert_eq!(
+            table.at(1, 2).unwrap(),
+            BinTableValue::String("wxyz".into())
+        );
+        assert!(table.at(2, 0).is_err());
+        assert!(table.at(0, 3).is_err());
+    }
+
+    #[test]
+    fn test_heap_overflow() {
+        let header = Header::new(vec![
+            kw("XTENSION", KeywordValue::String("BINTABLE".into())),
+            kw("BITPIX", KeywordValue::Int(8)),
+            kw("NAXIS", KeywordValue::Int(2)),
+            kw("NAXIS1", KeywordValue::Int(16)),
+            kw("NAXIS2", KeywordValue::Int(1)),
+            kw("PCOUNT", KeywordValue::Int(8)),
+            kw("GCOUNT", KeywordValue::Int(1)),
+            kw("TFIELDS", KeywordValue::Int(1)),
+            kw("TFORM1", KeywordValue::String("1QD".into())),
+            kw("END", KeywordValue::None),
+        ]);
+        for (count, offset) in [(0x2000000000000000u64, 0u64), (1, u64::MAX - 4)] {
+            let mut raw = Vec::new();
+            raw.extend_from_slice(&count.to_be_bytes());
+            raw.extend_from_slice(&offset.to_be_bytes());
+            raw.extend_from_slice(&[0; 8]);
+            let table = match BinTable::from_bytes(&header, &raw).unwrap() {
+                (HDUData::BinTable(t), _) => *t,
+                _ => panic!("expected binary table"),
+            };
+            assert!(matches!(
+                table.at(0, 0),
+                Err(FITSError::Table(TableError::HeapOutOfRange(0)))
+            ));
+        }
+    }
+
+    #[test]
+    fn test_at_physical() {
+        let table = sample_table();
+        assert_eq!(table.at_physical(0, 0).unwrap(), BinTableValue::UInt16(0));
+        assert_eq!(
+            table.at_physical(1, 0).unwrap(),
+            BinTableValue::UInt16(65535)
+        );
+        assert_eq!(
+            table.at_physical(0, 1).unwrap(),
+            BinTableValue::Array(vec![BinTableValue::Float64(11.0), BinTableValue::Null])
+        );
+        assert_eq!(
+            table.at_physical(0, 2).unwrap(),
+            BinTableValue::String("ab  ".into())
+        );
+    }
+
+    #[test]
+    fn test_column_wcs() {
+        let header = Header::new(vec![
+            kw("XTENSION", KeywordValue::String("BINTABLE".into())),
+            kw("BITPIX", KeywordValue::Int(8)),
+            kw("NAXIS", KeywordValue::Int(2)),
+            kw("NAXIS1", KeywordValue::Int(12)),
+            kw("NAXIS2", KeywordValue::Int(2)),
+            kw("PCOUNT", KeywordValue::Int(0)),
+            kw("GCOUNT", KeywordValue::Int(1)),
+            kw("TFIELDS", KeywordValue::Int(3)),
+            kw("TTYPE1", KeywordValue::String("ENERGY".into())),
+            kw("TFORM1", KeywordValue::String("1J".into())),
+            kw("TTYPE2", KeywordValue::String("X".into())),
+            kw("TFORM2", KeywordValue::String("1E".into())),
+            kw("TCTYP2", KeywordValue::String("RA---TAN".into())),
+            kw("TCRVL2", KeywordValue::Float(83.6)),
+            kw("TCRPX2", KeywordValue::Float(4096.5)),
+            kw("TCDLT2", KeywordValue::Float(-1.0e-4)),
+            kw("TTYPE3", KeywordValue::String("Y".into())),
+            kw("TFORM3", KeywordValue::String("1E".into())),
+            kw("TCTYP3", KeywordValue::String("DEC--TAN".into())),
+            kw("TCRVL3", KeywordValue::Float(22.0)),
+            kw("TCRPX3", KeywordValue::Float(4096.5)),
+            kw("TCDLT3", KeywordValue::Float(1.0e-4)),
+            kw("END", KeywordValue::None),
+        ]);
+        let mut raw = Vec::new();
+        for (e, x, y) in [(100i32, 4096.5f32, 4096.5f32), (200, 4096.5, 4106.5)] {
+            raw.extend_from_slice(&e.to_be_bytes());
+            raw.extend_from_slice(&x.to_be_bytes());
+            raw.extend_from_slice(&y.to_be_bytes());
+        }
+        let table = match BinTable::from_bytes(&header, &raw).unwrap() {
+            (HDUData::BinTable(t), _) => *t,
+            _ => panic!("expected binary table"),
+        };
+        assert!(table.wcs_columns(0).is_empty());
+        assert!(table.wcs_for_column(0).unwrap().is_none());
+        assert_eq!(table.wcs_columns(2), vec![1, 2]);
+
+        let world = table.world_at(0, 1).unwrap();
+        assert!((world[0] - 83.6).abs() < 1.0e-10);
+        assert!((world[1] - 22.0).abs() < 1.0e-10);
+        // Ten pixels north of the reference point
+        let world = table.world_at(1, 2).unwrap();
+        assert!((world[0] - 83.6).abs() < 1.0e-10);
+        assert!((world[1] - 22.001).abs() < 1.0e-8);
+        assert!(table.world_at(0, 0).is_err());
+        let coords = table.world_coords(1).unwrap();
+        assert_eq!(coords.len(), 2);
+        assert_eq!(coords[1], world);
+        assert!(table.world_coords(0).is_err());
+    }
+
+    #[test]
+    fn test_bin_image() {
+        let header = Header::new(vec![
+            kw("XTENSION", KeywordValue::String("BINTABLE".into())),
+            kw("BITPIX", KeywordValue::Int(8)),
+            kw("NAXIS", KeywordValue::Int(2)),
+            kw("NAXIS1", KeywordValue::Int(12)),
+            kw("NAXIS2", KeywordValue::Int(4)),
+            kw("PCOUNT", KeywordValue::Int(0)),
+            kw("GCOUNT", KeywordValue::Int(1)),
+            kw("TFIELDS", KeywordValue::Int(3)),
+            kw("TTYPE1", KeywordValue::String("X".into())),
+            kw("TFORM1", KeywordValue::String("1E".into())),
+            kw("TLMIN1", KeywordValue::Int(1)),
+            kw("TLMAX1", KeywordValue::Int(8)),
+            kw("TCTYP1", KeywordValue::String("RA---TAN".into())),
+            kw("TCRVL1", KeywordValue::Float(83.6)),
+            kw("TCRPX1", KeywordValue::Float(4.5)),
+            kw("TCDLT1", KeywordValue::Float(-1.0e-4)),
+            kw("TTYPE2", KeywordValue::String("Y".into())),
+            kw("TFORM2", KeywordValue::String("1E".into())),
+            kw("TLMIN2", KeywordValue::Int(1)),
+            kw("TLMAX2", KeywordValue::Int(8)),
+            kw("TCTYP2", KeywordValue::String("DEC--TAN".into())),
+            kw("TCRVL2", KeywordValue::Float(22.0)),
+            kw("TCRPX2", KeywordValue::Float(4.5)),
+            kw("TCDLT2", KeywordValue::Float(1.0e-4)),
+            kw("TTYPE3", KeywordValue::String("PI".into())),
+            kw("TFORM3", KeywordValue::String("1J".into())),
+            kw("END", KeywordValue::None),
+        ]);
+        let mut raw = Vec::new();
+        for (x, y, pi) in [
+            (1.0f32, 1.0f32, 10i32),
+            (1.0, 2.0, 20),
+            (8.0, 8.0, 5),
+            (9.0, 1.0, 7),
+        ] {
+            raw.extend_from_slice(&x.to_be_bytes());
+            raw.extend_from_slice(&y.to_be_bytes());
+            raw.extend_from_slice(&pi.to_be_bytes());
+        }
+        let table = match BinTable::from_bytes(&header, &raw).unwrap() {
+            (HDUData::BinTable(t), _) => *t,
+            _ => panic!("expected binary table"),
+        };
+
+        let image = table.bin_image("X", "Y", [4, 4], None).unwrap();
+        assert_eq!(image.axes, vec![4, 4]);
+        let counts = image.pixels::<i32>().unwrap();
+        assert_eq!(
+            (counts[0], counts[15], counts.iter().sum::<i32>()),
+            (2, 1, 3)
+        );
+        // Column value 4.5, the reference point, is pixel 1.5 of the image
+        let world = image
+            .wcs
+            .as_ref()
+            .unwrap()
+            .pixel_to_world(&[1.5, 1.5])
+            .unwrap();
+        assert!((world[0] - 83.6).abs() < 1.0e-10);
+        assert!((world[1] - 22.0).abs() < 1.0e-10);
+
+        let image = table.bin_image("X", "Y", [8, 8], Some("PI")).unwrap();
+        assert_eq!(image.pixels::<f64>().unwrap()[8], 20.0);
+        assert!(table.bin_image("X", "Y", [0, 8], None).is_err());
+    }
+
+    #[test]
+    fn test_bin_lightcurve() {
+        let header = Header::new(vec![
+            kw("XTENSION", KeywordValue::String("BINTABLE".into())),
+            kw("BITPIX", KeywordValue::Int(8)),
+            kw("NAXIS", KeywordValue::Int(2)),
+            kw("NAXIS1", KeywordValue::Int(8)),
+            kw("NAXIS2", KeywordValue::Int(6)),
+            kw("PCOUNT", KeywordValue::Int(0)),
+            kw("GCOUNT", KeywordValue::Int(1)),
+            kw("TFIELDS", KeywordValue::Int(1)),
+            kw("TTYPE1", KeywordValue::String("TIME".into())),
+            kw("TFORM1", KeywordValue::String("1D".into())),
+            kw("TUNIT1", KeywordValue::String("s".into())),
+            kw("MJDREF", KeywordValue::Float(51544.0)),
+            kw("END", KeywordValue::None),
+        ]);
+        let raw: Vec<u8> = [0.5f64, 1.5, 1.7, 2.2, 5.5, 9.9]
+            .iter()
+            .flat_map(|t| t.to_be_bytes())
+            .collect();
+        let table = match BinTable::from_bytes(&header, &raw).unwrap() {
+            (HDUData::BinTable(t), _) => *t,
+            _ => panic!("expected binary table"),
+        };
+
+        let gti = crate::Gti::new(&[(0.0, 3.0), (5.0, 9.5)]);
+        let curve = table.bin_lightcurve("TIME", 1.0, Some(&gti)).unwrap();
+        // Bins 3 and 4 are outside the GTIs; 9.9 is after the last GTI
+        assert_eq!(
+            curve.column::<f64>("TIME").unwrap(),
+            [0.5, 1.5, 2.5, 5.5, 6.5, 7.5, 8.5, 9.5].map(Some).to_vec()
+        );
+        let counts = curve.column::<i64>("COUNTS").unwrap();
+        assert_eq!(counts[..4], [Some(1), Some(2), Some(1), Some(1)]);
+        let rate = curve.column::<f64>("RATE").unwrap();
+        assert_eq!(rate[7], Some(0.0));
+        let error = curve.column::<f64>("ERROR").unwrap();
+        assert_eq!(error[1], Some(2f64.sqrt()));
+        assert_eq!(curve.header().get::<f64>("MJDREF").unwrap(), 51544.0);
+        assert_eq!(curve.unit(2), Some("count/s"));
+
+        let curve = table.bin_lightcurve("TIME", 2.0, None).unwrap();
+        assert_eq!(
+            curve.column::<i64>("COUNTS").unwrap(),
+            [4, 0, 1, 0, 1].map(Some).to_vec()
+        );
+        assert!(table.bin_lightcurve("TIME", 0.0, None).is_err());
+    }
+
+    #[test]
+    fn test_invalid_size() {
+        let table = |naxis2: &str, pcount: &str| {
+            crate::testutil::hdu_bytes(
+                &[
+                    "XTENSION= 'BINTABLE'",
+                    "BITPIX  =                    8",
+                    "NAXIS   =                    2",
+                    "NAXIS1  =                    8",
+                    &format!("NAXIS2  = {:>20}", naxis2),
+                    &format!("PCOUNT  = {:>20}", pcount),
+                    "GCOUNT  =                    1",
+                    "TFIELDS =                    1",
+                    "TFORM1  = '1D      '",
+                    "END",
+                ],
+                &[0; 16],
+            )
+        };
+        assert!(crate::FITS::from_bytes(&table("2", "0")).is_ok());
+        for (naxis2, pcount) in [("2305843009213693952", "0"), ("2", "-8")] {
+            let raw = table(naxis2, pcount);
+            let (header, _) = crate::hdu::read_header(&raw, None).unwrap();
+            assert!(matches!(
+                BinTable::from_bytes(&header, &raw[2880..]),
+                Err(FITSError::Header(HeaderError::GenericError(_)))
+            ));
+            assert!(crate::FITS::from_bytes(&raw).is_err());
+        }
+    }
+}
@@ -0,0 +1,125 @@
+//! Conversion of tables to Polars, enabled by the `polars` feature
+
+use ::polars::prelude::{Column, DataFrame, DataType, IntoColumn, NamedFrom, PlSmallStr, Series};
+
+use super::export::{column_cells, column_layout, complex_parts, elements, ElementType, Layout};
+use super::BinTableValue;
+use crate::FITSError;
+use crate::TableLike;
+
+/// Polars type of a single element; complex numbers are a list of two
+/// floats, real part first
+fn data_type(etype: ElementType) -> DataType {
+    let complex = |t: DataType| DataType::List(Box::new(t));
+    match etype {
+        ElementType::Boolean => DataType::Boolean,
+        ElementType::String => DataType::String,
+        ElementType::UInt8 => DataType::UInt8,
+        ElementType::Int8 => DataType::Int8,
+        ElementType::Int16 => DataType::Int16,
+        ElementType::UInt16 => DataType::UInt16,
+        ElementType::Int32 => DataType::Int32,
+        ElementType::UInt32 => DataType::UInt32,
+        ElementType::Int64 => DataType::Int64,
+        ElementType::UInt64 => DataType::UInt64,
+        ElementType::Float32 => DataType::Float32,
+        ElementType::Float64 => DataType::Float64,
+        ElementType::Complex32 => complex(DataType::Float32),
+        ElementType::Complex64 => complex(DataType::Float64),
+    }
+}
+
+/// Build a series from values, with nulls where `$variant` does not match
+macro_rules! primitive {
+    ($name:expr, $values:expr, $variant:ident) => {
+        Series::new(
+            $name,
+            $values
+                .iter()
+                .map(|v| match v {
+                    BinTableValue::$variant(x) => Some(*x),
+                    _ => None,
+                })
+                .collect::<Vec<_>>(),
+        )
+    };
+}
+
+/// Build a series of elements of the given type
+fn element_series(
+    name: PlSmallStr,
+    dtype: &DataType,
+    values: &[BinTableValue],
+) -> Result<Series, FITSError> {
+    Ok(match dtype {
+        DataType::Boolean => primitive!(name, values, Logical),
+        DataType::UInt8 => primitive!(name, values, Byte),
+        DataType::Int8 => primitive!(name, values, Int8),
+        DataType::Int16 => primitive!(name, values, Int16),
+        DataType::UInt16 => primitive!(name, values, UInt16),
+        DataType::Int32 => primitive!(name, values, Int32),
+        DataType::UInt32 => primitive!(name, values, UInt32),
+        DataType::Int64 => primitive!(name, values, Int64),
+        DataType::UInt64 => primitive!(name, values, UInt64),
+        DataType::Float32 => primitive!(name, values, Float32),
+        DataType::Float64 => Series::new(
+            name,
+            values.iter().map(BinTableValue::as_f64).collect::<Vec<_>>(),
+        ),
+        DataType::String => Series::new(
+            name,
+            values
+                .iter()
+                .map(|v| match v {
+                    BinTableValue::String(s) => Some(s.trim_end_matches([' ', '\0'])),
+                    _ => None,
+                })
+                .collect::<Vec<_>>(),
+        ),
+        DataType::List(inner) => {
+            let lists = values
+                .iter()
+                .map(|v| element_series(PlSmallStr::EMPTY, inner, &complex_parts(v)))
+                .collect::<Result<Vec<_>, _>>()?;
+            list_series(name, inner, lists)
+        }
+        other => {
+            return Err(FITSError::Polars(format!(
+                "unsupported element type {}",
+                other
+            )))
+        }
+    })
+}
+
+/// Build a list series, one list per cell
+fn list_series(name: PlSmallStr, inner: &DataType, lists: Vec<Series>) -> Series {
+    match lists.is_empty() {
+        true => Series::new_empty(name, &DataType::List(Box::new(inner.clone()))),
+        false => Series::new(name, lists),
+    }
+}
+
+/// Convert a table to a Polars data frame; see `TableLike::to_polars`
+pub(crate) fn data_frame<T: TableLike>(table: &T) -> Result<DataFrame, FITSError> {
+    let mut columns: Vec<Column> = Vec::with_capacity(table.ncols());
+    for (col, name) in table.column_names().into_iter().enumerate() {
+        let (etype, layout) = column_layout(table, col)?;
+        let etype = data_type(etype);
+        let cells = column_cells(table, col)?;
+        let name = PlSmallStr::from(name);
+
+        let series = match layout {
+            Layout::Scalar => element_series(name, &etype, &cells)?,
+            Layout::Fixed(_) | Layout::Variable => {
+                let lists = cells
+                    .into_iter()
+                    .map(|cell| element_series(PlSmallStr::EMPTY, &etype, &elements(cell)))
+                    .collect::<Result<Vec<_>, _>>()?;
+                list_series(name, &etype, lists)
+            }
+        };
+        columns.push(series.into_column());
+    }
+    DataFrame::new(columns).map_err(|e| FITSError::Polars(e.to_string()))
+}
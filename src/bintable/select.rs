@@ -0,0 +1,277 @@
+//! Tables made from a subset of the rows and columns of another
+//!
+//! The main table is copied row by row.  Variable-length arrays are
+//! gathered into a new heap holding only the arrays the copied
+//! descriptors point to, so dropped rows and columns do not leave
+//! unused heap bytes behind
+
+use super::encode::push_descriptor;
+use super::BinTable;
+use crate::FITSError;
+use crate::Header;
+use crate::Keyword;
+use crate::KeywordValue;
+use crate::TFormType;
+use crate::TableError;
+
+/// Column keywords renumbered by `select_columns`; see Table 17 of
+/// FITS standard, version 4, and Table 22 of the WCS paper I
+const COLUMN_KEYWORDS: &[&str] = &[
+    "TTYPE", "TFORM", "TUNIT", "TSCAL", "TZERO", "TNULL", "TDISP", "TDIM", "TDMIN", "TDMAX",
+    "TLMIN", "TLMAX", "TCTYP", "TCUNI", "TCRPX", "TCRVL", "TCDLT", "TCROT", "TCRDE", "TCSYE",
+];
+
+/// Split a column keyword name into its prefix and column number
+fn column_keyword(name: &str) -> Option<(&str, usize)> {
+    let split = name.find(|c: char| c.is_ascii_digit())?;
+    let (prefix, number) = name.split_at(split);
+    match COLUMN_KEYWORDS.contains(&prefix) {
+        true => Some((prefix, number.parse().ok()?)),
+        false => None,
+    }
+}
+
+/// Header with the column keywords of the given columns, numbered in
+/// their new order
+///
+/// The new keywords take the place of the first column keyword;
+/// keywords whose number is unchanged keep their original card
+fn renumber_columns(header: &Header, cols: &[usize]) -> Header {
+    let renumbered: Vec<Keyword> = cols
+        .iter()
+        .enumerate()
+        .flat_map(|(new, &col)| {
+            header
+                .iter()
+                .filter_map(move |kw| match column_keyword(&kw.name) {
+                    Some((_, n)) if n == col + 1 && n == new + 1 => Some(kw.clone()),
+                    Some((prefix, n)) if n == col + 1 => Some(Keyword {
+                        name: format!("{}{}", prefix, new + 1),
+                        value: kw.value.clone(),
+                        comment: kw.comment.clone(),
+                        unit: kw.unit.clone(),
+                        raw: None,
+                    }),
+                    _ => None,
+                })
+        })
+        .collect();
+    let mut keywords = Vec::with_capacity(header.len());
+    let mut renumbered = Some(renumbered);
+    for kw in header.iter() {
+        match column_keyword(&kw.name) {
+            Some(_) => keywords.extend(renumbered.take().unwrap_or_default()),
+            None => keywords.push(kw.clone()),
+        }
+    }
+    Header::new(keywords)
+}
+
+impl BinTable {
+    /// Table with only the named columns, in the given order
+    ///
+    /// Column keywords (TTYPEn, TFORMn, TUNITn, TSCALn, TZEROn, TNULLn,
+    /// TDISPn, TDIMn, limits, and the single-column WCS keywords
+    /// TCTYPn etc.) of the kept columns are renumbered in `header`, and
+    /// those of dropped columns removed.  NAXIS1, TFIELDS, and PCOUNT
+    /// are updated, so the table can be written as a new HDU with
+    /// `header` as its header
+    ///
+    /// # Arguments
+    ///
+    /// * `names` - Column names (TTYPEn), matched case-insensitively
+    ///
+    /// # Returns
+    ///
+    /// New table, or an error if a name does not match a column
+    ///
+    pub fn select_columns(&self, names: &[&str]) -> Result<BinTable, FITSError> {
+        let cols = names
+            .iter()
+            .map(|name| self.column_index(name))
+            .collect::<Result<Vec<_>, _>>()?;
+        self.subset(0..self.nrows, &cols)
+    }
+
+    /// Table of a contiguous range of rows
+    ///
+    /// Only the heap arrays of the rows in the range are copied, so a
+    /// large table can be processed in chunks, e.g. `rows_range(0..1000)`,
+    /// `rows_range(1000..2000)`, and so on.  NAXIS2 and PCOUNT are
+    /// updated in `header`
+    ///
+    /// # Arguments
+    ///
+    /// * `range` - Rows to keep
+    ///
+    /// # Returns
+    ///
+    /// New table, or an error if the range starts after it ends or
+    /// extends past the last row
+    ///
+    pub fn rows_range(&self, range: std::ops::Range<usize>) -> Result<BinTable, FITSError> {
+        if range.start > range.end.min(self.nrows) {
+            return Err(FITSError::from(TableError::RowOutOfRange(
+                range.start,
+                self.nrows,
+            )));
+        }
+        if range.end > self.nrows {
+            return Err(FITSError::from(TableError::RowOutOfRange(
+                range.end - 1,
+                self.nrows,
+            )));
+        }
+        self.take_rows(range)
+    }
+
+    /// Table of the rows where a mask is true
+    ///
+    /// Only the heap arrays of the kept rows are copied.  NAXIS2 and
+    /// PCOUNT are updated in `header`
+    ///
+    /// # Arguments
+    ///
+    /// * `mask` - One entry per row; rows where it is true are kept
+    ///
+    /// # Returns
+    ///
+    /// New table, or an error if the mask length differs from the
+    /// number of rows
+    ///
+    pub fn filter_rows(&self, mask: &[bool]) -> Result<BinTable, FITSError> {
+        if mask.len() != self.nrows {
+            return Err(FITSError::from(TableError::MaskLength(
+                mask.len(),
+                self.nrows,
+            )));
+        }
+        self.take_rows(
+            mask.iter()
+                .enumerate()
+                .filter(|(_, &m)| m)
+                .map(|(row, _)| row),
+        )
+    }
+
+    /// Table with the given rows, in the given order, and all columns
+    pub(super) fn take_rows<I>(&self, rows: I) -> Result<BinTable, FITSError>
+    where
+        I: IntoIterator<Item = usize>,
+    {
+        let cols: Vec<usize> = (0..self.ncols()).collect();
+        self.subset(rows, &cols)
+    }
+
+    /// Table with the given rows and columns, in the given order
+    ///
+    /// The heap of the new table holds only the arrays of the copied
+    /// cells, starting right after the main table (THEAP is updated if
+    /// present)
+    pub(super) fn subset<I>(&self, rows: I, cols: &[usize]) -> Result<BinTable, FITSError>
+    where
+        I: IntoIterator<Item = usize>,
+    {
+        let mut offsets = Vec::with_capacity(cols.len());
+        let mut rowbytes = 0;
+        for &col in cols {
+            offsets.push(rowbytes);
+            rowbytes += self.tform[col].size();
+        }
+
+        let mut rawbytes = Vec::new();
+        let mut heap = Vec::new();
+        let nrows = self.copy_rows(rows, cols, &mut rawbytes, &mut heap)?;
+
+        let identity = cols.len() == self.ncols() && cols.iter().enumerate().all(|(i, &c)| i == c);
+        let header = match identity {
+            true => self.header.clone(),
+            false => renumber_columns(&self.header, cols),
+        };
+        let mut table = BinTable {
+            nrows,
+            rowbytes,
+            ttype: cols.iter().map(|&c| self.ttype[c].clone()).collect(),
+            tform: cols.iter().map(|&c| self.tform[c].clone()).collect(),
+            tunit: cols.iter().map(|&c| self.tunit[c].clone()).collect(),
+            tscal: cols.iter().map(|&c| self.tscal[c]).collect(),
+            tzero: cols.iter().map(|&c| self.tzero[c]).collect(),
+            tnull: cols.iter().map(|&c| self.tnull[c]).collect(),
+            theap: rawbytes.len(),
+            rawbytes,
+            heap,
+            offsets,
+            header,
+        };
+        table.set_layout_keywords();
+        Ok(table)
+    }
+
+    /// Append rows of the given columns to a main table and heap
+    ///
+    /// Rows are checked against the table size as they are copied, so
+    /// rows may come from any iterator without collecting them first.
+    /// Variable-length arrays are appended to `heap`, and their
+    /// descriptors rewritten to point there
+    ///
+    /// # Returns
+    ///
+    /// The number of rows copied
+    ///
+    pub(super) fn copy_rows<I>(
+        &self,
+        rows: I,
+        cols: &[usize],
+        rawbytes: &mut Vec<u8>,
+        heap: &mut Vec<u8>,
+    ) -> Result<usize, FITSError>
+    where
+        I: IntoIterator<Item = usize>,
+    {
+        // Rows of all columns without heap arrays are copied whole
+        let identity = cols.len() == self.ncols() && cols.iter().enumerate().all(|(i, &c)| i == c);
+        let whole_rows = identity
+            && self
+                .tform
+                .iter()
+                .all(|t| !matches!(t.ftype, TFormType::ArrayD32(_) | TFormType::ArrayD64(_)));
+
+        let mut nrows = 0;
+        for row in rows {
+            if row >= self.nrows {
+                return Err(FITSError::from(TableError::RowOutOfRange(row, self.nrows)));
+            }
+            nrows += 1;
+            if whole_rows {
+                let start = row * self.rowbytes;
+                rawbytes.extend_from_slice(&self.rawbytes[start..start + self.rowbytes]);
+                continue;
+            }
+            for &col in cols {
+                let start = row * self.rowbytes + self.offsets[col];
+                let field = &self.rawbytes[start..start + self.tform[col].size()];
+                match self.heap_array(col, field)? {
+                    None => rawbytes.extend_from_slice(field),
+                    Some((_, count, range)) => {
+                        push_descriptor(rawbytes, &self.tform[col].ftype, count, heap.len(), col)?;
+                        heap.extend_from_slice(&self.heap[range]);
+                    }
+                }
+            }
+        }
+        Ok(nrows)
+    }
+
+    /// Set NAXIS1, NAXIS2, PCOUNT, TFIELDS, and THEAP (if present) in
+    /// `header` from the table layout
+    pub(super) fn set_layout_keywords(&mut self) {
+        let header = &mut self.header;
+        header.set("NAXIS1", KeywordValue::Int(self.rowbytes as i64));
+        header.set("NAXIS2", KeywordValue::Int(self.nrows as i64));
+        header.set("PCOUNT", KeywordValue::Int(self.heap.len() as i64));
+        header.set("TFIELDS", KeywordValue::Int(self.tform.len() as i64));
+        if header.find("THEAP").is_some() {
+            header.set("THEAP", KeywordValue::Int(self.theap as i64));
+        }
+    }
+}
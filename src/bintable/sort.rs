@@ -0,0 +1,114 @@
+//! Sorting of table rows by column values
+
+use super::BinTable;
+use super::BinTableValue;
+use crate::FITSError;
+use std::cmp::Ordering;
+
+/// Direction of a sort
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SortOrder {
+    /// Smallest values first
+    #[default]
+    Ascending,
+    /// Largest values first
+    Descending,
+}
+
+/// Compare two cell values
+///
+/// Numbers compare by value across types, strings without trailing
+/// blanks, and arrays element by element.  Nulls and NaN sort after
+/// every other value, whatever the order
+fn compare(a: &BinTableValue, b: &BinTableValue, order: SortOrder) -> Ordering {
+    let is_null =
+        |v: &BinTableValue| matches!(v, BinTableValue::Null) || v.as_f64().is_some_and(f64::is_nan);
+    match (is_null(a), is_null(b)) {
+        (true, true) => return Ordering::Equal,
+        (true, false) => return Ordering::Greater,
+        (false, true) => return Ordering::Less,
+        _ => {}
+    }
+    let ordering = match (a, b) {
+        (BinTableValue::String(a), BinTableValue::String(b)) => a.trim_end().cmp(b.trim_end()),
+        (BinTableValue::Logical(a), BinTableValue::Logical(b)) => a.cmp(b),
+        (BinTableValue::Bits(a), BinTableValue::Bits(b)) => a.cmp(b),
+        (BinTableValue::Array(a), BinTableValue::Array(b)) => {
+            // Element comparisons already follow the order
+            return a
+                .iter()
+                .zip(b)
+                .map(|(a, b)| compare(a, b, order))
+                .find(|o| o.is_ne())
+                .unwrap_or_else(|| match order {
+                    SortOrder::Ascending => a.len().cmp(&b.len()),
+                    SortOrder::Descending => b.len().cmp(&a.len()),
+                });
+        }
+        _ => match (a.as_integer(), b.as_integer()) {
+            (Some(a), Some(b)) => a.cmp(&b),
+            _ => match (a.as_f64(), b.as_f64()) {
+                (Some(a), Some(b)) => a.total_cmp(&b),
+                _ => Ordering::Equal,
+            },
+        },
+    };
+    match order {
+        SortOrder::Ascending => ordering,
+        SortOrder::Descending => ordering.reverse(),
+    }
+}
+
+impl BinTable {
+    /// Reorder rows by the values of one or more columns
+    ///
+    /// The sort is stable: rows with equal keys keep their relative
+    /// order, so sorting an event list already ordered by detector by
+    /// `TIME` keeps simultaneous events in detector order.  Later
+    /// columns break ties in earlier ones.  Values are physical (see
+    /// `at_physical`), and nulls sort last.  Only the main table is
+    /// reordered; variable-length array descriptors move with their
+    /// rows and still point to the same heap arrays
+    ///
+    /// # Arguments
+    ///
+    /// * `columns` - Names (TTYPEn) of the sort keys, matched
+    ///   case-insensitively
+    /// * `order` - Ascending or descending, for all keys
+    ///
+    /// # Returns
+    ///
+    /// Nothing, or an error if a name does not match a column
+    ///
+    pub fn sort_by(&mut self, columns: &[&str], order: SortOrder) -> Result<(), FITSError> {
+        let cols = columns
+            .iter()
+            .map(|name| self.column_index(name))
+            .collect::<Result<Vec<_>, _>>()?;
+        let keys = (0..self.nrows)
+            .map(|row| {
+                cols.iter()
+                    .map(|&col| self.at_physical(row, col))
+                    .collect::<Result<Vec<_>, _>>()
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let mut rows: Vec<usize> = (0..self.nrows).collect();
+        rows.sort_by(|&a, &b| {
+            keys[a]
+                .iter()
+                .zip(&keys[b])
+                .map(|(a, b)| compare(a, b, order))
+                .find(|o| o.is_ne())
+                .unwrap_or(Ordering::Equal)
+        });
+
+        let mut rawbytes = Vec::with_capacity(self.rawbytes.len());
+        for row in rows {
+            let start = row * self.rowbytes;
+            rawbytes.extend_from_slice(&self.rawbytes[start..start + self.rowbytes]);
+        }
+        self.rawbytes = rawbytes;
+        Ok(())
+    }
+}
@@ -0,0 +1,136 @@
+//! Statistics of numeric table columns
+
+use super::BinTable;
+use super::BinTableValue;
+use crate::FITSError;
+use crate::KeywordValue;
+use crate::TFormType;
+use crate::TableError;
+
+/// Summary statistics over the valid values of a table column
+///
+/// Statistics are computed on physical values (see
+/// `BinTable::at_physical`), with nulls (TNULLn values and NaN)
+/// excluded.  Every element of vector and variable-length columns
+/// counts as a value.  If there are no valid values, `min`, `max`,
+/// `mean`, and `stddev` are NaN
+#[derive(Clone, Debug, PartialEq)]
+pub struct ColumnStats {
+    /// Total number of values, including nulls
+    pub nvalues: usize,
+    /// Number of null values
+    pub nnull: usize,
+    pub min: f64,
+    pub max: f64,
+    pub mean: f64,
+    /// Population standard deviation
+    pub stddev: f64,
+}
+
+/// Is a field type numeric (integer or real, possibly in a heap array)
+fn is_numeric(ftype: &TFormType) -> bool {
+    match ftype {
+        TFormType::Byte
+        | TFormType::Int16
+        | TFormType::Int32
+        | TFormType::Int64
+        | TFormType::Float32
+        | TFormType::Float64 => true,
+        TFormType::ArrayD32(etype) | TFormType::ArrayD64(etype) => is_numeric(etype),
+        _ => false,
+    }
+}
+
+/// Append the elements of a cell value, as numbers or None for nulls
+fn push_values(value: &BinTableValue, values: &mut Vec<Option<f64>>) {
+    match value {
+        BinTableValue::Array(v) => v.iter().for_each(|x| push_values(x, values)),
+        v => values.push(v.as_f64().filter(|x| !x.is_nan())),
+    }
+}
+
+impl BinTable {
+    /// Compute summary statistics of a numeric column
+    ///
+    /// # Arguments
+    ///
+    /// * `col` - Column index
+    ///
+    /// # Returns
+    ///
+    /// The statistics, or an error if the column is out of range or
+    /// not numeric (logical, bit, character, or complex)
+    ///
+    pub fn column_stats(&self, col: usize) -> Result<ColumnStats, FITSError> {
+        if col >= self.ncols() {
+            return Err(FITSError::from(TableError::ColumnOutOfRange(
+                col,
+                self.ncols(),
+            )));
+        }
+        if !is_numeric(&self.tform[col].ftype) {
+            return Err(FITSError::from(TableError::NotNumeric(col)));
+        }
+        let mut values = Vec::new();
+        for row in 0..self.nrows {
+            push_values(&self.at_physical(row, col)?, &mut values);
+        }
+        let valid: Vec<f64> = values.iter().flatten().copied().collect();
+        let n = valid.len() as f64;
+        let mean = valid.iter().sum::<f64>() / n;
+        let var = valid.iter().map(|x| (x - mean) * (x - mean)).sum::<f64>() / n;
+        let (min, max) = match valid.is_empty() {
+            true => (f64::NAN, f64::NAN),
+            false => (
+                valid.iter().copied().fold(f64::INFINITY, f64::min),
+                valid.iter().copied().fold(f64::NEG_INFINITY, f64::max),
+            ),
+        };
+        Ok(ColumnStats {
+            nvalues: values.len(),
+            nnull: values.len() - valid.len(),
+            min,
+            max,
+            mean,
+            stddev: var.sqrt(),
+        })
+    }
+
+    /// Set TDMINn and TDMAXn in `header` to the range of the data
+    ///
+    /// Call this before writing a table that has been filtered or
+    /// edited, so the limits describe the data written.  Limits are
+    /// physical values; they are integers for integer columns without
+    /// TSCALn.  Non-numeric columns, and columns with no valid values,
+    /// are left unchanged
+    ///
+    /// # Returns
+    ///
+    /// Nothing, or an error if the data cannot be decoded
+    ///
+    pub fn update_limits(&mut self) -> Result<(), FITSError> {
+        for col in 0..self.ncols() {
+            if !is_numeric(&self.tform[col].ftype) {
+                continue;
+            }
+            let stats = self.column_stats(col)?;
+            if stats.nvalues == stats.nnull {
+                continue;
+            }
+            let integer = self.tscal[col].is_none_or(|s| s == 1.0)
+                && matches!(
+                    self.tform[col].ftype,
+                    TFormType::Byte | TFormType::Int16 | TFormType::Int32 | TFormType::Int64
+                );
+            let limit = |v: f64| match integer {
+                true => KeywordValue::Int(v as i64),
+                false => KeywordValue::Float(v),
+            };
+            self.header
+                .set(&format!("TDMIN{}", col + 1), limit(stats.min));
+            self.header
+                .set(&format!("TDMAX{}", col + 1), limit(stats.max));
+        }
+        Ok(())
+    }
+}
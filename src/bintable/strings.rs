@@ -0,0 +1,79 @@
+//! Options for reading character columns
+
+use super::decode_string;
+use super::BinTable;
+use super::BinTableValue;
+use crate::FITSError;
+use crate::TFormType;
+use crate::TableError;
+
+/// Options for `BinTable::at_string`
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct StringOptions {
+    /// Remove trailing blanks, which pad strings to the field width
+    pub trim: bool,
+    /// Split fields with a substring width (TFORMn = `rAw`, e.g.
+    /// "20A10") into an array of `r / w` strings
+    pub split: bool,
+}
+
+impl StringOptions {
+    /// Decode one string, ending at an ASCII NUL if there is one
+    fn decode(&self, bytes: &[u8]) -> BinTableValue {
+        let s = decode_string(bytes);
+        BinTableValue::String(match self.trim {
+            true => s.trim_end_matches(' ').to_string(),
+            false => s,
+        })
+    }
+}
+
+impl BinTable {
+    /// Get value of a character cell
+    ///
+    /// Like `at`, but with options to trim trailing blanks and to split
+    /// fields of fixed-width substrings.  Strings end at the first ASCII
+    /// NUL, as for `at`
+    ///
+    /// # Arguments
+    ///
+    /// * `row` - Row index (zero-based)
+    /// * `col` - Column index (zero-based)
+    /// * `options` - How to decode the string
+    ///
+    /// # Returns
+    ///
+    /// A `BinTableValue::String`, or a `BinTableValue::Array` of strings
+    /// for split substrings; an error if the cell is out of range or the
+    /// column does not hold characters (`A` or `PA`/`QA`)
+    ///
+    pub fn at_string(
+        &self,
+        row: usize,
+        col: usize,
+        options: &StringOptions,
+    ) -> Result<BinTableValue, FITSError> {
+        if row >= self.nrows {
+            return Err(FITSError::from(TableError::RowOutOfRange(row, self.nrows)));
+        }
+        if col >= self.ncols() {
+            return Err(FITSError::from(TableError::ColumnOutOfRange(
+                col,
+                self.ncols(),
+            )));
+        }
+        let tform = &self.tform[col];
+        let start = row * self.rowbytes + self.offsets[col];
+        let field = &self.rawbytes[start..start + tform.size()];
+        match (&tform.ftype, self.heap_array(col, field)?) {
+            (TFormType::Char, _) => match tform.width {
+                Some(width) if options.split && width < tform.repeat => Ok(BinTableValue::Array(
+                    field.chunks(width).map(|b| options.decode(b)).collect(),
+                )),
+                _ => Ok(options.decode(field)),
+            },
+            (_, Some((TFormType::Char, _, range))) => Ok(options.decode(&self.heap[range])),
+            _ => Err(FITSError::from(TableError::NotCharacter(col))),
+        }
+    }
+}
@@ -0,0 +1,321 @@
+use super::BinTableValue;
+use crate::AsciiTForm;
+use crate::AsciiTFormType;
+use crate::TableError;
+
+/// Display format type of a table column
+///
+/// See Table 20 of FITS standard, version 4
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TDispType {
+    /// `Aw` : Character string
+    Char,
+    /// `Lw` : Logical, as T or F
+    Logical,
+    /// `Iw.m` : Decimal integer
+    Int,
+    /// `Bw.m` : Binary integer
+    Binary,
+    /// `Ow.m` : Octal integer
+    Octal,
+    /// `Zw.m` : Hexadecimal integer
+    Hex,
+    /// `Fw.d` : Fixed-point real
+    Float,
+    /// `Ew.dEe` : Real, exponential notation
+    Exp,
+    /// `ENw.d` : Real, engineering notation (exponent a multiple of 3)
+    Engineering,
+    /// `ESw.d` : Real, scientific notation
+    Scientific,
+    /// `Gw.dEe` : Real, fixed-point if the magnitude allows, otherwise
+    /// exponential notation
+    General,
+    /// `Dw.dEe` : Real, exponential notation with a D exponent
+    Double,
+}
+
+/// Display format of a table column, from the TDISPn keyword
+///
+/// The format is `Tw`, `Tw.m`, or `Tw.mEe`, where `T` is the type, `w`
+/// the width of the field, `m` the minimum number of digits (integers)
+/// or number of digits after the decimal point (reals), and `e` the
+/// number of exponent digits
+#[derive(Clone, Debug, PartialEq)]
+pub struct TDisp {
+    pub ftype: TDispType,
+    pub width: usize,
+    /// Minimum digits (integers) or digits after the decimal point (reals)
+    pub digits: Option<usize>,
+    /// Number of exponent digits, for E, G, and D formats
+    pub exponent: Option<usize>,
+}
+
+impl std::str::FromStr for TDisp {
+    type Err = TableError;
+
+    /// Parse TDISP string
+    ///
+    /// # Arguments
+    ///
+    /// * `s` - The value of the TDISPn keyword, e.g. "I6", "F10.3", "E15.7E3"
+    ///
+    /// # Returns
+    ///
+    /// The parsed format
+    ///
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let bad = || TableError::InvalidTDisp(s.to_string());
+        let (ftype, rest) = [
+            ("EN", TDispType::Engineering),
+            ("ES", TDispType::Scientific),
+            ("A", TDispType::Char),
+            ("L", TDispType::Logical),
+            ("I", TDispType::Int),
+            ("B", TDispType::Binary),
+            ("O", TDispType::Octal),
+            ("Z", TDispType::Hex),
+            ("F", TDispType::Float),
+            ("E", TDispType::Exp),
+            ("G", TDispType::General),
+            ("D", TDispType::Double),
+        ]
+        .into_iter()
+        .find_map(|(code, ftype)| s.strip_prefix(code).map(|rest| (ftype, rest)))
+        .ok_or_else(bad)?;
+
+        let (rest, exponent) = match rest.split_once('E') {
+            Some((rest, e))
+                if matches!(
+                    ftype,
+                    TDispType::Exp | TDispType::General | TDispType::Double
+                ) =>
+            {
+                (rest, Some(e.parse::<usize>().map_err(|_| bad())?))
+            }
+            Some(_) => return Err(bad()),
+            None => (rest, None),
+        };
+        let (width, digits) = match rest.split_once('.') {
+            Some((w, d)) => (w, Some(d.parse::<usize>().map_err(|_| bad())?)),
+            None => (rest, None),
+        };
+        let width = width.parse::<usize>().map_err(|_| bad())?;
+        if width == 0 || (digits.is_some() && matches!(ftype, TDispType::Char | TDispType::Logical))
+        {
+            return Err(bad());
+        }
+        Ok(TDisp {
+            ftype,
+            width,
+            digits,
+            exponent,
+        })
+    }
+}
+
+impl std::fmt::Display for TDisp {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let code = match self.ftype {
+            TDispType::Char => "A",
+            TDispType::Logical => "L",
+            TDispType::Int => "I",
+            TDispType::Binary => "B",
+            TDispType::Octal => "O",
+            TDispType::Hex => "Z",
+            TDispType::Float => "F",
+            TDispType::Exp => "E",
+            TDispType::Engineering => "EN",
+            TDispType::Scientific => "ES",
+            TDispType::General => "G",
+            TDispType::Double => "D",
+        };
+        write!(f, "{}{}", code, self.width)?;
+        if let Some(digits) = self.digits {
+            write!(f, ".{}", digits)?;
+        }
+        if let Some(exponent) = self.exponent {
+            write!(f, "E{}", exponent)?;
+        }
+        Ok(())
+    }
+}
+
+/// Display format of an ASCII table field, which writes values as the
+/// field stores them
+impl From<&AsciiTForm> for TDisp {
+    fn from(tform: &AsciiTForm) -> Self {
+        TDisp {
+            ftype: match tform.ftype {
+                AsciiTFormType::Char => TDispType::Char,
+                AsciiTFormType::Int => TDispType::Int,
+                AsciiTFormType::Float => TDispType::Float,
+                AsciiTFormType::Exp => TDispType::Exp,
+                AsciiTFormType::Double => TDispType::Double,
+            },
+            width: tform.width,
+            digits: tform.decimals,
+            exponent: None,
+        }
+    }
+}
+
+impl TDisp {
+    /// Format a value for display
+    ///
+    /// Strings are left-justified and other values right-justified in
+    /// a field of `width` characters.  Values too wide for the field
+    /// are written in full rather than truncated or replaced by `*`.
+    /// Nulls and NaN are blank.  Integers may be written with real
+    /// formats and reals with integer formats (rounded); values of
+    /// other types are written as by `Display`.  Arrays are written
+    /// element by element, separated by a blank
+    ///
+    /// # Arguments
+    ///
+    /// * `value` - Cell value
+    ///
+    /// # Returns
+    ///
+    /// The formatted text
+    ///
+    pub fn format(&self, value: &BinTableValue) -> String {
+        if let BinTableValue::Array(values) = value {
+            let items: Vec<String> = values.iter().map(|v| self.format(v)).collect();
+            return items.join(" ");
+        }
+        let text = self.text(value);
+        match self.ftype {
+            TDispType::Char => format!("{:<w$}", text, w = self.width),
+            _ => format!("{:>w$}", text, w = self.width),
+        }
+    }
+
+    /// Text of a scalar value, before justification
+    fn text(&self, value: &BinTableValue) -> String {
+        match (value, self.ftype) {
+            (BinTableValue::Null, _) => String::new(),
+            (BinTableValue::String(s), _) => s.trim_end_matches([' ', '\0']).to_string(),
+            (BinTableValue::Logical(b), _) => if *b { "T" } else { "F" }.to_string(),
+            (BinTableValue::Complex32(re, im), _) => self.complex(*re as f64, *im as f64),
+            (BinTableValue::Complex64(re, im), _) => self.complex(*re, *im),
+            (v, TDispType::Char) => v.to_string(),
+            (v, TDispType::Logical) => match v.as_f64() {
+                Some(x) if x.is_nan() => String::new(),
+                Some(x) => if x != 0.0 { "T" } else { "F" }.to_string(),
+                None => v.to_string(),
+            },
+            (v, TDispType::Int | TDispType::Binary | TDispType::Octal | TDispType::Hex) => {
+                let integer = match v.as_integer() {
+                    Some(i) => i,
+                    None => match v.as_f64() {
+                        Some(x) if x.is_finite() => x.round() as i128,
+                        Some(_) => return String::new(),
+                        None => return v.to_string(),
+                    },
+                };
+                self.integer(integer)
+            }
+            (v, _) => match v.as_f64() {
+                Some(x) if x.is_nan() => String::new(),
+                Some(x) => self.real(x),
+                None => v.to_string(),
+            },
+        }
+    }
+
+    /// Integer with at least `digits` digits, in the format's radix
+    fn integer(&self, value: i128) -> String {
+        let magnitude = value.unsigned_abs();
+        let digits = match self.ftype {
+            TDispType::Binary => format!("{:b}", magnitude),
+            TDispType::Octal => format!("{:o}", magnitude),
+            TDispType::Hex => format!("{:X}", magnitude),
+            _ => magnitude.to_string(),
+        };
+        let sign = if value < 0 { "-" } else { "" };
+        format!("{}{:0>m$}", sign, digits, m = self.digits.unwrap_or(1))
+    }
+
+    /// Real number in the format's notation
+    fn real(&self, value: f64) -> String {
+        let digits = self.digits.unwrap_or(0);
+        if value.is_infinite() {
+            return value.to_string();
+        }
+        match self.ftype {
+            TDispType::Float => format!("{:.*}", digits, value),
+            TDispType::Engineering => {
+                let text = format!("{:.*E}", digits, value);
+                let (_, exponent) = split_exponent(&text);
+                let shift = exponent.rem_euclid(3);
+                let mantissa = value / 10f64.powi(exponent - shift);
+                let mantissa = format!("{:.*}", digits, mantissa);
+                self.exponential(&mantissa, exponent - shift)
+            }
+            TDispType::General => {
+                // Fixed-point for 0.1 <= |value| < 10^d, keeping d
+                // significant digits
+                let magnitude = value.abs();
+                if value == 0.0 || (0.1..10f64.powi(digits as i32)).contains(&magnitude) {
+                    let integer_digits = match value {
+                        0.0 => 1,
+                        _ => magnitude.log10().floor() as i32 + 1,
+                    };
+                    let decimals = (digits as i32 - integer_digits).max(0) as usize;
+                    format!("{:.*}", decimals, value)
+                } else {
+                    let text = format!("{:.*E}", digits.saturating_sub(1), value);
+                    let (mantissa, exponent) = split_exponent(&text);
+                    self.exponential(mantissa, exponent)
+                }
+            }
+            _ => {
+                let text = format!("{:.*E}", digits, value);
+                let (mantissa, exponent) = split_exponent(&text);
+                self.exponential(mantissa, exponent)
+            }
+        }
+    }
+
+    /// Mantissa followed by a signed exponent of at least `exponent`
+    /// (default 2) digits
+    fn exponential(&self, mantissa: &str, exponent: i32) -> String {
+        let letter = match self.ftype {
+            TDispType::Double => 'D',
+            _ => 'E',
+        };
+        let sign = if exponent < 0 { '-' } else { '+' };
+        format!(
+            "{}{}{}{:0e$}",
+            mantissa,
+            letter,
+            sign,
+            exponent.unsigned_abs(),
+            e = self.exponent.unwrap_or(2)
+        )
+    }
+
+    /// Complex number as "(re, im)"
+    fn complex(&self, re: f64, im: f64) -> String {
+        match self.ftype {
+            TDispType::Char
+            | TDispType::Logical
+            | TDispType::Int
+            | TDispType::Binary
+            | TDispType::Octal
+            | TDispType::Hex => format!("({}, {})", re, im),
+            _ => format!("({}, {})", self.real(re), self.real(im)),
+        }
+    }
+}
+
+/// Split Rust's exponential notation ("1.5E-3") into mantissa and
+/// exponent
+fn split_exponent(text: &str) -> (&str, i32) {
+    match text.split_once('E') {
+        Some((mantissa, exponent)) => (mantissa, exponent.parse().unwrap_or(0)),
+        None => (text, 0),
+    }
+}
@@ -0,0 +1,215 @@
+use crate::TableError;
+
+/// Data type of a binary table field
+///
+/// See Table 18 of FITS standard, version 4
+#[derive(Clone, Debug, PartialEq)]
+pub enum TFormType {
+    /// `L` : Logical, stored as 'T' or 'F'
+    Logical,
+    /// `X` : Bit array
+    Bit,
+    /// `B` : Unsigned byte
+    Byte,
+    /// `I` : 16-bit signed integer
+    Int16,
+    /// `J` : 32-bit signed integer
+    Int32,
+    /// `K` : 64-bit signed integer
+    Int64,
+    /// `A` : Character
+    Char,
+    /// `E` : Single-precision floating point
+    Float32,
+    /// `D` : Double-precision floating point
+    Float64,
+    /// `C` : Single-precision complex
+    Complex32,
+    /// `M` : Double-precision complex
+    Complex64,
+    /// `P` : Variable-length array with 32-bit descriptor
+    ArrayD32(Box<TFormType>),
+    /// `Q` : Variable-length array with 64-bit descriptor
+    ArrayD64(Box<TFormType>),
+}
+
+impl TFormType {
+    fn from_char(c: char) -> Option<Self> {
+        match c {
+            'L' => Some(TFormType::Logical),
+            'X' => Some(TFormType::Bit),
+            'B' => Some(TFormType::Byte),
+            'I' => Some(TFormType::Int16),
+            'J' => Some(TFormType::Int32),
+            'K' => Some(TFormType::Int64),
+            'A' => Some(TFormType::Char),
+            'E' => Some(TFormType::Float32),
+            'D' => Some(TFormType::Float64),
+            'C' => Some(TFormType::Complex32),
+            'M' => Some(TFormType::Complex64),
+            _ => None,
+        }
+    }
+
+    /// Size in bytes of a single element of this type
+    ///
+    /// # Note
+    /// Bit arrays are packed, so the size of a `X` element
+    /// is reported as 1 byte; use `TForm::size` for field sizes
+    pub fn size(&self) -> usize {
+        match self {
+            TFormType::Logical => 1,
+            TFormType::Bit => 1,
+            TFormType::Byte => 1,
+            TFormType::Int16 => 2,
+            TFormType::Int32 => 4,
+            TFormType::Int64 => 8,
+            TFormType::Char => 1,
+            TFormType::Float32 => 4,
+            TFormType::Float64 => 8,
+            TFormType::Complex32 => 8,
+            TFormType::Complex64 => 16,
+            TFormType::ArrayD32(_) => 8,
+            TFormType::ArrayD64(_) => 16,
+        }
+    }
+}
+
+/// Binary table field format, from the TFORMn keyword
+///
+/// The format is `rTa`, where `r` is the repeat count,
+/// `T` is the data type, and `a` is additional characters
+/// whose meaning depends on the type
+#[derive(Clone, Debug, PartialEq)]
+pub struct TForm {
+    pub repeat: usize,
+    pub ftype: TFormType,
+    /// Maximum number of elements for variable-length arrays
+    pub maxlen: Option<usize>,
+    /// Width of the fixed-length substrings of a character field, from
+    /// the `rAw` convention, e.g. 10 for "20A10"
+    pub width: Option<usize>,
+}
+
+impl std::str::FromStr for TForm {
+    type Err = TableError;
+
+    /// Parse TFORM string
+    ///
+    /// # Arguments
+    ///
+    /// * `s` - The value of the TFORMn keyword, e.g. "1J", "20A",
+    ///   "20A10", "1PE(100)"
+    ///
+    /// # Returns
+    ///
+    /// The parsed format
+    ///
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let bad = || TableError::InvalidTForm(s.to_string());
+
+        let ndigits = s.chars().take_while(|c| c.is_ascii_digit()).count();
+        let repeat = match ndigits {
+            0 => 1,
+            _ => s[0..ndigits].parse::<usize>().map_err(|_| bad())?,
+        };
+        let mut chars = s[ndigits..].chars();
+        let tchar = chars.next().ok_or_else(bad)?;
+        let remainder: String = chars.collect();
+
+        match tchar {
+            'P' | 'Q' => {
+                // Variable-length array: rPt(emax)
+                let mut rchars = remainder.chars();
+                let etype = rchars
+                    .next()
+                    .and_then(TFormType::from_char)
+                    .ok_or_else(bad)?;
+                let rest: String = rchars.collect();
+                let rest = rest.trim();
+                let maxlen = if rest.is_empty() {
+                    None
+                } else if rest.starts_with('(') && rest.ends_with(')') {
+                    Some(
+                        rest[1..rest.len() - 1]
+                            .trim()
+                            .parse::<usize>()
+                            .map_err(|_| bad())?,
+                    )
+                } else {
+                    return Err(bad());
+                };
+                let ftype = match tchar {
+                    'P' => TFormType::ArrayD32(Box::new(etype)),
+                    _ => TFormType::ArrayD64(Box::new(etype)),
+                };
+                Ok(TForm {
+                    repeat,
+                    ftype,
+                    maxlen,
+                    width: None,
+                })
+            }
+            _ => {
+                let ftype = TFormType::from_char(tchar).ok_or_else(bad)?;
+                // Additional characters are only defined for character
+                // fields; a number there is the substring width of the
+                // rAw convention, other characters are ignored
+                let remainder = remainder.trim();
+                if !remainder.is_empty() && ftype != TFormType::Char {
+                    return Err(bad());
+                }
+                Ok(TForm {
+                    repeat,
+                    ftype,
+                    maxlen: None,
+                    width: remainder.parse().ok().filter(|w| *w > 0),
+                })
+            }
+        }
+    }
+}
+
+impl TForm {
+    /// Number of bytes occupied by the field in each table row
+    pub fn size(&self) -> usize {
+        match self.ftype {
+            TFormType::Bit => self.repeat.div_ceil(8),
+            _ => self.repeat.saturating_mul(self.ftype.size()),
+        }
+    }
+}
+
+impl std::fmt::Display for TForm {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        fn code(t: &TFormType) -> char {
+            match t {
+                TFormType::Logical => 'L',
+                TFormType::Bit => 'X',
+                TFormType::Byte => 'B',
+                TFormType::Int16 => 'I',
+                TFormType::Int32 => 'J',
+                TFormType::Int64 => 'K',
+                TFormType::Char => 'A',
+                TFormType::Float32 => 'E',
+                TFormType::Float64 => 'D',
+                TFormType::Complex32 => 'C',
+                TFormType::Complex64 => 'M',
+                TFormType::ArrayD32(_) => 'P',
+                TFormType::ArrayD64(_) => 'Q',
+            }
+        }
+        write!(f, "{}{}", self.repeat, code(&self.ftype))?;
+        if let Some(width) = self.width {
+            write!(f, "{}", width)?;
+        }
+        if let TFormType::ArrayD32(t) | TFormType::ArrayD64(t) = &self.ftype {
+            write!(f, "{}", code(t))?;
+            if let Some(maxlen) = self.maxlen {
+                write!(f, "({})", maxlen)?;
+            }
+        }
+        Ok(())
+    }
+}
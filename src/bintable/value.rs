@@ -0,0 +1,191 @@
+/// A single cell value in a binary table
+///
+/// Fields with a repeat count greater than one, and
+/// variable-length array fields, are returned as `Array`.
+/// Integer cells equal to the column TNULLn value are returned as `Null`
+#[derive(Clone, Debug, PartialEq)]
+pub enum BinTableValue {
+    Null,
+    Logical(bool),
+    Bits(Vec<bool>),
+    Byte(u8),
+    Int8(i8),
+    Int16(i16),
+    UInt16(u16),
+    Int32(i32),
+    UInt32(u32),
+    Int64(i64),
+    UInt64(u64),
+    String(String),
+    Float32(f32),
+    Float64(f64),
+    Complex32(f32, f32),
+    Complex64(f64, f64),
+    Array(Vec<BinTableValue>),
+}
+
+impl BinTableValue {
+    /// Numeric scalar value as a float
+    ///
+    /// # Returns
+    ///
+    /// The value, or None for null, non-numeric, complex, and array values
+    ///
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            BinTableValue::Byte(v) => Some(*v as f64),
+            BinTableValue::Int8(v) => Some(*v as f64),
+            BinTableValue::Int16(v) => Some(*v as f64),
+            BinTableValue::UInt16(v) => Some(*v as f64),
+            BinTableValue::Int32(v) => Some(*v as f64),
+            BinTableValue::UInt32(v) => Some(*v as f64),
+            BinTableValue::Int64(v) => Some(*v as f64),
+            BinTableValue::UInt64(v) => Some(*v as f64),
+            BinTableValue::Float32(v) => Some(*v as f64),
+            BinTableValue::Float64(v) => Some(*v),
+            _ => None,
+        }
+    }
+
+    /// Complex scalar value as (real, imaginary)
+    ///
+    /// # Returns
+    ///
+    /// The value, with a zero imaginary part for real numbers, or None
+    /// for null, non-numeric, and array values
+    ///
+    pub fn as_complex(&self) -> Option<(f64, f64)> {
+        match self {
+            BinTableValue::Complex32(re, im) => Some((*re as f64, *im as f64)),
+            BinTableValue::Complex64(re, im) => Some((*re, *im)),
+            _ => self.as_f64().map(|re| (re, 0.0)),
+        }
+    }
+
+    /// Integer scalar value, exactly
+    ///
+    /// # Returns
+    ///
+    /// The value, or None for null, non-integer, and array values
+    ///
+    pub(crate) fn as_integer(&self) -> Option<i128> {
+        match self {
+            BinTableValue::Byte(v) => Some(*v as i128),
+            BinTableValue::Int8(v) => Some(*v as i128),
+            BinTableValue::Int16(v) => Some(*v as i128),
+            BinTableValue::UInt16(v) => Some(*v as i128),
+            BinTableValue::Int32(v) => Some(*v as i128),
+            BinTableValue::UInt32(v) => Some(*v as i128),
+            BinTableValue::Int64(v) => Some(*v as i128),
+            BinTableValue::UInt64(v) => Some(*v as i128),
+            _ => None,
+        }
+    }
+}
+
+/// Conversion from a table cell value to a Rust type
+///
+/// Integers coerce to floating point; reals do not coerce to integers.
+/// Used by `BinTable::column` and `Table::column`
+pub trait FromTableValue: Sized {
+    /// Convert the value, or None if it has an incompatible type
+    fn from_value(value: &BinTableValue) -> Option<Self>;
+}
+
+impl FromTableValue for f64 {
+    fn from_value(value: &BinTableValue) -> Option<Self> {
+        value.as_f64()
+    }
+}
+
+impl FromTableValue for f32 {
+    fn from_value(value: &BinTableValue) -> Option<Self> {
+        value.as_f64().map(|v| v as f32)
+    }
+}
+
+impl FromTableValue for i64 {
+    fn from_value(value: &BinTableValue) -> Option<Self> {
+        value.as_integer().and_then(|v| v.try_into().ok())
+    }
+}
+
+impl FromTableValue for i32 {
+    fn from_value(value: &BinTableValue) -> Option<Self> {
+        value.as_integer().and_then(|v| v.try_into().ok())
+    }
+}
+
+impl FromTableValue for bool {
+    fn from_value(value: &BinTableValue) -> Option<Self> {
+        match value {
+            BinTableValue::Logical(b) => Some(*b),
+            _ => None,
+        }
+    }
+}
+
+impl FromTableValue for String {
+    fn from_value(value: &BinTableValue) -> Option<Self> {
+        match value {
+            BinTableValue::String(s) => Some(s.clone()),
+            _ => None,
+        }
+    }
+}
+
+impl FromTableValue for (f64, f64) {
+    fn from_value(value: &BinTableValue) -> Option<Self> {
+        value.as_complex()
+    }
+}
+
+impl From<(f32, f32)> for BinTableValue {
+    fn from((re, im): (f32, f32)) -> Self {
+        BinTableValue::Complex32(re, im)
+    }
+}
+
+impl From<(f64, f64)> for BinTableValue {
+    fn from((re, im): (f64, f64)) -> Self {
+        BinTableValue::Complex64(re, im)
+    }
+}
+
+impl std::fmt::Display for BinTableValue {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            BinTableValue::Null => write!(f, "null"),
+            BinTableValue::Logical(b) => write!(f, "{}", b),
+            BinTableValue::Bits(bits) => {
+                for b in bits {
+                    write!(f, "{}", if *b { 1 } else { 0 })?;
+                }
+                Ok(())
+            }
+            BinTableValue::Byte(v) => write!(f, "{}", v),
+            BinTableValue::Int8(v) => write!(f, "{}", v),
+            BinTableValue::Int16(v) => write!(f, "{}", v),
+            BinTableValue::UInt16(v) => write!(f, "{}", v),
+            BinTableValue::Int32(v) => write!(f, "{}", v),
+            BinTableValue::UInt32(v) => write!(f, "{}", v),
+            BinTableValue::Int64(v) => write!(f, "{}", v),
+            BinTableValue::UInt64(v) => write!(f, "{}", v),
+            BinTableValue::String(s) => write!(f, "\"{}\"", s),
+            BinTableValue::Float32(v) => write!(f, "{}", v),
+            BinTableValue::Float64(v) => write!(f, "{}", v),
+            BinTableValue::Complex32(r, i) => write!(f, "({}, {})", r, i),
+            BinTableValue::Complex64(r, i) => write!(f, "({}, {})", r, i),
+            BinTableValue::Array(v) => {
+                write!(f, "[")?;
+                for (i, x) in v.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{}", x)?;
+                }
+                write!(f, "]")
+            }
+        }
+    }
+}
@@ -0,0 +1,227 @@
+use super::BinTable;
+
+use crate::wcs::split_ctype;
+use crate::FITSError;
+use crate::Header;
+use crate::Keyword;
+use crate::KeywordValue;
+use crate::WCS;
+
+/// Per-column table keywords and the image keywords they correspond to,
+/// for keywords indexed by a single axis, e.g. TCRVL5 -> CRVALi
+///
+/// See Table 8 of WCS Paper I and Section 8.2 of the FITS standard, version 4
+const AXIS_KEYS: [(&str, &str); 6] = [
+    ("TCTYP", "CTYPE"),
+    ("TCUNI", "CUNIT"),
+    ("TCRVL", "CRVAL"),
+    ("TCDLT", "CDELT"),
+    ("TCRPX", "CRPIX"),
+    ("TCROT", "CROTA"),
+];
+
+/// Per-column keywords that describe the whole coordinate
+/// description, e.g. LONP5 -> LONPOLE
+const GLOBAL_KEYS: [(&str, &str); 7] = [
+    ("TWCS", "WCSNAME"),
+    ("LONP", "LONPOLE"),
+    ("LATP", "LATPOLE"),
+    ("RFRQ", "RESTFRQ"),
+    ("RWAV", "RESTWAV"),
+    ("RADE", "RADESYS"),
+    ("EQUI", "EQUINOX"),
+];
+
+/// Matching axis type for the other half of a celestial pair,
+/// e.g. "RA" -> "DEC", "GLON" -> "GLAT"
+fn celestial_partner(axtype: &str) -> Option<String> {
+    match axtype {
+        "RA" => Some("DEC".to_string()),
+        "DEC" => Some("RA".to_string()),
+        t if t.len() == 4 && t.ends_with("LON") => Some(format!("{}LAT", &t[0..1])),
+        t if t.len() == 4 && t.ends_with("LAT") => Some(format!("{}LON", &t[0..1])),
+        _ => None,
+    }
+}
+
+impl BinTable {
+    /// Coordinate type of a column (TCTYPn)
+    fn column_ctype(&self, col: usize) -> Option<&str> {
+        match self.header.value(format!("TCTYP{}", col + 1).as_str()) {
+            Some(KeywordValue::String(s)) => Some(s.as_str()),
+            _ => None,
+        }
+    }
+
+    /// Columns that together form the coordinate description of a column
+    ///
+    /// A celestial column (e.g. TCTYPn = 'RA---TAN') is paired with the
+    /// column holding the other celestial coordinate with the same
+    /// projection; other columns with a TCTYPn keyword stand alone.
+    ///
+    /// # Arguments
+    ///
+    /// * `col` - Column index (zero-based)
+    ///
+    /// # Returns
+    ///
+    /// The columns in table order, or an empty vector if the column
+    /// has no coordinate type
+    ///
+    pub fn wcs_columns(&self, col: usize) -> Vec<usize> {
+        let Some(ctype) = self.column_ctype(col) else {
+            return Vec::new();
+        };
+        let (axtype, code) = split_ctype(ctype);
+        let partner = match (celestial_partner(axtype), code) {
+            (Some(partner), Some(code)) => (0..self.ncols()).find(|&c| {
+                c != col
+                    && self
+                        .column_ctype(c)
+                        .map(split_ctype)
+                        .is_some_and(|(t, p)| t == partner && p == Some(code))
+            }),
+            _ => None,
+        };
+        match partner {
+            Some(p) if p < col => vec![p, col],
+            Some(p) => vec![col, p],
+            None => vec![col],
+        }
+    }
+
+    /// World coordinate system attached to a table column
+    ///
+    /// Event lists give the coordinates of each event in pixel-like
+    /// columns, e.g. X and Y, described by the TCTYPn, TCRVLn, TCRPXn,
+    /// TCDLTn, TCUNIn, TCROTn, TPn_k (or TPCn_k), TCn_k (or TCDn_k),
+    /// and TVn_m keywords.  These are translated into the equivalent
+    /// image keywords for the columns returned by `wcs_columns`,
+    /// one axis per column in table order.
+    ///
+    /// Table pixel coordinates are the column values themselves, so the
+    /// reference pixel of the returned WCS is shifted by one such that
+    /// `pixel_to_world` takes the column values directly, rather than
+    /// the zero-based image pixel indices it takes for images.
+    ///
+    /// # Arguments
+    ///
+    /// * `col` - Column index (zero-based)
+    ///
+    /// # Returns
+    ///
+    /// The WCS, or None if the column has no coordinate keywords
+    ///
+    pub fn wcs_for_column(&self, col: usize) -> Result<Option<WCS>, FITSError> {
+        let columns = self.wcs_columns(col);
+        if columns.is_empty() {
+            return Ok(None);
+        }
+        let mut keywords = Vec::new();
+        let mut push = |name: String, value: &KeywordValue| {
+            keywords.push(Keyword {
+                name,
+                value: value.clone(),
+                ..Default::default()
+            });
+        };
+        for (i, &c) in columns.iter().enumerate() {
+            for (tkey, ikey) in AXIS_KEYS {
+                if let Some(v) = self.header.value(format!("{}{}", tkey, c + 1).as_str()) {
+                    push(format!("{}{}", ikey, i + 1), v);
+                }
+            }
+            for (j, &k) in columns.iter().enumerate() {
+                for (tkey, ikey) in [("TP", "PC"), ("TPC", "PC"), ("TC", "CD"), ("TCD", "CD")] {
+                    let name = format!("{}{}_{}", tkey, c + 1, k + 1);
+                    if let Some(v) = self.header.value(name.as_str()) {
+                        push(format!("{}{}_{}", ikey, i + 1, j + 1), v);
+                    }
+                }
+            }
+            for m in 0..100 {
+                if let Some(v) = self.header.value(format!("TV{}_{}", c + 1, m).as_str()) {
+                    push(format!("PV{}_{}", i + 1, m), v);
+                }
+            }
+            for (tkey, ikey) in GLOBAL_KEYS {
+                if let Some(v) = self.header.value(format!("{}{}", tkey, c + 1).as_str()) {
+                    push(ikey.to_string(), v);
+                }
+            }
+        }
+        // Keywords without a column number (e.g. RADESYS, MJD-OBS)
+        // apply to all columns, but are overridden by the per-column forms
+        // above since header lookups take the first match
+        keywords.extend(self.header.iter().cloned());
+
+        let Some(mut wcs) = WCS::from_header(&Header::new(keywords))? else {
+            return Ok(None);
+        };
+        if let Some(crpix) = wcs.crpix.as_mut() {
+            crpix.iter_mut().for_each(|p| *p += 1.0);
+        } else {
+            wcs.crpix = Some(vec![1.0; columns.len()]);
+        }
+        Ok(Some(wcs))
+    }
+
+    /// World coordinates of a table row
+    ///
+    /// The WCS is built from the header on each call; use
+    /// `world_coords` to convert every row with one WCS
+    ///
+    /// # Arguments
+    ///
+    /// * `row` - Row index (zero-based)
+    /// * `col` - Column index (zero-based); for a celestial column both
+    ///   halves of the pair (see `wcs_columns`) are read
+    ///
+    /// # Returns
+    ///
+    /// World coordinates, one per column returned by `wcs_columns`
+    ///
+    pub fn world_at(&self, row: usize, col: usize) -> Result<Vec<f64>, FITSError> {
+        let wcs = self.required_wcs(col)?;
+        self.row_world(&wcs, &self.wcs_columns(col), row)
+    }
+
+    /// World coordinates of every table row
+    ///
+    /// # Arguments
+    ///
+    /// * `col` - Column index (zero-based), as for `world_at`
+    ///
+    /// # Returns
+    ///
+    /// World coordinates of each row, one per column returned by
+    /// `wcs_columns`
+    ///
+    pub fn world_coords(&self, col: usize) -> Result<Vec<Vec<f64>>, FITSError> {
+        let wcs = self.required_wcs(col)?;
+        let columns = self.wcs_columns(col);
+        (0..self.nrows)
+            .map(|row| self.row_world(&wcs, &columns, row))
+            .collect()
+    }
+
+    /// WCS of a column, or an error if it has none
+    fn required_wcs(&self, col: usize) -> Result<WCS, FITSError> {
+        Ok(self
+            .wcs_for_column(col)?
+            .ok_or(crate::WCSError::MissingKeyword(format!("TCTYP{}", col + 1)))?)
+    }
+
+    /// Convert the values of `columns` in a row with `wcs`
+    fn row_world(&self, wcs: &WCS, columns: &[usize], row: usize) -> Result<Vec<f64>, FITSError> {
+        let pixel = columns
+            .iter()
+            .map(|&c| {
+                self.at_physical(row, c)?
+                    .as_f64()
+                    .ok_or_else(|| crate::TableError::InvalidValue(row, c).into())
+            })
+            .collect::<Result<Vec<f64>, FITSError>>()?;
+        wcs.pixel_to_world(&pixel)
+    }
+}
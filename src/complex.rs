@@ -0,0 +1,130 @@
+//! Conversions between complex keyword and table values and
+//! `num_complex::Complex`
+//!
+//! Complex values are held as (real, imaginary) pairs, e.g.
+//! `KeywordValue::ComplexFloat` and `BinTableValue::Complex64`.  With
+//! the `num-complex` feature they convert to and from `Complex`, so
+//! they can be used in complex arithmetic directly:
+//!
+//! ```no_run
+//! # use fits::{Complex, HDUData, TableLike, FITS};
+//! # fn main() -> Result<(), fits::FITSError> {
+//! # let fits = FITS::from_file("visibilities.fits")?;
+//! # let (header, HDUData::BinTable(table)) = (&fits[1].header, &fits[1].data) else {
+//! #     return Ok(());
+//! # };
+//! let vis: Vec<Option<Complex<f64>>> = table.column("VISIBILITY")?;
+//! let gain: Complex<f64> = header.get("CGAIN")?;
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::BinTableValue;
+use crate::FromKeywordValue;
+use crate::FromTableValue;
+use crate::KeywordValue;
+use num_complex::Complex;
+
+impl BinTableValue {
+    /// Complex scalar value; see `as_complex`
+    pub fn to_complex(&self) -> Option<Complex<f64>> {
+        self.as_complex().map(|(re, im)| Complex::new(re, im))
+    }
+}
+
+impl FromTableValue for Complex<f64> {
+    fn from_value(value: &BinTableValue) -> Option<Self> {
+        value.to_complex()
+    }
+}
+
+impl FromTableValue for Complex<f32> {
+    fn from_value(value: &BinTableValue) -> Option<Self> {
+        value
+            .as_complex()
+            .map(|(re, im)| Complex::new(re as f32, im as f32))
+    }
+}
+
+impl From<Complex<f32>> for BinTableValue {
+    fn from(c: Complex<f32>) -> Self {
+        BinTableValue::Complex32(c.re, c.im)
+    }
+}
+
+impl From<Complex<f64>> for BinTableValue {
+    fn from(c: Complex<f64>) -> Self {
+        BinTableValue::Complex64(c.re, c.im)
+    }
+}
+
+impl FromKeywordValue for Complex<f64> {
+    fn from_value(value: &KeywordValue) -> Option<Self> {
+        <(f64, f64) as FromKeywordValue>::from_value(value).map(|(re, im)| Complex::new(re, im))
+    }
+}
+
+impl From<Complex<i64>> for KeywordValue {
+    fn from(c: Complex<i64>) -> Self {
+        KeywordValue::ComplexInt(c.re, c.im)
+    }
+}
+
+impl From<Complex<f64>> for KeywordValue {
+    fn from(c: Complex<f64>) -> Self {
+        KeywordValue::ComplexFloat(c.re, c.im)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testutil::header;
+    use crate::BinTable;
+    use crate::HDUData;
+    use crate::TableLike;
+
+    #[test]
+    fn test_complex() {
+        let mut header = header(&[
+            "XTENSION= 'BINTABLE'",
+            "BITPIX  =                    8",
+            "NAXIS   =                    2",
+            "NAXIS1  =                    8",
+            "NAXIS2  =                    2",
+            "PCOUNT  =                    0",
+            "GCOUNT  =                    1",
+            "TFIELDS =                    1",
+            "TTYPE1  = 'VIS     '",
+            "TFORM1  = '1C      '",
+            "END",
+        ]);
+        header.set("CGAIN", Complex::new(2i64, -1).into());
+        let gain: Complex<f64> = header.get("CGAIN").unwrap();
+        assert_eq!(gain, Complex::new(2.0, -1.0));
+        assert_eq!(header.get::<(f64, f64)>("NAXIS1").unwrap(), (8.0, 0.0));
+
+        let mut raw = Vec::new();
+        for v in [1.0f32, 2.0, -0.5, 0.0] {
+            raw.extend(v.to_be_bytes());
+        }
+        let (HDUData::BinTable(mut table), _) = BinTable::from_bytes(&header, &raw).unwrap() else {
+            panic!("expected binary table");
+        };
+        let vis: Vec<Option<Complex<f64>>> = table.column("VIS").unwrap();
+        assert_eq!(
+            vis,
+            [Some(Complex::new(1.0, 2.0)), Some(Complex::new(-0.5, 0.0))]
+        );
+        let scaled: Vec<BinTableValue> = vis.iter().flatten().map(|v| (v * gain).into()).collect();
+        assert_eq!(scaled[0], BinTableValue::Complex64(4.0, 3.0));
+
+        table.add_column("SCALED", "1M", &scaled).unwrap();
+        let column: Vec<Option<Complex<f32>>> = table.column("SCALED").unwrap();
+        assert_eq!(column[1], Some(Complex::new(-1.0, 0.5)));
+        assert_eq!(
+            header.value("CGAIN"),
+            Some(&KeywordValue::ComplexInt(2, -1))
+        );
+    }
+}
@@ -1,3 +1,4 @@
+use crate::Bitpix;
 use thiserror::Error;
 
 #[derive(Clone, Error, Debug)]
@@ -20,4 +21,155 @@ pub enum HeaderError {
     UnsupportedExtension(String),
     #[error("Unexpected Value Type in Keyword {0}")]
     UnexpectedValueType(String),
+    #[error("Missing Keyword: {0}")]
+    MissingKeyword(String),
+    #[error("Invalid HDU {0}: {1}")]
+    InvalidHDU(usize, String),
+    #[error("Header needs {0} bytes but only {1} are available")]
+    HeaderTooLarge(usize, usize),
+    #[error("Invalid JSON: {0}")]
+    InvalidJSON(String),
+    #[error("Invalid template line {0}: {1}")]
+    InvalidTemplate(usize, String),
+    #[error("Duplicate keyword: {0}")]
+    DuplicateKeyword(String),
+}
+
+#[derive(Clone, Error, Debug)]
+pub enum TableError {
+    #[error("Invalid TFORM: \"{0}\"")]
+    InvalidTForm(String),
+    #[error("Invalid TDISP: \"{0}\"")]
+    InvalidTDisp(String),
+    #[error("Row index {0} out of range (table has {1} rows)")]
+    RowOutOfRange(usize, usize),
+    #[error("Column index {0} out of range (table has {1} columns)")]
+    ColumnOutOfRange(usize, usize),
+    #[error("Variable-length array descriptor in column {0} points outside heap")]
+    HeapOutOfRange(usize),
+    #[error("Value in row {0}, column {1} is not a numeric scalar")]
+    InvalidValue(usize, usize),
+    #[error("Invalid expression: {0}")]
+    InvalidExpression(String),
+    #[error("No column named \"{0}\"")]
+    UnknownColumn(String),
+    #[error("More than one column named \"{0}\"")]
+    AmbiguousColumn(String),
+    #[error("Row mask has {0} entries (table has {1} rows)")]
+    MaskLength(usize, usize),
+    #[error("Tables cannot be concatenated: {0}")]
+    IncompatibleTables(String),
+    #[error("Column {0} is not numeric")]
+    NotNumeric(usize),
+    #[error("Column {0} does not hold characters")]
+    NotCharacter(usize),
+    #[error("Value cannot be stored in column {0}: {1}")]
+    IncompatibleValue(usize, String),
+    #[error("Column \"{0}\" already exists")]
+    DuplicateColumn(String),
+    #[error("{0} values given for a table of {1} rows")]
+    ColumnLength(usize, usize),
+    #[error("{0} values given for a table of {1} columns")]
+    RowLength(usize, usize),
+    #[error("Value in row {0}, column {1} cannot be read as {2}")]
+    InvalidType(usize, usize, &'static str),
+    #[error("Invalid binning: {0}")]
+    InvalidBinning(String),
+}
+
+#[derive(Clone, Error, Debug)]
+pub enum ImageError {
+    #[error("Dimension mismatch: expected {0} axes, got {1}")]
+    DimensionMismatch(usize, usize),
+    #[error("Region out of bounds on axis {0}")]
+    RegionOutOfBounds(usize),
+    #[error("Invalid axis {0} for image with {1} axes")]
+    InvalidAxis(usize, usize),
+    #[error("Image has no world coordinate system")]
+    NoWCS,
+    #[error("Image shapes differ: {0:?} and {1:?}")]
+    ShapeMismatch(Vec<usize>, Vec<usize>),
+    #[error("No images to combine")]
+    NoImages,
+    #[error("Invalid aperture: {0}")]
+    InvalidAperture(String),
+    #[error("Invalid mask: {0}")]
+    InvalidMask(String),
+    #[error("Pixel type mismatch: image has {0:?} pixels, requested {1:?}")]
+    TypeMismatch(Bitpix, Bitpix),
+    #[error("Pixels are not in native byte order; call convert_to_native")]
+    NotNative,
+    #[error("Invalid decoded pixels: {0}")]
+    InvalidDecode(String),
+}
+
+#[derive(Clone, Error, Debug)]
+pub enum WCSError {
+    #[error("Dimension mismatch: expected {0} coordinates, got {1}")]
+    DimensionMismatch(usize, usize),
+    #[error("Missing WCS keyword: {0}")]
+    MissingKeyword(String),
+    #[error("Unsupported projection: {0}")]
+    UnsupportedProjection(String),
+    #[error("Singular linear transformation matrix")]
+    SingularMatrix,
+    #[error("Coordinate cannot be projected: {0}")]
+    InvalidCoordinate(String),
+    #[error("Unsupported celestial reference frame: {0}")]
+    UnsupportedFrame(String),
+    #[error("Invalid sexagesimal angle: {0}")]
+    InvalidAngle(String),
+}
+
+#[derive(Clone, Error, Debug)]
+pub enum UnitError {
+    #[error("Invalid unit \"{0}\": {1}")]
+    InvalidUnit(String, String),
+    #[error("Unknown unit \"{0}\" in \"{1}\"")]
+    UnknownUnit(String, String),
+    #[error("Cannot convert {0} to {1}")]
+    Incompatible(String, String),
+}
+
+/// Error returned by the public API, wrapping the specific error kinds
+#[derive(Error, Debug)]
+pub enum FITSError {
+    #[error(transparent)]
+    Header(#[from] HeaderError),
+    #[error(transparent)]
+    Table(#[from] TableError),
+    #[error(transparent)]
+    Image(#[from] ImageError),
+    #[error(transparent)]
+    WCS(#[from] WCSError),
+    #[error(transparent)]
+    Unit(#[from] UnitError),
+    #[error("I/O Error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("HTTP Error: {0}")]
+    Http(String),
+    #[error("Arrow Error: {0}")]
+    Arrow(String),
+    #[error("Polars Error: {0}")]
+    Polars(String),
+    #[error("Invalid UTF-8: {0}")]
+    Utf8(#[from] std::string::FromUtf8Error),
+    #[error("Invalid integer: {0}")]
+    ParseInt(#[from] std::num::ParseIntError),
+    #[error("Invalid float: {0}")]
+    ParseFloat(#[from] std::num::ParseFloatError),
+    #[error("HDU index {0} out of range (file has {1} HDUs)")]
+    IndexOutOfRange(usize, usize),
+    #[error("No HDU matches {0}")]
+    HDUNotFound(String),
+    #[error("Invalid file name: {0}")]
+    InvalidFilename(String),
+    #[error("Invalid grouping table: {0}")]
+    InvalidGroup(String),
+    #[error("Invalid HEALPix map: {0}")]
+    InvalidHealpix(String),
+    #[error("Invalid SDFITS table: {0}")]
+    InvalidSdfits(String),
+    #[error("Invalid BITPIX: {0}")]
+    InvalidBitpix(i64),
 }
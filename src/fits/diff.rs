@@ -0,0 +1,520 @@
+//! Comparison of two files, similar to `fitsdiff`
+
+use super::FITS;
+use crate::BinTable;
+use crate::BinTableValue;
+use crate::HDUData;
+use crate::Header;
+use crate::Image;
+use crate::KeywordValue;
+use crate::HDU;
+
+/// Options for `FITS::diff`
+///
+/// Two numbers `a` and `b` are considered equal if
+/// `|a - b| <= atol + rtol * |b|`; two NaNs are equal
+#[derive(Clone, Debug)]
+pub struct DiffOptions {
+    /// Relative tolerance
+    pub rtol: f64,
+    /// Absolute tolerance
+    pub atol: f64,
+    /// Keywords not compared.  COMMENT, HISTORY, and blank commentary
+    /// keywords are never compared
+    pub ignore_keywords: Vec<String>,
+    /// Largest number of differing table cells listed in
+    /// `DataDiff::Cells`; all are counted
+    pub max_cells: usize,
+}
+
+impl Default for DiffOptions {
+    fn default() -> Self {
+        DiffOptions {
+            rtol: 0.0,
+            atol: 0.0,
+            ignore_keywords: vec!["CHECKSUM".to_string(), "DATASUM".to_string()],
+            max_cells: 20,
+        }
+    }
+}
+
+impl DiffOptions {
+    /// Are two numbers equal within the tolerances
+    fn close(&self, a: f64, b: f64) -> bool {
+        a == b || (a.is_nan() && b.is_nan()) || (a - b).abs() <= self.atol + self.rtol * b.abs()
+    }
+}
+
+/// A keyword whose value differs, or which is present in only one file
+#[derive(Clone, Debug, PartialEq)]
+pub struct KeywordDiff {
+    pub name: String,
+    /// Value in the first file, None if absent
+    pub a: Option<KeywordValue>,
+    /// Value in the second file, None if absent
+    pub b: Option<KeywordValue>,
+}
+
+/// Difference between the data units of two HDUs
+#[derive(Clone, Debug, PartialEq)]
+pub enum DataDiff {
+    /// Data of different kinds, shapes, or table columns; the values
+    /// are not compared
+    Structure(String),
+    /// Number of differing image pixels, and largest absolute
+    /// difference of physical values
+    Pixels { count: usize, max_deviation: f64 },
+    /// Differing table cells, as (row, column) indices, and largest
+    /// absolute difference of numeric physical values
+    Cells {
+        count: usize,
+        max_deviation: f64,
+        cells: Vec<(usize, usize)>,
+    },
+    /// Number of differing bytes, for data compared byte by byte
+    /// (ASCII tables and uninterpreted extensions)
+    Bytes { count: usize },
+}
+
+/// Differences between two HDUs at the same position
+#[derive(Clone, Debug, PartialEq)]
+pub struct HDUDiff {
+    /// Position of the HDUs in the files; 0 is the primary HDU
+    pub index: usize,
+    pub keywords: Vec<KeywordDiff>,
+    pub data: Option<DataDiff>,
+}
+
+/// Result of comparing two files; see `FITS::diff`
+#[derive(Clone, Debug, PartialEq)]
+pub struct FITSDiff {
+    /// Number of HDUs in each file
+    pub hdu_counts: (usize, usize),
+    /// HDUs that differ; HDUs beyond the end of the shorter file are
+    /// not compared
+    pub hdus: Vec<HDUDiff>,
+}
+
+impl FITSDiff {
+    /// True if no difference was found
+    pub fn is_identical(&self) -> bool {
+        self.hdu_counts.0 == self.hdu_counts.1 && self.hdus.is_empty()
+    }
+}
+
+impl std::fmt::Display for FITSDiff {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let value = |v: &Option<KeywordValue>| match v {
+            Some(v) => v.to_string(),
+            None => "(missing)".to_string(),
+        };
+        if self.hdu_counts.0 != self.hdu_counts.1 {
+            writeln!(
+                f,
+                "Files have different numbers of HDUs: {} and {}",
+                self.hdu_counts.0, self.hdu_counts.1
+            )?;
+        }
+        for hdu in &self.hdus {
+            writeln!(f, "HDU {}:", hdu.index)?;
+            for kw in &hdu.keywords {
+                writeln!(
+                    f,
+                    "  Keyword {}: {} != {}",
+                    kw.name,
+                    value(&kw.a),
+                    value(&kw.b)
+                )?;
+            }
+            match &hdu.data {
+                None => {}
+                Some(DataDiff::Structure(message)) => writeln!(f, "  Data: {}", message)?,
+                Some(DataDiff::Pixels {
+                    count,
+                    max_deviation,
+                }) => writeln!(
+                    f,
+                    "  Data: {} pixels differ, maximum deviation {}",
+                    count, max_deviation
+                )?,
+                Some(DataDiff::Cells {
+                    count,
+                    max_deviation,
+                    cells,
+                }) => {
+                    writeln!(
+                        f,
+                        "  Data: {} table cells differ, maximum deviation {}",
+                        count, max_deviation
+                    )?;
+                    for (row, col) in cells {
+                        writeln!(f, "    row {}, column {}", row, col)?;
+                    }
+                }
+                Some(DataDiff::Bytes { count }) => writeln!(f, "  Data: {} bytes differ", count)?,
+            }
+        }
+        match self.is_identical() {
+            true => write!(f, "No differences found"),
+            false => write!(f, "{} HDUs differ", self.hdus.len()),
+        }
+    }
+}
+
+/// Is a keyword excluded from comparison
+fn ignored(name: &str, options: &DiffOptions) -> bool {
+    matches!(name, "COMMENT" | "HISTORY" | "" | "END")
+        || options
+            .ignore_keywords
+            .iter()
+            .any(|k| k.eq_ignore_ascii_case(name))
+}
+
+/// Are two keyword values equal, comparing numbers within tolerance
+fn values_equal(a: &KeywordValue, b: &KeywordValue, options: &DiffOptions) -> bool {
+    let number = |v: &KeywordValue| match v {
+        KeywordValue::Int(i) => Some(*i as f64),
+        KeywordValue::Float(f) => Some(*f),
+        _ => None,
+    };
+    match (a, b) {
+        (KeywordValue::Int(x), KeywordValue::Int(y)) => x == y,
+        (KeywordValue::ComplexFloat(ar, ai), KeywordValue::ComplexFloat(br, bi)) => {
+            options.close(*ar, *br) && options.close(*ai, *bi)
+        }
+        _ => match (number(a), number(b)) {
+            (Some(x), Some(y)) => options.close(x, y),
+            _ => a == b,
+        },
+    }
+}
+
+/// Keywords whose values differ between two headers
+///
+/// Where a keyword is repeated, the first occurrence is compared
+fn diff_headers(a: &Header, b: &Header, options: &DiffOptions) -> Vec<KeywordDiff> {
+    let mut diffs = Vec::new();
+    let mut seen = std::collections::HashSet::new();
+    for name in a.iter().chain(b.iter()).map(|kw| kw.name.as_str()) {
+        if ignored(name, options) || !seen.insert(name) {
+            continue;
+        }
+        let (va, vb) = (a.value(name), b.value(name));
+        let equal = match (va, vb) {
+            (Some(va), Some(vb)) => values_equal(va, vb, options),
+            _ => false,
+        };
+        if !equal {
+            diffs.push(KeywordDiff {
+                name: name.to_string(),
+                a: va.cloned(),
+                b: vb.cloned(),
+            });
+        }
+    }
+    diffs
+}
+
+/// Compare image pixels by physical value
+fn diff_images(a: &Image, b: &Image, options: &DiffOptions) -> Option<DataDiff> {
+    if a.axes != b.axes {
+        return Some(DataDiff::Structure(format!(
+            "image dimensions differ: {:?} and {:?}",
+            a.axes, b.axes
+        )));
+    }
+    let mut count = 0;
+    let mut max_deviation: f64 = 0.0;
+    for (x, y) in a.physical_pixels().into_iter().zip(b.physical_pixels()) {
+        if !options.close(x, y) {
+            count += 1;
+            if (x - y).is_finite() {
+                max_deviation = max_deviation.max((x - y).abs());
+            }
+        }
+    }
+    match count {
+        0 => None,
+        _ => Some(DataDiff::Pixels {
+            count,
+            max_deviation,
+        }),
+    }
+}
+
+/// Do two table cells differ, recording the deviation of numeric values
+fn cells_differ(
+    a: &BinTableValue,
+    b: &BinTableValue,
+    options: &DiffOptions,
+    max_deviation: &mut f64,
+) -> bool {
+    let mut numbers = |x: f64, y: f64| {
+        if options.close(x, y) {
+            return false;
+        }
+        if (x - y).is_finite() {
+            *max_deviation = max_deviation.max((x - y).abs());
+        }
+        true
+    };
+    match (a, b) {
+        (BinTableValue::Array(x), BinTableValue::Array(y)) => {
+            x.len() != y.len()
+                || x.iter().zip(y).fold(false, |differ, (x, y)| {
+                    cells_differ(x, y, options, max_deviation) || differ
+                })
+        }
+        (BinTableValue::Complex32(ar, ai), BinTableValue::Complex32(br, bi)) => {
+            numbers(*ar as f64, *br as f64) | numbers(*ai as f64, *bi as f64)
+        }
+        (BinTableValue::Complex64(ar, ai), BinTableValue::Complex64(br, bi)) => {
+            numbers(*ar, *br) | numbers(*ai, *bi)
+        }
+        _ => match (a.as_f64(), b.as_f64()) {
+            (Some(x), Some(y)) => numbers(x, y),
+            _ => a != b,
+        },
+    }
+}
+
+/// Compare binary table cells by physical value
+fn diff_bintables(a: &BinTable, b: &BinTable, options: &DiffOptions) -> Option<DataDiff> {
+    if a.nrows != b.nrows || a.ncols() != b.ncols() {
+        return Some(DataDiff::Structure(format!(
+            "table sizes differ: {} rows x {} columns and {} rows x {} columns",
+            a.nrows,
+            a.ncols(),
+            b.nrows,
+            b.ncols()
+        )));
+    }
+    if a.ttype != b.ttype {
+        return Some(DataDiff::Structure("column names differ".to_string()));
+    }
+    let mut count = 0;
+    let mut max_deviation: f64 = 0.0;
+    let mut cells = Vec::new();
+    for row in 0..a.nrows {
+        for col in 0..a.ncols() {
+            let differ = match (a.at_physical(row, col), b.at_physical(row, col)) {
+                (Ok(x), Ok(y)) => cells_differ(&x, &y, options, &mut max_deviation),
+                (x, y) => x.is_ok() != y.is_ok(),
+            };
+            if differ {
+                count += 1;
+                if cells.len() < options.max_cells {
+                    cells.push((row, col));
+                }
+            }
+        }
+    }
+    match count {
+        0 => None,
+        _ => Some(DataDiff::Cells {
+            count,
+            max_deviation,
+            cells,
+        }),
+    }
+}
+
+/// Compare raw data bytes
+fn diff_bytes(a: &[u8], b: &[u8]) -> Option<DataDiff> {
+    let count = a.iter().zip(b).filter(|(x, y)| x != y).count() + a.len().abs_diff(b.len());
+    match count {
+        0 => None,
+        _ => Some(DataDiff::Bytes { count }),
+    }
+}
+
+/// Compare the data units of two HDUs
+fn diff_data(a: &HDU, b: &HDU, options: &DiffOptions) -> Option<DataDiff> {
+    let kind = |data: &HDUData| match data {
+        HDUData::None => "no data".to_string(),
+        HDUData::Image(_) => "image".to_string(),
+        HDUData::BinTable(_) => "binary table".to_string(),
+        HDUData::Table(_) => "ASCII table".to_string(),
+        HDUData::Unknown { xtension, .. } => xtension.clone(),
+    };
+    match (&a.data, &b.data) {
+        (HDUData::None, HDUData::None) => None,
+        (HDUData::Image(x), HDUData::Image(y)) => diff_images(x, y, options),
+        (HDUData::BinTable(x), HDUData::BinTable(y)) => diff_bintables(x, y, options),
+        (HDUData::Table(x), HDUData::Table(y)) => diff_bytes(&x.rawbytes, &y.rawbytes),
+        (HDUData::Unknown { raw: x, .. }, HDUData::Unknown { raw: y, .. }) => diff_bytes(x, y),
+        (x, y) => Some(DataDiff::Structure(format!(
+            "data types differ: {} and {}",
+            kind(x),
+            kind(y)
+        ))),
+    }
+}
+
+impl FITS {
+    /// Compare with another file
+    ///
+    /// HDUs are compared by position.  Header keyword values are
+    /// compared by name, ignoring commentary keywords and those in
+    /// `options.ignore_keywords`.  Images are compared pixel by pixel,
+    /// and binary tables cell by cell, using physical values (after
+    /// BSCALE/BZERO or TSCALn/TZEROn) within the tolerances of
+    /// `options`
+    ///
+    /// # Arguments
+    ///
+    /// * `other` - File to compare with
+    /// * `options` - Tolerances and keywords to ignore
+    ///
+    /// # Returns
+    ///
+    /// Report of the HDUs that differ
+    ///
+    pub fn diff(&self, other: &FITS, options: &DiffOptions) -> FITSDiff {
+        FITSDiff {
+            hdu_counts: (self.hdus.len(), other.hdus.len()),
+            hdus: self
+                .hdus
+                .iter()
+                .zip(other.hdus.iter())
+                .enumerate()
+                .filter_map(|(index, (a, b))| {
+                    let diff = HDUDiff {
+                        index,
+                        keywords: diff_headers(&a.header, &b.header, options),
+                        data: diff_data(a, b, options),
+                    };
+                    match diff.keywords.is_empty() && diff.data.is_none() {
+                        true => None,
+                        false => Some(diff),
+                    }
+                })
+                .collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testutil::hdu_bytes;
+
+    fn image_file(pixels: &[u8], extra: &str) -> FITS {
+        let raw = hdu_bytes(
+            &[
+                "SIMPLE  =                    T",
+                "BITPIX  =                    8",
+                "NAXIS   =                    1",
+                "NAXIS1  =                    4",
+                "EXPTIME =                 10.0",
+                extra,
+                "END",
+            ],
+            pixels,
+        );
+        FITS::from_bytes(&raw).unwrap()
+    }
+
+    #[test]
+    fn test_diff() {
+        let a = image_file(&[1, 2, 3, 4], "OBJECT  = 'M31     '");
+        let options = DiffOptions::default();
+        assert!(a.diff(&a, &options).is_identical());
+
+        let b = image_file(&[1, 2, 5, 9], "CHECKSUM= 'abc     '");
+        let diff = a.diff(&b, &options);
+        assert!(!diff.is_identical());
+        assert_eq!(diff.hdus.len(), 1);
+        assert_eq!(
+            diff.hdus[0].keywords,
+            vec![KeywordDiff {
+                name: "OBJECT".to_string(),
+                a: Some(KeywordValue::String("M31".to_string())),
+                b: None,
+            }]
+        );
+        assert_eq!(
+            diff.hdus[0].data,
+            Some(DataDiff::Pixels {
+                count: 2,
+                max_deviation: 5.0
+            })
+        );
+
+        // Within tolerance, only the third pixel differs; EXPTIME is
+        // compared numerically
+        let c = image_file(&[1, 2, 5, 4], "OBJECT  = 'M31     '");
+        let d = image_file(&[1, 2, 3, 4], "OBJECT  = 'M31     ' / target");
+        let mut options = DiffOptions {
+            atol: 1.0,
+            ..Default::default()
+        };
+        assert_eq!(
+            a.diff(&c, &options).hdus[0].data,
+            Some(DataDiff::Pixels {
+                count: 1,
+                max_deviation: 2.0
+            })
+        );
+        options.atol = 2.0;
+        assert!(a.diff(&c, &options).is_identical());
+        assert!(a.diff(&d, &options).is_identical());
+
+        let mut e = image_file(&[1, 2, 3, 4], "OBJECT  = 'M31     '");
+        e.push_hdu(e.hdus[0].clone());
+        let diff = a.diff(&e, &options);
+        assert_eq!(diff.hdu_counts, (1, 2));
+        assert!(!diff.is_identical());
+        assert!(diff.to_string().contains("different numbers of HDUs"));
+    }
+
+    #[test]
+    fn test_cells_differ() {
+        let options = DiffOptions {
+            rtol: 0.01,
+            ..Default::default()
+        };
+        let mut max = 0.0;
+        let array = |v: &[f64]| {
+            BinTableValue::Array(v.iter().map(|&x| BinTableValue::Float64(x)).collect())
+        };
+        assert!(!cells_differ(
+            &BinTableValue::Int16(100),
+            &BinTableValue::Float64(100.5),
+            &options,
+            &mut max
+        ));
+        assert!(cells_differ(
+            &array(&[1.0, 2.0, 3.0]),
+            &array(&[1.0, 2.5, 4.0]),
+            &options,
+            &mut max
+        ));
+        assert_eq!(max, 1.0);
+        assert!(cells_differ(
+            &array(&[1.0]),
+            &array(&[1.0, 2.0]),
+            &options,
+            &mut max
+        ));
+        assert!(!cells_differ(
+            &BinTableValue::Float32(f32::NAN),
+            &BinTableValue::Float32(f32::NAN),
+            &options,
+            &mut max
+        ));
+        assert!(cells_differ(
+            &BinTableValue::String("A".to_string()),
+            &BinTableValue::String("B".to_string()),
+            &options,
+            &mut max
+        ));
+        assert!(cells_differ(
+            &BinTableValue::Null,
+            &BinTableValue::Int16(0),
+            &options,
+            &mut max
+        ));
+        assert_eq!(max, 1.0);
+    }
+}
@@ -0,0 +1,341 @@
+//! Extended file names selecting an HDU and table columns, as in cfitsio
+//!
+//! `data.fits[EVENTS]` or `data.fits[EVENTS,2]` select an extension by
+//! EXTNAME (and EXTVER), `data.fits[2]` by position (0 is the primary
+//! HDU), and a following `[col X;Y]` keeps only the named columns of a
+//! binary table
+
+use super::FITS;
+use crate::hdu::data_unit_size;
+use crate::FITSError;
+use crate::HDUData;
+use crate::HDULocation;
+use crate::HDU;
+
+/// HDU selected by an extended file name
+#[derive(Clone, Debug, PartialEq)]
+pub enum HDUSelector {
+    /// Position in the file; 0 is the primary HDU
+    Index(usize),
+    /// Extension name (EXTNAME, case-insensitive), and optionally
+    /// version (EXTVER)
+    Name(String, Option<i64>),
+}
+
+impl HDUSelector {
+    /// Does an HDU at a given position match
+    pub(super) fn matches(&self, index: usize, header: &crate::Header) -> bool {
+        match self {
+            HDUSelector::Index(i) => *i == index,
+            HDUSelector::Name(name, version) => {
+                header
+                    .extname()
+                    .is_some_and(|n| n.trim_end().eq_ignore_ascii_case(name))
+                    && version.is_none_or(|v| header.extver() == v)
+            }
+        }
+    }
+}
+
+impl std::fmt::Display for HDUSelector {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            HDUSelector::Index(i) => write!(f, "[{}]", i),
+            HDUSelector::Name(name, None) => write!(f, "[{}]", name),
+            HDUSelector::Name(name, Some(v)) => write!(f, "[{},{}]", name, v),
+        }
+    }
+}
+
+/// A file name with optional HDU and column selection
+#[derive(Clone, Debug, PartialEq)]
+pub struct FileSpec {
+    pub path: String,
+    pub hdu: Option<HDUSelector>,
+    /// Columns to keep, from `[col X;Y]`
+    pub columns: Option<Vec<String>>,
+}
+
+impl FileSpec {
+    /// Parse an extended file name
+    ///
+    /// # Arguments
+    ///
+    /// * `spec` - File name, optionally followed by `[hdu]` and/or
+    ///   `[col name;name...]`
+    ///
+    /// # Returns
+    ///
+    /// The path and selections, or an error if a bracketed part is
+    /// malformed
+    ///
+    pub fn parse(spec: &str) -> Result<Self, FITSError> {
+        let invalid = || FITSError::InvalidFilename(spec.to_string());
+        let mut parsed = FileSpec {
+            path: spec.to_string(),
+            hdu: None,
+            columns: None,
+        };
+        let Some(open) = spec.find('[') else {
+            return Ok(parsed);
+        };
+        parsed.path = spec[..open].to_string();
+        if parsed.path.is_empty() {
+            return Err(invalid());
+        }
+
+        let mut rest = &spec[open..];
+        while !rest.is_empty() {
+            let close = match rest.strip_prefix('[') {
+                Some(_) => rest.find(']').ok_or_else(invalid)?,
+                None => return Err(invalid()),
+            };
+            let part = rest[1..close].trim();
+            rest = &rest[close + 1..];
+
+            let column_list = part
+                .get(..4)
+                .filter(|p| p.eq_ignore_ascii_case("col "))
+                .map(|_| &part[4..]);
+            if let Some(list) = column_list {
+                if parsed.columns.is_some() {
+                    return Err(invalid());
+                }
+                let names: Vec<String> = list
+                    .split([';', ','])
+                    .map(|n| n.trim().to_string())
+                    .filter(|n| !n.is_empty())
+                    .collect();
+                if names.is_empty() {
+                    return Err(invalid());
+                }
+                parsed.columns = Some(names);
+            } else {
+                if parsed.hdu.is_some() || parsed.columns.is_some() || part.is_empty() {
+                    return Err(invalid());
+                }
+                parsed.hdu = Some(match part.parse::<usize>() {
+                    Ok(i) => HDUSelector::Index(i),
+                    Err(_) => match part.split_once(',') {
+                        Some((name, version)) => HDUSelector::Name(
+                            name.trim().to_string(),
+                            Some(version.trim().parse().map_err(|_| invalid())?),
+                        ),
+                        None => HDUSelector::Name(part.to_string(), None),
+                    },
+                });
+            }
+        }
+        Ok(parsed)
+    }
+}
+
+impl FITS {
+    /// Read a file named with optional HDU and column selection
+    ///
+    /// Names that exist as files are read whole.  Otherwise the name is
+    /// parsed as a `FileSpec`; only the selected HDU is decoded, and the
+    /// result holds just that HDU.  A column selection without an HDU
+    /// selection applies to the first binary table
+    pub(crate) fn read_spec(file: &str, native: bool) -> Result<Self, FITSError> {
+        #[cfg(feature = "tracing")]
+        let _span = tracing::debug_span!("read", file).entered();
+        if std::path::Path::new(file).exists() {
+            return Self::parse(&std::fs::read(file)?, native);
+        }
+        let spec = FileSpec::parse(file)?;
+        let rawbytes = std::fs::read(&spec.path)?;
+        let selector = match (&spec.hdu, &spec.columns) {
+            (None, None) => return Self::parse(&rawbytes, native),
+            (selector, _) => selector,
+        };
+
+        // Skip HDUs by their headers until the selected one
+        let mut offset = 0;
+        let mut index = 0;
+        let mut hdu = loop {
+            if offset >= rawbytes.len() {
+                let what = match selector {
+                    Some(s) => s.to_string(),
+                    None => "a binary table".to_string(),
+                };
+                return Err(FITSError::HDUNotFound(what));
+            }
+            let (header, hbytes) = HDU::header_from_bytes(&rawbytes[offset..])?;
+            let found = match selector {
+                Some(s) => s.matches(index, &header),
+                None => header.first().is_some_and(|kw| {
+                    kw.name == "XTENSION"
+                        && kw.value == crate::KeywordValue::String("BINTABLE".to_string())
+                }),
+            };
+            if found {
+                let size = data_unit_size(&header)?;
+                let mut hdu = HDU::from_header(header, &rawbytes[offset + hbytes..], native)?;
+                hdu.location = Some(HDULocation::new(offset, hbytes, size));
+                break hdu;
+            }
+            let size = data_unit_size(&header)?;
+            offset += hbytes + size.div_ceil(2880) * 2880;
+            index += 1;
+        };
+
+        if let Some(columns) = &spec.columns {
+            let HDUData::BinTable(table) = &hdu.data else {
+                return Err(FITSError::InvalidFilename(format!(
+                    "{}: column selection requires a binary table",
+                    file
+                )));
+            };
+            let names: Vec<&str> = columns.iter().map(String::as_str).collect();
+            let table = table.select_columns(&names)?;
+            hdu = HDU {
+                header: table.header().clone(),
+                data: HDUData::BinTable(Box::new(table)),
+                location: hdu.location,
+            };
+        }
+        let mut fits = FITS::new();
+        fits.push_hdu(hdu);
+        Ok(fits)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testutil::hdu_bytes;
+
+    #[test]
+    fn test_parse_filespec() {
+        let spec = |s: &str| FileSpec::parse(s).unwrap();
+        assert_eq!(
+            spec("data.fits"),
+            FileSpec {
+                path: "data.fits".to_string(),
+                hdu: None,
+                columns: None
+            }
+        );
+        assert_eq!(spec("data.fits[2]").hdu, Some(HDUSelector::Index(2)));
+        assert_eq!(
+            spec("dir/data.fits[EVENTS]").hdu,
+            Some(HDUSelector::Name("EVENTS".to_string(), None))
+        );
+        let events = spec("data.fits[events, 2][col TIME; PI]");
+        assert_eq!(events.path, "data.fits");
+        assert_eq!(
+            events.hdu,
+            Some(HDUSelector::Name("events".to_string(), Some(2)))
+        );
+        assert_eq!(
+            events.columns,
+            Some(vec!["TIME".to_string(), "PI".to_string()])
+        );
+        assert_eq!(spec("data.fits[COL X]").hdu, None);
+
+        for bad in [
+            "data.fits[",
+            "data.fits[]",
+            "data.fits[1][2]",
+            "data.fits[col X][1]",
+            "data.fits[EVENTS,x]",
+            "data.fits[1]x",
+            "[1]",
+            "data.fits[col ;]",
+        ] {
+            assert!(FileSpec::parse(bad).is_err(), "{}", bad);
+        }
+    }
+
+    #[test]
+    fn test_from_file_selection() {
+        let mut raw = hdu_bytes(
+            &[
+                "SIMPLE  =                    T",
+                "BITPIX  =                    8",
+                "NAXIS   =                    1",
+                "NAXIS1  =                    4",
+                "EXTEND  =                    T",
+                "END",
+            ],
+            &[9, 9, 9, 9],
+        );
+        raw.extend(hdu_bytes(
+            &[
+                "XTENSION= 'IMAGE   '",
+                "BITPIX  =                    8",
+                "NAXIS   =                    1",
+                "NAXIS1  =                    4",
+                "PCOUNT  =                    0",
+                "GCOUNT  =                    1",
+                "EXTNAME = 'SCI     '",
+                "END",
+            ],
+            &[1, 2, 3, 4],
+        ));
+        let mut rows = Vec::new();
+        for (time, pi) in [(1.5f64, 7i32), (2.5, 9)] {
+            rows.extend(time.to_be_bytes());
+            rows.extend(pi.to_be_bytes());
+        }
+        raw.extend(hdu_bytes(
+            &[
+                "XTENSION= 'BINTABLE'",
+                "BITPIX  =                    8",
+                "NAXIS   =                    2",
+                "NAXIS1  =                   12",
+                "NAXIS2  =                    2",
+                "PCOUNT  =                    0",
+                "GCOUNT  =                    1",
+                "TFIELDS =                    2",
+                "TTYPE1  = 'TIME    '",
+                "TFORM1  = '1D      '",
+                "TUNIT1  = 's       '",
+                "TTYPE2  = 'PI      '",
+                "TFORM2  = '1J      '",
+                "EXTNAME = 'EVENTS  '",
+                "END",
+            ],
+            &rows,
+        ));
+        let path = std::env::temp_dir().join(format!("fits_filename_{}.fits", std::process::id()));
+        let path = path.to_str().unwrap();
+        std::fs::write(path, &raw).unwrap();
+
+        assert_eq!(FITS::from_file(path).unwrap().len(), 3);
+        let fits = FITS::from_file(&format!("{}[1]", path)).unwrap();
+        assert_eq!(fits.len(), 1);
+        assert_eq!(fits.at(0).unwrap().extname(), Some("SCI"));
+
+        let fits = FITS::from_file(&format!("{}[events][col PI]", path)).unwrap();
+        let hdu = fits.at(0).unwrap();
+        let HDUData::BinTable(table) = &hdu.data else {
+            panic!("expected binary table");
+        };
+        assert_eq!(table.ncols(), 1);
+        assert_eq!(table.at(1, 0).unwrap(), crate::BinTableValue::Int32(9));
+        assert_eq!(hdu.header.get::<String>("TTYPE1").unwrap(), "PI");
+        assert_eq!(hdu.header.get::<String>("TFORM1").unwrap(), "1J");
+        assert!(hdu.header.find("TUNIT1").is_none());
+        assert!(hdu.header.find("TTYPE2").is_none());
+        assert_eq!(hdu.header.get::<i64>("NAXIS1").unwrap(), 4);
+
+        let fits = FITS::from_file_deferred(&format!("{}[col time]", path)).unwrap();
+        assert_eq!(
+            fits.at(0).unwrap().header.get::<String>("TUNIT1").unwrap(),
+            "s"
+        );
+
+        assert!(matches!(
+            FITS::from_file(&format!("{}[EVENTS,2]", path)),
+            Err(FITSError::HDUNotFound(_))
+        ));
+        assert!(matches!(
+            FITS::from_file(&format!("{}[1][col PI]", path)),
+            Err(FITSError::InvalidFilename(_))
+        ));
+        assert!(FITS::from_file(&format!("{}[EVENTS][col ENERGY]", path)).is_err());
+        std::fs::remove_file(path).unwrap();
+    }
+}
@@ -0,0 +1,377 @@
+//! Hierarchical grouping convention
+//!
+//! A grouping table is a table extension with EXTNAME = 'GROUPING'
+//! whose rows identify the member HDUs of a group, in the same or
+//! other files.  Members are identified by the columns MEMBER_XTENSION,
+//! MEMBER_NAME, MEMBER_VERSION, and MEMBER_POSITION, and located in
+//! other files by MEMBER_LOCATION (or MEMBER_URI) and MEMBER_URI_TYPE;
+//! any of these columns may be omitted
+
+use super::HDUSelector;
+use super::FITS;
+use crate::FITSError;
+use crate::FromTableValue;
+use crate::HDUData;
+use crate::Header;
+use crate::TableLike;
+use crate::HDU;
+use std::borrow::Cow;
+use std::path::Path;
+
+/// A member of a group, as listed in a row of a grouping table
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct GroupMember {
+    /// Extension type ("IMAGE", "BINTABLE", "TABLE", or "PRIMARY")
+    pub xtension: Option<String>,
+    /// Extension name (EXTNAME)
+    pub name: Option<String>,
+    /// Extension version (EXTVER)
+    pub version: Option<i64>,
+    /// Position of the HDU in its file, counting the primary HDU as 1
+    pub position: Option<usize>,
+    /// File containing the member, as a path or URI; None if it is in
+    /// the same file as the grouping table
+    pub location: Option<String>,
+    /// Type of `location`, e.g. "URL"
+    pub uri_type: Option<String>,
+}
+
+/// A group read from a grouping table
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Group {
+    /// Name of the group, from GRPNAME
+    pub name: Option<String>,
+    pub members: Vec<GroupMember>,
+}
+
+/// Values of a column that may be absent, with blank strings as None
+fn optional_column<T: FromTableValue, L: TableLike>(
+    table: &L,
+    names: &[&str],
+) -> Result<Vec<Option<T>>, FITSError> {
+    match names.iter().find(|n| table.column_index(n).is_ok()) {
+        Some(name) => table.column(name),
+        None => Ok((0..table.nrows()).map(|_| None).collect()),
+    }
+}
+
+/// Trimmed text of a string cell, None if blank
+fn text(value: Option<String>) -> Option<String> {
+    value
+        .map(|s| s.trim_end_matches([' ', '\0']).trim_start().to_string())
+        .filter(|s| !s.is_empty())
+}
+
+impl Group {
+    /// Read a group from a grouping table
+    ///
+    /// # Arguments
+    ///
+    /// * `hdu` - A binary or ASCII table extension with EXTNAME = 'GROUPING'
+    ///
+    /// # Returns
+    ///
+    /// The group, or an error if the HDU is not a grouping table
+    ///
+    pub fn from_hdu(hdu: &HDU) -> Result<Self, FITSError> {
+        if !is_grouping(&hdu.header) {
+            return Err(FITSError::InvalidGroup(
+                "EXTNAME is not GROUPING".to_string(),
+            ));
+        }
+        let members = match &hdu.data {
+            HDUData::BinTable(table) => read_members(table.as_ref())?,
+            HDUData::Table(table) => read_members(table.as_ref())?,
+            _ => {
+                return Err(FITSError::InvalidGroup(
+                    "grouping HDU is not a table".to_string(),
+                ))
+            }
+        };
+        Ok(Group {
+            name: hdu.header.get::<String>("GRPNAME").ok(),
+            members,
+        })
+    }
+
+    /// Iterate over the members
+    pub fn iter(&self) -> std::slice::Iter<'_, GroupMember> {
+        self.members.iter()
+    }
+
+    /// Iterate over the member HDUs; see `GroupMember::resolve`
+    ///
+    /// # Arguments
+    ///
+    /// * `fits` - The file containing the grouping table
+    /// * `base` - Directory against which relative member locations
+    ///   are resolved, usually that of the grouping table's file
+    ///
+    pub fn resolve_all<'a>(
+        &'a self,
+        fits: &'a FITS,
+        base: Option<&'a Path>,
+    ) -> impl Iterator<Item = Result<Cow<'a, HDU>, FITSError>> + 'a {
+        self.members.iter().map(move |m| m.resolve(fits, base))
+    }
+}
+
+impl<'a> IntoIterator for &'a Group {
+    type Item = &'a GroupMember;
+    type IntoIter = std::slice::Iter<'a, GroupMember>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.members.iter()
+    }
+}
+
+fn is_grouping(header: &Header) -> bool {
+    header
+        .extname()
+        .is_some_and(|n| n.trim_end().eq_ignore_ascii_case("GROUPING"))
+}
+
+fn read_members<L: TableLike>(table: &L) -> Result<Vec<GroupMember>, FITSError> {
+    let xtension = optional_column::<String, _>(table, &["MEMBER_XTENSION"])?;
+    let name = optional_column::<String, _>(table, &["MEMBER_NAME"])?;
+    let version = optional_column::<i64, _>(table, &["MEMBER_VERSION"])?;
+    let position = optional_column::<i64, _>(table, &["MEMBER_POSITION"])?;
+    let location = optional_column::<String, _>(table, &["MEMBER_LOCATION", "MEMBER_URI"])?;
+    let uri_type = optional_column::<String, _>(table, &["MEMBER_URI_TYPE"])?;
+    Ok((0..table.nrows())
+        .map(|row| GroupMember {
+            xtension: text(xtension[row].clone()),
+            name: text(name[row].clone()),
+            // Zero is the conventional null value of both columns
+            version: version[row].filter(|v| *v > 0),
+            position: position[row].filter(|p| *p > 0).map(|p| p as usize),
+            location: text(location[row].clone()),
+            uri_type: text(uri_type[row].clone()),
+        })
+        .collect())
+}
+
+impl GroupMember {
+    /// HDU selector for the member within its file
+    ///
+    /// By name (and version) if known, else by position; a member with
+    /// neither is taken to be the primary HDU
+    pub fn selector(&self) -> HDUSelector {
+        match (&self.name, self.position) {
+            (Some(name), _) => HDUSelector::Name(name.clone(), self.version),
+            (None, Some(position)) => HDUSelector::Index(position - 1),
+            (None, None) => HDUSelector::Index(0),
+        }
+    }
+
+    /// Does an HDU at a given position match the member's extension
+    /// type and selector
+    fn matches(&self, index: usize, header: &Header) -> bool {
+        let xtension = match header.get::<String>("XTENSION") {
+            Ok(x) => x,
+            Err(_) => "PRIMARY".to_string(),
+        };
+        self.xtension
+            .as_ref()
+            .is_none_or(|x| x.eq_ignore_ascii_case(xtension.trim_end()))
+            && self.selector().matches(index, header)
+    }
+
+    /// Find the member HDU
+    ///
+    /// Members without a location are looked up in `fits`.  Others are
+    /// read from the file at their location, which may be a path or a
+    /// "file:" URI; only the member HDU of that file is decoded
+    ///
+    /// # Arguments
+    ///
+    /// * `fits` - The file containing the grouping table
+    /// * `base` - Directory against which relative member locations
+    ///   are resolved
+    ///
+    /// # Returns
+    ///
+    /// The HDU, borrowed from `fits` or read from another file, or an
+    /// error if it cannot be found
+    ///
+    pub fn resolve<'a>(
+        &self,
+        fits: &'a FITS,
+        base: Option<&Path>,
+    ) -> Result<Cow<'a, HDU>, FITSError> {
+        let not_found = || FITSError::HDUNotFound(self.to_string());
+        let Some(location) = &self.location else {
+            return fits
+                .iter()
+                .enumerate()
+                .find(|(i, hdu)| self.matches(*i, &hdu.header))
+                .map(|(_, hdu)| Cow::Borrowed(hdu))
+                .ok_or_else(not_found);
+        };
+
+        let path = location
+            .strip_prefix("file://")
+            .or_else(|| location.strip_prefix("file:"))
+            .unwrap_or(location);
+        if path.contains("://") {
+            return Err(FITSError::InvalidGroup(format!(
+                "unsupported member location {}",
+                location
+            )));
+        }
+        let path = match base {
+            Some(base) => base.join(path),
+            None => Path::new(path).to_path_buf(),
+        };
+        let spec = format!("{}{}", path.display(), self.selector());
+        let mut member = FITS::from_file(&spec)?;
+        match member.is_empty() {
+            false => member.remove_hdu(0).map(Cow::Owned),
+            true => Err(not_found()),
+        }
+    }
+}
+
+impl std::fmt::Display for GroupMember {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        if let Some(location) = &self.location {
+            write!(f, "{}", location)?;
+        }
+        write!(f, "{}", self.selector())
+    }
+}
+
+impl FITS {
+    /// Groups defined by the grouping tables of the file
+    ///
+    /// # Returns
+    ///
+    /// The groups, in file order, or an error if a grouping table
+    /// cannot be read
+    ///
+    pub fn groups(&self) -> Result<Vec<Group>, FITSError> {
+        self.iter()
+            .filter(|hdu| is_grouping(&hdu.header))
+            .map(Group::from_hdu)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testutil::hdu_bytes;
+
+    fn image(xtension: bool, extname: &str) -> Vec<u8> {
+        let first = match xtension {
+            true => "XTENSION= 'IMAGE   '",
+            false => "SIMPLE  =                    T",
+        };
+        let extname = format!("EXTNAME = '{:<8}'", extname);
+        hdu_bytes(
+            &[
+                first,
+                "BITPIX  =                    8",
+                "NAXIS   =                    1",
+                "NAXIS1  =                    2",
+                "PCOUNT  =                    0",
+                "GCOUNT  =                    1",
+                &extname,
+                "END",
+            ],
+            &[1, 2],
+        )
+    }
+
+    #[test]
+    fn test_group() {
+        let other = std::env::temp_dir().join(format!("fits_group_{}.fits", std::process::id()));
+        let mut raw = image(false, "PRIM");
+        raw.extend(image(true, "FAR"));
+        std::fs::write(&other, &raw).unwrap();
+
+        // Members: SCI by name, the primary by position, FAR in another file
+        let mut rows = Vec::new();
+        let location = format!("{:<40}", other.file_name().unwrap().to_str().unwrap());
+        for (xtension, name, version, position, location) in [
+            ("IMAGE   ", "SCI     ", 0i32, 0i32, format!("{:40}", "")),
+            ("PRIMARY ", "        ", 0, 1, format!("{:40}", "")),
+            ("IMAGE   ", "FAR     ", 1, 0, location),
+        ] {
+            rows.extend(xtension.as_bytes());
+            rows.extend(name.as_bytes());
+            rows.extend(version.to_be_bytes());
+            rows.extend(position.to_be_bytes());
+            rows.extend(location.as_bytes());
+        }
+        let mut raw = image(false, "PRIM");
+        raw.extend(image(true, "SCI"));
+        raw.extend(hdu_bytes(
+            &[
+                "XTENSION= 'BINTABLE'",
+                "BITPIX  =                    8",
+                "NAXIS   =                    2",
+                "NAXIS1  =                   64",
+                "NAXIS2  =                    3",
+                "PCOUNT  =                    0",
+                "GCOUNT  =                    1",
+                "TFIELDS =                    5",
+                "TTYPE1  = 'MEMBER_XTENSION'",
+                "TFORM1  = '8A      '",
+                "TTYPE2  = 'MEMBER_NAME'",
+                "TFORM2  = '8A      '",
+                "TTYPE3  = 'MEMBER_VERSION'",
+                "TFORM3  = '1J      '",
+                "TNULL3  =                    0",
+                "TTYPE4  = 'MEMBER_POSITION'",
+                "TFORM4  = '1J      '",
+                "TNULL4  =                    0",
+                "TTYPE5  = 'MEMBER_LOCATION'",
+                "TFORM5  = '40A     '",
+                "EXTNAME = 'GROUPING'",
+                "GRPNAME = 'OBSERVATION'",
+                "END",
+            ],
+            &rows,
+        ));
+        let fits = FITS::from_bytes(&raw).unwrap();
+
+        let groups = fits.groups().unwrap();
+        assert_eq!(groups.len(), 1);
+        let group = &groups[0];
+        assert_eq!(group.name.as_deref(), Some("OBSERVATION"));
+        assert_eq!(
+            group.members[0],
+            GroupMember {
+                xtension: Some("IMAGE".to_string()),
+                name: Some("SCI".to_string()),
+                ..Default::default()
+            }
+        );
+        assert_eq!(group.members[1].position, Some(1));
+        assert_eq!(group.iter().count(), 3);
+
+        let base = other.parent().unwrap();
+        let hdus = group
+            .resolve_all(&fits, Some(base))
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(
+            hdus.iter()
+                .map(|h| h.extname().unwrap())
+                .collect::<Vec<_>>(),
+            vec!["SCI", "PRIM", "FAR"]
+        );
+        assert!(matches!(hdus[0], Cow::Borrowed(_)));
+        assert!(matches!(hdus[2], Cow::Owned(_)));
+
+        let missing = GroupMember {
+            xtension: Some("BINTABLE".to_string()),
+            name: Some("SCI".to_string()),
+            ..Default::default()
+        };
+        assert!(missing.resolve(&fits, None).is_err());
+        assert!(Group::from_hdu(fits.at(1).unwrap()).is_err());
+        std::fs::remove_file(&other).unwrap();
+    }
+}
@@ -0,0 +1,703 @@
+//! Index of the HDUs of many files, built from their headers
+//!
+//! Scanning a directory reads only the headers of each file, and
+//! records selected keywords and the sky footprint of each data unit.
+//! The index can be queried by keyword value, by a range of values
+//! (e.g. DATE-OBS), or by sky position, and saved as a FITS binary
+//! table so that it need not be rebuilt
+
+use super::structure::xtension;
+use super::FITSStream;
+use super::FITS;
+use crate::hdu::data_unit_size;
+use crate::BinTable;
+use crate::FITSError;
+use crate::HDUData;
+use crate::Header;
+use crate::Keyword;
+use crate::KeywordValue;
+use crate::TableLike;
+use crate::HDU;
+use crate::WCS;
+use std::collections::BTreeMap;
+
+/// Keywords indexed by `FITSIndex::scan` when none are given
+pub const DEFAULT_INDEX_KEYWORDS: [&str; 3] = ["OBJECT", "DATE-OBS", "FILTER"];
+
+/// File name extensions of the files read by `FITSIndex::scan`
+const FITS_EXTENSIONS: [&str; 3] = ["fits", "fit", "fts"];
+
+/// Columns of a saved index that precede the keyword columns
+const PATH_COLUMN: &str = "PATH";
+const HDU_COLUMN: &str = "HDU";
+const EXTNAME_COLUMN: &str = "EXTNAME";
+const FOOTPRINT_COLUMN: &str = "FOOTPRNT";
+
+/// EXTNAME of the binary table written by `FITSIndex::to_hdu`
+const INDEX_EXTNAME: &str = "FITSINDEX";
+
+/// One data unit of an indexed file
+#[derive(Clone, Debug, PartialEq)]
+pub struct IndexEntry {
+    /// Path of the file
+    pub path: String,
+    /// Position of the HDU in the file; 0 is the primary HDU
+    pub hdu: usize,
+    /// Extension name, from EXTNAME
+    pub extname: Option<String>,
+    /// Values of the indexed keywords, as text.  Keywords absent from
+    /// both the HDU and the primary header are missing
+    pub values: BTreeMap<String, String>,
+    /// Celestial (longitude, latitude) of the image corners in
+    /// degrees, as from `WCS::footprint`; None without a celestial WCS
+    pub footprint: Option<[(f64, f64); 4]>,
+}
+
+impl IndexEntry {
+    /// Value of an indexed keyword; the name is not case sensitive
+    pub fn value(&self, keyword: &str) -> Option<&str> {
+        self.values
+            .get(&keyword.to_ascii_uppercase())
+            .map(String::as_str)
+    }
+
+    /// Is a sky position within the footprint
+    ///
+    /// The footprint is treated as a quadrilateral with great-circle
+    /// edges, so positions near the edges of strongly distorted images
+    /// may be misjudged
+    ///
+    /// # Arguments
+    ///
+    /// * `lon` - Longitude (e.g. right ascension) in degrees
+    /// * `lat` - Latitude (e.g. declination) in degrees
+    ///
+    pub fn contains(&self, lon: f64, lat: f64) -> bool {
+        self.footprint
+            .is_some_and(|corners| polygon_contains(&corners, lon, lat))
+    }
+}
+
+/// Index of the data units of a collection of FITS files
+///
+/// # Example
+///
+/// ```no_run
+/// # use fits::FITSIndex;
+/// # fn main() -> Result<(), fits::FITSError> {
+/// let index = FITSIndex::scan("archive/", &["OBJECT", "DATE-OBS", "FILTER", "EXPTIME"])?;
+/// index.save("archive/index.fits")?;
+///
+/// let index = FITSIndex::load("archive/index.fits")?;
+/// for entry in index.between("DATE-OBS", "2024-01-01", "2024-02-01") {
+///     if entry.value("FILTER") == Some("R") && entry.contains(83.82, -5.39) {
+///         println!("{}[{}]", entry.path, entry.hdu);
+///     }
+/// }
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct FITSIndex {
+    keywords: Vec<String>,
+    entries: Vec<IndexEntry>,
+}
+
+/// Unit vector of a sky position given in degrees
+fn unit_vector(lon: f64, lat: f64) -> [f64; 3] {
+    let (slon, clon) = lon.to_radians().sin_cos();
+    let (slat, clat) = lat.to_radians().sin_cos();
+    [clat * clon, clat * slon, slat]
+}
+
+fn dot(a: &[f64; 3], b: &[f64; 3]) -> f64 {
+    a[0] * b[0] + a[1] * b[1] + a[2] * b[2]
+}
+
+/// Is a sky position inside a polygon with great-circle edges
+///
+/// The corners and the position are projected gnomonically about the
+/// center of the polygon, which maps great circles to straight lines,
+/// and the crossing-number test is applied in the plane
+fn polygon_contains(corners: &[(f64, f64)], lon: f64, lat: f64) -> bool {
+    let vectors: Vec<[f64; 3]> = corners.iter().map(|&(l, b)| unit_vector(l, b)).collect();
+    let mut center = [0.0; 3];
+    for v in &vectors {
+        for i in 0..3 {
+            center[i] += v[i];
+        }
+    }
+    let norm = dot(&center, &center).sqrt();
+    if norm == 0.0 {
+        return false;
+    }
+    center.iter_mut().for_each(|c| *c /= norm);
+    // Orthonormal axes of the tangent plane at the center
+    let pole = match center[2].abs() < 0.9 {
+        true => [0.0, 0.0, 1.0],
+        false => [1.0, 0.0, 0.0],
+    };
+    let mut east = [
+        pole[1] * center[2] - pole[2] * center[1],
+        pole[2] * center[0] - pole[0] * center[2],
+        pole[0] * center[1] - pole[1] * center[0],
+    ];
+    let norm = dot(&east, &east).sqrt();
+    east.iter_mut().for_each(|c| *c /= norm);
+    let north = [
+        center[1] * east[2] - center[2] * east[1],
+        center[2] * east[0] - center[0] * east[2],
+        center[0] * east[1] - center[1] * east[0],
+    ];
+    let project = |v: &[f64; 3]| -> Option<(f64, f64)> {
+        let z = dot(v, &center);
+        match z > 0.0 {
+            true => Some((dot(v, &east) / z, dot(v, &north) / z)),
+            false => None,
+        }
+    };
+    let Some((x, y)) = project(&unit_vector(lon, lat)) else {
+        return false;
+    };
+    let Some(polygon) = vectors.iter().map(project).collect::<Option<Vec<_>>>() else {
+        return false;
+    };
+    let mut inside = false;
+    for (i, &(x1, y1)) in polygon.iter().enumerate() {
+        let (x2, y2) = polygon[(i + 1) % polygon.len()];
+        if (y1 > y) != (y2 > y) && x < x1 + (y - y1) * (x2 - x1) / (y2 - y1) {
+            inside = !inside;
+        }
+    }
+    inside
+}
+
+/// Text of a keyword value as stored in the index
+fn value_text(value: &KeywordValue) -> Option<String> {
+    match value {
+        KeywordValue::String(s) => Some(s.trim().to_string()),
+        KeywordValue::Int(i) => Some(i.to_string()),
+        KeywordValue::Float(f) => Some(f.to_string()),
+        KeywordValue::Bool(b) => Some(if *b { "T" } else { "F" }.to_string()),
+        _ => None,
+    }
+}
+
+/// Sky footprint of an image HDU from its header
+fn header_footprint(header: &Header) -> Option<[(f64, f64); 4]> {
+    if !matches!(xtension(header), None | Some("IMAGE")) {
+        return None;
+    }
+    let axes = header.naxes()?;
+    let wcs = WCS::from_header(header).ok()??;
+    if wcs.naxes() != axes.len() {
+        return None;
+    }
+    wcs.footprint(&axes).ok()?.try_into().ok()
+}
+
+/// Compare two indexed values: numerically if both are numbers, else
+/// as text (which orders ISO 8601 dates correctly)
+fn compare(a: &str, b: &str) -> std::cmp::Ordering {
+    match (a.parse::<f64>(), b.parse::<f64>()) {
+        (Ok(x), Ok(y)) => x.total_cmp(&y),
+        _ => a.cmp(b),
+    }
+}
+
+/// Add the subdirectories and FITS files of a directory to the lists
+///
+/// The type of each entry is its own, not that of a link's target, so
+/// symbolic links to directories are not followed
+fn list_dir(
+    dir: &std::path::Path,
+    dirs: &mut Vec<std::path::PathBuf>,
+    files: &mut Vec<std::path::PathBuf>,
+) -> std::io::Result<()> {
+    for entry in std::fs::read_dir(dir)?.flatten() {
+        let Ok(ftype) = entry.file_type() else {
+            continue;
+        };
+        let path = entry.path();
+        if ftype.is_dir() {
+            dirs.push(path);
+        } else if path.is_file()
+            && path
+                .extension()
+                .is_some_and(|ext| FITS_EXTENSIONS.iter().any(|e| ext.eq_ignore_ascii_case(e)))
+        {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
+
+impl FITSIndex {
+    /// Create an empty index
+    ///
+    /// # Arguments
+    ///
+    /// * `keywords` - Keywords whose values are recorded for each HDU
+    ///
+    pub fn new(keywords: &[&str]) -> Self {
+        FITSIndex {
+            keywords: keywords.iter().map(|k| k.to_uppercase()).collect(),
+            entries: Vec::new(),
+        }
+    }
+
+    /// Index the FITS files of a directory and its subdirectories
+    ///
+    /// Files ending in .fits, .fit, or .fts (in any case) are read,
+    /// headers only; see `add_file`.  Files are visited in path order.
+    /// Symbolic links to directories are not followed, so links cannot
+    /// make the scan loop, and subdirectories or files that cannot be
+    /// read are skipped
+    ///
+    /// # Arguments
+    ///
+    /// * `dir` - Directory to scan
+    /// * `keywords` - Keywords to record; `DEFAULT_INDEX_KEYWORDS` if empty
+    ///
+    /// # Returns
+    ///
+    /// The index, or an error if `dir` cannot be read
+    ///
+    pub fn scan(dir: &str, keywords: &[&str]) -> Result<Self, FITSError> {
+        let mut index = match keywords.is_empty() {
+            true => FITSIndex::new(&DEFAULT_INDEX_KEYWORDS),
+            false => FITSIndex::new(keywords),
+        };
+        let mut dirs = Vec::new();
+        let mut files = Vec::new();
+        list_dir(std::path::Path::new(dir), &mut dirs, &mut files)?;
+        while let Some(dir) = dirs.pop() {
+            if let Err(_e) = list_dir(&dir, &mut dirs, &mut files) {
+                #[cfg(feature = "tracing")]
+                tracing::warn!(dir = %dir.display(), error = %_e, "skipped directory");
+            }
+        }
+        files.sort();
+        for file in files {
+            let file = file.to_string_lossy();
+            if let Err(_e) = index.add_file(&file) {
+                #[cfg(feature = "tracing")]
+                tracing::warn!(%file, error = %_e, "skipped file");
+            }
+        }
+        Ok(index)
+    }
+
+    /// Add the HDUs of a file that have a data unit
+    ///
+    /// Only the headers are read, seeking past each data unit, and
+    /// problems in them are tolerated as by `FITS::from_file_lenient`;
+    /// reading stops at the first header that cannot be recovered.
+    /// A keyword missing from an extension is taken from the primary
+    /// header, where multi-extension files usually keep OBJECT and
+    /// DATE-OBS
+    ///
+    /// # Arguments
+    ///
+    /// * `file` - Path of the file
+    ///
+    /// # Returns
+    ///
+    /// The number of entries added, or an error if the file cannot be
+    /// read
+    ///
+    pub fn add_file(&mut self, file: &str) -> Result<usize, FITSError> {
+        let reader = std::io::BufReader::new(std::fs::File::open(file)?);
+        let mut stream = FITSStream::seekable(reader);
+        let mut headers = Vec::new();
+        let mut warnings = Vec::new();
+        while let Ok(Some(header)) = stream.read_header(Some(&mut warnings)) {
+            headers.push(header.clone());
+        }
+        let before = self.entries.len();
+        self.add_headers(file, &headers);
+        Ok(self.entries.len() - before)
+    }
+
+    /// Add the HDUs of an already-read file that have a data unit;
+    /// see `add_file`.  Index tables written by `save` are skipped
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - Path recorded for the file
+    /// * `fits` - The file; only the headers are used
+    ///
+    pub fn add_fits(&mut self, path: &str, fits: &FITS) {
+        let headers: Vec<Header> = fits.iter().map(|hdu| hdu.header.clone()).collect();
+        self.add_headers(path, &headers);
+    }
+
+    /// Add the HDUs with a data unit, given their headers in file order
+    fn add_headers(&mut self, path: &str, headers: &[Header]) {
+        let primary = headers.first();
+        for (i, header) in headers.iter().enumerate() {
+            // Data-less HDUs, and saved indexes in the scanned directory
+            if data_unit_size(header).unwrap_or(0) == 0
+                || header.extname().map(str::trim_end) == Some(INDEX_EXTNAME)
+            {
+                continue;
+            }
+            let values = self
+                .keywords
+                .iter()
+                .filter_map(|key| {
+                    let value = header
+                        .value(key)
+                        .or_else(|| primary.and_then(|p| p.value(key)))?;
+                    Some((key.clone(), value_text(value)?))
+                })
+                .collect();
+            self.entries.push(IndexEntry {
+                path: path.to_string(),
+                hdu: i,
+                extname: header.extname().map(|s| s.trim_end().to_string()),
+                values,
+                footprint: header_footprint(header),
+            });
+        }
+    }
+
+    /// Keywords recorded for each HDU
+    pub fn keywords(&self) -> &[String] {
+        &self.keywords
+    }
+
+    /// The indexed HDUs, in the order they were added
+    pub fn entries(&self) -> &[IndexEntry] {
+        &self.entries
+    }
+
+    /// Number of indexed HDUs
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// True if no HDUs are indexed
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// HDUs whose keyword has a value, compared ignoring case
+    ///
+    /// # Arguments
+    ///
+    /// * `keyword` - Indexed keyword, e.g. "OBJECT"
+    /// * `value` - Value to match, e.g. "M31"
+    ///
+    pub fn matching(&self, keyword: &str, value: &str) -> Vec<&IndexEntry> {
+        self.entries
+            .iter()
+            .filter(|e| {
+                e.value(keyword)
+                    .is_some_and(|v| v.eq_ignore_ascii_case(value))
+            })
+            .collect()
+    }
+
+    /// HDUs whose keyword value lies in a range
+    ///
+    /// Values are compared as numbers if both are numbers, else as
+    /// text, which orders ISO 8601 dates such as DATE-OBS correctly
+    ///
+    /// # Arguments
+    ///
+    /// * `keyword` - Indexed keyword, e.g. "DATE-OBS"
+    /// * `start` - Smallest value included
+    /// * `end` - First value excluded
+    ///
+    pub fn between(&self, keyword: &str, start: &str, end: &str) -> Vec<&IndexEntry> {
+        self.entries
+            .iter()
+            .filter(|e| {
+                e.value(keyword)
+                    .is_some_and(|v| compare(v, start).is_ge() && compare(v, end).is_lt())
+            })
+            .collect()
+    }
+
+    /// HDUs whose footprint covers a sky position; see
+    /// `IndexEntry::contains`
+    ///
+    /// # Arguments
+    ///
+    /// * `lon` - Longitude (e.g. right ascension) in degrees
+    /// * `lat` - Latitude (e.g. declination) in degrees
+    ///
+    pub fn containing(&self, lon: f64, lat: f64) -> Vec<&IndexEntry> {
+        self.entries
+            .iter()
+            .filter(|e| e.contains(lon, lat))
+            .collect()
+    }
+
+    /// The index as a binary table HDU (EXTNAME = 'FITSINDEX')
+    ///
+    /// Columns are PATH, HDU, EXTNAME, one string column per indexed
+    /// keyword, and FOOTPRNT, the eight corner coordinates (NaN
+    /// without a footprint).  Missing values are blank strings
+    pub fn to_hdu(&self) -> Result<HDU, FITSError> {
+        let mut columns: Vec<(String, Vec<String>)> = vec![
+            (
+                PATH_COLUMN.to_string(),
+                self.entries.iter().map(|e| e.path.clone()).collect(),
+            ),
+            (
+                EXTNAME_COLUMN.to_string(),
+                self.entries
+                    .iter()
+                    .map(|e| e.extname.clone().unwrap_or_default())
+                    .collect(),
+            ),
+        ];
+        for key in &self.keywords {
+            columns.push((
+                key.clone(),
+                self.entries
+                    .iter()
+                    .map(|e| e.value(key).unwrap_or_default().to_string())
+                    .collect(),
+            ));
+        }
+        let widths: Vec<usize> = columns
+            .iter()
+            .map(|(_, values)| values.iter().map(String::len).max().unwrap_or(0).max(1))
+            .collect();
+
+        let mut rawbytes = Vec::new();
+        for (row, entry) in self.entries.iter().enumerate() {
+            for ((name, values), width) in columns.iter().zip(&widths) {
+                if name == EXTNAME_COLUMN {
+                    rawbytes.extend((entry.hdu as i32).to_be_bytes());
+                }
+                let mut text = values[row].clone().into_bytes();
+                text.resize(*width, b' ');
+                rawbytes.extend(text);
+            }
+            let corners = entry.footprint.unwrap_or([(f64::NAN, f64::NAN); 4]);
+            for (lon, lat) in corners {
+                rawbytes.extend(lon.to_be_bytes());
+                rawbytes.extend(lat.to_be_bytes());
+            }
+        }
+
+        let keyword = |name: &str, value: KeywordValue| Keyword {
+            name: name.to_string(),
+            value,
+            ..Default::default()
+        };
+        let string = |s: &str| KeywordValue::String(s.to_string());
+        let mut fields = vec![];
+        for ((name, _), width) in columns.iter().zip(&widths) {
+            if name == EXTNAME_COLUMN {
+                fields.push((HDU_COLUMN.to_string(), "1J".to_string()));
+            }
+            fields.push((name.clone(), format!("{}A", width)));
+        }
+        fields.push((FOOTPRINT_COLUMN.to_string(), "8D".to_string()));
+        let rowbytes = widths.iter().sum::<usize>() + 4 + 64;
+        let mut keywords = vec![
+            keyword("XTENSION", string("BINTABLE")),
+            keyword("BITPIX", KeywordValue::Int(8)),
+            keyword("NAXIS", KeywordValue::Int(2)),
+            keyword("NAXIS1", KeywordValue::Int(rowbytes as i64)),
+            keyword("NAXIS2", KeywordValue::Int(self.entries.len() as i64)),
+            keyword("PCOUNT", KeywordValue::Int(0)),
+            keyword("GCOUNT", KeywordValue::Int(1)),
+            keyword("TFIELDS", KeywordValue::Int(fields.len() as i64)),
+        ];
+        for (n, (name, tform)) in fields.iter().enumerate() {
+            keywords.push(keyword(&format!("TTYPE{}", n + 1), string(name)));
+            keywords.push(keyword(&format!("TFORM{}", n + 1), string(tform)));
+        }
+        keywords.push(keyword(&format!("TUNIT{}", fields.len()), string("deg")));
+        keywords.push(keyword("EXTNAME", string(INDEX_EXTNAME)));
+        keywords.push(keyword("END", KeywordValue::None));
+
+        let header = Header::new(keywords);
+        let (data, _) = BinTable::from_bytes(&header, &rawbytes)?;
+        Ok(HDU {
+            header,
+            data,
+            location: None,
+        })
+    }
+
+    /// Read an index from a binary table written by `to_hdu`
+    ///
+    /// # Arguments
+    ///
+    /// * `hdu` - Binary table with PATH, HDU, EXTNAME, keyword, and
+    ///   FOOTPRNT columns
+    ///
+    /// # Returns
+    ///
+    /// The index, or an error if the HDU is not an index table
+    ///
+    pub fn from_hdu(hdu: &HDU) -> Result<Self, FITSError> {
+        let HDUData::BinTable(table) = &hdu.data else {
+            return Err(FITSError::HDUNotFound(format!(
+                "{} binary table",
+                INDEX_EXTNAME
+            )));
+        };
+        let names = table.column_names();
+        let fixed = [PATH_COLUMN, HDU_COLUMN, EXTNAME_COLUMN, FOOTPRINT_COLUMN];
+        let keywords: Vec<String> = names
+            .iter()
+            .filter(|n| !fixed.contains(&n.as_str()))
+            .cloned()
+            .collect();
+        let text = |name: &str| -> Result<Vec<String>, FITSError> {
+            Ok(table
+                .column::<String>(name)?
+                .into_iter()
+                .map(|s| s.unwrap_or_default().trim_end().to_string())
+                .collect())
+        };
+        let paths = text(PATH_COLUMN)?;
+        let hdus = table.column::<i64>(HDU_COLUMN)?;
+        let extnames = text(EXTNAME_COLUMN)?;
+        let values = keywords
+            .iter()
+            .map(|k| text(k))
+            .collect::<Result<Vec<_>, _>>()?;
+        let footprint_col = table.column_index(FOOTPRINT_COLUMN)?;
+
+        let mut entries = Vec::with_capacity(table.nrows());
+        for row in 0..table.nrows() {
+            let corners: Vec<f64> = match table.at_physical(row, footprint_col)? {
+                crate::BinTableValue::Array(values) => values
+                    .iter()
+                    .map(|v| v.as_f64().unwrap_or(f64::NAN))
+                    .collect(),
+                _ => Vec::new(),
+            };
+            let footprint = match corners.len() == 8 && corners.iter().all(|c| c.is_finite()) {
+                true => Some(std::array::from_fn(|i| {
+                    (corners[2 * i], corners[2 * i + 1])
+                })),
+                false => None,
+            };
+            entries.push(IndexEntry {
+                path: paths[row].clone(),
+                hdu: hdus[row].unwrap_or(0) as usize,
+                extname: Some(extnames[row].clone()).filter(|s| !s.is_empty()),
+                values: keywords
+                    .iter()
+                    .zip(&values)
+                    .filter(|(_, v)| !v[row].is_empty())
+                    .map(|(k, v)| (k.clone(), v[row].clone()))
+                    .collect(),
+                footprint,
+            });
+        }
+        Ok(FITSIndex { keywords, entries })
+    }
+
+    /// Write the index to a FITS file, as a binary table extension;
+    /// see `to_hdu`
+    ///
+    /// # Arguments
+    ///
+    /// * `file` - Path of the file to create (or overwrite)
+    ///
+    pub fn save(&self, file: &str) -> Result<(), FITSError> {
+        let mut fits = FITS::new();
+        fits.push_hdu(self.to_hdu()?);
+        fits.to_file(file)
+    }
+
+    /// Read an index written by `save`
+    ///
+    /// # Arguments
+    ///
+    /// * `file` - Path of the index file
+    ///
+    /// # Returns
+    ///
+    /// The index, or an error if the file has no index table
+    ///
+    pub fn load(file: &str) -> Result<Self, FITSError> {
+        let fits = FITS::from_file(file)?;
+        let hdu = fits
+            .iter()
+            .find(|hdu| hdu.extname().map(str::trim_end) == Some(INDEX_EXTNAME))
+            .ok_or_else(|| FITSError::HDUNotFound(format!("EXTNAME = '{}'", INDEX_EXTNAME)))?;
+        Self::from_hdu(hdu)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testutil::hdu_bytes;
+
+    fn image(cards: &[&str]) -> Vec<u8> {
+        hdu_bytes(cards, &[0, 1, 0, 2])
+    }
+
+    #[test]
+    fn test_index() {
+        let dir = std::env::temp_dir().join(format!("fits_index_{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("night2")).unwrap();
+        let observation = |object: &str, date: &str, crval1: f64| {
+            image(&[
+                "SIMPLE  =                    T",
+                "BITPIX  =                   16",
+                "NAXIS   =                    2",
+                "NAXIS1  =                    2",
+                "NAXIS2  =                    1",
+                &format!("OBJECT  = '{}'", object),
+                &format!("DATE-OBS= '{}'", date),
+                "FILTER  = 'R       '",
+                "CTYPE1  = 'RA---TAN'",
+                "CTYPE2  = 'DEC--TAN'",
+                &format!("CRVAL1  = {:20.1}", crval1),
+                "CRVAL2  =                 10.0",
+                "CRPIX1  =                  1.5",
+                "CRPIX2  =                  1.0",
+                "CDELT1  =                 -0.1",
+                "CDELT2  =                  0.1",
+                "END",
+            ])
+        };
+        std::fs::write(dir.join("a.fits"), observation("M31", "2024-01-05", 10.0)).unwrap();
+        std::fs::write(
+            dir.join("night2").join("b.FIT"),
+            observation("M33", "2024-02-05T01:00:00", 23.5),
+        )
+        .unwrap();
+        std::fs::write(dir.join("notes.txt"), b"not FITS").unwrap();
+        std::fs::write(dir.join("broken.fits"), b"not FITS either").unwrap();
+        // A link back up the tree is not followed
+        #[cfg(unix)]
+        std::os::unix::fs::symlink(&dir, dir.join("night2").join("loop")).unwrap();
+
+        let index = FITSIndex::scan(dir.to_str().unwrap(), &[]).unwrap();
+        assert_eq!(index.len(), 2);
+        assert_eq!(index.keywords(), &DEFAULT_INDEX_KEYWORDS);
+        assert_eq!(index.matching("OBJECT", "m31").len(), 1);
+        assert_eq!(index.matching("object", "M31").len(), 1);
+        assert_eq!(index.matching("FILTER", "R").len(), 2);
+        let january = index.between("DATE-OBS", "2024-01-01", "2024-02-01");
+        assert_eq!(january.len(), 1);
+        assert_eq!(january[0].value("OBJECT"), Some("M31"));
+        assert_eq!(january[0].value("date-obs"), Some("2024-01-05"));
+        let m33 = index.containing(23.5, 10.0);
+        assert_eq!(m33.len(), 1);
+        assert!(m33[0].path.ends_with("b.FIT"));
+        assert!(index.containing(23.5, 11.0).is_empty());
+        assert!(index.containing(203.5, -10.0).is_empty());
+
+        let file = dir.join("index.fits");
+        index.save(file.to_str().unwrap()).unwrap();
+        let loaded = FITSIndex::load(file.to_str().unwrap()).unwrap();
+        assert_eq!(loaded, index);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,161 @@
+use std::io::Read;
+
+use super::FITSReadOptions;
+use super::FITS;
+use crate::FITSError;
+
+/// A recoverable problem found while reading a file leniently
+///
+/// See `FITS::from_file_lenient`
+#[derive(Clone, Debug, PartialEq)]
+pub struct FITSWarning {
+    /// Position of the HDU in the file; 0 is the primary HDU
+    pub hdu: usize,
+    /// Description of the problem and how it was handled
+    pub message: String,
+}
+
+impl std::fmt::Display for FITSWarning {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "HDU {}: {}", self.hdu, self.message)
+    }
+}
+
+impl FITS {
+    /// Read a FITS file, tolerating common violations of the standard
+    ///
+    /// Unlike `from_file`, problems that can be worked around do not
+    /// fail the load: invalid header cards are kept as commentary text,
+    /// misplaced mandatory keywords are reordered, truncated or
+    /// uninterpretable data is kept as raw bytes, and missing fill is
+    /// ignored.  Reading stops at an HDU that cannot
+    /// be read at all.  Each problem is recorded in `warnings()`
+    ///
+    /// # Arguments
+    ///
+    /// * `file` - Path of the FITS file
+    ///
+    /// # Returns
+    ///
+    /// The FITS structure, or an error if the file cannot be read
+    ///
+    pub fn from_file_lenient(file: &str) -> Result<Self, FITSError> {
+        let mut rawbytes = Vec::new();
+        std::fs::File::open(file)?.read_to_end(&mut rawbytes)?;
+        Ok(Self::from_bytes_lenient(&rawbytes))
+    }
+
+    /// Parse FITS bytes leniently; see `from_file_lenient`
+    pub(crate) fn from_bytes_lenient(rawbytes: &[u8]) -> Self {
+        let options = FITSReadOptions {
+            lenient: true,
+            ..Default::default()
+        };
+        // Lenient reads record errors as warnings rather than failing
+        Self::from_bytes_with(rawbytes, &options).unwrap_or_default()
+    }
+
+    /// Problems recovered from when the file was read leniently
+    ///
+    /// Empty for files read with `from_file`
+    pub fn warnings(&self) -> &[FITSWarning] {
+        &self.warnings
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testutil::cards;
+    use crate::{HDUData, KeywordValue};
+
+    #[test]
+    fn test_lenient() {
+        // Bad keyword character and GCOUNT before PCOUNT
+        let mut raw = cards(&[
+            "SIMPLE  =                    T",
+            "BITPIX  =                    8",
+            "NAXIS   =                    1",
+            "NAXIS1  =                    1",
+            "EXTEND  =                    T",
+            "END",
+        ]);
+        raw.resize(2880, b' ');
+        raw.resize(5760, 0);
+        raw.extend(cards(&[
+            "XTENSION= 'IMAGE   '",
+            "BITPIX  =                    8",
+            "NAXIS   =                    1",
+            "NAXIS1  =                    4",
+            "GCOUNT  =                    1",
+            "PCOUNT  =                    0",
+            "date-obs= '2000-01-01'",
+            "END",
+        ]));
+        raw.resize(8640, b' ');
+        // Data with no fill
+        raw.extend([1, 2, 3, 4]);
+
+        let fits = FITS::from_bytes_lenient(&raw);
+        assert_eq!(fits.len(), 2);
+        let messages: Vec<String> = fits.warnings().iter().map(|w| w.to_string()).collect();
+        assert_eq!(messages.len(), 3, "{:?}", messages);
+        assert!(messages[0].starts_with("HDU 1: card 7:"));
+        assert_eq!(
+            messages[1],
+            "HDU 1: mandatory keywords out of order; reordered"
+        );
+        assert_eq!(
+            messages[2],
+            "HDU 1: data unit is not padded to a multiple of 2880 bytes"
+        );
+
+        let hdu = &fits[1];
+        assert_eq!(hdu.header[4].name, "PCOUNT");
+        assert_eq!(
+            hdu.value("date-obs"),
+            Some(&KeywordValue::CommentText("= '2000-01-01'".to_string()))
+        );
+        assert!(matches!(hdu.data, HDUData::Image(_)));
+
+        // A truncated data unit, here with a corrupt size, is kept as is
+        raw.truncate(5760);
+        raw.extend(cards(&[
+            "XTENSION= 'IMAGE   '",
+            "BITPIX  =                   64",
+            "NAXIS   =                    2",
+            "NAXIS1  =              1000000",
+            "NAXIS2  =              1000000",
+            "PCOUNT  =                    0",
+            "GCOUNT  =                    1",
+            "END",
+        ]));
+        raw.resize(8640, b' ');
+        raw.extend([1, 2, 3]);
+        let fits = FITS::from_bytes_lenient(&raw);
+        assert_eq!(fits.len(), 2);
+        let messages: Vec<String> = fits.warnings().iter().map(|w| w.to_string()).collect();
+        assert!(
+            messages[0].starts_with("HDU 1: data unit truncated"),
+            "{:?}",
+            messages
+        );
+        assert!(matches!(&fits[1].data, HDUData::Unknown { raw, .. } if raw == &[1, 2, 3]));
+
+        // A size that overflows is reported rather than panicking
+        raw.truncate(5760);
+        raw.extend(cards(&[
+            "XTENSION= 'IMAGE   '",
+            "BITPIX  =                   64",
+            "NAXIS   =                    2",
+            "NAXIS1  =           4294967296",
+            "NAXIS2  =           4294967296",
+            "PCOUNT  =                    0",
+            "GCOUNT  =                    1",
+            "END",
+        ]));
+        raw.resize(8640, b' ');
+        let fits = FITS::from_bytes_lenient(&raw);
+        assert!(!fits.warnings().is_empty());
+    }
+}
@@ -1,6 +1,35 @@
-use crate::HDU;
+mod diff;
+mod filename;
+mod group;
+mod index;
+mod lenient;
+mod options;
+#[cfg(feature = "rayon")]
+mod parallel;
+mod progress;
+#[cfg(feature = "remote")]
+mod remote;
+pub(crate) mod stream;
+pub(crate) mod structure;
+mod summary;
+pub(crate) mod update;
+mod verify;
+
+pub use diff::{DataDiff, DiffOptions, FITSDiff, HDUDiff, KeywordDiff};
+pub use filename::{FileSpec, HDUSelector};
+pub use group::{Group, GroupMember};
+pub use index::{FITSIndex, IndexEntry, DEFAULT_INDEX_KEYWORDS};
+pub use lenient::FITSWarning;
+pub use options::{FITSReadOptions, LoadPolicy};
+#[cfg(feature = "remote")]
+pub use remote::RemoteFITS;
+pub use stream::FITSStream;
+pub use summary::{FITSSummary, HDUSummary};
+pub use verify::{Severity, VerifyIssue, VerifyReport};
 
-use std::io::Read;
+use crate::FITSError;
+use crate::HDU;
+use std::io::Write;
 
 /// FITS File Structure
 ///
@@ -26,8 +55,8 @@ use std::io::Read;
 /// # Examples:
 ///
 /// * The following example reads a FITS file and prints the contents of the file:
-/// ```
-/// use fits_rs::FITS;
+/// ```no_run
+/// use fits::FITS;
 /// let fits = FITS::from_file("samp/WFPC2u5780205r_c0fx.fits");
 ///     match fits {
 ///         Ok(fits) => {
@@ -41,8 +70,8 @@ use std::io::Read;
 /// ```
 ///
 /// * The following example reads a FITS file and prints the contents of the first HDU:
-/// ```
-/// use fits_rs::FITS;
+/// ```no_run
+/// use fits::FITS;
 /// let fits = FITS::from_file("samp/WFPC2u5780205r_c0fx.fits");
 ///     match fits {
 ///         Ok(fits) => {
@@ -58,14 +87,16 @@ use std::io::Read;
 /// * The following example accesses an image in the first (primary) HDU
 /// * and some of the associated image fields
 ///
-/// ```
-/// use fits_rs::FITS;
+/// ```no_run
+/// use fits::{HDUData, FITS};
 /// let fits = FITS::from_file("samp/WFPC2u5780205r_c0fx.fits");
 ///     match fits {
 ///         Ok(fits) => {
-///         let HDUData::Image(im) = &fits[0].data;
+///         let HDUData::Image(im) = &fits[0].data else {
+///             panic!("Primary HDU is not an image");
+///         };
 ///         println!("Image shape: {:?}", im.axes);
-///         println!("Image pixel type: {:?}", im.bitpix);
+///         println!("Image pixel type: {:?}", im.pixeltype);
 ///         println!("Image WCS: {:?}", im.wcs);
 ///     }
 ///     Err(e) => {
@@ -77,6 +108,7 @@ use std::io::Read;
 #[derive(Clone, Debug)]
 pub struct FITS {
     hdus: Vec<HDU>,
+    warnings: Vec<FITSWarning>,
 }
 
 impl std::fmt::Display for FITS {
@@ -96,43 +128,234 @@ impl Default for FITS {
 
 impl FITS {
     pub fn new() -> Self {
-        FITS { hdus: Vec::new() }
+        FITS {
+            hdus: Vec::new(),
+            warnings: Vec::new(),
+        }
     }
 
     /// indexing and return a result to ensure valid
-    pub fn at(&self, index: usize) -> Result<&HDU, Box<dyn std::error::Error>> {
-        if index < self.hdus.len() {
-            Ok(&self.hdus[index])
-        } else {
-            Err(Box::new(std::io::Error::new(
-                std::io::ErrorKind::InvalidData,
-                "Index out of bounds",
-            )))
+    pub fn at(&self, index: usize) -> Result<&HDU, FITSError> {
+        self.hdus
+            .get(index)
+            .ok_or(FITSError::IndexOutOfRange(index, self.hdus.len()))
+    }
+
+    /// Mutable indexing, returning a result to ensure valid
+    pub fn at_mut(&mut self, index: usize) -> Result<&mut HDU, FITSError> {
+        let len = self.hdus.len();
+        self.hdus
+            .get_mut(index)
+            .ok_or(FITSError::IndexOutOfRange(index, len))
+    }
+
+    /// Number of HDUs
+    pub fn len(&self) -> usize {
+        self.hdus.len()
+    }
+
+    /// True if there are no HDUs
+    pub fn is_empty(&self) -> bool {
+        self.hdus.is_empty()
+    }
+
+    /// Iterator over the HDUs
+    pub fn iter(&self) -> std::slice::Iter<'_, HDU> {
+        self.hdus.iter()
+    }
+
+    /// Mutable iterator over the HDUs
+    pub fn iter_mut(&mut self) -> std::slice::IterMut<'_, HDU> {
+        self.hdus.iter_mut()
+    }
+
+    /// Append an HDU
+    ///
+    /// # Arguments
+    ///
+    /// * `hdu` - The HDU to add after the existing HDUs
+    ///
+    pub fn push_hdu(&mut self, hdu: HDU) {
+        self.hdus.push(hdu);
+    }
+
+    /// Insert an HDU, shifting later HDUs down
+    ///
+    /// # Arguments
+    ///
+    /// * `index` - Position of the new HDU; may equal the number of HDUs
+    /// * `hdu` - The HDU to insert
+    ///
+    pub fn insert_hdu(&mut self, index: usize, hdu: HDU) -> Result<(), FITSError> {
+        if index > self.hdus.len() {
+            return Err(FITSError::IndexOutOfRange(index, self.hdus.len()));
         }
+        self.hdus.insert(index, hdu);
+        Ok(())
     }
 
-    pub fn from_file(file: &str) -> Result<Self, Box<dyn std::error::Error>> {
-        let mut fits = FITS::new();
+    /// Remove an HDU
+    ///
+    /// # Arguments
+    ///
+    /// * `index` - Position of the HDU to remove
+    ///
+    /// # Returns
+    ///
+    /// The removed HDU
+    ///
+    pub fn remove_hdu(&mut self, index: usize) -> Result<HDU, FITSError> {
+        if index >= self.hdus.len() {
+            return Err(FITSError::IndexOutOfRange(index, self.hdus.len()));
+        }
+        Ok(self.hdus.remove(index))
+    }
+
+    /// Replace an HDU
+    ///
+    /// # Arguments
+    ///
+    /// * `index` - Position of the HDU to replace
+    /// * `hdu` - The new HDU
+    ///
+    /// # Returns
+    ///
+    /// The HDU previously at that position
+    ///
+    pub fn replace_hdu(&mut self, index: usize, hdu: HDU) -> Result<HDU, FITSError> {
+        let old = self.at_mut(index)?;
+        Ok(std::mem::replace(old, hdu))
+    }
 
-        // Read the file and parse the header
-        // Create a stream for the file
-        let mut file = std::fs::File::open(file)?;
-        let mut rawbytes = Vec::new();
-        file.read_to_end(&mut rawbytes)?;
+    /// Read a FITS file
+    ///
+    /// The name may select a single HDU, and columns of a binary
+    /// table, as in cfitsio: `data.fits[EVENTS]`, `data.fits[EVENTS,2]`
+    /// (EXTNAME and EXTVER), `data.fits[2]` (position, 0 is the primary
+    /// HDU), `data.fits[EVENTS][col TIME;PI]`.  Only the selected HDU is
+    /// decoded, and it is the only HDU in the result.  See `FileSpec`
+    ///
+    /// # Arguments
+    ///
+    /// * `file` - Path of the FITS file, optionally with selections
+    ///
+    /// # Returns
+    ///
+    /// The FITS structure, or an error if the file cannot be read or
+    /// nothing matches the selection
+    ///
+    pub fn from_file(file: &str) -> Result<Self, FITSError> {
+        Self::read_spec(file, true)
+    }
+
+    /// Read a FITS file, leaving image pixels as stored in the file
+    ///
+    /// Byte-order conversion of image data is skipped, so loading is
+    /// faster and pixels are only decoded when accessed, e.g. through
+    /// `Image::pixels_iter` or `Image::at`.  Useful when only a small
+    /// part of a large image is needed.  `Image::pixels` requires
+    /// calling `Image::convert_to_native` first
+    ///
+    /// # Arguments
+    ///
+    /// * `file` - Path of the FITS file, optionally with selections as
+    ///   for `from_file`
+    ///
+    /// # Returns
+    ///
+    /// The FITS structure, or an error if the file cannot be read
+    ///
+    pub fn from_file_deferred(file: &str) -> Result<Self, FITSError> {
+        Self::read_spec(file, false)
+    }
+
+    /// Read FITS data held in memory
+    ///
+    /// Does no file I/O, so it can be used where there is no file
+    /// system, e.g. in a browser with bytes from a `fetch`
+    ///
+    /// # Arguments
+    ///
+    /// * `rawbytes` - Contents of a FITS file
+    ///
+    /// # Returns
+    ///
+    /// The FITS structure, or an error if the data is not valid FITS
+    ///
+    pub fn from_bytes(rawbytes: &[u8]) -> Result<Self, FITSError> {
+        Self::parse(rawbytes, true)
+    }
+
+    fn parse(rawbytes: &[u8], native: bool) -> Result<Self, FITSError> {
+        // The span's duration is the parse time
+        #[cfg(feature = "tracing")]
+        let _span = tracing::debug_span!("parse", nbytes = rawbytes.len(), native).entered();
+        let mut fits = FITS::new();
 
         // The FITS file is a concatenation of
         // Header and Data units.  Read them in sequentially
-        let mut offset = 0;
-        while offset < rawbytes.len() {
-            println!("offset: {}", offset);
-            let (hdu, nbytes) = HDU::from_bytes(&rawbytes[offset..])?;
-            fits.hdus.push(hdu);
-            offset += nbytes;
+        #[cfg(not(feature = "rayon"))]
+        {
+            let mut offset = 0;
+            while offset < rawbytes.len() {
+                let (mut hdu, nbytes) = match native {
+                    true => HDU::from_bytes(&rawbytes[offset..])?,
+                    false => HDU::from_bytes_deferred(&rawbytes[offset..])?,
+                };
+                #[cfg(feature = "tracing")]
+                tracing::debug!(index = fits.hdus.len(), offset, nbytes, "read HDU");
+                hdu.location = hdu.location.map(|l| l.shifted(offset));
+                fits.hdus.push(hdu);
+                offset += nbytes;
+            }
+        }
+        // With the rayon feature, data units are decoded in parallel
+        #[cfg(feature = "rayon")]
+        {
+            fits.hdus = Self::hdus_parallel(rawbytes, native)?;
         }
         Ok(fits)
     }
 }
 
+impl FITS {
+    /// Serialize all HDUs as a FITS file
+    ///
+    /// The file always starts with a valid primary HDU: an IMAGE
+    /// extension in first position is written as the primary HDU, and
+    /// an empty primary HDU is added before any other extension.
+    /// EXTEND = T is set if there are extensions.  See `HDU::to_bytes`
+    ///
+    /// # Returns
+    ///
+    /// File bytes, or an error if an HDU lacks mandatory keywords
+    ///
+    pub fn to_bytes(&self) -> Result<Vec<u8>, FITSError> {
+        #[cfg(feature = "tracing")]
+        let _span = tracing::debug_span!("to_bytes", nhdus = self.len()).entered();
+        self.validate()?;
+        let (inserted, headers) = self.write_headers();
+        let mut bytes = inserted.map(|h| h.to_bytes()).unwrap_or_default();
+        for (hdu, header) in self.hdus.iter().zip(headers) {
+            bytes.extend(header.to_bytes());
+            bytes.extend(hdu.data_bytes());
+        }
+        Ok(bytes)
+    }
+
+    /// Write to a file
+    ///
+    /// # Arguments
+    ///
+    /// * `file` - Path of the file to create (or overwrite)
+    ///
+    pub fn to_file(&self, file: &str) -> Result<(), FITSError> {
+        let mut file = std::fs::File::create(file)?;
+        file.write_all(&self.to_bytes()?)?;
+        Ok(())
+    }
+}
+
 // indexing the fits structure just indexes the HDUs
 impl std::ops::Index<usize> for FITS {
     type Output = HDU;
@@ -142,9 +365,75 @@ impl std::ops::Index<usize> for FITS {
     }
 }
 
+impl std::ops::IndexMut<usize> for FITS {
+    fn index_mut(&mut self, index: usize) -> &mut Self::Output {
+        &mut self.hdus[index]
+    }
+}
+
+impl IntoIterator for FITS {
+    type Item = HDU;
+    type IntoIter = std::vec::IntoIter<HDU>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.hdus.into_iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a FITS {
+    type Item = &'a HDU;
+    type IntoIter = std::slice::Iter<'a, HDU>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.hdus.iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a mut FITS {
+    type Item = &'a mut HDU;
+    type IntoIter = std::slice::IterMut<'a, HDU>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.hdus.iter_mut()
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::testutil::hdu_bytes;
+
+    #[test]
+    fn test_from_bytes() {
+        let hdu = |cards: &[&str]| hdu_bytes(cards, &[1, 2, 3, 4]);
+        let mut raw = hdu(&[
+            "SIMPLE  =                    T",
+            "BITPIX  =                    8",
+            "NAXIS   =                    1",
+            "NAXIS1  =                    4",
+            "END",
+        ]);
+        raw.extend(hdu(&[
+            "XTENSION= 'IMAGE   '",
+            "BITPIX  =                    8",
+            "NAXIS   =                    1",
+            "NAXIS1  =                    4",
+            "PCOUNT  =                    0",
+            "GCOUNT  =                    1",
+            "EXTNAME = 'SCI     '",
+            "END",
+        ]));
+
+        let fits = FITS::from_bytes(&raw).unwrap();
+        assert_eq!(fits.len(), 2);
+        assert_eq!(fits.at(1).unwrap().extname(), Some("SCI"));
+        assert!(matches!(fits.at(1).unwrap().data, crate::HDUData::Image(_)));
+        let location = fits.at(1).unwrap().location.unwrap();
+        assert_eq!(location, crate::HDULocation::new(5760, 2880, 4));
+        assert_eq!(location.data_offset, 8640);
+        assert_eq!(location.end(), raw.len());
+        assert!(FITS::from_bytes(&raw[..100]).is_err());
+    }
 
     #[test]
     fn test_fits_from_file1() {
@@ -204,4 +493,47 @@ mod tests {
             }
         }
     }
+
+    #[test]
+    fn test_hdu_list() {
+        let hdu = |name: &str| HDU {
+            header: crate::Header::new(vec![crate::Keyword {
+                name: "EXTNAME".to_string(),
+                value: crate::KeywordValue::String(name.to_string()),
+                ..Default::default()
+            }]),
+            data: crate::HDUData::None,
+            location: None,
+        };
+        let names = |fits: &FITS| -> Vec<String> {
+            fits.iter()
+                .map(|h| h.extname().unwrap().to_string())
+                .collect()
+        };
+
+        let mut fits = FITS::new();
+        assert!(fits.is_empty());
+        fits.push_hdu(hdu("A"));
+        fits.push_hdu(hdu("C"));
+        fits.insert_hdu(1, hdu("B")).unwrap();
+        fits.insert_hdu(3, hdu("D")).unwrap();
+        assert!(fits.insert_hdu(5, hdu("X")).is_err());
+        assert_eq!(names(&fits), vec!["A", "B", "C", "D"]);
+        assert_eq!(fits.len(), 4);
+
+        let old = fits.replace_hdu(2, hdu("Z")).unwrap();
+        assert_eq!(old.extname(), Some("C"));
+        let removed = fits.remove_hdu(0).unwrap();
+        assert_eq!(removed.extname(), Some("A"));
+        assert!(matches!(
+            fits.remove_hdu(3),
+            Err(FITSError::IndexOutOfRange(3, 3))
+        ));
+        assert_eq!(names(&fits), vec!["B", "Z", "D"]);
+
+        for h in &mut fits {
+            h.header.set("EXTVER", crate::KeywordValue::Int(2));
+        }
+        assert!(fits.into_iter().all(|h| h.extver() == 2));
+    }
 }
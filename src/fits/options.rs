@@ -0,0 +1,340 @@
+//! Options controlling how files are read
+
+use std::io::Read;
+
+use super::FITSStream;
+use super::FITSWarning;
+use super::FITS;
+use crate::hdu::apply_duplicate_policy;
+use crate::DuplicatePolicy;
+use crate::FITSError;
+use crate::HDUData;
+use crate::HDU;
+
+/// How much of each HDU is loaded
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum LoadPolicy {
+    /// Decode data units, converting image pixels to native byte
+    /// order, as `FITS::from_file` does
+    #[default]
+    Native,
+    /// Decode data units, but leave image pixels as stored, as
+    /// `FITS::from_file_deferred` does
+    Deferred,
+    /// Read headers only; every HDU has `HDUData::None`
+    HeadersOnly,
+}
+
+/// Options for `FITS::open_with`
+///
+/// There is no option for decoding tile-compressed images, since the
+/// crate does not read them; they are kept as binary tables
+///
+/// # Example
+///
+/// ```no_run
+/// # use fits::{FITSReadOptions, LoadPolicy, FITS};
+/// # fn main() -> Result<(), fits::FITSError> {
+/// let options = FITSReadOptions {
+///     load_data: LoadPolicy::HeadersOnly,
+///     max_hdus: Some(2),
+///     ..Default::default()
+/// };
+/// let fits = FITS::open_with("big.fits", &options)?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct FITSReadOptions {
+    /// Recover from violations of the standard as
+    /// `FITS::from_file_lenient` does, recording them in `warnings()`
+    pub lenient: bool,
+    /// How much of each HDU is loaded
+    pub load_data: LoadPolicy,
+    /// Stop after this many HDUs; None reads them all
+    pub max_hdus: Option<usize>,
+    /// Handling of duplicated value keywords.  The policy is set on
+    /// each header read; `Error` fails (or, if lenient, stops reading)
+    /// at the first header with duplicates, and `Warn` records one
+    /// warning per duplicated name.  Data units are decoded using the
+    /// occurrence that lookups return, e.g. the last BSCALE for `Last`
+    pub duplicates: DuplicatePolicy,
+}
+
+impl FITS {
+    /// Read a FITS file as directed by options
+    ///
+    /// With the default options this is the same as `from_file`,
+    /// except that selections in the file name are not supported
+    ///
+    /// # Arguments
+    ///
+    /// * `file` - Path of the FITS file
+    /// * `options` - How to read the file
+    ///
+    /// # Returns
+    ///
+    /// The FITS structure, or an error if the file cannot be read.
+    /// Lenient reads only fail if the file cannot be opened.  With
+    /// `LoadPolicy::HeadersOnly` only the headers are read from the
+    /// file, seeking past each data unit
+    ///
+    pub fn open_with(file: &str, options: &FITSReadOptions) -> Result<Self, FITSError> {
+        if options.load_data == LoadPolicy::HeadersOnly {
+            let file = std::io::BufReader::new(std::fs::File::open(file)?);
+            return read_headers(FITSStream::seekable(file), options);
+        }
+        Self::from_bytes_with(&std::fs::read(file)?, options)
+    }
+
+    /// Read FITS data held in memory as directed by options; see
+    /// `open_with`
+    pub fn from_bytes_with(rawbytes: &[u8], options: &FITSReadOptions) -> Result<Self, FITSError> {
+        let native = options.load_data != LoadPolicy::Deferred;
+        if options.load_data == LoadPolicy::HeadersOnly {
+            return read_headers(
+                FITSStream::seekable(std::io::Cursor::new(rawbytes)),
+                options,
+            );
+        }
+        if !options.lenient
+            && options.max_hdus.is_none()
+            && options.duplicates == DuplicatePolicy::First
+        {
+            return Self::parse(rawbytes, native);
+        }
+
+        let mut offset = 0;
+        read_hdus(options, |warnings| {
+            if offset >= rawbytes.len() {
+                return Ok(None);
+            }
+            let rest = &rawbytes[offset..];
+            let (mut hdu, nbytes) =
+                HDU::from_bytes_with(rest, warnings, native, options.duplicates)?;
+            if nbytes == 0 {
+                return Ok(None);
+            }
+            hdu.location = hdu.location.map(|l| l.shifted(offset));
+            offset += nbytes;
+            Ok(Some(hdu))
+        })
+    }
+}
+
+/// Read only the headers of a file, skipping the data units
+fn read_headers<R: Read>(
+    mut stream: FITSStream<R>,
+    options: &FITSReadOptions,
+) -> Result<FITS, FITSError> {
+    read_hdus(options, |warnings| {
+        let Some(header) = stream.read_header(warnings)? else {
+            return Ok(None);
+        };
+        let mut header = header.clone();
+        apply_duplicate_policy(&mut header, options.duplicates)?;
+        Ok(Some(HDU {
+            header,
+            data: HDUData::None,
+            location: stream.location(),
+        }))
+    })
+}
+
+/// Read HDUs until `next` returns None, applying the options common
+/// to all load policies
+///
+/// # Arguments
+///
+/// * `options` - How to read the file
+/// * `next` - Reads the next HDU, recovering from problems and
+///   describing them in the warnings if given
+///
+fn read_hdus(
+    options: &FITSReadOptions,
+    mut next: impl FnMut(Option<&mut Vec<String>>) -> Result<Option<HDU>, FITSError>,
+) -> Result<FITS, FITSError> {
+    let mut fits = FITS::new();
+    while options.max_hdus.is_none_or(|max| fits.len() < max) {
+        let index = fits.len();
+        let mut messages = Vec::new();
+        let result = next(options.lenient.then_some(&mut messages));
+        if let (Ok(Some(hdu)), DuplicatePolicy::Warn) = (&result, options.duplicates) {
+            messages.extend(
+                hdu.header
+                    .duplicates()
+                    .iter()
+                    .map(|name| format!("duplicate keyword {}; the first value is used", name)),
+            );
+        }
+        fits.warnings
+            .extend(messages.into_iter().map(|message| FITSWarning {
+                hdu: index,
+                message,
+            }));
+        match result {
+            Ok(None) => break,
+            Ok(Some(hdu)) => fits.hdus.push(hdu),
+            Err(e) if options.lenient => {
+                fits.warnings.push(FITSWarning {
+                    hdu: index,
+                    message: format!("{}; rest of the file ignored", e),
+                });
+                break;
+            }
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(fits)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testutil::hdu_bytes;
+    use crate::HeaderError;
+
+    #[test]
+    fn test_read_options() {
+        let image = |name: &str| {
+            hdu_bytes(
+                &[
+                    "XTENSION= 'IMAGE   '",
+                    "BITPIX  =                   16",
+                    "NAXIS   =                    1",
+                    "NAXIS1  =                    2",
+                    "PCOUNT  =                    0",
+                    "GCOUNT  =                    1",
+                    &format!("EXTNAME = '{:<8}'", name),
+                    "END",
+                ],
+                &[0, 1, 0, 2],
+            )
+        };
+        let mut raw = hdu_bytes(
+            &[
+                "SIMPLE  =                    T",
+                "BITPIX  =                    8",
+                "NAXIS   =                    0",
+                "EXTEND  =                    T",
+                "END",
+            ],
+            &[],
+        );
+        raw.extend(image("A"));
+        raw.extend(image("B"));
+
+        let fits = FITS::from_bytes_with(&raw, &FITSReadOptions::default()).unwrap();
+        assert_eq!(fits.len(), 3);
+
+        let options = FITSReadOptions {
+            load_data: LoadPolicy::HeadersOnly,
+            max_hdus: Some(2),
+            ..Default::default()
+        };
+        let fits = FITS::from_bytes_with(&raw, &options).unwrap();
+        assert_eq!(fits.len(), 2);
+        assert_eq!(fits[1].extname(), Some("A"));
+        assert!(matches!(fits[1].data, HDUData::None));
+        let location = fits[1].location.unwrap();
+        assert_eq!((location.header_offset, location.data_size), (2880, 4));
+        assert_eq!(location.end(), 8640);
+
+        // Headers are streamed from a file, seeking past the data
+        let path = std::env::temp_dir().join(format!("fits_options_{}.fits", std::process::id()));
+        std::fs::write(&path, &raw).unwrap();
+        let options = FITSReadOptions {
+            load_data: LoadPolicy::HeadersOnly,
+            ..Default::default()
+        };
+        let fits = FITS::open_with(path.to_str().unwrap(), &options).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(fits.len(), 3);
+        assert_eq!(fits[2].extname(), Some("B"));
+        assert_eq!(fits[2].location.unwrap().header_offset, 8640);
+
+        let options = FITSReadOptions {
+            load_data: LoadPolicy::Deferred,
+            max_hdus: Some(2),
+            ..Default::default()
+        };
+        let fits = FITS::from_bytes_with(&raw, &options).unwrap();
+        let HDUData::Image(im) = &fits[1].data else {
+            panic!("expected image");
+        };
+        assert!(!im.native);
+
+        // Truncated final data unit
+        raw.truncate(raw.len() - 2880);
+        raw.extend(&image("B")[..2880 + 2]);
+        assert!(FITS::from_bytes_with(&raw, &FITSReadOptions::default()).is_err());
+        let options = FITSReadOptions {
+            lenient: true,
+            ..Default::default()
+        };
+        let fits = FITS::from_bytes_with(&raw, &options).unwrap();
+        assert_eq!(fits.len(), 3);
+        assert!(!fits.warnings().is_empty());
+
+        // Duplicated keyword in the primary header
+        let mut raw = hdu_bytes(
+            &[
+                "SIMPLE  =                    T",
+                "BITPIX  =                    8",
+                "NAXIS   =                    0",
+                "OBJECT  = 'M31     '",
+                "OBJECT  = 'M33     '",
+                "END",
+            ],
+            &[],
+        );
+        raw.extend(image("A"));
+        let fits = FITS::from_bytes_with(&raw, &FITSReadOptions::default()).unwrap();
+        assert_eq!(fits[0].header.get::<String>("OBJECT").unwrap(), "M31");
+        let options = FITSReadOptions {
+            duplicates: DuplicatePolicy::Last,
+            ..Default::default()
+        };
+        let fits = FITS::from_bytes_with(&raw, &options).unwrap();
+        assert_eq!(fits[0].header.get::<String>("OBJECT").unwrap(), "M33");
+        let options = FITSReadOptions {
+            duplicates: DuplicatePolicy::Warn,
+            ..Default::default()
+        };
+        let fits = FITS::from_bytes_with(&raw, &options).unwrap();
+        assert_eq!(fits.len(), 2);
+        assert_eq!(fits.warnings().len(), 1);
+        assert_eq!(fits.warnings()[0].hdu, 0);
+        let options = FITSReadOptions {
+            duplicates: DuplicatePolicy::Error,
+            ..Default::default()
+        };
+        assert!(matches!(
+            FITS::from_bytes_with(&raw, &options),
+            Err(FITSError::Header(HeaderError::DuplicateKeyword(name))) if name == "OBJECT"
+        ));
+
+        // Data is decoded with the occurrence that lookups return
+        let raw = hdu_bytes(
+            &[
+                "SIMPLE  =                    T",
+                "BITPIX  =                    8",
+                "NAXIS   =                    1",
+                "NAXIS1  =                    1",
+                "BSCALE  =                  2.0",
+                "BSCALE  =                  3.0",
+                "END",
+            ],
+            &[5],
+        );
+        let options = FITSReadOptions {
+            duplicates: DuplicatePolicy::Last,
+            ..Default::default()
+        };
+        let fits = FITS::from_bytes_with(&raw, &options).unwrap();
+        let HDUData::Image(im) = &fits[0].data else {
+            panic!("expected image");
+        };
+        assert_eq!(im.physical_at(&[0]).unwrap(), Some(15.0));
+    }
+}
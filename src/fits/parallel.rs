@@ -0,0 +1,101 @@
+//! Parallel decoding of HDUs, enabled by the `rayon` feature
+
+use rayon::prelude::*;
+
+use super::FITS;
+use crate::hdu::data_unit_size;
+use crate::FITSError;
+use crate::HDULocation;
+use crate::HDU;
+
+impl FITS {
+    /// Decode the HDUs of a file, with data units decoded in parallel
+    ///
+    /// Headers are read sequentially, since each one gives the size of
+    /// the data unit that follows and thus where the next HDU starts.
+    /// The data units (endian conversion, scaling, table parsing) are
+    /// then decoded on the rayon thread pool
+    ///
+    /// # Arguments
+    ///
+    /// * `rawbytes` - Bytes of the whole file
+    /// * `native` - Convert image pixels to native byte order
+    ///
+    /// # Returns
+    ///
+    /// The HDUs in file order, or the first error encountered
+    ///
+    pub(super) fn hdus_parallel(rawbytes: &[u8], native: bool) -> Result<Vec<HDU>, FITSError> {
+        let mut units = Vec::new();
+        let mut offset = 0;
+        while offset < rawbytes.len() {
+            let (header, hbytes) = HDU::header_from_bytes(&rawbytes[offset..])?;
+            let start = offset + hbytes;
+            let size = data_unit_size(&header)?;
+            #[cfg(feature = "tracing")]
+            tracing::debug!(
+                index = units.len(),
+                offset,
+                nbytes = size,
+                "read HDU header"
+            );
+            units.push((header, HDULocation::new(offset, hbytes, size)));
+            offset = start + size.div_ceil(2880) * 2880;
+        }
+        units
+            .into_par_iter()
+            .map(|(header, location)| {
+                let mut hdu = HDU::from_header(header, &rawbytes[location.data_offset..], native)?;
+                hdu.location = Some(location);
+                Ok(hdu)
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testutil::hdu_bytes;
+
+    #[test]
+    fn test_parallel() {
+        let mut raw = hdu_bytes(
+            &[
+                "SIMPLE  =                    T",
+                "BITPIX  =                   16",
+                "NAXIS   =                    1",
+                "NAXIS1  =                    2",
+                "EXTEND  =                    T",
+                "END",
+            ],
+            &[0, 1, 0, 2],
+        );
+        for name in ["'SCI'", "'ERR'", "'DQ'"] {
+            raw.extend(hdu_bytes(
+                &[
+                    "XTENSION= 'IMAGE   '",
+                    "BITPIX  =                  -32",
+                    "NAXIS   =                    2",
+                    "NAXIS1  =                    2",
+                    "NAXIS2  =                    1",
+                    "PCOUNT  =                    0",
+                    "GCOUNT  =                    1",
+                    &format!("EXTNAME = {}", name),
+                    "END",
+                ],
+                &[0x3f, 0x80, 0, 0, 0x40, 0, 0, 0],
+            ));
+        }
+
+        let hdus = FITS::hdus_parallel(&raw, true).unwrap();
+        assert_eq!(hdus.len(), 4);
+        let mut offset = 0;
+        for hdu in &hdus {
+            let (expected, nbytes) = HDU::from_bytes(&raw[offset..]).unwrap();
+            assert_eq!(hdu.to_bytes(), expected.to_bytes());
+            offset += nbytes;
+        }
+        assert_eq!(hdus[3].extname(), Some("DQ"));
+    }
+}
@@ -0,0 +1,228 @@
+//! Progress reporting while reading and writing large files
+
+use std::io::{BufReader, BufWriter, Read, Write};
+
+use super::FITSStream;
+use super::FITS;
+use crate::FITSError;
+
+/// Bytes transferred between progress reports
+const PROGRESS_STEP: u64 = 1 << 20;
+
+/// Counter of bytes transferred, reporting every `PROGRESS_STEP` bytes
+/// and on completion
+struct Progress<F: FnMut(u64, u64)> {
+    done: u64,
+    total: u64,
+    reported: u64,
+    callback: F,
+}
+
+impl<F: FnMut(u64, u64)> Progress<F> {
+    fn new(total: u64, callback: F) -> Self {
+        Progress {
+            done: 0,
+            total,
+            reported: 0,
+            callback,
+        }
+    }
+
+    fn advance(&mut self, nbytes: usize) {
+        self.done += nbytes as u64;
+        if self.done - self.reported >= PROGRESS_STEP || self.done >= self.total {
+            self.reported = self.done;
+            (self.callback)(self.done, self.total);
+        }
+    }
+}
+
+/// Reader or writer counting the bytes passing through it
+///
+/// Each call transfers at most `PROGRESS_STEP` bytes, so that reports
+/// are made during large data units
+struct Counted<T, F: FnMut(u64, u64)> {
+    inner: T,
+    progress: Progress<F>,
+}
+
+impl<R: Read, F: FnMut(u64, u64)> Read for Counted<R, F> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let len = buf.len().min(PROGRESS_STEP as usize);
+        let n = self.inner.read(&mut buf[..len])?;
+        if n > 0 {
+            self.progress.advance(n);
+        }
+        Ok(n)
+    }
+}
+
+impl<W: Write, F: FnMut(u64, u64)> Write for Counted<W, F> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let len = buf.len().min(PROGRESS_STEP as usize);
+        let n = self.inner.write(&buf[..len])?;
+        self.progress.advance(n);
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+impl FITS {
+    /// Read a FITS file, reporting progress as it is loaded
+    ///
+    /// The file is read and decoded one HDU at a time, so the reports
+    /// track the whole load.  The HDUs, including their `location`, are
+    /// those `from_file` returns.  Selections in the file name (see
+    /// `from_file`) are not supported
+    ///
+    /// # Arguments
+    ///
+    /// * `file` - Path of the FITS file
+    /// * `progress` - Called with the bytes read so far and the file
+    ///   size, every megabyte and once the whole file is read
+    ///
+    /// # Returns
+    ///
+    /// The FITS structure, or an error if the file cannot be read
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use fits::FITS;
+    /// # fn main() -> Result<(), fits::FITSError> {
+    /// let fits = FITS::from_file_with_progress("big.fits", |done, total| {
+    ///     eprint!("\r{:3}%", 100 * done / total.max(1));
+    /// })?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    pub fn from_file_with_progress(
+        file: &str,
+        progress: impl FnMut(u64, u64),
+    ) -> Result<Self, FITSError> {
+        let file = std::fs::File::open(file)?;
+        let total = file.metadata()?.len();
+        let reader = BufReader::new(Counted {
+            inner: file,
+            progress: Progress::new(total, progress),
+        });
+        let mut fits = FITS::new();
+        for hdu in FITSStream::new(reader) {
+            fits.push_hdu(hdu?);
+        }
+        Ok(fits)
+    }
+
+    /// Write to a file, reporting progress
+    ///
+    /// The bytes written are those of `to_bytes`, but each HDU is
+    /// written as it is serialized rather than the whole file first
+    ///
+    /// # Arguments
+    ///
+    /// * `file` - Path of the file to create (or overwrite)
+    /// * `progress` - Called with the bytes written so far and the
+    ///   total, every megabyte and once the whole file is written
+    ///
+    pub fn to_file_with_progress(
+        &self,
+        file: &str,
+        progress: impl FnMut(u64, u64),
+    ) -> Result<(), FITSError> {
+        self.validate()?;
+        let (inserted, headers) = self.write_headers();
+        let header_bytes: Vec<Vec<u8>> = inserted
+            .iter()
+            .chain(headers.iter().map(|h| h.as_ref()))
+            .map(|h| h.to_bytes())
+            .collect();
+        // Validated headers give the size of each data unit
+        let total = header_bytes.iter().map(|h| h.len()).sum::<usize>()
+            + headers
+                .iter()
+                .map(|h| h.data_size().unwrap_or(0).div_ceil(2880) * 2880)
+                .sum::<usize>();
+
+        let mut writer = BufWriter::new(Counted {
+            inner: std::fs::File::create(file)?,
+            progress: Progress::new(total as u64, progress),
+        });
+        let mut header_bytes = header_bytes.into_iter();
+        if inserted.is_some() {
+            writer.write_all(&header_bytes.next().unwrap_or_default())?;
+        }
+        for (hdu, header) in self.iter().zip(header_bytes) {
+            writer.write_all(&header)?;
+            writer.write_all(&hdu.data_bytes())?;
+        }
+        writer.flush()?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testutil::{header, image};
+    use crate::Bitpix;
+    use crate::HDUData;
+    use crate::HDU;
+
+    #[test]
+    fn test_progress() {
+        let path = std::env::temp_dir().join(format!("fits_progress_{}.fits", std::process::id()));
+        let path = path.to_str().unwrap();
+
+        // A primary HDU, then a 1024 x 1024 image of 2 megabytes
+        let header = header(&[
+            "XTENSION= 'IMAGE   '",
+            "BITPIX  =                   16",
+            "NAXIS   =                    2",
+            "NAXIS1  =                 1024",
+            "NAXIS2  =                 1024",
+            "PCOUNT  =                    0",
+            "GCOUNT  =                    1",
+            "END",
+        ]);
+        let image = HDU {
+            header,
+            data: HDUData::Image(Box::new(image(
+                Bitpix::Int16,
+                &[1024, 1024],
+                &vec![0i16; 1024 * 1024],
+            ))),
+            location: None,
+        };
+        let mut fits = FITS::new();
+        fits.push_hdu(HDU::empty_primary());
+        fits.push_hdu(image);
+
+        let mut writes = Vec::new();
+        fits.to_file_with_progress(path, |done, total| writes.push((done, total)))
+            .unwrap();
+        let size = std::fs::metadata(path).unwrap().len();
+        assert_eq!(writes.last(), Some(&(size, size)));
+        assert!(writes.len() >= 3);
+
+        let mut reads = Vec::new();
+        let read =
+            FITS::from_file_with_progress(path, |done, total| reads.push((done, total))).unwrap();
+        // The same HDUs, with the same locations, as a plain read
+        let plain = FITS::from_file(path).unwrap();
+        std::fs::remove_file(path).unwrap();
+        assert_eq!(read.len(), plain.len());
+        for (a, b) in read.iter().zip(plain.iter()) {
+            assert!(a.location.is_some());
+            assert_eq!(a.location, b.location);
+            assert_eq!(a.to_bytes(), b.to_bytes());
+        }
+        assert_eq!(read.len(), 2);
+        assert!(matches!(&read[1].data, HDUData::Image(im) if im.axes == vec![1024, 1024]));
+        assert_eq!(reads.last(), Some(&(size, size)));
+        assert!(reads.windows(2).all(|w| w[0].0 < w[1].0));
+    }
+}
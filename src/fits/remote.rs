@@ -0,0 +1,334 @@
+//! Lazy access to FITS files on HTTP servers, enabled by the `remote` feature
+
+use std::io::Read;
+use std::ops::Range;
+
+use super::stream::rows_header;
+use super::FITS;
+use crate::hdu::{data_unit_size, is_end_card};
+use crate::FITSError;
+use crate::HDUData;
+use crate::Header;
+use crate::HeaderError;
+use crate::HDU;
+
+/// Header of a remote HDU and the location of its data unit
+#[derive(Clone, Debug)]
+struct RemoteHDU {
+    header: Header,
+    /// Offset of the data unit in the file
+    offset: u64,
+    /// Size of the data unit in bytes, excluding fill
+    size: usize,
+}
+
+/// A FITS file on an HTTP server
+///
+/// Only the headers are fetched when the file is opened; data units
+/// are fetched with HTTP range requests when asked for, and are not
+/// cached.  The server must support range requests.  See
+/// `FITS::open_url`
+#[derive(Debug)]
+pub struct RemoteFITS {
+    url: String,
+    agent: ureq::Agent,
+    hdus: Vec<RemoteHDU>,
+}
+
+/// Convert an HTTP client error
+fn http_error(e: ureq::Error) -> FITSError {
+    FITSError::Http(e.to_string())
+}
+
+impl FITS {
+    /// Open a FITS file on an HTTP server, fetching only the headers
+    ///
+    /// Header blocks are read with range requests, skipping over the
+    /// data units, so opening a large file transfers little data.  Use
+    /// the returned `RemoteFITS` to fetch HDUs or rows of a data unit
+    ///
+    /// # Arguments
+    ///
+    /// * `url` - URL of the file
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use fits::FITS;
+    /// # fn main() -> Result<(), fits::FITSError> {
+    /// let remote = FITS::open_url("https://example.org/survey/tile42.fits")?;
+    /// println!("{} rows", remote.header(1)?.get::<i64>("NAXIS2")?);
+    /// // Fetch 100 lines of a large image
+    /// let rows = remote.read_rows(1, 2000..2100)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    pub fn open_url(url: &str) -> Result<RemoteFITS, FITSError> {
+        let mut remote = RemoteFITS {
+            url: url.to_string(),
+            agent: ureq::Agent::new(),
+            hdus: Vec::new(),
+        };
+        let mut offset = 0u64;
+        let mut total = None;
+        while total.is_none_or(|t| offset < t) {
+            // Header blocks up to the one holding END
+            let mut bytes = Vec::new();
+            loop {
+                let (block, size) = remote.fetch(offset + bytes.len() as u64, 2880)?;
+                total = Some(size);
+                bytes.extend(block);
+                if bytes[bytes.len() - 2880..]
+                    .chunks_exact(80)
+                    .any(is_end_card)
+                {
+                    break;
+                }
+                if offset + bytes.len() as u64 >= size {
+                    return Err(FITSError::from(HeaderError::GenericError(
+                        "header ends before END card".to_string(),
+                    )));
+                }
+            }
+            let (header, hbytes) = HDU::header_from_bytes(&bytes)?;
+            let size = data_unit_size(&header)?;
+            let data = offset + hbytes as u64;
+            remote.hdus.push(RemoteHDU {
+                header,
+                offset: data,
+                size,
+            });
+            offset = data + (size.div_ceil(2880) * 2880) as u64;
+        }
+        Ok(remote)
+    }
+}
+
+impl RemoteFITS {
+    /// Number of HDUs
+    pub fn len(&self) -> usize {
+        self.hdus.len()
+    }
+
+    /// True if there are no HDUs
+    pub fn is_empty(&self) -> bool {
+        self.hdus.is_empty()
+    }
+
+    /// URL of the file
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    /// Header of an HDU; no data is fetched
+    ///
+    /// # Arguments
+    ///
+    /// * `index` - Position of the HDU; 0 is the primary HDU
+    ///
+    pub fn header(&self, index: usize) -> Result<&Header, FITSError> {
+        Ok(&self.at(index)?.header)
+    }
+
+    /// Fetch a complete HDU
+    ///
+    /// # Arguments
+    ///
+    /// * `index` - Position of the HDU; 0 is the primary HDU
+    ///
+    pub fn hdu(&self, index: usize) -> Result<HDU, FITSError> {
+        let hdu = self.at(index)?;
+        if hdu.header.naxes().is_some_and(|axes| axes.is_empty()) {
+            return Ok(HDU {
+                header: hdu.header.clone(),
+                data: HDUData::None,
+                location: None,
+            });
+        }
+        let (bytes, _) = self.fetch(hdu.offset, hdu.size)?;
+        HDU::from_header(hdu.header.clone(), &bytes, true)
+    }
+
+    /// Fetch a range of rows of a data unit
+    ///
+    /// A row is one step along the last axis: a table row, or one plane
+    /// of an image (a line of a 2-D image).  Only the bytes of those rows
+    /// are transferred, so a band of a large image can be cut out
+    /// cheaply.  Variable-length array columns are not available
+    ///
+    /// # Arguments
+    ///
+    /// * `index` - Position of the HDU; 0 is the primary HDU
+    /// * `rows` - Rows to fetch
+    ///
+    /// # Returns
+    ///
+    /// The rows, as data of the same type as the HDU
+    ///
+    pub fn read_rows(&self, index: usize, rows: Range<usize>) -> Result<HDUData, FITSError> {
+        let hdu = self.at(index)?;
+        let nrows = hdu
+            .header
+            .naxes()
+            .and_then(|axes| axes.last().copied())
+            .unwrap_or(0);
+        if rows.start > rows.end || rows.end > nrows {
+            return Err(FITSError::from(HeaderError::GenericError(format!(
+                "rows {}..{} out of range (HDU has {} rows)",
+                rows.start, rows.end, nrows
+            ))));
+        }
+        let (part, rowbytes) = rows_header(&hdu.header, rows.len())?;
+        let (bytes, _) = self.fetch(
+            hdu.offset + (rows.start * rowbytes) as u64,
+            rows.len() * rowbytes,
+        )?;
+        Ok(HDU::from_header(part, &bytes, true)?.data)
+    }
+
+    fn at(&self, index: usize) -> Result<&RemoteHDU, FITSError> {
+        self.hdus
+            .get(index)
+            .ok_or(FITSError::IndexOutOfRange(index, self.hdus.len()))
+    }
+
+    /// Fetch bytes with a range request
+    ///
+    /// # Returns
+    ///
+    /// The bytes and the total size of the file
+    ///
+    fn fetch(&self, start: u64, len: usize) -> Result<(Vec<u8>, u64), FITSError> {
+        if len == 0 {
+            return Ok((Vec::new(), 0));
+        }
+        let response = self
+            .agent
+            .get(&self.url)
+            .set(
+                "Range",
+                &format!("bytes={}-{}", start, start + len as u64 - 1),
+            )
+            .call()
+            .map_err(http_error)?;
+        if response.status() != 206 {
+            return Err(FITSError::Http(format!(
+                "server does not support range requests (status {})",
+                response.status()
+            )));
+        }
+        // Content-Range: bytes start-end/total
+        let total = response
+            .header("Content-Range")
+            .and_then(|r| r.rsplit('/').next())
+            .and_then(|t| t.trim().parse::<u64>().ok())
+            .ok_or(FITSError::Http("missing Content-Range".to_string()))?;
+        let mut bytes = Vec::with_capacity(len);
+        response
+            .into_reader()
+            .take(len as u64)
+            .read_to_end(&mut bytes)?;
+        if bytes.len() != len {
+            return Err(FITSError::Http(format!(
+                "expected {} bytes at offset {}, received {}",
+                len,
+                start,
+                bytes.len()
+            )));
+        }
+        Ok((bytes, total))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testutil::hdu_bytes;
+    use std::io::{BufRead, BufReader, Write};
+    use std::net::TcpListener;
+
+    /// Serve `body` over HTTP on a local port, honoring Range headers
+    fn serve(body: Vec<u8>) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/test.fits", listener.local_addr().unwrap());
+        std::thread::spawn(move || {
+            for stream in listener.incoming() {
+                let mut stream = stream.unwrap();
+                let mut range = None;
+                let mut reader = BufReader::new(stream.try_clone().unwrap());
+                loop {
+                    let mut line = String::new();
+                    reader.read_line(&mut line).unwrap();
+                    if line.trim().is_empty() {
+                        break;
+                    }
+                    if let Some(r) = line.to_ascii_lowercase().strip_prefix("range: bytes=") {
+                        let (a, b) = r.trim().split_once('-').unwrap();
+                        range = Some((a.parse::<usize>().unwrap(), b.parse::<usize>().unwrap()));
+                    }
+                }
+                let (a, b) = range.unwrap();
+                let b = b.min(body.len() - 1);
+                write!(
+                    stream,
+                    "HTTP/1.1 206 Partial Content\r\nContent-Length: {}\r\n\
+                     Content-Range: bytes {}-{}/{}\r\nConnection: close\r\n\r\n",
+                    b + 1 - a,
+                    a,
+                    b,
+                    body.len()
+                )
+                .unwrap();
+                stream.write_all(&body[a..=b]).unwrap();
+            }
+        });
+        url
+    }
+
+    #[test]
+    fn test_open_url() {
+        let mut raw = hdu_bytes(
+            &[
+                "SIMPLE  =                    T",
+                "BITPIX  =                    8",
+                "NAXIS   =                    0",
+                "EXTEND  =                    T",
+                "END",
+            ],
+            &[],
+        );
+        raw.extend(hdu_bytes(
+            &[
+                "XTENSION= 'IMAGE   '",
+                "BITPIX  =                   16",
+                "NAXIS   =                    2",
+                "NAXIS1  =                    2",
+                "NAXIS2  =                    3",
+                "PCOUNT  =                    0",
+                "GCOUNT  =                    1",
+                "EXTNAME = 'SCI     '",
+                "END",
+            ],
+            &[0, 1, 0, 2, 0, 3, 0, 4, 0, 5, 0, 6],
+        ));
+
+        let remote = FITS::open_url(&serve(raw)).unwrap();
+        assert_eq!(remote.len(), 2);
+        assert_eq!(remote.header(1).unwrap().extname(), Some("SCI"));
+
+        let hdu = remote.hdu(1).unwrap();
+        let HDUData::Image(im) = hdu.data else {
+            panic!("expected image");
+        };
+        assert_eq!(im.pixels::<i16>().unwrap(), &[1, 2, 3, 4, 5, 6]);
+
+        let HDUData::Image(rows) = remote.read_rows(1, 1..3).unwrap() else {
+            panic!("expected image");
+        };
+        assert_eq!(rows.axes, vec![2, 2]);
+        assert_eq!(rows.pixels::<i16>().unwrap(), &[3, 4, 5, 6]);
+        assert!(remote.read_rows(1, 2..4).is_err());
+        assert!(remote.hdu(2).is_err());
+    }
+}
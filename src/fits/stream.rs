@@ -0,0 +1,411 @@
+//! Reading FITS data one HDU at a time from any byte source
+
+use std::io::{Read, Seek, SeekFrom};
+
+use crate::hdu::{data_unit_size, is_end_card, read_header};
+use crate::FITSError;
+use crate::HDUData;
+use crate::HDULocation;
+use crate::Header;
+use crate::HeaderError;
+use crate::KeywordValue;
+use crate::HDU;
+
+/// Header describing a run of rows of a data unit
+///
+/// A row is one step along the last axis.  The last axis is shortened
+/// to `nrows` and PCOUNT is zeroed, since the heap is not included
+///
+/// # Returns
+///
+/// The header and the size of one row in bytes
+///
+pub(crate) fn rows_header(header: &Header, nrows: usize) -> Result<(Header, usize), FITSError> {
+    let axes = header
+        .naxes()
+        .ok_or(HeaderError::GenericError("invalid NAXIS".to_string()))?;
+    let bitpix = header.get::<i64>("BITPIX")?;
+    let rowbytes = axes[..axes.len().saturating_sub(1)]
+        .iter()
+        .try_fold(bitpix.unsigned_abs() as usize / 8, |n, &len| {
+            n.checked_mul(len)
+        })
+        .ok_or_else(|| HeaderError::GenericError(format!("axes {:?} are too large", axes)))?;
+
+    let mut part = header.clone();
+    part.set(
+        &format!("NAXIS{}", axes.len()),
+        KeywordValue::Int(nrows as i64),
+    );
+    if part.find("PCOUNT").is_some() {
+        part.set("PCOUNT", KeywordValue::Int(0));
+    }
+    Ok((part, rowbytes))
+}
+
+/// Streaming reader yielding HDUs one at a time
+///
+/// Only the HDU being read is held in memory, so files larger than RAM,
+/// or data arriving over a socket, can be processed.  Used as an
+/// iterator it yields complete HDUs.  For finer control, read each
+/// header with `next_header` and pull its data in pieces with
+/// `read_rows`; unread data is skipped when the next header is read,
+/// by seeking if the stream was created with `seekable`
+///
+/// # Example
+///
+/// ```no_run
+/// # use fits::FITSStream;
+/// # fn main() -> Result<(), fits::FITSError> {
+/// let file = std::fs::File::open("events.fits")?;
+/// let mut stream = FITSStream::new(std::io::BufReader::new(file));
+/// while let Some(header) = stream.next_header()? {
+///     while let Some(rows) = stream.read_rows(10000)? {
+///         // process a block of table rows or image planes
+///     }
+/// }
+/// # Ok(())
+/// # }
+/// ```
+///
+pub struct FITSStream<R: Read> {
+    reader: R,
+    /// Header of the current HDU
+    header: Option<Header>,
+    /// Bytes of the current data unit not yet read, excluding fill
+    data_left: usize,
+    /// Fill bytes following the current data unit
+    fill: usize,
+    /// Rows (steps along the last axis) not yet read by `read_rows`
+    rows_left: usize,
+    /// Bytes consumed from the start of the stream
+    position: usize,
+    /// Position of the current HDU
+    location: Option<HDULocation>,
+    /// Skip bytes without reading them, for seekable sources
+    seek: Option<fn(&mut R, usize) -> std::io::Result<()>>,
+}
+
+impl<R: Read> FITSStream<R> {
+    /// Create a stream reading from the start of a FITS file
+    ///
+    /// # Arguments
+    ///
+    /// * `reader` - Source of the file bytes; wrap unbuffered sources
+    ///   in a `BufReader`
+    ///
+    pub fn new(reader: R) -> Self {
+        FITSStream {
+            reader,
+            header: None,
+            data_left: 0,
+            fill: 0,
+            rows_left: 0,
+            position: 0,
+            location: None,
+            seek: None,
+        }
+    }
+
+    /// Header of the HDU currently being read
+    pub fn header(&self) -> Option<&Header> {
+        self.header.as_ref()
+    }
+
+    /// Position of the HDU currently being read, with offsets from the
+    /// start of the stream
+    pub fn location(&self) -> Option<HDULocation> {
+        self.location
+    }
+
+    /// Read the header of the next HDU
+    ///
+    /// Any unread data of the current HDU is skipped
+    ///
+    /// # Returns
+    ///
+    /// The header, or None at the end of the stream
+    ///
+    pub fn next_header(&mut self) -> Result<Option<&Header>, FITSError> {
+        self.read_header(None)
+    }
+
+    /// Read the header of the next HDU, recovering from invalid cards
+    /// if `warnings` is given; see `read_header`
+    pub(crate) fn read_header(
+        &mut self,
+        warnings: Option<&mut Vec<String>>,
+    ) -> Result<Option<&Header>, FITSError> {
+        self.skip(self.data_left + self.fill)?;
+        self.header = None;
+        self.location = None;
+        self.data_left = 0;
+        self.fill = 0;
+        self.rows_left = 0;
+
+        let mut bytes = Vec::new();
+        loop {
+            let mut block = [0u8; 2880];
+            if !self.read_block(&mut block)? {
+                if bytes.is_empty() {
+                    return Ok(None);
+                }
+                return Err(FITSError::from(HeaderError::GenericError(
+                    "header ends before END card".to_string(),
+                )));
+            }
+            bytes.extend_from_slice(&block);
+            if block.chunks_exact(80).any(is_end_card) {
+                break;
+            }
+        }
+        let start = self.position - bytes.len();
+        let (header, hbytes) = read_header(&bytes, warnings)?;
+        self.data_left = data_unit_size(&header)?;
+        self.location = Some(HDULocation::new(start, hbytes, self.data_left));
+        self.fill = self.data_left.div_ceil(2880) * 2880 - self.data_left;
+        self.rows_left = header
+            .naxes()
+            .and_then(|axes| axes.last().copied())
+            .unwrap_or(0);
+        Ok(Some(self.header.insert(header)))
+    }
+
+    /// Read the next rows of the current data unit
+    ///
+    /// A row is one step along the last axis: a table row, or one plane
+    /// of an image (a line of a 2-D image).  The rows are returned as
+    /// data of the same type as the HDU, with the last axis shortened to
+    /// the rows read.  Variable-length array columns of binary tables
+    /// are not available, as their heap follows all the rows
+    ///
+    /// # Arguments
+    ///
+    /// * `nrows` - Maximum number of rows to read
+    ///
+    /// # Returns
+    ///
+    /// The rows, or None when all rows have been read
+    ///
+    pub fn read_rows(&mut self, nrows: usize) -> Result<Option<HDUData>, FITSError> {
+        let nrows = nrows.min(self.rows_left);
+        let Some(header) = self.header.as_ref() else {
+            return Ok(None);
+        };
+        if nrows == 0 {
+            return Ok(None);
+        }
+        let (part, rowbytes) = rows_header(header, nrows)?;
+        let bytes = self.read_bytes(nrows * rowbytes)?;
+        self.rows_left -= nrows;
+        Ok(Some(HDU::from_header(part, &bytes, true)?.data))
+    }
+
+    /// Read exactly `buf.len()` bytes
+    ///
+    /// # Returns
+    ///
+    /// False if the stream ended before any byte was read
+    ///
+    fn read_block(&mut self, buf: &mut [u8]) -> Result<bool, FITSError> {
+        let mut nread = 0;
+        while nread < buf.len() {
+            match self.reader.read(&mut buf[nread..]) {
+                Ok(0) if nread == 0 => return Ok(false),
+                Ok(0) => return Err(std::io::Error::from(std::io::ErrorKind::UnexpectedEof).into()),
+                Ok(n) => nread += n,
+                Err(e) if e.kind() == std::io::ErrorKind::Interrupted => {}
+                Err(e) => return Err(e.into()),
+            }
+        }
+        self.position += nread;
+        Ok(true)
+    }
+
+    /// Read bytes of the current data unit
+    fn read_bytes(&mut self, nbytes: usize) -> Result<Vec<u8>, FITSError> {
+        let mut bytes = vec![0u8; nbytes];
+        self.reader.read_exact(&mut bytes)?;
+        self.data_left -= nbytes;
+        self.position += nbytes;
+        Ok(bytes)
+    }
+
+    /// Discard bytes; a missing final fill is tolerated
+    fn skip(&mut self, nbytes: usize) -> Result<(), FITSError> {
+        match self.seek {
+            Some(seek) => seek(&mut self.reader, nbytes)?,
+            None => {
+                std::io::copy(
+                    &mut (&mut self.reader).take(nbytes as u64),
+                    &mut std::io::sink(),
+                )?;
+            }
+        }
+        self.position += nbytes;
+        Ok(())
+    }
+}
+
+impl<R: Read + Seek> FITSStream<R> {
+    /// Create a stream over a seekable source, such as a file, that
+    /// skips unread data by seeking past it instead of reading it
+    ///
+    /// # Arguments
+    ///
+    /// * `reader` - Source of the file bytes, positioned at the start
+    ///   of the file
+    ///
+    pub fn seekable(reader: R) -> Self {
+        FITSStream {
+            seek: Some(|reader, nbytes| {
+                reader.seek(SeekFrom::Current(nbytes as i64))?;
+                Ok(())
+            }),
+            ..Self::new(reader)
+        }
+    }
+}
+
+impl<R: Read> Iterator for FITSStream<R> {
+    type Item = Result<HDU, FITSError>;
+
+    /// Read the next complete HDU
+    fn next(&mut self) -> Option<Self::Item> {
+        let header = match self.next_header() {
+            Ok(Some(header)) => header.clone(),
+            Ok(None) => return None,
+            Err(e) => return Some(Err(e)),
+        };
+        let location = self.location;
+        if header.naxes().is_some_and(|axes| axes.is_empty()) {
+            return Some(Ok(HDU {
+                header,
+                data: HDUData::None,
+                location,
+            }));
+        }
+        Some(
+            self.read_bytes(self.data_left)
+                .and_then(|bytes| HDU::from_header(header, &bytes, true))
+                .map(|hdu| HDU { location, ..hdu }),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testutil::hdu_bytes;
+
+    fn file_bytes() -> Vec<u8> {
+        let mut raw = hdu_bytes(
+            &[
+                "SIMPLE  =                    T",
+                "BITPIX  =                    8",
+                "NAXIS   =                    0",
+                "EXTEND  =                    T",
+                "END",
+            ],
+            &[],
+        );
+        raw.extend(hdu_bytes(
+            &[
+                "XTENSION= 'IMAGE   '",
+                "BITPIX  =                   16",
+                "NAXIS   =                    2",
+                "NAXIS1  =                    2",
+                "NAXIS2  =                    3",
+                "PCOUNT  =                    0",
+                "GCOUNT  =                    1",
+                "EXTNAME = 'SCI     '",
+                "END",
+            ],
+            &[0, 1, 0, 2, 0, 3, 0, 4, 0, 5, 0, 6],
+        ));
+        raw.extend(hdu_bytes(
+            &[
+                "XTENSION= 'BINTABLE'",
+                "BITPIX  =                    8",
+                "NAXIS   =                    2",
+                "NAXIS1  =                    2",
+                "NAXIS2  =                    3",
+                "PCOUNT  =                    0",
+                "GCOUNT  =                    1",
+                "TFIELDS =                    1",
+                "TFORM1  = '1I      '",
+                "END",
+            ],
+            &[0, 7, 0, 8, 0, 9],
+        ));
+        raw
+    }
+
+    #[test]
+    fn test_stream_hdus() {
+        let raw = file_bytes();
+        let hdus: Vec<HDU> = FITSStream::new(raw.as_slice())
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(hdus.len(), 3);
+        assert!(matches!(hdus[0].data, HDUData::None));
+        assert_eq!(hdus[1].extname(), Some("SCI"));
+        let HDUData::Image(im) = &hdus[1].data else {
+            panic!("expected image");
+        };
+        assert_eq!(im.pixels::<i16>().unwrap(), &[1, 2, 3, 4, 5, 6]);
+        let HDUData::BinTable(table) = &hdus[2].data else {
+            panic!("expected binary table");
+        };
+        assert_eq!(table.nrows, 3);
+    }
+
+    #[test]
+    fn test_stream_rows() {
+        let raw = file_bytes();
+        let mut stream = FITSStream::new(raw.as_slice());
+        assert!(stream.next_header().unwrap().is_some());
+        assert!(stream.read_rows(10).unwrap().is_none());
+
+        // Image read two lines at a time
+        stream.next_header().unwrap();
+        let mut lines = Vec::new();
+        while let Some(HDUData::Image(im)) = stream.read_rows(2).unwrap() {
+            lines.push(im.pixels::<i16>().unwrap().to_vec());
+        }
+        assert_eq!(lines, vec![vec![1, 2, 3, 4], vec![5, 6]]);
+
+        // Table partly read; the rest is skipped
+        stream.next_header().unwrap();
+        let Some(HDUData::BinTable(rows)) = stream.read_rows(1).unwrap() else {
+            panic!("expected binary table");
+        };
+        assert_eq!(rows.nrows, 1);
+        assert!(stream.next_header().unwrap().is_none());
+    }
+
+    #[test]
+    fn test_stream_end_card() {
+        // Text after END does not hide the end of the header
+        let raw = hdu_bytes(
+            &[
+                "SIMPLE  =                    T",
+                "BITPIX  =                    8",
+                "NAXIS   =                    1",
+                "NAXIS1  =                    3",
+                "END      trailing text",
+            ],
+            &[1, 2, 3],
+        );
+        let expected = crate::FITS::from_bytes(&raw).unwrap();
+        let hdus: Vec<HDU> = FITSStream::new(raw.as_slice())
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(hdus.len(), 1);
+        assert_eq!(hdus[0].location, expected.at(0).unwrap().location);
+        let HDUData::Image(im) = &hdus[0].data else {
+            panic!("expected image");
+        };
+        assert_eq!(im.pixels::<u8>().unwrap(), &[1, 2, 3]);
+    }
+}
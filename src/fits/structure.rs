@@ -0,0 +1,351 @@
+//! Structure of a FITS file for writing
+//!
+//! A file starts with a primary HDU (SIMPLE = T), followed by any
+//! number of extensions.  Each HDU begins with a fixed sequence of
+//! mandatory keywords.  See Sections 4.4.1 and 7 of FITS standard,
+//! version 4
+
+use std::borrow::Cow;
+
+use super::FITS;
+use crate::HDUData;
+use crate::Header;
+use crate::HeaderError;
+use crate::Keyword;
+use crate::KeywordValue;
+
+/// Make a keyword with a value and comment
+fn card(name: &str, value: KeywordValue, comment: &str) -> Keyword {
+    Keyword {
+        name: name.to_string(),
+        value,
+        comment: Some(comment.to_string()),
+        ..Default::default()
+    }
+}
+
+/// Header of a primary HDU without data
+pub(crate) fn empty_primary() -> Header {
+    Header::new(vec![
+        card(
+            "SIMPLE",
+            KeywordValue::Bool(true),
+            "conforms to FITS standard",
+        ),
+        card("BITPIX", KeywordValue::Int(8), "array data type"),
+        card("NAXIS", KeywordValue::Int(0), "number of array dimensions"),
+    ])
+}
+
+/// Value of XTENSION, if the header is an extension
+pub(super) fn xtension(header: &Header) -> Option<&str> {
+    match header.first() {
+        Some(Keyword {
+            name,
+            value: KeywordValue::String(s),
+            ..
+        }) if name == "XTENSION" => Some(s.trim_end()),
+        _ => None,
+    }
+}
+
+/// Convert an IMAGE extension header into a primary header
+///
+/// XTENSION is replaced by SIMPLE, and PCOUNT and GCOUNT are removed
+fn image_to_primary(header: &Header) -> Header {
+    let mut primary = header.clone();
+    primary[0] = card(
+        "SIMPLE",
+        KeywordValue::Bool(true),
+        "conforms to FITS standard",
+    );
+    for name in ["PCOUNT", "GCOUNT"] {
+        if let Some(pos) = primary.iter().position(|kw| kw.name == name) {
+            primary.remove(pos);
+        }
+    }
+    primary
+}
+
+/// Set EXTEND = T, placed after the mandatory keywords if not present
+fn set_extend(header: &mut Header) {
+    if header.find("EXTEND").is_some() {
+        header.set("EXTEND", KeywordValue::Bool(true));
+        return;
+    }
+    let naxis = header.get::<usize>("NAXIS").unwrap_or(0);
+    let pos = (3 + naxis).min(header.len());
+    header.insert(
+        pos,
+        card(
+            "EXTEND",
+            KeywordValue::Bool(true),
+            "file may contain extensions",
+        ),
+    );
+}
+
+/// Check that a keyword is at the position required by the standard
+///
+/// Errors give the position of the offending card and a description
+fn expect_keyword(header: &Header, pos: usize, name: &str) -> Result<(), (usize, String)> {
+    match header.iter().nth(pos) {
+        Some(kw) if kw.name == name => Ok(()),
+        Some(kw) => Err((
+            pos,
+            format!("expected {} at card {}, found {}", name, pos + 1, kw.name),
+        )),
+        None => Err((pos, format!("missing mandatory keyword {}", name))),
+    }
+}
+
+/// Check the mandatory keywords of an HDU header and their order
+///
+/// # Arguments
+///
+/// * `header` - The header to check
+/// * `primary` - True if this is the primary HDU
+///
+/// # Returns
+///
+/// Ok, or the position of the offending card and a description
+///
+pub(crate) fn check_mandatory(header: &Header, primary: bool) -> Result<(), (usize, String)> {
+    if primary {
+        expect_keyword(header, 0, "SIMPLE")?;
+        if header[0].value != KeywordValue::Bool(true) {
+            return Err((0, "SIMPLE must be T".to_string()));
+        }
+    } else {
+        expect_keyword(header, 0, "XTENSION")?;
+        if xtension(header).is_none() {
+            return Err((0, "XTENSION must be a string".to_string()));
+        }
+    }
+    expect_keyword(header, 1, "BITPIX")?;
+    if header.bitpix().is_none() {
+        return Err((1, "invalid BITPIX".to_string()));
+    }
+    expect_keyword(header, 2, "NAXIS")?;
+    let naxis = header
+        .get::<usize>("NAXIS")
+        .map_err(|_| (2, "invalid NAXIS".to_string()))?;
+    if naxis > 999 {
+        return Err((2, "NAXIS exceeds 999".to_string()));
+    }
+    for i in 1..=naxis {
+        expect_keyword(header, 2 + i, &format!("NAXIS{}", i))?;
+    }
+    if !primary {
+        expect_keyword(header, 3 + naxis, "PCOUNT")?;
+        expect_keyword(header, 4 + naxis, "GCOUNT")?;
+        if matches!(xtension(header), Some("TABLE") | Some("BINTABLE")) {
+            expect_keyword(header, 5 + naxis, "TFIELDS")?;
+        }
+    }
+    Ok(())
+}
+
+impl FITS {
+    /// Headers as they will be written, starting with a valid primary
+    ///
+    /// If the first HDU is an IMAGE extension it is written as the
+    /// primary HDU; if it is any other extension, an empty primary HDU
+    /// is written before it.  EXTEND = T is set on the primary header
+    /// when there are extensions, structural keywords are made to
+    /// describe the data (see `HDU::sync_structural_keywords`), and
+    /// duplicated value keywords are removed (see `Header::deduplicate`)
+    ///
+    /// # Returns
+    ///
+    /// Header for an inserted empty primary HDU, if needed, and the
+    /// header to write for each HDU
+    ///
+    pub(super) fn write_headers(&self) -> (Option<Header>, Vec<Cow<'_, Header>>) {
+        let mut headers: Vec<Cow<Header>> = self
+            .iter()
+            .map(|hdu| {
+                let mut header = hdu.synced_header();
+                if !header.duplicates().is_empty() {
+                    header.to_mut().deduplicate();
+                }
+                header
+            })
+            .collect();
+        let mut inserted = None;
+        match headers.first().map(|h| xtension(h)) {
+            Some(Some("IMAGE")) => headers[0] = Cow::Owned(image_to_primary(&headers[0])),
+            Some(Some(_)) | None => inserted = Some(empty_primary()),
+            Some(None) => {}
+        }
+        let nhdus = headers.len() + inserted.is_some() as usize;
+        if nhdus > 1 {
+            let primary = match inserted.as_mut() {
+                Some(h) => h,
+                None => headers[0].to_mut(),
+            };
+            if primary.value("EXTEND") != Some(&KeywordValue::Bool(true)) {
+                set_extend(primary);
+            }
+        }
+        (inserted, headers)
+    }
+
+    /// Check the structure of the file as it will be written
+    ///
+    /// The first HDU must be a primary HDU (see `write_headers` for the
+    /// conversions applied) and every HDU must begin with the mandatory
+    /// keywords in the order required by the standard.  An HDU without
+    /// data must have a header describing no data, e.g. NAXIS = 0, and
+    /// HIERARCH keywords must fit on their cards (see `Keyword::to_bytes`)
+    ///
+    /// # Returns
+    ///
+    /// Ok, or an error naming the offending HDU (by its position in
+    /// this list) and keyword
+    ///
+    pub fn validate(&self) -> Result<(), HeaderError> {
+        let (inserted, headers) = self.write_headers();
+        headers
+            .iter()
+            .zip(self.iter())
+            .enumerate()
+            .try_for_each(|(index, (header, hdu))| {
+                check_mandatory(header, index == 0 && inserted.is_none())
+                    .map_err(|(_, msg)| HeaderError::InvalidHDU(index, msg))?;
+                if let Some(kw) = header.iter().find(|kw| kw.overflows_card()) {
+                    return Err(HeaderError::InvalidHDU(
+                        index,
+                        format!("HIERARCH keyword {} does not fit on a header card", kw.name),
+                    ));
+                }
+                // A header-only HDU must not promise a data unit
+                match (&hdu.data, header.data_size()) {
+                    (HDUData::None, Some(nbytes)) if nbytes > 0 => Err(HeaderError::InvalidHDU(
+                        index,
+                        format!(
+                            "header describes {} data bytes, but there is no data",
+                            nbytes
+                        ),
+                    )),
+                    _ => Ok(()),
+                }
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testutil::image;
+    use crate::Bitpix;
+    use crate::HDU;
+
+    fn image_extension() -> HDU {
+        let int = KeywordValue::Int;
+        HDU {
+            header: Header::new(vec![
+                card("XTENSION", KeywordValue::String("IMAGE".into()), ""),
+                card("BITPIX", int(8), ""),
+                card("NAXIS", int(0), ""),
+                card("PCOUNT", int(0), ""),
+                card("GCOUNT", int(1), ""),
+                card("EXTNAME", KeywordValue::String("SCI".into()), ""),
+            ]),
+            data: HDUData::None,
+            location: None,
+        }
+    }
+
+    #[test]
+    fn test_primary() {
+        // An IMAGE extension becomes the primary HDU
+        let mut fits = FITS::new();
+        fits.push_hdu(image_extension());
+        fits.validate().unwrap();
+        let (inserted, headers) = fits.write_headers();
+        assert!(inserted.is_none());
+        let names: Vec<&str> = headers[0].iter().map(|kw| kw.name.as_str()).collect();
+        assert_eq!(names, vec!["SIMPLE", "BITPIX", "NAXIS", "EXTNAME"]);
+
+        // With extensions following, EXTEND is set after NAXIS
+        fits.push_hdu(image_extension());
+        let (_, headers) = fits.write_headers();
+        assert_eq!(headers[0][3].name, "EXTEND");
+        assert!(matches!(headers[1], Cow::Borrowed(_)));
+
+        // A table first requires an empty primary HDU
+        let mut table = image_extension();
+        table.header[0].value = KeywordValue::String("BINTABLE".into());
+        table
+            .header
+            .insert(5, card("TFIELDS", KeywordValue::Int(0), ""));
+        let mut fits = FITS::new();
+        fits.push_hdu(table);
+        fits.validate().unwrap();
+        let (inserted, _) = fits.write_headers();
+        assert_eq!(
+            inserted.unwrap().value("EXTEND"),
+            Some(&KeywordValue::Bool(true))
+        );
+    }
+
+    #[test]
+    fn test_validate() {
+        let mut fits = FITS::new();
+        fits.push_hdu(image_extension());
+        let mut bad = image_extension();
+        bad.header.remove(3);
+        fits.push_hdu(bad);
+        match fits.validate() {
+            Err(HeaderError::InvalidHDU(1, msg)) => {
+                assert_eq!(msg, "expected PCOUNT at card 4, found GCOUNT")
+            }
+            other => panic!("unexpected result {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_to_bytes() {
+        let mut fits = FITS::new();
+        fits.push_hdu(image_extension());
+        fits.push_hdu(image_extension());
+        let bytes = fits.to_bytes().unwrap();
+        assert_eq!(bytes.len(), 2 * 2880);
+        let cards: Vec<&[u8]> = bytes.chunks(80).map(|c| &c[0..8]).collect();
+        assert_eq!(
+            &cards[0..4],
+            [b"SIMPLE  ", b"BITPIX  ", b"NAXIS   ", b"EXTEND  "]
+        );
+        assert_eq!(cards[36], b"XTENSION");
+    }
+
+    #[test]
+    fn test_empty_primary() {
+        let mut extension = image_extension();
+        extension.header.set("NAXIS", KeywordValue::Int(1));
+        extension
+            .header
+            .insert(3, card("NAXIS1", KeywordValue::Int(2), ""));
+        extension.data = HDUData::Image(Box::new(image(Bitpix::Int8, &[2], &[1u8, 2])));
+
+        let mut fits = FITS::new();
+        fits.push_hdu(HDU::empty_primary());
+        fits.push_hdu(extension.clone());
+        let bytes = fits.to_bytes().unwrap();
+        // The primary is a header block with no data blocks
+        assert_eq!(bytes.len(), 3 * 2880);
+        let read = FITS::from_bytes(&bytes).unwrap();
+        assert_eq!(read.len(), 2);
+        assert!(matches!(read[0].data, HDUData::None));
+        assert!(matches!(read[1].data, HDUData::Image(_)));
+
+        // A header promising data that is absent is rejected
+        extension.data = HDUData::None;
+        fits.push_hdu(extension);
+        assert!(matches!(
+            fits.validate(),
+            Err(HeaderError::InvalidHDU(2, _))
+        ));
+    }
+}
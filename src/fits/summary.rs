@@ -0,0 +1,223 @@
+//! Overview of the HDUs in a file, similar to `fitsinfo`
+
+use super::structure::xtension;
+use super::FITS;
+use crate::Header;
+
+/// Structure of a single HDU, taken from its header
+#[derive(Clone, Debug, PartialEq)]
+pub struct HDUSummary {
+    /// Position of the HDU in the file; 0 is the primary HDU
+    pub index: usize,
+    /// Extension name, from EXTNAME
+    pub name: Option<String>,
+    /// Extension version, from EXTVER
+    pub version: i64,
+    /// "PRIMARY", or the value of XTENSION, e.g. "IMAGE" or "BINTABLE"
+    pub kind: String,
+    /// Number of header keywords, excluding END
+    pub cards: usize,
+    pub bitpix: Option<i64>,
+    /// Axis lengths, NAXIS1 first
+    pub axes: Vec<usize>,
+    /// Number of table rows (NAXIS2), for table extensions
+    pub rows: Option<usize>,
+    /// Number of table columns (TFIELDS), for table extensions
+    pub columns: Option<usize>,
+    /// Size of the data unit in bytes, including any heap but not fill
+    pub data_size: usize,
+}
+
+impl HDUSummary {
+    /// Summarize an HDU from its header
+    ///
+    /// Only the header is needed, so HDUs can be summarized while
+    /// skipping their data, e.g. with `FITSStream::next_header`
+    ///
+    /// # Arguments
+    ///
+    /// * `index` - Position of the HDU in the file
+    /// * `header` - The HDU header
+    ///
+    pub fn from_header(index: usize, header: &Header) -> Self {
+        let kind = xtension(header).unwrap_or("PRIMARY").to_string();
+        let axes = header.naxes().unwrap_or_default();
+        let is_table = kind == "TABLE" || kind == "BINTABLE";
+        HDUSummary {
+            index,
+            name: header.extname().map(|s| s.trim_end().to_string()),
+            version: header.extver(),
+            cards: header.iter().filter(|kw| kw.name != "END").count(),
+            bitpix: header.get::<i64>("BITPIX").ok(),
+            rows: match is_table {
+                true => axes.get(1).copied(),
+                false => None,
+            },
+            columns: match is_table {
+                true => header.get::<usize>("TFIELDS").ok(),
+                false => None,
+            },
+            axes,
+            data_size: header.data_size().unwrap_or(0),
+            kind,
+        }
+    }
+
+    /// Shape of the data: axis lengths for arrays, rows and columns
+    /// for tables
+    pub fn dimensions(&self) -> String {
+        match (self.rows, self.columns) {
+            (Some(rows), Some(columns)) => format!("{} rows x {} cols", rows, columns),
+            _ => self
+                .axes
+                .iter()
+                .map(|n| n.to_string())
+                .collect::<Vec<_>>()
+                .join(" x "),
+        }
+    }
+}
+
+/// Structure of every HDU in a file; see `FITS::summary`
+///
+/// Displays as a table with one line per HDU
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct FITSSummary {
+    pub hdus: Vec<HDUSummary>,
+}
+
+impl std::fmt::Display for FITSSummary {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(
+            f,
+            "{:>3}  {:<12} {:>3}  {:<8} {:>5}  {:>6}  {:<20} {:>10}",
+            "No.", "Name", "Ver", "Type", "Cards", "BITPIX", "Dimensions", "Size"
+        )?;
+        for hdu in &self.hdus {
+            let name = match (&hdu.name, hdu.index) {
+                (Some(name), _) => name.as_str(),
+                (None, 0) => "PRIMARY",
+                (None, _) => "",
+            };
+            let bitpix = hdu.bitpix.map(|b| b.to_string()).unwrap_or_default();
+            write!(
+                f,
+                "\n{:>3}  {:<12} {:>3}  {:<8} {:>5}  {:>6}  {:<20} {:>10}",
+                hdu.index,
+                name,
+                hdu.version,
+                hdu.kind,
+                hdu.cards,
+                bitpix,
+                hdu.dimensions(),
+                hdu.data_size
+            )?;
+        }
+        Ok(())
+    }
+}
+
+impl FITS {
+    /// Overview of the HDUs: type, name, BITPIX, dimensions, table rows
+    /// and columns, and data size
+    ///
+    /// # Returns
+    ///
+    /// One `HDUSummary` per HDU, in file order
+    ///
+    pub fn summary(&self) -> FITSSummary {
+        FITSSummary {
+            hdus: self
+                .hdus
+                .iter()
+                .enumerate()
+                .map(|(index, hdu)| HDUSummary::from_header(index, &hdu.header))
+                .collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Keyword, KeywordValue, HDU};
+
+    #[test]
+    fn test_summary() {
+        let header = |cards: &[(&str, KeywordValue)]| {
+            Header::new(
+                cards
+                    .iter()
+                    .map(|(name, value)| Keyword {
+                        name: name.to_string(),
+                        value: value.clone(),
+                        ..Default::default()
+                    })
+                    .collect(),
+            )
+        };
+        let int = KeywordValue::Int;
+        let string = |s: &str| KeywordValue::String(s.to_string());
+
+        let mut fits = FITS::new();
+        fits.push_hdu(HDU {
+            header: header(&[
+                ("SIMPLE", KeywordValue::Bool(true)),
+                ("BITPIX", int(-32)),
+                ("NAXIS", int(2)),
+                ("NAXIS1", int(100)),
+                ("NAXIS2", int(50)),
+                ("END", KeywordValue::None),
+            ]),
+            data: crate::HDUData::None,
+            location: None,
+        });
+        fits.push_hdu(HDU {
+            header: header(&[
+                ("XTENSION", string("BINTABLE")),
+                ("BITPIX", int(8)),
+                ("NAXIS", int(2)),
+                ("NAXIS1", int(12)),
+                ("NAXIS2", int(1000)),
+                ("PCOUNT", int(64)),
+                ("GCOUNT", int(1)),
+                ("TFIELDS", int(3)),
+                ("EXTNAME", string("EVENTS")),
+                ("EXTVER", int(2)),
+                ("END", KeywordValue::None),
+            ]),
+            data: crate::HDUData::None,
+            location: None,
+        });
+
+        let summary = fits.summary();
+        assert_eq!(
+            summary.hdus[0],
+            HDUSummary {
+                index: 0,
+                name: None,
+                version: 1,
+                kind: "PRIMARY".to_string(),
+                cards: 5,
+                bitpix: Some(-32),
+                axes: vec![100, 50],
+                rows: None,
+                columns: None,
+                data_size: 20000,
+            }
+        );
+        let events = &summary.hdus[1];
+        assert_eq!(events.name.as_deref(), Some("EVENTS"));
+        assert_eq!(events.version, 2);
+        assert_eq!(events.kind, "BINTABLE");
+        assert_eq!((events.rows, events.columns), (Some(1000), Some(3)));
+        assert_eq!(events.data_size, 12064);
+        assert_eq!(summary.hdus[0].dimensions(), "100 x 50");
+        assert_eq!(events.dimensions(), "1000 rows x 3 cols");
+
+        let lines: Vec<String> = summary.to_string().lines().map(String::from).collect();
+        assert_eq!(lines.len(), 3);
+        assert!(lines[1].contains("PRIMARY") && lines[1].contains("100 x 50"));
+        assert!(lines[2].contains("EVENTS") && lines[2].ends_with("12064"));
+    }
+}
@@ -0,0 +1,189 @@
+use std::io::{Read, Seek, SeekFrom, Write};
+
+use super::FITS;
+use crate::hdu::data_unit_size;
+use crate::FITSBlock;
+use crate::FITSError;
+use crate::Header;
+use crate::HeaderError;
+
+/// Read a header starting at the current file position
+///
+/// # Returns
+///
+/// The header and its size on disk in bytes
+///
+fn read_header(file: &mut std::fs::File) -> Result<(Header, usize), FITSError> {
+    let mut header = Header::default();
+    let mut block = [0u8; 2880];
+    let mut nbytes = 0;
+    loop {
+        file.read_exact(&mut block)?;
+        nbytes += 2880;
+        for keyword in FITSBlock::from_bytes(&block)?.0 {
+            let end = keyword.name == "END";
+            header.push_card(keyword);
+            if end {
+                return Ok((header, nbytes));
+            }
+        }
+    }
+}
+
+/// Find an HDU in a file, reading only the headers
+///
+/// # Arguments
+///
+/// * `file` - The open file
+/// * `index` - Position of the HDU in the file; 0 is the primary HDU
+///
+/// # Returns
+///
+/// The header of the HDU, its offset in the file, and its size on disk
+/// in bytes.  The file is left positioned at the start of the data unit
+///
+pub(crate) fn seek_hdu(
+    file: &mut std::fs::File,
+    index: usize,
+) -> Result<(Header, u64, usize), FITSError> {
+    // Skip over preceding HDUs, reading only their headers
+    let mut offset = 0u64;
+    file.seek(SeekFrom::Start(0))?;
+    for _ in 0..index {
+        let (old, nbytes) = read_header(file)?;
+        let datasize = data_unit_size(&old)?;
+        offset += (nbytes + datasize.div_ceil(2880) * 2880) as u64;
+        file.seek(SeekFrom::Start(offset))?;
+    }
+    let (header, nbytes) = read_header(file)?;
+    Ok((header, offset, nbytes))
+}
+
+impl FITS {
+    /// Replace the header of one HDU in a file, without rewriting data
+    ///
+    /// Only the header blocks of the chosen HDU are written, so this is
+    /// fast even for very large files.  The new header must fit in the
+    /// same number of 2880-byte blocks as the old one (blank cards are
+    /// added before END to fill any extra space) and must describe a
+    /// data unit of the same size and BITPIX
+    ///
+    /// # Arguments
+    ///
+    /// * `file` - Path of the FITS file
+    /// * `index` - Position of the HDU in the file; 0 is the primary HDU
+    /// * `header` - The new header
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use fits::{KeywordValue, FITS};
+    /// # fn main() -> Result<(), fits::FITSError> {
+    /// let fits = FITS::from_file("image.fits")?;
+    /// let mut header = fits[1].header.clone();
+    /// header.set("OBJECT", KeywordValue::String("M31".into()));
+    /// FITS::update_header_in_file("image.fits", 1, &header)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    pub fn update_header_in_file(
+        file: &str,
+        index: usize,
+        header: &Header,
+    ) -> Result<(), FITSError> {
+        let mut file = std::fs::OpenOptions::new()
+            .read(true)
+            .write(true)
+            .open(file)?;
+        let (old, offset, nbytes) = seek_hdu(&mut file, index)?;
+        let size = data_unit_size(&old)?;
+        if data_unit_size(header).ok() != Some(size) {
+            return Err(FITSError::from(HeaderError::GenericError(
+                "new header changes the size of the data unit".to_string(),
+            )));
+        }
+        // The data would be reinterpreted, e.g. integers as floats
+        if header.get::<i64>("BITPIX").ok() != old.get::<i64>("BITPIX").ok() {
+            return Err(FITSError::from(HeaderError::GenericError(
+                "new header changes the data type (BITPIX)".to_string(),
+            )));
+        }
+        let bytes = header
+            .to_bytes_sized(nbytes)
+            .ok_or_else(|| HeaderError::HeaderTooLarge(header.to_bytes().len(), nbytes))?;
+        file.seek(SeekFrom::Start(offset))?;
+        file.write_all(&bytes)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testutil::hdu_bytes;
+    use crate::{KeywordValue, HDU};
+
+    #[test]
+    fn test_update_header() {
+        let mut raw = hdu_bytes(
+            &[
+                "SIMPLE  =                    T",
+                "BITPIX  =                   16",
+                "NAXIS   =                    1",
+                "NAXIS1  =                    2",
+                "EXTEND  =                    T",
+                "END",
+            ],
+            &[0, 1, 0, 2],
+        );
+        raw.extend(hdu_bytes(
+            &[
+                "XTENSION= 'IMAGE   '",
+                "BITPIX  =                   16",
+                "NAXIS   =                    1",
+                "NAXIS1  =                    2",
+                "PCOUNT  =                    0",
+                "GCOUNT  =                    1",
+                "END",
+            ],
+            &[0, 1, 0, 2],
+        ));
+
+        let path = std::env::temp_dir().join(format!("fits_update_{}.fits", std::process::id()));
+        let path = path.to_str().unwrap();
+        std::fs::write(path, &raw).unwrap();
+
+        let fits = FITS::from_file(path).unwrap();
+        let mut header = fits[1].header.clone();
+        header.set("OBJECT", KeywordValue::String("M31".to_string()));
+        FITS::update_header_in_file(path, 1, &header).unwrap();
+
+        let bytes = std::fs::read(path).unwrap();
+        assert_eq!(bytes.len(), raw.len());
+        assert_eq!(bytes[..5760 + 480], raw[..5760 + 480]);
+        assert_eq!(bytes[5760 + 2880..], raw[5760 + 2880..]);
+        let (hdu, _) = HDU::from_bytes(&bytes[5760..]).unwrap();
+        assert_eq!(hdu.header.object(), Some("M31"));
+
+        // Too many keywords for one block
+        for i in 0..40 {
+            header.set(&format!("KEY{}", i), KeywordValue::Int(i));
+        }
+        assert!(FITS::update_header_in_file(path, 1, &header).is_err());
+
+        // Changing the data size is refused
+        let mut header = fits[1].header.clone();
+        header.set("NAXIS1", KeywordValue::Int(3));
+        assert!(FITS::update_header_in_file(path, 1, &header).is_err());
+
+        // As is changing the data type, or an invalid data size
+        let mut header = fits[1].header.clone();
+        header.set("BITPIX", KeywordValue::Int(-16));
+        assert!(FITS::update_header_in_file(path, 1, &header).is_err());
+        let mut header = fits[1].header.clone();
+        header.set("NAXIS1", KeywordValue::Bool(true));
+        assert!(FITS::update_header_in_file(path, 1, &header).is_err());
+        std::fs::remove_file(path).unwrap();
+    }
+}
@@ -0,0 +1,296 @@
+//! Verification of files against the FITS standard, similar to `fitsverify`
+
+use std::io::Read;
+
+use super::FITS;
+use crate::hdu::{data_unit_size, is_end_card};
+use crate::FITSError;
+use crate::HDUData;
+use crate::KeywordValue;
+
+/// How serious a verification finding is
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    /// Not a problem, but worth knowing
+    Info,
+    /// Allowed by the standard, but likely to cause problems
+    Warning,
+    /// Violates the standard
+    Error,
+}
+
+/// A single verification finding
+#[derive(Clone, Debug, PartialEq)]
+pub struct VerifyIssue {
+    pub severity: Severity,
+    /// Position of the HDU in the file; 0 is the primary HDU
+    pub hdu: usize,
+    /// Header card number (starting at 1), if the issue concerns a card
+    pub card: Option<usize>,
+    pub message: String,
+}
+
+impl std::fmt::Display for VerifyIssue {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{:?}: HDU {}", self.severity, self.hdu)?;
+        if let Some(card) = self.card {
+            write!(f, ", card {}", card)?;
+        }
+        write!(f, ": {}", self.message)
+    }
+}
+
+/// Result of verifying a file or HDU
+#[derive(Clone, Debug, Default)]
+pub struct VerifyReport {
+    pub issues: Vec<VerifyIssue>,
+}
+
+impl VerifyReport {
+    /// Record a finding
+    pub(crate) fn push(&mut self, severity: Severity, card: Option<usize>, message: String) {
+        self.issues.push(VerifyIssue {
+            severity,
+            hdu: 0,
+            card,
+            message,
+        });
+    }
+
+    /// True if no issue has `Severity::Error`
+    pub fn is_compliant(&self) -> bool {
+        self.issues.iter().all(|i| i.severity < Severity::Error)
+    }
+
+    /// Issues with the given severity
+    pub fn with_severity(&self, severity: Severity) -> impl Iterator<Item = &VerifyIssue> {
+        self.issues.iter().filter(move |i| i.severity == severity)
+    }
+}
+
+impl std::fmt::Display for VerifyReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        for issue in &self.issues {
+            writeln!(f, "{}", issue)?;
+        }
+        let count = |s| self.with_severity(s).count();
+        write!(
+            f,
+            "{} errors, {} warnings",
+            count(Severity::Error),
+            count(Severity::Warning)
+        )
+    }
+}
+
+impl FITS {
+    /// Check the HDUs against the FITS standard
+    ///
+    /// Runs `HDU::verify` on each HDU, and checks that the first HDU is
+    /// a primary HDU and that EXTEND = T is present when there are
+    /// extensions.  Fill bytes are not kept when a file is read; use
+    /// `verify_file` to check them as well
+    ///
+    /// # Returns
+    ///
+    /// Report of all issues found
+    ///
+    pub fn verify(&self) -> VerifyReport {
+        let mut report = VerifyReport::default();
+        for (index, hdu) in self.hdus.iter().enumerate() {
+            let mut issues = hdu.verify().issues;
+            if index == 0 && hdu.header.first().is_some_and(|kw| kw.name != "SIMPLE") {
+                issues.push(VerifyIssue {
+                    severity: Severity::Error,
+                    hdu: 0,
+                    card: Some(1),
+                    message: "first HDU is not a primary HDU (SIMPLE = T)".to_string(),
+                });
+            }
+            report
+                .issues
+                .extend(issues.into_iter().map(|i| VerifyIssue { hdu: index, ..i }));
+        }
+        if let Some(primary) = self.hdus.first() {
+            if self.hdus.len() > 1 && primary.value("EXTEND") != Some(&KeywordValue::Bool(true)) {
+                report.issues.push(VerifyIssue {
+                    severity: Severity::Warning,
+                    hdu: 0,
+                    card: None,
+                    message: "file has extensions but EXTEND = T is absent".to_string(),
+                });
+            }
+        }
+        report
+    }
+
+    /// Check a file against the FITS standard
+    ///
+    /// In addition to the checks of `verify`, the file is read leniently
+    /// (each recovered problem is an error) and the fill after each
+    /// header and data unit is checked: blanks after END, and zeros after
+    /// data (blanks for ASCII tables)
+    ///
+    /// # Arguments
+    ///
+    /// * `file` - Path of the FITS file
+    ///
+    /// # Returns
+    ///
+    /// Report of all issues found, or an error if the file cannot be read
+    ///
+    pub fn verify_file(file: &str) -> Result<VerifyReport, FITSError> {
+        let mut rawbytes = Vec::new();
+        std::fs::File::open(file)?.read_to_end(&mut rawbytes)?;
+        Ok(Self::verify_bytes(&rawbytes))
+    }
+
+    /// Check file bytes against the FITS standard; see `verify_file`
+    pub(crate) fn verify_bytes(rawbytes: &[u8]) -> VerifyReport {
+        let fits = FITS::from_bytes_lenient(rawbytes);
+        let mut report = VerifyReport {
+            issues: fits
+                .warnings()
+                .iter()
+                .map(|w| VerifyIssue {
+                    severity: Severity::Error,
+                    hdu: w.hdu,
+                    card: None,
+                    message: w.message.clone(),
+                })
+                .collect(),
+        };
+        report.issues.extend(fits.verify().issues);
+
+        let mut offset = 0;
+        for (index, hdu) in fits.iter().enumerate() {
+            let Some(end) = rawbytes[offset..].chunks_exact(80).position(is_end_card) else {
+                break;
+            };
+            let hend = offset + (end + 1) * 80;
+            let hblock = (offset + (end + 1) * 80).div_ceil(2880) * 2880;
+            let mut issue = |message: &str| {
+                report.issues.push(VerifyIssue {
+                    severity: Severity::Error,
+                    hdu: index,
+                    card: None,
+                    message: message.to_string(),
+                })
+            };
+            if rawbytes[hend..hblock.min(rawbytes.len())]
+                .iter()
+                .any(|&b| b != b' ')
+            {
+                issue("header fill after END is not all blanks");
+            }
+
+            let Ok(size) = data_unit_size(&hdu.header) else {
+                issue("data unit size is not valid");
+                break;
+            };
+            let dend = hblock + size;
+            let dblock = hblock + size.div_ceil(2880) * 2880;
+            let (fill, name) = match hdu.data {
+                HDUData::Table(_) => (b' ', "blanks"),
+                _ => (0u8, "zeros"),
+            };
+            if dend < rawbytes.len()
+                && rawbytes[dend..dblock.min(rawbytes.len())]
+                    .iter()
+                    .any(|&b| b != fill)
+            {
+                issue(&format!("data fill is not all {}", name));
+            }
+            offset = dblock;
+            if offset >= rawbytes.len() {
+                break;
+            }
+        }
+        report
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testutil::hdu_bytes;
+
+    #[test]
+    fn test_verify() {
+        let mut raw = hdu_bytes(
+            &[
+                "SIMPLE  =                    T",
+                "BITPIX  =                    8",
+                "NAXIS   =                    1",
+                "NAXIS1  =                    4",
+                "DATASUM = '16909060'",
+                "END",
+            ],
+            &[1, 2, 3, 4],
+        );
+        // Non-zero data fill
+        raw[2880 + 100] = 1;
+        raw.extend(hdu_bytes(
+            &[
+                "XTENSION= 'IMAGE   '",
+                "BITPIX  =                    8",
+                "NAXIS   =                    1",
+                "NAXIS1  =                    4",
+                "PCOUNT  =                    0",
+                "GCOUNT  =                    1",
+                "DATASUM = '0'",
+                "END",
+            ],
+            &[1, 2, 3, 4],
+        ));
+
+        let report = FITS::verify_bytes(&raw);
+        let messages: Vec<String> = report.issues.iter().map(|i| i.to_string()).collect();
+        assert_eq!(
+            messages,
+            vec![
+                "Error: HDU 1, card 7: DATASUM = '0' does not match the data (16909060)",
+                "Warning: HDU 0: file has extensions but EXTEND = T is absent",
+                "Error: HDU 0: data fill is not all zeros",
+            ]
+        );
+        assert!(!report.is_compliant());
+        assert_eq!(report.with_severity(Severity::Warning).count(), 1);
+    }
+
+    #[test]
+    fn test_verify_hdu() {
+        let raw = hdu_bytes(
+            &[
+                "XTENSION= 'BINTABLE'",
+                "BITPIX  =                    8",
+                "NAXIS   =                    2",
+                "NAXIS1  =                    6",
+                "NAXIS2  =                    0",
+                "GCOUNT  =                    1",
+                "PCOUNT  =                    0",
+                "TFIELDS =                    1",
+                "TFORM1  = '1J      '",
+                "END",
+            ],
+            &[],
+        );
+        let mut warnings = Vec::new();
+        let (hdu, _) = crate::HDU::from_bytes_with(
+            &raw,
+            Some(&mut warnings),
+            true,
+            crate::DuplicatePolicy::First,
+        )
+        .unwrap();
+        let messages: Vec<String> = hdu.verify().issues.iter().map(|i| i.to_string()).collect();
+        assert_eq!(
+            messages,
+            vec![
+                "Error: HDU 0, card 6: expected PCOUNT at card 6, found GCOUNT",
+                "Error: HDU 0, card 4: NAXIS1 = 6 does not match the sum of TFORMn widths (4)",
+                "Info: HDU 0: data of type 'BINTABLE' not interpreted",
+            ]
+        );
+    }
+}
@@ -0,0 +1,248 @@
+//! Good Time Intervals of X-ray event files
+//!
+//! A GTI extension is a binary table whose START and STOP columns list
+//! the time ranges over which data are valid.  Events outside every
+//! interval are discarded by filtering the TIME column of the event
+//! table
+
+use crate::BinTable;
+use crate::FITSError;
+use crate::HDUData;
+use crate::TableLike;
+use crate::HDU;
+
+/// Good Time Intervals, sorted and non-overlapping
+///
+/// Each interval includes its start and excludes its stop time
+///
+/// # Example
+///
+/// ```no_run
+/// # use fits::{Gti, HDUData, FITS};
+/// # fn main() -> Result<(), fits::FITSError> {
+/// # let fits = FITS::from_file("events.fits")?;
+/// # let HDUData::BinTable(events) = &fits.at(1)?.data else { return Ok(()) };
+/// let gti = Gti::from_hdu(fits.at(2)?)?;
+/// println!("exposure {} s", gti.exposure());
+/// let events = events.filter_gti(&gti)?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Gti {
+    intervals: Vec<(f64, f64)>,
+}
+
+impl Gti {
+    /// Create from intervals in any order
+    ///
+    /// Overlapping and adjacent intervals are merged, and intervals
+    /// whose stop is not after their start are dropped
+    ///
+    /// # Arguments
+    ///
+    /// * `intervals` - (start, stop) pairs
+    ///
+    pub fn new(intervals: &[(f64, f64)]) -> Self {
+        let mut sorted: Vec<(f64, f64)> = intervals
+            .iter()
+            .copied()
+            .filter(|(start, stop)| stop > start)
+            .collect();
+        sorted.sort_by(|a, b| a.0.total_cmp(&b.0));
+        let mut merged: Vec<(f64, f64)> = Vec::with_capacity(sorted.len());
+        for (start, stop) in sorted {
+            match merged.last_mut() {
+                Some(last) if start <= last.1 => last.1 = last.1.max(stop),
+                _ => merged.push((start, stop)),
+            }
+        }
+        Gti { intervals: merged }
+    }
+
+    /// Read the intervals of a GTI extension
+    ///
+    /// # Arguments
+    ///
+    /// * `hdu` - Binary table with START and STOP columns
+    ///
+    /// # Returns
+    ///
+    /// The intervals, or an error if the HDU is not a binary table or
+    /// lacks the columns.  Rows with a null start or stop are ignored
+    ///
+    pub fn from_hdu(hdu: &HDU) -> Result<Self, FITSError> {
+        let HDUData::BinTable(table) = &hdu.data else {
+            return Err(FITSError::HDUNotFound(
+                "GTI binary table with START and STOP columns".to_string(),
+            ));
+        };
+        let start = table.column::<f64>("START")?;
+        let stop = table.column::<f64>("STOP")?;
+        let intervals: Vec<(f64, f64)> = start
+            .into_iter()
+            .zip(stop)
+            .filter_map(|(start, stop)| Some((start?, stop?)))
+            .collect();
+        Ok(Gti::new(&intervals))
+    }
+
+    /// The intervals, as sorted, non-overlapping (start, stop) pairs
+    pub fn intervals(&self) -> &[(f64, f64)] {
+        &self.intervals
+    }
+
+    /// Total time covered by the intervals
+    pub fn exposure(&self) -> f64 {
+        self.intervals
+            .iter()
+            .map(|(start, stop)| stop - start)
+            .sum()
+    }
+
+    /// Is a time within an interval
+    pub fn contains(&self, time: f64) -> bool {
+        // First interval starting after `time`; the one before may hold it
+        let next = self.intervals.partition_point(|(start, _)| *start <= time);
+        next > 0 && time < self.intervals[next - 1].1
+    }
+
+    /// Times covered by both sets of intervals
+    pub fn intersection(&self, other: &Gti) -> Gti {
+        let mut intervals = Vec::new();
+        let (mut i, mut j) = (0, 0);
+        while i < self.intervals.len() && j < other.intervals.len() {
+            let (a, b) = (self.intervals[i], other.intervals[j]);
+            let start = a.0.max(b.0);
+            let stop = a.1.min(b.1);
+            if stop > start {
+                intervals.push((start, stop));
+            }
+            if a.1 < b.1 {
+                i += 1;
+            } else {
+                j += 1;
+            }
+        }
+        Gti { intervals }
+    }
+
+    /// Times covered by either set of intervals
+    pub fn union(&self, other: &Gti) -> Gti {
+        let all: Vec<(f64, f64)> = self
+            .intervals
+            .iter()
+            .chain(&other.intervals)
+            .copied()
+            .collect();
+        Gti::new(&all)
+    }
+}
+
+impl BinTable {
+    /// Rows of an event table whose time is within the Good Time Intervals
+    ///
+    /// # Arguments
+    ///
+    /// * `gti` - The intervals
+    ///
+    /// # Returns
+    ///
+    /// New table with the rows whose TIME is in an interval, or an
+    /// error if there is no TIME column.  Rows with a null TIME are
+    /// dropped
+    ///
+    pub fn filter_gti(&self, gti: &Gti) -> Result<BinTable, FITSError> {
+        let mask: Vec<bool> = self
+            .column::<f64>("TIME")?
+            .into_iter()
+            .map(|time| time.is_some_and(|t| gti.contains(t)))
+            .collect();
+        self.filter_rows(&mask)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testutil::header;
+
+    fn table(cards: &[&str], raw: &[u8]) -> HDU {
+        let header = header(cards);
+        let (data, _) = BinTable::from_bytes(&header, raw).unwrap();
+        HDU {
+            header,
+            data,
+            location: None,
+        }
+    }
+
+    #[test]
+    fn test_gti() {
+        let mut raw = Vec::new();
+        for (start, stop) in [(30.0f64, 40.0f64), (0.0, 10.0), (5.0, 20.0), (50.0, 50.0)] {
+            raw.extend(start.to_be_bytes());
+            raw.extend(stop.to_be_bytes());
+        }
+        let hdu = table(
+            &[
+                "XTENSION= 'BINTABLE'",
+                "BITPIX  =                    8",
+                "NAXIS   =                    2",
+                "NAXIS1  =                   16",
+                "NAXIS2  =                    4",
+                "PCOUNT  =                    0",
+                "GCOUNT  =                    1",
+                "TFIELDS =                    2",
+                "TTYPE1  = 'START   '",
+                "TFORM1  = '1D      '",
+                "TTYPE2  = 'STOP    '",
+                "TFORM2  = '1D      '",
+                "EXTNAME = 'GTI     '",
+                "END",
+            ],
+            &raw,
+        );
+        let gti = Gti::from_hdu(&hdu).unwrap();
+        assert_eq!(gti.intervals(), &[(0.0, 20.0), (30.0, 40.0)]);
+        assert_eq!(gti.exposure(), 30.0);
+        assert!(gti.contains(0.0) && gti.contains(35.0));
+        assert!(!gti.contains(20.0) && !gti.contains(25.0) && !gti.contains(-1.0));
+
+        let other = Gti::new(&[(15.0, 35.0), (38.0, 60.0)]);
+        assert_eq!(
+            gti.intersection(&other).intervals(),
+            &[(15.0, 20.0), (30.0, 35.0), (38.0, 40.0)]
+        );
+        assert_eq!(gti.union(&other).intervals(), &[(0.0, 60.0)]);
+
+        let mut raw = Vec::new();
+        for time in [1.0f64, 25.0, 39.5, 40.0] {
+            raw.extend(time.to_be_bytes());
+        }
+        let events = table(
+            &[
+                "XTENSION= 'BINTABLE'",
+                "BITPIX  =                    8",
+                "NAXIS   =                    2",
+                "NAXIS1  =                    8",
+                "NAXIS2  =                    4",
+                "PCOUNT  =                    0",
+                "GCOUNT  =                    1",
+                "TFIELDS =                    1",
+                "TTYPE1  = 'TIME    '",
+                "TFORM1  = '1D      '",
+                "END",
+            ],
+            &raw,
+        );
+        let HDUData::BinTable(events) = &events.data else {
+            panic!("expected binary table");
+        };
+        let kept = events.filter_gti(&gti).unwrap();
+        assert_eq!(
+            kept.column::<f64>("TIME").unwrap(),
+            vec![Some(1.0), Some(39.5)]
+        );
+    }
+}
@@ -0,0 +1,178 @@
+use super::HDU;
+use crate::FITSError;
+use crate::FromKeywordValue;
+use crate::Header;
+use crate::HeaderError;
+use crate::KeywordValue;
+use crate::FITS;
+
+/// True if a primary-header keyword describes the primary HDU itself
+/// and so is never inherited by extensions
+///
+/// These are the mandatory keywords, commentary cards, and the
+/// checksums, per the INHERIT keyword convention
+fn is_local(name: &str) -> bool {
+    matches!(
+        name,
+        "SIMPLE"
+            | "BITPIX"
+            | "EXTEND"
+            | "INHERIT"
+            | "COMMENT"
+            | "HISTORY"
+            | ""
+            | "CHECKSUM"
+            | "DATASUM"
+            | "END"
+    ) || name
+        .strip_prefix("NAXIS")
+        .is_some_and(|n| n.chars().all(|c| c.is_ascii_digit()))
+}
+
+impl HDU {
+    /// True if this is an extension with INHERIT = T, whose keyword
+    /// lookups fall back to the primary header
+    pub fn inherits(&self) -> bool {
+        self.header.find("XTENSION").is_some()
+            && self.header.get::<bool>("INHERIT").unwrap_or(false)
+    }
+
+    /// Return the value of a keyword, following the INHERIT convention
+    ///
+    /// If the keyword is absent from this HDU and the HDU has
+    /// INHERIT = T, the value is taken from the primary header.
+    /// Mandatory keywords, commentary, and checksums of the primary
+    /// header are never inherited
+    ///
+    /// # Arguments
+    ///
+    /// * `fits` - The file containing this HDU, whose first HDU is the
+    ///   primary
+    /// * `key` - The name of the keyword
+    ///
+    /// # Returns
+    ///
+    /// The value, or None if neither header has the keyword
+    ///
+    pub fn value_inherited<'a>(&'a self, fits: &'a FITS, key: &str) -> Option<&'a KeywordValue> {
+        if let Some(value) = self.value(key) {
+            return Some(value);
+        }
+        if !self.inherits() || is_local(key) {
+            return None;
+        }
+        fits.at(0).ok()?.value(key)
+    }
+
+    /// Return the value of a keyword converted to a given type,
+    /// following the INHERIT convention
+    ///
+    /// See `value_inherited` and `Header::get`
+    ///
+    /// # Arguments
+    ///
+    /// * `fits` - The file containing this HDU
+    /// * `key` - The name of the keyword
+    ///
+    pub fn get_inherited<T: FromKeywordValue>(
+        &self,
+        fits: &FITS,
+        key: &str,
+    ) -> Result<T, HeaderError> {
+        let value = self
+            .value_inherited(fits, key)
+            .ok_or_else(|| HeaderError::MissingKeyword(key.to_string()))?;
+        T::from_value(value).ok_or_else(|| HeaderError::UnexpectedValueType(key.to_string()))
+    }
+}
+
+impl FITS {
+    /// Header of an HDU merged with the keywords it inherits
+    ///
+    /// If the HDU has INHERIT = T, primary-header keywords it does not
+    /// define itself are added before its END card, in primary-header
+    /// order.  Otherwise the header is returned unchanged
+    ///
+    /// # Arguments
+    ///
+    /// * `index` - Index of the HDU
+    ///
+    /// # Returns
+    ///
+    /// A copy of the merged header
+    ///
+    pub fn inherited_header(&self, index: usize) -> Result<Header, FITSError> {
+        let hdu = self.at(index)?;
+        let mut header = hdu.header.clone();
+        if hdu.inherits() {
+            for kw in self.at(0)?.header.iter() {
+                if !is_local(&kw.name) && header.find(&kw.name).is_none() {
+                    header.insert_before_end(kw.clone());
+                }
+            }
+        }
+        Ok(header)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testutil::header;
+
+    #[test]
+    fn test_inherit() {
+        let mut fits = FITS::new();
+        fits.push_hdu(HDU {
+            header: header(&[
+                "SIMPLE  =                    T",
+                "BITPIX  =                    8",
+                "NAXIS   =                    0",
+                "FILTER  = 'V       '",
+                "EXPTIME =                 30.0",
+                "END",
+            ]),
+            ..Default::default()
+        });
+        let extension = [
+            "XTENSION= 'IMAGE   '",
+            "BITPIX  =                  -32",
+            "NAXIS   =                    0",
+            "PCOUNT  =                    0",
+            "GCOUNT  =                    1",
+            "EXPTIME =                 10.0",
+        ];
+        let mut cards = extension.to_vec();
+        cards.extend(["INHERIT =                    T", "END"]);
+        fits.push_hdu(HDU {
+            header: header(&cards),
+            ..Default::default()
+        });
+        let mut cards = extension.to_vec();
+        cards.push("END");
+        fits.push_hdu(HDU {
+            header: header(&cards),
+            ..Default::default()
+        });
+
+        let hdu = fits.at(1).unwrap();
+        assert!(hdu.inherits());
+        assert_eq!(hdu.get_inherited::<String>(&fits, "FILTER").unwrap(), "V");
+        assert_eq!(hdu.get_inherited::<f64>(&fits, "EXPTIME").unwrap(), 10.0);
+        assert_eq!(hdu.get_inherited::<i64>(&fits, "BITPIX").unwrap(), -32);
+        assert!(hdu.value_inherited(&fits, "SIMPLE").is_none());
+        assert!(!fits.at(0).unwrap().inherits());
+        assert!(fits
+            .at(2)
+            .unwrap()
+            .value_inherited(&fits, "FILTER")
+            .is_none());
+
+        let merged = fits.inherited_header(1).unwrap();
+        assert_eq!(merged.get::<String>("FILTER").unwrap(), "V");
+        assert_eq!(merged.get::<f64>("EXPTIME").unwrap(), 10.0);
+        assert!(merged.find("SIMPLE").is_none());
+        assert_eq!(merged.iter().last().unwrap().name, "END");
+        assert_eq!(fits.inherited_header(2).unwrap().len(), 7);
+    }
+}
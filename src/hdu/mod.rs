@@ -1,8 +1,16 @@
+mod inherit;
+mod sync;
+mod verify;
+
+use crate::image::find_decoder;
 use crate::types::HDUData;
-use crate::FITSBlock;
+use crate::BinTable;
+use crate::DuplicatePolicy;
+use crate::FITSError;
 use crate::Header;
 use crate::HeaderError;
 use crate::Image;
+use crate::Keyword;
 use crate::KeywordValue;
 use crate::Table;
 
@@ -17,6 +25,52 @@ use crate::Table;
 pub struct HDU {
     pub header: Header,
     pub data: HDUData,
+    /// Where the HDU was read from; None for HDUs created in memory or
+    /// streamed.  It is not updated when the HDU is modified
+    pub location: Option<HDULocation>,
+}
+
+/// Position of an HDU in the bytes it was read from
+///
+/// Offsets are from the start of the file; sizes exclude the fill to
+/// the next 2880-byte block
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct HDULocation {
+    /// Offset of the first header card
+    pub header_offset: usize,
+    /// Bytes of header, including the fill after END
+    pub header_size: usize,
+    /// Offset of the data unit
+    pub data_offset: usize,
+    /// Bytes of data, without fill
+    pub data_size: usize,
+}
+
+impl HDULocation {
+    /// Location of an HDU whose header starts at `offset`
+    pub(crate) fn new(offset: usize, header_size: usize, data_size: usize) -> Self {
+        HDULocation {
+            header_offset: offset,
+            header_size,
+            data_offset: offset + header_size,
+            data_size,
+        }
+    }
+
+    /// The same location moved `offset` bytes later
+    pub(crate) fn shifted(self, offset: usize) -> Self {
+        HDULocation::new(
+            self.header_offset + offset,
+            self.header_size,
+            self.data_size,
+        )
+    }
+
+    /// Offset just past the fill of the data unit, where the next HDU
+    /// starts
+    pub fn end(&self) -> usize {
+        self.data_offset + self.data_size.div_ceil(2880) * 2880
+    }
 }
 
 impl Default for HDU {
@@ -24,97 +78,489 @@ impl Default for HDU {
         HDU {
             header: Header::default(),
             data: HDUData::None,
+            location: None,
         }
     }
 }
 
 impl HDU {
+    /// Primary HDU without data (NAXIS = 0), as starts most
+    /// multi-extension files
+    ///
+    /// `FITS::to_bytes` adds one as needed, so this is only required
+    /// to give the primary header keywords of its own
+    pub fn empty_primary() -> Self {
+        HDU {
+            header: crate::fits::structure::empty_primary(),
+            data: HDUData::None,
+            location: None,
+        }
+    }
+
     // Get the value associated with the input keyword
     pub fn value(&self, key: &str) -> Option<&KeywordValue> {
-        self.header.iter().find(|x| x.name == key).map(|x| &x.value)
+        self.header.value(key)
     }
 
-    pub fn from_bytes(rawbytes: &[u8]) -> Result<(Self, usize), Box<dyn std::error::Error>> {
-        let mut record = HDU::default();
-        let mut nheaders = 0;
+    /// Name of the extension, from EXTNAME
+    pub fn extname(&self) -> Option<&str> {
+        self.header.extname()
+    }
 
-        let mut end_found: bool = false;
-        loop {
-            let header = FITSBlock::from_bytes(&rawbytes[nheaders * 2880..(nheaders + 1) * 2880])?;
-            for keyword in &header.0 {
-                if !keyword.name.is_empty() {
-                    record.header.push(keyword.clone());
-                }
-                if keyword.name == "END" {
-                    end_found = true;
-                    break;
-                }
-            }
-            nheaders += 1;
-            if end_found {
-                break;
-            }
-        }
-        let mut offset = nheaders * 2880;
+    /// Version of the extension, from EXTVER (1 if absent)
+    pub fn extver(&self) -> i64 {
+        self.header.extver()
+    }
+
+    pub fn from_bytes(rawbytes: &[u8]) -> Result<(Self, usize), FITSError> {
+        Self::parse(rawbytes, None, true, DuplicatePolicy::First)
+    }
+
+    /// Read an HDU without converting image pixels to native byte order
+    ///
+    /// Pixels are decoded when accessed; see `Image::pixels_iter`
+    ///
+    /// # Arguments
+    ///
+    /// * `rawbytes` - Bytes starting at the HDU header
+    ///
+    /// # Returns
+    ///
+    /// The HDU and number of bytes consumed
+    ///
+    pub fn from_bytes_deferred(rawbytes: &[u8]) -> Result<(Self, usize), FITSError> {
+        Self::parse(rawbytes, None, false, DuplicatePolicy::First)
+    }
+
+    /// Read an HDU, optionally recovering from common violations of
+    /// the standard
+    ///
+    /// When recovering, invalid cards are kept as commentary text,
+    /// misplaced mandatory keywords are moved into order, and data that
+    /// still cannot be interpreted, or is truncated, is kept as raw
+    /// bytes.  The duplicate policy is set on the header before the
+    /// data unit is decoded, so the data is interpreted with the
+    /// keywords lookups return
+    ///
+    /// # Arguments
+    ///
+    /// * `rawbytes` - Bytes starting at the HDU header
+    /// * `warnings` - If given, recover, appending a description of
+    ///   each recovered problem here
+    /// * `native` - Convert image pixels to native byte order
+    /// * `duplicates` - See `apply_duplicate_policy`
+    ///
+    /// # Returns
+    ///
+    /// The HDU and number of bytes consumed
+    ///
+    pub(crate) fn from_bytes_with(
+        rawbytes: &[u8],
+        warnings: Option<&mut Vec<String>>,
+        native: bool,
+        duplicates: DuplicatePolicy,
+    ) -> Result<(Self, usize), FITSError> {
+        Self::parse(rawbytes, warnings, native, duplicates)
+    }
+
+    /// Read only the header of an HDU
+    ///
+    /// # Returns
+    ///
+    /// The header and the offset of the data unit in `rawbytes`
+    ///
+    pub(crate) fn header_from_bytes(rawbytes: &[u8]) -> Result<(Header, usize), FITSError> {
+        read_header(rawbytes, None)
+    }
+
+    /// Decode the data unit described by an already-read header
+    ///
+    /// # Arguments
+    ///
+    /// * `header` - The HDU header
+    /// * `rawbytes` - Bytes following the header
+    /// * `native` - Convert image pixels to native byte order
+    ///
+    pub(crate) fn from_header(
+        header: Header,
+        rawbytes: &[u8],
+        native: bool,
+    ) -> Result<Self, FITSError> {
+        let data = if header.is_empty() {
+            HDUData::None
+        } else {
+            parse_data(&header, rawbytes, native)?.0
+        };
+        Ok(HDU {
+            header,
+            data,
+            location: None,
+        })
+    }
+
+    fn parse(
+        rawbytes: &[u8],
+        mut warnings: Option<&mut Vec<String>>,
+        native: bool,
+        duplicates: DuplicatePolicy,
+    ) -> Result<(Self, usize), FITSError> {
+        let (mut header, mut offset) = read_header(rawbytes, warnings.as_deref_mut())?;
+        apply_duplicate_policy(&mut header, duplicates)?;
+        let mut record = HDU {
+            header,
+            data: HDUData::None,
+            location: Some(HDULocation::new(0, offset, 0)),
+        };
         // Use the keywords to determine the data type
         if record.header.is_empty() {
             return Ok((record, offset));
         }
 
-        match record.header[0].name.as_str() {
-            "SIMPLE" => {
-                // This is a primary header
-                // read in an image
-                let (image, nbytes) =
-                    crate::Image::from_bytes(&record.header, &rawbytes[offset..])?;
-                record.data = image;
-                offset += nbytes;
+        let data = &rawbytes[offset..];
+        let (hdudata, nbytes) = match (
+            parse_data(&record.header, data, native),
+            warnings.as_deref_mut(),
+        ) {
+            (Ok(parsed), _) => parsed,
+            (Err(e), None) => return Err(e),
+            (Err(e), Some(w)) => recover_data(&mut record.header, data, e, w),
+        };
+        record.data = hdudata;
+        record.location = Some(HDULocation::new(0, offset, nbytes));
+        offset += nbytes;
+
+        if offset % 2880 != 0 {
+            offset += 2880 - offset % 2880;
+        }
+        if offset > rawbytes.len() {
+            if let Some(w) = warnings {
+                w.push("data unit is not padded to a multiple of 2880 bytes".to_string());
             }
-            "XTENSION" => {
-                match &record.header[0].value {
-                    KeywordValue::String(value) => {
-                        match value.as_str() {
-                            "IMAGE" => {
-                                // This is an image extension
-                                // read in an image
-                                let (image, nbytes) =
-                                    Image::from_bytes(&record.header, &rawbytes[offset..])?;
-                                record.data = image;
-                                offset += nbytes;
-                            }
-                            "TABLE" => {
-                                // This is a table extension
-                                // read in the table
-                                // read in an image
-                                let (image, nbytes) =
-                                    Table::from_bytes(&record.header, &rawbytes[offset..])?;
-                                record.data = image;
-                                offset += nbytes;
-                            }
-                            _ => {
-                                // Unsupported extension ; report error
-                                return Err(Box::new(HeaderError::UnsupportedExtension(
-                                    value.clone(),
+            offset = rawbytes.len();
+        }
+        Ok((record, offset))
+    }
+}
+
+/// Set the policy for duplicated keywords on a header just read
+///
+/// # Returns
+///
+/// Nothing, or an error for `DuplicatePolicy::Error` if the header
+/// has duplicated value keywords
+///
+pub(crate) fn apply_duplicate_policy(
+    header: &mut Header,
+    policy: DuplicatePolicy,
+) -> Result<(), FITSError> {
+    if policy == DuplicatePolicy::Error {
+        if let Some(name) = header.duplicates().first() {
+            return Err(FITSError::from(HeaderError::DuplicateKeyword(
+                name.to_string(),
+            )));
+        }
+    }
+    header.set_duplicate_policy(policy);
+    Ok(())
+}
+
+/// Read the header cards of an HDU, up to and including END
+///
+/// # Arguments
+///
+/// * `rawbytes` - Bytes starting at the HDU header
+/// * `warnings` - If given, recoverable problems are appended here
+///   instead of failing
+///
+/// # Returns
+///
+/// The header and the offset of the data unit in `rawbytes`
+///
+pub(crate) fn read_header(
+    rawbytes: &[u8],
+    mut warnings: Option<&mut Vec<String>>,
+) -> Result<(Header, usize), FITSError> {
+    let mut header = Header::default();
+
+    // Header cards up to and including END
+    let mut pos = 0;
+    loop {
+        if pos + 80 > rawbytes.len() {
+            let Some(w) = warnings.as_deref_mut() else {
+                return Err(FITSError::from(HeaderError::GenericError(
+                    "header ends before END card".to_string(),
+                )));
+            };
+            w.push("header ends before END card".to_string());
+            break;
+        }
+        let card = &rawbytes[pos..pos + 80];
+        pos += 80;
+        let keyword = match (Keyword::new(card), warnings.as_deref_mut()) {
+            (Ok(keyword), _) => keyword,
+            (Err(e), None) => return Err(e),
+            (Err(e), Some(w)) => {
+                w.push(format!("card {}: {}; kept as text", pos / 80, e));
+                text_card(card)
+            }
+        };
+        // Blank cards are kept for round-trip fidelity
+        let end = keyword.name == "END";
+        header.push_card(keyword);
+        if end {
+            break;
+        }
+    }
+    let mut offset = pos.div_ceil(2880) * 2880;
+    if offset > rawbytes.len() {
+        let Some(w) = warnings else {
+            return Err(FITSError::from(HeaderError::InvalidHeader));
+        };
+        w.push("header is not padded to a multiple of 2880 bytes".to_string());
+        offset = rawbytes.len();
+    }
+    Ok((header, offset))
+}
+
+/// Whether a card is the END card that terminates a header
+///
+/// The card is identified by its keyword name, as when parsing the
+/// header; the rest of the card should be blank but is not checked
+pub(crate) fn is_end_card(card: &[u8]) -> bool {
+    card.starts_with(b"END     ")
+}
+
+/// Keep an invalid card as commentary text
+fn text_card(card: &[u8]) -> Keyword {
+    let text = String::from_utf8_lossy(card);
+    let (name, rest) = text.split_at(text.char_indices().nth(8).map_or(text.len(), |(i, _)| i));
+    Keyword {
+        name: name.trim().to_string(),
+        value: KeywordValue::CommentText(rest.trim_end().to_string()),
+        ..Default::default()
+    }
+}
+
+/// Bytes in the data unit a header describes, without fill
+///
+/// Images accepted by a registered `PixelDecoder` use its `data_size`,
+/// so the size matches what reading the data unit consumes; other
+/// HDUs use `Header::data_size`
+///
+/// # Returns
+///
+/// The size, or an error if the header gives no valid size, e.g. if
+/// it overflows
+///
+pub(crate) fn data_unit_size(header: &Header) -> Result<usize, FITSError> {
+    let image = match header.first() {
+        Some(kw) if kw.name == "SIMPLE" => true,
+        Some(kw) if kw.name == "XTENSION" => kw.value == KeywordValue::String("IMAGE".into()),
+        _ => false,
+    };
+    let size = match image.then(|| find_decoder(header)).flatten() {
+        Some(decoder) => decoder.data_size(header),
+        None => header.data_size(),
+    };
+    size.ok_or_else(|| FITSError::from(HeaderError::GenericError("invalid data size".to_string())))
+}
+
+/// Interpret the data unit described by a header
+///
+/// # Arguments
+///
+/// * `header` - The HDU header
+/// * `rawbytes` - Bytes following the header
+/// * `native` - Convert image pixels to native byte order; if false they
+///   are kept as stored and decoded on access
+///
+/// # Returns
+///
+/// The data and number of bytes it occupies, without fill
+///
+fn parse_data(
+    header: &Header,
+    rawbytes: &[u8],
+    native: bool,
+) -> Result<(HDUData, usize), FITSError> {
+    let image = match native {
+        true => Image::from_bytes,
+        false => Image::from_bytes_deferred,
+    };
+    match header[0].name.as_str() {
+        "SIMPLE" => {
+            // This is a primary header
+            // read in an image
+            image(header, rawbytes)
+        }
+        "XTENSION" => {
+            match &header[0].value {
+                KeywordValue::String(value) => {
+                    match value.as_str() {
+                        // This is an image extension
+                        "IMAGE" => image(header, rawbytes),
+                        // This is a table extension
+                        "TABLE" => Table::from_bytes(header, rawbytes),
+                        // This is a binary table extension
+                        "BINTABLE" => BinTable::from_bytes(header, rawbytes),
+                        _ => {
+                            // Unsupported extension; keep the raw data so the
+                            // following HDUs can still be read
+                            let nbytes = header
+                                .data_size()
+                                .ok_or_else(|| HeaderError::UnsupportedExtension(value.clone()))?;
+                            if rawbytes.len() < nbytes {
+                                return Err(FITSError::from(HeaderError::GenericError(
+                                    "not enough data bytes for extension".to_string(),
                                 )));
                             }
+                            let raw = rawbytes[0..nbytes].to_vec();
+                            Ok((
+                                HDUData::Unknown {
+                                    xtension: value.clone(),
+                                    raw,
+                                },
+                                nbytes,
+                            ))
                         }
                     }
-                    _ => {
-                        // Unsupported extension ; report error
-                        return Err(Box::new(HeaderError::UnsupportedExtension(
-                            "Extension Value not a string".to_string(),
-                        )));
-                    }
+                }
+                _ => {
+                    // Unsupported extension ; report error
+                    Err(FITSError::from(HeaderError::UnsupportedExtension(
+                        "Extension Value not a string".to_string(),
+                    )))
                 }
             }
-            _ => {
-                // This is a header
+        }
+        _ => {
+            // This is a header
+            Ok((HDUData::None, 0))
+        }
+    }
+}
+
+/// Header with the mandatory keywords moved into the order required
+/// by Section 4.4.1 of FITS standard, version 4
+fn canonical_order(header: &Header) -> Header {
+    let naxis = header.get::<usize>("NAXIS").unwrap_or(0);
+    let mut names = vec!["BITPIX".to_string(), "NAXIS".to_string()];
+    names.extend((1..=naxis).map(|i| format!("NAXIS{}", i)));
+    if header[0].name == "XTENSION" {
+        names.extend(["PCOUNT", "GCOUNT", "TFIELDS"].map(String::from));
+    }
+    let mut keywords = vec![header[0].clone()];
+    for name in &names {
+        keywords.extend(header.find(name).cloned());
+    }
+    keywords.extend(
+        header
+            .iter()
+            .skip(1)
+            .filter(|kw| !names.contains(&kw.name))
+            .cloned(),
+    );
+    let mut reordered = Header::new(keywords);
+    reordered.set_duplicate_policy(header.duplicate_policy());
+    reordered
+}
+
+/// Recover what can be read from a data unit that failed to parse
+///
+/// # Arguments
+///
+/// * `header` - The HDU header; replaced by a reordered copy if that helps
+/// * `rawbytes` - Bytes following the header
+/// * `err` - The error from parsing the data with the original header
+/// * `warnings` - Descriptions of recovered problems are appended here
+///
+/// # Returns
+///
+/// The data and number of bytes consumed from `rawbytes`
+///
+fn recover_data(
+    header: &mut Header,
+    rawbytes: &[u8],
+    err: FITSError,
+    warnings: &mut Vec<String>,
+) -> (HDUData, usize) {
+    let reordered = canonical_order(header);
+    let moved = reordered
+        .iter()
+        .zip(header.iter())
+        .any(|(a, b)| a.name != b.name);
+
+    let xtension = match &header[0].value {
+        KeywordValue::String(s) => s.clone(),
+        _ => header[0].name.clone(),
+    };
+    // The declared size may be corrupt, so a truncated data unit is
+    // kept as the bytes that are there rather than filled out
+    let nbytes = reordered.data_size().unwrap_or(0);
+    if rawbytes.len() < nbytes {
+        warnings.push(format!(
+            "data unit truncated: expected {} bytes, found {}; kept as raw bytes",
+            nbytes,
+            rawbytes.len()
+        ));
+        let raw = rawbytes.to_vec();
+        return (HDUData::Unknown { xtension, raw }, rawbytes.len());
+    }
+
+    match parse_data(&reordered, rawbytes, true) {
+        Ok((hdudata, _)) => {
+            if moved {
+                warnings.push("mandatory keywords out of order; reordered".to_string());
+                *header = reordered;
             }
-        } // end of parsing data 1st keyword type
-        if offset % 2880 != 0 {
-            offset += 2880 - offset % 2880;
+            (hdudata, nbytes)
+        }
+        Err(_) => {
+            warnings.push(format!("could not read data ({}); kept as raw bytes", err));
+            let raw = rawbytes[0..nbytes].to_vec();
+            (HDUData::Unknown { xtension, raw }, nbytes)
         }
-        Ok((record, offset))
+    }
+}
+
+impl HDU {
+    /// Serialize the HDU as it would be stored in a file
+    ///
+    /// The header is followed by the data unit, each padded to a
+    /// multiple of 2880 bytes.  Header cards read from a file and not
+    /// since edited are written as their original bytes, so an
+    /// unmodified HDU is reproduced byte for byte.
+    ///
+    /// # Note: the header is written as-is, so structural keywords
+    ///   (BITPIX, NAXISn, PCOUNT, ...) must describe the data; see
+    ///   `sync_structural_keywords`
+    ///
+    /// # Returns
+    ///
+    /// HDU bytes
+    ///
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = self.header.to_bytes();
+        bytes.extend(self.data_bytes());
+        bytes
+    }
+
+    /// Serialize the data unit, padded to a multiple of 2880 bytes
+    pub(crate) fn data_bytes(&self) -> Vec<u8> {
+        let (mut data, fill) = match &self.data {
+            HDUData::None => (Vec::new(), 0u8),
+            HDUData::Image(image) => (image.to_bytes(), 0),
+            HDUData::BinTable(table) => {
+                let mut data = table.rawbytes.clone();
+                data.extend_from_slice(&table.heap);
+                (data, 0)
+            }
+            // ASCII tables are padded with blanks, not zeros
+            HDUData::Table(table) => (table.rawbytes.clone(), b' '),
+            HDUData::Unknown { raw, .. } => (raw.clone(), 0),
+        };
+        data.resize(data.len().div_ceil(2880) * 2880, fill);
+        data
     }
 }
 
@@ -126,3 +572,75 @@ impl std::fmt::Display for HDU {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testutil::{cards, hdu_bytes};
+
+    #[test]
+    fn test_roundtrip() {
+        let data: Vec<u8> = [1i16, -2, 3, 300, -32768, 32767]
+            .iter()
+            .flat_map(|v| v.to_be_bytes())
+            .collect();
+        let raw = hdu_bytes(
+            &[
+                "SIMPLE  =                    T / conforms",
+                "BITPIX  =                   16",
+                "NAXIS   =                    2",
+                "NAXIS1  =                    3",
+                "NAXIS2  =                    2",
+                "EXPTIME =         1.50000E+01  /   seconds",
+                "OBSERVER= 'E. Hubble'         /name",
+                "COMMENT   free text",
+                "END",
+            ],
+            &data,
+        );
+
+        let (mut hdu, nbytes) = HDU::from_bytes(&raw).unwrap();
+        assert_eq!(nbytes, 5760);
+        assert_eq!(hdu.to_bytes(), raw);
+
+        // Only the edited card is regenerated
+        hdu.header[6].value = KeywordValue::String("Leavitt".to_string());
+        let bytes = hdu.to_bytes();
+        assert_eq!(
+            bytes[480..560],
+            cards(&["OBSERVER= 'Leavitt '           / name"])
+        );
+        assert_eq!(bytes[0..480], raw[0..480]);
+        assert_eq!(bytes[560..], raw[560..]);
+    }
+
+    #[test]
+    fn test_unknown_extension() {
+        let data: Vec<u8> = (1..=10u8).collect();
+        let mut raw = hdu_bytes(
+            &[
+                "XTENSION= 'FOREIGN '",
+                "BITPIX  =                    8",
+                "NAXIS   =                    1",
+                "NAXIS1  =                   10",
+                "PCOUNT  =                    0",
+                "GCOUNT  =                    1",
+                "END",
+            ],
+            &data,
+        );
+        // Start of a following HDU
+        raw.extend(b"SIMPLE  =");
+
+        let (hdu, nbytes) = HDU::from_bytes(&raw).unwrap();
+        assert_eq!(nbytes, 5760);
+        match &hdu.data {
+            HDUData::Unknown { xtension, raw } => {
+                assert_eq!(xtension, "FOREIGN");
+                assert_eq!(raw, &(1..=10u8).collect::<Vec<u8>>());
+            }
+            _ => panic!("expected unknown extension"),
+        }
+        assert_eq!(hdu.to_bytes(), raw[..5760]);
+    }
+}
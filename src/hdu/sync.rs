@@ -0,0 +1,400 @@
+//! Structural keywords kept consistent with the data unit
+//!
+//! The mandatory keywords (BITPIX, NAXISn, PCOUNT, GCOUNT, TFIELDS,
+//! and the TFORMn and TBCOLn column layout) describe the data unit
+//! and are easily left stale when the data is edited, e.g. an image
+//! is cropped or a table gains a column.  They are rewritten here from
+//! the data itself.  See Sections 4.4.1 and 7 of FITS standard,
+//! version 4
+
+use super::HDU;
+use crate::BinTable;
+use crate::HDUData;
+use crate::Header;
+use crate::Image;
+use crate::Keyword;
+use crate::KeywordValue;
+use crate::TForm;
+use crate::Table;
+use std::borrow::Cow;
+
+/// Put a keyword with a value at a position, moving it there if it is
+/// elsewhere; a new keyword gets the comment
+///
+/// # Returns
+///
+/// True if the header changed
+///
+fn place(header: &mut Header, pos: usize, name: &str, value: KeywordValue, comment: &str) -> bool {
+    if header.iter().nth(pos).is_some_and(|kw| kw.name == name) {
+        if header[pos].value == value {
+            return false;
+        }
+        header[pos].value = value;
+        return true;
+    }
+    let mut kw = match header.iter().position(|kw| kw.name == name) {
+        Some(existing) => header.remove(existing),
+        None => Keyword {
+            name: name.to_string(),
+            comment: Some(comment.to_string()),
+            ..Default::default()
+        },
+    };
+    kw.value = value;
+    header.insert(pos.min(header.len()), kw);
+    true
+}
+
+/// Set a keyword unless it already holds an equal value, as judged by
+/// `same`; None removes the keyword
+///
+/// # Returns
+///
+/// True if the header changed
+///
+fn update(
+    header: &mut Header,
+    name: &str,
+    value: Option<KeywordValue>,
+    same: impl Fn(&Header, &KeywordValue) -> bool,
+) -> bool {
+    match value {
+        Some(value) if same(header, &value) => false,
+        Some(value) => {
+            header.set(name, value);
+            true
+        }
+        None => remove_where(header, |kw| kw.name == name),
+    }
+}
+
+/// Remove every keyword matching a predicate
+///
+/// # Returns
+///
+/// True if any keyword was removed
+///
+fn remove_where(header: &mut Header, matches: impl Fn(&Keyword) -> bool) -> bool {
+    let mut changed = false;
+    while let Some(pos) = header.iter().position(&matches) {
+        header.remove(pos);
+        changed = true;
+    }
+    changed
+}
+
+/// Remove members of an indexed keyword family beyond `n`, e.g.
+/// NAXIS3 of a two-dimensional image
+fn truncate_family(header: &mut Header, prefix: &str, n: usize) -> bool {
+    let stale: Vec<String> = header
+        .indexed(prefix)
+        .filter(|(i, _)| *i > n)
+        .map(|(_, kw)| kw.name.clone())
+        .collect();
+    remove_where(header, |kw| stale.contains(&kw.name))
+}
+
+/// Float value, for keywords that may be written as integers
+fn same_number(name: &str) -> impl Fn(&Header, &KeywordValue) -> bool + '_ {
+    move |header, value| match value {
+        KeywordValue::Float(v) => header.get::<f64>(name).ok() == Some(*v),
+        other => header.value(name) == Some(other),
+    }
+}
+
+/// String value, ignoring trailing blanks
+fn same_text(name: &str) -> impl Fn(&Header, &KeywordValue) -> bool + '_ {
+    move |header, value| match (header.value(name), value) {
+        (Some(KeywordValue::String(old)), KeywordValue::String(new)) => {
+            old.trim_end() == new.trim_end()
+        }
+        _ => false,
+    }
+}
+
+/// Keywords common to all extensions after the axes: PCOUNT and GCOUNT
+fn place_counts(header: &mut Header, naxis: usize, pcount: usize) -> bool {
+    place(
+        header,
+        3 + naxis,
+        "PCOUNT",
+        KeywordValue::Int(pcount as i64),
+        "number of parameters",
+    ) | place(
+        header,
+        4 + naxis,
+        "GCOUNT",
+        KeywordValue::Int(1),
+        "number of groups",
+    )
+}
+
+/// BITPIX and the NAXISn axis lengths
+fn place_axes(header: &mut Header, bitpix: i64, axes: &[usize]) -> bool {
+    let mut changed = place(
+        header,
+        1,
+        "BITPIX",
+        KeywordValue::Int(bitpix),
+        "array data type",
+    );
+    changed |= place(
+        header,
+        2,
+        "NAXIS",
+        KeywordValue::Int(axes.len() as i64),
+        "number of array dimensions",
+    );
+    for (i, n) in axes.iter().enumerate() {
+        changed |= place(
+            header,
+            3 + i,
+            &format!("NAXIS{}", i + 1),
+            KeywordValue::Int(*n as i64),
+            "length of data axis",
+        );
+    }
+    changed | truncate_family(header, "NAXIS", axes.len())
+}
+
+fn sync_image(header: &mut Header, image: &Image, primary: bool) -> bool {
+    let mut changed = place_axes(header, image.pixeltype.to_i64(), &image.axes);
+    if !primary {
+        changed |= place_counts(header, image.axes.len(), 0);
+    }
+    // Unsigned pixels are stored with an offset (see `Bitpix`)
+    let bzero = image.bzero + image.pixeltype.unsigned_offset().unwrap_or(0.0);
+    changed |= update(
+        header,
+        "BSCALE",
+        (image.bscale != 1.0).then_some(KeywordValue::Float(image.bscale)),
+        same_number("BSCALE"),
+    );
+    changed
+        | update(
+            header,
+            "BZERO",
+            (bzero != 0.0).then_some(KeywordValue::Float(bzero)),
+            same_number("BZERO"),
+        )
+}
+
+/// TFIELDS and the TFORMn, TTYPEn, and (for ASCII tables) TBCOLn
+/// column keywords
+fn sync_columns(
+    header: &mut Header,
+    ttype: &[Option<String>],
+    tform: &[String],
+    tbcol: Option<&[usize]>,
+) -> bool {
+    let ncols = tform.len();
+    let mut changed = place(
+        header,
+        7,
+        "TFIELDS",
+        KeywordValue::Int(ncols as i64),
+        "number of table fields",
+    );
+    for (col, (ttype, tform)) in ttype.iter().zip(tform).enumerate() {
+        let n = col + 1;
+        let name = format!("TFORM{}", n);
+        let value = KeywordValue::String(tform.clone());
+        changed |= update(header, &name, Some(value), same_text(&name));
+        if let Some(ttype) = ttype {
+            let name = format!("TTYPE{}", n);
+            let value = KeywordValue::String(ttype.clone());
+            changed |= update(header, &name, Some(value), same_text(&name));
+        }
+        if let Some(tbcol) = tbcol {
+            let name = format!("TBCOL{}", n);
+            let value = KeywordValue::Int(tbcol[col] as i64);
+            changed |= update(header, &name, Some(value), same_number(&name));
+        }
+    }
+    for prefix in ["TFORM", "TTYPE", "TBCOL"] {
+        changed |= truncate_family(header, prefix, ncols);
+    }
+    changed
+}
+
+fn sync_bintable(header: &mut Header, table: &BinTable) -> bool {
+    let mut changed = place_axes(header, 8, &[table.rowbytes, table.nrows]);
+    changed |= place_counts(header, 2, table.heap.len());
+    let tform: Vec<String> = table.tform.iter().map(TForm::to_string).collect();
+    changed |= sync_columns(header, &table.ttype, &tform, None);
+    // THEAP is only needed if the heap does not follow the main table
+    let theap = (table.theap != table.rawbytes.len() || header.find("THEAP").is_some())
+        .then_some(KeywordValue::Int(table.theap as i64));
+    changed | update(header, "THEAP", theap, same_number("THEAP"))
+}
+
+fn sync_table(header: &mut Header, table: &Table) -> bool {
+    let mut changed = place_axes(header, 8, &[table.rowbytes, table.nrows]);
+    changed |= place_counts(header, 2, 0);
+    let tform: Vec<String> = table.tform.iter().map(|t| t.to_string()).collect();
+    changed | sync_columns(header, &table.ttype, &tform, Some(&table.tbcol))
+}
+
+/// Rewrite the structural keywords of a header to describe data
+///
+/// # Returns
+///
+/// True if the header changed
+///
+fn sync(header: &mut Header, data: &HDUData) -> bool {
+    let primary = header.first().is_some_and(|kw| kw.name == "SIMPLE");
+    match data {
+        HDUData::Image(image) => sync_image(header, image, primary),
+        HDUData::BinTable(table) if !primary => sync_bintable(header, table),
+        HDUData::Table(table) if !primary => sync_table(header, table),
+        _ => false,
+    }
+}
+
+impl HDU {
+    /// Rewrite the structural keywords of the header to describe the
+    /// data unit
+    ///
+    /// For images, BITPIX, NAXIS, NAXISn, BSCALE and BZERO (including
+    /// the offset of unsigned pixel types), and for extensions PCOUNT
+    /// and GCOUNT, are set from the image.  For tables, NAXIS1 (row
+    /// width), NAXIS2 (rows), PCOUNT (heap size), TFIELDS, THEAP, and
+    /// the TFORMn, TTYPEn, and TBCOLn column keywords are set from the
+    /// table; these keywords beyond the last column are removed.
+    /// Mandatory keywords are moved to the position the standard
+    /// requires.  Headers without data, and of unknown extensions, are
+    /// left as they are.
+    ///
+    /// Keywords that already hold the right value are not touched, so
+    /// an unmodified HDU read from a file is unchanged.  `FITS::to_bytes`
+    /// and `FITS::to_file` apply this to the headers they write
+    ///
+    /// # Returns
+    ///
+    /// True if any keyword changed
+    ///
+    pub fn sync_structural_keywords(&mut self) -> bool {
+        sync(&mut self.header, &self.data)
+    }
+
+    /// Header with structural keywords describing the data unit, see
+    /// `sync_structural_keywords`; borrowed if already consistent
+    pub(crate) fn synced_header(&self) -> Cow<'_, Header> {
+        let mut header = self.header.clone();
+        match sync(&mut header, &self.data) {
+            true => Cow::Owned(header),
+            false => Cow::Borrowed(&self.header),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testutil::{hdu_bytes, header, image};
+    use crate::Bitpix;
+    use crate::FITS;
+
+    fn names(header: &Header) -> Vec<&str> {
+        header.iter().map(|kw| kw.name.as_str()).collect()
+    }
+
+    #[test]
+    fn test_sync_image() {
+        let mut hdu = HDU {
+            header: header(&[
+                "XTENSION= 'IMAGE   '",
+                "BITPIX  =                    8",
+                "NAXIS   =                    3",
+                "NAXIS1  =                   10",
+                "NAXIS2  =                   10",
+                "NAXIS3  =                    2",
+                "GCOUNT  =                    1",
+                "PCOUNT  =                    0",
+                "BZERO   =                  0.0",
+                "EXTNAME = 'SCI     '",
+                "END",
+            ]),
+            data: HDUData::Image(Box::new(image(Bitpix::Uint16, &[2, 3], &[0u16; 6]))),
+            location: None,
+        };
+        assert!(matches!(hdu.synced_header(), Cow::Owned(_)));
+        assert!(hdu.sync_structural_keywords());
+        assert_eq!(
+            names(&hdu.header),
+            [
+                "XTENSION", "BITPIX", "NAXIS", "NAXIS1", "NAXIS2", "PCOUNT", "GCOUNT", "BZERO",
+                "EXTNAME", "END"
+            ]
+        );
+        assert_eq!(hdu.header.get::<i64>("BITPIX").unwrap(), 16);
+        assert_eq!(hdu.header.get::<usize>("NAXIS2").unwrap(), 3);
+        assert_eq!(hdu.header.get::<f64>("BZERO").unwrap(), 32768.0);
+        assert!(!hdu.sync_structural_keywords());
+        assert!(matches!(hdu.synced_header(), Cow::Borrowed(_)));
+
+        // The written file reads back with the edited shape
+        let mut fits = FITS::new();
+        fits.push_hdu(hdu);
+        let HDUData::Image(im) = &mut fits[0].data else {
+            unreachable!()
+        };
+        im.axes = vec![6];
+        let read = FITS::from_bytes(&fits.to_bytes().unwrap()).unwrap();
+        let HDUData::Image(im) = &read[0].data else {
+            panic!("expected image");
+        };
+        assert_eq!(im.axes, [6]);
+        assert_eq!(im.pixeltype, Bitpix::Uint16);
+    }
+
+    #[test]
+    fn test_sync_table() {
+        let cards = [
+            "XTENSION= 'BINTABLE'",
+            "BITPIX  =                    8",
+            "NAXIS   =                    2",
+            "NAXIS1  =                    4",
+            "NAXIS2  =                    2",
+            "PCOUNT  =                    0",
+            "GCOUNT  =                    1",
+            "TFIELDS =                    1",
+            "TTYPE1  = 'COUNTS  '",
+            "TFORM1  = '1J      '",
+            "END",
+        ];
+        let raw = hdu_bytes(&cards, &[0, 0, 0, 1, 0, 0, 0, 2]);
+        let (mut hdu, _) = HDU::from_bytes(&raw).unwrap();
+        assert!(!hdu.sync_structural_keywords());
+
+        let HDUData::BinTable(table) = &mut hdu.data else {
+            panic!("expected binary table");
+        };
+        table
+            .add_column(
+                "FLAG",
+                "1L",
+                &[
+                    crate::BinTableValue::Logical(true),
+                    crate::BinTableValue::Logical(false),
+                ],
+            )
+            .unwrap();
+        assert!(hdu.sync_structural_keywords());
+        assert_eq!(hdu.header.get::<usize>("NAXIS1").unwrap(), 5);
+        assert_eq!(hdu.header.get::<usize>("TFIELDS").unwrap(), 2);
+        assert_eq!(hdu.header.get::<String>("TTYPE2").unwrap(), "FLAG");
+        assert_eq!(hdu.header.get::<String>("TFORM2").unwrap(), "1L");
+
+        let HDUData::BinTable(table) = &mut hdu.data else {
+            unreachable!()
+        };
+        table.remove_column("COUNTS").unwrap();
+        assert!(hdu.sync_structural_keywords());
+        assert_eq!(hdu.header.get::<usize>("NAXIS1").unwrap(), 1);
+        assert_eq!(hdu.header.get::<String>("TTYPE1").unwrap(), "FLAG");
+        assert!(hdu.header.find("TFORM2").is_none());
+        assert!(hdu.header.find("TTYPE2").is_none());
+    }
+}
@@ -0,0 +1,155 @@
+use super::HDU;
+use crate::fits::structure::check_mandatory;
+use crate::HDUData;
+use crate::KeywordValue;
+use crate::Severity;
+use crate::TForm;
+use crate::VerifyReport;
+
+/// 32-bit ones' complement sum of big-endian words
+///
+/// See Appendix J of FITS standard, version 4.  A correctly
+/// checksummed HDU sums to all ones (negative zero)
+///
+/// # Arguments
+///
+/// * `bytes` - Bytes to sum; a multiple of 4 bytes long
+///
+pub(crate) fn checksum(bytes: &[u8]) -> u32 {
+    let mut sum: u64 = 0;
+    for word in bytes.chunks_exact(4) {
+        sum += u32::from_be_bytes(word.try_into().unwrap()) as u64;
+    }
+    while sum >> 32 != 0 {
+        sum = (sum & 0xFFFF_FFFF) + (sum >> 32);
+    }
+    sum as u32
+}
+
+impl HDU {
+    /// Check the HDU against the FITS standard
+    ///
+    /// Checks the order of the mandatory keywords, that header cards
+    /// are printable ASCII with valid keyword names, that TFORMn widths
+    /// add up to NAXIS1 for binary tables, and the CHECKSUM and DATASUM
+    /// keywords if present.  Issues are reported with `hdu` 0; see
+    /// `FITS::verify` for checks of a whole file
+    ///
+    /// # Returns
+    ///
+    /// Report of all issues found
+    ///
+    pub fn verify(&self) -> VerifyReport {
+        let mut report = VerifyReport::default();
+        let header = &self.header;
+        if header.is_empty() {
+            report.push(Severity::Error, None, "header is empty".to_string());
+            return report;
+        }
+
+        // Card number of each keyword; continued strings span several cards
+        let mut cards = Vec::with_capacity(header.len());
+        let mut ncard = 1;
+        for kw in header.iter() {
+            let bytes = kw.to_bytes();
+            let card = ncard;
+            cards.push(card);
+            ncard += bytes.len() / 80;
+            if bytes.iter().any(|b| !(0x20..=0x7E).contains(b)) {
+                report.push(
+                    Severity::Error,
+                    Some(card),
+                    "card contains characters other than printable ASCII".to_string(),
+                );
+            }
+            if !kw
+                .name
+                .bytes()
+                .all(|c| c.is_ascii_uppercase() || c.is_ascii_digit() || b" _-".contains(&c))
+            {
+                report.push(
+                    Severity::Error,
+                    Some(card),
+                    format!("invalid keyword name \"{}\"", kw.name),
+                );
+            }
+            if kw.overflows_card() {
+                report.push(
+                    Severity::Error,
+                    Some(card),
+                    format!("HIERARCH keyword {} does not fit on the card", kw.name),
+                );
+            }
+        }
+        let card_of = |name: &str| {
+            header
+                .iter()
+                .position(|kw| kw.name == name)
+                .map(|p| cards[p])
+        };
+
+        let primary = header[0].name == "SIMPLE";
+        if let Err((pos, message)) = check_mandatory(header, primary) {
+            report.push(Severity::Error, cards.get(pos).copied(), message);
+        }
+
+        if header.value("XTENSION") == Some(&KeywordValue::String("BINTABLE".to_string())) {
+            let tfields = header.get::<usize>("TFIELDS").unwrap_or(0);
+            let mut width = Some(0);
+            for i in 1..=tfields {
+                let key = format!("TFORM{}", i);
+                match header.get::<String>(&key).ok().map(|s| s.parse::<TForm>()) {
+                    Some(Ok(tform)) => width = width.map(|w| w + tform.size()),
+                    Some(Err(e)) => {
+                        report.push(Severity::Error, card_of(&key), e.to_string());
+                        width = None;
+                    }
+                    None => {
+                        report.push(Severity::Error, None, format!("missing {}", key));
+                        width = None;
+                    }
+                }
+            }
+            let naxis1 = header.get::<usize>("NAXIS1").ok();
+            if let (Some(width), Some(naxis1)) = (width, naxis1) {
+                if width != naxis1 {
+                    report.push(
+                        Severity::Error,
+                        card_of("NAXIS1"),
+                        format!(
+                            "NAXIS1 = {} does not match the sum of TFORMn widths ({})",
+                            naxis1, width
+                        ),
+                    );
+                }
+            }
+        }
+
+        if header.find("CHECKSUM").is_some() && checksum(&self.to_bytes()) != 0xFFFF_FFFF {
+            report.push(
+                Severity::Error,
+                card_of("CHECKSUM"),
+                "CHECKSUM does not match the HDU contents".to_string(),
+            );
+        }
+        if let Ok(datasum) = header.get::<String>("DATASUM") {
+            let sum = checksum(&self.data_bytes());
+            if datasum.trim().parse::<u32>().ok() != Some(sum) {
+                report.push(
+                    Severity::Error,
+                    card_of("DATASUM"),
+                    format!("DATASUM = '{}' does not match the data ({})", datasum, sum),
+                );
+            }
+        }
+
+        if let HDUData::Unknown { xtension, .. } = &self.data {
+            report.push(
+                Severity::Info,
+                None,
+                format!("data of type '{}' not interpreted", xtension),
+            );
+        }
+        report
+    }
+}
@@ -0,0 +1,522 @@
+//! Dictionary of standard and commonly used keywords
+//!
+//! Entries cover the reserved keywords of the FITS standard (version
+//! 4, Sections 4.4, 7, 8, and 9) and keywords of widely used
+//! conventions: checksums, INHERIT, long strings, tiled image
+//! compression, HEALPix maps, and common observatory keywords.  See
+//! the [FITS keyword dictionary](https://fits.gsfc.nasa.gov/fits_dictionary.html)
+
+use super::Header;
+use crate::KeywordValue;
+use crate::Severity;
+use crate::VerifyReport;
+use KeywordType::{Any, Integer, Logical, Real, String as Text};
+
+/// Expected type of a keyword value
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum KeywordType {
+    /// T or F
+    Logical,
+    /// Quoted character string
+    String,
+    /// Integer
+    Integer,
+    /// Floating-point number; integers are accepted
+    Real,
+    /// Complex number, integer or floating point
+    Complex,
+    /// Any value, e.g. TNULLn, an integer for binary tables and a
+    /// string for ASCII tables
+    Any,
+}
+
+impl KeywordType {
+    /// Does a value have this type
+    ///
+    /// Undefined values and cards without a value always match
+    pub fn accepts(&self, value: &KeywordValue) -> bool {
+        matches!(
+            (self, value),
+            (_, KeywordValue::Undefined | KeywordValue::None)
+                | (KeywordType::Any, _)
+                | (KeywordType::Logical, KeywordValue::Bool(_))
+                | (KeywordType::String, KeywordValue::String(_))
+                | (KeywordType::Integer, KeywordValue::Int(_))
+                | (
+                    KeywordType::Real,
+                    KeywordValue::Int(_) | KeywordValue::Float(_)
+                )
+                | (
+                    KeywordType::Complex,
+                    KeywordValue::ComplexInt(..) | KeywordValue::ComplexFloat(..)
+                )
+        )
+    }
+
+    /// Description used in validation messages
+    fn describe(&self) -> &'static str {
+        match self {
+            KeywordType::Logical => "a logical",
+            KeywordType::String => "a string",
+            KeywordType::Integer => "an integer",
+            KeywordType::Real => "a real number",
+            KeywordType::Complex => "a complex number",
+            KeywordType::Any => "any value",
+        }
+    }
+}
+
+/// Dictionary entry of a keyword
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct KeywordInfo {
+    /// Name, or the root of an indexed name, e.g. "TTYPE" for TTYPEn
+    /// and "PC" for PCi_j
+    pub name: &'static str,
+    /// Expected type of the value
+    pub kind: KeywordType,
+    /// Number of indices following the root: 0 for plain keywords,
+    /// 1 for e.g. NAXISn, 2 for e.g. PCi_j
+    pub indices: usize,
+    /// True if the name may end with a letter A-Z selecting an
+    /// alternate WCS, e.g. CTYPE1A
+    pub alternate: bool,
+    /// Allowed values, if restricted; strings are compared without
+    /// trailing blanks, numbers by their decimal form
+    pub allowed: &'static [&'static str],
+    /// If the keyword is deprecated, what to do instead, e.g.
+    /// "use EQUINOX"
+    pub deprecated: Option<&'static str>,
+    /// True for keywords reserved by the FITS standard, false for
+    /// those of conventions
+    pub standard: bool,
+    /// Short description
+    pub comment: &'static str,
+}
+
+/// Entry with default flags, for the table below
+const fn entry(name: &'static str, kind: KeywordType, comment: &'static str) -> KeywordInfo {
+    KeywordInfo {
+        name,
+        kind,
+        indices: 0,
+        alternate: false,
+        allowed: &[],
+        deprecated: None,
+        standard: true,
+        comment,
+    }
+}
+
+const fn indexed(name: &'static str, kind: KeywordType, comment: &'static str) -> KeywordInfo {
+    KeywordInfo {
+        indices: 1,
+        ..entry(name, kind, comment)
+    }
+}
+
+const fn wcs(
+    name: &'static str,
+    kind: KeywordType,
+    indices: usize,
+    comment: &'static str,
+) -> KeywordInfo {
+    KeywordInfo {
+        indices,
+        alternate: true,
+        ..entry(name, kind, comment)
+    }
+}
+
+const fn convention(name: &'static str, kind: KeywordType, comment: &'static str) -> KeywordInfo {
+    KeywordInfo {
+        standard: false,
+        ..entry(name, kind, comment)
+    }
+}
+
+const fn allowed(info: KeywordInfo, values: &'static [&'static str]) -> KeywordInfo {
+    KeywordInfo {
+        allowed: values,
+        ..info
+    }
+}
+
+const fn deprecated(info: KeywordInfo, advice: &'static str) -> KeywordInfo {
+    KeywordInfo {
+        deprecated: Some(advice),
+        ..info
+    }
+}
+
+static DICTIONARY: &[KeywordInfo] = &[
+    // Mandatory keywords, Section 4.4.1
+    entry("SIMPLE", Logical, "file conforms to the FITS standard"),
+    allowed(
+        entry("BITPIX", Integer, "bits per data value"),
+        &["8", "16", "32", "64", "-32", "-64"],
+    ),
+    entry("NAXIS", Integer, "number of data axes"),
+    indexed("NAXIS", Integer, "length of data axis n"),
+    entry("EXTEND", Logical, "file may contain extensions"),
+    allowed(
+        entry("XTENSION", Text, "type of extension"),
+        &[
+            "IMAGE", "TABLE", "BINTABLE", "IUEIMAGE", "A3DTABLE", "FOREIGN", "DUMP",
+        ],
+    ),
+    entry("PCOUNT", Integer, "size of the heap or random parameters"),
+    entry("GCOUNT", Integer, "number of groups"),
+    entry("GROUPS", Logical, "random groups structure"),
+    indexed("PTYPE", Text, "name of random parameter n"),
+    indexed("PSCAL", Real, "scale of random parameter n"),
+    indexed("PZERO", Real, "offset of random parameter n"),
+    // Other reserved keywords, Section 4.4.2
+    entry("DATE", Text, "date the HDU was created"),
+    entry("ORIGIN", Text, "organization that created the file"),
+    deprecated(
+        entry(
+            "BLOCKED",
+            Logical,
+            "file may be blocked in multiples of 2880 bytes",
+        ),
+        "records are always 2880 bytes",
+    ),
+    entry("AUTHOR", Text, "author of the data"),
+    entry("REFERENC", Text, "bibliographic reference"),
+    entry("DATE-OBS", Text, "date of the observation"),
+    entry("DATE-BEG", Text, "start of the observation"),
+    entry("DATE-END", Text, "end of the observation"),
+    entry("DATE-AVG", Text, "mean date of the observation"),
+    entry("DATEREF", Text, "reference date of time values"),
+    entry("MJD-OBS", Real, "MJD of the observation"),
+    entry("MJD-BEG", Real, "MJD of the start of the observation"),
+    entry("MJD-END", Real, "MJD of the end of the observation"),
+    entry("MJD-AVG", Real, "mean MJD of the observation"),
+    entry("TELESCOP", Text, "telescope"),
+    entry("INSTRUME", Text, "instrument"),
+    entry("OBSERVER", Text, "observer"),
+    entry("OBJECT", Text, "name of the object observed"),
+    entry(
+        "EQUINOX",
+        Real,
+        "equinox of celestial coordinates, in years",
+    ),
+    deprecated(entry("EPOCH", Real, "equinox, in years"), "use EQUINOX"),
+    entry("BSCALE", Real, "scale of array values"),
+    entry("BZERO", Real, "offset of array values"),
+    entry("BUNIT", Text, "unit of array values"),
+    entry(
+        "BLANK",
+        Integer,
+        "value of undefined integer array elements",
+    ),
+    entry("DATAMAX", Real, "largest array value"),
+    entry("DATAMIN", Real, "smallest array value"),
+    entry("EXTNAME", Text, "name of the extension"),
+    entry("EXTVER", Integer, "version of the extension"),
+    entry("EXTLEVEL", Integer, "level of the extension in a hierarchy"),
+    // Tables, Sections 7.2 and 7.3
+    entry("TFIELDS", Integer, "number of columns"),
+    indexed("TTYPE", Text, "name of column n"),
+    indexed("TFORM", Text, "format of column n"),
+    indexed("TUNIT", Text, "unit of column n"),
+    indexed("TSCAL", Real, "scale of column n"),
+    indexed("TZERO", Real, "offset of column n"),
+    indexed("TNULL", Any, "undefined value of column n"),
+    indexed("TDISP", Text, "display format of column n"),
+    indexed("TDIM", Text, "dimensions of column n"),
+    indexed("TBCOL", Integer, "first character of ASCII table column n"),
+    indexed("TDMIN", Real, "smallest value of column n"),
+    indexed("TDMAX", Real, "largest value of column n"),
+    indexed("TLMIN", Real, "smallest legal value of column n"),
+    indexed("TLMAX", Real, "largest legal value of column n"),
+    entry("THEAP", Integer, "offset of the heap"),
+    // World coordinates, Section 8
+    wcs("WCSAXES", Integer, 0, "number of WCS axes"),
+    wcs("WCSNAME", Text, 0, "name of the coordinate system"),
+    wcs("CTYPE", Text, 1, "type of axis n"),
+    wcs("CUNIT", Text, 1, "unit of axis n"),
+    wcs(
+        "CRVAL",
+        Real,
+        1,
+        "coordinate of the reference point on axis n",
+    ),
+    wcs("CRPIX", Real, 1, "reference pixel on axis n"),
+    wcs("CDELT", Real, 1, "coordinate increment on axis n"),
+    deprecated(
+        indexed("CROTA", Real, "rotation of axis n, in degrees"),
+        "use PCi_j",
+    ),
+    wcs("PC", Real, 2, "linear transformation matrix"),
+    wcs("CD", Real, 2, "linear transformation matrix with scale"),
+    wcs("PV", Real, 2, "numeric projection parameter"),
+    wcs("PS", Text, 2, "string projection parameter"),
+    wcs("CNAME", Text, 1, "description of axis n"),
+    wcs("CRDER", Real, 1, "random error of axis n"),
+    wcs("CSYER", Real, 1, "systematic error of axis n"),
+    wcs("LONPOLE", Real, 0, "native longitude of the celestial pole"),
+    wcs("LATPOLE", Real, 0, "native latitude of the celestial pole"),
+    allowed(
+        wcs("RADESYS", Text, 0, "celestial reference frame"),
+        &["ICRS", "FK5", "FK4", "FK4-NO-E", "GAPPT"],
+    ),
+    deprecated(
+        entry("RADECSYS", Text, "celestial reference frame"),
+        "use RADESYS",
+    ),
+    wcs("RESTFRQ", Real, 0, "rest frequency, in Hz"),
+    deprecated(
+        entry("RESTFREQ", Real, "rest frequency, in Hz"),
+        "use RESTFRQ",
+    ),
+    wcs("RESTWAV", Real, 0, "rest wavelength, in m"),
+    allowed(
+        wcs("SPECSYS", Text, 0, "spectral reference frame"),
+        &[
+            "TOPOCENT", "GEOCENTR", "BARYCENT", "HELIOCEN", "LSRK", "LSRD", "GALACTOC", "LOCALGRP",
+            "CMBDIPOL", "SOURCE",
+        ],
+    ),
+    wcs("SSYSOBS", Text, 0, "spectral frame of constant velocity"),
+    wcs("VELOSYS", Real, 0, "relative radial velocity, in m/s"),
+    wcs("ZSOURCE", Real, 0, "redshift of the source"),
+    // Time, Section 9
+    allowed(
+        entry("TIMESYS", Text, "time scale"),
+        &[
+            "TAI", "TT", "TDT", "ET", "IAT", "UT1", "UTC", "GMT", "UT", "GPS", "TCG", "TCB", "TDB",
+            "LOCAL",
+        ],
+    ),
+    entry("TIMEUNIT", Text, "unit of time values"),
+    entry("TREFPOS", Text, "location where time is measured"),
+    entry("TIMEREF", Text, "reference position of times"),
+    entry("MJDREF", Real, "MJD of the reference time"),
+    entry("MJDREFI", Integer, "integer part of MJDREF"),
+    entry("MJDREFF", Real, "fractional part of MJDREF"),
+    entry("JDREF", Real, "JD of the reference time"),
+    entry("TSTART", Real, "start time"),
+    entry("TSTOP", Real, "stop time"),
+    entry("TIMEDEL", Real, "time resolution"),
+    entry("TIMEZERO", Real, "offset of time values"),
+    entry("TIMEPIXR", Real, "position of time stamps in their bins"),
+    entry("XPOSURE", Real, "effective exposure time"),
+    entry("TELAPSE", Real, "elapsed time of the observation"),
+    // Conventions
+    convention("CHECKSUM", Text, "HDU checksum"),
+    convention("DATASUM", Text, "data unit checksum"),
+    convention("INHERIT", Logical, "inherit primary header keywords"),
+    convention("LONGSTRN", Text, "long string convention version"),
+    convention("EXPTIME", Real, "exposure time"),
+    convention("FILTER", Text, "filter"),
+    convention("AIRMASS", Real, "airmass"),
+    convention("GAIN", Real, "detector gain"),
+    convention("RDNOISE", Real, "read noise"),
+    convention("HDUCLASS", Text, "class of the HDU"),
+    convention("ZIMAGE", Logical, "tile-compressed image"),
+    allowed(
+        convention("ZCMPTYPE", Text, "compression algorithm"),
+        &[
+            "RICE_1",
+            "GZIP_1",
+            "GZIP_2",
+            "PLIO_1",
+            "HCOMPRESS_1",
+            "NOCOMPRESS",
+        ],
+    ),
+    convention("ZBITPIX", Integer, "BITPIX of the uncompressed image"),
+    convention("ZNAXIS", Integer, "NAXIS of the uncompressed image"),
+    KeywordInfo {
+        indices: 1,
+        ..convention(
+            "ZNAXIS",
+            Integer,
+            "length of axis n of the uncompressed image",
+        )
+    },
+    KeywordInfo {
+        indices: 1,
+        ..convention("ZTILE", Integer, "length of axis n of the tiles")
+    },
+    allowed(
+        convention("PIXTYPE", Text, "HEALPix pixelization"),
+        &["HEALPIX"],
+    ),
+    allowed(
+        convention("ORDERING", Text, "HEALPix pixel ordering"),
+        &["RING", "NESTED"],
+    ),
+    convention("NSIDE", Integer, "HEALPix resolution"),
+    convention("FIRSTPIX", Integer, "first HEALPix pixel"),
+    convention("LASTPIX", Integer, "last HEALPix pixel"),
+    convention("COORDSYS", Text, "HEALPix coordinate system"),
+];
+
+/// Does `name` consist of `root` followed by `indices` indices (the
+/// second after an underscore) and, if `alternate`, an optional letter
+fn matches_indexed(name: &str, root: &str, indices: usize, alternate: bool) -> bool {
+    let Some(mut rest) = name.strip_prefix(root) else {
+        return false;
+    };
+    if alternate {
+        if let Some(stripped) = rest.strip_suffix(|c: char| c.is_ascii_uppercase()) {
+            rest = stripped;
+        }
+    }
+    let number = |s: &str| !s.is_empty() && s.len() <= 3 && s.bytes().all(|c| c.is_ascii_digit());
+    match indices {
+        0 => rest.is_empty(),
+        1 => number(rest),
+        _ => rest
+            .split_once('_')
+            .is_some_and(|(i, j)| number(i) && number(j)),
+    }
+}
+
+impl KeywordInfo {
+    /// All entries of the dictionary
+    pub fn dictionary() -> &'static [KeywordInfo] {
+        DICTIONARY
+    }
+
+    /// Dictionary entry of a keyword
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - Keyword name, e.g. "EXPTIME", "TTYPE3", "CTYPE1A", or
+    ///   "PC1_2"
+    ///
+    /// # Returns
+    ///
+    /// The entry, or None if the keyword is not in the dictionary
+    ///
+    pub fn lookup(name: &str) -> Option<&'static KeywordInfo> {
+        DICTIONARY
+            .iter()
+            .find(|info| matches_indexed(name, info.name, info.indices, info.alternate))
+    }
+}
+
+impl Header {
+    /// Check keyword values against the dictionary of standard and
+    /// convention keywords; see `KeywordInfo::lookup`
+    ///
+    /// Reported are values of the wrong type or outside the allowed
+    /// set (errors for reserved keywords, warnings for conventions),
+    /// and deprecated keywords (warnings).  Keywords not in the
+    /// dictionary are not checked
+    ///
+    /// # Returns
+    ///
+    /// Report of all issues found, with `hdu` 0 and the card number
+    /// of each keyword
+    ///
+    pub fn validate_against_dictionary(&self) -> VerifyReport {
+        let mut report = VerifyReport::default();
+        // Card number of each keyword; continued strings span several cards
+        let mut card = 1;
+        for kw in self.iter() {
+            let ncards = kw.to_bytes().len() / 80;
+            let this = Some(card);
+            card += ncards;
+            let Some(info) = KeywordInfo::lookup(&kw.name) else {
+                continue;
+            };
+            if let Some(advice) = info.deprecated {
+                report.push(
+                    Severity::Warning,
+                    this,
+                    format!("{} is deprecated; {}", kw.name, advice),
+                );
+            }
+            if let KeywordValue::CommentText(_) = kw.value {
+                report.push(
+                    Severity::Warning,
+                    this,
+                    format!("{} has no value indicator", kw.name),
+                );
+                continue;
+            }
+            let severity = match info.standard {
+                true => Severity::Error,
+                false => Severity::Warning,
+            };
+            if !info.kind.accepts(&kw.value) {
+                report.push(
+                    severity,
+                    this,
+                    format!("{} should be {}", kw.name, info.kind.describe()),
+                );
+                continue;
+            }
+            let text = match &kw.value {
+                KeywordValue::String(s) => s.trim_end().to_string(),
+                KeywordValue::Int(i) => i.to_string(),
+                _ => continue,
+            };
+            if !info.allowed.is_empty() && !info.allowed.contains(&text.as_str()) {
+                report.push(
+                    severity,
+                    this,
+                    format!(
+                        "{} = {} is not one of {}",
+                        kw.name,
+                        text,
+                        info.allowed.join(", ")
+                    ),
+                );
+            }
+        }
+        report
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testutil::header;
+
+    #[test]
+    fn test_dictionary() {
+        assert_eq!(KeywordInfo::lookup("TTYPE12").unwrap().name, "TTYPE");
+        assert_eq!(KeywordInfo::lookup("NAXIS").unwrap().indices, 0);
+        assert_eq!(KeywordInfo::lookup("NAXIS2").unwrap().indices, 1);
+        assert_eq!(KeywordInfo::lookup("CTYPE1A").unwrap().name, "CTYPE");
+        assert_eq!(KeywordInfo::lookup("PC1_2").unwrap().indices, 2);
+        assert!(KeywordInfo::lookup("TTYPE1A").is_none());
+        assert!(KeywordInfo::lookup("PC12").is_none());
+        assert!(KeywordInfo::lookup("MYKEY").is_none());
+
+        let header = header(&[
+            "SIMPLE  =                    T",
+            "BITPIX  =                   12",
+            "NAXIS   =                    0",
+            "OBJECT  =                   42",
+            "EXPTIME = 'long'",
+            "EPOCH   =               2000.0",
+            "RADESYS = 'FK5     '",
+            "CRVAL1  =                   10",
+            "CTYPE1A =                  1.0",
+            "MYKEY   = 'anything'",
+            "END",
+        ]);
+        let messages: Vec<String> = header
+            .validate_against_dictionary()
+            .issues
+            .iter()
+            .map(|i| i.to_string())
+            .collect();
+        assert_eq!(
+            messages,
+            vec![
+                "Error: HDU 0, card 2: BITPIX = 12 is not one of 8, 16, 32, 64, -32, -64",
+                "Error: HDU 0, card 4: OBJECT should be a string",
+                "Warning: HDU 0, card 5: EXPTIME should be a real number",
+                "Warning: HDU 0, card 6: EPOCH is deprecated; use EQUINOX",
+                "Error: HDU 0, card 9: CTYPE1A should be a string",
+            ]
+        );
+    }
+}
@@ -1,3 +1,4 @@
+use crate::FITSError;
 use crate::HeaderError;
 use crate::Keyword;
 
@@ -5,9 +6,9 @@ use crate::Keyword;
 pub struct FITSBlock(pub [Keyword; 36]);
 
 impl FITSBlock {
-    pub fn from_bytes(bytes: &[u8]) -> Result<Self, Box<dyn std::error::Error>> {
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, FITSError> {
         if bytes.len() != 2880 {
-            return Err(Box::new(HeaderError::InvalidHeader));
+            return Err(FITSError::from(HeaderError::InvalidHeader));
         }
 
         Ok(FITSBlock(
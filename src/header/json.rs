@@ -0,0 +1,272 @@
+//! Export and import of headers as JSON
+//!
+//! A header is written as an array with one object per keyword:
+//!
+//! ```text
+//! [
+//!   {"name": "BITPIX", "type": "int", "value": -32, "comment": "array data type"},
+//!   {"name": "CRVAL1", "type": "float", "value": 150.0},
+//!   {"name": "HISTORY", "type": "text", "value": "reduced"}
+//! ]
+//! ```
+//!
+//! The `type` is one of `bool`, `int`, `float`, `string`, `complex_int`,
+//! `complex_float` (value `[real, imaginary]`), `undefined` (no value),
+//! `text` (commentary cards) and `none`, so values keep their FITS type
+//! even where JSON readers do not distinguish integers from floats.
+//! Float values that are NaN or infinite are written as the strings
+//! `"NaN"`, `"Infinity"` and `"-Infinity"`.
+//!
+//! Enabled by the `serde` feature
+
+use serde_json::Value;
+
+use super::Header;
+use super::Keyword;
+use super::KeywordValue;
+use crate::HeaderError;
+
+/// Write a string as a JSON string literal
+fn quote(s: &str) -> String {
+    serde_json::Value::from(s).to_string()
+}
+
+/// Write a float so that it reads back as a float, e.g. "1.0"
+///
+/// JSON has no literal for NaN and infinities, so they are written as
+/// the strings "NaN", "Infinity" and "-Infinity"
+fn float(v: f64) -> String {
+    match v {
+        v if v.is_nan() => quote("NaN"),
+        f64::INFINITY => quote("Infinity"),
+        f64::NEG_INFINITY => quote("-Infinity"),
+        v => format!("{:?}", v),
+    }
+}
+
+/// Type name and JSON value of a keyword value
+fn value_json(value: &KeywordValue) -> (&'static str, Option<String>) {
+    match value {
+        KeywordValue::None => ("none", None),
+        KeywordValue::Undefined => ("undefined", None),
+        KeywordValue::Bool(b) => ("bool", Some(b.to_string())),
+        KeywordValue::Int(i) => ("int", Some(i.to_string())),
+        KeywordValue::Float(f) => ("float", Some(float(*f))),
+        KeywordValue::String(s) => ("string", Some(quote(s))),
+        KeywordValue::CommentText(s) => ("text", Some(quote(s))),
+        KeywordValue::ComplexInt(r, i) => ("complex_int", Some(format!("[{}, {}]", r, i))),
+        KeywordValue::ComplexFloat(r, i) => (
+            "complex_float",
+            Some(format!("[{}, {}]", float(*r), float(*i))),
+        ),
+    }
+}
+
+/// Keyword from a parsed JSON object
+fn keyword(object: &Value) -> Result<Keyword, HeaderError> {
+    let Value::Object(members) = object else {
+        return Err(HeaderError::InvalidJSON(
+            "expected an object for each keyword".to_string(),
+        ));
+    };
+    let text = |name: &str| match members.get(name) {
+        Some(Value::String(s)) => Ok(Some(s.clone())),
+        None | Some(Value::Null) => Ok(None),
+        _ => Err(HeaderError::InvalidJSON(format!(
+            "\"{}\" must be a string",
+            name
+        ))),
+    };
+    let name = text("name")?
+        .ok_or_else(|| HeaderError::InvalidJSON("keyword without \"name\"".to_string()))?;
+    let bad = || HeaderError::InvalidJSON(format!("invalid value for keyword {}", name));
+    let int = |v: &Value| v.as_i64().ok_or_else(bad);
+    let real = |v: &Value| match v {
+        Value::String(s) if s == "NaN" => Ok(f64::NAN),
+        Value::String(s) if s == "Infinity" => Ok(f64::INFINITY),
+        Value::String(s) if s == "-Infinity" => Ok(f64::NEG_INFINITY),
+        v => v.as_f64().ok_or_else(bad),
+    };
+    let value = || members.get("value").ok_or_else(bad);
+    let pair = || match value()? {
+        Value::Array(items) if items.len() == 2 => Ok((&items[0], &items[1])),
+        _ => Err(bad()),
+    };
+    let string = || value()?.as_str().map(str::to_string).ok_or_else(bad);
+
+    let value = match text("type")?.as_deref() {
+        Some("none") | None => KeywordValue::None,
+        Some("undefined") => KeywordValue::Undefined,
+        Some("bool") => KeywordValue::Bool(value()?.as_bool().ok_or_else(bad)?),
+        Some("int") => KeywordValue::Int(int(value()?)?),
+        Some("float") => KeywordValue::Float(real(value()?)?),
+        Some("string") => KeywordValue::String(string()?),
+        Some("text") => KeywordValue::CommentText(string()?),
+        Some("complex_int") => {
+            let (r, i) = pair()?;
+            KeywordValue::ComplexInt(int(r)?, int(i)?)
+        }
+        Some("complex_float") => {
+            let (r, i) = pair()?;
+            KeywordValue::ComplexFloat(real(r)?, real(i)?)
+        }
+        Some(other) => {
+            return Err(HeaderError::InvalidJSON(format!(
+                "unknown type \"{}\" for keyword {}",
+                other, name
+            )))
+        }
+    };
+    Ok(Keyword {
+        comment: text("comment")?,
+        unit: text("unit")?,
+        name,
+        value,
+        raw: None,
+    })
+}
+
+impl Header {
+    /// Header as JSON, one object per keyword
+    ///
+    /// Each object has the keyword `name`, the `type` of its value, the
+    /// `value` (absent for undefined values), and the `unit` and
+    /// `comment` if any;
+    /// see the module documentation for the types.  Read it back with
+    /// `from_json`
+    ///
+    /// # Returns
+    ///
+    /// JSON text, with one keyword per line
+    ///
+    pub fn to_json(&self) -> String {
+        let mut out = String::from("[");
+        for (i, kw) in self.iter().enumerate() {
+            let (vtype, value) = value_json(&kw.value);
+            out.push_str(if i == 0 { "\n  " } else { ",\n  " });
+            out.push_str(&format!(
+                "{{\"name\": {}, \"type\": \"{}\"",
+                quote(&kw.name),
+                vtype
+            ));
+            if let Some(value) = value {
+                out.push_str(&format!(", \"value\": {}", value));
+            }
+            if let Some(unit) = &kw.unit {
+                out.push_str(&format!(", \"unit\": {}", quote(unit)));
+            }
+            if let Some(comment) = &kw.comment {
+                out.push_str(&format!(", \"comment\": {}", quote(comment)));
+            }
+            out.push('}');
+        }
+        out.push_str("\n]");
+        out
+    }
+
+    /// Read a header written by `to_json`
+    ///
+    /// Objects without a `type` are keywords without a value; other
+    /// members than `name`, `type`, `value`, `unit` and `comment` are
+    /// ignored
+    ///
+    /// # Arguments
+    ///
+    /// * `text` - JSON array of keyword objects
+    ///
+    /// # Returns
+    ///
+    /// The header, or an error if the text is not valid JSON or a
+    /// value does not match its type
+    ///
+    pub fn from_json(text: &str) -> Result<Header, HeaderError> {
+        let json: Value =
+            serde_json::from_str(text).map_err(|e| HeaderError::InvalidJSON(e.to_string()))?;
+        let Value::Array(items) = json else {
+            return Err(HeaderError::InvalidJSON(
+                "expected an array of keywords".to_string(),
+            ));
+        };
+        Ok(Header::new(
+            items.iter().map(keyword).collect::<Result<Vec<_>, _>>()?,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testutil::kw;
+
+    #[test]
+    fn test_json() {
+        let commented = |name: &str, value, comment: &str| Keyword {
+            comment: Some(comment.to_string()),
+            ..kw(name, value)
+        };
+        let header = Header::new(vec![
+            commented("SIMPLE", KeywordValue::Bool(true), "conforms"),
+            kw("NAXIS", KeywordValue::Int(0)),
+            commented("EXPTIME", KeywordValue::Float(30.0), "[s]"),
+            kw("OBJECT", KeywordValue::String("M31 \"core\"\n".into())),
+            kw("PHASE", KeywordValue::ComplexFloat(1.5, -2e-10)),
+            kw("GAIN", KeywordValue::ComplexInt(1, 2)),
+            commented("AIRMASS", KeywordValue::Undefined, "unknown"),
+            kw("HISTORY", KeywordValue::CommentText("flat ✓".into())),
+        ]);
+        let json = header.to_json();
+        assert!(json.contains(
+            "{\"name\": \"EXPTIME\", \"type\": \"float\", \"value\": 30.0, \"comment\": \"[s]\"}"
+        ));
+        assert!(json.contains("\"value\": \"M31 \\\"core\\\"\\n\""));
+
+        let back = Header::from_json(&json).unwrap();
+        assert_eq!(back.len(), header.len());
+        for (a, b) in back.iter().zip(header.iter()) {
+            assert_eq!(
+                (&a.name, &a.value, &a.comment),
+                (&b.name, &b.value, &b.comment)
+            );
+        }
+
+        let header = Header::from_json(
+            r#"[{"name": "A", "type": "string", "value": "\u00e9\ud83d\ude00"},
+                {"name": "B", "type": "int", "value": 1e3}]"#,
+        );
+        assert!(matches!(header, Err(HeaderError::InvalidJSON(_))));
+        let header = Header::from_json(
+            r#"[{"name": "A", "type": "string", "value": "\u00e9\ud83d\ude00"}]"#,
+        )
+        .unwrap();
+        assert_eq!(header.value("A"), Some(&KeywordValue::String("é😀".into())));
+        assert!(Header::from_json("[{\"name\": \"A\"}] x").is_err());
+        assert!(Header::from_json("[{\"type\": \"int\"}]").is_err());
+    }
+
+    #[test]
+    fn test_json_limits() {
+        let header = Header::new(vec![
+            kw("A", KeywordValue::Float(f64::NAN)),
+            kw("B", KeywordValue::Float(f64::NEG_INFINITY)),
+            kw("C", KeywordValue::ComplexFloat(f64::INFINITY, 1.0)),
+        ]);
+        let json = header.to_json();
+        assert!(json.contains("\"value\": \"NaN\""));
+        let back = Header::from_json(&json).unwrap();
+        assert!(matches!(back.value("A"), Some(KeywordValue::Float(v)) if v.is_nan()));
+        assert_eq!(
+            back.value("B"),
+            Some(&KeywordValue::Float(f64::NEG_INFINITY))
+        );
+        assert_eq!(
+            back.value("C"),
+            Some(&KeywordValue::ComplexFloat(f64::INFINITY, 1.0))
+        );
+
+        // Deeply nested input is rejected instead of overflowing the stack
+        assert!(matches!(
+            Header::from_json(&"[".repeat(200000)),
+            Err(HeaderError::InvalidJSON(_))
+        ));
+    }
+}
@@ -1,6 +1,9 @@
+use crate::FITSError;
 use crate::HeaderError;
+use std::num::IntErrorKind;
 
 #[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum KeywordValue {
     None,
     Bool(bool),
@@ -10,6 +13,9 @@ pub enum KeywordValue {
     ComplexInt(i64, i64),
     ComplexFloat(f64, f64),
     Undefined,
+    /// Free text of a commentary card (COMMENT, HISTORY, blank keyword,
+    /// or any card without a value indicator), from columns 9-80
+    CommentText(String),
 }
 
 impl std::fmt::Display for KeywordValue {
@@ -23,21 +29,126 @@ impl std::fmt::Display for KeywordValue {
             KeywordValue::ComplexInt(r, i) => write!(f, "Complex Int: ({}, {})", r, i),
             KeywordValue::ComplexFloat(r, i) => write!(f, "Complex Float: ({}, {})", r, i),
             KeywordValue::Undefined => write!(f, "Undefined"),
+            KeywordValue::CommentText(s) => write!(f, "Text: \"{}\"", s),
         }
     }
 }
 
+/// Conversion from a keyword value to a Rust type
+///
+/// Integers coerce to floating point, and string values are parsed
+/// as numbers when the whole (trimmed) string is a valid number.
+/// Used by `Header::get`
+pub trait FromKeywordValue: Sized {
+    /// Convert the value, or None if it has an incompatible type
+    fn from_value(value: &KeywordValue) -> Option<Self>;
+}
+
+impl FromKeywordValue for f64 {
+    fn from_value(value: &KeywordValue) -> Option<Self> {
+        match value {
+            KeywordValue::Float(f) => Some(*f),
+            KeywordValue::Int(i) => Some(*i as f64),
+            // FITS allows a 'D' exponent for double precision
+            KeywordValue::String(s) => s.trim().replace(['D', 'd'], "E").parse().ok(),
+            _ => None,
+        }
+    }
+}
+
+impl FromKeywordValue for f32 {
+    fn from_value(value: &KeywordValue) -> Option<Self> {
+        f64::from_value(value).map(|f| f as f32)
+    }
+}
+
+impl FromKeywordValue for i64 {
+    fn from_value(value: &KeywordValue) -> Option<Self> {
+        match value {
+            KeywordValue::Int(i) => Some(*i),
+            KeywordValue::String(s) => s.trim().parse().ok(),
+            _ => None,
+        }
+    }
+}
+
+impl FromKeywordValue for i32 {
+    fn from_value(value: &KeywordValue) -> Option<Self> {
+        i64::from_value(value).and_then(|i| i32::try_from(i).ok())
+    }
+}
+
+impl FromKeywordValue for usize {
+    fn from_value(value: &KeywordValue) -> Option<Self> {
+        i64::from_value(value).and_then(|i| usize::try_from(i).ok())
+    }
+}
+
+impl FromKeywordValue for bool {
+    fn from_value(value: &KeywordValue) -> Option<Self> {
+        match value {
+            KeywordValue::Bool(b) => Some(*b),
+            _ => None,
+        }
+    }
+}
+
+impl FromKeywordValue for String {
+    fn from_value(value: &KeywordValue) -> Option<Self> {
+        match value {
+            KeywordValue::String(s) => Some(s.clone()),
+            _ => None,
+        }
+    }
+}
+
+/// Complex number as (real, imaginary); real numbers have a zero
+/// imaginary part
+impl FromKeywordValue for (f64, f64) {
+    fn from_value(value: &KeywordValue) -> Option<Self> {
+        match value {
+            KeywordValue::ComplexInt(r, i) => Some((*r as f64, *i as f64)),
+            KeywordValue::ComplexFloat(r, i) => Some((*r, *i)),
+            KeywordValue::Int(_) | KeywordValue::Float(_) => Some((f64::from_value(value)?, 0.0)),
+            _ => None,
+        }
+    }
+}
+
+impl From<(i64, i64)> for KeywordValue {
+    fn from((re, im): (i64, i64)) -> Self {
+        KeywordValue::ComplexInt(re, im)
+    }
+}
+
+impl From<(f64, f64)> for KeywordValue {
+    fn from((re, im): (f64, f64)) -> Self {
+        KeywordValue::ComplexFloat(re, im)
+    }
+}
+
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Keyword {
     pub name: String,
     pub value: KeywordValue,
     pub comment: Option<String>,
+    /// Unit of the value, from a comment starting with the unit in
+    /// brackets, e.g. "/ [km/s] radial velocity".  The unit is written
+    /// back in the same form, ahead of `comment`
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub unit: Option<String>,
+    /// Original card bytes (several cards for continued strings) if the
+    /// keyword was read from a file.  These are written back unchanged
+    /// unless the name, value, or comment have since been edited
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub raw: Option<Vec<u8>>,
 }
 
 impl std::fmt::Display for Keyword {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
         write!(f, "{} = {}", self.name, self.value)?;
-        if let Some(comment) = &self.comment {
+        if let Some(comment) = self.comment_text() {
             write!(f, " :: {}", comment)?;
         }
         Ok(())
@@ -50,14 +161,237 @@ impl Default for Keyword {
             name: String::new(),
             value: KeywordValue::None,
             comment: None,
+            unit: None,
+            raw: None,
+        }
+    }
+}
+
+/// Comment following the first '/' in a value field, if any
+fn parse_comment(field: &str) -> Option<String> {
+    let pos = field.find('/')?;
+    if pos < field.len() - 1 {
+        Some(field[(pos + 1)..].trim().to_string())
+    } else {
+        None
+    }
+}
+
+/// Parse a quoted string value and any following comment
+///
+/// `field` starts with the opening quote.  Doubled single quotes
+/// within the string denote a single quote, and trailing spaces
+/// are not significant
+fn parse_quoted(field: &str) -> (KeywordValue, Option<String>) {
+    // find end quote, skipping double single quotes
+    let bytes = field.as_bytes();
+    let mut end = 1;
+    while end < bytes.len() {
+        if bytes[end] == b'\'' {
+            if end + 1 < bytes.len() && bytes[end + 1] == b'\'' {
+                end += 2;
+            } else {
+                break;
+            }
+        } else {
+            end += 1;
+        }
+    }
+    let value = KeywordValue::String(field[1..end.min(field.len())].trim_end().replace("''", "'"));
+    // look for comment anywhere in remainder
+    (value, parse_comment(&field[end.min(field.len())..]))
+}
+
+/// Parse a free-format value field, e.g. following a HIERARCH keyword name
+///
+/// # Returns
+///
+/// The value and comment, or None if the value cannot be parsed
+///
+pub(super) fn parse_free_value(field: &str) -> Option<(KeywordValue, Option<String>)> {
+    let field = field.trim_start();
+    if field.starts_with('\'') {
+        return Some(parse_quoted(field));
+    }
+    let (token, comment) = match field.find('/') {
+        Some(pos) => (field[..pos].trim(), parse_comment(field)),
+        None => (field.trim(), None),
+    };
+    let float = |s: &str| s.trim().replace('D', "E").parse::<f64>().ok();
+    let value = match token {
+        "" => KeywordValue::Undefined,
+        "T" => KeywordValue::Bool(true),
+        "F" => KeywordValue::Bool(false),
+        t if t.starts_with('(') && t.ends_with(')') => {
+            let (re, im) = t[1..t.len() - 1].split_once(',')?;
+            match (re.trim().parse::<i64>(), im.trim().parse::<i64>()) {
+                (Ok(re), Ok(im)) => KeywordValue::ComplexInt(re, im),
+                _ => KeywordValue::ComplexFloat(float(re)?, float(im)?),
+            }
+        }
+        t => match t.parse::<i64>() {
+            Ok(v) => KeywordValue::Int(v),
+            Err(_) => KeywordValue::Float(float(t)?),
+        },
+    };
+    Some((value, comment))
+}
+
+/// Largest number of string characters on one card:
+/// 80 less the name, value indicator, and enclosing quotes
+const MAX_STRING_LEN: usize = 68;
+
+/// Format a float so that it is read back as a float, within 20 characters
+fn format_float(value: f64) -> String {
+    let s = format!("{:?}", value).replace('e', "E");
+    let s = match s.find('E') {
+        Some(pos) if !s[..pos].contains('.') => format!("{}.0{}", &s[..pos], &s[pos..]),
+        _ => s,
+    };
+    if s.len() <= 20 {
+        return s;
+    }
+    (0..16)
+        .rev()
+        .map(|prec| format!("{:.*E}", prec, value))
+        .find(|s| s.len() <= 20)
+        .unwrap_or(s)
+}
+
+/// Split a string into pieces for the CONTINUE long-string convention
+///
+/// Pieces are quoted (with embedded quotes doubled) and, except for
+/// the last, end in '&'.  Doubled quotes are never split across cards
+///
+/// # Arguments
+///
+/// * `value` - The string
+/// * `first` - Most characters of the string on the first card, which
+///   may be shorter than the rest (e.g. after a HIERARCH name)
+///
+fn split_long_string(value: &str, first: usize) -> Vec<String> {
+    let escaped: Vec<String> = value
+        .chars()
+        .map(|c| {
+            if c == '\'' {
+                "''".to_string()
+            } else {
+                c.to_string()
+            }
+        })
+        .collect();
+    let mut pieces = Vec::new();
+    let mut current = String::new();
+    for c in escaped {
+        let limit = match pieces.is_empty() {
+            true => first,
+            false => MAX_STRING_LEN - 1,
+        };
+        if current.len() + c.len() > limit {
+            pieces.push(std::mem::take(&mut current));
         }
+        current.push_str(&c);
     }
+    pieces.push(current);
+    let n = pieces.len();
+    pieces
+        .into_iter()
+        .enumerate()
+        .map(|(i, p)| {
+            if i + 1 < n {
+                format!("'{}&'", p)
+            } else if p.len() < 8 {
+                // Fixed-format strings are at least 8 characters
+                format!("'{:<8}'", p)
+            } else {
+                format!("'{}'", p)
+            }
+        })
+        .collect()
+}
+
+/// Cards for a string split by `split_long_string`
+///
+/// The first card starts with `prefix`, the rest with CONTINUE, and
+/// the comment is placed on the last card
+fn continued_cards(prefix: &str, pieces: Vec<String>, comment: &str) -> Vec<u8> {
+    let n = pieces.len();
+    let mut bytes = Vec::with_capacity(80 * n);
+    for (i, piece) in pieces.into_iter().enumerate() {
+        let prefix = if i == 0 { prefix } else { "CONTINUE  " };
+        let text = match i + 1 == n {
+            true => format!("{}{:<20}{}", prefix, piece, comment),
+            false => format!("{}{}", prefix, piece),
+        };
+        bytes.extend(card(text));
+    }
+    bytes
+}
+
+/// Pad or truncate a card to 80 bytes
+fn card(text: String) -> Vec<u8> {
+    let mut bytes = text.into_bytes();
+    bytes.resize(80, b' ');
+    bytes
 }
 
 impl Keyword {
-    pub fn new(kwstr: &[u8]) -> Result<Self, Box<dyn std::error::Error>> {
+    /// Parse a keyword from an 80-byte header card
+    ///
+    /// A unit in brackets at the start of the comment is moved to
+    /// `unit`, except on CONTINUE cards, whose comment is merged into
+    /// the preceding keyword (see `Header::push_card`)
+    ///
+    /// # Arguments
+    ///
+    /// * `kwstr` - The card bytes
+    ///
+    /// # Returns
+    ///
+    /// The keyword, or an error if the card is invalid
+    ///
+    pub fn new(kwstr: &[u8]) -> Result<Self, FITSError> {
+        let mut kw = Self::parse_card(kwstr)?;
+        if kw.name != "CONTINUE" {
+            kw.split_unit();
+        }
+        Ok(kw)
+    }
+
+    /// Move a unit in brackets at the start of the comment to `unit`
+    pub(crate) fn split_unit(&mut self) {
+        if self.unit.is_some()
+            || matches!(
+                self.value,
+                KeywordValue::None | KeywordValue::CommentText(_)
+            )
+        {
+            return;
+        }
+        let Some(comment) = self.comment.as_deref() else {
+            return;
+        };
+        let Some((unit, rest)) = comment.strip_prefix('[').and_then(|c| c.split_once(']')) else {
+            return;
+        };
+        self.unit = Some(unit.trim().to_string());
+        let rest = rest.trim();
+        self.comment = (!rest.is_empty()).then(|| rest.to_string());
+    }
+
+    /// Comment as written on the card, with the unit (if any) in
+    /// brackets ahead of it
+    fn comment_text(&self) -> Option<String> {
+        match (&self.unit, &self.comment) {
+            (Some(unit), Some(comment)) => Some(format!("[{}] {}", unit, comment)),
+            (Some(unit), None) => Some(format!("[{}]", unit)),
+            (None, comment) => comment.clone(),
+        }
+    }
+
+    fn parse_card(kwstr: &[u8]) -> Result<Self, FITSError> {
         if kwstr.len() != 80 {
-            return Err(Box::new(HeaderError::BadKeywordLength(kwstr.len())));
+            return Err(FITSError::from(HeaderError::BadKeywordLength(kwstr.len())));
         }
         let kwname = &kwstr[0..8];
 
@@ -65,7 +399,7 @@ impl Keyword {
         for c in kwname {
             let c = *c as char;
             if !c.is_ascii_uppercase() && c != ' ' && !c.is_ascii_digit() && c != '_' && c != '-' {
-                return Err(Box::new(HeaderError::InvalidCharacterInKeyword(
+                return Err(FITSError::from(HeaderError::InvalidCharacterInKeyword(
                     String::from_utf8(kwname.to_vec())?,
                 )));
             }
@@ -77,8 +411,9 @@ impl Keyword {
         // per Section 4.1.2.1
         kwname = kwname.trim_ascii().to_string();
         if kwname.contains(' ') {
-            println!("here");
-            return Err(Box::new(HeaderError::InvalidCharacterInKeyword(kwname)));
+            return Err(FITSError::from(HeaderError::InvalidCharacterInKeyword(
+                kwname,
+            )));
         }
 
         // Construct the keyword to be returned later
@@ -86,36 +421,64 @@ impl Keyword {
             name: kwname,
             value: KeywordValue::None,
             comment: None,
+            unit: None,
+            raw: Some(kwstr.to_vec()),
         };
 
+        // ESO HIERARCH convention: a long, space-separated name
+        // followed by "=" and a free-format value, e.g.
+        // "HIERARCH ESO DET CHIP1 NAME = 'value' / comment"
+        if kw.name == "HIERARCH" {
+            let rest = String::from_utf8(kwstr[8..].to_vec())?;
+            let Some((name, field)) = rest.split_once('=') else {
+                kw.value = KeywordValue::CommentText(rest.trim_end().to_string());
+                return Ok(kw);
+            };
+            kw.name = name.split_whitespace().collect::<Vec<_>>().join(" ");
+            (kw.value, kw.comment) = parse_free_value(field).ok_or_else(|| {
+                HeaderError::InvalidKeywordRecord(String::from_utf8_lossy(kwstr).to_string())
+            })?;
+            return Ok(kw);
+        }
+
+        // CONTINUE cards of the long-string convention have a quoted
+        // string but no value indicator; see Section 4.2.1.2
+        if kw.name == "CONTINUE" {
+            let field = String::from_utf8(kwstr[8..].to_vec())?;
+            let field = field.trim_start();
+            if field.starts_with('\'') {
+                (kw.value, kw.comment) = parse_quoted(field);
+            }
+            return Ok(kw);
+        }
+
+        // Commentary keywords always hold free text, as does any other
+        // card without a value indicator; see Section 4.4.2.4
+        let commentary = matches!(kw.name.as_str(), "COMMENT" | "HISTORY" | "");
+        if kw.name != "END" && (commentary || !(kwstr[8] == b'=' && kwstr[9] == b' ')) {
+            let text = String::from_utf8(kwstr[8..].to_vec())?;
+            kw.value = KeywordValue::CommentText(text.trim_end().to_string());
+            return Ok(kw);
+        }
+
         // Does this keyword have a value?
         if kwstr[8] == 61 && kwstr[9] == 32 {
             let kvchars = String::from_utf8(kwstr[10..].to_vec())?;
 
             // See if there is a string enclosed in single quotes
             if kvchars.starts_with('\'') {
-                // find end quote, skipping double single quotes
-                let mut end = 1;
-                while end < kvchars.len() {
-                    if kvchars.chars().nth(end).unwrap() == '\'' {
-                        if end + 1 < kvchars.len() && kvchars.chars().nth(end + 1).unwrap() == '\''
-                        {
-                            end += 2;
-                        } else {
-                            break;
-                        }
-                    } else {
-                        end += 1;
-                    }
-                }
-                kw.value = KeywordValue::String(kvchars[1..end].to_string().trim().to_string());
-                let remainder = kvchars[end..].to_string();
-                // look for comment anywhere in remainder
-                if let Some(pos) = remainder.find('/') {
-                    if pos < remainder.len() - 1 {
-                        kw.comment = Some(remainder[(pos + 1)..].to_string().trim().to_string());
-                    }
-                }
+                (kw.value, kw.comment) = parse_quoted(&kvchars);
+            }
+            // An empty value field means the value is undefined
+            else if kvchars
+                .split('/')
+                .next()
+                .unwrap_or_default()
+                .trim()
+                .is_empty()
+            {
+                kw.value = KeywordValue::Undefined;
+                kw.comment = parse_comment(&kvchars);
             }
             // look for boolean in 30th byte of keyword
             else if kwstr[29] == b'T' || kwstr[29] == b'F' {
@@ -133,7 +496,7 @@ impl Keyword {
             }
             // Look for integer or float or complex types
             else {
-                let realstr = kvchars[0..20].to_string().trim_start().to_string();
+                let realstr = kvchars[0..20].trim().to_string();
                 let mut complexstr = kvchars[20..].to_string().trim_start().to_string();
                 // find if complex string has a comment,
                 // if so, remove it
@@ -173,7 +536,17 @@ impl Keyword {
                 });
 
                 if is_int {
-                    kw.value = KeywordValue::Int(realstr.parse::<i64>()?);
+                    // Integers beyond the range of i64 (such as the
+                    // BZERO = 2^63 of unsigned 64-bit images) are kept as floats
+                    kw.value = match realstr.parse::<i64>() {
+                        Ok(v) => KeywordValue::Int(v),
+                        Err(e) => match e.kind() {
+                            IntErrorKind::PosOverflow | IntErrorKind::NegOverflow => {
+                                KeywordValue::Float(realstr.parse::<f64>()?)
+                            }
+                            _ => return Err(FITSError::from(e)),
+                        },
+                    };
                     let remainder = kvchars[20..].to_string();
                     if let Some(pos) = remainder.find('/') {
                         if pos < remainder.len() - 1 {
@@ -196,21 +569,21 @@ impl Keyword {
                     let start = complexstr.find('(');
                     let end = complexstr.find(')');
                     if start.is_none() || end.is_none() {
-                        return Err(Box::new(HeaderError::InvalidKeywordRecord(
+                        return Err(FITSError::from(HeaderError::InvalidKeywordRecord(
                             String::from_utf8(kwstr.to_vec())?,
                         )));
                     }
                     let start = start.unwrap();
                     let end = end.unwrap();
                     if end < start {
-                        return Err(Box::new(HeaderError::InvalidKeywordRecord(
+                        return Err(FITSError::from(HeaderError::InvalidKeywordRecord(
                             String::from_utf8(kwstr.to_vec())?,
                         )));
                     }
                     let parts = complexstr[(start + 1)..end].split(",");
                     let parts = parts.map(|x| x.trim()).collect::<Vec<_>>();
                     if parts.len() != 2 {
-                        return Err(Box::new(HeaderError::InvalidKeywordRecord(
+                        return Err(FITSError::from(HeaderError::InvalidKeywordRecord(
                             String::from_utf8(kwstr.to_vec())?,
                         )));
                     }
@@ -230,21 +603,21 @@ impl Keyword {
                     let start = complexstr.find('(');
                     let end = complexstr.find(')');
                     if start.is_none() || end.is_none() {
-                        return Err(Box::new(HeaderError::InvalidKeywordRecord(
+                        return Err(FITSError::from(HeaderError::InvalidKeywordRecord(
                             String::from_utf8(kwstr.to_vec())?,
                         )));
                     }
                     let start = start.unwrap();
                     let end = end.unwrap();
                     if end < start {
-                        return Err(Box::new(HeaderError::InvalidKeywordRecord(
+                        return Err(FITSError::from(HeaderError::InvalidKeywordRecord(
                             String::from_utf8(kwstr.to_vec())?,
                         )));
                     }
                     let parts = complexstr[(start + 1)..end].split(",");
                     let parts = parts.map(|x| x.trim()).collect::<Vec<_>>();
                     if parts.len() != 2 {
-                        return Err(Box::new(HeaderError::InvalidKeywordRecord(
+                        return Err(FITSError::from(HeaderError::InvalidKeywordRecord(
                             String::from_utf8(kwstr.to_vec())?,
                         )));
                     }
@@ -259,7 +632,7 @@ impl Keyword {
                         }
                     }
                 } else {
-                    return Err(Box::new(HeaderError::InvalidKeywordRecord(
+                    return Err(FITSError::from(HeaderError::InvalidKeywordRecord(
                         String::from_utf8(kwstr.to_vec())?,
                     )));
                 }
@@ -268,4 +641,163 @@ impl Keyword {
 
         Ok(kw)
     }
+
+    /// Original card bytes, if present and still describing this keyword
+    fn unmodified_raw(&self) -> Option<&[u8]> {
+        let raw = self.raw.as_deref()?;
+        if raw.is_empty() || raw.len() % 80 != 0 {
+            return None;
+        }
+        let mut header = crate::Header::default();
+        for card in raw.chunks_exact(80) {
+            header.push_card(Keyword::new(card).ok()?);
+        }
+        match header.first() {
+            Some(kw)
+                if header.len() == 1
+                    && kw.name == self.name
+                    && kw.value == self.value
+                    && kw.comment == self.comment
+                    && kw.unit == self.unit =>
+            {
+                Some(raw)
+            }
+            _ => None,
+        }
+    }
+
+    /// Serialize the keyword as 80-byte header cards
+    ///
+    /// Keywords read from a file are written as their original card
+    /// bytes unless they have been edited, so unmodified headers are
+    /// reproduced exactly.  Strings longer than fit on a single card
+    /// are split over CONTINUE cards following the long-string
+    /// convention (Section 4.2.1.2 of FITS standard, version 4), also
+    /// after HIERARCH names.  Other HIERARCH values that do not fit on
+    /// one card are cut off; `FITS::validate` rejects such keywords
+    ///
+    /// # Returns
+    ///
+    /// Card bytes; a multiple of 80 bytes long
+    ///
+    pub fn to_bytes(&self) -> Vec<u8> {
+        if let Some(raw) = self.unmodified_raw() {
+            return raw.to_vec();
+        }
+        if self.is_hierarch() {
+            return self.hierarch_bytes();
+        }
+        let name = format!("{:<8}", self.name);
+        let comment = self
+            .comment_text()
+            .map(|c| format!(" / {}", c))
+            .unwrap_or_default();
+        let fixed = |value: String| format!("{}= {:>20}", name, value);
+        let text = match &self.value {
+            KeywordValue::None => format!("{}{}", name, self.comment.clone().unwrap_or_default()),
+            KeywordValue::Undefined => format!("{}= {:20}", name, ""),
+            KeywordValue::CommentText(t) => {
+                // Long text continues on further cards with the same name
+                let chars: Vec<char> = t.chars().collect();
+                return chars
+                    .chunks(72)
+                    .map(|c| card(format!("{}{}", name, c.iter().collect::<String>())))
+                    .chain(chars.is_empty().then(|| card(name.clone())))
+                    .flatten()
+                    .collect();
+            }
+            KeywordValue::Bool(b) => fixed(if *b { "T" } else { "F" }.to_string()),
+            KeywordValue::Int(v) => fixed(v.to_string()),
+            KeywordValue::Float(v) => fixed(format_float(*v)),
+            KeywordValue::ComplexInt(r, i) => fixed(format!("({}, {})", r, i)),
+            KeywordValue::ComplexFloat(r, i) => {
+                fixed(format!("({}, {})", format_float(*r), format_float(*i)))
+            }
+            KeywordValue::String(s) => {
+                let pieces = split_long_string(s, MAX_STRING_LEN - 1);
+                return continued_cards(&format!("{}= ", name), pieces, &comment);
+            }
+        };
+        let text = match &self.value {
+            KeywordValue::None => text,
+            _ => text + &comment,
+        };
+        card(text)
+    }
+
+    /// True if the name can only be written with the HIERARCH convention,
+    /// i.e. it is longer than 8 characters or contains spaces
+    pub fn is_hierarch(&self) -> bool {
+        self.name.len() > 8 || self.name.contains(' ')
+    }
+
+    /// True if a HIERARCH keyword cannot be written without losing
+    /// its name or value: the name and value (or commentary text) are
+    /// too long for one card, and the value is not a string that can
+    /// continue on CONTINUE cards.  Comments are truncated as for
+    /// other keywords
+    pub(crate) fn overflows_card(&self) -> bool {
+        if !self.is_hierarch() {
+            return false;
+        }
+        let prefix = format!("HIERARCH {} = ", self.name).len();
+        let text = match &self.value {
+            KeywordValue::None => self.comment.clone().unwrap_or_default(),
+            KeywordValue::CommentText(t) => t.trim_start().to_string(),
+            // At least one character on the first card, with its quotes and '&'
+            KeywordValue::String(_) => return prefix + 4 > 80,
+            _ => return prefix + self.hierarch_value().len() > 80,
+        };
+        format!("HIERARCH {} {}", self.name, text).len() > 80
+    }
+
+    /// Free-format value of a HIERARCH card, for values other than
+    /// strings, commentary text, and no value
+    fn hierarch_value(&self) -> String {
+        match &self.value {
+            KeywordValue::Bool(b) => if *b { "T" } else { "F" }.to_string(),
+            KeywordValue::Int(v) => v.to_string(),
+            KeywordValue::Float(v) => format_float(*v),
+            KeywordValue::ComplexInt(r, i) => format!("({}, {})", r, i),
+            KeywordValue::ComplexFloat(r, i) => {
+                format!("({}, {})", format_float(*r), format_float(*i))
+            }
+            _ => String::new(),
+        }
+    }
+
+    /// Serialize as a HIERARCH card with a free-format value
+    ///
+    /// Strings too long for the card continue on CONTINUE cards
+    fn hierarch_bytes(&self) -> Vec<u8> {
+        let comment = self
+            .comment_text()
+            .map(|c| format!(" / {}", c))
+            .unwrap_or_default();
+        let prefix = format!("HIERARCH {} = ", self.name);
+        let value = match &self.value {
+            KeywordValue::None => {
+                return card(format!(
+                    "HIERARCH {} {}",
+                    self.name,
+                    self.comment.clone().unwrap_or_default()
+                ))
+            }
+            KeywordValue::CommentText(t) => {
+                return card(format!("HIERARCH {} {}", self.name, t.trim_start()))
+            }
+            KeywordValue::Undefined => String::new(),
+            KeywordValue::String(s) => {
+                let quoted = format!("'{}'", s.replace('\'', "''"));
+                if prefix.len() + quoted.len() > 80 {
+                    // Quotes and '&' take three characters of the first card
+                    let first = 80usize.saturating_sub(prefix.len() + 3).max(1);
+                    return continued_cards(&prefix, split_long_string(s, first), &comment);
+                }
+                quoted
+            }
+            _ => self.hierarch_value(),
+        };
+        card(format!("{}{}{}", prefix, value, comment))
+    }
 }
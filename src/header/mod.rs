@@ -1,9 +1,43 @@
+mod dictionary;
 mod fitsblock;
+#[cfg(feature = "serde")]
+mod json;
 mod keyword;
+#[cfg(feature = "serde")]
+mod serialize;
+mod standard;
+mod template;
+#[cfg(feature = "time")]
+mod time;
 
+use crate::HeaderError;
+use std::collections::HashMap;
+
+pub use dictionary::{KeywordInfo, KeywordType};
 pub use fitsblock::FITSBlock;
+pub use keyword::FromKeywordValue;
 pub use keyword::Keyword;
 pub use keyword::KeywordValue;
+#[cfg(feature = "serde")]
+pub use serialize::serde_cards;
+#[cfg(feature = "time")]
+pub use time::{datetime_to_mjd, format_datetime, parse_datetime};
+
+/// Handling of value keywords that appear more than once in a header
+///
+/// The standard forbids duplicates, but files have them anyway
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum DuplicatePolicy {
+    /// Lookups return the first occurrence
+    #[default]
+    First,
+    /// Lookups return the last occurrence
+    Last,
+    /// Reading a header with duplicates fails; see `FITSReadOptions`
+    Error,
+    /// As `First`, but reading records a warning; see `FITSReadOptions`
+    Warn,
+}
 
 /// A Header structure represents the header portion of a
 /// FITS Header-Data Unit (HDU)
@@ -12,31 +46,149 @@ pub use keyword::KeywordValue;
 ///
 ///
 #[derive(Clone, Debug, Default)]
-pub struct Header(pub Vec<Keyword>);
+pub struct Header {
+    keywords: Vec<Keyword>,
+    // Position of the keyword returned by lookups of each name (the
+    // first, or the last per `duplicates`), for O(1) lookups
+    index: HashMap<String, usize>,
+    duplicates: DuplicatePolicy,
+    // Set when keywords have been handed out mutably (and so may have
+    // been renamed), until the index is next rebuilt
+    stale: bool,
+}
 
 impl std::ops::Deref for Header {
     type Target = Vec<Keyword>;
 
     fn deref(&self) -> &Self::Target {
-        &self.0
+        &self.keywords
     }
 }
 
-impl std::ops::DerefMut for Header {
-    fn deref_mut(&mut self) -> &mut Self::Target {
-        &mut self.0
+impl std::ops::Index<usize> for Header {
+    type Output = Keyword;
+
+    fn index(&self, idx: usize) -> &Self::Output {
+        &self.keywords[idx]
+    }
+}
+
+/// Mutable access to a keyword by position
+///
+/// Keywords may be renamed this way; lookups by name scan the header
+/// until the name index is next rebuilt (e.g. by `push` or `insert`)
+impl std::ops::IndexMut<usize> for Header {
+    fn index_mut(&mut self, idx: usize) -> &mut Self::Output {
+        self.stale = true;
+        &mut self.keywords[idx]
     }
 }
 
 impl Header {
+    /// Create a header from a list of keywords
+    ///
+    /// # Arguments
+    ///
+    /// * `keywords` - The keywords, in header order
+    ///
+    /// # Returns
+    ///
+    /// The header
+    ///
+    pub fn new(keywords: Vec<Keyword>) -> Header {
+        let mut header = Header {
+            keywords,
+            index: HashMap::new(),
+            duplicates: DuplicatePolicy::First,
+            stale: false,
+        };
+        header.reindex();
+        header
+    }
+
+    /// Rebuild the name index after keywords have moved
+    fn reindex(&mut self) {
+        self.stale = false;
+        self.index.clear();
+        for i in 0..self.keywords.len() {
+            self.index_keyword(i);
+        }
+    }
+
+    /// Add the keyword at a position to the name index
+    fn index_keyword(&mut self, pos: usize) {
+        let name = self.keywords[pos].name.clone();
+        match self.duplicates {
+            DuplicatePolicy::Last => {
+                self.index.insert(name, pos);
+            }
+            _ => {
+                self.index.entry(name).or_insert(pos);
+            }
+        }
+    }
+
     // Iterator to the keywords
-    pub fn iter(&self) -> std::slice::Iter<Keyword> {
-        self.0.iter()
+    pub fn iter(&self) -> std::slice::Iter<'_, Keyword> {
+        self.keywords.iter()
+    }
+
+    /// Append a keyword to the end of the header
+    ///
+    /// # Arguments
+    ///
+    /// * `kw` - The keyword to append
+    ///
+    pub fn push(&mut self, kw: Keyword) {
+        self.keywords.push(kw);
+        match self.stale {
+            true => self.reindex(),
+            false => self.index_keyword(self.keywords.len() - 1),
+        }
+    }
+
+    /// Position of the keyword returned by lookups of a name
+    fn position(&self, key: &str) -> Option<usize> {
+        if self.stale {
+            return match self.duplicates {
+                DuplicatePolicy::Last => self.keywords.iter().rposition(|kw| kw.name == key),
+                _ => self.keywords.iter().position(|kw| kw.name == key),
+            };
+        }
+        self.index.get(key).copied()
+    }
+
+    /// Insert a keyword at a position, shifting later keywords down
+    ///
+    /// # Arguments
+    ///
+    /// * `pos` - Position of the new keyword
+    /// * `kw` - The keyword to insert
+    ///
+    pub fn insert(&mut self, pos: usize, kw: Keyword) {
+        self.keywords.insert(pos, kw);
+        self.reindex();
+    }
+
+    /// Remove the keyword at a position
+    ///
+    /// # Arguments
+    ///
+    /// * `pos` - Position of the keyword to remove
+    ///
+    /// # Returns
+    ///
+    /// The removed keyword
+    ///
+    pub fn remove(&mut self, pos: usize) -> Keyword {
+        let kw = self.keywords.remove(pos);
+        self.reindex();
+        kw
     }
 
     /// Find a keyword in the header by key name
     ///
-    /// # ArgumentsIntoI
+    /// # Arguments
     ///
     /// * `key` - The name of the keyword to find
     ///
@@ -45,7 +197,82 @@ impl Header {
     /// The keyword if found, otherwise None
     ///
     pub fn find(&self, key: &str) -> Option<&Keyword> {
-        self.0.iter().find(|x| x.name == key)
+        let key = key.strip_prefix("HIERARCH ").unwrap_or(key);
+        self.position(key).map(|i| &self.keywords[i])
+    }
+
+    /// Find every keyword with a name, e.g. a value keyword that is
+    /// (against the standard) duplicated, or all HISTORY cards
+    ///
+    /// # Arguments
+    ///
+    /// * `key` - The name of the keywords to find
+    ///
+    /// # Returns
+    ///
+    /// The keywords, in header order
+    ///
+    pub fn find_all(&self, key: &str) -> Vec<&Keyword> {
+        let key = key.strip_prefix("HIERARCH ").unwrap_or(key);
+        self.keywords.iter().filter(|kw| kw.name == key).collect()
+    }
+
+    /// Names of value keywords that appear more than once, in order of
+    /// their second occurrence.  Commentary and CONTINUE cards may
+    /// repeat and are not included
+    pub fn duplicates(&self) -> Vec<&str> {
+        let mut seen = std::collections::HashSet::new();
+        let mut duplicates = Vec::new();
+        for kw in &self.keywords {
+            if matches!(kw.value, KeywordValue::CommentText(_))
+                || matches!(
+                    kw.name.as_str(),
+                    "COMMENT" | "HISTORY" | "" | "CONTINUE" | "END"
+                )
+            {
+                continue;
+            }
+            if !seen.insert(kw.name.as_str()) && !duplicates.contains(&kw.name.as_str()) {
+                duplicates.push(kw.name.as_str());
+            }
+        }
+        duplicates
+    }
+
+    /// How duplicated keywords are looked up
+    pub fn duplicate_policy(&self) -> DuplicatePolicy {
+        self.duplicates
+    }
+
+    /// Choose which occurrence of a duplicated keyword lookups such as
+    /// `find`, `value`, and `get` return: the last for
+    /// `DuplicatePolicy::Last`, otherwise the first
+    pub fn set_duplicate_policy(&mut self, policy: DuplicatePolicy) {
+        self.duplicates = policy;
+        self.reindex();
+    }
+
+    /// Remove duplicated value keywords, keeping the occurrence that
+    /// lookups return (see `set_duplicate_policy`) in its place
+    ///
+    /// # Returns
+    ///
+    /// The number of keywords removed
+    ///
+    pub fn deduplicate(&mut self) -> usize {
+        if self.stale {
+            self.reindex();
+        }
+        let duplicates: Vec<String> = self.duplicates().iter().map(|s| s.to_string()).collect();
+        let before = self.keywords.len();
+        let index = &self.index;
+        let mut pos = 0;
+        self.keywords.retain(|kw| {
+            pos += 1;
+            !duplicates.contains(&kw.name) || index.get(&kw.name) == Some(&(pos - 1))
+        });
+        self.reindex();
+        before - self.keywords.len()
     }
 
     /// Return value given a key
@@ -59,7 +286,258 @@ impl Header {
     /// The value of the keyword if found, otherwise None
     ///
     pub fn value(&self, key: &str) -> Option<&KeywordValue> {
-        self.0.iter().find(|x| x.name == key).map(|x| &x.value)
+        self.find(key).map(|x| &x.value)
+    }
+
+    /// Return value and unit given a key
+    ///
+    /// The unit is taken from the keyword comment, where it is given
+    /// in brackets ahead of the comment text, e.g. "/ [s] exposure time"
+    ///
+    /// # Arguments
+    ///
+    /// * `key` - The name of the keyword to find
+    ///
+    /// # Returns
+    ///
+    /// The value of the keyword and its unit, if any, or None if the
+    /// keyword is not found
+    ///
+    pub fn value_with_unit(&self, key: &str) -> Option<(&KeywordValue, Option<&str>)> {
+        self.find(key).map(|kw| (&kw.value, kw.unit.as_deref()))
+    }
+
+    /// Return the value of a keyword converted to a given type
+    ///
+    /// Integer values coerce to floats, and numeric strings are
+    /// parsed.  See `FromKeywordValue` for the supported types
+    ///
+    /// # Arguments
+    ///
+    /// * `key` - The name of the keyword to find
+    ///
+    /// # Returns
+    ///
+    /// The converted value, `HeaderError::MissingKeyword` if the keyword
+    /// is not present, or `HeaderError::UnexpectedValueType` if its value
+    /// cannot be converted
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use fits::{Header, KeywordValue};
+    /// # fn main() -> Result<(), fits::HeaderError> {
+    /// # let mut header = Header::default();
+    /// # header.set("EXPTIME", KeywordValue::Float(30.0));
+    /// let exptime = header.get::<f64>("EXPTIME")?;
+    /// assert_eq!(exptime, 30.0);
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    pub fn get<T: FromKeywordValue>(&self, key: &str) -> Result<T, HeaderError> {
+        let value = self
+            .value(key)
+            .ok_or_else(|| HeaderError::MissingKeyword(key.to_string()))?;
+        T::from_value(value).ok_or_else(|| HeaderError::UnexpectedValueType(key.to_string()))
+    }
+
+    /// Keywords of an indexed family, such as NAXISn or TTYPEn
+    ///
+    /// Matches keywords whose name is `prefix` followed only by a
+    /// positive decimal index.  Indices need not be contiguous or
+    /// appear in order in the header
+    ///
+    /// # Arguments
+    ///
+    /// * `prefix` - The keyword name without its index, e.g. "TTYPE"
+    ///
+    /// # Returns
+    ///
+    /// Iterator of (index, keyword) pairs, sorted by index.  Keywords
+    /// with the same index are returned in header order
+    ///
+    pub fn indexed<'a>(&'a self, prefix: &str) -> impl Iterator<Item = (usize, &'a Keyword)> {
+        let mut family: Vec<(usize, &Keyword)> = self
+            .keywords
+            .iter()
+            .filter_map(|kw| {
+                let n = kw.name.strip_prefix(prefix)?;
+                if n.is_empty() || !n.bytes().all(|b| b.is_ascii_digit()) {
+                    return None;
+                }
+                n.parse().ok().filter(|&n| n > 0).map(|n| (n, kw))
+            })
+            .collect();
+        family.sort_by_key(|(n, _)| *n);
+        family.into_iter()
+    }
+
+    /// Text of commentary cards with the given keyword name
+    ///
+    /// The text starts after the space that conventionally separates
+    /// it from the keyword name in column 9, as added by `add_comment`
+    /// and `add_history`
+    fn commentary(&self, name: &str) -> Vec<&str> {
+        self.keywords
+            .iter()
+            .filter(|kw| kw.name == name)
+            .filter_map(|kw| match &kw.value {
+                KeywordValue::CommentText(t) => Some(t.strip_prefix(' ').unwrap_or(t)),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Text of all COMMENT cards, in header order
+    pub fn comments(&self) -> Vec<&str> {
+        self.commentary("COMMENT")
+    }
+
+    /// Text of all HISTORY cards, in header order
+    pub fn history(&self) -> Vec<&str> {
+        self.commentary("HISTORY")
+    }
+
+    /// Add a commentary card before the END card, if present
+    fn add_commentary(&mut self, name: &str, text: &str) {
+        let kw = Keyword {
+            name: name.to_string(),
+            value: KeywordValue::CommentText(format!(" {}", text)),
+            ..Default::default()
+        };
+        self.insert_before_end(kw);
+    }
+
+    /// Insert a keyword before the END card, or append if there is none
+    pub(crate) fn insert_before_end(&mut self, kw: Keyword) {
+        match self.position("END") {
+            Some(pos) => self.insert(pos, kw),
+            None => self.push(kw),
+        }
+    }
+
+    /// Set the value of a keyword
+    ///
+    /// The keyword that lookups return (the first with the given name,
+    /// or the last per `set_duplicate_policy`) is updated, keeping its
+    /// comment; if there is none, a new keyword is added before END
+    ///
+    /// # Arguments
+    ///
+    /// * `key` - The name of the keyword
+    /// * `value` - The new value
+    ///
+    pub fn set(&mut self, key: &str, value: KeywordValue) {
+        match self.position(key) {
+            Some(pos) => self.keywords[pos].value = value,
+            None => self.insert_before_end(Keyword {
+                name: key.to_string(),
+                value,
+                ..Default::default()
+            }),
+        }
+    }
+
+    /// Add a COMMENT card
+    ///
+    /// Text longer than fits on one card is written over
+    /// several consecutive COMMENT cards
+    pub fn add_comment(&mut self, text: &str) {
+        self.add_commentary("COMMENT", text);
+    }
+
+    /// Add a HISTORY card
+    ///
+    /// Text longer than fits on one card is written over
+    /// several consecutive HISTORY cards
+    pub fn add_history(&mut self, text: &str) {
+        self.add_commentary("HISTORY", text);
+    }
+
+    /// Append a keyword parsed from a header card
+    ///
+    /// CONTINUE cards following a string value that ends in '&' are
+    /// merged into that value, per the long-string convention
+    /// (Section 4.2.1.2 of FITS standard, version 4).  Comments of the
+    /// continued cards are joined with a space
+    pub(crate) fn push_card(&mut self, kw: Keyword) {
+        if kw.name == "CONTINUE" {
+            if let (Some(prev), KeywordValue::String(more)) = (self.keywords.last_mut(), &kw.value)
+            {
+                if let KeywordValue::String(s) = &mut prev.value {
+                    if s.ends_with('&') {
+                        s.pop();
+                        s.push_str(more);
+                        prev.comment = match (prev.comment.take(), kw.comment) {
+                            (Some(a), Some(b)) => Some(format!("{} {}", a, b)),
+                            (a, b) => a.or(b),
+                        };
+                        if let (Some(raw), Some(more)) = (prev.raw.as_mut(), kw.raw) {
+                            raw.extend(more);
+                        }
+                        prev.split_unit();
+                        return;
+                    }
+                }
+            }
+        }
+        self.push(kw);
+    }
+
+    /// Serialize the header as FITS blocks
+    ///
+    /// An END card is added if not present, and the result is padded
+    /// with spaces to a multiple of 2880 bytes
+    ///
+    /// # Returns
+    ///
+    /// Header bytes
+    ///
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = self.card_bytes();
+        bytes.extend(Self::end_card());
+        bytes.resize(bytes.len().div_ceil(2880) * 2880, b' ');
+        bytes
+    }
+
+    /// Serialize the header into exactly `nbytes` bytes
+    ///
+    /// Blank cards are added before the END card to fill the space
+    ///
+    /// # Arguments
+    ///
+    /// * `nbytes` - Size of the serialized header, a multiple of 2880
+    ///
+    /// # Returns
+    ///
+    /// Header bytes, or None if the keywords do not fit
+    ///
+    pub(crate) fn to_bytes_sized(&self, nbytes: usize) -> Option<Vec<u8>> {
+        let mut bytes = self.card_bytes();
+        if bytes.len() + 80 > nbytes {
+            return None;
+        }
+        bytes.resize(nbytes - 80, b' ');
+        bytes.extend(Self::end_card());
+        Some(bytes)
+    }
+
+    /// Bytes of all cards before END
+    fn card_bytes(&self) -> Vec<u8> {
+        self.keywords
+            .iter()
+            .take_while(|kw| kw.name != "END")
+            .flat_map(|kw| kw.to_bytes())
+            .collect()
+    }
+
+    fn end_card() -> Vec<u8> {
+        Keyword {
+            name: "END".to_string(),
+            ..Default::default()
+        }
+        .to_bytes()
     }
 }
 
@@ -73,7 +551,7 @@ impl<'a> Iterator for HeaderIntoIterator<'a> {
 
     fn next(&mut self) -> Option<Self::Item> {
         if self.index < self.header.len() {
-            let kw = self.header.0[self.index].clone();
+            let kw = self.header.keywords[self.index].clone();
             self.index += 1;
             Some(kw)
         } else {
@@ -93,3 +571,322 @@ impl<'a> IntoIterator for &'a Header {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testutil::{cards, kw};
+
+    /// Parse header bytes into a header, one card at a time up to END
+    fn parse(bytes: &[u8]) -> Header {
+        let mut header = Header::default();
+        for chunk in bytes.chunks_exact(80) {
+            let kw = Keyword::new(chunk).unwrap();
+            let end = kw.name == "END";
+            header.push_card(kw);
+            if end {
+                break;
+            }
+        }
+        header
+    }
+
+    #[test]
+    fn test_long_string() {
+        let long = "/data/archive/".to_string() + &"subdirectory/".repeat(10) + "it's.fits";
+        let header = Header::new(vec![
+            Keyword {
+                name: "FILENAME".to_string(),
+                value: KeywordValue::String(long.clone()),
+                comment: Some("original file".to_string()),
+                ..Default::default()
+            },
+            Keyword {
+                name: "SHORT".to_string(),
+                value: KeywordValue::String("abc".to_string()),
+                ..Default::default()
+            },
+        ]);
+        let bytes = header.to_bytes();
+        assert_eq!(bytes.len(), 2880);
+        assert_eq!(&bytes[160..170], b"CONTINUE  ");
+        assert_eq!(&bytes[80..90], b"CONTINUE  ");
+        assert_eq!(&bytes[240..270], b"SHORT   = 'abc     '          ");
+
+        let parsed = parse(&bytes);
+        assert_eq!(parsed.len(), 3);
+        assert_eq!(
+            parsed.value("FILENAME"),
+            Some(&KeywordValue::String(long.clone()))
+        );
+        assert_eq!(parsed[0].comment, Some("original file".to_string()));
+        assert_eq!(parsed[2].name, "END");
+    }
+
+    #[test]
+    fn test_card_values() {
+        let header = Header::new(vec![
+            Keyword {
+                name: "EXPTIME".to_string(),
+                value: KeywordValue::Float(1.5e-12),
+                unit: Some("s".to_string()),
+                ..Default::default()
+            },
+            Keyword {
+                name: "NAXIS".to_string(),
+                value: KeywordValue::Int(-42),
+                ..Default::default()
+            },
+            Keyword {
+                name: "SIMPLE".to_string(),
+                value: KeywordValue::Bool(true),
+                ..Default::default()
+            },
+            Keyword {
+                name: "BLANKVAL".to_string(),
+                value: KeywordValue::Undefined,
+                ..Default::default()
+            },
+        ]);
+        let bytes = header.to_bytes();
+        assert_eq!(&bytes[0..30], b"EXPTIME =              1.5E-12");
+        let parsed = parse(&bytes);
+        for (a, b) in header.iter().zip(parsed.iter()) {
+            assert_eq!(a.name, b.name);
+            assert_eq!(a.value, b.value);
+            assert_eq!(a.comment, b.comment);
+            assert_eq!(a.unit, b.unit);
+        }
+    }
+
+    #[test]
+    fn test_units() {
+        let raw = cards(&[
+            "VELOCITY=                -12.5 / [km/s] radial velocity",
+            "EXPTIME =                   30 / [s]",
+            "RA      = '12:34:56.7'         / [hh:mm:ss] right ascension",
+            "NOTE    =                    1 / see [1]",
+            "END",
+        ]);
+        let mut header = parse(&raw);
+        assert_eq!(
+            header.value_with_unit("VELOCITY"),
+            Some((&KeywordValue::Float(-12.5), Some("km/s")))
+        );
+        assert_eq!(header[0].comment.as_deref(), Some("radial velocity"));
+        assert_eq!(header[1].comment, None);
+        assert_eq!(header[2].unit.as_deref(), Some("hh:mm:ss"));
+        assert_eq!(
+            header.value_with_unit("NOTE"),
+            Some((&KeywordValue::Int(1), None))
+        );
+        assert_eq!(header.to_bytes()[..raw.len()], raw[..]);
+
+        header.set("EXPTIME", KeywordValue::Int(60));
+        header[0].unit = Some("m/s".to_string());
+        let bytes = header.to_bytes();
+        assert_eq!(
+            bytes[..160],
+            cards(&[
+                "VELOCITY=                -12.5 / [m/s] radial velocity",
+                "EXPTIME =                   60 / [s]",
+            ])
+        );
+    }
+
+    #[test]
+    fn test_hierarch() {
+        let text = b"HIERARCH ESO DET CHIP1 NAME = 'CCD-44' / chip name";
+        let mut card = [b' '; 80];
+        card[..text.len()].copy_from_slice(text);
+        let kw = Keyword::new(&card).unwrap();
+        assert_eq!(kw.name, "ESO DET CHIP1 NAME");
+        assert_eq!(kw.value, KeywordValue::String("CCD-44".to_string()));
+        assert_eq!(kw.comment, Some("chip name".to_string()));
+        assert!(kw.is_hierarch());
+        assert_eq!(kw.to_bytes(), card.to_vec());
+
+        card[30..].copy_from_slice(&[b' '; 50]);
+        card[30..36].copy_from_slice(b"2.5D-3");
+        let kw = Keyword::new(&card).unwrap();
+        assert_eq!(kw.value, KeywordValue::Float(2.5e-3));
+
+        let header = parse(&Header::new(vec![kw]).to_bytes());
+        assert_eq!(
+            header.value("HIERARCH ESO DET CHIP1 NAME"),
+            Some(&KeywordValue::Float(2.5e-3))
+        );
+        assert_eq!(header.find("ESO DET CHIP1 NAME").unwrap().comment, None);
+
+        // Long strings continue on CONTINUE cards
+        let long = "'x' ".repeat(30);
+        let kw = Keyword {
+            name: "ESO OBS TARGET DESCRIPTION".to_string(),
+            value: KeywordValue::String(long.clone()),
+            comment: Some("target".to_string()),
+            ..Default::default()
+        };
+        assert!(!kw.overflows_card());
+        let bytes = kw.to_bytes();
+        assert_eq!(bytes.len(), 320);
+        assert_eq!(&bytes[80..90], b"CONTINUE  ");
+        let header = parse(&Header::new(vec![kw]).to_bytes());
+        let parsed = header.find("ESO OBS TARGET DESCRIPTION").unwrap();
+        assert_eq!(
+            parsed.value,
+            KeywordValue::String(long.trim_end().to_string())
+        );
+        assert_eq!(parsed.comment, Some("target".to_string()));
+
+        // Other values too long for the card are refused on write
+        let kw = Keyword {
+            name: "ESO ".repeat(16).trim_end().to_string(),
+            value: KeywordValue::Float(1.0e-300),
+            ..Default::default()
+        };
+        assert!(kw.overflows_card());
+    }
+
+    #[test]
+    fn test_commentary() {
+        let mut bytes = cards(&[
+            "SIMPLE  =                    T",
+            "COMMENT = not a value",
+            "",
+            "HISTORY   flat-fielded",
+            "        free text with blank name",
+            "END",
+        ]);
+        bytes.resize(2880, b' ');
+        let mut header = parse(&bytes[0..480]);
+        assert_eq!(header.len(), 6);
+        assert_eq!(header.comments(), vec!["= not a value"]);
+        assert_eq!(header.history(), vec![" flat-fielded"]);
+        assert_eq!(header[2].value, KeywordValue::CommentText(String::new()));
+        assert_eq!(header.to_bytes(), bytes);
+
+        header.add_history(&"x".repeat(100));
+        assert_eq!(header.len(), 7);
+        assert_eq!(header[5].name, "HISTORY");
+        let parsed = parse(&header.to_bytes()[0..640]);
+        assert_eq!(parsed.history().len(), 3);
+        assert_eq!(parsed.history()[1], "x".repeat(71));
+        assert_eq!(parsed.history()[2], "x".repeat(29));
+
+        header.add_comment("flat-fielded");
+        assert_eq!(header.comments().last(), Some(&"flat-fielded"));
+    }
+
+    #[test]
+    fn test_index() {
+        let mut header = Header::new(vec![
+            kw("A", KeywordValue::Int(1)),
+            kw("B", KeywordValue::Int(2)),
+            kw("A", KeywordValue::Int(3)),
+        ]);
+        assert_eq!(header.value("A"), Some(&KeywordValue::Int(1)));
+
+        header.remove(0);
+        assert_eq!(header.value("A"), Some(&KeywordValue::Int(3)));
+        header.insert(0, kw("C", KeywordValue::Int(4)));
+        header.push(kw("D", KeywordValue::Int(5)));
+        assert_eq!(header.value("B"), Some(&KeywordValue::Int(2)));
+        assert_eq!(header.value("C"), Some(&KeywordValue::Int(4)));
+        assert_eq!(header.value("D"), Some(&KeywordValue::Int(5)));
+        assert_eq!(header.value("E"), None);
+
+        // Renaming through IndexMut keeps lookups correct
+        header[0].name = "E".to_string();
+        assert_eq!(header.value("E"), Some(&KeywordValue::Int(4)));
+        assert_eq!(header.value("C"), None);
+        header[1].name = "C".to_string();
+        assert_eq!(header.value("C"), Some(&KeywordValue::Int(2)));
+        header.push(kw("F", KeywordValue::Int(6)));
+        assert_eq!(header.value("C"), Some(&KeywordValue::Int(2)));
+        assert_eq!(header.value("B"), None);
+    }
+
+    #[test]
+    fn test_duplicates() {
+        let mut header = Header::new(vec![
+            kw("A", KeywordValue::Int(1)),
+            kw("B", KeywordValue::Int(2)),
+            kw("A", KeywordValue::Int(3)),
+            kw("HISTORY", KeywordValue::Int(0)),
+            kw("A", KeywordValue::Int(4)),
+        ]);
+        header.push(Keyword {
+            name: "HISTORY".to_string(),
+            value: KeywordValue::CommentText("again".to_string()),
+            ..Default::default()
+        });
+        let values: Vec<&KeywordValue> = header.find_all("A").iter().map(|kw| &kw.value).collect();
+        assert_eq!(
+            values,
+            [
+                &KeywordValue::Int(1),
+                &KeywordValue::Int(3),
+                &KeywordValue::Int(4)
+            ]
+        );
+        assert_eq!(header.duplicates(), ["A"]);
+
+        header.set_duplicate_policy(DuplicatePolicy::Last);
+        assert_eq!(header.value("A"), Some(&KeywordValue::Int(4)));
+        header.push(kw("B", KeywordValue::Int(5)));
+        assert_eq!(header.value("B"), Some(&KeywordValue::Int(5)));
+
+        assert_eq!(header.deduplicate(), 3);
+        let names: Vec<&str> = header.iter().map(|kw| kw.name.as_str()).collect();
+        assert_eq!(names, ["HISTORY", "A", "HISTORY", "B"]);
+        assert_eq!(header.value("A"), Some(&KeywordValue::Int(4)));
+        assert!(header.duplicates().is_empty());
+    }
+
+    #[test]
+    fn test_get() {
+        let header = Header::new(vec![
+            kw("NAXIS", KeywordValue::Int(2)),
+            kw("EXPTIME", KeywordValue::Float(15.0)),
+            kw("GAIN", KeywordValue::String(" 1.5D0 ".to_string())),
+            kw("OBSERVER", KeywordValue::String("Hubble".to_string())),
+            kw("SIMPLE", KeywordValue::Bool(true)),
+        ]);
+        assert_eq!(header.get::<usize>("NAXIS").unwrap(), 2);
+        assert_eq!(header.get::<f64>("NAXIS").unwrap(), 2.0);
+        assert_eq!(header.get::<f64>("EXPTIME").unwrap(), 15.0);
+        assert_eq!(header.get::<f64>("GAIN").unwrap(), 1.5);
+        assert_eq!(header.get::<String>("OBSERVER").unwrap(), "Hubble");
+        assert!(header.get::<bool>("SIMPLE").unwrap());
+        assert!(matches!(
+            header.get::<i64>("EXPTIME"),
+            Err(HeaderError::UnexpectedValueType(_))
+        ));
+        assert!(matches!(
+            header.get::<f64>("OBSERVER"),
+            Err(HeaderError::UnexpectedValueType(_))
+        ));
+        assert!(matches!(
+            header.get::<f64>("AIRMASS"),
+            Err(HeaderError::MissingKeyword(_))
+        ));
+    }
+
+    #[test]
+    fn test_indexed() {
+        let header = Header::new(
+            [
+                "TFIELDS", "TTYPE3", "TTYPE1", "TTYPEX", "TTYPE", "TTYPE0", "TTYPE10",
+            ]
+            .iter()
+            .map(|name| kw(name, KeywordValue::String(name.to_lowercase())))
+            .collect(),
+        );
+        let family: Vec<(usize, &str)> = header
+            .indexed("TTYPE")
+            .map(|(n, kw)| (n, kw.name.as_str()))
+            .collect();
+        assert_eq!(family, vec![(1, "TTYPE1"), (3, "TTYPE3"), (10, "TTYPE10")]);
+        assert_eq!(header.indexed("NAXIS").count(), 0);
+    }
+}
@@ -0,0 +1,104 @@
+//! Serde support for headers, enabled by the `serde` feature
+//!
+//! A header serializes as the list of its keywords.  For a compact
+//! form that preserves the exact card text, use `serde_cards`
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use super::Header;
+use super::Keyword;
+
+impl Serialize for Header {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.keywords.serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Header {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Ok(Header::new(Vec::<Keyword>::deserialize(deserializer)?))
+    }
+}
+
+/// Serialize a header as the text of its 80-character cards
+///
+/// Trailing blanks of each card are dropped and the END card is
+/// omitted.  Use with `#[serde(with = "fits::serde_cards")]`:
+///
+/// ```
+/// # use fits::Header;
+/// # use serde::{Deserialize, Serialize};
+/// #[derive(Serialize, Deserialize)]
+/// struct Entry {
+///     path: String,
+///     #[serde(with = "fits::serde_cards")]
+///     header: Header,
+/// }
+/// ```
+pub mod serde_cards {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(header: &Header, serializer: S) -> Result<S::Ok, S::Error> {
+        let cards: Vec<String> = header
+            .card_bytes()
+            .chunks(80)
+            .map(|card| String::from_utf8_lossy(card).trim_end().to_string())
+            .collect();
+        cards.serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Header, D::Error> {
+        let mut header = Header::default();
+        for card in Vec::<String>::deserialize(deserializer)? {
+            if card.len() > 80 {
+                return Err(serde::de::Error::custom(format!(
+                    "card longer than 80 characters: {}",
+                    card
+                )));
+            }
+            let keyword = Keyword::new(format!("{:<80}", card).as_bytes())
+                .map_err(serde::de::Error::custom)?;
+            if keyword.name != "END" {
+                header.push_card(keyword);
+            }
+        }
+        Ok(header)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::KeywordValue;
+
+    fn header() -> Header {
+        let mut header = Header::default();
+        header.set("SIMPLE", KeywordValue::Bool(true));
+        header.set("NAXIS", KeywordValue::Int(0));
+        header.set("OBJECT", KeywordValue::String("M31".to_string()));
+        header.add_comment("a comment");
+        header
+    }
+
+    #[test]
+    fn test_serde_header() {
+        let json = serde_json::to_string(&header()).unwrap();
+        let back: Header = serde_json::from_str(&json).unwrap();
+        assert_eq!(back.len(), 4);
+        assert_eq!(back.object(), Some("M31"));
+        assert_eq!(back.to_bytes(), header().to_bytes());
+    }
+
+    #[test]
+    fn test_serde_cards() {
+        #[derive(Serialize, Deserialize)]
+        struct Entry {
+            #[serde(with = "serde_cards")]
+            header: Header,
+        }
+        let json = serde_json::to_string(&Entry { header: header() }).unwrap();
+        assert!(json.contains("\"OBJECT  = 'M31     '\""));
+        let back: Entry = serde_json::from_str(&json).unwrap();
+        assert_eq!(back.header.to_bytes(), header().to_bytes());
+    }
+}
@@ -0,0 +1,147 @@
+//! Accessors for commonly used standard keywords
+//!
+//! See Section 4.4 of FITS standard, version 4
+
+use super::Header;
+use crate::Bitpix;
+use crate::KeywordValue;
+
+impl Header {
+    /// String value of a keyword, if present and a string
+    fn string_value(&self, key: &str) -> Option<&str> {
+        match self.value(key) {
+            Some(KeywordValue::String(s)) => Some(s.as_str()),
+            _ => None,
+        }
+    }
+
+    /// Data type of the array, from BITPIX
+    pub fn bitpix(&self) -> Option<Bitpix> {
+        self.get::<i64>("BITPIX")
+            .ok()
+            .and_then(|b| Bitpix::from_i64(b).ok())
+    }
+
+    /// Lengths of the data axes, NAXIS1 through NAXISn
+    ///
+    /// # Returns
+    ///
+    /// The axis lengths, or None if NAXIS or any NAXISn is missing
+    ///
+    pub fn naxes(&self) -> Option<Vec<usize>> {
+        let naxis = self.get::<usize>("NAXIS").ok()?;
+        (1..=naxis)
+            .map(|i| self.get::<usize>(format!("NAXIS{}", i).as_str()).ok())
+            .collect()
+    }
+
+    /// Size of the data unit in bytes, excluding fill
+    ///
+    /// Computed from BITPIX, NAXISn, PCOUNT and GCOUNT, per Section 4.4.1.1
+    /// (primary) and 7.4.1 (extensions) of FITS standard, version 4.
+    /// Random-groups data (NAXIS1 = 0) skips the first axis
+    ///
+    /// # Returns
+    ///
+    /// Number of bytes, or None if a structural keyword is missing or
+    /// invalid, or the size overflows
+    ///
+    pub fn data_size(&self) -> Option<usize> {
+        let bytes = (self.get::<i64>("BITPIX").ok()?.unsigned_abs() / 8) as usize;
+        let axes = self.naxes()?;
+        if axes.is_empty() {
+            return Some(0);
+        }
+        let axes = match axes[0] {
+            0 => &axes[1..],
+            _ => &axes[..],
+        };
+        let npix = axes.iter().try_fold(1usize, |n, &len| n.checked_mul(len))?;
+        let pcount = self.get::<usize>("PCOUNT").unwrap_or(0);
+        let gcount = self.get::<usize>("GCOUNT").unwrap_or(1);
+        bytes
+            .checked_mul(gcount)?
+            .checked_mul(pcount.checked_add(npix)?)
+    }
+
+    /// Name of the extension, from EXTNAME
+    pub fn extname(&self) -> Option<&str> {
+        self.string_value("EXTNAME")
+    }
+
+    /// Version of the extension, from EXTVER
+    ///
+    /// # Returns
+    ///
+    /// The version, or 1 if EXTVER is absent, as the standard specifies
+    ///
+    pub fn extver(&self) -> i64 {
+        self.get::<i64>("EXTVER").unwrap_or(1)
+    }
+
+    /// Name of the observed object, from OBJECT
+    pub fn object(&self) -> Option<&str> {
+        self.string_value("OBJECT")
+    }
+
+    /// Telescope used for the observation, from TELESCOP
+    pub fn telescope(&self) -> Option<&str> {
+        self.string_value("TELESCOP")
+    }
+
+    /// Instrument used for the observation, from INSTRUME
+    pub fn instrument(&self) -> Option<&str> {
+        self.string_value("INSTRUME")
+    }
+
+    /// Exposure time in seconds, from EXPTIME
+    pub fn exptime(&self) -> Option<f64> {
+        self.get::<f64>("EXPTIME").ok()
+    }
+
+    /// Start time of the observation, from DATE-OBS, as the header string
+    pub fn date_obs(&self) -> Option<&str> {
+        self.string_value("DATE-OBS")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::testutil::kw;
+    use crate::{Bitpix, Header, KeywordValue};
+
+    #[test]
+    fn test_standard() {
+        let s = |v: &str| KeywordValue::String(v.to_string());
+        let header = Header::new(vec![
+            kw("XTENSION", s("IMAGE")),
+            kw("BITPIX", KeywordValue::Int(-32)),
+            kw("NAXIS", KeywordValue::Int(2)),
+            kw("NAXIS1", KeywordValue::Int(100)),
+            kw("NAXIS2", KeywordValue::Int(50)),
+            kw("EXTNAME", s("SCI")),
+            kw("OBJECT", s("M31")),
+            kw("TELESCOP", s("HST")),
+            kw("INSTRUME", s("WFPC2")),
+            kw("EXPTIME", KeywordValue::Int(300)),
+            kw("DATE-OBS", s("1995-12-18T12:00:00")),
+        ]);
+        assert_eq!(header.bitpix(), Some(Bitpix::Float32));
+        assert_eq!(header.naxes(), Some(vec![100, 50]));
+        assert_eq!(header.extname(), Some("SCI"));
+        assert_eq!(header.extver(), 1);
+        assert_eq!(header.object(), Some("M31"));
+        assert_eq!(header.telescope(), Some("HST"));
+        assert_eq!(header.instrument(), Some("WFPC2"));
+        assert_eq!(header.exptime(), Some(300.0));
+        assert_eq!(header.date_obs(), Some("1995-12-18T12:00:00"));
+        assert_eq!(header.data_size(), Some(100 * 50 * 4));
+        assert_eq!(Header::default().naxes(), None);
+        assert_eq!(Header::default().data_size(), None);
+
+        let mut header = header;
+        header.set("NAXIS1", KeywordValue::Int(1 << 32));
+        header.set("NAXIS2", KeywordValue::Int(1 << 32));
+        assert_eq!(header.data_size(), None);
+    }
+}
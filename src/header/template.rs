@@ -0,0 +1,252 @@
+//! Header templates in the style of cfitsio's `fits_parse_template`
+//!
+//! A template is text with one card specification per line:
+//!
+//! ```text
+//! # Standard keywords of the pipeline
+//! TELESCOP = 'NOT'            / telescope
+//! INSTRUME   ALFOSC
+//! EXPTIME    0.0              / [s] exposure time
+//! TTYPE#   = 'TIME'
+//! TTYPE#   = 'RATE'
+//! HISTORY    created from template
+//! -BLANK
+//! ```
+//!
+//! - Blank lines, and lines starting with `#`, are ignored
+//! - The `=` after the name is optional, and the value is written as
+//!   on a card: quoted strings, `T` or `F`, integers, floats, and
+//!   complex numbers in parentheses.  Any other value is taken as a
+//!   string, so quotes may be omitted for single words
+//! - A `#` in the name is replaced by a counter, which starts at 1
+//!   and increases each time the same name is repeated, so that
+//!   `TTYPE#` gives TTYPE1, TTYPE2, and so on
+//! - A name starting with `-` removes the keyword when the template
+//!   is applied to a header
+//! - An `END` line ends the template
+
+use super::keyword::parse_free_value;
+use super::Header;
+use super::Keyword;
+use super::KeywordValue;
+use crate::FITSError;
+use crate::HeaderError;
+use std::collections::HashMap;
+
+/// A parsed template line
+enum TemplateCard {
+    Set(Keyword),
+    Delete(String),
+}
+
+/// Parse a template into cards, in order
+fn parse(text: &str) -> Result<Vec<TemplateCard>, HeaderError> {
+    let mut cards = Vec::new();
+    let mut counters: HashMap<String, usize> = HashMap::new();
+    for (n, line) in text.lines().enumerate() {
+        let invalid = |why: &str| HeaderError::InvalidTemplate(n + 1, why.to_string());
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let (delete, line) = match line.strip_prefix('-') {
+            Some(rest) => (true, rest),
+            None => (false, line),
+        };
+        let end = line
+            .find(|c: char| c.is_whitespace() || c == '=')
+            .unwrap_or(line.len());
+        let mut name = line[..end].to_uppercase();
+        let rest = line[end..].trim_start();
+        let rest = rest.strip_prefix('=').unwrap_or(rest).trim();
+        if name == "END" && !delete {
+            break;
+        }
+        if name.contains('#') {
+            let counter = counters.entry(name.clone()).or_insert(0);
+            *counter += 1;
+            name = name.replace('#', &counter.to_string());
+        }
+        if name.is_empty()
+            || name.len() > 8
+            || !name
+                .chars()
+                .all(|c| c.is_ascii_uppercase() || c.is_ascii_digit() || c == '_' || c == '-')
+        {
+            return Err(invalid(&format!("invalid keyword name \"{}\"", name)));
+        }
+        if delete {
+            cards.push(TemplateCard::Delete(name));
+            continue;
+        }
+        let (value, comment) = match name.as_str() {
+            "COMMENT" | "HISTORY" => (KeywordValue::CommentText(format!(" {}", rest)), None),
+            _ => parse_free_value(rest).unwrap_or_else(|| match rest.split_once('/') {
+                Some((value, comment)) => (
+                    KeywordValue::String(value.trim().to_string()),
+                    Some(comment.trim().to_string()).filter(|c| !c.is_empty()),
+                ),
+                None => (KeywordValue::String(rest.to_string()), None),
+            }),
+        };
+        let mut kw = Keyword {
+            name,
+            value,
+            comment,
+            ..Default::default()
+        };
+        kw.split_unit();
+        cards.push(TemplateCard::Set(kw));
+    }
+    Ok(cards)
+}
+
+impl Header {
+    /// Create a header from a template; see the module documentation
+    /// for the syntax
+    ///
+    /// # Arguments
+    ///
+    /// * `text` - The template
+    ///
+    /// # Returns
+    ///
+    /// The header, with the cards in template order, or an error
+    /// naming the first invalid line.  Removals (`-NAME`) are ignored
+    ///
+    pub fn from_template(text: &str) -> Result<Header, HeaderError> {
+        let mut header = Header::default();
+        for card in parse(text)? {
+            if let TemplateCard::Set(kw) = card {
+                header.push(kw);
+            }
+        }
+        Ok(header)
+    }
+
+    /// Create a header from a template file; see `from_template`
+    ///
+    /// # Arguments
+    ///
+    /// * `file` - Path of the template
+    ///
+    pub fn from_template_file(file: &str) -> Result<Header, FITSError> {
+        Ok(Self::from_template(&std::fs::read_to_string(file)?)?)
+    }
+
+    /// Stamp the keywords of a template onto this header
+    ///
+    /// Keywords already present take the template value, and its
+    /// comment and unit if given, in place; other keywords and
+    /// commentary cards are added before END.  `-NAME` lines remove
+    /// every keyword of that name
+    ///
+    /// # Arguments
+    ///
+    /// * `text` - The template
+    ///
+    /// # Returns
+    ///
+    /// An error naming the first invalid line, in which case the
+    /// header is unchanged
+    ///
+    pub fn apply_template(&mut self, text: &str) -> Result<(), HeaderError> {
+        for card in parse(text)? {
+            match card {
+                TemplateCard::Delete(name) => {
+                    while let Some(pos) = self.position(&name) {
+                        self.remove(pos);
+                    }
+                }
+                TemplateCard::Set(kw) if matches!(kw.value, KeywordValue::CommentText(_)) => {
+                    self.insert_before_end(kw)
+                }
+                TemplateCard::Set(kw) => match self.position(&kw.name) {
+                    Some(pos) => {
+                        let old = &mut self.keywords[pos];
+                        old.value = kw.value;
+                        old.raw = None;
+                        if kw.comment.is_some() || kw.unit.is_some() {
+                            old.comment = kw.comment;
+                            old.unit = kw.unit;
+                        }
+                    }
+                    None => self.insert_before_end(kw),
+                },
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_template() {
+        let template = "\
+# Pipeline keywords
+telescop = 'NOT'   / telescope
+INSTRUME   ALFOSC
+EXPTIME    0.0     / [s] exposure time
+SIMPLE     T
+TTYPE#   = 'TIME'
+TTYPE#   = 'RATE'
+TFORM#     1D
+HISTORY    created from template
+-BLANK
+END
+IGNORED  = 1
+";
+        let header = Header::from_template(template).unwrap();
+        let names: Vec<&str> = header.iter().map(|kw| kw.name.as_str()).collect();
+        assert_eq!(
+            names,
+            [
+                "TELESCOP", "INSTRUME", "EXPTIME", "SIMPLE", "TTYPE1", "TTYPE2", "TFORM1",
+                "HISTORY"
+            ]
+        );
+        assert_eq!(header.get::<String>("INSTRUME").unwrap(), "ALFOSC");
+        assert_eq!(header.value("EXPTIME"), Some(&KeywordValue::Float(0.0)));
+        assert_eq!(header.find("EXPTIME").unwrap().unit.as_deref(), Some("s"));
+        assert!(header.get::<bool>("SIMPLE").unwrap());
+        assert_eq!(header.get::<String>("TTYPE2").unwrap(), "RATE");
+        assert_eq!(header.history(), vec!["created from template"]);
+
+        let mut header = Header::new(vec![
+            Keyword {
+                name: "EXPTIME".to_string(),
+                value: KeywordValue::Float(30.0),
+                comment: Some("exposure".to_string()),
+                ..Default::default()
+            },
+            Keyword {
+                name: "BLANK".to_string(),
+                value: KeywordValue::Int(-1),
+                ..Default::default()
+            },
+            Keyword {
+                name: "END".to_string(),
+                ..Default::default()
+            },
+        ]);
+        header
+            .apply_template("EXPTIME 60\nOBJECT 'M31'\n-BLANK")
+            .unwrap();
+        let names: Vec<&str> = header.iter().map(|kw| kw.name.as_str()).collect();
+        assert_eq!(names, ["EXPTIME", "OBJECT", "END"]);
+        assert_eq!(header.value("EXPTIME"), Some(&KeywordValue::Int(60)));
+        assert_eq!(
+            header.find("EXPTIME").unwrap().comment.as_deref(),
+            Some("exposure")
+        );
+
+        assert!(matches!(
+            Header::from_template("OK = 1\nTOOLONGNAME = 2"),
+            Err(HeaderError::InvalidTemplate(2, _))
+        ));
+        assert!(header.apply_template("BAD!NAME 1").is_err());
+    }
+}
@@ -0,0 +1,164 @@
+//! Date and time keywords
+//!
+//! FITS dates are ISO-8601 strings, "YYYY-MM-DD" optionally followed
+//! by "Thh:mm:ss[.sss...]", or in older files "DD/MM/YY" for years
+//! 1900-1999.  See Section 4.4.2.1 and 9.1.1 of FITS standard,
+//! version 4.  The time scale (TIMESYS) is not applied; values are
+//! returned as written, labelled as UTC
+
+use chrono::{DateTime, NaiveDate, NaiveDateTime, Utc};
+
+use super::Header;
+use crate::KeywordValue;
+
+/// Modified Julian Date of the Unix epoch, 1970-01-01T00:00:00
+const MJD_UNIX_EPOCH: f64 = 40587.0;
+
+/// Parse a FITS date/time string
+///
+/// # Arguments
+///
+/// * `s` - The date string, in ISO-8601 or DD/MM/YY form
+///
+/// # Returns
+///
+/// The date and time, or None if the string is not a valid FITS date
+///
+pub fn parse_datetime(s: &str) -> Option<DateTime<Utc>> {
+    let s = s.trim();
+    if let Ok(dt) = NaiveDateTime::parse_from_str(s, "%Y-%m-%dT%H:%M:%S%.f") {
+        return Some(dt.and_utc());
+    }
+    if let Ok(d) = NaiveDate::parse_from_str(s, "%Y-%m-%d") {
+        return d.and_hms_opt(0, 0, 0).map(|dt| dt.and_utc());
+    }
+    // Deprecated form, years 1900-1999 only
+    let parts: Vec<&str> = s.split('/').collect();
+    if parts.len() == 3 && parts.iter().all(|p| p.len() == 2) {
+        let day = parts[0].parse().ok()?;
+        let month = parts[1].parse().ok()?;
+        let year: i32 = parts[2].parse().ok()?;
+        return NaiveDate::from_ymd_opt(1900 + year, month, day)?
+            .and_hms_opt(0, 0, 0)
+            .map(|dt| dt.and_utc());
+    }
+    None
+}
+
+/// Format a date/time as a FITS ISO-8601 string
+///
+/// Fractional seconds are written only if non-zero
+///
+/// # Arguments
+///
+/// * `dt` - The date and time
+///
+/// # Returns
+///
+/// String of the form "YYYY-MM-DDThh:mm:ss[.sss]"
+///
+pub fn format_datetime(dt: &DateTime<Utc>) -> String {
+    dt.format("%Y-%m-%dT%H:%M:%S%.f").to_string()
+}
+
+/// Modified Julian Date of a date/time
+///
+/// # Arguments
+///
+/// * `dt` - The date and time
+///
+/// # Returns
+///
+/// MJD, in days since 1858-11-17T00:00:00
+///
+pub fn datetime_to_mjd(dt: &DateTime<Utc>) -> f64 {
+    let seconds = dt.timestamp() as f64 + dt.timestamp_subsec_nanos() as f64 * 1.0e-9;
+    seconds / 86400.0 + MJD_UNIX_EPOCH
+}
+
+impl Header {
+    /// Value of a date keyword, such as DATE or DATE-OBS, parsed as a date/time
+    ///
+    /// # Arguments
+    ///
+    /// * `key` - The name of the keyword
+    ///
+    /// # Returns
+    ///
+    /// The date and time, or None if the keyword is missing or not a valid date
+    ///
+    pub fn datetime(&self, key: &str) -> Option<DateTime<Utc>> {
+        match self.value(key) {
+            Some(KeywordValue::String(s)) => parse_datetime(s),
+            _ => None,
+        }
+    }
+
+    /// Set a date keyword, written in ISO-8601 form
+    ///
+    /// # Arguments
+    ///
+    /// * `key` - The name of the keyword
+    /// * `dt` - The date and time
+    ///
+    pub fn set_datetime(&mut self, key: &str, dt: &DateTime<Utc>) {
+        self.set(key, KeywordValue::String(format_datetime(dt)));
+    }
+
+    /// Start time of the observation as a Modified Julian Date
+    ///
+    /// # Returns
+    ///
+    /// MJD-OBS if present, otherwise computed from DATE-OBS
+    ///
+    pub fn mjd_obs(&self) -> Option<f64> {
+        self.get::<f64>("MJD-OBS")
+            .ok()
+            .or_else(|| self.datetime("DATE-OBS").map(|dt| datetime_to_mjd(&dt)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Keyword;
+    use chrono::{Datelike, Timelike};
+
+    #[test]
+    fn test_parse() {
+        let dt = parse_datetime("2000-01-01T12:00:00").unwrap();
+        assert_eq!(datetime_to_mjd(&dt), 51544.5);
+
+        let dt = parse_datetime("1995-12-18T03:04:05.25").unwrap();
+        assert_eq!((dt.hour(), dt.minute(), dt.second()), (3, 4, 5));
+        assert_eq!(dt.timestamp_subsec_millis(), 250);
+        assert_eq!(format_datetime(&dt), "1995-12-18T03:04:05.250");
+
+        let dt = parse_datetime("1995-12-18").unwrap();
+        assert_eq!(format_datetime(&dt), "1995-12-18T00:00:00");
+
+        let dt = parse_datetime("18/12/95").unwrap();
+        assert_eq!((dt.year(), dt.month(), dt.day()), (1995, 12, 18));
+
+        assert!(parse_datetime("yesterday").is_none());
+        assert!(parse_datetime("1995-13-01").is_none());
+    }
+
+    #[test]
+    fn test_header() {
+        let mut header = Header::new(vec![Keyword {
+            name: "END".to_string(),
+            ..Default::default()
+        }]);
+        assert!(header.mjd_obs().is_none());
+
+        let dt = parse_datetime("2000-01-01T12:00:00").unwrap();
+        header.set_datetime("DATE-OBS", &dt);
+        assert_eq!(header[0].name, "DATE-OBS");
+        assert_eq!(header.datetime("DATE-OBS"), Some(dt));
+        assert_eq!(header.mjd_obs(), Some(51544.5));
+
+        header.set("MJD-OBS", KeywordValue::Float(51544.25));
+        assert_eq!(header.mjd_obs(), Some(51544.25));
+    }
+}
@@ -0,0 +1,436 @@
+//! HEALPix all-sky maps stored in binary tables
+//!
+//! Maps such as those of Planck and WMAP are binary tables with
+//! PIXTYPE = 'HEALPIX', and NSIDE and ORDERING keywords giving the
+//! resolution and pixel numbering.  With implicit indexing (INDXSCHM =
+//! 'IMPLICIT', the default) the pixel values of a column are stored in
+//! pixel order, row after row, starting at pixel FIRSTPIX (default 0);
+//! with explicit indexing a PIXEL column numbers each value.
+//!
+//! See Gorski et al. (2005), ApJ 622, 759, for the pixel numbering schemes
+
+use crate::BinTable;
+use crate::BinTableValue;
+use crate::FITSError;
+use crate::HDUData;
+use crate::TableLike;
+use crate::HDU;
+
+/// Sentinel of unobserved pixels in HEALPix maps; read as NaN
+pub const UNSEEN: f64 = -1.6375e30;
+
+/// Pixel numbering scheme of a HEALPix map
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HealpixOrdering {
+    /// Pixels numbered along rings of constant latitude, north to south
+    Ring,
+    /// Pixels numbered hierarchically within each of the 12 base pixels
+    Nested,
+}
+
+impl std::str::FromStr for HealpixOrdering {
+    type Err = FITSError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_uppercase().as_str() {
+            "RING" => Ok(HealpixOrdering::Ring),
+            "NESTED" | "NEST" => Ok(HealpixOrdering::Nested),
+            _ => Err(FITSError::InvalidHealpix(format!("ORDERING = '{}'", s))),
+        }
+    }
+}
+
+/// Number of pixels of a map
+pub fn nside_to_npix(nside: usize) -> usize {
+    12 * nside * nside
+}
+
+/// Map resolution, from the number of pixels
+///
+/// # Returns
+///
+/// NSIDE, or an error if `npix` is not 12 times a power of 4
+///
+pub fn npix_to_nside(npix: usize) -> Result<usize, FITSError> {
+    let nside = ((npix / 12) as f64).sqrt().round() as usize;
+    match nside.is_power_of_two() && nside_to_npix(nside) == npix {
+        true => Ok(nside),
+        false => Err(FITSError::InvalidHealpix(format!(
+            "{} pixels is not a valid map size",
+            npix
+        ))),
+    }
+}
+
+/// Ring number of the northernmost corner and longitude index of each
+/// base pixel
+const JRLL: [i64; 12] = [2, 2, 2, 2, 3, 3, 3, 3, 4, 4, 4, 4];
+const JPLL: [i64; 12] = [1, 3, 5, 7, 0, 2, 4, 6, 1, 3, 5, 7];
+
+/// Interleave the bits of x and y, x in the even bits
+fn interleave(x: i64, y: i64) -> i64 {
+    (0..32).fold(0, |acc, bit| {
+        acc | ((x >> bit) & 1) << (2 * bit) | ((y >> bit) & 1) << (2 * bit + 1)
+    })
+}
+
+/// Even bits of a value, packed
+fn compress(v: i64) -> i64 {
+    (0..32).fold(0, |acc, bit| acc | ((v >> (2 * bit)) & 1) << bit)
+}
+
+/// Ring pixel number of position (x, y) in a base pixel
+fn xyf_to_ring(nside: i64, ix: i64, iy: i64, face: usize) -> i64 {
+    let nl4 = 4 * nside;
+    let ncap = 2 * nside * (nside - 1);
+    let npix = 12 * nside * nside;
+    let jr = JRLL[face] * nside - ix - iy - 1;
+    let (nr, n_before, kshift) = if jr < nside {
+        (jr, 2 * jr * (jr - 1), 0)
+    } else if jr > 3 * nside {
+        let nr = nl4 - jr;
+        (nr, npix - 2 * (nr + 1) * nr, 0)
+    } else {
+        (nside, ncap + (jr - nside) * nl4, (jr - nside) & 1)
+    };
+    let mut jp = (JPLL[face] * nr + ix - iy + 1 + kshift) / 2;
+    if jp > nl4 {
+        jp -= nl4;
+    } else if jp < 1 {
+        jp += nl4;
+    }
+    n_before + jp - 1
+}
+
+/// Position (x, y) in a base pixel, and the base pixel, of a ring pixel
+fn ring_to_xyf(nside: i64, pix: i64) -> (i64, i64, usize) {
+    let nl2 = 2 * nside;
+    let ncap = 2 * nside * (nside - 1);
+    let npix = 12 * nside * nside;
+    let isqrt = |v: i64| (v as f64).sqrt() as i64;
+    let (iring, iphi, kshift, nr, face) = if pix < ncap {
+        let iring = (1 + isqrt(1 + 2 * pix)) >> 1;
+        let iphi = pix + 1 - 2 * iring * (iring - 1);
+        (iring, iphi, 0, iring, ((iphi - 1) / iring) as usize)
+    } else if pix < npix - ncap {
+        let ip = pix - ncap;
+        let tmp = ip / (4 * nside);
+        let iring = tmp + nside;
+        let iphi = ip - tmp * 4 * nside + 1;
+        let ire = tmp + 1;
+        let irm = nl2 + 2 - ire;
+        let ifm = (iphi - ire / 2 + nside - 1) / nside;
+        let ifp = (iphi - irm / 2 + nside - 1) / nside;
+        let face = match ifp.cmp(&ifm) {
+            std::cmp::Ordering::Equal => ifp | 4,
+            std::cmp::Ordering::Less => ifp,
+            std::cmp::Ordering::Greater => ifm + 8,
+        };
+        (iring, iphi, (iring + nside) & 1, nside, face as usize)
+    } else {
+        let ip = npix - pix;
+        let iring = (1 + isqrt(2 * ip - 1)) >> 1;
+        let iphi = 4 * iring + 1 - (ip - 2 * iring * (iring - 1));
+        let face = ((iphi - 1) / iring + 8) as usize;
+        (2 * nl2 - iring, iphi, 0, iring, face)
+    };
+    let irt = iring - JRLL[face] * nside + 1;
+    let mut ipt = 2 * iphi - JPLL[face] * nr - kshift - 1;
+    if ipt >= nl2 {
+        ipt -= 8 * nside;
+    }
+    ((ipt - irt) >> 1, (-ipt - irt) >> 1, face)
+}
+
+/// Convert a RING pixel number to NESTED
+///
+/// # Arguments
+///
+/// * `nside` - Map resolution; a power of 2
+/// * `pix` - Pixel number in RING ordering
+///
+pub fn ring_to_nested(nside: usize, pix: usize) -> usize {
+    let (ix, iy, face) = ring_to_xyf(nside as i64, pix as i64);
+    face * nside * nside + interleave(ix, iy) as usize
+}
+
+/// Convert a NESTED pixel number to RING
+///
+/// # Arguments
+///
+/// * `nside` - Map resolution; a power of 2
+/// * `pix` - Pixel number in NESTED ordering
+///
+pub fn nested_to_ring(nside: usize, pix: usize) -> usize {
+    let npface = nside * nside;
+    let ipf = (pix % npface) as i64;
+    xyf_to_ring(
+        nside as i64,
+        compress(ipf),
+        compress(ipf >> 1),
+        pix / npface,
+    ) as usize
+}
+
+/// Reorder a full-sky map between pixel numbering schemes
+///
+/// # Arguments
+///
+/// * `map` - Pixel values, one per pixel
+/// * `from` - Ordering of `map`
+/// * `to` - Ordering of the result
+///
+/// # Returns
+///
+/// The reordered values, or an error if `map` is not a full-sky map
+///
+pub fn reorder<T: Copy>(
+    map: &[T],
+    from: HealpixOrdering,
+    to: HealpixOrdering,
+) -> Result<Vec<T>, FITSError> {
+    let nside = npix_to_nside(map.len())?;
+    if from == to {
+        return Ok(map.to_vec());
+    }
+    let mut out = map.to_vec();
+    for (pix, value) in map.iter().enumerate() {
+        let dest = match from {
+            HealpixOrdering::Ring => ring_to_nested(nside, pix),
+            HealpixOrdering::Nested => nested_to_ring(nside, pix),
+        };
+        out[dest] = *value;
+    }
+    Ok(out)
+}
+
+/// A HEALPix map held in a binary table
+///
+/// # Example
+///
+/// ```no_run
+/// # use fits::{HealpixMap, HealpixOrdering, FITS};
+/// # fn main() -> Result<(), fits::FITSError> {
+/// # let fits = FITS::from_file("map.fits")?;
+/// let map = HealpixMap::from_hdu(fits.at(1)?)?;
+/// let temperature = map.column_ordered("I_STOKES", HealpixOrdering::Nested)?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Debug)]
+pub struct HealpixMap<'a> {
+    table: &'a BinTable,
+    /// Map resolution, from NSIDE
+    pub nside: usize,
+    /// Pixel numbering, from ORDERING
+    pub ordering: HealpixOrdering,
+    /// Coordinate system, from COORDSYS (e.g. "G" for galactic)
+    pub coordsys: Option<String>,
+    /// True if pixels are numbered by a PIXEL column (INDXSCHM = 'EXPLICIT')
+    pub explicit: bool,
+    /// First pixel of an implicitly indexed map, from FIRSTPIX
+    pub first_pixel: usize,
+}
+
+impl<'a> HealpixMap<'a> {
+    /// Validate the HEALPix keywords of a binary table HDU
+    ///
+    /// # Arguments
+    ///
+    /// * `hdu` - Binary table with PIXTYPE = 'HEALPIX'
+    ///
+    /// # Returns
+    ///
+    /// The map, or an error if the HDU is not a binary table or the
+    /// PIXTYPE, NSIDE, ORDERING, or INDXSCHM keywords are missing or
+    /// invalid
+    ///
+    pub fn from_hdu(hdu: &'a HDU) -> Result<Self, FITSError> {
+        let HDUData::BinTable(table) = &hdu.data else {
+            return Err(FITSError::InvalidHealpix("not a binary table".to_string()));
+        };
+        let header = &hdu.header;
+        let invalid = |what: &str| FITSError::InvalidHealpix(what.to_string());
+        match header.get::<String>("PIXTYPE") {
+            Ok(p) if p.trim().eq_ignore_ascii_case("HEALPIX") => {}
+            _ => return Err(invalid("PIXTYPE is not 'HEALPIX'")),
+        }
+        let ordering = header
+            .get::<String>("ORDERING")
+            .map_err(|_| invalid("missing ORDERING"))?
+            .parse::<HealpixOrdering>()?;
+        let nside = match header.get::<i64>("NSIDE") {
+            Ok(n) if n > 0 && (n as u64).is_power_of_two() => n as usize,
+            Ok(n) => return Err(FITSError::InvalidHealpix(format!("NSIDE = {}", n))),
+            Err(_) => return Err(invalid("missing NSIDE")),
+        };
+        let explicit = match header.get::<String>("INDXSCHM") {
+            Ok(s) if s.trim().eq_ignore_ascii_case("EXPLICIT") => true,
+            Ok(s) if s.trim().eq_ignore_ascii_case("IMPLICIT") => false,
+            Ok(s) => return Err(FITSError::InvalidHealpix(format!("INDXSCHM = '{}'", s))),
+            Err(_) => false,
+        };
+        if explicit && table.column_index("PIXEL").is_err() {
+            return Err(invalid("explicit indexing without a PIXEL column"));
+        }
+        Ok(HealpixMap {
+            table,
+            nside,
+            ordering,
+            coordsys: header.get::<String>("COORDSYS").ok(),
+            explicit,
+            first_pixel: header.get::<usize>("FIRSTPIX").unwrap_or(0),
+        })
+    }
+
+    /// Number of pixels of the full-sky map
+    pub fn npix(&self) -> usize {
+        nside_to_npix(self.nside)
+    }
+
+    /// Values of a map column, in the map's ordering
+    ///
+    /// Pixels not stored in the table, nulls, and UNSEEN values are NaN
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - Column name, e.g. "I_STOKES" or "TEMPERATURE"
+    ///
+    /// # Returns
+    ///
+    /// One value per pixel of the full sky, or an error if the column
+    /// does not exist or numbers pixels outside the map
+    ///
+    pub fn column(&self, name: &str) -> Result<Vec<f64>, FITSError> {
+        let col = self.table.column_index(name)?;
+        let npix = self.npix();
+        let mut map = vec![f64::NAN; npix];
+        let out_of_range = |pix: usize| {
+            FITSError::InvalidHealpix(format!("pixel {} outside map of {} pixels", pix, npix))
+        };
+        if self.explicit {
+            let pixcol = self.table.column_index("PIXEL")?;
+            for row in 0..self.table.nrows() {
+                let pixels = flatten(self.table.at(row, pixcol)?);
+                let values = flatten(self.table.at_physical(row, col)?);
+                for (pix, value) in pixels.into_iter().zip(values) {
+                    if !(0.0..npix as f64).contains(&pix) {
+                        return Err(out_of_range(pix as usize));
+                    }
+                    map[pix as usize] = value;
+                }
+            }
+        } else {
+            let mut pix = self.first_pixel;
+            for row in 0..self.table.nrows() {
+                for value in flatten(self.table.at_physical(row, col)?) {
+                    *map.get_mut(pix).ok_or_else(|| out_of_range(pix))? = value;
+                    pix += 1;
+                }
+            }
+        }
+        Ok(map)
+    }
+
+    /// Values of a map column, in a given ordering; see `column`
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - Column name
+    /// * `ordering` - Pixel numbering of the result
+    ///
+    pub fn column_ordered(
+        &self,
+        name: &str,
+        ordering: HealpixOrdering,
+    ) -> Result<Vec<f64>, FITSError> {
+        reorder(&self.column(name)?, self.ordering, ordering)
+    }
+}
+
+/// Cell values as floats; nulls and UNSEEN become NaN
+fn flatten(value: BinTableValue) -> Vec<f64> {
+    let scalar = |v: &BinTableValue| match v.as_f64() {
+        Some(x) if x == UNSEEN || x as f32 == UNSEEN as f32 => f64::NAN,
+        Some(x) => x,
+        None => f64::NAN,
+    };
+    match value {
+        BinTableValue::Array(values) => values.iter().map(scalar).collect(),
+        v => vec![scalar(&v)],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testutil::header;
+
+    #[test]
+    fn test_pixel_numbering() {
+        let ring: Vec<usize> = (0..4).map(|p| nested_to_ring(2, p)).collect();
+        assert_eq!(ring, vec![13, 5, 4, 0]);
+        for nside in [1, 2, 4, 16] {
+            for pix in 0..nside_to_npix(nside) {
+                assert_eq!(ring_to_nested(nside, nested_to_ring(nside, pix)), pix);
+            }
+        }
+        let map: Vec<usize> = (0..48).collect();
+        let nested = reorder(&map, HealpixOrdering::Ring, HealpixOrdering::Nested).unwrap();
+        assert_eq!(nested[0], 13);
+        assert_eq!(
+            reorder(&nested, HealpixOrdering::Nested, HealpixOrdering::Ring).unwrap(),
+            map
+        );
+        assert!(reorder(&map[..47], HealpixOrdering::Ring, HealpixOrdering::Nested).is_err());
+    }
+
+    #[test]
+    fn test_map() {
+        let header = header(&[
+            "XTENSION= 'BINTABLE'",
+            "BITPIX  =                    8",
+            "NAXIS   =                    2",
+            "NAXIS1  =                   16",
+            "NAXIS2  =                    3",
+            "PCOUNT  =                    0",
+            "GCOUNT  =                    1",
+            "TFIELDS =                    1",
+            "TTYPE1  = 'TEMPERATURE'",
+            "TFORM1  = '4E      '",
+            "PIXTYPE = 'HEALPIX '",
+            "ORDERING= 'RING    '",
+            "NSIDE   =                    1",
+            "END",
+        ]);
+        let mut raw = Vec::new();
+        for pix in 0..12 {
+            let value = if pix == 5 { UNSEEN as f32 } else { pix as f32 };
+            raw.extend(value.to_be_bytes());
+        }
+        let (data, _) = BinTable::from_bytes(&header, &raw).unwrap();
+        let mut hdu = HDU {
+            header,
+            data,
+            location: None,
+        };
+
+        let map = HealpixMap::from_hdu(&hdu).unwrap();
+        assert_eq!(map.npix(), 12);
+        let values = map.column("TEMPERATURE").unwrap();
+        assert_eq!(values[11], 11.0);
+        assert!(values[5].is_nan());
+        // For NSIDE 1 both orderings number the base pixels
+        let nested = map
+            .column_ordered("TEMPERATURE", HealpixOrdering::Nested)
+            .unwrap();
+        assert_eq!(nested[..5], values[..5]);
+
+        hdu.header.set("NSIDE", crate::KeywordValue::Int(3));
+        assert!(HealpixMap::from_hdu(&hdu).is_err());
+        hdu.header.set("NSIDE", crate::KeywordValue::Int(1));
+        hdu.header
+            .set("ORDERING", crate::KeywordValue::String("SPIRAL".into()));
+        assert!(HealpixMap::from_hdu(&hdu).is_err());
+    }
+}
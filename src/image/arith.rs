@@ -0,0 +1,138 @@
+use super::Image;
+use super::Mask;
+use crate::Bitpix;
+use crate::FITSError;
+use crate::ImageError;
+
+impl Image {
+    /// Image with the same shape and coordinates, holding given
+    /// physical values as 64-bit floats
+    ///
+    /// # Arguments
+    ///
+    /// * `pixels` - One value per pixel, first axis fastest
+    /// * `bunit` - Unit of the new values
+    ///
+    pub(crate) fn with_f64_pixels(&self, pixels: &[f64], bunit: Option<String>) -> Image {
+        Image {
+            pixeltype: Bitpix::Float64,
+            axes: self.axes.clone(),
+            rawbytes: bytemuck::cast_slice(pixels).to_vec(),
+            bscale: 1.0,
+            bzero: 0.0,
+            blank: None,
+            bunit,
+            wcs: self.wcs.clone(),
+            alt_wcs: self.alt_wcs.clone(),
+            native: true,
+        }
+    }
+
+    /// Combine the physical values of two images of the same shape;
+    /// pixels bad in the optional mask are NaN
+    fn zip_with(
+        &self,
+        other: &Image,
+        mask: Option<&Mask>,
+        bunit: Option<String>,
+        f: impl Fn(f64, f64) -> f64,
+    ) -> Result<Image, FITSError> {
+        if self.axes != other.axes {
+            return Err(FITSError::from(ImageError::ShapeMismatch(
+                self.axes.clone(),
+                other.axes.clone(),
+            )));
+        }
+        self.check_mask(mask)?;
+        let pixels: Vec<f64> = (0..self.npixels())
+            .map(|i| match mask {
+                Some(mask) if mask.is_bad_index(i) => f64::NAN,
+                _ => f(self.physical_value(i), other.physical_value(i)),
+            })
+            .collect();
+        Ok(self.with_f64_pixels(&pixels, bunit))
+    }
+
+    /// Apply a function to the physical value of every pixel
+    fn map(&self, bunit: Option<String>, f: impl Fn(f64) -> f64) -> Image {
+        let pixels: Vec<f64> = (0..self.npixels())
+            .map(|i| f(self.physical_value(i)))
+            .collect();
+        self.with_f64_pixels(&pixels, bunit)
+    }
+
+    /// Pixel-by-pixel sum of two images
+    ///
+    /// This and the other arithmetic operations work on physical
+    /// values (see `physical_pixels`), so images of any BITPIX, BSCALE,
+    /// and BZERO may be combined.  The result is a 64-bit float image
+    /// with the WCS of `self`; undefined pixels of either input are NaN
+    ///
+    /// # Arguments
+    ///
+    /// * `other` - Image of the same shape
+    ///
+    /// # Returns
+    ///
+    /// The sum, or an error if the shapes differ
+    ///
+    pub fn add(&self, other: &Image) -> Result<Image, FITSError> {
+        self.zip_with(other, None, self.bunit.clone(), |a, b| a + b)
+    }
+
+    /// Pixel-by-pixel difference of two images, e.g. to subtract a dark
+    /// frame; see `add`
+    pub fn subtract(&self, other: &Image) -> Result<Image, FITSError> {
+        self.zip_with(other, None, self.bunit.clone(), |a, b| a - b)
+    }
+
+    /// Pixel-by-pixel product of two images; see `add`.  The result
+    /// has no BUNIT
+    pub fn multiply(&self, other: &Image) -> Result<Image, FITSError> {
+        self.zip_with(other, None, None, |a, b| a * b)
+    }
+
+    /// Pixel-by-pixel quotient of two images, e.g. to divide by a flat
+    /// field; see `add`.  Division by zero gives infinity or NaN, and
+    /// the result has no BUNIT
+    pub fn divide(&self, other: &Image) -> Result<Image, FITSError> {
+        self.zip_with(other, None, None, |a, b| a / b)
+    }
+
+    /// Image with a constant added to every pixel; see `add`
+    pub fn add_scalar(&self, value: f64) -> Image {
+        self.map(self.bunit.clone(), |a| a + value)
+    }
+
+    /// Image with every pixel multiplied by a constant; see `add`
+    pub fn scale(&self, factor: f64) -> Image {
+        self.map(self.bunit.clone(), |a| a * factor)
+    }
+
+    /// Pixel-by-pixel sum of two images, with pixels bad in a mask
+    /// made undefined (NaN) in the result; see `add`
+    ///
+    /// This and the other masked operations fail if the mask's shape
+    /// differs from the images'
+    pub fn add_masked(&self, other: &Image, mask: Option<&Mask>) -> Result<Image, FITSError> {
+        self.zip_with(other, mask, self.bunit.clone(), |a, b| a + b)
+    }
+
+    /// Pixel-by-pixel difference of two images, excluding masked
+    /// pixels; see `add_masked`
+    pub fn subtract_masked(&self, other: &Image, mask: Option<&Mask>) -> Result<Image, FITSError> {
+        self.zip_with(other, mask, self.bunit.clone(), |a, b| a - b)
+    }
+
+    /// Pixel-by-pixel product of two images, excluding masked pixels;
+    /// see `add_masked` and `multiply`
+    pub fn multiply_masked(&self, other: &Image, mask: Option<&Mask>) -> Result<Image, FITSError> {
+        self.zip_with(other, mask, None, |a, b| a * b)
+    }
+
+    /// Pixel-by-pixel quotient of two images, excluding masked pixels;
+    /// see `add_masked` and `divide`
+    pub fn divide_masked(&self, other: &Image, mask: Option<&Mask>) -> Result<Image, FITSError> {
+        self.zip_with(other, mask, None, |a, b| a / b)
+    }
+}
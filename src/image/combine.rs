@@ -0,0 +1,256 @@
+use super::Image;
+use crate::FITSError;
+use crate::ImageError;
+
+/// Pixels combined per unit of work; chunks are spread over threads
+/// with the `rayon` feature
+const CHUNK: usize = 4096;
+
+/// How `Image::combine` merges the values of a pixel across images
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum CombineMethod {
+    /// Mean of the valid values
+    Mean,
+    /// Median of the valid values
+    Median,
+    /// Iteratively reject values more than `low` standard deviations
+    /// below or `high` above the mean, then average the rest
+    MeanSigmaClip {
+        low: f64,
+        high: f64,
+        /// Maximum number of rejection passes
+        iterations: usize,
+    },
+    /// As `MeanSigmaClip`, but rejecting about the median, which is
+    /// less affected by the outliers being rejected
+    MedianSigmaClip {
+        low: f64,
+        high: f64,
+        /// Maximum number of rejection passes
+        iterations: usize,
+    },
+}
+
+fn mean(values: &[f64]) -> f64 {
+    values.iter().sum::<f64>() / values.len() as f64
+}
+
+fn median(values: &mut [f64]) -> f64 {
+    values.sort_by(f64::total_cmp);
+    let n = values.len();
+    match n % 2 {
+        0 => 0.5 * (values[n / 2 - 1] + values[n / 2]),
+        _ => values[n / 2],
+    }
+}
+
+/// Combine the values of one pixel
+///
+/// # Arguments
+///
+/// * `values` - Value of the pixel in each image; NaN if undefined
+/// * `rejected` - Set true for each value that is undefined or rejected
+/// * `scratch` - Work space, reused between pixels
+///
+fn combine_pixel(
+    values: &[f64],
+    method: &CombineMethod,
+    rejected: &mut [bool],
+    scratch: &mut Vec<f64>,
+) -> f64 {
+    for (r, v) in rejected.iter_mut().zip(values) {
+        *r = v.is_nan();
+    }
+    let kept = |rejected: &[bool], scratch: &mut Vec<f64>| {
+        scratch.clear();
+        scratch.extend(
+            values
+                .iter()
+                .zip(rejected)
+                .filter(|(_, r)| !**r)
+                .map(|(v, _)| *v),
+        );
+    };
+    kept(rejected, scratch);
+    if scratch.is_empty() {
+        return f64::NAN;
+    }
+    let (low, high, iterations, use_median) = match *method {
+        CombineMethod::Mean => return mean(scratch),
+        CombineMethod::Median => return median(scratch),
+        CombineMethod::MeanSigmaClip {
+            low,
+            high,
+            iterations,
+        } => (low, high, iterations, false),
+        CombineMethod::MedianSigmaClip {
+            low,
+            high,
+            iterations,
+        } => (low, high, iterations, true),
+    };
+    for _ in 0..iterations {
+        let m = mean(scratch);
+        let sigma =
+            (scratch.iter().map(|v| (v - m) * (v - m)).sum::<f64>() / scratch.len() as f64).sqrt();
+        let center = match use_median {
+            true => median(scratch),
+            false => m,
+        };
+        let mut changed = false;
+        for (r, v) in rejected.iter_mut().zip(values) {
+            if !*r && (*v < center - low * sigma || *v > center + high * sigma) {
+                *r = true;
+                changed = true;
+            }
+        }
+        if !changed {
+            break;
+        }
+        kept(rejected, scratch);
+        if scratch.is_empty() {
+            return f64::NAN;
+        }
+    }
+    mean(scratch)
+}
+
+/// Combine a run of pixels starting at linear index `start`
+///
+/// `mask`, if given, receives the rejection flags pixel by pixel, one
+/// per image
+fn combine_chunk(
+    images: &[&Image],
+    method: &CombineMethod,
+    start: usize,
+    out: &mut [f64],
+    mut mask: Option<&mut [bool]>,
+) {
+    let n = images.len();
+    let mut values = vec![0.0; n];
+    let mut rejected = vec![false; n];
+    let mut scratch = Vec::with_capacity(n);
+    for (j, o) in out.iter_mut().enumerate() {
+        for (v, image) in values.iter_mut().zip(images) {
+            *v = image.physical_value(start + j);
+        }
+        *o = combine_pixel(&values, method, &mut rejected, &mut scratch);
+        if let Some(mask) = mask.as_deref_mut() {
+            mask[j * n..(j + 1) * n].copy_from_slice(&rejected);
+        }
+    }
+}
+
+impl Image {
+    /// Combine images pixel by pixel, e.g. to build a master bias,
+    /// dark, or flat frame
+    ///
+    /// Physical values (see `physical_pixels`) are combined, and
+    /// undefined pixels are ignored; pixels undefined in every image
+    /// are NaN.  With the `rayon` feature, the work is spread over the
+    /// rayon thread pool
+    ///
+    /// # Arguments
+    ///
+    /// * `images` - Images of the same shape
+    /// * `method` - How the values of each pixel are combined
+    ///
+    /// # Returns
+    ///
+    /// A 64-bit float image with the WCS and BUNIT of the first image,
+    /// or an error if there are no images or their shapes differ
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use fits::{CombineMethod, HDUData, Image, FITS};
+    /// # fn main() -> Result<(), fits::FITSError> {
+    /// # let image = |file: &str| -> Result<Image, fits::FITSError> {
+    /// #     match FITS::from_file(file)?.at(0)?.data.clone() {
+    /// #         HDUData::Image(image) => Ok(*image),
+    /// #         _ => panic!("expected an image"),
+    /// #     }
+    /// # };
+    /// # let (flat1, flat2, flat3) = (image("flat1.fits")?, image("flat2.fits")?, image("flat3.fits")?);
+    /// let method = CombineMethod::MedianSigmaClip { low: 3.0, high: 3.0, iterations: 5 };
+    /// let master = Image::combine(&[&flat1, &flat2, &flat3], &method)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    pub fn combine(images: &[&Image], method: &CombineMethod) -> Result<Image, FITSError> {
+        Ok(Self::combine_impl(images, method, false)?.0)
+    }
+
+    /// Combine images as `combine` does, also returning which values
+    /// were rejected
+    ///
+    /// # Returns
+    ///
+    /// The combined image, and for each input image a mask with one
+    /// entry per pixel, true where the pixel was undefined or rejected
+    ///
+    pub fn combine_with_rejection(
+        images: &[&Image],
+        method: &CombineMethod,
+    ) -> Result<(Image, Vec<Vec<bool>>), FITSError> {
+        let (image, mask) = Self::combine_impl(images, method, true)?;
+        let n = images.len();
+        let masks = (0..n)
+            .map(|k| mask.iter().skip(k).step_by(n).copied().collect())
+            .collect();
+        Ok((image, masks))
+    }
+
+    /// Combine images, optionally keeping rejection flags pixel by
+    /// pixel, one per image
+    fn combine_impl(
+        images: &[&Image],
+        method: &CombineMethod,
+        keep_mask: bool,
+    ) -> Result<(Image, Vec<bool>), FITSError> {
+        let Some(first) = images.first() else {
+            return Err(FITSError::from(ImageError::NoImages));
+        };
+        if let Some(other) = images.iter().find(|im| im.axes != first.axes) {
+            return Err(FITSError::from(ImageError::ShapeMismatch(
+                first.axes.clone(),
+                other.axes.clone(),
+            )));
+        }
+        let n = images.len();
+        let npixels = first.npixels();
+        let mut out = vec![0.0; npixels];
+        let mut mask = vec![false; if keep_mask { npixels * n } else { 0 }];
+
+        #[cfg(feature = "rayon")]
+        {
+            use rayon::prelude::*;
+            match keep_mask {
+                true => out
+                    .par_chunks_mut(CHUNK)
+                    .zip(mask.par_chunks_mut(CHUNK * n))
+                    .enumerate()
+                    .for_each(|(c, (o, m))| combine_chunk(images, method, c * CHUNK, o, Some(m))),
+                false => out
+                    .par_chunks_mut(CHUNK)
+                    .enumerate()
+                    .for_each(|(c, o)| combine_chunk(images, method, c * CHUNK, o, None)),
+            }
+        }
+        #[cfg(not(feature = "rayon"))]
+        match keep_mask {
+            true => out
+                .chunks_mut(CHUNK)
+                .zip(mask.chunks_mut(CHUNK * n))
+                .enumerate()
+                .for_each(|(c, (o, m))| combine_chunk(images, method, c * CHUNK, o, Some(m))),
+            false => out
+                .chunks_mut(CHUNK)
+                .enumerate()
+                .for_each(|(c, o)| combine_chunk(images, method, c * CHUNK, o, None)),
+        }
+
+        Ok((first.with_f64_pixels(&out, first.bunit.clone()), mask))
+    }
+}
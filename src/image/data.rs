@@ -0,0 +1,127 @@
+use super::Image;
+use crate::Bitpix;
+use crate::FITSError;
+use crate::ImageError;
+use crate::Pixel;
+
+/// Pixels of an image, as a slice of the Rust type of its pixel type
+///
+/// Returned by `Image::data`, so that images of any pixel type can be
+/// handled by matching rather than by choosing the type for `pixels`
+///
+/// # Example
+///
+/// ```no_run
+/// # use fits::{HDUData, ImageData, FITS};
+/// # fn main() -> Result<(), fits::FITSError> {
+/// # let fits = FITS::from_file("image.fits")?;
+/// # let HDUData::Image(image) = &fits[0].data else { return Ok(()) };
+/// match image.data()? {
+///     ImageData::I16(pixels) => println!("{} 16-bit pixels", pixels.len()),
+///     ImageData::F32(pixels) => println!("{} float pixels", pixels.len()),
+///     _ => {}
+/// }
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ImageData<'a> {
+    I8(&'a [u8]),
+    I16(&'a [i16]),
+    I32(&'a [i32]),
+    I64(&'a [i64]),
+    U16(&'a [u16]),
+    U32(&'a [u32]),
+    U64(&'a [u64]),
+    F32(&'a [f32]),
+    F64(&'a [f64]),
+}
+
+impl Image {
+    /// Pixels as a slice, checking the type against the pixel type
+    ///
+    /// # Returns
+    ///
+    /// The pixels, first axis fastest, or an error if `T` is not the
+    /// Rust type of the pixel type (see `at`) or the pixels are not in
+    /// native byte order (see `convert_to_native`)
+    ///
+    pub fn try_pixels<T>(&self) -> Result<&[T], FITSError>
+    where
+        T: Pixel,
+    {
+        if T::BITPIX != self.pixeltype {
+            return Err(FITSError::from(ImageError::TypeMismatch(
+                self.pixeltype,
+                T::BITPIX,
+            )));
+        }
+        self.pixels()
+    }
+
+    /// Pixels of an 8-bit image; see `try_pixels`
+    pub fn as_u8(&self) -> Result<&[u8], FITSError> {
+        self.try_pixels()
+    }
+
+    /// Pixels of a 16-bit signed integer image; see `try_pixels`
+    pub fn as_i16(&self) -> Result<&[i16], FITSError> {
+        self.try_pixels()
+    }
+
+    /// Pixels of a 32-bit signed integer image; see `try_pixels`
+    pub fn as_i32(&self) -> Result<&[i32], FITSError> {
+        self.try_pixels()
+    }
+
+    /// Pixels of a 64-bit signed integer image; see `try_pixels`
+    pub fn as_i64(&self) -> Result<&[i64], FITSError> {
+        self.try_pixels()
+    }
+
+    /// Pixels of a 16-bit unsigned integer image; see `try_pixels`
+    pub fn as_u16(&self) -> Result<&[u16], FITSError> {
+        self.try_pixels()
+    }
+
+    /// Pixels of a 32-bit unsigned integer image; see `try_pixels`
+    pub fn as_u32(&self) -> Result<&[u32], FITSError> {
+        self.try_pixels()
+    }
+
+    /// Pixels of a 64-bit unsigned integer image; see `try_pixels`
+    pub fn as_u64(&self) -> Result<&[u64], FITSError> {
+        self.try_pixels()
+    }
+
+    /// Pixels of a 32-bit float image; see `try_pixels`
+    pub fn as_f32(&self) -> Result<&[f32], FITSError> {
+        self.try_pixels()
+    }
+
+    /// Pixels of a 64-bit float image; see `try_pixels`
+    pub fn as_f64(&self) -> Result<&[f64], FITSError> {
+        self.try_pixels()
+    }
+
+    /// Pixels as a slice of the Rust type of the pixel type
+    ///
+    /// # Returns
+    ///
+    /// The pixels, or an error if they are not in native byte order
+    /// (see `convert_to_native`)
+    ///
+    pub fn data(&self) -> Result<ImageData<'_>, FITSError> {
+        Ok(match self.pixeltype {
+            Bitpix::Int8 => ImageData::I8(self.try_pixels()?),
+            Bitpix::Int16 => ImageData::I16(self.try_pixels()?),
+            Bitpix::Int32 => ImageData::I32(self.try_pixels()?),
+            Bitpix::Int64 => ImageData::I64(self.try_pixels()?),
+            Bitpix::Uint16 => ImageData::U16(self.try_pixels()?),
+            Bitpix::Uint32 => ImageData::U32(self.try_pixels()?),
+            Bitpix::Uint64 => ImageData::U64(self.try_pixels()?),
+            Bitpix::Float32 => ImageData::F32(self.try_pixels()?),
+            Bitpix::Float64 => ImageData::F64(self.try_pixels()?),
+        })
+    }
+}
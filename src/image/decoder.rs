@@ -0,0 +1,240 @@
+//! Decoders for image data units the standard does not describe
+//!
+//! Some instruments write pixels that need custom interpretation, e.g.
+//! half-precision floats with BITPIX = -16, or 16-bit integers with a
+//! non-linear scaling given by private keywords.  Reading such a file
+//! normally fails (e.g. with `FITSError::InvalidBitpix`).  A
+//! `PixelDecoder` registered with `Image::register_decoder` is offered
+//! every image header before the standard decoding, and turns the data
+//! unit into pixels of a standard type
+
+use super::Image;
+use crate::Bitpix;
+use crate::FITSError;
+use crate::HDUData;
+use crate::Header;
+use crate::HeaderError;
+use crate::ImageError;
+use crate::WCS;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, RwLock};
+
+/// Decoder for an unconventional image data unit
+///
+/// # Example
+///
+/// ```no_run
+/// # use fits::{Float16Decoder, Image, FITS};
+/// # fn main() -> Result<(), fits::FITSError> {
+/// Image::register_decoder(Float16Decoder);
+/// let fits = FITS::from_file("half.fits")?;
+/// # Ok(())
+/// # }
+/// ```
+pub trait PixelDecoder: Send + Sync {
+    /// Whether this decoder reads the data unit the header describes,
+    /// e.g. BITPIX = -16
+    fn accepts(&self, header: &Header) -> bool;
+
+    /// Bytes in the data unit, without fill; also used to skip the
+    /// data unit when only headers are read
+    ///
+    /// The default is the size given by BITPIX and NAXISn (see
+    /// `Header::data_size`), with |BITPIX| / 8 bytes per pixel
+    fn data_size(&self, header: &Header) -> Option<usize> {
+        header.data_size()
+    }
+
+    /// Decode the data unit
+    ///
+    /// # Arguments
+    ///
+    /// * `header` - Header of the HDU
+    /// * `rawbytes` - The data unit, `data_size` bytes
+    ///
+    /// # Returns
+    ///
+    /// Type of the decoded pixels and their values in native byte
+    /// order, in the order of the data unit.  Values are physical:
+    /// any scaling has been applied, so BSCALE and BZERO are not
+    /// applied again to the decoded image
+    ///
+    fn decode(&self, header: &Header, rawbytes: &[u8]) -> Result<(Bitpix, Vec<u8>), FITSError>;
+}
+
+/// Identifies a registered decoder, for `Image::unregister_decoder`
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct DecoderId(u64);
+
+/// Registered decoders, in order of registration
+static DECODERS: RwLock<Vec<(DecoderId, Arc<dyn PixelDecoder>)>> = RwLock::new(Vec::new());
+
+/// Identifier of the next decoder registered
+static NEXT_ID: AtomicU64 = AtomicU64::new(0);
+
+/// The most recently registered decoder accepting a header
+pub(crate) fn find(header: &Header) -> Option<Arc<dyn PixelDecoder>> {
+    let decoders = DECODERS.read().unwrap_or_else(|e| e.into_inner());
+    decoders
+        .iter()
+        .rev()
+        .map(|(_, d)| d)
+        .find(|d| d.accepts(header))
+        .cloned()
+}
+
+/// Decoder for half-precision (IEEE 754 binary16) pixels, written with
+/// BITPIX = -16 by some instruments.  Pixels become 32-bit floats,
+/// with BSCALE and BZERO applied
+///
+/// Not registered by default; see `Image::register_decoder`
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Float16Decoder;
+
+/// Convert a half-precision float to single precision
+fn f16_to_f32(bits: u16) -> f32 {
+    let sign = ((bits >> 15) as u32) << 31;
+    let exponent = ((bits >> 10) & 0x1f) as u32;
+    let mantissa = (bits & 0x3ff) as u32;
+    let magnitude = match exponent {
+        // Zero and subnormals: mantissa * 2^-24
+        0 => {
+            let value = mantissa as f32 * 2f32.powi(-24);
+            return match sign {
+                0 => value,
+                _ => -value,
+            };
+        }
+        // Infinity and NaN
+        0x1f => 0x7f80_0000 | (mantissa << 13),
+        _ => ((exponent + 112) << 23) | (mantissa << 13),
+    };
+    f32::from_bits(sign | magnitude)
+}
+
+impl PixelDecoder for Float16Decoder {
+    fn accepts(&self, header: &Header) -> bool {
+        header.get::<i64>("BITPIX").ok() == Some(-16)
+    }
+
+    fn decode(&self, header: &Header, rawbytes: &[u8]) -> Result<(Bitpix, Vec<u8>), FITSError> {
+        let bscale = header.get::<f64>("BSCALE").unwrap_or(1.0) as f32;
+        let bzero = header.get::<f64>("BZERO").unwrap_or(0.0) as f32;
+        let pixels: Vec<f32> = rawbytes
+            .chunks_exact(2)
+            .map(|b| bzero + bscale * f16_to_f32(u16::from_be_bytes([b[0], b[1]])))
+            .collect();
+        Ok((Bitpix::Float32, bytemuck::cast_slice(&pixels).to_vec()))
+    }
+}
+
+impl Image {
+    /// Register a decoder for image data units the standard does not
+    /// describe
+    ///
+    /// The decoder applies to every file read afterwards, in all
+    /// threads.  Decoders are tried in reverse order of registration,
+    /// before the standard decoding, so a decoder may also take over
+    /// standard BITPIX values, e.g. for custom scaling
+    ///
+    /// # Arguments
+    ///
+    /// * `decoder` - The decoder
+    ///
+    /// # Returns
+    ///
+    /// Identifier of the registration, to remove the decoder again
+    /// with `unregister_decoder`
+    ///
+    pub fn register_decoder(decoder: impl PixelDecoder + 'static) -> DecoderId {
+        let id = DecoderId(NEXT_ID.fetch_add(1, Ordering::Relaxed));
+        DECODERS
+            .write()
+            .unwrap_or_else(|e| e.into_inner())
+            .push((id, Arc::new(decoder)));
+        id
+    }
+
+    /// Remove a decoder added with `register_decoder`
+    ///
+    /// Files read afterwards no longer use the decoder; images already
+    /// decoded with it are unchanged
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - Identifier returned by `register_decoder`
+    ///
+    /// # Returns
+    ///
+    /// True if the decoder was registered, false if it had already
+    /// been removed
+    ///
+    pub fn unregister_decoder(id: DecoderId) -> bool {
+        let mut decoders = DECODERS.write().unwrap_or_else(|e| e.into_inner());
+        let count = decoders.len();
+        decoders.retain(|(registered, _)| *registered != id);
+        decoders.len() != count
+    }
+
+    /// Construct an image with a registered decoder
+    ///
+    /// # Returns
+    ///
+    /// The image and the number of bytes of the data unit
+    ///
+    pub(super) fn decode_with(
+        decoder: &dyn PixelDecoder,
+        header: &Header,
+        rawbytes: &[u8],
+    ) -> Result<(HDUData, usize), FITSError> {
+        let axes = header
+            .naxes()
+            .ok_or_else(|| HeaderError::GenericError("Invalid NAXIS value".to_string()))?;
+        if axes.is_empty() || axes.contains(&0) {
+            return Ok((HDUData::None, 0));
+        }
+        let npixels = axes
+            .iter()
+            .try_fold(1usize, |n, &len| n.checked_mul(len))
+            .ok_or_else(|| {
+                HeaderError::GenericError(format!("image dimensions {:?} are too large", axes))
+            })?;
+        let nbytes = decoder
+            .data_size(header)
+            .ok_or_else(|| HeaderError::GenericError("Invalid data size".to_string()))?;
+        if rawbytes.len() < nbytes {
+            return Err(FITSError::from(HeaderError::GenericError(format!(
+                "not enough data bytes for image: expected {}, found {}",
+                nbytes,
+                rawbytes.len()
+            ))));
+        }
+        let (pixeltype, pixels) = decoder.decode(header, &rawbytes[..nbytes])?;
+        if npixels.checked_mul(pixeltype.size()) != Some(pixels.len()) {
+            return Err(FITSError::from(ImageError::InvalidDecode(format!(
+                "{} bytes of {:?} pixels for {} pixels",
+                pixels.len(),
+                pixeltype,
+                npixels
+            ))));
+        }
+        let mut alt_wcs = WCS::all_from_header(header)?;
+        let wcs = alt_wcs.remove(&' ');
+        let image = Image {
+            pixeltype,
+            axes,
+            rawbytes: pixels,
+            bscale: 1.0,
+            bzero: 0.0,
+            blank: None,
+            bunit: header
+                .get::<String>("BUNIT")
+                .ok()
+                .map(|s| s.trim().to_string()),
+            wcs,
+            alt_wcs,
+            native: true,
+        };
+        Ok((HDUData::Image(Box::new(image)), nbytes))
+    }
+}
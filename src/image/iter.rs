@@ -0,0 +1,111 @@
+use super::region::check_ranges;
+use super::Image;
+use crate::FITSError;
+
+use std::ops::Range;
+
+/// Pixel locations of a rectangular region, first axis fastest
+struct Locations {
+    ranges: Vec<Range<usize>>,
+    next: Option<Vec<usize>>,
+}
+
+impl Locations {
+    fn new(ranges: Vec<Range<usize>>) -> Self {
+        let empty = ranges.is_empty() || ranges.iter().any(|r| r.is_empty());
+        let next = match empty {
+            true => None,
+            false => Some(ranges.iter().map(|r| r.start).collect()),
+        };
+        Locations { ranges, next }
+    }
+}
+
+impl Iterator for Locations {
+    type Item = Vec<usize>;
+
+    fn next(&mut self) -> Option<Vec<usize>> {
+        let loc = self.next.take()?;
+        let mut next = loc.clone();
+        for (ix, r) in self.ranges.iter().enumerate() {
+            next[ix] += 1;
+            if next[ix] < r.end {
+                self.next = Some(next);
+                break;
+            }
+            next[ix] = r.start;
+        }
+        Some(loc)
+    }
+}
+
+impl Image {
+    /// Iterate over pixel locations and values
+    ///
+    /// Pixels are visited in storage order, with the first (FITS NAXIS1)
+    /// axis incrementing most rapidly.  The value type is chosen from
+    /// the pixel type as for `pixels`, and pixels are converted to
+    /// native byte order as needed
+    ///
+    /// # Returns
+    ///
+    /// Iterator of (zero-based location, one index per axis; value)
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use fits::{HDUData, FITS};
+    /// # fn main() -> Result<(), fits::FITSError> {
+    /// # let fits = FITS::from_file("image.fits")?;
+    /// # let HDUData::Image(image) = &fits[0].data else { return Ok(()) };
+    /// for (loc, value) in image.iter_indexed::<f32>() {
+    ///     println!("{:?} {}", loc, value);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    pub fn iter_indexed<T>(&self) -> impl Iterator<Item = (Vec<usize>, T)> + '_
+    where
+        T: bytemuck::Pod,
+    {
+        let ranges = self.axes.iter().map(|n| 0..*n).collect();
+        self.locations(ranges)
+    }
+
+    /// Iterate over the pixel locations and values of a rectangular
+    /// region, as `iter_indexed` does for the whole image
+    ///
+    /// # Arguments
+    ///
+    /// * `ranges` - Zero-based pixel range along each axis
+    ///
+    /// # Returns
+    ///
+    /// Iterator of (location in the image, value), or an error if the
+    /// region does not fit the image
+    ///
+    pub fn enumerate_region<T>(
+        &self,
+        ranges: &[Range<usize>],
+    ) -> Result<impl Iterator<Item = (Vec<usize>, T)> + '_, FITSError>
+    where
+        T: bytemuck::Pod,
+    {
+        check_ranges(&self.axes, ranges)?;
+        Ok(self.locations(ranges.to_vec()))
+    }
+
+    /// Values of the pixels of a region, with their locations
+    fn locations<T>(&self, ranges: Vec<Range<usize>>) -> impl Iterator<Item = (Vec<usize>, T)> + '_
+    where
+        T: bytemuck::Pod,
+    {
+        let size = self.pixeltype.size();
+        Locations::new(ranges).map(move |loc| {
+            let offset = self.index(&loc) * size;
+            let value = self.read_pixel(&self.rawbytes[offset..offset + size]);
+            (loc, value)
+        })
+    }
+}
@@ -0,0 +1,223 @@
+use super::Image;
+use crate::Bitpix;
+use crate::FITSError;
+use crate::ImageError;
+
+/// Per-pixel data-quality flags
+///
+/// Each pixel holds a set of bit flags, as in the DQ extensions of HST
+/// and JWST data, where each bit records a condition such as a
+/// saturated, hot, or cosmic-ray-hit pixel.  A pixel is bad, and is
+/// excluded by the masked statistics and arithmetic of `Image`, if any
+/// of its flags is among the mask's bad bits (by default, all bits)
+///
+/// # Example
+///
+/// ```no_run
+/// # use fits::{HDUData, Mask, FITS};
+/// # fn main() -> Result<(), fits::FITSError> {
+/// # let fits = FITS::from_file("image.fits")?;
+/// # let HDUData::Image(sci) = &fits.at(1)?.data else { return Ok(()) };
+/// let HDUData::Image(dq) = &fits.at(3)?.data else {
+///     return Ok(());
+/// };
+/// // Ignore bit 2, which flags merely informational conditions
+/// let mask = Mask::from_dq(dq)?.with_bad_bits(!4).union(&Mask::from_undefined(sci))?;
+/// let stats = sci.stats_masked(Some(&mask))?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Mask {
+    axes: Vec<usize>,
+    flags: Vec<u32>,
+    bad_bits: u32,
+}
+
+impl Mask {
+    /// Flag set by `from_undefined` on undefined pixels; matches the
+    /// DO_NOT_USE bit of JWST data-quality arrays
+    pub const UNDEFINED: u32 = 1;
+
+    /// Mask of the given shape with no pixels flagged
+    pub fn new(axes: &[usize]) -> Self {
+        Mask {
+            axes: axes.to_vec(),
+            flags: vec![0; axes.iter().product()],
+            bad_bits: u32::MAX,
+        }
+    }
+
+    /// Mask flagging the undefined pixels of an image: integer pixels
+    /// equal to BLANK, and NaN floating-point pixels
+    ///
+    /// Undefined pixels get the `UNDEFINED` flag
+    ///
+    pub fn from_undefined(image: &Image) -> Self {
+        Mask {
+            axes: image.axes.clone(),
+            flags: (0..image.npixels())
+                .map(|i| match image.physical_value(i).is_nan() {
+                    true => Self::UNDEFINED,
+                    false => 0,
+                })
+                .collect(),
+            bad_bits: u32::MAX,
+        }
+    }
+
+    /// Mask from a data-quality image, whose integer pixel values are
+    /// the flags
+    ///
+    /// # Arguments
+    ///
+    /// * `dq` - Data-quality image, e.g. the 'DQ' extension.  Signed
+    ///   values are read as their bit pattern; 64-bit values keep
+    ///   their lower 32 bits
+    ///
+    /// # Returns
+    ///
+    /// The mask, or an error if the image has floating-point pixels
+    ///
+    pub fn from_dq(dq: &Image) -> Result<Self, FITSError> {
+        if matches!(dq.pixeltype, Bitpix::Float32 | Bitpix::Float64) {
+            return Err(FITSError::from(ImageError::InvalidMask(
+                "data-quality image has floating-point pixels".to_string(),
+            )));
+        }
+        let size = dq.pixeltype.size();
+        let flag = |i: usize| {
+            let bytes = &dq.rawbytes[i * size..(i + 1) * size];
+            match dq.pixeltype {
+                Bitpix::Int8 => dq.read_pixel::<u8>(bytes) as u32,
+                Bitpix::Int16 | Bitpix::Uint16 => dq.read_pixel::<u16>(bytes) as u32,
+                Bitpix::Int32 | Bitpix::Uint32 => dq.read_pixel::<u32>(bytes),
+                _ => dq.read_pixel::<u64>(bytes) as u32,
+            }
+        };
+        Ok(Mask {
+            axes: dq.axes.clone(),
+            flags: (0..dq.npixels()).map(flag).collect(),
+            bad_bits: u32::MAX,
+        })
+    }
+
+    /// Select the flags that make a pixel bad; pixels with only other
+    /// flags are used
+    pub fn with_bad_bits(mut self, bits: u32) -> Self {
+        self.bad_bits = bits;
+        self
+    }
+
+    /// Flags that make a pixel bad
+    pub fn bad_bits(&self) -> u32 {
+        self.bad_bits
+    }
+
+    /// Shape of the mask
+    pub fn axes(&self) -> &[usize] {
+        &self.axes
+    }
+
+    /// Flags of every pixel, first axis fastest
+    pub fn flags(&self) -> &[u32] {
+        &self.flags
+    }
+
+    /// Linear index of a location
+    fn index(&self, loc: &[usize]) -> usize {
+        loc.iter()
+            .zip(&self.axes)
+            .rev()
+            .fold(0, |offset, (l, n)| offset * n + l)
+    }
+
+    /// Flags of the pixel at a location, one index per axis
+    pub fn flags_at(&self, loc: &[usize]) -> u32 {
+        self.flags[self.index(loc)]
+    }
+
+    /// Add flags to the pixel at a location, one index per axis
+    pub fn set(&mut self, loc: &[usize], bits: u32) {
+        let idx = self.index(loc);
+        self.flags[idx] |= bits;
+    }
+
+    /// Is the pixel at a location bad
+    pub fn is_bad(&self, loc: &[usize]) -> bool {
+        self.is_bad_index(self.index(loc))
+    }
+
+    /// Is the pixel at a linear index bad
+    pub(crate) fn is_bad_index(&self, idx: usize) -> bool {
+        self.flags[idx] & self.bad_bits != 0
+    }
+
+    /// Number of bad pixels
+    pub fn count_bad(&self) -> usize {
+        (0..self.flags.len())
+            .filter(|i| self.is_bad_index(*i))
+            .count()
+    }
+
+    /// Combine two masks of the same shape
+    ///
+    /// # Returns
+    ///
+    /// Mask with the flags of both masks, and whose bad bits are those
+    /// of either, or an error if the shapes differ
+    ///
+    pub fn union(&self, other: &Mask) -> Result<Mask, FITSError> {
+        if self.axes != other.axes {
+            return Err(FITSError::from(ImageError::ShapeMismatch(
+                self.axes.clone(),
+                other.axes.clone(),
+            )));
+        }
+        Ok(Mask {
+            axes: self.axes.clone(),
+            flags: self
+                .flags
+                .iter()
+                .zip(&other.flags)
+                .map(|(a, b)| a | b)
+                .collect(),
+            bad_bits: self.bad_bits | other.bad_bits,
+        })
+    }
+}
+
+impl Image {
+    /// Check that an optional mask has the shape of the image
+    pub(crate) fn check_mask(&self, mask: Option<&Mask>) -> Result<(), FITSError> {
+        match mask {
+            Some(mask) if mask.axes != self.axes => Err(FITSError::from(
+                ImageError::ShapeMismatch(self.axes.clone(), mask.axes.clone()),
+            )),
+            _ => Ok(()),
+        }
+    }
+
+    /// Physical pixel values, with pixels that are bad in an optional
+    /// mask set to NaN.  The mask must have the shape of the image
+    pub(crate) fn masked_pixels(&self, mask: Option<&Mask>) -> Vec<f64> {
+        (0..self.npixels())
+            .map(|i| match mask {
+                Some(mask) if mask.is_bad_index(i) => f64::NAN,
+                _ => self.physical_value(i),
+            })
+            .collect()
+    }
+
+    /// Image with the bad pixels of a mask made undefined
+    ///
+    /// # Returns
+    ///
+    /// A 64-bit float image with the WCS and BUNIT of this image, and
+    /// NaN at bad pixels, or an error if the shapes differ
+    ///
+    pub fn masked(&self, mask: &Mask) -> Result<Image, FITSError> {
+        self.check_mask(Some(mask))?;
+        Ok(self.with_f64_pixels(&self.masked_pixels(Some(mask)), self.bunit.clone()))
+    }
+}
@@ -1,10 +1,37 @@
+mod arith;
+mod combine;
+mod data;
+mod decoder;
+mod iter;
+mod mask;
+#[cfg(feature = "image")]
+mod preview;
+mod region;
+mod stats;
+
+pub use combine::CombineMethod;
+pub use data::ImageData;
+pub(crate) use decoder::find as find_decoder;
+pub use decoder::{DecoderId, Float16Decoder, PixelDecoder};
+pub use mask::Mask;
+#[cfg(feature = "image")]
+pub use preview::Stretch;
+pub use stats::Histogram;
+pub use stats::ImageStats;
+
 use crate::Bitpix;
+use crate::FITSError;
 use crate::HDUData;
 use crate::Header;
 use crate::HeaderError;
+use crate::ImageError;
 use crate::KeywordValue;
+use crate::Pixel;
+use crate::Unit;
 use crate::WCS;
 
+use std::collections::HashMap;
+
 /// Represent image data as described in a FITS file
 ///
 /// # This include2:
@@ -18,7 +45,62 @@ pub struct Image {
     pub pixeltype: Bitpix,
     pub axes: Vec<usize>,
     pub rawbytes: Vec<u8>,
+    /// Scale factor applied to stored values (BSCALE)
+    pub bscale: f64,
+    /// Offset applied to stored values (BZERO)
+    ///
+    /// # Note: this is zero for unsigned pixel types,
+    ///   as the offset is applied on data ingest
+    pub bzero: f64,
+    /// Stored value representing undefined pixels in integer images (BLANK)
+    pub blank: Option<i64>,
+    /// Unit of the physical pixel values (BUNIT)
+    pub bunit: Option<String>,
+    /// Primary World Coordinate System
     pub wcs: Option<WCS>,
+    /// Alternate World Coordinate Systems, keyed by 'A' through 'Z'
+    pub alt_wcs: HashMap<char, WCS>,
+    /// True if `rawbytes` holds pixels in native byte order
+    ///
+    /// False if pixels were kept as stored in the file (big-endian,
+    /// sign bit flipped for unsigned types) and are decoded on access;
+    /// see `FITS::from_file_deferred`
+    pub native: bool,
+}
+
+/// Convert big-endian pixels to native byte order, in place
+///
+/// Unsigned types also have their sign bit flipped, which applies the
+/// BZERO = 2^(bits-1) offset they are stored with.  Words are swapped
+/// through an aligned view of the buffer when possible, so the loop
+/// can be vectorized; otherwise each pixel is converted separately
+///
+/// # Arguments
+///
+/// * `bytes` - Pixel bytes, as stored in the file
+/// * `bitpix` - Pixel type
+///
+fn to_native(bytes: &mut [u8], bitpix: Bitpix) {
+    let flip = bitpix.unsigned_offset().is_some();
+    match bitpix.size() {
+        2 => swap_words(bytes, |w: u16| u16::from_be(w) ^ ((flip as u16) << 15)),
+        4 => swap_words(bytes, |w: u32| u32::from_be(w) ^ ((flip as u32) << 31)),
+        8 => swap_words(bytes, |w: u64| u64::from_be(w) ^ ((flip as u64) << 63)),
+        _ => {}
+    }
+}
+
+/// Apply a conversion to each word of a byte buffer, in place
+fn swap_words<T: bytemuck::Pod>(bytes: &mut [u8], convert: impl Fn(T) -> T) {
+    match bytemuck::try_cast_slice_mut::<u8, T>(bytes) {
+        Ok(words) => words.iter_mut().for_each(|w| *w = convert(*w)),
+        Err(_) => {
+            for chunk in bytes.chunks_exact_mut(std::mem::size_of::<T>()) {
+                let w: T = bytemuck::pod_read_unaligned(chunk);
+                chunk.copy_from_slice(bytemuck::bytes_of(&convert(w)));
+            }
+        }
+    }
 }
 
 impl Image {
@@ -27,6 +109,11 @@ impl Image {
         self.axes.len()
     }
 
+    /// Total number of pixels
+    pub fn npixels(&self) -> usize {
+        self.axes.iter().product()
+    }
+
     /// Construct an image from raw bytes from the file
     ///
     /// Arguments:
@@ -42,31 +129,103 @@ impl Image {
     pub(crate) fn from_bytes(
         header: &Header,
         rawbytes: &[u8],
-    ) -> Result<(HDUData, usize), Box<dyn std::error::Error>> {
-        let mut image = HDUData::None;
+    ) -> Result<(HDUData, usize), FITSError> {
+        Self::parse(header, rawbytes, true)
+    }
+
+    /// Construct an image keeping the pixels as stored in the file
+    ///
+    /// No byte-order conversion is done; pixels are decoded when
+    /// accessed.  See `from_bytes`
+    ///
+    pub(crate) fn from_bytes_deferred(
+        header: &Header,
+        rawbytes: &[u8],
+    ) -> Result<(HDUData, usize), FITSError> {
+        Self::parse(header, rawbytes, false)
+    }
 
+    fn parse(
+        header: &Header,
+        rawbytes: &[u8],
+        native: bool,
+    ) -> Result<(HDUData, usize), FITSError> {
+        if let Some(decoder) = decoder::find(header) {
+            return Self::decode_with(&*decoder, header, rawbytes);
+        }
         let kwbitpix = header
-            .get(1)
+            .iter()
+            .nth(1)
             .ok_or(HeaderError::GenericError("not enough keywords".to_string()))?;
         if kwbitpix.name != "BITPIX" {
-            return Err(Box::new(HeaderError::InvalidKeywordPlacement(
+            return Err(FITSError::from(HeaderError::InvalidKeywordPlacement(
                 kwbitpix.name.clone(),
                 1,
             )));
         }
-        let bitpix = match &kwbitpix.value {
+        let mut bitpix = match &kwbitpix.value {
             KeywordValue::Int(value) => Bitpix::from_i64(*value)?,
             _ => {
-                return Err(Box::new(HeaderError::GenericError(
+                return Err(FITSError::from(HeaderError::GenericError(
                     "Invalid BITPIX value".to_string(),
                 )))
             }
         };
+        // Linear scaling of stored values to physical values
+        // See Section 4.4.2.5 of FITS standard
+        let bscale = match header.value("BSCALE") {
+            None => 1.0,
+            Some(KeywordValue::Int(v)) => *v as f64,
+            Some(KeywordValue::Float(v)) => *v,
+            Some(_) => {
+                return Err(FITSError::from(HeaderError::UnexpectedValueType(
+                    "BSCALE".into(),
+                )))
+            }
+        };
+        let mut bzero = match header.value("BZERO") {
+            None => 0.0,
+            Some(KeywordValue::Int(v)) => *v as f64,
+            Some(KeywordValue::Float(v)) => *v,
+            Some(_) => {
+                return Err(FITSError::from(HeaderError::UnexpectedValueType(
+                    "BZERO".into(),
+                )))
+            }
+        };
+        let blank = match header.value("BLANK") {
+            None => None,
+            Some(KeywordValue::Int(v)) => Some(*v),
+            Some(_) => {
+                return Err(FITSError::from(HeaderError::UnexpectedValueType(
+                    "BLANK".into(),
+                )))
+            }
+        };
+        let bunit = match header.value("BUNIT") {
+            None => None,
+            Some(KeywordValue::String(s)) => Some(s.trim().to_string()),
+            Some(_) => {
+                return Err(FITSError::from(HeaderError::UnexpectedValueType(
+                    "BUNIT".into(),
+                )))
+            }
+        };
+        // Unsigned integers are stored as signed integers with
+        // BZERO = 2^(bits-1) and BSCALE = 1
+        if let Some(unsigned) = bitpix.to_unsigned() {
+            if bscale == 1.0 && Some(bzero) == unsigned.unsigned_offset() {
+                bitpix = unsigned;
+                // Offset is applied when the pixels are read in
+                bzero = 0.0;
+            }
+        }
         let kwaxes = header
-            .get(2)
+            .iter()
+            .nth(2)
             .ok_or(HeaderError::GenericError("not enough keywords".to_string()))?;
         if kwaxes.name != "NAXIS" {
-            return Err(Box::new(HeaderError::InvalidKeywordPlacement(
+            return Err(FITSError::from(HeaderError::InvalidKeywordPlacement(
                 kwaxes.name.clone(),
                 2,
             )));
@@ -74,7 +233,7 @@ impl Image {
         let naxis = match &kwaxes.value {
             KeywordValue::Int(value) => *value as u16,
             _ => {
-                return Err(Box::new(HeaderError::GenericError(
+                return Err(FITSError::from(HeaderError::GenericError(
                     "Invalid NAXIS value".to_string(),
                 )))
             }
@@ -82,10 +241,11 @@ impl Image {
         let mut axes = Vec::with_capacity(naxis as usize);
         for i in 0..naxis {
             let kwaxis = header
-                .get(3 + i as usize)
+                .iter()
+                .nth(3 + i as usize)
                 .ok_or(HeaderError::GenericError("not enough keywords".to_string()))?;
             if kwaxis.name != format!("NAXIS{}", i + 1) {
-                return Err(Box::new(HeaderError::InvalidKeywordPlacement(
+                return Err(FITSError::from(HeaderError::InvalidKeywordPlacement(
                     kwaxis.name.clone(),
                     3 + i as usize,
                 )));
@@ -93,7 +253,7 @@ impl Image {
             let axis = match &kwaxis.value {
                 KeywordValue::Int(value) => *value as usize,
                 _ => {
-                    return Err(Box::new(HeaderError::GenericError(
+                    return Err(FITSError::from(HeaderError::GenericError(
                         "Invalid NAXIS value".to_string(),
                     )))
                 }
@@ -105,12 +265,13 @@ impl Image {
         if KeywordValue::String("IMAGE".to_string()) == header[0].value {
             // loog for PCOUNT and GCOUNT keywords
 
-            let kwidx = 4 + naxis as usize;
+            let kwidx = 3 + naxis as usize;
             let kwpcount = header
-                .get(kwidx)
+                .iter()
+                .nth(kwidx)
                 .ok_or(HeaderError::GenericError("not enough keywords".to_string()))?;
             if kwpcount.name != "PCOUNT" {
-                return Err(Box::new(HeaderError::InvalidKeywordPlacement(
+                return Err(FITSError::from(HeaderError::InvalidKeywordPlacement(
                     kwpcount.name.clone(),
                     kwidx,
                 )));
@@ -118,16 +279,17 @@ impl Image {
             match &kwpcount.value {
                 KeywordValue::Int(value) => _pcount = *value as usize,
                 _ => {
-                    return Err(Box::new(HeaderError::GenericError(
+                    return Err(FITSError::from(HeaderError::GenericError(
                         "Invalid PCOUNT value".to_string(),
                     )))
                 }
             }
             let kwgcount = header
-                .get(kwidx + 1)
+                .iter()
+                .nth(kwidx + 1)
                 .ok_or(HeaderError::GenericError("not enough keywords".to_string()))?;
             if kwgcount.name != "GCOUNT" {
-                return Err(Box::new(HeaderError::InvalidKeywordPlacement(
+                return Err(FITSError::from(HeaderError::InvalidKeywordPlacement(
                     kwgcount.name.clone(),
                     kwidx + 1,
                 )));
@@ -135,80 +297,84 @@ impl Image {
             match &kwgcount.value {
                 KeywordValue::Int(value) => _gcount = *value as usize,
                 _ => {
-                    return Err(Box::new(HeaderError::GenericError(
+                    return Err(FITSError::from(HeaderError::GenericError(
                         "Invalid GCOUNT value".to_string(),
                     )))
                 }
             }
         }
 
-        let npixels = axes.iter().product::<usize>();
-        let nbytes = npixels * bitpix.size();
-        if nbytes > 0 {
-            // Extract raw bytes of image, but make sure they match large endian format
-            // for fast data retreival later
-            let imgrawbytes: Vec<u8> = match bitpix {
-                Bitpix::Int8 => rawbytes[0..nbytes].to_vec(),
-                Bitpix::Int16 => {
-                    // Convert raw bytes to u16 taking as big endian
-                    bytemuck::cast_slice(
-                        &rawbytes[0..nbytes]
-                            .chunks_exact(2)
-                            .map(|x| u16::from_be_bytes(x.try_into().unwrap()))
-                            .collect::<Vec<u16>>(),
-                    )
-                    .to_vec()
-                }
-                Bitpix::Int32 => {
-                    // Convert raw bytes to u32 taking as big endian
-                    bytemuck::cast_slice(
-                        &rawbytes[0..nbytes]
-                            .chunks_exact(4)
-                            .map(|x| u32::from_be_bytes(x.try_into().unwrap()))
-                            .collect::<Vec<u32>>(),
-                    )
-                    .to_vec()
-                }
-                Bitpix::Int64 => {
-                    // Convert raw bytes to u64 taking as big endian
-                    bytemuck::cast_slice(
-                        &rawbytes[0..nbytes]
-                            .chunks_exact(8)
-                            .map(|x| u64::from_be_bytes(x.try_into().unwrap()))
-                            .collect::<Vec<u64>>(),
-                    )
-                    .to_vec()
-                }
-                Bitpix::Float32 => {
-                    // Convert raw bytes to f32 taking as big endian
-                    bytemuck::cast_slice(
-                        &rawbytes[0..nbytes]
-                            .chunks_exact(4)
-                            .map(|x| f32::from_be_bytes(x.try_into().unwrap()))
-                            .collect::<Vec<f32>>(),
-                    )
-                    .to_vec()
+        // NAXIS = 0, or any NAXISn = 0, means there is no data unit
+        // (Section 4.4.1.1 of FITS standard, version 4), as in the
+        // empty primary HDU of most multi-extension files
+        if axes.is_empty() || axes.contains(&0) {
+            return Ok((HDUData::None, 0));
+        }
+        let nbytes = axes
+            .iter()
+            .try_fold(bitpix.size(), |n, &len| n.checked_mul(len))
+            .ok_or_else(|| {
+                HeaderError::GenericError(format!("image dimensions {:?} are too large", axes))
+            })?;
+        if rawbytes.len() < nbytes {
+            return Err(FITSError::from(HeaderError::GenericError(format!(
+                "not enough data bytes for image: expected {}, found {}",
+                nbytes,
+                rawbytes.len()
+            ))));
+        }
+        // Extract raw bytes of image, converted to native byte order
+        // for fast data retreival later
+        let mut imgrawbytes = rawbytes[0..nbytes].to_vec();
+        if native {
+            to_native(&mut imgrawbytes, bitpix);
+        }
+        let mut alt_wcs = WCS::all_from_header(header)?;
+        let wcs = alt_wcs.remove(&' ');
+        let image = HDUData::Image(Box::new(Image {
+            pixeltype: bitpix,
+            axes,
+            rawbytes: imgrawbytes,
+            bscale,
+            bzero,
+            blank,
+            bunit,
+            wcs,
+            alt_wcs,
+            native,
+        }));
+        Ok((image, nbytes))
+    }
+
+    /// Encode the pixels as stored in a FITS data unit
+    ///
+    /// This reverses the conversion done on ingest: values are
+    /// written big-endian, with the sign bit flipped for unsigned
+    /// types (which are stored with BZERO = 2^(bits-1)).  The result
+    /// is not padded to a multiple of 2880 bytes
+    ///
+    /// # Returns
+    ///
+    /// Data unit bytes
+    ///
+    pub fn to_bytes(&self) -> Vec<u8> {
+        if !self.native {
+            return self.rawbytes.clone();
+        }
+        let size = self.pixeltype.size();
+        let unsigned = self.pixeltype.unsigned_offset().is_some();
+        let mut bytes = self.rawbytes.clone();
+        if size > 1 {
+            for px in bytes.chunks_exact_mut(size) {
+                if cfg!(target_endian = "little") {
+                    px.reverse();
                 }
-                Bitpix::Float64 => {
-                    // Convert raw bytes to f64 taking as big endian
-                    bytemuck::cast_slice(
-                        &rawbytes[0..nbytes]
-                            .chunks_exact(8)
-                            .map(|x| f64::from_be_bytes(x.try_into().unwrap()))
-                            .collect::<Vec<f64>>(),
-                    )
-                    .to_vec()
+                if unsigned {
+                    px[0] ^= 0x80;
                 }
-            };
-            image = HDUData::Image(Box::new(Image {
-                pixeltype: bitpix,
-                axes,
-                rawbytes: imgrawbytes,
-                wcs: crate::WCS::from_header(header)?,
-            }))
+            }
         }
-
-        Ok((image, nbytes))
+        bytes
     }
 
     /// Access raw pixels in native format ...
@@ -220,25 +386,79 @@ impl Image {
     ///
     /// # Casting based upon Bitpix  values
     /// * `Bitpix::Int8`    : u8
-    /// * `Bitpix::Int16`   : u16
-    /// * `Bitpix::Int32`   : u32
-    /// * `Bitpix::Int64`   : u64
+    /// * `Bitpix::Int16`   : i16
+    /// * `Bitpix::Int32`   : i32
+    /// * `Bitpix::Int64`   : i64
+    /// * `Bitpix::Uint16`  : u16
+    /// * `Bitpix::Uint32`  : u32
+    /// * `Bitpix::Uint64`  : u64
     /// * `Bitpix::Float32` : f32
     /// * `Bitpix::Float64` : f64
-    pub fn pixels<T>(&self) -> &[T]
+    ///
+    /// # Returns
+    ///
+    /// The pixels, or `ImageError::NotNative` if they are not in native
+    /// byte order (see `native`); use `pixels_iter` or
+    /// `convert_to_native` for such images
+    ///
+    pub fn pixels<T>(&self) -> Result<&[T], FITSError>
+    where
+        T: bytemuck::Pod,
+    {
+        if !self.native {
+            return Err(FITSError::from(ImageError::NotNative));
+        }
+        Ok(bytemuck::cast_slice(&self.rawbytes))
+    }
+
+    /// Iterate over pixels, converting to native byte order as needed
+    ///
+    /// Works whether or not the pixels were converted on ingest, so
+    /// only the pixels visited pay for conversion.  The type is chosen
+    /// from the pixel type as for `pixels`
+    ///
+    pub fn pixels_iter<T>(&self) -> impl Iterator<Item = T> + '_
+    where
+        T: bytemuck::Pod,
+    {
+        self.rawbytes
+            .chunks_exact(self.pixeltype.size())
+            .map(|b| self.read_pixel(b))
+    }
+
+    /// Convert the pixels to native byte order in place, if not already
+    pub fn convert_to_native(&mut self) {
+        if !self.native {
+            to_native(&mut self.rawbytes, self.pixeltype);
+            self.native = true;
+        }
+    }
+
+    /// Decode one pixel from its bytes in `rawbytes`
+    fn read_pixel<T>(&self, bytes: &[u8]) -> T
     where
         T: bytemuck::Pod,
     {
-        bytemuck::cast_slice(&self.rawbytes)
+        if self.native {
+            return bytemuck::pod_read_unaligned(bytes);
+        }
+        let mut buf = [0u8; 8];
+        let buf = &mut buf[..bytes.len()];
+        buf.copy_from_slice(bytes);
+        to_native(buf, self.pixeltype);
+        bytemuck::pod_read_unaligned(buf)
     }
 
     /// Get pixel value at a given location
     ///
     /// # Casting based upon Bitpix  values
     /// * `Bitpix::Int8`    : u8
-    /// * `Bitpix::Int16`   : u16
-    /// * `Bitpix::Int32`   : u32
-    /// * `Bitpix::Int64`   : u64
+    /// * `Bitpix::Int16`   : i16
+    /// * `Bitpix::Int32`   : i32
+    /// * `Bitpix::Int64`   : i64
+    /// * `Bitpix::Uint16`  : u16
+    /// * `Bitpix::Uint32`  : u32
+    /// * `Bitpix::Uint64`  : u64
     /// * `Bitpix::Float32` : f32
     /// * `Bitpix::Float64` : f64
     ///
@@ -247,6 +467,52 @@ impl Image {
         T: bytemuck::Pod,
     {
         let bitsize = self.pixeltype.size();
+        let offset = self.index(loc) * bitsize;
+        self.read_pixel(&self.rawbytes[offset..(offset + bitsize)])
+    }
+
+    /// Get pixel value at a given location, checking the location and type
+    ///
+    /// # Arguments
+    ///
+    /// * `loc` - Pixel location, one zero-based index per axis
+    ///
+    /// # Returns
+    ///
+    /// The stored value, or an error if `loc` does not have one index
+    /// per axis, an index is beyond its axis, or `T` is not the Rust
+    /// type of the pixel type (see `at`)
+    ///
+    pub fn try_at<T>(&self, loc: &[usize]) -> Result<T, FITSError>
+    where
+        T: Pixel,
+    {
+        self.check_location(loc)?;
+        if T::BITPIX != self.pixeltype {
+            return Err(FITSError::from(ImageError::TypeMismatch(
+                self.pixeltype,
+                T::BITPIX,
+            )));
+        }
+        Ok(self.at(loc))
+    }
+
+    /// Check that a location has one index per axis, each within its axis
+    fn check_location(&self, loc: &[usize]) -> Result<(), FITSError> {
+        if loc.len() != self.ndims() {
+            return Err(FITSError::from(ImageError::DimensionMismatch(
+                self.ndims(),
+                loc.len(),
+            )));
+        }
+        if let Some(ix) = (0..loc.len()).find(|&ix| loc[ix] >= self.axes[ix]) {
+            return Err(FITSError::from(ImageError::RegionOutOfBounds(ix)));
+        }
+        Ok(())
+    }
+
+    /// Linear pixel index of a given location
+    fn index(&self, loc: &[usize]) -> usize {
         let mut offmult = 1;
         let mut offset = 0;
         // The first index increments most rapidly
@@ -254,6 +520,735 @@ impl Image {
             offset += offmult * loc_val;
             offmult *= self.axes[ix];
         }
-        bytemuck::cast_slice(&self.rawbytes[offset..(offset + bitsize)])[0]
+        offset
+    }
+
+    /// Stored value of pixel at linear index, along with the value
+    /// as written in the file for comparison against BLANK
+    fn stored_value(&self, idx: usize) -> (f64, Option<i64>) {
+        let sz = self.pixeltype.size();
+        let b = &self.rawbytes[idx * sz..(idx + 1) * sz];
+        match self.pixeltype {
+            Bitpix::Int8 => {
+                let v: u8 = self.read_pixel(b);
+                (v as f64, Some(v as i64))
+            }
+            Bitpix::Int16 => {
+                let v: i16 = self.read_pixel(b);
+                (v as f64, Some(v as i64))
+            }
+            Bitpix::Int32 => {
+                let v: i32 = self.read_pixel(b);
+                (v as f64, Some(v as i64))
+            }
+            Bitpix::Int64 => {
+                let v: i64 = self.read_pixel(b);
+                (v as f64, Some(v))
+            }
+            Bitpix::Uint16 => {
+                let v: u16 = self.read_pixel(b);
+                (v as f64, Some((v ^ 0x8000) as i16 as i64))
+            }
+            Bitpix::Uint32 => {
+                let v: u32 = self.read_pixel(b);
+                (v as f64, Some((v ^ 0x8000_0000) as i32 as i64))
+            }
+            Bitpix::Uint64 => {
+                let v: u64 = self.read_pixel(b);
+                (v as f64, Some((v ^ 0x8000_0000_0000_0000) as i64))
+            }
+            Bitpix::Float32 => {
+                let v: f32 = self.read_pixel(b);
+                (v as f64, None)
+            }
+            Bitpix::Float64 => (self.read_pixel(b), None),
+        }
+    }
+
+    /// Physical value of pixel at linear index, NaN if undefined
+    fn physical_value(&self, idx: usize) -> f64 {
+        let (value, stored) = self.stored_value(idx);
+        if self.blank.is_some() && stored == self.blank {
+            return f64::NAN;
+        }
+        self.bzero + self.bscale * value
+    }
+
+    /// Physical pixel values
+    ///
+    /// Applies the linear scaling `physical = BZERO + BSCALE * stored`
+    /// to every pixel.  Integer pixels equal to the BLANK value, and
+    /// floating-point pixels that are NaN, are returned as NaN
+    ///
+    /// # Returns
+    ///
+    /// Vector of physical values, with the first axis incrementing most rapidly
+    ///
+    pub fn physical_pixels(&self) -> Vec<f64> {
+        (0..self.npixels())
+            .map(|i| self.physical_value(i))
+            .collect()
+    }
+
+    /// Physical pixel value at a given location
+    ///
+    /// # Arguments
+    ///
+    /// * `loc` - Pixel location, one index per axis
+    ///
+    /// # Returns
+    ///
+    /// The scaled value (see `physical_pixels`), None if the pixel is
+    /// undefined (BLANK or NaN), or an error if `loc` does not have one
+    /// index per axis or an index is beyond its axis
+    ///
+    pub fn physical_at(&self, loc: &[usize]) -> Result<Option<f64>, FITSError> {
+        self.check_location(loc)?;
+        let value = self.physical_value(self.index(loc));
+        match value.is_nan() {
+            true => Ok(None),
+            false => Ok(Some(value)),
+        }
+    }
+
+    /// Unit of the physical pixel values
+    ///
+    /// # Returns
+    ///
+    /// The parsed BUNIT, None if the image has no BUNIT, or an error if
+    /// BUNIT is not a valid unit string
+    ///
+    pub fn unit(&self) -> Result<Option<Unit>, FITSError> {
+        Ok(match &self.bunit {
+            Some(bunit) => Some(bunit.parse()?),
+            None => None,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testutil::{cards, hdu_bytes, image, kw};
+    use crate::Keyword;
+
+    #[test]
+    fn test_unsigned16() {
+        let header = Header::new(vec![
+            kw("SIMPLE", KeywordValue::Bool(true)),
+            kw("BITPIX", KeywordValue::Int(16)),
+            kw("NAXIS", KeywordValue::Int(2)),
+            kw("NAXIS1", KeywordValue::Int(2)),
+            kw("NAXIS2", KeywordValue::Int(2)),
+            kw("BZERO", KeywordValue::Float(32768.0)),
+            kw("BSCALE", KeywordValue::Int(1)),
+            kw("END", KeywordValue::None),
+        ]);
+        let raw: Vec<u8> = [-32768i16, -1, 0, 32767]
+            .iter()
+            .flat_map(|x| x.to_be_bytes())
+            .collect();
+        let (data, nbytes) = Image::from_bytes(&header, &raw).unwrap();
+        assert_eq!(nbytes, 8);
+        let HDUData::Image(im) = data else {
+            panic!("expected image");
+        };
+        assert_eq!(im.pixeltype, Bitpix::Uint16);
+        assert_eq!(im.pixels::<u16>().unwrap(), &[0, 32767, 32768, 65535]);
+        assert_eq!(im.at::<u16>(&[1, 1]), 65535);
+        assert_eq!(im.at::<u16>(&[0, 1]), 32768);
+        assert_eq!(im.physical_at(&[1, 1]).unwrap(), Some(65535.0));
+        assert!(im.physical_at(&[1]).is_err());
+        assert!(im.physical_at(&[2, 0]).is_err());
+    }
+
+    #[test]
+    fn test_unsigned64() {
+        let mut header = Header::new(vec![
+            kw("SIMPLE", KeywordValue::Bool(true)),
+            kw("BITPIX", KeywordValue::Int(64)),
+            kw("NAXIS", KeywordValue::Int(1)),
+            kw("NAXIS1", KeywordValue::Int(2)),
+        ]);
+        let card = cards(&["BZERO   =  9223372036854775808"]);
+        header.push_card(Keyword::new(&card).unwrap());
+        header.push_card(kw("END", KeywordValue::None));
+        let raw: Vec<u8> = [i64::MIN, i64::MAX]
+            .iter()
+            .flat_map(|x| x.to_be_bytes())
+            .collect();
+        let (data, _) = Image::from_bytes(&header, &raw).unwrap();
+        let HDUData::Image(im) = data else {
+            panic!("expected image");
+        };
+        assert_eq!(im.pixeltype, Bitpix::Uint64);
+        assert_eq!(im.pixels::<u64>().unwrap(), &[0, u64::MAX]);
+    }
+
+    #[test]
+    fn test_deferred() {
+        let header = Header::new(vec![
+            kw("SIMPLE", KeywordValue::Bool(true)),
+            kw("BITPIX", KeywordValue::Int(16)),
+            kw("NAXIS", KeywordValue::Int(1)),
+            kw("NAXIS1", KeywordValue::Int(3)),
+            kw("BZERO", KeywordValue::Float(32768.0)),
+            kw("END", KeywordValue::None),
+        ]);
+        let raw: Vec<u8> = [-32768i16, 0, 32767]
+            .iter()
+            .flat_map(|x| x.to_be_bytes())
+            .collect();
+        let (data, _) = Image::from_bytes_deferred(&header, &raw).unwrap();
+        let HDUData::Image(mut im) = data else {
+            panic!("expected image");
+        };
+        assert!(!im.native);
+        assert_eq!(im.rawbytes, raw);
+        assert_eq!(
+            im.pixels_iter::<u16>().collect::<Vec<_>>(),
+            [0, 32768, 65535]
+        );
+        assert_eq!(im.at::<u16>(&[1]), 32768);
+        assert_eq!(im.physical_at(&[2]).unwrap(), Some(65535.0));
+        assert_eq!(im.to_bytes(), raw);
+        assert!(matches!(
+            im.pixels::<u16>(),
+            Err(FITSError::Image(ImageError::NotNative))
+        ));
+
+        im.convert_to_native();
+        assert_eq!(im.pixels::<u16>().unwrap(), &[0, 32768, 65535]);
+        assert_eq!(im.to_bytes(), raw);
+    }
+
+    #[test]
+    fn test_to_native() {
+        let values = [1.5f64, -2.25, 1e300];
+        let raw: Vec<u8> = values.iter().flat_map(|x| x.to_be_bytes()).collect();
+        // Aligned and unaligned buffers give the same result
+        let mut aligned = raw.clone();
+        to_native(&mut aligned, Bitpix::Float64);
+        let mut buf = vec![0u8];
+        buf.extend(&raw);
+        to_native(&mut buf[1..], Bitpix::Float64);
+        assert_eq!(aligned, buf[1..]);
+        let pixels: Vec<f64> = aligned
+            .chunks_exact(8)
+            .map(bytemuck::pod_read_unaligned)
+            .collect();
+        assert_eq!(pixels, values);
+
+        let mut raw: Vec<u8> = [i32::MIN, -1, 0]
+            .iter()
+            .flat_map(|x| x.to_be_bytes())
+            .collect();
+        to_native(&mut raw, Bitpix::Uint32);
+        let pixels: Vec<u32> = raw
+            .chunks_exact(4)
+            .map(bytemuck::pod_read_unaligned)
+            .collect();
+        assert_eq!(pixels, [0, 0x7FFF_FFFF, 0x8000_0000]);
+    }
+
+    #[test]
+    fn test_no_data() {
+        let header = |naxis: &[i64]| {
+            let mut cards = vec![
+                kw("SIMPLE", KeywordValue::Bool(true)),
+                kw("BITPIX", KeywordValue::Int(16)),
+                kw("NAXIS", KeywordValue::Int(naxis.len() as i64)),
+            ];
+            for (i, n) in naxis.iter().enumerate() {
+                cards.push(kw(&format!("NAXIS{}", i + 1), KeywordValue::Int(*n)));
+            }
+            cards.push(kw("END", KeywordValue::None));
+            Header::new(cards)
+        };
+        assert!(matches!(
+            Image::from_bytes(&header(&[]), &[]).unwrap(),
+            (HDUData::None, 0)
+        ));
+        assert!(matches!(
+            Image::from_bytes(&header(&[4, 0]), &[]).unwrap(),
+            (HDUData::None, 0)
+        ));
+        // Truncated data is an error rather than a panic
+        assert!(Image::from_bytes(&header(&[4]), &[0u8; 6]).is_err());
+        // As is a size that overflows
+        let huge = 1i64 << 32;
+        assert!(Image::from_bytes(&header(&[huge, huge]), &[0u8; 6]).is_err());
+    }
+
+    #[test]
+    fn test_image_extension() {
+        let header = Header::new(vec![
+            kw("XTENSION", KeywordValue::String("IMAGE".into())),
+            kw("BITPIX", KeywordValue::Int(8)),
+            kw("NAXIS", KeywordValue::Int(2)),
+            kw("NAXIS1", KeywordValue::Int(2)),
+            kw("NAXIS2", KeywordValue::Int(1)),
+            kw("PCOUNT", KeywordValue::Int(0)),
+            kw("GCOUNT", KeywordValue::Int(1)),
+            kw("END", KeywordValue::None),
+        ]);
+        let (data, nbytes) = Image::from_bytes(&header, &[3, 4]).unwrap();
+        assert_eq!(nbytes, 2);
+        let HDUData::Image(im) = data else {
+            panic!("expected image");
+        };
+        assert_eq!(im.axes, vec![2, 1]);
+    }
+
+    #[test]
+    fn test_physical() {
+        let header = Header::new(vec![
+            kw("SIMPLE", KeywordValue::Bool(true)),
+            kw("BITPIX", KeywordValue::Int(16)),
+            kw("NAXIS", KeywordValue::Int(1)),
+            kw("NAXIS1", KeywordValue::Int(3)),
+            kw("BZERO", KeywordValue::Float(100.0)),
+            kw("BSCALE", KeywordValue::Float(0.5)),
+            kw("BLANK", KeywordValue::Int(-1)),
+            kw("END", KeywordValue::None),
+        ]);
+        let raw: Vec<u8> = [10i16, -1, -20]
+            .iter()
+            .flat_map(|x| x.to_be_bytes())
+            .collect();
+        let HDUData::Image(im) = Image::from_bytes(&header, &raw).unwrap().0 else {
+            panic!("expected image");
+        };
+        let phys = im.physical_pixels();
+        assert_eq!(phys[0], 105.0);
+        assert!(phys[1].is_nan());
+        assert_eq!(phys[2], 90.0);
+        assert_eq!(im.physical_at(&[1]).unwrap(), None);
+        assert_eq!(im.physical_at(&[2]).unwrap(), Some(90.0));
+
+        let stats = im.stats().unwrap();
+        assert_eq!(stats.npixels, 3);
+        assert_eq!(stats.nvalid, 2);
+        assert_eq!(stats.min, 90.0);
+        assert_eq!(stats.max, 105.0);
+        assert_eq!(stats.median, 97.5);
+        assert_eq!(stats.stddev, 7.5);
+        assert_eq!(im.percentile(100.0), Some(105.0));
+        let hist = im.histogram(3).unwrap();
+        assert_eq!(hist.counts, vec![1, 0, 1]);
+        assert_eq!(hist.edges, vec![90.0, 95.0, 100.0, 105.0]);
+    }
+
+    #[test]
+    fn test_arithmetic() {
+        let im = |pixeltype, rawbytes: Vec<u8>, bscale| Image {
+            bscale,
+            blank: Some(-1),
+            bunit: Some("adu".to_string()),
+            ..image(pixeltype, &[2, 2], &rawbytes)
+        };
+        let a = im(
+            Bitpix::Int16,
+            bytemuck::cast_slice(&[10i16, 20, -1, 40]).to_vec(),
+            2.0,
+        );
+        let b = im(
+            Bitpix::Float32,
+            bytemuck::cast_slice(&[1.0f32, 2.0, 3.0, 0.0]).to_vec(),
+            1.0,
+        );
+
+        let sum = a.add(&b).unwrap();
+        assert_eq!(sum.pixeltype, Bitpix::Float64);
+        assert_eq!(sum.bunit.as_deref(), Some("adu"));
+        assert_eq!(sum.pixels::<f64>().unwrap()[..2], [21.0, 42.0]);
+        assert!(sum.pixels::<f64>().unwrap()[2].is_nan());
+        assert_eq!(a.subtract(&b).unwrap().pixels::<f64>().unwrap()[3], 80.0);
+        assert_eq!(a.multiply(&b).unwrap().pixels::<f64>().unwrap()[1], 80.0);
+        let ratio = a.divide(&b).unwrap();
+        assert_eq!(ratio.pixels::<f64>().unwrap()[0], 20.0);
+        assert!(ratio.pixels::<f64>().unwrap()[3].is_infinite());
+        assert!(ratio.bunit.is_none());
+        assert_eq!(b.add_scalar(1.5).pixels::<f64>().unwrap()[0], 2.5);
+        assert_eq!(a.scale(0.5).pixels::<f64>().unwrap()[3], 40.0);
+
+        let mut c = b.clone();
+        c.axes = vec![4, 1];
+        assert!(a.add(&c).is_err());
+    }
+
+    #[test]
+    fn test_interval() {
+        // A ramp of 0 to 999 with a few very bright pixels
+        let mut pixels: Vec<f64> = (0..1000).map(|i| i as f64).collect();
+        pixels[10] = 1.0e6;
+        pixels[20] = 2.0e6;
+        let im = image(Bitpix::Float64, &[1000], &pixels);
+        let (vmin, vmax) = im.zscale().unwrap();
+        // The slope over the ramp, widened by the contrast, sets the
+        // range; the bright pixels are ignored
+        assert_eq!(vmin, 0.0);
+        assert!(vmax > 999.0 && vmax < 3000.0);
+        let (lo, hi) = im.percentile_interval(99.0).unwrap();
+        assert!((lo - 4.995).abs() < 1e-9 && (hi - 996.005).abs() < 1e-9);
+        assert_eq!(im.percentile_interval(100.0), Some((0.0, 2.0e6)));
+
+        let flat = Image {
+            rawbytes: bytemuck::cast_slice(&[f64::NAN; 1000]).to_vec(),
+            ..im
+        };
+        assert_eq!(flat.zscale(), None);
+    }
+
+    #[test]
+    fn test_mask() {
+        let sci = image(Bitpix::Float32, &[2, 2], &[1.0f32, f32::NAN, 3.0, 100.0]);
+        let header = Header::new(vec![
+            kw("XTENSION", KeywordValue::String("IMAGE".into())),
+            kw("BITPIX", KeywordValue::Int(16)),
+            kw("NAXIS", KeywordValue::Int(2)),
+            kw("NAXIS1", KeywordValue::Int(2)),
+            kw("NAXIS2", KeywordValue::Int(2)),
+            kw("PCOUNT", KeywordValue::Int(0)),
+            kw("GCOUNT", KeywordValue::Int(1)),
+            kw("EXTNAME", KeywordValue::String("DQ".into())),
+            kw("END", KeywordValue::None),
+        ]);
+        let raw: Vec<u8> = [0i16, 0, 4, i16::MIN]
+            .iter()
+            .flat_map(|x| x.to_be_bytes())
+            .collect();
+        let HDUData::Image(dq) = Image::from_bytes(&header, &raw).unwrap().0 else {
+            panic!("expected image");
+        };
+
+        let dq = Mask::from_dq(&dq).unwrap();
+        assert_eq!(dq.flags(), &[0, 0, 4, 0x8000]);
+        let undefined = Mask::from_undefined(&sci);
+        assert!(undefined.is_bad(&[1, 0]) && !undefined.is_bad(&[0, 1]));
+        let mask = dq.union(&undefined).unwrap();
+        assert_eq!(mask.count_bad(), 3);
+        let stats = sci.stats_masked(Some(&mask)).unwrap().unwrap();
+        assert_eq!((stats.nvalid, stats.max), (1, 1.0));
+
+        // Bit 2 is informational only
+        let mask = mask.with_bad_bits(!4);
+        assert_eq!(mask.count_bad(), 2);
+        assert_eq!(
+            sci.percentiles_masked(&[0.0, 100.0], Some(&mask)).unwrap(),
+            Some(vec![1.0, 3.0])
+        );
+        let sum = sci.add_masked(&sci, Some(&mask)).unwrap();
+        let sum = sum.pixels::<f64>().unwrap();
+        assert_eq!((sum[0], sum[2]), (2.0, 6.0));
+        assert!(sum[1].is_nan() && sum[3].is_nan());
+        assert_eq!(sci.masked(&mask).unwrap().stats().unwrap().nvalid, 2);
+
+        assert!(sci.stats_masked(Some(&Mask::new(&[4]))).is_err());
+        let mut float = sci.clone();
+        float.axes = vec![4];
+        assert!(Mask::from_dq(&float).is_err());
+    }
+
+    #[test]
+    fn test_combine() {
+        let frame = |values: [f32; 3]| image(Bitpix::Float32, &[3], &values);
+        let frames = [
+            frame([1.0, 10.0, f32::NAN]),
+            frame([2.0, 11.0, f32::NAN]),
+            frame([3.0, 10.0, f32::NAN]),
+            frame([2.0, 9.0, f32::NAN]),
+            frame([100.0, 10.0, f32::NAN]),
+        ];
+        let images: Vec<&Image> = frames.iter().collect();
+
+        let mean = Image::combine(&images, &CombineMethod::Mean).unwrap();
+        assert_eq!(mean.pixels::<f64>().unwrap()[..2], [21.6, 10.0]);
+        assert!(mean.pixels::<f64>().unwrap()[2].is_nan());
+        let median = Image::combine(&images, &CombineMethod::Median).unwrap();
+        assert_eq!(median.pixels::<f64>().unwrap()[..2], [2.0, 10.0]);
+
+        let method = CombineMethod::MedianSigmaClip {
+            low: 1.5,
+            high: 1.5,
+            iterations: 3,
+        };
+        let (clipped, masks) = Image::combine_with_rejection(&images, &method).unwrap();
+        assert_eq!(clipped.pixels::<f64>().unwrap()[0], 2.0);
+        assert_eq!(masks[4], vec![true, false, true]);
+        assert_eq!(masks[0], vec![false, false, true]);
+
+        assert!(Image::combine(&[], &CombineMethod::Mean).is_err());
+        let other = Image {
+            axes: vec![1, 3],
+            ..frames[0].clone()
+        };
+        assert!(Image::combine(&[&frames[0], &other], &CombineMethod::Mean).is_err());
+    }
+
+    #[test]
+    fn test_cutout() {
+        let wcs = WCS {
+            crpix: Some(vec![2.0, 3.0]),
+            ..Default::default()
+        };
+        let im = Image {
+            wcs: Some(wcs),
+            ..image(Bitpix::Int32, &[4, 3], &(0..12).collect::<Vec<i32>>())
+        };
+        let cut = im.cutout(&[1..3, 1..3]).unwrap();
+        assert_eq!(cut.axes, vec![2, 2]);
+        assert_eq!(cut.pixels::<i32>().unwrap(), &[5, 6, 9, 10]);
+        assert_eq!(cut.wcs.unwrap().crpix, Some(vec![1.0, 2.0]));
+        assert!(im.cutout(&[0..5, 0..1]).is_err());
+        assert!(im.cutout(&[0..1, 0..1, 0..1]).is_err());
+    }
+
+    #[test]
+    fn test_iter_indexed() {
+        let pixels: Vec<i16> = (0..6).collect();
+        let im = image(Bitpix::Int16, &[3, 2], &pixels);
+        let all: Vec<(Vec<usize>, i16)> = im.iter_indexed().collect();
+        assert_eq!(all.len(), 6);
+        assert_eq!(all[1], (vec![1, 0], 1));
+        assert_eq!(all[4], (vec![1, 1], 4));
+        for (loc, value) in &all {
+            assert_eq!(im.at::<i16>(loc), *value);
+        }
+
+        let region: Vec<(Vec<usize>, i16)> = im.enumerate_region(&[1..3, 1..2]).unwrap().collect();
+        assert_eq!(region, vec![(vec![1, 1], 4), (vec![2, 1], 5)]);
+        assert!(im.enumerate_region::<i16>(&[0..4, 0..1]).is_err());
+    }
+
+    #[test]
+    fn test_try_at() {
+        let pixels: Vec<i16> = (0..6).collect();
+        let im = image(Bitpix::Int16, &[3, 2], &pixels);
+        assert_eq!(im.try_at::<i16>(&[2, 1]).unwrap(), 5);
+        assert!(matches!(
+            im.try_at::<i16>(&[3, 0]),
+            Err(FITSError::Image(ImageError::RegionOutOfBounds(0)))
+        ));
+        assert!(matches!(
+            im.try_at::<i16>(&[1]),
+            Err(FITSError::Image(ImageError::DimensionMismatch(2, 1)))
+        ));
+        assert!(matches!(
+            im.try_at::<u16>(&[0, 0]),
+            Err(FITSError::Image(ImageError::TypeMismatch(
+                Bitpix::Int16,
+                Bitpix::Uint16
+            )))
+        ));
+    }
+
+    #[test]
+    fn test_image_data() {
+        let header = Header::new(vec![
+            kw("SIMPLE", KeywordValue::Bool(true)),
+            kw("BITPIX", KeywordValue::Int(16)),
+            kw("NAXIS", KeywordValue::Int(1)),
+            kw("NAXIS1", KeywordValue::Int(2)),
+            kw("BZERO", KeywordValue::Int(32768)),
+            kw("END", KeywordValue::None),
+        ]);
+        let raw: Vec<u8> = [i16::MIN, 0].iter().flat_map(|x| x.to_be_bytes()).collect();
+        let HDUData::Image(im) = Image::from_bytes(&header, &raw).unwrap().0 else {
+            panic!("expected image");
+        };
+        assert_eq!(im.as_u16().unwrap(), &[0, 32768]);
+        assert_eq!(im.data().unwrap(), ImageData::U16(&[0, 32768]));
+        assert!(matches!(
+            im.as_i16(),
+            Err(FITSError::Image(ImageError::TypeMismatch(..)))
+        ));
+
+        let HDUData::Image(deferred) = Image::from_bytes_deferred(&header, &raw).unwrap().0 else {
+            panic!("expected image");
+        };
+        assert!(deferred.data().is_err());
+    }
+
+    #[test]
+    fn test_read_region() {
+        let cards = [
+            "SIMPLE  =                    T",
+            "BITPIX  =                   32",
+            "NAXIS   =                    2",
+            "NAXIS1  =                    4",
+            "NAXIS2  =                    3",
+            "CRPIX1  =                  2.0",
+            "CRPIX2  =                  3.0",
+            "END",
+        ];
+        let data: Vec<u8> = (0..12i32).flat_map(|x| x.to_be_bytes()).collect();
+        let raw = hdu_bytes(&cards, &data);
+
+        let path = std::env::temp_dir().join(format!("fits_region_{}.fits", std::process::id()));
+        let path = path.to_str().unwrap();
+        std::fs::write(path, &raw).unwrap();
+        let cut = Image::read_region(path, 0, &[1..3, 1..3]).unwrap();
+        assert_eq!(cut.axes, vec![2, 2]);
+        assert_eq!(cut.pixels::<i32>().unwrap(), &[5, 6, 9, 10]);
+        assert_eq!(cut.wcs.unwrap().crpix, Some(vec![1.0, 2.0]));
+        assert!(Image::read_region(path, 0, &[0..5, 0..1]).is_err());
+        assert!(Image::read_region(path, 1, &[0..1, 0..1]).is_err());
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_slice() {
+        let im = image(Bitpix::Int16, &[2, 3, 4], &(0..24).collect::<Vec<i16>>());
+        let plane = im.slice(2, 1).unwrap();
+        assert_eq!(plane.axes, vec![2, 3]);
+        assert_eq!(plane.pixels::<i16>().unwrap(), &[6, 7, 8, 9, 10, 11]);
+        let plane = im.slice(1, 2).unwrap();
+        assert_eq!(plane.axes, vec![2, 4]);
+        assert_eq!(
+            plane.pixels::<i16>().unwrap(),
+            &[4, 5, 10, 11, 16, 17, 22, 23]
+        );
+        let plane = im.slice(0, 1).unwrap();
+        assert_eq!(plane.at::<i16>(&[2, 3]), 23);
+        assert!(im.slice(3, 0).is_err());
+        assert!(im.slice(2, 4).is_err());
+    }
+
+    #[cfg(feature = "image")]
+    #[test]
+    fn test_preview() {
+        let mut pixels: Vec<f32> = (0..200 * 100).map(|i| (i % 200) as f32).collect();
+        pixels[0] = f32::NAN;
+        let im = image(Bitpix::Float32, &[200, 100], &pixels);
+        let preview = im.to_preview(Stretch::Linear).unwrap();
+        assert_eq!(preview.dimensions(), (200, 100));
+        // First image row is at the bottom; NaN is black
+        assert_eq!(preview.get_pixel(0, 99).0, [0]);
+        assert_eq!(preview.get_pixel(1, 99).0, [0]);
+        assert_eq!(preview.get_pixel(199, 0).0, [255]);
+        assert_eq!(preview.get_pixel(100, 50).0, [128]);
+        // Log and asinh brighten the midtones
+        assert!(im.to_preview(Stretch::Log).unwrap().get_pixel(100, 50).0[0] > 200);
+        assert!(im.to_preview(Stretch::Asinh).unwrap().get_pixel(100, 50).0[0] > 128);
+        let zscale = im.to_preview(Stretch::ZScale).unwrap();
+        assert_eq!(zscale.get_pixel(199, 0).0, [255]);
+
+        let thumb = im.thumbnail(Stretch::Linear, 64).unwrap();
+        assert_eq!(thumb.dimensions(), (50, 25));
+        assert!(im.slice(1, 0).unwrap().to_preview(Stretch::Linear).is_err());
+    }
+
+    #[test]
+    fn test_cutout_sky() {
+        let wcs = WCS {
+            ctype: Some(vec!["RA---TAN".into(), "DEC--TAN".into()]),
+            crval: Some(vec![150.0, 2.0]),
+            crpix: Some(vec![50.5, 50.5]),
+            cdelt: Some(vec![-1.0 / 3600.0, 1.0 / 3600.0]),
+            ..Default::default()
+        };
+        let im = Image {
+            wcs: Some(wcs),
+            ..image(Bitpix::Int16, &[100, 100], &[0i16; 10000])
+        };
+        let cut = im.cutout_sky(150.0, 2.0, 10.0).unwrap();
+        assert_eq!(cut.axes, vec![11, 11]);
+        let world = cut.wcs.unwrap().pixel_to_world(&[4.5, 4.5]).unwrap();
+        assert!((world[0] - 150.0).abs() < 1.0e-3 / 3600.0);
+        assert!((world[1] - 2.0).abs() < 1.0e-3 / 3600.0);
+
+        // Clipped at the image edge
+        let cut = im.cutout_sky(150.0, 2.0 - 44.2 / 3600.0, 20.0).unwrap();
+        assert_eq!(cut.axes, vec![21, 16]);
+        assert!(im.cutout_sky(151.0, 2.0, 10.0).is_err());
+    }
+
+    #[test]
+    fn test_decoder() {
+        // 16-bit counts stored as their square roots
+        struct SquareRoot;
+        impl PixelDecoder for SquareRoot {
+            fn accepts(&self, header: &Header) -> bool {
+                header.get::<bool>("SQRTCNTS").unwrap_or(false)
+            }
+            fn decode(
+                &self,
+                _header: &Header,
+                rawbytes: &[u8],
+            ) -> Result<(Bitpix, Vec<u8>), FITSError> {
+                let counts: Vec<f64> = rawbytes
+                    .chunks_exact(2)
+                    .map(|b| (i16::from_be_bytes([b[0], b[1]]) as f64).powi(2))
+                    .collect();
+                Ok((Bitpix::Float64, bytemuck::cast_slice(&counts).to_vec()))
+            }
+        }
+        // Decoders are registered process-wide, so both only accept
+        // headers marked for this test; other tests running at the same
+        // time never see them
+        struct MarkedFloat16;
+        impl PixelDecoder for MarkedFloat16 {
+            fn accepts(&self, header: &Header) -> bool {
+                header.get::<bool>("F16TEST").unwrap_or(false) && Float16Decoder.accepts(header)
+            }
+            fn decode(
+                &self,
+                header: &Header,
+                rawbytes: &[u8],
+            ) -> Result<(Bitpix, Vec<u8>), FITSError> {
+                Float16Decoder.decode(header, rawbytes)
+            }
+        }
+        let sqrt = Image::register_decoder(SquareRoot);
+        let f16 = Image::register_decoder(MarkedFloat16);
+
+        let header = |bitpix: i64, extra: Keyword| {
+            Header::new(vec![
+                kw("SIMPLE", KeywordValue::Bool(true)),
+                kw("BITPIX", KeywordValue::Int(bitpix)),
+                kw("NAXIS", KeywordValue::Int(1)),
+                kw("NAXIS1", KeywordValue::Int(3)),
+                kw("F16TEST", KeywordValue::Bool(true)),
+                extra,
+                kw("END", KeywordValue::None),
+            ])
+        };
+        let raw: Vec<u8> = [0x3c00u16, 0xc000, 0x3800]
+            .iter()
+            .flat_map(|x| x.to_be_bytes())
+            .collect();
+        let (data, nbytes) =
+            Image::from_bytes(&header(-16, kw("BZERO", KeywordValue::Int(1))), &raw).unwrap();
+        assert_eq!(nbytes, 6);
+        let HDUData::Image(im) = data else {
+            panic!("expected image");
+        };
+        assert_eq!(im.pixels::<f32>().unwrap(), &[2.0, -1.0, 1.5]);
+
+        let raw: Vec<u8> = [3i16, 4, 5].iter().flat_map(|x| x.to_be_bytes()).collect();
+        let (data, _) =
+            Image::from_bytes(&header(16, kw("SQRTCNTS", KeywordValue::Bool(true))), &raw).unwrap();
+        let HDUData::Image(im) = data else {
+            panic!("expected image");
+        };
+        assert_eq!(im.pixels::<f64>().unwrap(), &[9.0, 16.0, 25.0]);
+        // Other 16-bit images are decoded as usual
+        let (data, _) =
+            Image::from_bytes(&header(16, kw("SQRTCNTS", KeywordValue::Bool(false))), &raw)
+                .unwrap();
+        let HDUData::Image(im) = data else {
+            panic!("expected image");
+        };
+        assert_eq!(im.pixels::<i16>().unwrap(), &[3, 4, 5]);
+
+        // The data unit size used to skip an HDU comes from the decoder
+        let header = header(-16, kw("BZERO", KeywordValue::Int(1)));
+        assert_eq!(crate::hdu::data_unit_size(&header).unwrap(), 6);
+
+        assert!(Image::unregister_decoder(f16));
+        assert!(Image::unregister_decoder(sqrt));
+        assert!(!Image::unregister_decoder(f16));
+        assert!(Image::from_bytes(&header, &raw).is_err());
     }
 }
@@ -0,0 +1,131 @@
+//! Grayscale previews of images, enabled by the `image` feature
+
+use ::image::GrayImage;
+
+use super::stats::{sorted_percentile, zscale};
+use crate::FITSError;
+use crate::Image;
+use crate::ImageError;
+
+/// Mapping of pixel values to display brightness
+///
+/// `Linear`, `Log` and `Asinh` map the range between the 0.5 and 99.5
+/// percentiles of the valid pixels; `ZScale` maps the range chosen by
+/// the IRAF zscale algorithm linearly
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Stretch {
+    Linear,
+    /// `log10(1000 x + 1) / log10(1001)`, as in SAOImage DS9
+    Log,
+    /// `asinh(10 x) / asinh(10)`, which brings out faint features
+    Asinh,
+    /// Linear over a range around the median, robust to bright stars
+    ZScale,
+}
+
+impl Image {
+    /// Render the image as an 8-bit grayscale preview
+    ///
+    /// The first plane of images with more than two axes is used.  Rows
+    /// are flipped so that the first image row is at the bottom, as
+    /// FITS viewers display it.  Undefined (BLANK or NaN) pixels are
+    /// black
+    ///
+    /// # Arguments
+    ///
+    /// * `stretch` - Mapping of pixel values to brightness
+    ///
+    /// # Returns
+    ///
+    /// The preview, the same size as the image; save it with `save`
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use fits::{HDUData, Stretch, FITS};
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// # let fits = FITS::from_file("image.fits")?;
+    /// # let HDUData::Image(image) = &fits[0].data else { return Ok(()) };
+    /// image.to_preview(Stretch::ZScale)?.save("preview.png")?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    pub fn to_preview(&self, stretch: Stretch) -> Result<GrayImage, FITSError> {
+        self.thumbnail(stretch, u32::MAX)
+    }
+
+    /// Render the image as an 8-bit grayscale preview no larger than
+    /// a given size
+    ///
+    /// The image is reduced by averaging blocks of pixels, by the
+    /// smallest integer factor that fits; see `to_preview`
+    ///
+    /// # Arguments
+    ///
+    /// * `stretch` - Mapping of pixel values to brightness
+    /// * `max_size` - Maximum width and height of the preview
+    ///
+    pub fn thumbnail(&self, stretch: Stretch, max_size: u32) -> Result<GrayImage, FITSError> {
+        if self.ndims() < 2 {
+            return Err(FITSError::from(ImageError::DimensionMismatch(
+                2,
+                self.ndims(),
+            )));
+        }
+        let (width, height) = (self.axes[0], self.axes[1]);
+        let factor = width.max(height).div_ceil(max_size.max(1) as usize).max(1);
+        let (w, h) = (width.div_ceil(factor), height.div_ceil(factor));
+
+        // Block averages of valid pixels
+        let mut sums = vec![(0.0, 0usize); w * h];
+        for y in 0..height {
+            for x in 0..width {
+                let value = self.physical_value(y * width + x);
+                if !value.is_nan() {
+                    let block = &mut sums[(y / factor) * w + x / factor];
+                    block.0 += value;
+                    block.1 += 1;
+                }
+            }
+        }
+        let values: Vec<f64> = sums
+            .iter()
+            .map(|&(sum, n)| match n {
+                0 => f64::NAN,
+                n => sum / n as f64,
+            })
+            .collect();
+
+        let mut sorted: Vec<f64> = values.iter().copied().filter(|v| !v.is_nan()).collect();
+        sorted.sort_by(|a, b| a.total_cmp(b));
+        let (low, high) = match (sorted.is_empty(), stretch) {
+            (true, _) => (0.0, 1.0),
+            (false, Stretch::ZScale) => zscale(&sorted),
+            (false, _) => (
+                sorted_percentile(&sorted, 0.5),
+                sorted_percentile(&sorted, 99.5),
+            ),
+        };
+        let range = match high > low {
+            true => high - low,
+            false => 1.0,
+        };
+
+        let mut preview = GrayImage::new(w as u32, h as u32);
+        for (i, value) in values.into_iter().enumerate() {
+            let x = ((value - low) / range).clamp(0.0, 1.0);
+            let y = match stretch {
+                Stretch::Linear | Stretch::ZScale => x,
+                Stretch::Log => (1000.0 * x + 1.0).log10() / 1001f64.log10(),
+                Stretch::Asinh => (10.0 * x).asinh() / 10f64.asinh(),
+            };
+            let level = match value.is_nan() {
+                true => 0,
+                false => (y * 255.0).round() as u8,
+            };
+            preview.put_pixel((i % w) as u32, (h - 1 - i / w) as u32, [level].into());
+        }
+        Ok(preview)
+    }
+}
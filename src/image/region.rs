@@ -0,0 +1,341 @@
+use crate::fits::update::seek_hdu;
+use crate::FITSError;
+use crate::HDUData;
+use crate::HeaderError;
+use crate::Image;
+use crate::ImageError;
+use crate::KeywordValue;
+use crate::WCSError;
+use crate::WCS;
+
+use std::io::{Read, Seek, SeekFrom};
+use std::ops::Range;
+
+/// Shift the reference pixel of a WCS to the origin of a sub-region
+fn shift_wcs(wcs: &mut WCS, ranges: &[Range<usize>]) {
+    if let Some(crpix) = wcs.crpix.as_mut() {
+        for (c, r) in crpix.iter_mut().zip(ranges.iter()) {
+            *c -= r.start as f64;
+        }
+    }
+}
+
+/// Check a pixel region against image dimensions
+pub(super) fn check_ranges(axes: &[usize], ranges: &[Range<usize>]) -> Result<(), FITSError> {
+    if ranges.len() != axes.len() {
+        return Err(FITSError::from(ImageError::DimensionMismatch(
+            axes.len(),
+            ranges.len(),
+        )));
+    }
+    for (ix, r) in ranges.iter().enumerate() {
+        if r.start >= r.end || r.end > axes[ix] {
+            return Err(FITSError::from(ImageError::RegionOutOfBounds(ix)));
+        }
+    }
+    Ok(())
+}
+
+impl Image {
+    /// World coordinates of the image corners, from the primary WCS
+    ///
+    /// See `WCS::footprint`
+    ///
+    /// # Returns
+    ///
+    /// Celestial (longitude, latitude) of the four corners in degrees
+    ///
+    pub fn footprint(&self) -> Result<Vec<(f64, f64)>, FITSError> {
+        self.wcs
+            .as_ref()
+            .ok_or(ImageError::NoWCS)?
+            .footprint(&self.axes)
+    }
+
+    /// Test whether a celestial position falls within the image,
+    /// using the primary WCS
+    ///
+    /// # Arguments
+    ///
+    /// * `ra` - Longitude (e.g. right ascension) in degrees
+    /// * `dec` - Latitude (e.g. declination) in degrees
+    ///
+    /// # Returns
+    ///
+    /// True if the position is within the image; false if it is
+    /// outside or the image has no celestial WCS
+    ///
+    pub fn contains(&self, ra: f64, dec: f64) -> bool {
+        self.wcs
+            .as_ref()
+            .is_some_and(|w| w.contains(&self.axes, ra, dec))
+    }
+
+    /// Read a rectangular sub-region of an image directly from a file
+    ///
+    /// Only the headers of the file and the bytes within the region are
+    /// read: one contiguous stripe along the first axis for each
+    /// combination of the remaining axes.  The result is the same as
+    /// reading the whole image and calling `cutout`, without holding
+    /// the full array in memory
+    ///
+    /// # Arguments
+    ///
+    /// * `file` - Path of the FITS file
+    /// * `index` - Position of the image HDU in the file; 0 is the primary HDU
+    /// * `ranges` - Zero-based pixel range along each axis
+    ///
+    /// # Returns
+    ///
+    /// New image containing the sub-region
+    ///
+    pub fn read_region(
+        file: &str,
+        index: usize,
+        ranges: &[Range<usize>],
+    ) -> Result<Image, FITSError> {
+        let mut file = std::fs::File::open(file)?;
+        let (header, offset, nbytes) = seek_hdu(&mut file, index)?;
+        let is_image = match header.first().map(|kw| (kw.name.as_str(), &kw.value)) {
+            Some(("SIMPLE", _)) => true,
+            Some(("XTENSION", KeywordValue::String(x))) => x.trim_end() == "IMAGE",
+            _ => false,
+        };
+        let axes = header.naxes().unwrap_or_default();
+        if !is_image || axes.is_empty() {
+            return Err(FITSError::from(HeaderError::GenericError(
+                "HDU has no image data".to_string(),
+            )));
+        }
+        check_ranges(&axes, ranges)?;
+        let bitsize = header
+            .bitpix()
+            .ok_or(FITSError::from(HeaderError::GenericError(
+                "Invalid BITPIX value".to_string(),
+            )))?
+            .size();
+        let start = offset + nbytes as u64;
+
+        let rowbytes = ranges[0].len() * bitsize;
+        let nrows = ranges.iter().skip(1).map(|r| r.len()).product::<usize>();
+        let mut rawbytes = vec![0u8; rowbytes * nrows];
+        let mut loc: Vec<usize> = ranges.iter().map(|r| r.start).collect();
+        for row in rawbytes.chunks_exact_mut(rowbytes) {
+            let mut pixel = 0;
+            for ix in (0..loc.len()).rev() {
+                pixel = pixel * axes[ix] + loc[ix];
+            }
+            file.seek(SeekFrom::Start(start + (pixel * bitsize) as u64))?;
+            file.read_exact(row)?;
+            for ix in 1..loc.len() {
+                loc[ix] += 1;
+                if loc[ix] < ranges[ix].end {
+                    break;
+                }
+                loc[ix] = ranges[ix].start;
+            }
+        }
+
+        // Parse the region as an image of its own size, so scaling and
+        // unsigned conversion are applied as for a full read
+        let mut region = header.clone();
+        for (ix, r) in ranges.iter().enumerate() {
+            region.set(
+                &format!("NAXIS{}", ix + 1),
+                KeywordValue::Int(r.len() as i64),
+            );
+        }
+        let (data, _) = Image::from_bytes(&region, &rawbytes)?;
+        let HDUData::Image(mut image) = data else {
+            return Err(FITSError::from(HeaderError::GenericError(
+                "HDU has no image data".to_string(),
+            )));
+        };
+        if let Some(w) = image.wcs.as_mut() {
+            shift_wcs(w, ranges);
+        }
+        image
+            .alt_wcs
+            .values_mut()
+            .for_each(|w| shift_wcs(w, ranges));
+        Ok(*image)
+    }
+
+    /// Extract a rectangular sub-region of the image
+    ///
+    /// Only the bytes within the region are copied.  The WCS, if present,
+    /// is updated so that the reference pixel (CRPIXn) refers to the
+    /// same world coordinate in the cutout
+    ///
+    /// # Arguments
+    ///
+    /// * `ranges` - Zero-based pixel range along each axis
+    ///
+    /// # Returns
+    ///
+    /// New image containing the sub-region
+    ///
+    pub fn cutout(&self, ranges: &[Range<usize>]) -> Result<Image, FITSError> {
+        check_ranges(&self.axes, ranges)?;
+        let axes: Vec<usize> = ranges.iter().map(|r| r.len()).collect();
+        let bitsize = self.pixeltype.size();
+        let rowbytes = axes[0] * bitsize;
+        let nrows = axes.iter().skip(1).product::<usize>();
+
+        // Copy contiguous runs along the first (fastest) axis,
+        // stepping through all combinations of the remaining axes
+        let mut rawbytes = Vec::with_capacity(rowbytes * nrows);
+        let mut loc: Vec<usize> = ranges.iter().map(|r| r.start).collect();
+        for _ in 0..nrows {
+            let offset = self.index(&loc) * bitsize;
+            rawbytes.extend_from_slice(&self.rawbytes[offset..offset + rowbytes]);
+            for ix in 1..loc.len() {
+                loc[ix] += 1;
+                if loc[ix] < ranges[ix].end {
+                    break;
+                }
+                loc[ix] = ranges[ix].start;
+            }
+        }
+
+        let mut wcs = self.wcs.clone();
+        if let Some(w) = wcs.as_mut() {
+            shift_wcs(w, ranges);
+        }
+        let mut alt_wcs = self.alt_wcs.clone();
+        alt_wcs.values_mut().for_each(|w| shift_wcs(w, ranges));
+
+        Ok(Image {
+            pixeltype: self.pixeltype,
+            axes,
+            rawbytes,
+            bscale: self.bscale,
+            bzero: self.bzero,
+            blank: self.blank,
+            bunit: self.bunit.clone(),
+            wcs,
+            alt_wcs,
+            native: self.native,
+        })
+    }
+
+    /// Extract a square sub-region of the image centered on a sky position
+    ///
+    /// The center is converted to pixel coordinates with the primary WCS,
+    /// and the size converted to pixels using the mean pixel scale at the
+    /// reference point.  The region is clipped to the image bounds, and
+    /// spans all pixels along any non-celestial axes.  See `cutout` for
+    /// how the WCS is updated
+    ///
+    /// # Arguments
+    ///
+    /// * `ra` - Longitude (e.g. right ascension) of the center, in degrees
+    /// * `dec` - Latitude (e.g. declination) of the center, in degrees
+    /// * `size_arcsec` - Width of the region, in arcseconds
+    ///
+    /// # Returns
+    ///
+    /// New image containing the sub-region
+    ///
+    pub fn cutout_sky(&self, ra: f64, dec: f64, size_arcsec: f64) -> Result<Image, FITSError> {
+        let wcs = self.wcs.as_ref().ok_or(ImageError::NoWCS)?;
+        let (lon, lat) = wcs
+            .celestial_axes()
+            .ok_or(WCSError::MissingKeyword("celestial CTYPEn".to_string()))?;
+        let n = wcs.naxes();
+        if n != self.ndims() {
+            return Err(FITSError::from(WCSError::DimensionMismatch(
+                self.ndims(),
+                n,
+            )));
+        }
+
+        let mut world = wcs.pixel_to_world(&vec![0.0; n])?;
+        world[lon] = ra;
+        world[lat] = dec;
+        let center = wcs.world_to_pixel(&world)?;
+
+        // Mean pixel scale in degrees, from the determinant of the
+        // celestial part of the linear transformation
+        let m = wcs.linear_matrix();
+        let det = m[(lon, lon)] * m[(lat, lat)] - m[(lon, lat)] * m[(lat, lon)];
+        if det == 0.0 {
+            return Err(FITSError::from(WCSError::SingularMatrix));
+        }
+        let half = size_arcsec / 3600.0 / det.abs().sqrt() / 2.0;
+
+        let ranges = self
+            .axes
+            .iter()
+            .enumerate()
+            .map(|(ix, &len)| {
+                if ix != lon && ix != lat {
+                    return Ok(0..len);
+                }
+                let start = (center[ix] - half).round().max(0.0);
+                let end = ((center[ix] + half).round() + 1.0).min(len as f64);
+                if end <= start {
+                    return Err(ImageError::RegionOutOfBounds(ix));
+                }
+                Ok(start as usize..end as usize)
+            })
+            .collect::<Result<Vec<Range<usize>>, ImageError>>()?;
+        self.cutout(&ranges)
+    }
+
+    /// Extract a lower-dimensional slice of the image at a fixed index along one axis
+    ///
+    /// For example, slicing a spectral cube along the third axis
+    /// returns a single channel plane.  The WCS, if present, has
+    /// the sliced axis removed
+    ///
+    /// # Arguments
+    ///
+    /// * `axis` - Zero-based axis to slice along
+    /// * `index` - Zero-based pixel index along `axis`
+    ///
+    /// # Returns
+    ///
+    /// New image with one fewer axis
+    ///
+    pub fn slice(&self, axis: usize, index: usize) -> Result<Image, FITSError> {
+        if axis >= self.ndims() {
+            return Err(FITSError::from(ImageError::InvalidAxis(axis, self.ndims())));
+        }
+        if index >= self.axes[axis] {
+            return Err(FITSError::from(ImageError::RegionOutOfBounds(axis)));
+        }
+        // The first axis increments most rapidly, so each slice
+        // consists of contiguous blocks spanning all faster axes
+        let bitsize = self.pixeltype.size();
+        let blockbytes = self.axes[0..axis].iter().product::<usize>() * bitsize;
+        let nblocks = self.axes[axis + 1..].iter().product::<usize>();
+        let mut rawbytes = Vec::with_capacity(blockbytes * nblocks);
+        for block in 0..nblocks {
+            let offset = (block * self.axes[axis] + index) * blockbytes;
+            rawbytes.extend_from_slice(&self.rawbytes[offset..offset + blockbytes]);
+        }
+
+        let mut axes = self.axes.clone();
+        axes.remove(axis);
+        let mut wcs = self.wcs.clone();
+        if let Some(w) = wcs.as_mut() {
+            w.remove_axis(axis);
+        }
+        let mut alt_wcs = self.alt_wcs.clone();
+        alt_wcs.values_mut().for_each(|w| w.remove_axis(axis));
+
+        Ok(Image {
+            pixeltype: self.pixeltype,
+            axes,
+            rawbytes,
+            bscale: self.bscale,
+            bzero: self.bzero,
+            blank: self.blank,
+            bunit: self.bunit.clone(),
+            wcs,
+            alt_wcs,
+            native: self.native,
+        })
+    }
+}
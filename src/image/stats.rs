@@ -0,0 +1,319 @@
+use super::Mask;
+use crate::FITSError;
+use crate::Image;
+
+/// Summary statistics over the valid pixels of an image
+///
+/// Statistics are computed on physical values (see `Image::physical_pixels`),
+/// with undefined (BLANK or NaN) pixels excluded
+#[derive(Clone, Debug, PartialEq)]
+pub struct ImageStats {
+    /// Total number of pixels
+    pub npixels: usize,
+    /// Number of valid pixels used in the statistics
+    pub nvalid: usize,
+    pub min: f64,
+    pub max: f64,
+    pub mean: f64,
+    pub median: f64,
+    /// Population standard deviation
+    pub stddev: f64,
+}
+
+/// Histogram of valid pixel values
+#[derive(Clone, Debug, PartialEq)]
+pub struct Histogram {
+    /// Bin edges; there is one more edge than there are bins
+    pub edges: Vec<f64>,
+    /// Number of pixels in each bin
+    pub counts: Vec<usize>,
+}
+
+/// Percentile of sorted data, linearly interpolating between closest ranks
+pub(super) fn sorted_percentile(sorted: &[f64], p: f64) -> f64 {
+    let rank = (p.clamp(0.0, 100.0) / 100.0) * (sorted.len() - 1) as f64;
+    let lo = rank.floor() as usize;
+    let hi = rank.ceil() as usize;
+    sorted[lo] + (sorted[hi] - sorted[lo]) * (rank - lo as f64)
+}
+
+/// Number of pixels sampled by zscale
+const ZSCALE_SAMPLES: usize = 1000;
+/// Contrast of zscale; smaller values give a wider range
+const ZSCALE_CONTRAST: f64 = 0.25;
+
+/// Display range of the IRAF zscale algorithm
+///
+/// A line is fit to the sorted sample with iterative rejection of
+/// outliers; the range is the median extended by the slope, divided by
+/// the contrast, over the sample
+///
+/// # Arguments
+///
+/// * `sorted` - Valid pixel values, sorted in increasing order
+///
+pub(super) fn zscale(sorted: &[f64]) -> (f64, f64) {
+    let step = sorted.len().div_ceil(ZSCALE_SAMPLES);
+    let sample: Vec<f64> = sorted.iter().step_by(step).copied().collect();
+    let n = sample.len();
+    let (min, max) = (sample[0], sample[n - 1]);
+    let median = sample[n / 2];
+    if n < 5 {
+        return (min, max);
+    }
+
+    let mut keep = vec![true; n];
+    let mut slope = 0.0;
+    for _ in 0..5 {
+        let points = || (0..n).filter(|&i| keep[i]).map(|i| (i as f64, sample[i]));
+        let count = points().count() as f64;
+        if count < n as f64 / 2.0 {
+            break;
+        }
+        let (sx, sy) = points().fold((0.0, 0.0), |(sx, sy), (x, y)| (sx + x, sy + y));
+        let (mx, my) = (sx / count, sy / count);
+        let (sxy, sxx) = points().fold((0.0, 0.0), |(sxy, sxx), (x, y)| {
+            (sxy + (x - mx) * (y - my), sxx + (x - mx) * (x - mx))
+        });
+        slope = sxy / sxx;
+        let residual = |i: usize| sample[i] - (my + slope * (i as f64 - mx));
+        let sigma = (points()
+            .map(|(x, _)| residual(x as usize).powi(2))
+            .sum::<f64>()
+            / count)
+            .sqrt();
+        let mut changed = false;
+        for (i, k) in keep.iter_mut().enumerate() {
+            let reject = residual(i).abs() > 2.5 * sigma;
+            changed |= *k == reject;
+            *k = !reject;
+        }
+        if !changed {
+            break;
+        }
+    }
+    let slope = slope / ZSCALE_CONTRAST;
+    let center = (n / 2) as f64;
+    (
+        min.max(median - center * slope),
+        max.min(median + (n as f64 - 1.0 - center) * slope),
+    )
+}
+
+impl Image {
+    /// Physical values of valid pixels, excluding those bad in an
+    /// optional mask
+    fn valid(&self, mask: Option<&Mask>) -> Vec<f64> {
+        self.masked_pixels(mask)
+            .into_iter()
+            .filter(|x| !x.is_nan())
+            .collect()
+    }
+
+    /// Physical values of valid pixels, sorted in increasing order
+    fn sorted_valid(&self, mask: Option<&Mask>) -> Vec<f64> {
+        let mut v = self.valid(mask);
+        v.sort_by(|a, b| a.total_cmp(b));
+        v
+    }
+
+    /// Compute summary statistics over valid pixels
+    ///
+    /// # Returns
+    ///
+    /// The statistics, or None if the image has no valid pixels
+    ///
+    pub fn stats(&self) -> Option<ImageStats> {
+        self.stats_of(None)
+    }
+
+    /// Compute summary statistics over valid pixels that are not bad in
+    /// a mask
+    ///
+    /// # Arguments
+    ///
+    /// * `mask` - Pixels to exclude, if any
+    ///
+    /// # Returns
+    ///
+    /// The statistics, None if no pixels remain, or an error if the
+    /// mask and image shapes differ.  `nvalid` counts only the pixels used
+    ///
+    pub fn stats_masked(&self, mask: Option<&Mask>) -> Result<Option<ImageStats>, FITSError> {
+        self.check_mask(mask)?;
+        Ok(self.stats_of(mask))
+    }
+
+    fn stats_of(&self, mask: Option<&Mask>) -> Option<ImageStats> {
+        let sorted = self.sorted_valid(mask);
+        if sorted.is_empty() {
+            return None;
+        }
+        let n = sorted.len() as f64;
+        let mean = sorted.iter().sum::<f64>() / n;
+        let var = sorted.iter().map(|x| (x - mean) * (x - mean)).sum::<f64>() / n;
+        Some(ImageStats {
+            npixels: self.npixels(),
+            nvalid: sorted.len(),
+            min: sorted[0],
+            max: sorted[sorted.len() - 1],
+            mean,
+            median: sorted_percentile(&sorted, 50.0),
+            stddev: var.sqrt(),
+        })
+    }
+
+    /// Percentile of valid pixel values
+    ///
+    /// # Arguments
+    ///
+    /// * `p` - Percentile, in range [0, 100]
+    ///
+    /// # Returns
+    ///
+    /// The value, linearly interpolated between closest ranks,
+    /// or None if the image has no valid pixels
+    ///
+    pub fn percentile(&self, p: f64) -> Option<f64> {
+        self.percentiles(&[p]).map(|v| v[0])
+    }
+
+    /// Multiple percentiles of valid pixel values
+    ///
+    /// This sorts the pixel values only once, so is preferred
+    /// over repeated calls to `percentile`
+    ///
+    /// # Arguments
+    ///
+    /// * `ps` - Percentiles, each in range [0, 100]
+    ///
+    /// # Returns
+    ///
+    /// The values, or None if the image has no valid pixels
+    ///
+    pub fn percentiles(&self, ps: &[f64]) -> Option<Vec<f64>> {
+        self.percentiles_of(ps, None)
+    }
+
+    /// Percentiles of valid pixel values that are not bad in a mask
+    ///
+    /// # Arguments
+    ///
+    /// * `ps` - Percentiles, each in range [0, 100]
+    /// * `mask` - Pixels to exclude, if any
+    ///
+    /// # Returns
+    ///
+    /// The values, None if no pixels remain, or an error if the mask
+    /// and image shapes differ
+    ///
+    pub fn percentiles_masked(
+        &self,
+        ps: &[f64],
+        mask: Option<&Mask>,
+    ) -> Result<Option<Vec<f64>>, FITSError> {
+        self.check_mask(mask)?;
+        Ok(self.percentiles_of(ps, mask))
+    }
+
+    fn percentiles_of(&self, ps: &[f64], mask: Option<&Mask>) -> Option<Vec<f64>> {
+        let sorted = self.sorted_valid(mask);
+        if sorted.is_empty() {
+            return None;
+        }
+        Some(ps.iter().map(|p| sorted_percentile(&sorted, *p)).collect())
+    }
+
+    /// Histogram of valid pixel values
+    ///
+    /// # Arguments
+    ///
+    /// * `nbins` - Number of equal-width bins spanning the range of values
+    ///
+    /// # Returns
+    ///
+    /// The histogram, or None if the image has no valid pixels or `nbins` is zero
+    ///
+    pub fn histogram(&self, nbins: usize) -> Option<Histogram> {
+        self.histogram_of(nbins, None)
+    }
+
+    /// Histogram of valid pixel values that are not bad in a mask
+    ///
+    /// # Arguments
+    ///
+    /// * `nbins` - Number of equal-width bins spanning the range of values
+    /// * `mask` - Pixels to exclude, if any
+    ///
+    /// # Returns
+    ///
+    /// The histogram, None if no pixels remain or `nbins` is zero, or
+    /// an error if the mask and image shapes differ
+    ///
+    pub fn histogram_masked(
+        &self,
+        nbins: usize,
+        mask: Option<&Mask>,
+    ) -> Result<Option<Histogram>, FITSError> {
+        self.check_mask(mask)?;
+        Ok(self.histogram_of(nbins, mask))
+    }
+
+    fn histogram_of(&self, nbins: usize, mask: Option<&Mask>) -> Option<Histogram> {
+        let values = self.valid(mask);
+        if values.is_empty() || nbins == 0 {
+            return None;
+        }
+        let min = values.iter().cloned().fold(f64::INFINITY, f64::min);
+        let max = values.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
+        let width = match max > min {
+            true => (max - min) / nbins as f64,
+            false => 1.0,
+        };
+        let mut counts = vec![0; nbins];
+        for v in values {
+            // Maximum value is included in the last bin
+            let bin = (((v - min) / width) as usize).min(nbins - 1);
+            counts[bin] += 1;
+        }
+        Some(Histogram {
+            edges: (0..=nbins).map(|i| min + width * i as f64).collect(),
+            counts,
+        })
+    }
+
+    /// Display range of the IRAF zscale algorithm
+    ///
+    /// The range brackets the median closely enough to show faint
+    /// structure while ignoring bright stars and bad pixels; it is the
+    /// `Stretch::ZScale` range of previews
+    ///
+    /// # Returns
+    ///
+    /// (vmin, vmax), or None if the image has no valid pixels
+    ///
+    pub fn zscale(&self) -> Option<(f64, f64)> {
+        let sorted = self.sorted_valid(None);
+        match sorted.is_empty() {
+            true => None,
+            false => Some(zscale(&sorted)),
+        }
+    }
+
+    /// Display range holding a central fraction of the valid pixels
+    ///
+    /// # Arguments
+    ///
+    /// * `percent` - Percentage of pixels within the range, e.g. 99.5
+    ///   for the range from the 0.25 to the 99.75 percentile
+    ///
+    /// # Returns
+    ///
+    /// (vmin, vmax), or None if the image has no valid pixels
+    ///
+    pub fn percentile_interval(&self, percent: f64) -> Option<(f64, f64)> {
+        let half = percent.clamp(0.0, 100.0) / 2.0;
+        self.percentiles(&[50.0 - half, 50.0 + half])
+            .map(|v| (v[0], v[1]))
+    }
+}
@@ -19,9 +19,13 @@
 //!
 //! The FITS structure contains an array of HDUs.  These HDUs can be indexed in the FITS structure
 //! itself.  For example, to access the first HDU in a FITS file, you can use the following syntax:
-//! ```
-//! let fits = FITS::from_file("samp/WFPC2u5780205r_c0fx.fits");
-//! let hdu = fits.hdus[0];
+//! ```no_run
+//! # use fits::FITS;
+//! # fn main() -> Result<(), fits::FITSError> {
+//! let fits = FITS::from_file("samp/WFPC2u5780205r_c0fx.fits")?;
+//! let hdu = &fits[0];
+//! # Ok(())
+//! # }
 //! ```
 //!
 //! ### HDU Structure
@@ -62,8 +66,8 @@
 //!
 //! ## Example
 //!
-//! ```
-//! use fits_rs::FITS;
+//! ```no_run
+//! use fits::FITS;
 //! let fits = FITS::from_file("samp/WFPC2u5780205r_c0fx.fits");
 //!     match fits {
 //!         Ok(fits) => {
@@ -77,26 +81,112 @@
 //! ```
 //!
 
+mod angle;
+mod bintable;
+#[cfg(feature = "num-complex")]
+mod complex;
 mod errors;
 mod fits;
+mod gti;
 mod hdu;
 mod header;
+pub mod healpix;
 mod image;
+pub mod photometry;
+mod sdfits;
 mod table;
+mod tablelike;
+mod tableprint;
+#[cfg(test)]
+pub(crate) mod testutil;
 mod types;
+mod units;
+#[cfg(feature = "wasm")]
+mod wasm;
 mod wcs;
 
 pub(crate) use header::FITSBlock;
 
+pub use angle::Angle;
+pub use bintable::ArrayStyle;
+pub use bintable::BinTable;
+pub use bintable::BinTableValue;
+pub use bintable::ColumnStats;
+pub use bintable::CsvOptions;
+pub use bintable::FromTableValue;
+pub use bintable::SortOrder;
+pub use bintable::StringOptions;
+pub use bintable::TDisp;
+pub use bintable::TDispType;
+pub use bintable::TForm;
+pub use bintable::TFormType;
+pub use errors::FITSError;
 pub use errors::HeaderError;
+pub use errors::ImageError;
+pub use errors::TableError;
+pub use errors::UnitError;
+pub use errors::WCSError;
 pub use fits::*;
+pub use gti::Gti;
+pub use hdu::HDULocation;
 pub use hdu::HDU;
+#[cfg(feature = "serde")]
+pub use header::serde_cards;
+pub use header::DuplicatePolicy;
+pub use header::FromKeywordValue;
 pub use header::Header;
 pub use header::Keyword;
+pub use header::KeywordInfo;
+pub use header::KeywordType;
 pub use header::KeywordValue;
+#[cfg(feature = "time")]
+pub use header::{datetime_to_mjd, format_datetime, parse_datetime};
+pub use healpix::{HealpixMap, HealpixOrdering};
+pub use image::CombineMethod;
+pub use image::DecoderId;
+pub use image::Float16Decoder;
+pub use image::Histogram;
 pub use image::Image;
+pub use image::ImageData;
+pub use image::ImageStats;
+pub use image::Mask;
+pub use image::PixelDecoder;
+#[cfg(feature = "image")]
+pub use image::Stretch;
+#[cfg(feature = "num-complex")]
+pub use num_complex::Complex;
+pub use sdfits::{SdFits, Spectrum};
+pub use table::AsciiTForm;
+pub use table::AsciiTFormType;
 pub use table::Table;
+pub use tablelike::TableLike;
+pub use tableprint::PrintOptions;
 pub use types::*;
+pub use units::Unit;
+#[cfg(feature = "wasm")]
+pub use wasm::WasmFITS;
+pub use wcs::CelestialFrame;
+pub use wcs::Projection;
+pub use wcs::ProjectionParams;
+pub use wcs::Sip;
+pub use wcs::SpectralType;
+pub use wcs::TabularAxis;
 pub use wcs::WCS;
 
 pub type Matrix = nalgebra::DMatrix<f64>;
+
+// Parsed files can be shared across threads; fail to compile if a
+// non-thread-safe type (e.g. `Rc`) ever creeps into these structures
+const _: () = {
+    const fn assert_send_sync<T: Send + Sync>() {}
+    assert_send_sync::<FITS>();
+    assert_send_sync::<HDU>();
+    assert_send_sync::<HDUData>();
+    assert_send_sync::<Header>();
+    assert_send_sync::<BinTable>();
+    assert_send_sync::<TFormType>();
+    assert_send_sync::<Table>();
+    assert_send_sync::<Image>();
+    assert_send_sync::<WCS>();
+    assert_send_sync::<FITSError>();
+};
@@ -0,0 +1,329 @@
+//! Source detection and aperture photometry on two-dimensional images
+//!
+//! Sources are groups of touching pixels (including diagonal
+//! neighbours) more than a chosen number of standard deviations above
+//! the background, which is estimated by iteratively clipping outliers
+//! from the pixel values.  Aperture photometry sums the pixels whose
+//! centers fall within a circle, less a local background measured in a
+//! surrounding annulus.
+//!
+//! Pixel coordinates are zero-based, as for `WCS::pixel_to_world`; sky
+//! positions are given when the image has a celestial WCS
+
+use crate::Angle;
+use crate::FITSError;
+use crate::Image;
+use crate::ImageError;
+
+/// Clipping threshold, in standard deviations, of background estimates
+const CLIP_SIGMA: f64 = 3.0;
+
+/// Maximum clipping passes of background estimates
+const CLIP_ITERATIONS: usize = 10;
+
+/// A source found by `detect_sources`
+#[derive(Clone, Debug, PartialEq)]
+pub struct Source {
+    /// Flux-weighted centroid, zero-based pixel coordinates
+    pub x: f64,
+    pub y: f64,
+    /// Sum of the background-subtracted pixel values
+    pub flux: f64,
+    /// Largest background-subtracted pixel value
+    pub peak: f64,
+    /// Number of pixels above the threshold
+    pub npixels: usize,
+    /// Celestial coordinates of the centroid, if the image has a
+    /// celestial WCS
+    pub sky: Option<(Angle, Angle)>,
+}
+
+/// Result of `aperture_photometry`
+#[derive(Clone, Debug, PartialEq)]
+pub struct ApertureFlux {
+    /// Center of the aperture, zero-based pixel coordinates
+    pub x: f64,
+    pub y: f64,
+    /// Sum over the aperture less `background` times `npixels`
+    pub flux: f64,
+    /// Background per pixel: clipped median of the annulus, or 0 if
+    /// no annulus was given.  NaN if the annulus has no valid pixels
+    pub background: f64,
+    /// Number of valid pixels in the aperture
+    pub npixels: usize,
+    /// Celestial coordinates of the center, if the image has a
+    /// celestial WCS
+    pub sky: Option<(Angle, Angle)>,
+}
+
+/// Median of values, which are reordered
+fn median(values: &mut [f64]) -> f64 {
+    values.sort_by(f64::total_cmp);
+    let n = values.len();
+    match n % 2 {
+        0 => 0.5 * (values[n / 2 - 1] + values[n / 2]),
+        _ => values[n / 2],
+    }
+}
+
+/// Background level and noise of a set of values
+///
+/// Values more than 3 standard deviations from the median are
+/// rejected until none remain to reject
+///
+/// # Returns
+///
+/// Median and standard deviation of the remaining values, or None if
+/// there are no finite values
+///
+pub fn background(values: &[f64]) -> Option<(f64, f64)> {
+    let mut kept: Vec<f64> = values.iter().copied().filter(|v| v.is_finite()).collect();
+    if kept.is_empty() {
+        return None;
+    }
+    let mut level = median(&mut kept);
+    let mut sigma = 0.0;
+    for _ in 0..CLIP_ITERATIONS {
+        let mean = kept.iter().sum::<f64>() / kept.len() as f64;
+        sigma =
+            (kept.iter().map(|v| (v - mean) * (v - mean)).sum::<f64>() / kept.len() as f64).sqrt();
+        let n = kept.len();
+        kept.retain(|v| (v - level).abs() <= CLIP_SIGMA * sigma);
+        if kept.len() == n || kept.is_empty() {
+            break;
+        }
+        level = median(&mut kept);
+    }
+    Some((level, sigma))
+}
+
+/// Width and height of an image, which must have two axes
+fn shape(image: &Image) -> Result<(usize, usize), FITSError> {
+    match image.axes[..] {
+        [nx, ny] => Ok((nx, ny)),
+        _ => Err(FITSError::from(ImageError::DimensionMismatch(
+            2,
+            image.ndims(),
+        ))),
+    }
+}
+
+/// Celestial coordinates of a pixel, if the image has a celestial WCS
+fn sky_position(image: &Image, x: f64, y: f64) -> Result<Option<(Angle, Angle)>, FITSError> {
+    match &image.wcs {
+        Some(wcs) => wcs.pixel_to_angles(&[x, y]),
+        None => Ok(None),
+    }
+}
+
+/// Find sources in an image
+///
+/// # Arguments
+///
+/// * `image` - Two-dimensional image
+/// * `nsigma` - Detection threshold, in standard deviations of the
+///   background above the background level
+/// * `min_pixels` - Smallest number of pixels above the threshold for
+///   a group to count as a source, to reject noise spikes
+///
+/// # Returns
+///
+/// Sources in order of decreasing flux, or an error if the image does
+/// not have two axes.  An image with no valid pixels has no sources
+///
+/// # Example
+///
+/// ```no_run
+/// # use fits::{HDUData, FITS};
+/// # fn main() -> Result<(), fits::FITSError> {
+/// # let fits = FITS::from_file("image.fits")?;
+/// # let HDUData::Image(image) = &fits[0].data else { return Ok(()) };
+/// # use fits::photometry;
+/// for source in photometry::detect_sources(image, 5.0, 4)? {
+///     println!("{:.2} {:.2} {:.1}", source.x, source.y, source.flux);
+/// }
+/// # Ok(())
+/// # }
+/// ```
+///
+pub fn detect_sources(
+    image: &Image,
+    nsigma: f64,
+    min_pixels: usize,
+) -> Result<Vec<Source>, FITSError> {
+    let (nx, ny) = shape(image)?;
+    let pixels = image.physical_pixels();
+    let Some((level, sigma)) = background(&pixels) else {
+        return Ok(Vec::new());
+    };
+    let threshold = level + nsigma * sigma;
+
+    // NaN pixels fail the comparison, so are never part of a source
+    let above = |value: f64| value > threshold;
+
+    let mut visited = vec![false; pixels.len()];
+    let mut stack = Vec::new();
+    let mut sources = Vec::new();
+    for start in 0..pixels.len() {
+        if visited[start] || !above(pixels[start]) {
+            continue;
+        }
+        visited[start] = true;
+        stack.push(start);
+        let (mut flux, mut sx, mut sy, mut peak, mut npixels) = (0.0, 0.0, 0.0, 0.0f64, 0);
+        while let Some(idx) = stack.pop() {
+            let (x, y) = (idx % nx, idx / nx);
+            let value = pixels[idx] - level;
+            flux += value;
+            sx += value * x as f64;
+            sy += value * y as f64;
+            peak = peak.max(value);
+            npixels += 1;
+            for qy in y.saturating_sub(1)..(y + 2).min(ny) {
+                for qx in x.saturating_sub(1)..(x + 2).min(nx) {
+                    let n = qy * nx + qx;
+                    if !visited[n] && above(pixels[n]) {
+                        visited[n] = true;
+                        stack.push(n);
+                    }
+                }
+            }
+        }
+        if npixels < min_pixels {
+            continue;
+        }
+        let (x, y) = (sx / flux, sy / flux);
+        sources.push(Source {
+            x,
+            y,
+            flux,
+            peak,
+            npixels,
+            sky: sky_position(image, x, y)?,
+        });
+    }
+    sources.sort_by(|a, b| b.flux.total_cmp(&a.flux));
+    Ok(sources)
+}
+
+/// Measure the flux within a circular aperture
+///
+/// Pixels whose centers are within `radius` of the center are summed;
+/// undefined pixels are skipped
+///
+/// # Arguments
+///
+/// * `image` - Two-dimensional image
+/// * `x`, `y` - Center of the aperture, zero-based pixel coordinates
+/// * `radius` - Radius of the aperture, in pixels
+/// * `annulus` - Inner and outer radii of the background annulus; if
+///   None, no background is subtracted
+///
+/// # Returns
+///
+/// The measurement, or an error if the image does not have two axes
+/// or the radii are not positive and increasing
+///
+pub fn aperture_photometry(
+    image: &Image,
+    x: f64,
+    y: f64,
+    radius: f64,
+    annulus: Option<(f64, f64)>,
+) -> Result<ApertureFlux, FITSError> {
+    let (nx, ny) = shape(image)?;
+    let invalid = |why: &str| FITSError::from(ImageError::InvalidAperture(why.to_string()));
+    if radius.is_nan() || radius <= 0.0 {
+        return Err(invalid("radius must be positive"));
+    }
+    if let Some((inner, outer)) = annulus {
+        if !(inner >= 0.0 && outer > inner) {
+            return Err(invalid("annulus radii must be increasing"));
+        }
+    }
+    let reach = annulus.map_or(radius, |(_, outer)| outer.max(radius));
+
+    let (mut sum, mut npixels, mut sky) = (0.0, 0, Vec::new());
+    // Range of pixels within `reach` of a coordinate
+    let lo = |c: f64| (c - reach).ceil().max(0.0) as usize;
+    let hi = |c: f64, n: usize| ((c + reach).floor() + 1.0).clamp(0.0, n as f64) as usize;
+    for py in lo(y)..hi(y, ny) {
+        for px in lo(x)..hi(x, nx) {
+            let value = match image.physical_at(&[px, py])? {
+                Some(value) => value,
+                None => continue,
+            };
+            let r = ((px as f64 - x).powi(2) + (py as f64 - y).powi(2)).sqrt();
+            if r <= radius {
+                sum += value;
+                npixels += 1;
+            }
+            if let Some((inner, outer)) = annulus {
+                if r >= inner && r <= outer {
+                    sky.push(value);
+                }
+            }
+        }
+    }
+    let background = match annulus {
+        Some(_) => background(&sky).map_or(f64::NAN, |(level, _)| level),
+        None => 0.0,
+    };
+    Ok(ApertureFlux {
+        x,
+        y,
+        flux: sum - background * npixels as f64,
+        background,
+        npixels,
+        sky: sky_position(image, x, y)?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testutil::image;
+    use crate::Bitpix;
+
+    /// 20 x 20 image with a background cycling through 9, 10, and 11,
+    /// a 3 x 3 source of 90 above it centered on (12, 6), and a hot pixel
+    fn field() -> Image {
+        let mut pixels = vec![0.0f64; 400];
+        for (i, p) in pixels.iter_mut().enumerate() {
+            *p = 9.0 + (i % 3) as f64;
+        }
+        for y in 5..8 {
+            for x in 11..14 {
+                pixels[y * 20 + x] = 100.0;
+            }
+        }
+        pixels[13 * 20 + 4] = 100.0;
+        image(Bitpix::Float64, &[20, 20], &pixels)
+    }
+
+    #[test]
+    fn test_detect_sources() {
+        let image = field();
+        let sources = detect_sources(&image, 5.0, 2).unwrap();
+        assert_eq!(sources.len(), 1);
+        let source = &sources[0];
+        assert_eq!((source.x, source.y), (12.0, 6.0));
+        assert_eq!(source.npixels, 9);
+        assert_eq!(source.flux, 810.0);
+        assert!(source.sky.is_none());
+        // The single hot pixel is a source when one pixel suffices
+        assert_eq!(detect_sources(&image, 5.0, 1).unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_aperture_photometry() {
+        let image = field();
+        let result = aperture_photometry(&image, 12.0, 6.0, 1.5, Some((4.0, 6.0))).unwrap();
+        assert_eq!(result.npixels, 9);
+        assert_eq!(result.background, 10.0);
+        assert_eq!(result.flux, 810.0);
+        let raw = aperture_photometry(&image, 12.0, 6.0, 1.0, None).unwrap();
+        assert_eq!((raw.npixels, raw.flux), (5, 500.0));
+        assert!(aperture_photometry(&image, 12.0, 6.0, 2.0, Some((3.0, 2.0))).is_err());
+    }
+}
@@ -0,0 +1,262 @@
+//! Single-dish FITS (SDFITS) radio spectra
+//!
+//! An SDFITS table is a binary table with EXTNAME = 'SINGLE DISH' and
+//! one spectrum per row in its DATA column.  Each row carries its own
+//! description of the spectral axis in the CRVAL1, CDELT1, and CRPIX1
+//! columns.  Any of these "core" values may instead be a header
+//! keyword, which then applies to every row (a virtual column)
+
+use crate::BinTable;
+use crate::BinTableValue;
+use crate::FITSError;
+use crate::HDUData;
+use crate::KeywordValue;
+use crate::TableLike;
+use crate::HDU;
+
+/// A spectrum read from a row of an SDFITS table
+#[derive(Clone, Debug, PartialEq)]
+pub struct Spectrum {
+    /// Row of the table
+    pub row: usize,
+    /// Spectral values; nulls are NaN.  Multidimensional DATA cells
+    /// hold several spectra, one after the other
+    pub data: Vec<f64>,
+    /// Frequency (or other spectral coordinate) of each channel
+    pub frequency: Vec<f64>,
+    /// Type of the spectral axis, from CTYPE1, e.g. "FREQ-OBS"
+    pub ctype: Option<String>,
+    /// Name of the source, from OBJECT
+    pub object: Option<String>,
+}
+
+/// Spectra of an SDFITS table
+///
+/// # Example
+///
+/// ```no_run
+/// # use fits::{SdFits, FITS};
+/// # fn main() -> Result<(), fits::FITSError> {
+/// # let fits = FITS::from_file("spectra.fits")?;
+/// let sdfits = SdFits::from_hdu(fits.at(1)?)?;
+/// for spectrum in sdfits.spectra() {
+///     let spectrum = spectrum?;
+///     println!("{:?} {} channels", spectrum.object, spectrum.frequency.len());
+/// }
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Debug)]
+pub struct SdFits<'a> {
+    table: &'a BinTable,
+    data_col: usize,
+    /// Channels per spectrum: the first TDIMn dimension of DATA
+    nchan: Option<usize>,
+}
+
+/// First dimension of a TDIMn value such as "(1024,1,1,1)"
+fn first_dimension(tdim: &str) -> Option<usize> {
+    tdim.trim()
+        .strip_prefix('(')?
+        .split([',', ')'])
+        .next()?
+        .trim()
+        .parse()
+        .ok()
+}
+
+impl<'a> SdFits<'a> {
+    /// Is an HDU an SDFITS table: a binary table with EXTNAME =
+    /// 'SINGLE DISH' and a DATA column
+    pub fn is_sdfits(hdu: &HDU) -> bool {
+        matches!(&hdu.data, HDUData::BinTable(table) if table.column_index("DATA").is_ok())
+            && hdu
+                .extname()
+                .is_some_and(|n| n.trim_end().eq_ignore_ascii_case("SINGLE DISH"))
+    }
+
+    /// Spectra of an SDFITS HDU
+    ///
+    /// # Arguments
+    ///
+    /// * `hdu` - SDFITS binary table; see `is_sdfits`
+    ///
+    /// # Returns
+    ///
+    /// The spectra, or an error if the HDU is not an SDFITS table
+    ///
+    pub fn from_hdu(hdu: &'a HDU) -> Result<Self, FITSError> {
+        let table = match &hdu.data {
+            HDUData::BinTable(table) if Self::is_sdfits(hdu) => table,
+            _ => {
+                return Err(FITSError::InvalidSdfits(
+                    "not a SINGLE DISH binary table with a DATA column".to_string(),
+                ))
+            }
+        };
+        let data_col = table.column_index("DATA")?;
+        let nchan = table
+            .header()
+            .get::<String>(&format!("TDIM{}", data_col + 1))
+            .ok()
+            .and_then(|tdim| first_dimension(&tdim));
+        Ok(SdFits {
+            table,
+            data_col,
+            nchan,
+        })
+    }
+
+    /// Number of spectra
+    pub fn len(&self) -> usize {
+        self.table.nrows()
+    }
+
+    /// True if the table has no rows
+    pub fn is_empty(&self) -> bool {
+        self.table.nrows() == 0
+    }
+
+    /// Value of a core keyword for a row: the column of that name if
+    /// present, else the header keyword
+    fn core(&self, row: usize, name: &str) -> Result<Option<BinTableValue>, FITSError> {
+        match self.table.column_index(name) {
+            Ok(col) => match self.table.at_physical(row, col)? {
+                BinTableValue::Null => Ok(None),
+                value => Ok(Some(value)),
+            },
+            Err(_) => Ok(match self.table.header().value(name) {
+                Some(KeywordValue::Int(i)) => Some(BinTableValue::Int64(*i)),
+                Some(KeywordValue::Float(f)) => Some(BinTableValue::Float64(*f)),
+                Some(KeywordValue::String(s)) => Some(BinTableValue::String(s.clone())),
+                _ => None,
+            }),
+        }
+    }
+
+    /// Numeric core value of a row
+    fn core_f64(&self, row: usize, name: &str) -> Result<Option<f64>, FITSError> {
+        Ok(self.core(row, name)?.and_then(|v| v.as_f64()))
+    }
+
+    /// String core value of a row, trimmed
+    fn core_string(&self, row: usize, name: &str) -> Result<Option<String>, FITSError> {
+        Ok(match self.core(row, name)? {
+            Some(BinTableValue::String(s)) => Some(s.trim().to_string()),
+            _ => None,
+        })
+    }
+
+    /// Spectrum of a row
+    ///
+    /// Channel `i` (zero-based) has frequency
+    /// `CRVAL1 + (i + 1 - CRPIX1) * CDELT1`
+    ///
+    /// # Arguments
+    ///
+    /// * `row` - Row of the table
+    ///
+    /// # Returns
+    ///
+    /// The spectrum, or an error if the row is out of range or CRVAL1
+    /// or CDELT1 is missing
+    ///
+    pub fn spectrum(&self, row: usize) -> Result<Spectrum, FITSError> {
+        let data: Vec<f64> = match self.table.at_physical(row, self.data_col)? {
+            BinTableValue::Array(values) => values
+                .iter()
+                .map(|v| v.as_f64().unwrap_or(f64::NAN))
+                .collect(),
+            value => vec![value.as_f64().unwrap_or(f64::NAN)],
+        };
+        let missing = |name: &str| {
+            FITSError::InvalidSdfits(format!("row {} has no {} column or keyword", row, name))
+        };
+        let crval = self
+            .core_f64(row, "CRVAL1")?
+            .ok_or_else(|| missing("CRVAL1"))?;
+        let cdelt = self
+            .core_f64(row, "CDELT1")?
+            .ok_or_else(|| missing("CDELT1"))?;
+        let crpix = self.core_f64(row, "CRPIX1")?.unwrap_or(1.0);
+        let nchan = self.nchan.unwrap_or(data.len()).min(data.len());
+        Ok(Spectrum {
+            row,
+            frequency: (0..nchan)
+                .map(|i| crval + (i as f64 + 1.0 - crpix) * cdelt)
+                .collect(),
+            data,
+            ctype: self.core_string(row, "CTYPE1")?,
+            object: self.core_string(row, "OBJECT")?,
+        })
+    }
+
+    /// Iterate over the spectra, in row order
+    pub fn spectra(&self) -> impl Iterator<Item = Result<Spectrum, FITSError>> + '_ {
+        (0..self.len()).map(|row| self.spectrum(row))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testutil::header;
+
+    #[test]
+    fn test_sdfits() {
+        let header = header(&[
+            "XTENSION= 'BINTABLE'",
+            "BITPIX  =                    8",
+            "NAXIS   =                    2",
+            "NAXIS1  =                   32",
+            "NAXIS2  =                    2",
+            "PCOUNT  =                    0",
+            "GCOUNT  =                    1",
+            "TFIELDS =                    3",
+            "TTYPE1  = 'OBJECT  '",
+            "TFORM1  = '8A      '",
+            "TTYPE2  = 'CRVAL1  '",
+            "TFORM2  = '1D      '",
+            "TTYPE3  = 'DATA    '",
+            "TFORM3  = '4E      '",
+            "TDIM3   = '(4,1,1,1)'",
+            "EXTNAME = 'SINGLE DISH'",
+            "CTYPE1  = 'FREQ-OBS'",
+            "CDELT1  =                 1.E6",
+            "CRPIX1  =                  2.0",
+            "END",
+        ]);
+        let mut raw = Vec::new();
+        for (object, crval) in [("ORION   ", 1.4e9f64), ("W51     ", 1.6e9)] {
+            raw.extend(object.as_bytes());
+            raw.extend(crval.to_be_bytes());
+            for v in [1.0f32, 2.0, f32::NAN, 4.0] {
+                raw.extend(v.to_be_bytes());
+            }
+        }
+        let (data, _) = BinTable::from_bytes(&header, &raw).unwrap();
+        let hdu = HDU {
+            header,
+            data,
+            location: None,
+        };
+
+        assert!(SdFits::is_sdfits(&hdu));
+        let sdfits = SdFits::from_hdu(&hdu).unwrap();
+        assert_eq!(sdfits.len(), 2);
+        let spectra = sdfits.spectra().collect::<Result<Vec<_>, _>>().unwrap();
+        assert_eq!(spectra[0].object.as_deref(), Some("ORION"));
+        assert_eq!(spectra[0].ctype.as_deref(), Some("FREQ-OBS"));
+        assert_eq!(spectra[0].frequency, vec![1.399e9, 1.4e9, 1.401e9, 1.402e9]);
+        assert_eq!(spectra[1].frequency[1], 1.6e9);
+        assert_eq!(spectra[1].data[3], 4.0);
+        assert!(spectra[1].data[2].is_nan());
+        assert!(sdfits.spectrum(2).is_err());
+
+        let mut other = hdu.clone();
+        other
+            .header
+            .set("EXTNAME", KeywordValue::String("EVENTS".into()));
+        assert!(SdFits::from_hdu(&other).is_err());
+    }
+}
@@ -0,0 +1,351 @@
+//! Conversion between ASCII tables and binary tables
+//!
+//! Both directions build the header of the new table from the column
+//! metadata of the old one, copy the keywords that do not describe the
+//! data layout, and then parse the new table as if read from a file
+
+use super::{AsciiTForm, AsciiTFormType, Table};
+use crate::BinTable;
+use crate::BinTableValue;
+use crate::FITSError;
+use crate::HDUData;
+use crate::Header;
+use crate::Keyword;
+use crate::KeywordValue;
+use crate::TDisp;
+use crate::TForm;
+use crate::TFormType;
+use crate::TableError;
+
+/// Column keywords rewritten by the conversions; the others (TLMINn,
+/// TCTYPn, etc.) are copied unchanged
+const LAYOUT_COLUMN_KEYWORDS: &[&str] = &[
+    "TTYPE", "TFORM", "TBCOL", "TUNIT", "TSCAL", "TZERO", "TNULL", "TDISP", "TDIM",
+];
+
+/// Whether a keyword describes the layout of the table data
+fn is_layout_keyword(name: &str) -> bool {
+    let structural = [
+        "XTENSION", "BITPIX", "NAXIS", "NAXIS1", "NAXIS2", "PCOUNT", "GCOUNT", "TFIELDS", "THEAP",
+        "END",
+    ];
+    structural.contains(&name)
+        || LAYOUT_COLUMN_KEYWORDS.iter().any(|prefix| {
+            name.strip_prefix(prefix)
+                .is_some_and(|n| !n.is_empty() && n.bytes().all(|b| b.is_ascii_digit()))
+        })
+}
+
+fn keyword(name: &str, value: KeywordValue) -> Keyword {
+    Keyword {
+        name: name.to_string(),
+        value,
+        ..Default::default()
+    }
+}
+
+/// Header of a converted table
+///
+/// # Arguments
+///
+/// * `xtension` - "TABLE" or "BINTABLE"
+/// * `rowbytes` - NAXIS1
+/// * `columns` - Column keywords, in order, after the mandatory keywords
+/// * `source` - Header of the original table, from which keywords not
+///   describing the layout are copied
+///
+fn converted_header(
+    xtension: &str,
+    rowbytes: usize,
+    tfields: usize,
+    columns: Vec<Keyword>,
+    source: &Header,
+) -> Header {
+    let mut keywords = vec![
+        keyword("XTENSION", KeywordValue::String(xtension.to_string())),
+        keyword("BITPIX", KeywordValue::Int(8)),
+        keyword("NAXIS", KeywordValue::Int(2)),
+        keyword("NAXIS1", KeywordValue::Int(rowbytes as i64)),
+        keyword("NAXIS2", KeywordValue::Int(0)),
+        keyword("PCOUNT", KeywordValue::Int(0)),
+        keyword("GCOUNT", KeywordValue::Int(1)),
+        keyword("TFIELDS", KeywordValue::Int(tfields as i64)),
+    ];
+    keywords.extend(columns);
+    keywords.extend(
+        source
+            .iter()
+            .filter(|kw| !is_layout_keyword(&kw.name))
+            .cloned(),
+    );
+    keywords.push(keyword("END", KeywordValue::None));
+    Header::new(keywords)
+}
+
+/// TTYPEn, TUNITn, TSCALn, and TZEROn keywords of a column, with
+/// TFORMn (and TBCOLn) after TTYPEn
+fn column_cards(
+    col: usize,
+    ttype: &Option<String>,
+    layout: Vec<Keyword>,
+    tunit: &Option<String>,
+    tscal: Option<f64>,
+    tzero: Option<f64>,
+) -> Vec<Keyword> {
+    let n = col + 1;
+    let mut cards = Vec::new();
+    if let Some(ttype) = ttype {
+        cards.push(keyword(
+            &format!("TTYPE{}", n),
+            KeywordValue::String(ttype.clone()),
+        ));
+    }
+    cards.extend(layout);
+    if let Some(tunit) = tunit {
+        cards.push(keyword(
+            &format!("TUNIT{}", n),
+            KeywordValue::String(tunit.clone()),
+        ));
+    }
+    if let Some(tscal) = tscal {
+        cards.push(keyword(&format!("TSCAL{}", n), KeywordValue::Float(tscal)));
+    }
+    if let Some(tzero) = tzero {
+        cards.push(keyword(&format!("TZERO{}", n), KeywordValue::Float(tzero)));
+    }
+    cards
+}
+
+/// Text of a value in an ASCII table field, right-justified for
+/// numbers and left-justified for strings; nulls are blank
+///
+/// # Returns
+///
+/// The text, exactly `tform.width` characters, or the reason the value
+/// does not fit
+///
+fn format_field(value: &BinTableValue, tform: &AsciiTForm) -> Result<String, String> {
+    let text = TDisp::from(tform).format(value);
+    match text.len() > tform.width {
+        true => Err(format!("{} does not fit in {}", text.trim(), tform)),
+        false => Ok(text),
+    }
+}
+
+impl Table {
+    /// Convert to a binary table
+    ///
+    /// Character fields `Aw` become `wA` columns, integer fields `Iw`
+    /// become `1J` (`1K` if wider than 9 characters), `Ew.d` fields
+    /// `1E`, `Dw.d` fields `1D`, and `Fw.d` fields `1E` (`1D` if wider
+    /// than 8 characters).  Stored values are copied, so TSCALn and
+    /// TZEROn keep their meaning; null integers are stored as TNULLn =
+    /// the smallest value of the type, and null reals as NaN.  The
+    /// ASCII format is kept as TDISPn unless the column already has
+    /// one.  Keywords that do not describe the layout are copied
+    ///
+    /// # Returns
+    ///
+    /// The binary table, or an error if a field cannot be parsed
+    ///
+    pub fn to_bintable(&self) -> Result<BinTable, FITSError> {
+        let mut columns = Vec::new();
+        let mut rowbytes = 0;
+        let mut values = vec![Vec::with_capacity(self.ncols()); self.nrows];
+        for (col, tform) in self.tform.iter().enumerate() {
+            let mut has_null = false;
+            for (row, row_values) in values.iter_mut().enumerate() {
+                let value = self.at(row, col)?;
+                has_null |= value == BinTableValue::Null;
+                row_values.push(value);
+            }
+
+            let n = col + 1;
+            let (bintform, tnull) = match tform.ftype {
+                AsciiTFormType::Char => (format!("{}A", tform.width), None),
+                AsciiTFormType::Int if tform.width <= 9 => {
+                    ("1J".to_string(), Some(i32::MIN as i64))
+                }
+                AsciiTFormType::Int => ("1K".to_string(), Some(i64::MIN)),
+                AsciiTFormType::Float if tform.width <= 8 => ("1E".to_string(), None),
+                AsciiTFormType::Exp => ("1E".to_string(), None),
+                AsciiTFormType::Float | AsciiTFormType::Double => ("1D".to_string(), None),
+            };
+            rowbytes += bintform.parse::<TForm>()?.size();
+            let mut layout = vec![keyword(
+                &format!("TFORM{}", n),
+                KeywordValue::String(bintform),
+            )];
+            if let Some(tnull) = tnull.filter(|_| has_null) {
+                layout.push(keyword(&format!("TNULL{}", n), KeywordValue::Int(tnull)));
+            }
+            let tdisp = self
+                .header
+                .get::<String>(&format!("TDISP{}", n))
+                .unwrap_or_else(|_| tform.to_string());
+            let mut cards = column_cards(
+                col,
+                &self.ttype[col],
+                layout,
+                &self.tunit[col],
+                self.tscal[col],
+                self.tzero[col],
+            );
+            cards.push(keyword(&format!("TDISP{}", n), KeywordValue::String(tdisp)));
+            columns.extend(cards);
+        }
+
+        let header = converted_header("BINTABLE", rowbytes, self.ncols(), columns, &self.header);
+        let mut table = match BinTable::from_bytes(&header, &[])? {
+            (HDUData::BinTable(table), _) => *table,
+            _ => unreachable!("BinTable::from_bytes returns a binary table"),
+        };
+        for row in values {
+            table.push_row(&row)?;
+        }
+        Ok(table)
+    }
+}
+
+impl BinTable {
+    /// Convert to an ASCII table
+    ///
+    /// Character columns become `Aw` fields, logical columns `A1`
+    /// fields holding T or F, and integer columns `Iw` fields as wide
+    /// as the longest value.  Real columns use TDISPn if it is an `F`,
+    /// `E`, or `D` format, and `E15.7` (`1E`) or `D25.17` (`1D`)
+    /// otherwise.  Fields are separated by one blank.  Stored values
+    /// are written, so TSCALn and TZEROn keep their meaning; nulls are
+    /// written as blank fields.  Keywords that do not describe the
+    /// layout are copied
+    ///
+    /// # Arguments
+    ///
+    /// * `tdisp_overrides` - ASCII formats (e.g. `("FLUX", "F10.4")`)
+    ///   for named columns, used instead of the automatic choice
+    ///
+    /// # Returns
+    ///
+    /// The ASCII table, or an error if a column holds vectors,
+    /// variable-length arrays, bits, or complex numbers, an override is
+    /// not a valid format for its column, or a value does not fit in
+    /// its field
+    ///
+    pub fn to_ascii_table(&self, tdisp_overrides: &[(&str, &str)]) -> Result<Table, FITSError> {
+        let mut overrides = vec![None; self.ncols()];
+        for (name, format) in tdisp_overrides {
+            overrides[self.column_index(name)?] = Some(format.parse::<AsciiTForm>()?);
+        }
+
+        let mut tforms = Vec::with_capacity(self.ncols());
+        for (col, tform) in self.tform.iter().enumerate() {
+            let unsupported = || {
+                FITSError::from(TableError::IncompatibleValue(
+                    col,
+                    format!("{} has no ASCII table format", tform),
+                ))
+            };
+            let ascii = match (&tform.ftype, tform.repeat) {
+                (TFormType::Char, width) if width > 0 => AsciiTForm {
+                    ftype: AsciiTFormType::Char,
+                    width,
+                    decimals: None,
+                },
+                (TFormType::Logical, 1) => AsciiTForm {
+                    ftype: AsciiTFormType::Char,
+                    width: 1,
+                    decimals: None,
+                },
+                (TFormType::Byte | TFormType::Int16 | TFormType::Int32 | TFormType::Int64, 1) => {
+                    let mut width = 1;
+                    for row in 0..self.nrows {
+                        if let Some(v) = self.at(row, col)?.as_integer() {
+                            width = width.max(v.to_string().len());
+                        }
+                    }
+                    AsciiTForm {
+                        ftype: AsciiTFormType::Int,
+                        width,
+                        decimals: None,
+                    }
+                }
+                (TFormType::Float32 | TFormType::Float64, 1) => self
+                    .header()
+                    .get::<String>(&format!("TDISP{}", col + 1))
+                    .ok()
+                    .and_then(|t| t.parse::<AsciiTForm>().ok())
+                    .filter(|t| t.ftype != AsciiTFormType::Char && t.ftype != AsciiTFormType::Int)
+                    .unwrap_or(match tform.ftype {
+                        TFormType::Float32 => AsciiTForm {
+                            ftype: AsciiTFormType::Exp,
+                            width: 15,
+                            decimals: Some(7),
+                        },
+                        _ => AsciiTForm {
+                            ftype: AsciiTFormType::Double,
+                            width: 25,
+                            decimals: Some(17),
+                        },
+                    }),
+                _ => return Err(unsupported()),
+            };
+            let ascii = match overrides[col].take() {
+                Some(o)
+                    if (o.ftype == AsciiTFormType::Char)
+                        == (ascii.ftype == AsciiTFormType::Char) =>
+                {
+                    o
+                }
+                Some(o) => return Err(FITSError::from(TableError::InvalidTForm(o.to_string()))),
+                None => ascii,
+            };
+            tforms.push(ascii);
+        }
+
+        let mut tbcol = Vec::with_capacity(tforms.len());
+        let mut rowbytes = 0;
+        for tform in &tforms {
+            if rowbytes > 0 {
+                rowbytes += 1;
+            }
+            tbcol.push(rowbytes + 1);
+            rowbytes += tform.width;
+        }
+
+        let mut rawbytes = vec![b' '; rowbytes * self.nrows];
+        for (col, tform) in tforms.iter().enumerate() {
+            for row in 0..self.nrows {
+                let text = format_field(&self.at(row, col)?, tform)
+                    .map_err(|reason| TableError::IncompatibleValue(col, reason))?;
+                let start = row * rowbytes + tbcol[col] - 1;
+                rawbytes[start..start + tform.width].copy_from_slice(text.as_bytes());
+            }
+        }
+
+        let mut columns = Vec::new();
+        for (col, tform) in tforms.iter().enumerate() {
+            let n = col + 1;
+            let layout = vec![
+                keyword(&format!("TBCOL{}", n), KeywordValue::Int(tbcol[col] as i64)),
+                keyword(
+                    &format!("TFORM{}", n),
+                    KeywordValue::String(tform.to_string()),
+                ),
+            ];
+            columns.extend(column_cards(
+                col,
+                &self.ttype[col],
+                layout,
+                &self.tunit[col],
+                self.tscal[col],
+                self.tzero[col],
+            ));
+        }
+        let mut header = converted_header("TABLE", rowbytes, tforms.len(), columns, self.header());
+        header.set("NAXIS2", KeywordValue::Int(self.nrows as i64));
+        match Table::from_bytes(&header, &rawbytes)? {
+            (HDUData::Table(table), _) => Ok(*table),
+            _ => unreachable!("Table::from_bytes returns an ASCII table"),
+        }
+    }
+}
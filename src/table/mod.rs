@@ -1,26 +1,78 @@
+mod convert;
+mod tform;
+
+pub use tform::{AsciiTForm, AsciiTFormType};
+
+use crate::bintable::{column_keywords, scale, write_csv};
+use crate::BinTableValue;
+use crate::CsvOptions;
+use crate::FITSError;
 use crate::HDUData;
 use crate::Header;
 use crate::HeaderError;
 use crate::KeywordValue;
+use crate::TableError;
 
+/// ASCII table extension (XTENSION = 'TABLE')
+///
+/// Each row is NAXIS1 characters; each field starts at column TBCOLn
+/// and is formatted as given by TFORMn.  Column metadata is parsed
+/// from the TTYPEn, TFORMn, TBCOLn, TUNITn, TSCALn, TZEROn, and TNULLn
+/// keywords, stored as one entry per column.
+///
+/// See Section 7.2 of FITS standard, version 4
 #[derive(Debug, Clone)]
-pub struct Table {}
+pub struct Table {
+    /// Number of rows (NAXIS2)
+    pub nrows: usize,
+    /// Number of characters in each row (NAXIS1)
+    pub rowbytes: usize,
+    pub ttype: Vec<Option<String>>,
+    pub tform: Vec<AsciiTForm>,
+    /// Starting character of each field (TBCOLn, 1-based)
+    pub tbcol: Vec<usize>,
+    pub tunit: Vec<Option<String>>,
+    pub tscal: Vec<Option<f64>>,
+    pub tzero: Vec<Option<f64>>,
+    /// String representing an undefined value in each field
+    pub tnull: Vec<Option<String>>,
+    /// Raw characters of the table (NAXIS1 * NAXIS2 bytes)
+    pub rawbytes: Vec<u8>,
+    /// Table header
+    header: Header,
+}
+
+/// Parse a real number written with an F, E, or D format
+///
+/// A `D` exponent is accepted, and if the field has no decimal point,
+/// the last `decimals` digits of the mantissa are taken as the
+/// fraction (see Section 7.2.5 of FITS standard, version 4)
+fn parse_real(text: &str, decimals: Option<usize>) -> Option<f64> {
+    let text = text.replace(['D', 'd'], "E");
+    let (mantissa, exponent) = match text.find(['E', 'e']) {
+        Some(i) => (&text[..i], text[i + 1..].parse::<i32>().ok()?),
+        None => (text.as_str(), 0),
+    };
+    let mut value = mantissa.parse::<f64>().ok()?;
+    if !mantissa.contains('.') {
+        value /= 10f64.powi(decimals.unwrap_or(0) as i32);
+    }
+    Some(value * 10f64.powi(exponent))
+}
 
 impl Table {
-    pub fn from_bytes(
-        header: &Header,
-        _rawbytes: &[u8],
-    ) -> Result<(HDUData, usize), Box<dyn std::error::Error>> {
+    pub fn from_bytes(header: &Header, rawbytes: &[u8]) -> Result<(HDUData, usize), FITSError> {
         // Section 7.2 of the fits standard 4.0 manual
         // Note: this is an objectively awful way to store a table
         // but it is the standard
 
         // Check bitpix is 8
         let kwbitpix = header
-            .get(1)
+            .iter()
+            .nth(1)
             .ok_or(HeaderError::GenericError("not enough keywords".to_string()))?;
         if kwbitpix.name != "BITPIX" {
-            return Err(Box::new(HeaderError::InvalidKeywordPlacement(
+            return Err(FITSError::from(HeaderError::InvalidKeywordPlacement(
                 kwbitpix.name.clone(),
                 1,
             )));
@@ -28,25 +80,25 @@ impl Table {
         match &kwbitpix.value {
             KeywordValue::Int(value) => {
                 if *value != 8 {
-                    return Err(Box::new(HeaderError::GenericError(
+                    return Err(FITSError::from(HeaderError::GenericError(
                         "Invalid BITPIX value".to_string(),
                     )));
                 }
             }
             _ => {
-                return Err(Box::new(HeaderError::GenericError(
+                return Err(FITSError::from(HeaderError::GenericError(
                     "Invalid BITPIX value".to_string(),
-                ))
-                .into());
+                )));
             }
         }
 
         // Check naxis is 2
         let kwaxes = header
-            .get(2)
+            .iter()
+            .nth(2)
             .ok_or(HeaderError::GenericError("not enough keywords".to_string()))?;
         if kwaxes.name != "NAXIS" {
-            return Err(Box::new(HeaderError::InvalidKeywordPlacement(
+            return Err(FITSError::from(HeaderError::InvalidKeywordPlacement(
                 kwaxes.name.clone(),
                 2,
             )));
@@ -54,24 +106,25 @@ impl Table {
         match &kwaxes.value {
             KeywordValue::Int(value) => {
                 if *value != 2 {
-                    return Err(Box::new(HeaderError::GenericError(
+                    return Err(FITSError::from(HeaderError::GenericError(
                         "Invalid NAXIS value".to_string(),
                     )));
                 }
             }
             _ => {
-                return Err(
-                    Box::new(HeaderError::GenericError("Invalid NAXIS value".to_string())).into(),
-                );
+                return Err(FITSError::from(HeaderError::GenericError(
+                    "Invalid NAXIS value".to_string(),
+                )));
             }
         }
 
         // get naxis1 and naxis2
         let kwaxis1 = header
-            .get(3)
+            .iter()
+            .nth(3)
             .ok_or(HeaderError::GenericError("not enough keywords".to_string()))?;
         if kwaxis1.name != "NAXIS1" {
-            return Err(Box::new(HeaderError::InvalidKeywordPlacement(
+            return Err(FITSError::from(HeaderError::InvalidKeywordPlacement(
                 kwaxis1.name.clone(),
                 3,
             )));
@@ -79,16 +132,17 @@ impl Table {
         let nrowchars = match &kwaxis1.value {
             KeywordValue::Int(value) => *value as usize,
             _ => {
-                return Err(Box::new(HeaderError::GenericError(
+                return Err(FITSError::from(HeaderError::GenericError(
                     "Invalid NROWCHARS (NAXIS1) value".to_string(),
                 )));
             }
         };
         let kwaxis2 = header
-            .get(4)
+            .iter()
+            .nth(4)
             .ok_or(HeaderError::GenericError("not enough keywords".to_string()))?;
         if kwaxis2.name != "NAXIS2" {
-            return Err(Box::new(HeaderError::InvalidKeywordPlacement(
+            return Err(FITSError::from(HeaderError::InvalidKeywordPlacement(
                 kwaxis2.name.clone(),
                 4,
             )));
@@ -96,12 +150,419 @@ impl Table {
         let nrows = match &kwaxis2.value {
             KeywordValue::Int(value) => *value as usize,
             _ => {
-                return Err(Box::new(HeaderError::GenericError(
+                return Err(FITSError::from(HeaderError::GenericError(
                     "Invalid NROWS (NAXIS2) value".to_string(),
                 )));
             }
         };
 
-        Ok((HDUData::Table(Box::new(Table {})), nrows * nrowchars))
+        let nbytes = nrows * nrowchars;
+        if rawbytes.len() < nbytes {
+            return Err(FITSError::from(HeaderError::GenericError(
+                "not enough data bytes for table".to_string(),
+            )));
+        }
+
+        let tfields = header.get::<i64>("TFIELDS")? as usize;
+        let mut tform = Vec::with_capacity(tfields);
+        let mut tbcol = Vec::with_capacity(tfields);
+        let tforms = column_keywords::<String>(header, "TFORM", tfields)?;
+        let tbcols = column_keywords::<i64>(header, "TBCOL", tfields)?;
+        for (i, (f, b)) in tforms.into_iter().zip(tbcols).enumerate() {
+            let f = f
+                .ok_or(HeaderError::GenericError(format!("missing TFORM{}", i + 1)))?
+                .parse::<AsciiTForm>()?;
+            let b = b.ok_or(HeaderError::GenericError(format!("missing TBCOL{}", i + 1)))?;
+            if b < 1 || b as usize - 1 + f.width > nrowchars {
+                return Err(FITSError::from(HeaderError::GenericError(format!(
+                    "Field {} (TBCOL = {}, TFORM = {}) extends past NAXIS1 ({})",
+                    i + 1,
+                    b,
+                    f,
+                    nrowchars
+                ))));
+            }
+            tform.push(f);
+            tbcol.push(b as usize);
+        }
+
+        let table = Table {
+            nrows,
+            rowbytes: nrowchars,
+            ttype: column_keywords(header, "TTYPE", tfields)?,
+            tform,
+            tbcol,
+            tunit: column_keywords(header, "TUNIT", tfields)?,
+            tscal: column_keywords(header, "TSCAL", tfields)?,
+            tzero: column_keywords(header, "TZERO", tfields)?,
+            tnull: column_keywords(header, "TNULL", tfields)?,
+            rawbytes: rawbytes[0..nbytes].to_vec(),
+            header: header.clone(),
+        };
+        Ok((HDUData::Table(Box::new(table)), nbytes))
+    }
+
+    /// Number of columns (TFIELDS)
+    pub fn ncols(&self) -> usize {
+        self.tform.len()
+    }
+
+    /// Header describing the table
+    pub fn header(&self) -> &Header {
+        &self.header
+    }
+
+    /// Names of the columns
+    ///
+    /// # Returns
+    ///
+    /// The TTYPEn value of each column, without trailing blanks, or
+    /// "COLn" (1-based) for columns without a name
+    ///
+    pub fn column_names(&self) -> Vec<String> {
+        self.ttype
+            .iter()
+            .enumerate()
+            .map(|(col, t)| match t {
+                Some(t) => t.trim_end().to_string(),
+                None => format!("COL{}", col + 1),
+            })
+            .collect()
+    }
+
+    /// Index of the column with a given name
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - Column name (TTYPEn), matched case-insensitively and
+    ///   ignoring trailing blanks
+    ///
+    /// # Returns
+    ///
+    /// The zero-based column index, or an error if no column, or more
+    /// than one column, has the name
+    ///
+    pub fn column_index(&self, name: &str) -> Result<usize, FITSError> {
+        let mut matches = self.ttype.iter().enumerate().filter(|(_, t)| {
+            t.as_deref()
+                .is_some_and(|t| t.trim_end().eq_ignore_ascii_case(name.trim_end()))
+        });
+        match (matches.next(), matches.next()) {
+            (Some((col, _)), None) => Ok(col),
+            (Some(_), Some(_)) => Err(FITSError::from(TableError::AmbiguousColumn(
+                name.to_string(),
+            ))),
+            (None, _) => Err(FITSError::from(TableError::UnknownColumn(name.to_string()))),
+        }
+    }
+
+    /// Characters of a table field
+    fn field(&self, row: usize, col: usize) -> Result<&str, FITSError> {
+        if row >= self.nrows {
+            return Err(FITSError::from(TableError::RowOutOfRange(row, self.nrows)));
+        }
+        if col >= self.ncols() {
+            return Err(FITSError::from(TableError::ColumnOutOfRange(
+                col,
+                self.ncols(),
+            )));
+        }
+        let start = row * self.rowbytes + self.tbcol[col] - 1;
+        let bytes = &self.rawbytes[start..start + self.tform[col].width];
+        std::str::from_utf8(bytes).map_err(|_| FITSError::from(TableError::InvalidValue(row, col)))
+    }
+
+    /// Get value of a table cell, as written in the file
+    ///
+    /// Character fields are returned as `String`, with their trailing
+    /// blanks; integer fields as `Int64`, and real fields as `Float64`.
+    /// Fields equal to TNULLn, and blank numeric fields, are `Null`
+    ///
+    /// # Arguments
+    ///
+    /// * `row` - Row index (zero-based)
+    /// * `col` - Column index (zero-based)
+    ///
+    /// # Returns
+    ///
+    /// The value, or an error if the cell is out of range or a numeric
+    /// field cannot be parsed
+    ///
+    pub fn at(&self, row: usize, col: usize) -> Result<BinTableValue, FITSError> {
+        let text = self.field(row, col)?;
+        let tform = &self.tform[col];
+        if self.tnull[col]
+            .as_deref()
+            .is_some_and(|null| null.trim() == text.trim())
+        {
+            return Ok(BinTableValue::Null);
+        }
+        if tform.ftype == AsciiTFormType::Char {
+            return Ok(BinTableValue::String(text.to_string()));
+        }
+        // Embedded blanks are not significant in numeric fields
+        let text: String = text.chars().filter(|c| *c != ' ').collect();
+        if text.is_empty() {
+            return Ok(BinTableValue::Null);
+        }
+        let invalid = || FITSError::from(TableError::InvalidValue(row, col));
+        match tform.ftype {
+            AsciiTFormType::Int => Ok(BinTableValue::Int64(
+                text.trim_start_matches('+')
+                    .parse()
+                    .map_err(|_| invalid())?,
+            )),
+            _ => Ok(BinTableValue::Float64(
+                parse_real(&text, tform.decimals).ok_or_else(invalid)?,
+            )),
+        }
+    }
+
+    /// Get physical value of a table cell
+    ///
+    /// Applies the TSCALn and TZEROn keywords to numeric values:
+    /// `physical = TZERO + TSCAL * stored`, returned as `Float64`.
+    /// Columns without TSCALn or TZEROn are returned unchanged, as are
+    /// character fields and null values
+    ///
+    /// # Arguments
+    ///
+    /// * `row` - Row index (zero-based)
+    /// * `col` - Column index (zero-based)
+    ///
+    pub fn at_physical(&self, row: usize, col: usize) -> Result<BinTableValue, FITSError> {
+        let value = self.at(row, col)?;
+        if self.tscal[col].is_none() && self.tzero[col].is_none() {
+            return Ok(value);
+        }
+        Ok(scale(
+            value,
+            self.tscal[col].unwrap_or(1.0),
+            self.tzero[col].unwrap_or(0.0),
+        ))
+    }
+
+    /// Write the table as delimited text
+    ///
+    /// Character fields are written without trailing blanks; otherwise
+    /// as `BinTable::to_csv`
+    ///
+    /// # Arguments
+    ///
+    /// * `writer` - Destination of the text
+    /// * `options` - Delimiter and null text; see `CsvOptions`
+    ///
+    pub fn to_csv<W: std::io::Write>(
+        &self,
+        writer: W,
+        options: &CsvOptions,
+    ) -> Result<(), FITSError> {
+        write_csv(self, writer, options)
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::testutil::kw;
+    use crate::TableLike;
+
+    /// Table with columns: NAME (A6), COUNT (I4, TNULL = -1), FLUX
+    /// (F8.2, TZERO = 100)
+    pub(crate) fn sample_table() -> Table {
+        let header = Header::new(vec![
+            kw("XTENSION", KeywordValue::String("TABLE".into())),
+            kw("BITPIX", KeywordValue::Int(8)),
+            kw("NAXIS", KeywordValue::Int(2)),
+            kw("NAXIS1", KeywordValue::Int(20)),
+            kw("NAXIS2", KeywordValue::Int(3)),
+            kw("PCOUNT", KeywordValue::Int(0)),
+            kw("GCOUNT", KeywordValue::Int(1)),
+            kw("TFIELDS", KeywordValue::Int(3)),
+            kw("TTYPE1", KeywordValue::String("NAME".into())),
+            kw("TBCOL1", KeywordValue::Int(1)),
+            kw("TFORM1", KeywordValue::String("A6".into())),
+            kw("TTYPE2", KeywordValue::String("COUNT".into())),
+            kw("TBCOL2", KeywordValue::Int(8)),
+            kw("TFORM2", KeywordValue::String("I4".into())),
+            kw("TNULL2", KeywordValue::String("-1".into())),
+            kw("TTYPE3", KeywordValue::String("FLUX".into())),
+            kw("TBCOL3", KeywordValue::Int(13)),
+            kw("TFORM3", KeywordValue::String("F8.2".into())),
+            kw("TUNIT3", KeywordValue::String("Jy".into())),
+            kw("TZERO3", KeywordValue::Float(100.0)),
+            kw("END", KeywordValue::None),
+        ]);
+        let raw = b"alpha    12     1.50beta     -1      250gamma        1.00D+1";
+        match Table::from_bytes(&header, raw).unwrap() {
+            (HDUData::Table(t), 60) => *t,
+            _ => panic!("expected ASCII table"),
+        }
+    }
+
+    #[test]
+    fn test_at() {
+        let table = sample_table();
+        assert_eq!(table.ncols(), 3);
+        assert_eq!(table.tform[2].to_string(), "F8.2");
+        assert_eq!(
+            table.at(0, 0).unwrap(),
+            BinTableValue::String("alpha ".to_string())
+        );
+        assert_eq!(table.at(0, 1).unwrap(), BinTableValue::Int64(12));
+        assert_eq!(table.at(1, 1).unwrap(), BinTableValue::Null);
+        assert_eq!(table.at(2, 1).unwrap(), BinTableValue::Null);
+        assert_eq!(table.at(1, 2).unwrap(), BinTableValue::Float64(2.5));
+        assert_eq!(table.at(2, 2).unwrap(), BinTableValue::Float64(10.0));
+        assert_eq!(
+            table.at_physical(0, 2).unwrap(),
+            BinTableValue::Float64(101.5)
+        );
+        assert!(table.at(3, 0).is_err());
+        assert!("X4".parse::<AsciiTForm>().is_err());
+        assert!("F8.x".parse::<AsciiTForm>().is_err());
+    }
+
+    #[test]
+    fn test_columns_and_rows() {
+        let table = sample_table();
+        assert_eq!(table.column_names(), vec!["NAME", "COUNT", "FLUX"]);
+        assert_eq!(
+            table.column::<f64>("flux").unwrap(),
+            vec![Some(101.5), Some(102.5), Some(110.0)]
+        );
+        assert_eq!(
+            table.column::<i64>("COUNT").unwrap(),
+            vec![Some(12), None, None]
+        );
+        assert!(table.column::<f64>("NAME").is_err());
+        assert!(table.column::<f64>("ENERGY").is_err());
+
+        let rows = table.rows().collect::<Result<Vec<_>, _>>().unwrap();
+        assert_eq!(rows.len(), 3);
+        assert_eq!(rows[2][2], BinTableValue::Float64(110.0));
+    }
+
+    #[test]
+    fn test_table_like() {
+        fn summary<T: TableLike>(table: &T) -> (usize, usize, Option<String>) {
+            let col = table.column_index("flux").unwrap();
+            (
+                table.nrows(),
+                table.ncols(),
+                table.unit(col).map(String::from),
+            )
+        }
+        assert_eq!(summary(&sample_table()), (3, 3, Some("Jy".to_string())));
+        let unit = sample_table().column_unit(2).unwrap().unwrap();
+        assert_eq!(
+            unit.conversion_factor(&"mJy".parse().unwrap()).unwrap(),
+            1000.0
+        );
+        let bintable = crate::bintable::tests::sample_table();
+        assert_eq!(summary(&bintable).0, bintable.nrows);
+
+        let mut out = Vec::new();
+        sample_table()
+            .to_csv(&mut out, &CsvOptions::default())
+            .unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "NAME,COUNT,FLUX\nalpha,12,101.5\nbeta,,102.5\ngamma,,110\n"
+        );
+    }
+
+    #[cfg(feature = "arrow")]
+    #[test]
+    fn test_to_record_batch() {
+        use arrow_array::cast::AsArray;
+        use arrow_array::types::{Float64Type, Int64Type};
+        use arrow_array::Array;
+        use arrow_schema::DataType;
+
+        let batch = sample_table().to_record_batch().unwrap();
+        assert_eq!(batch.num_rows(), 3);
+        let schema = batch.schema();
+        assert_eq!(schema.field(0).data_type(), &DataType::Utf8);
+        assert_eq!(schema.field(1).data_type(), &DataType::Int64);
+        assert_eq!(schema.field(2).data_type(), &DataType::Float64);
+        assert_eq!(schema.field(2).metadata()["unit"], "Jy");
+
+        assert_eq!(batch.column(0).as_string::<i32>().value(1), "beta");
+        let counts = batch.column(1).as_primitive::<Int64Type>();
+        assert_eq!(counts.value(0), 12);
+        assert!(counts.is_null(1));
+        let flux = batch.column(2).as_primitive::<Float64Type>();
+        assert_eq!(flux.values(), &[101.5, 102.5, 110.0]);
+    }
+
+    #[cfg(feature = "polars")]
+    #[test]
+    fn test_to_polars() {
+        let df = sample_table().to_polars().unwrap();
+        assert_eq!(df.shape(), (3, 3));
+        let counts: Vec<Option<i64>> = df
+            .column("COUNT")
+            .unwrap()
+            .i64()
+            .unwrap()
+            .into_iter()
+            .collect();
+        assert_eq!(counts, vec![Some(12), None, None]);
+        let names: Vec<Option<&str>> = df
+            .column("NAME")
+            .unwrap()
+            .str()
+            .unwrap()
+            .into_iter()
+            .collect();
+        assert_eq!(names, vec![Some("alpha"), Some("beta"), Some("gamma")]);
+    }
+
+    #[test]
+    fn test_convert() {
+        let ascii = sample_table();
+        let bintable = ascii.to_bintable().unwrap();
+        let tforms: Vec<String> = bintable.tform.iter().map(|t| t.to_string()).collect();
+        assert_eq!(tforms, vec!["6A", "1J", "1E"]);
+        assert_eq!(bintable.tnull[1], Some(i32::MIN as i64));
+        assert_eq!(bintable.header().get::<String>("TDISP3").unwrap(), "F8.2");
+        assert_eq!(bintable.at(1, 1).unwrap(), BinTableValue::Null);
+        assert_eq!(
+            bintable.at_physical(2, 2).unwrap(),
+            BinTableValue::Float64(110.0)
+        );
+
+        let back = bintable.to_ascii_table(&[("COUNT", "I4")]).unwrap();
+        assert_eq!(back.tbcol, vec![1, 8, 13]);
+        assert_eq!(back.tform[2].to_string(), "F8.2");
+        assert_eq!(&back.rawbytes[..back.rowbytes], b"alpha    12     1.50");
+        for row in 0..3 {
+            for col in 0..3 {
+                assert_eq!(back.at(row, col).unwrap(), ascii.at(row, col).unwrap());
+            }
+        }
+
+        let binary = crate::bintable::tests::sample_table();
+        let ascii = binary
+            .select_columns(&["NAME", "COUNTS"])
+            .unwrap()
+            .to_ascii_table(&[])
+            .unwrap();
+        assert_eq!(ascii.tform[1].to_string(), "I6");
+        assert_eq!(
+            ascii.at_physical(0, 1).unwrap(),
+            BinTableValue::Float64(0.0)
+        );
+        assert_eq!(
+            ascii.at(1, 0).unwrap(),
+            BinTableValue::String("wxyz".into())
+        );
+        assert!(binary.to_ascii_table(&[]).is_err());
+        assert!(ascii
+            .to_bintable()
+            .unwrap()
+            .to_ascii_table(&[("NAME", "I4")])
+            .is_err());
     }
 }
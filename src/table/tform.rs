@@ -0,0 +1,90 @@
+use crate::TableError;
+
+/// Data type of an ASCII table field
+///
+/// See Table 15 of FITS standard, version 4
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AsciiTFormType {
+    /// `Aw` : Character string
+    Char,
+    /// `Iw` : Decimal integer
+    Int,
+    /// `Fw.d` : Fixed-point real
+    Float,
+    /// `Ew.d` : Single-precision real, exponential notation
+    Exp,
+    /// `Dw.d` : Double-precision real, exponential notation
+    Double,
+}
+
+/// ASCII table field format, from the TFORMn keyword
+///
+/// The format is `Tw` or `Tw.d`, where `T` is the data type, `w` the
+/// width of the field in characters, and `d` the number of digits
+/// after the decimal point
+#[derive(Clone, Debug, PartialEq)]
+pub struct AsciiTForm {
+    pub ftype: AsciiTFormType,
+    pub width: usize,
+    /// Digits after the decimal point, for real fields
+    pub decimals: Option<usize>,
+}
+
+impl std::str::FromStr for AsciiTForm {
+    type Err = TableError;
+
+    /// Parse TFORM string
+    ///
+    /// # Arguments
+    ///
+    /// * `s` - The value of the TFORMn keyword, e.g. "A8", "I6", "F10.3"
+    ///
+    /// # Returns
+    ///
+    /// The parsed format
+    ///
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let bad = || TableError::InvalidTForm(s.to_string());
+        let mut chars = s.chars();
+        let ftype = match chars.next().ok_or_else(bad)? {
+            'A' => AsciiTFormType::Char,
+            'I' => AsciiTFormType::Int,
+            'F' => AsciiTFormType::Float,
+            'E' => AsciiTFormType::Exp,
+            'D' => AsciiTFormType::Double,
+            _ => return Err(bad()),
+        };
+        let rest = chars.as_str();
+        let (width, decimals) = match rest.split_once('.') {
+            Some((w, d)) => (w, Some(d.parse::<usize>().map_err(|_| bad())?)),
+            None => (rest, None),
+        };
+        let width = width.parse::<usize>().map_err(|_| bad())?;
+        if width == 0 {
+            return Err(bad());
+        }
+        Ok(AsciiTForm {
+            ftype,
+            width,
+            decimals,
+        })
+    }
+}
+
+impl std::fmt::Display for AsciiTForm {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let code = match self.ftype {
+            AsciiTFormType::Char => 'A',
+            AsciiTFormType::Int => 'I',
+            AsciiTFormType::Float => 'F',
+            AsciiTFormType::Exp => 'E',
+            AsciiTFormType::Double => 'D',
+        };
+        write!(f, "{}{}", code, self.width)?;
+        if let Some(decimals) = self.decimals {
+            write!(f, ".{}", decimals)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,372 @@
+//! Access common to ASCII and binary tables
+
+use crate::tableprint::format_table;
+use crate::AsciiTFormType;
+use crate::BinTable;
+use crate::BinTableValue;
+use crate::FITSError;
+use crate::FromTableValue;
+use crate::Header;
+use crate::PrintOptions;
+use crate::TForm;
+use crate::TFormType;
+use crate::Table;
+use crate::TableError;
+use crate::Unit;
+
+/// Read access shared by ASCII tables (`Table`) and binary tables
+/// (`BinTable`)
+///
+/// Code written against this trait, such as `to_csv`, works for both
+/// table extensions.  Cell values are `BinTableValue`s for both
+pub trait TableLike {
+    /// Number of rows (NAXIS2)
+    fn nrows(&self) -> usize;
+
+    /// Number of columns (TFIELDS)
+    fn ncols(&self) -> usize;
+
+    /// Header describing the table
+    fn header(&self) -> &Header;
+
+    /// Name of each column; "COLn" (1-based) for columns without TTYPEn
+    fn column_names(&self) -> Vec<String>;
+
+    /// Index of the column with a given name (TTYPEn), matched
+    /// case-insensitively
+    fn column_index(&self, name: &str) -> Result<usize, FITSError>;
+
+    /// Unit of a column (TUNITn)
+    fn unit(&self, col: usize) -> Option<&str>;
+
+    /// Parsed unit of a column
+    ///
+    /// # Returns
+    ///
+    /// The unit, None if the column has no TUNITn, or an error if
+    /// TUNITn is not a valid unit string
+    ///
+    fn column_unit(&self, col: usize) -> Result<Option<Unit>, FITSError> {
+        match self.unit(col) {
+            Some(unit) if !unit.trim().is_empty() => Ok(Some(unit.parse()?)),
+            _ => Ok(None),
+        }
+    }
+
+    /// Number of elements in each cell of a column: 1 for scalars,
+    /// strings, and bit fields, the repeat count for vectors, or None
+    /// for variable-length arrays
+    fn repeat(&self, col: usize) -> Option<usize>;
+
+    /// Binary table format of the values of a column, as returned by
+    /// `at`: for ASCII tables, `Aw` fields are `wA`, `Iw` fields `1K`,
+    /// and real fields `1D`
+    fn column_format(&self, col: usize) -> Option<TForm>;
+
+    /// Scaling of a column, as (TSCALn, TZEROn), or None if neither
+    /// keyword is present
+    fn scaling(&self, col: usize) -> Option<(f64, f64)>;
+
+    /// Value of a table cell, as stored in the file
+    fn at(&self, row: usize, col: usize) -> Result<BinTableValue, FITSError>;
+
+    /// Physical value of a table cell, after TSCALn and TZEROn
+    fn at_physical(&self, row: usize, col: usize) -> Result<BinTableValue, FITSError>;
+
+    /// Physical values of a column, converted to a Rust type
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - Column name (TTYPEn); see `column_index`
+    ///
+    /// # Returns
+    ///
+    /// One value per row, None for nulls, or an error if the column
+    /// does not exist or a value has an incompatible type (e.g. a
+    /// vector cell, or a string read as `f64`)
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use fits::{HDUData, TableLike, FITS};
+    /// # fn main() -> Result<(), fits::FITSError> {
+    /// # let fits = FITS::from_file("table.fits")?;
+    /// # let HDUData::BinTable(table) = &fits[1].data else { return Ok(()) };
+    /// let time: Vec<Option<f64>> = table.column("TIME")?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    fn column<T: FromTableValue>(&self, name: &str) -> Result<Vec<Option<T>>, FITSError>
+    where
+        Self: Sized,
+    {
+        let col = self.column_index(name)?;
+        (0..self.nrows())
+            .map(|row| match self.at_physical(row, col)? {
+                BinTableValue::Null => Ok(None),
+                v => T::from_value(&v).map(Some).ok_or_else(|| {
+                    FITSError::from(TableError::InvalidType(
+                        row,
+                        col,
+                        std::any::type_name::<T>(),
+                    ))
+                }),
+            })
+            .collect()
+    }
+
+    /// Iterate over rows
+    ///
+    /// # Returns
+    ///
+    /// The physical values (see `at_physical`) of each row, one per
+    /// column
+    ///
+    fn rows(&self) -> impl Iterator<Item = Result<Vec<BinTableValue>, FITSError>> + '_
+    where
+        Self: Sized,
+    {
+        (0..self.nrows()).map(|row| {
+            (0..self.ncols())
+                .map(|col| self.at_physical(row, col))
+                .collect()
+        })
+    }
+
+    /// Render the table as aligned text, like the `Display` impls but
+    /// with the given limits
+    ///
+    /// # Arguments
+    ///
+    /// * `options` - Maximum number of rows and line width
+    ///
+    /// # Returns
+    ///
+    /// One line per row under the column names, units, and a line of
+    /// dashes, or an error if a cell cannot be read
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use fits::PrintOptions;
+    /// # use fits::{HDUData, TableLike, FITS};
+    /// # fn main() -> Result<(), fits::FITSError> {
+    /// # let fits = FITS::from_file("table.fits")?;
+    /// # let HDUData::BinTable(table) = &fits[1].data else { return Ok(()) };
+    /// let options = PrintOptions { max_rows: 5, ..Default::default() };
+    /// println!("{}", table.to_pretty_string(&options)?);
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    fn to_pretty_string(&self, options: &PrintOptions) -> Result<String, FITSError>
+    where
+        Self: Sized,
+    {
+        format_table(self, options)
+    }
+
+    /// Convert the table to an Apache Arrow record batch, enabled by
+    /// the `arrow` feature
+    ///
+    /// Columns are named from TTYPEn (`COLn` if absent), with TUNITn
+    /// kept as the "unit" field metadata.  Values are physical values
+    /// (see `at_physical`), with TNULLn values as Arrow nulls.  Column
+    /// types map as follows:
+    ///
+    /// * `L` to Boolean, `A` to Utf8 (trailing blanks removed)
+    /// * `B`, `I`, `J`, `K`, `E`, `D` to UInt8, Int16, Int32, Int64,
+    ///   Float32 and Float64; scaled columns to Float64, or to the
+    ///   unsigned type for offset-binary integers
+    /// * `C` and `M` to a fixed-size list of two floats
+    /// * Repeat counts other than one, and `X` bit fields, to a
+    ///   fixed-size list of elements
+    /// * Variable-length arrays (`P`, `Q`) to a list of elements, or
+    ///   Utf8 for characters
+    ///
+    /// ASCII table columns map by `column_format`: `Aw` to Utf8, `Iw`
+    /// to Int64, and real fields to Float64
+    ///
+    /// # Returns
+    ///
+    /// The record batch, with one row per table row
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use fits::{HDUData, TableLike, FITS};
+    /// # fn main() -> Result<(), fits::FITSError> {
+    /// # let fits = FITS::from_file("table.fits")?;
+    /// # let HDUData::BinTable(table) = &fits[1].data else { return Ok(()) };
+    /// let batch = table.to_record_batch()?;
+    /// println!("{} rows, schema {:?}", batch.num_rows(), batch.schema());
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    #[cfg(feature = "arrow")]
+    fn to_record_batch(&self) -> Result<arrow_array::RecordBatch, FITSError>
+    where
+        Self: Sized,
+    {
+        crate::bintable::record_batch(self)
+    }
+
+    /// Convert the table to a Polars data frame, enabled by the
+    /// `polars` feature
+    ///
+    /// Columns are named from TTYPEn (`COLn` if absent).  Values are
+    /// physical values (see `at_physical`), with TNULLn values as
+    /// nulls.  Column types map as follows:
+    ///
+    /// * `L` to Boolean, `A` to String (trailing blanks removed)
+    /// * `B`, `I`, `J`, `K`, `E`, `D` to UInt8, Int16, Int32, Int64,
+    ///   Float32 and Float64; scaled columns to Float64, or to the
+    ///   unsigned type for offset-binary integers
+    /// * `C` and `M` to a list of two floats, real part first
+    /// * Repeat counts other than one, `X` bit fields, and
+    ///   variable-length arrays (`P`, `Q`) to a list of elements;
+    ///   variable-length character arrays to String
+    ///
+    /// ASCII table columns map as for `to_record_batch`
+    ///
+    /// # Returns
+    ///
+    /// The data frame, with one row per table row
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use fits::{HDUData, TableLike, FITS};
+    /// # fn main() -> Result<(), fits::FITSError> {
+    /// # let fits = FITS::from_file("table.fits")?;
+    /// # let HDUData::BinTable(table) = &fits[1].data else { return Ok(()) };
+    /// let df = table.to_polars()?;
+    /// println!("{}", df.head(Some(5)));
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    #[cfg(feature = "polars")]
+    fn to_polars(&self) -> Result<::polars::prelude::DataFrame, FITSError>
+    where
+        Self: Sized,
+    {
+        crate::bintable::data_frame(self)
+    }
+}
+
+impl TableLike for BinTable {
+    fn nrows(&self) -> usize {
+        self.nrows
+    }
+
+    fn ncols(&self) -> usize {
+        BinTable::ncols(self)
+    }
+
+    fn header(&self) -> &Header {
+        BinTable::header(self)
+    }
+
+    fn column_names(&self) -> Vec<String> {
+        BinTable::column_names(self)
+    }
+
+    fn column_index(&self, name: &str) -> Result<usize, FITSError> {
+        BinTable::column_index(self, name)
+    }
+
+    fn unit(&self, col: usize) -> Option<&str> {
+        self.tunit.get(col)?.as_deref()
+    }
+
+    fn repeat(&self, col: usize) -> Option<usize> {
+        let tform = self.tform.get(col)?;
+        match tform.ftype {
+            TFormType::Char | TFormType::Bit => Some(1),
+            TFormType::ArrayD32(_) | TFormType::ArrayD64(_) => None,
+            _ => Some(tform.repeat),
+        }
+    }
+
+    fn column_format(&self, col: usize) -> Option<TForm> {
+        self.tform.get(col).cloned()
+    }
+
+    fn scaling(&self, col: usize) -> Option<(f64, f64)> {
+        match (*self.tscal.get(col)?, *self.tzero.get(col)?) {
+            (None, None) => None,
+            (tscal, tzero) => Some((tscal.unwrap_or(1.0), tzero.unwrap_or(0.0))),
+        }
+    }
+
+    fn at(&self, row: usize, col: usize) -> Result<BinTableValue, FITSError> {
+        BinTable::at(self, row, col)
+    }
+
+    fn at_physical(&self, row: usize, col: usize) -> Result<BinTableValue, FITSError> {
+        BinTable::at_physical(self, row, col)
+    }
+}
+
+impl TableLike for Table {
+    fn nrows(&self) -> usize {
+        self.nrows
+    }
+
+    fn ncols(&self) -> usize {
+        Table::ncols(self)
+    }
+
+    fn header(&self) -> &Header {
+        Table::header(self)
+    }
+
+    fn column_names(&self) -> Vec<String> {
+        Table::column_names(self)
+    }
+
+    fn column_index(&self, name: &str) -> Result<usize, FITSError> {
+        Table::column_index(self, name)
+    }
+
+    fn unit(&self, col: usize) -> Option<&str> {
+        self.tunit.get(col)?.as_deref()
+    }
+
+    fn repeat(&self, _col: usize) -> Option<usize> {
+        Some(1)
+    }
+
+    fn column_format(&self, col: usize) -> Option<TForm> {
+        let tform = self.tform.get(col)?;
+        let (repeat, ftype) = match tform.ftype {
+            AsciiTFormType::Char => (tform.width, TFormType::Char),
+            AsciiTFormType::Int => (1, TFormType::Int64),
+            _ => (1, TFormType::Float64),
+        };
+        Some(TForm {
+            repeat,
+            ftype,
+            maxlen: None,
+            width: None,
+        })
+    }
+
+    fn scaling(&self, col: usize) -> Option<(f64, f64)> {
+        match (*self.tscal.get(col)?, *self.tzero.get(col)?) {
+            (None, None) => None,
+            (tscal, tzero) => Some((tscal.unwrap_or(1.0), tzero.unwrap_or(0.0))),
+        }
+    }
+
+    fn at(&self, row: usize, col: usize) -> Result<BinTableValue, FITSError> {
+        Table::at(self, row, col)
+    }
+
+    fn at_physical(&self, row: usize, col: usize) -> Result<BinTableValue, FITSError> {
+        Table::at_physical(self, row, col)
+    }
+}
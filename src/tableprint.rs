@@ -0,0 +1,176 @@
+//! Tabular text rendering of ASCII and binary tables
+
+use crate::BinTable;
+use crate::BinTableValue;
+use crate::FITSError;
+use crate::TDisp;
+use crate::Table;
+use crate::TableLike;
+
+/// Options for `TableLike::to_pretty_string` and the `Display` impls of
+/// `Table` and `BinTable`
+#[derive(Clone, Debug)]
+pub struct PrintOptions {
+    /// Maximum number of rows shown; longer tables show the first and
+    /// last rows around a `...` line
+    pub max_rows: usize,
+    /// Maximum line width in characters; columns that do not fit are
+    /// replaced by a `...` column
+    pub max_width: usize,
+}
+
+impl Default for PrintOptions {
+    fn default() -> Self {
+        PrintOptions {
+            max_rows: 20,
+            max_width: 100,
+        }
+    }
+}
+
+/// Text of a cell: TDISPn-formatted if the column has a display
+/// format, `--` for nulls, and `first .. last` for arrays
+fn cell_text(value: &BinTableValue, tdisp: Option<&TDisp>) -> String {
+    match value {
+        BinTableValue::Null => "--".to_string(),
+        BinTableValue::Float32(v) if v.is_nan() => "--".to_string(),
+        BinTableValue::Float64(v) if v.is_nan() => "--".to_string(),
+        BinTableValue::String(s) => s.trim_end_matches([' ', '\0']).to_string(),
+        BinTableValue::Array(values) => match values.as_slice() {
+            [] => "[]".to_string(),
+            [v] => cell_text(v, tdisp),
+            [first, .., last] => {
+                format!("{} .. {}", cell_text(first, tdisp), cell_text(last, tdisp))
+            }
+        },
+        value => match tdisp {
+            Some(tdisp) => tdisp.format(value).trim().to_string(),
+            None => value.to_string(),
+        },
+    }
+}
+
+/// Render a table as aligned text columns
+///
+/// The first lines hold the column names and, if any column has one,
+/// the units, followed by a line of dashes and one line per row.
+/// Values are physical (see `at_physical`) and right-aligned
+pub(crate) fn format_table<T: TableLike>(
+    table: &T,
+    options: &PrintOptions,
+) -> Result<String, FITSError> {
+    let nrows = table.nrows();
+    let rows: Vec<Option<usize>> = match nrows > options.max_rows {
+        true => {
+            let head = options.max_rows.div_ceil(2);
+            let tail = options.max_rows - head;
+            (0..head)
+                .map(Some)
+                .chain(std::iter::once(None))
+                .chain((nrows - tail..nrows).map(Some))
+                .collect()
+        }
+        false => (0..nrows).map(Some).collect(),
+    };
+    let has_units = (0..table.ncols()).any(|col| table.unit(col).is_some());
+
+    // Each column as its lines of text: name, unit, then cells
+    let mut columns: Vec<Vec<String>> = Vec::new();
+    let mut width = 0;
+    for (col, name) in table.column_names().into_iter().enumerate() {
+        let tdisp = table
+            .header()
+            .get::<String>(&format!("TDISP{}", col + 1))
+            .ok()
+            .and_then(|t| t.parse::<TDisp>().ok());
+        let mut lines = vec![name];
+        if has_units {
+            lines.push(table.unit(col).unwrap_or_default().to_string());
+        }
+        for row in &rows {
+            lines.push(match row {
+                Some(row) => cell_text(&table.at_physical(*row, col)?, tdisp.as_ref()),
+                None => "...".to_string(),
+            });
+        }
+        let colwidth = lines.iter().map(|l| l.chars().count()).max().unwrap_or(0);
+        // Leave room for a "..." column if later columns are dropped
+        let more = col + 1 < table.ncols();
+        let needed = width + colwidth + if more { 4 } else { 0 };
+        if !columns.is_empty() && needed > options.max_width {
+            columns.push(vec!["...".to_string(); lines.len()]);
+            break;
+        }
+        width += colwidth + 1;
+        columns.push(lines);
+    }
+
+    let widths: Vec<usize> = columns
+        .iter()
+        .map(|lines| lines.iter().map(|l| l.chars().count()).max().unwrap_or(0))
+        .collect();
+    let nheader = if has_units { 2 } else { 1 };
+    let nlines = nheader + rows.len();
+    let mut text = String::new();
+    for line in 0..nlines {
+        let cells: Vec<String> = columns
+            .iter()
+            .zip(&widths)
+            .map(|(lines, w)| format!("{:>w$}", lines[line], w = *w))
+            .collect();
+        text.push_str(cells.join(" ").trim_end());
+        text.push('\n');
+        if line + 1 == nheader {
+            let dashes: Vec<String> = widths.iter().map(|w| "-".repeat(*w)).collect();
+            text.push_str(&dashes.join(" "));
+            text.push('\n');
+        }
+    }
+    if nrows > options.max_rows {
+        text.push_str(&format!("Length = {} rows\n", nrows));
+    }
+    Ok(text)
+}
+
+impl std::fmt::Display for BinTable {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let text = format_table(self, &PrintOptions::default()).map_err(|_| std::fmt::Error)?;
+        write!(f, "{}", text)
+    }
+}
+
+impl std::fmt::Display for Table {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let text = format_table(self, &PrintOptions::default()).map_err(|_| std::fmt::Error)?;
+        write!(f, "{}", text)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_table() {
+        let table = crate::table::tests::sample_table();
+        assert_eq!(
+            table.to_string(),
+            " NAME COUNT  FLUX\n\
+             \x20              Jy\n\
+             ----- ----- -----\n\
+             alpha    12 101.5\n\
+             \x20beta    -- 102.5\n\
+             gamma    --   110\n"
+        );
+
+        let table = crate::bintable::tests::sample_table();
+        let options = PrintOptions {
+            max_rows: 1,
+            max_width: 12,
+        };
+        assert_eq!(
+            table.to_pretty_string(&options).unwrap(),
+            "COUNTS ...\n------ ---\n     0 ...\n   ... ...\nLength = 2 rows\n"
+        );
+    }
+}
@@ -0,0 +1,61 @@
+//! Helpers shared by the unit tests
+
+use crate::Bitpix;
+use crate::Header;
+use crate::Image;
+use crate::Keyword;
+use crate::KeywordValue;
+
+/// Keyword with the given value and no comment or unit
+pub(crate) fn kw(name: &str, value: KeywordValue) -> Keyword {
+    Keyword {
+        name: name.to_string(),
+        value,
+        ..Default::default()
+    }
+}
+
+/// Card text, each card padded with blanks to 80 characters
+pub(crate) fn cards(cards: &[&str]) -> Vec<u8> {
+    cards
+        .iter()
+        .flat_map(|c| format!("{:<80}", c).into_bytes())
+        .collect()
+}
+
+/// Header with a keyword parsed from each card
+pub(crate) fn header(cards: &[&str]) -> Header {
+    Header::new(
+        cards
+            .iter()
+            .map(|c| Keyword::new(format!("{:<80}", c).as_bytes()).unwrap())
+            .collect(),
+    )
+}
+
+/// Bytes of an HDU: the cards padded with blanks to whole blocks,
+/// followed by the data padded with zeros to whole blocks
+pub(crate) fn hdu_bytes(cards: &[&str], data: &[u8]) -> Vec<u8> {
+    let mut raw = self::cards(cards);
+    raw.resize(raw.len().div_ceil(2880) * 2880, b' ');
+    raw.extend(data);
+    raw.resize(raw.len().div_ceil(2880) * 2880, 0);
+    raw
+}
+
+/// Image of pixels in native byte order, with no scaling, blank
+/// value, unit or WCS
+pub(crate) fn image<T: bytemuck::Pod>(pixeltype: Bitpix, axes: &[usize], pixels: &[T]) -> Image {
+    Image {
+        pixeltype,
+        axes: axes.to_vec(),
+        rawbytes: bytemuck::cast_slice(pixels).to_vec(),
+        bscale: 1.0,
+        bzero: 0.0,
+        blank: None,
+        bunit: None,
+        wcs: None,
+        alt_wcs: Default::default(),
+        native: true,
+    }
+}
@@ -1,23 +1,39 @@
+use crate::FITSError;
+
 #[derive(Debug, Clone)]
 pub enum HDUData {
     None,
     Table(Box<crate::Table>),
+    BinTable(Box<crate::BinTable>),
     Image(Box<crate::Image>),
+    /// Extension of a type this library does not interpret, kept as
+    /// raw bytes (without fill) so it can be written back unchanged
+    Unknown {
+        xtension: String,
+        raw: Vec<u8>,
+    },
 }
 
 /// Bit Pix Types
+///
+/// The unsigned types are not distinct BITPIX values in the file, but
+/// are signed integers stored with an offset of BZERO = 2^(bits-1)
+/// and BSCALE = 1.  See Section 5.3 of FITS standard, version 4
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum Bitpix {
     Int8,
     Int16,
     Int32,
     Int64,
+    Uint16,
+    Uint32,
+    Uint64,
     Float32,
     Float64,
 }
 
 impl Bitpix {
-    pub fn from_i64(value: i64) -> Result<Self, Box<dyn std::error::Error>> {
+    pub fn from_i64(value: i64) -> Result<Self, FITSError> {
         match value {
             8 => Ok(Bitpix::Int8),
             16 => Ok(Bitpix::Int16),
@@ -25,19 +41,16 @@ impl Bitpix {
             64 => Ok(Bitpix::Int64),
             -32 => Ok(Bitpix::Float32),
             -64 => Ok(Bitpix::Float64),
-            _ => Err(Box::new(std::io::Error::new(
-                std::io::ErrorKind::InvalidData,
-                "Invalid BITPIX value",
-            ))),
+            _ => Err(FITSError::InvalidBitpix(value)),
         }
     }
 
     pub fn to_i64(&self) -> i64 {
         match self {
             Bitpix::Int8 => 8,
-            Bitpix::Int16 => 16,
-            Bitpix::Int32 => 32,
-            Bitpix::Int64 => 64,
+            Bitpix::Int16 | Bitpix::Uint16 => 16,
+            Bitpix::Int32 | Bitpix::Uint32 => 32,
+            Bitpix::Int64 | Bitpix::Uint64 => 64,
             Bitpix::Float32 => -32,
             Bitpix::Float64 => -64,
         }
@@ -46,11 +59,68 @@ impl Bitpix {
     pub fn size(&self) -> usize {
         match self {
             Bitpix::Int8 => 1,
-            Bitpix::Int16 => 2,
-            Bitpix::Int32 => 4,
-            Bitpix::Int64 => 8,
+            Bitpix::Int16 | Bitpix::Uint16 => 2,
+            Bitpix::Int32 | Bitpix::Uint32 => 4,
+            Bitpix::Int64 | Bitpix::Uint64 => 8,
             Bitpix::Float32 => 4,
             Bitpix::Float64 => 8,
         }
     }
+
+    /// The BZERO offset used to store unsigned types as signed integers
+    ///
+    /// # Returns
+    ///
+    /// The offset for unsigned types, otherwise None
+    ///
+    pub fn unsigned_offset(&self) -> Option<f64> {
+        match self {
+            Bitpix::Uint16 => Some(32768.0),
+            Bitpix::Uint32 => Some(2147483648.0),
+            Bitpix::Uint64 => Some(9223372036854775808.0),
+            _ => None,
+        }
+    }
+
+    /// The unsigned type corresponding to a signed integer type
+    pub fn to_unsigned(&self) -> Option<Self> {
+        match self {
+            Bitpix::Int16 => Some(Bitpix::Uint16),
+            Bitpix::Int32 => Some(Bitpix::Uint32),
+            Bitpix::Int64 => Some(Bitpix::Uint64),
+            _ => None,
+        }
+    }
+}
+
+/// Rust types of image pixels, tied to their `Bitpix`
+///
+/// Implemented for the types listed under `Image::at`; lets pixel
+/// accessors such as `Image::try_at` check the requested type against
+/// the image's pixel type
+pub trait Pixel: bytemuck::Pod {
+    /// Pixel type stored as this Rust type
+    const BITPIX: Bitpix;
 }
+
+macro_rules! impl_pixel {
+    ($($t:ty => $bitpix:ident),* $(,)?) => {
+        $(
+            impl Pixel for $t {
+                const BITPIX: Bitpix = Bitpix::$bitpix;
+            }
+        )*
+    };
+}
+
+impl_pixel!(
+    u8 => Int8,
+    i16 => Int16,
+    i32 => Int32,
+    i64 => Int64,
+    u16 => Uint16,
+    u32 => Uint32,
+    u64 => Uint64,
+    f32 => Float32,
+    f64 => Float64,
+);
@@ -0,0 +1,493 @@
+//! Physical units, as given by TUNITn, CUNITia, and BUNIT
+//!
+//! Unit strings follow Section 4.3 of FITS standard, version 4: unit
+//! symbols with optional SI prefixes, multiplied with `.`, `*`, or a
+//! blank, divided with `/`, raised to powers with `**`, `^`, or a
+//! trailing integer (`cm-2`), grouped with parentheses, and optionally
+//! scaled by a power of ten (`10**-17 erg/s/cm**2/Angstrom`)
+
+use crate::UnitError;
+
+/// Dimensions units are expressed in: the SI base units, with angles
+/// and the counting units of astronomy kept as separate dimensions
+const DIMENSIONS: [&str; 16] = [
+    "m", "kg", "s", "A", "K", "mol", "cd", "rad", "count", "pixel", "bit", "mag", "adu", "beam",
+    "chan", "bin",
+];
+const NDIMS: usize = DIMENSIONS.len();
+
+/// Index of a dimension in `DIMENSIONS`
+fn dim(name: &str) -> usize {
+    DIMENSIONS.iter().position(|d| *d == name).unwrap_or(0)
+}
+
+/// SI prefixes and their powers of ten
+const PREFIXES: &[(&str, i32)] = &[
+    ("da", 1),
+    ("y", -24),
+    ("z", -21),
+    ("a", -18),
+    ("f", -15),
+    ("p", -12),
+    ("n", -9),
+    ("u", -6),
+    ("m", -3),
+    ("c", -2),
+    ("d", -1),
+    ("h", 2),
+    ("k", 3),
+    ("M", 6),
+    ("G", 9),
+    ("T", 12),
+    ("P", 15),
+    ("E", 18),
+    ("Z", 21),
+    ("Y", 24),
+];
+
+/// Unit symbols: name, scale in SI units, SI expression, and whether
+/// the symbol takes SI prefixes
+///
+/// See Tables 4 to 7 of FITS standard, version 4
+const SYMBOLS: &[(&str, f64, &str, bool)] = &[
+    ("m", 1.0, "m", true),
+    ("g", 1e-3, "kg", true),
+    ("s", 1.0, "s", true),
+    ("rad", 1.0, "rad", true),
+    ("sr", 1.0, "rad2", true),
+    ("K", 1.0, "K", true),
+    ("A", 1.0, "A", true),
+    ("mol", 1.0, "mol", true),
+    ("cd", 1.0, "cd", true),
+    ("Hz", 1.0, "s-1", true),
+    ("J", 1.0, "kg.m2.s-2", true),
+    ("W", 1.0, "kg.m2.s-3", true),
+    ("V", 1.0, "kg.m2.s-3.A-1", true),
+    ("N", 1.0, "kg.m.s-2", true),
+    ("Pa", 1.0, "kg.m-1.s-2", true),
+    ("C", 1.0, "A.s", true),
+    ("Ohm", 1.0, "kg.m2.s-3.A-2", true),
+    ("S", 1.0, "kg-1.m-2.s3.A2", true),
+    ("F", 1.0, "kg-1.m-2.s4.A2", true),
+    ("Wb", 1.0, "kg.m2.s-2.A-1", true),
+    ("T", 1.0, "kg.s-2.A-1", true),
+    ("H", 1.0, "kg.m2.s-2.A-2", true),
+    ("lm", 1.0, "cd.rad2", true),
+    ("lx", 1.0, "cd.rad2.m-2", true),
+    ("deg", std::f64::consts::PI / 180.0, "rad", false),
+    ("arcmin", std::f64::consts::PI / 10800.0, "rad", false),
+    ("arcsec", std::f64::consts::PI / 648000.0, "rad", false),
+    ("mas", std::f64::consts::PI / 648000000.0, "rad", false),
+    ("min", 60.0, "s", false),
+    ("h", 3600.0, "s", false),
+    ("d", 86400.0, "s", false),
+    ("a", 31557600.0, "s", true),
+    ("yr", 31557600.0, "s", true),
+    ("eV", 1.602176634e-19, "kg.m2.s-2", true),
+    ("erg", 1e-7, "kg.m2.s-2", true),
+    ("Ry", 13.605693122994 * 1.602176634e-19, "kg.m2.s-2", false),
+    ("solMass", 1.988409870698051e30, "kg", false),
+    ("u", 1.66053906660e-27, "kg", false),
+    ("solLum", 3.828e26, "kg.m2.s-3", false),
+    ("Angstrom", 1e-10, "m", false),
+    ("angstrom", 1e-10, "m", false),
+    ("solRad", 6.957e8, "m", false),
+    ("AU", 1.495978707e11, "m", false),
+    ("au", 1.495978707e11, "m", false),
+    ("lyr", 9.4607304725808e15, "m", false),
+    ("pc", 3.085_677_581_491_367e16, "m", true),
+    ("count", 1.0, "count", false),
+    ("ct", 1.0, "count", false),
+    ("photon", 1.0, "count", false),
+    ("ph", 1.0, "count", false),
+    ("Jy", 1e-26, "kg.s-2", true),
+    ("mag", 1.0, "mag", true),
+    (
+        "R",
+        1e10 / (4.0 * std::f64::consts::PI),
+        "count.m-2.s-1.rad-2",
+        true,
+    ),
+    ("G", 1e-4, "kg.s-2.A-1", true),
+    ("barn", 1e-28, "m2", true),
+    ("D", 1e-21 / 299792458.0, "A.s.m", true),
+    ("pixel", 1.0, "pixel", false),
+    ("pix", 1.0, "pixel", false),
+    ("voxel", 1.0, "pixel", false),
+    ("bit", 1.0, "bit", true),
+    ("byte", 8.0, "bit", true),
+    ("adu", 1.0, "adu", false),
+    ("beam", 1.0, "beam", false),
+    ("chan", 1.0, "chan", false),
+    ("bin", 1.0, "bin", false),
+];
+
+/// A physical unit: a scale factor times a product of powers of the
+/// base dimensions
+///
+/// # Example
+///
+/// ```
+/// # use fits::Unit;
+/// # fn main() -> Result<(), fits::UnitError> {
+/// let flux: Unit = "erg/s/cm**2/Angstrom".parse()?;
+/// let si: Unit = "W.m-3".parse()?;
+/// assert_eq!(flux.conversion_factor(&si)?, 1e7);
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct Unit {
+    /// Value of the unit in SI base units
+    pub scale: f64,
+    /// Power of each base dimension
+    dims: [f64; NDIMS],
+}
+
+impl Unit {
+    /// The dimensionless unit
+    pub fn dimensionless() -> Self {
+        Unit {
+            scale: 1.0,
+            dims: [0.0; NDIMS],
+        }
+    }
+
+    fn mul(&self, other: &Unit) -> Unit {
+        let mut dims = self.dims;
+        for (d, o) in dims.iter_mut().zip(other.dims) {
+            *d += o;
+        }
+        Unit {
+            scale: self.scale * other.scale,
+            dims,
+        }
+    }
+
+    fn powf(&self, power: f64) -> Unit {
+        Unit {
+            scale: self.scale.powf(power),
+            dims: self.dims.map(|d| d * power),
+        }
+    }
+
+    /// Whether the unit has no dimension (it may still have a scale,
+    /// as in "10**-3")
+    pub fn is_dimensionless(&self) -> bool {
+        self.dims.iter().all(|d| d.abs() < 1e-9)
+    }
+
+    /// Whether values can be converted between the two units
+    pub fn is_compatible(&self, other: &Unit) -> bool {
+        self.dims
+            .iter()
+            .zip(other.dims)
+            .all(|(a, b)| (a - b).abs() < 1e-9)
+    }
+
+    /// Factor converting values in this unit to another unit
+    ///
+    /// # Arguments
+    ///
+    /// * `to` - Target unit
+    ///
+    /// # Returns
+    ///
+    /// The factor by which to multiply values, or an error if the
+    /// units have different dimensions
+    ///
+    pub fn conversion_factor(&self, to: &Unit) -> Result<f64, UnitError> {
+        match self.is_compatible(to) {
+            true => Ok(self.scale / to.scale),
+            false => Err(UnitError::Incompatible(self.to_string(), to.to_string())),
+        }
+    }
+}
+
+/// Unit in SI base units, e.g. "1e-7 m2.kg.s-2" for erg
+impl std::fmt::Display for Unit {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let powers: Vec<String> = DIMENSIONS
+            .iter()
+            .zip(self.dims)
+            .filter(|(_, p)| p.abs() >= 1e-9)
+            .map(|(name, p)| match p {
+                p if (p - 1.0).abs() < 1e-9 => name.to_string(),
+                p if (p - p.round()).abs() < 1e-9 => format!("{}{}", name, p.round()),
+                p => format!("{}**({})", name, p),
+            })
+            .collect();
+        match (self.scale == 1.0, powers.is_empty()) {
+            (true, true) => write!(f, "1"),
+            (true, false) => write!(f, "{}", powers.join(".")),
+            (false, true) => write!(f, "{:e}", self.scale),
+            (false, false) => write!(f, "{:e} {}", self.scale, powers.join(".")),
+        }
+    }
+}
+
+impl std::str::FromStr for Unit {
+    type Err = UnitError;
+
+    /// Parse a FITS unit string
+    ///
+    /// # Arguments
+    ///
+    /// * `s` - Unit string, e.g. "km/s", "erg/s/cm**2", "10**(-6) Jy"
+    ///
+    /// # Returns
+    ///
+    /// The unit, or an error if the string is not valid or uses an
+    /// unknown symbol
+    ///
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parser = Parser {
+            text: s,
+            chars: s.trim().chars().collect(),
+            pos: 0,
+        };
+        if parser.chars.is_empty() {
+            return Ok(Unit::dimensionless());
+        }
+        let unit = parser.expression()?;
+        match parser.peek() {
+            None => Ok(unit),
+            Some(c) => Err(parser.error(&format!("unexpected '{}'", c))),
+        }
+    }
+}
+
+/// Unit of a single symbol, with an optional SI prefix
+fn symbol(name: &str) -> Option<Unit> {
+    let base = |name: &str, prefixed: bool| -> Option<Unit> {
+        let (_, scale, si, prefixable) = SYMBOLS.iter().find(|(n, ..)| *n == name)?;
+        if prefixed && !prefixable {
+            return None;
+        }
+        let mut unit = Unit {
+            scale: *scale,
+            dims: [0.0; NDIMS],
+        };
+        for part in si.split('.') {
+            let split = part
+                .find(|c: char| c == '-' || c.is_ascii_digit())
+                .unwrap_or(part.len());
+            let power = part[split..].parse::<f64>().unwrap_or(1.0);
+            unit.dims[dim(&part[..split])] += power;
+        }
+        Some(unit)
+    };
+    base(name, false).or_else(|| {
+        PREFIXES.iter().find_map(|(prefix, power)| {
+            let rest = name.strip_prefix(prefix)?;
+            let mut unit = base(rest, true)?;
+            unit.scale *= 10f64.powi(*power);
+            Some(unit)
+        })
+    })
+}
+
+/// Recursive-descent parser of unit strings
+struct Parser<'a> {
+    text: &'a str,
+    chars: Vec<char>,
+    pos: usize,
+}
+
+impl Parser<'_> {
+    fn error(&self, reason: &str) -> UnitError {
+        UnitError::InvalidUnit(self.text.to_string(), reason.to_string())
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn skip_blanks(&mut self) -> bool {
+        let start = self.pos;
+        while self.peek() == Some(' ') {
+            self.pos += 1;
+        }
+        self.pos > start
+    }
+
+    fn take_while(&mut self, f: impl Fn(char) -> bool) -> String {
+        let start = self.pos;
+        while self.peek().is_some_and(&f) {
+            self.pos += 1;
+        }
+        self.chars[start..self.pos].iter().collect()
+    }
+
+    /// expression := product { '/' product }
+    fn expression(&mut self) -> Result<Unit, UnitError> {
+        let mut unit = self.product()?;
+        loop {
+            self.skip_blanks();
+            if self.peek() != Some('/') {
+                return Ok(unit);
+            }
+            self.pos += 1;
+            self.skip_blanks();
+            unit = unit.mul(&self.product()?.powf(-1.0));
+        }
+    }
+
+    /// product := factor { ('.' | '*' | ' ') factor }
+    fn product(&mut self) -> Result<Unit, UnitError> {
+        let mut unit = self.factor()?;
+        loop {
+            let blank = self.skip_blanks();
+            match self.peek() {
+                Some('.') | Some('*') if self.chars.get(self.pos + 1) != Some(&'*') => {
+                    self.pos += 1;
+                    self.skip_blanks();
+                }
+                Some(c) if blank && c != '/' && c != ')' => {}
+                _ => return Ok(unit),
+            }
+            unit = unit.mul(&self.factor()?);
+        }
+    }
+
+    /// factor := atom [ ('**' | '^') power ]
+    fn factor(&mut self) -> Result<Unit, UnitError> {
+        let unit = self.atom()?;
+        let rest: String = self.chars[self.pos..].iter().take(2).collect();
+        if rest == "**" {
+            self.pos += 2;
+        } else if rest.starts_with('^') {
+            self.pos += 1;
+        } else {
+            return Ok(unit);
+        }
+        let power = self.power()?;
+        Ok(unit.powf(power))
+    }
+
+    /// power := number | '(' number [ '/' number ] ')'
+    fn power(&mut self) -> Result<f64, UnitError> {
+        if self.peek() != Some('(') {
+            return self.number();
+        }
+        self.pos += 1;
+        let mut power = self.number()?;
+        if self.peek() == Some('/') {
+            self.pos += 1;
+            power /= self.number()?;
+        }
+        match self.peek() {
+            Some(')') => {
+                self.pos += 1;
+                Ok(power)
+            }
+            _ => Err(self.error("expected ')'")),
+        }
+    }
+
+    fn number(&mut self) -> Result<f64, UnitError> {
+        let start = self.pos;
+        if matches!(self.peek(), Some('+') | Some('-')) {
+            self.pos += 1;
+        }
+        self.take_while(|c| c.is_ascii_digit() || c == '.');
+        let text: String = self.chars[start..self.pos].iter().collect();
+        text.parse::<f64>()
+            .map_err(|_| self.error(&format!("invalid number \"{}\"", text)))
+    }
+
+    /// atom := '(' expression ')' | 'sqrt(' expression ')' | number
+    ///       | symbol [ integer ]
+    fn atom(&mut self) -> Result<Unit, UnitError> {
+        match self.peek() {
+            Some('(') => {
+                self.pos += 1;
+                self.skip_blanks();
+                let unit = self.expression()?;
+                self.skip_blanks();
+                match self.peek() {
+                    Some(')') => {
+                        self.pos += 1;
+                        Ok(unit)
+                    }
+                    _ => Err(self.error("expected ')'")),
+                }
+            }
+            Some(c) if c.is_ascii_digit() || c == '+' || c == '-' => {
+                // Scale factor, e.g. "10**-17" or "1e-3"
+                let mut scale = self.number()?;
+                if self.peek() == Some('e') || self.peek() == Some('E') {
+                    self.pos += 1;
+                    scale *= 10f64.powf(self.number()?);
+                }
+                Ok(Unit {
+                    scale,
+                    dims: [0.0; NDIMS],
+                })
+            }
+            Some(c) if c.is_ascii_alphabetic() => {
+                let name = self.take_while(|c| c.is_ascii_alphabetic());
+                if name == "sqrt" && self.peek() == Some('(') {
+                    return Ok(self.atom()?.powf(0.5));
+                }
+                let unit = symbol(&name)
+                    .ok_or_else(|| UnitError::UnknownUnit(name.clone(), self.text.to_string()))?;
+                // Trailing integer power, e.g. "cm-2" or "m2"
+                if self
+                    .peek()
+                    .is_some_and(|c| c.is_ascii_digit() || c == '-' || c == '+')
+                {
+                    let start = self.pos;
+                    self.pos += 1;
+                    self.take_while(|c| c.is_ascii_digit());
+                    let text: String = self.chars[start..self.pos].iter().collect();
+                    let power = text
+                        .parse::<i32>()
+                        .map_err(|_| self.error(&format!("invalid power \"{}\"", text)))?;
+                    return Ok(unit.powf(power as f64));
+                }
+                Ok(unit)
+            }
+            Some(c) => Err(self.error(&format!("unexpected '{}'", c))),
+            None => Err(self.error("expected a unit")),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn factor(from: &str, to: &str) -> f64 {
+        let from: Unit = from.parse().unwrap();
+        from.conversion_factor(&to.parse().unwrap()).unwrap()
+    }
+
+    fn close(a: f64, b: f64) -> bool {
+        (a - b).abs() <= 1e-12 * b.abs()
+    }
+
+    #[test]
+    fn test_units() {
+        assert!(close(factor("km/s", "m/s"), 1000.0));
+        assert!(close(factor("erg/s/cm**2/Angstrom", "W.m-3"), 1e7));
+        assert!(close(
+            factor("10**(-17) erg/s/cm2/Angstrom", "W m^-3"),
+            1e-10
+        ));
+        assert!(close(factor("deg", "arcsec"), 3600.0));
+        assert!(close(factor("mJy", "W/(m2.Hz)"), 1e-29));
+        assert!(close(factor("Hz**(1/2)", "sqrt(kHz)"), 1e-3f64.sqrt()));
+        assert!(close(factor("kpc", "pc"), 1000.0));
+        assert!(close(factor("count/s", "ct.s-1"), 1.0));
+        assert!(close(factor("Myr", "yr"), 1e6));
+
+        let deg: Unit = "deg".parse().unwrap();
+        assert!(deg.conversion_factor(&"m".parse().unwrap()).is_err());
+        assert!("".parse::<Unit>().unwrap().is_dimensionless());
+        assert!("furlong".parse::<Unit>().is_err());
+        assert!("m/(s".parse::<Unit>().is_err());
+        assert!("kdeg".parse::<Unit>().is_err());
+        assert_eq!("erg".parse::<Unit>().unwrap().to_string(), "1e-7 m2.kg.s-2");
+    }
+}
@@ -0,0 +1,106 @@
+//! JavaScript bindings, enabled by the `wasm` feature
+//!
+//! Build for the browser as a `cdylib` and generate the JavaScript glue
+//! with the `wasm-bindgen` tool:
+//!
+//! ```sh
+//! cargo rustc --lib --release --target wasm32-unknown-unknown --features wasm --crate-type cdylib
+//! wasm-bindgen --target web --out-dir pkg target/wasm32-unknown-unknown/release/fits.wasm
+//! ```
+//!
+//! then:
+//!
+//! ```js
+//! const bytes = new Uint8Array(await (await fetch("image.fits")).arrayBuffer());
+//! const fits = new FITS(bytes);
+//! const axes = fits.axes(0);       // Uint32Array, e.g. [NAXIS1, NAXIS2]
+//! const pixels = fits.pixels(0);   // Float32Array, first axis fastest
+//! const header = JSON.parse(fits.header(0));
+//! ```
+
+use wasm_bindgen::prelude::*;
+
+use crate::FITSError;
+use crate::HDUData;
+use crate::Image;
+use crate::FITS;
+
+/// Convert an error to a JavaScript `Error`
+fn js_error(e: FITSError) -> JsError {
+    JsError::new(&e.to_string())
+}
+
+/// A FITS file read from bytes, for use from JavaScript
+#[wasm_bindgen(js_name = FITS)]
+pub struct WasmFITS {
+    fits: FITS,
+}
+
+impl WasmFITS {
+    fn image(&self, index: usize) -> Result<&Image, JsError> {
+        match &self.fits.at(index).map_err(js_error)?.data {
+            HDUData::Image(image) => Ok(image),
+            _ => Err(JsError::new(&format!("HDU {} is not an image", index))),
+        }
+    }
+}
+
+#[wasm_bindgen(js_class = FITS)]
+impl WasmFITS {
+    /// Parse the contents of a FITS file
+    #[wasm_bindgen(constructor)]
+    pub fn new(bytes: &[u8]) -> Result<WasmFITS, JsError> {
+        Ok(WasmFITS {
+            fits: FITS::from_bytes(bytes).map_err(js_error)?,
+        })
+    }
+
+    /// Number of HDUs
+    #[wasm_bindgen(getter)]
+    pub fn length(&self) -> usize {
+        self.fits.len()
+    }
+
+    /// Kind of data of an HDU: "image", "bintable", "table", "none",
+    /// or the XTENSION of an uninterpreted extension
+    pub fn kind(&self, index: usize) -> Result<String, JsError> {
+        Ok(match &self.fits.at(index).map_err(js_error)?.data {
+            HDUData::None => "none".to_string(),
+            HDUData::Image(_) => "image".to_string(),
+            HDUData::BinTable(_) => "bintable".to_string(),
+            HDUData::Table(_) => "table".to_string(),
+            HDUData::Unknown { xtension, .. } => xtension.clone(),
+        })
+    }
+
+    /// Extension name of an HDU, from EXTNAME
+    pub fn extname(&self, index: usize) -> Result<Option<String>, JsError> {
+        Ok(self
+            .fits
+            .at(index)
+            .map_err(js_error)?
+            .extname()
+            .map(str::to_string))
+    }
+
+    /// Header of an HDU as JSON; see `Header::to_json`
+    pub fn header(&self, index: usize) -> Result<String, JsError> {
+        Ok(self.fits.at(index).map_err(js_error)?.header.to_json())
+    }
+
+    /// Axis lengths of an image, NAXIS1 first
+    pub fn axes(&self, index: usize) -> Result<Vec<u32>, JsError> {
+        Ok(self.image(index)?.axes.iter().map(|&n| n as u32).collect())
+    }
+
+    /// Physical pixel values of an image, with the first axis varying
+    /// fastest; undefined pixels are NaN.  See `Image::physical_pixels`
+    pub fn pixels(&self, index: usize) -> Result<Vec<f32>, JsError> {
+        Ok(self
+            .image(index)?
+            .physical_pixels()
+            .into_iter()
+            .map(|v| v as f32)
+            .collect())
+    }
+}
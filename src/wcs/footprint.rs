@@ -0,0 +1,139 @@
+use crate::FITSError;
+use crate::WCSError;
+use crate::WCS;
+
+/// Angular separation between two positions, in degrees
+fn separation(lon1: f64, lat1: f64, lon2: f64, lat2: f64) -> f64 {
+    let (slat1, clat1) = lat1.to_radians().sin_cos();
+    let (slat2, clat2) = lat2.to_radians().sin_cos();
+    let (sdlon, cdlon) = (lon2 - lon1).to_radians().sin_cos();
+    let x = clat2 * sdlon;
+    let y = clat1 * slat2 - slat1 * clat2 * cdlon;
+    let z = slat1 * slat2 + clat1 * clat2 * cdlon;
+    x.hypot(y).atan2(z).to_degrees()
+}
+
+impl WCS {
+    /// World coordinates of the corners of an image
+    ///
+    /// Corners are taken at the outer edges of the corner pixels,
+    /// so that the footprint covers the whole image.  Non-celestial
+    /// axes are evaluated at their first pixel.
+    ///
+    /// # Arguments
+    ///
+    /// * `axes` - Image dimensions (NAXISn), one per WCS axis
+    ///
+    /// # Returns
+    ///
+    /// Celestial (longitude, latitude) of the four corners in degrees,
+    /// in order (first, first), (last, first), (last, last), (first, last)
+    /// along the longitude and latitude pixel axes
+    ///
+    pub fn footprint(&self, axes: &[usize]) -> Result<Vec<(f64, f64)>, FITSError> {
+        let n = self.naxes();
+        if axes.len() != n {
+            return Err(FITSError::from(WCSError::DimensionMismatch(n, axes.len())));
+        }
+        let (lon, lat) = self
+            .celestial_axes()
+            .ok_or(WCSError::MissingKeyword("celestial CTYPEn".to_string()))?;
+        let xmax = axes[lon] as f64 - 0.5;
+        let ymax = axes[lat] as f64 - 0.5;
+        [(-0.5, -0.5), (xmax, -0.5), (xmax, ymax), (-0.5, ymax)]
+            .iter()
+            .map(|&(x, y)| {
+                let mut pixel = vec![0.0; n];
+                pixel[lon] = x;
+                pixel[lat] = y;
+                let world = self.pixel_to_world(&pixel)?;
+                Ok((world[lon], world[lat]))
+            })
+            .collect()
+    }
+
+    /// Test whether a celestial position falls within an image
+    ///
+    /// # Arguments
+    ///
+    /// * `axes` - Image dimensions (NAXISn), one per WCS axis
+    /// * `lon` - Longitude (e.g. right ascension) in degrees
+    /// * `lat` - Latitude (e.g. declination) in degrees
+    ///
+    /// # Returns
+    ///
+    /// True if the position maps to a pixel within the image.  Positions
+    /// that cannot be projected, or that only map into the image through
+    /// an ambiguity of the projection (e.g. the far hemisphere for SIN),
+    /// are outside.  Always false if there are no celestial axes.
+    ///
+    pub fn contains(&self, axes: &[usize], lon: f64, lat: f64) -> bool {
+        let n = self.naxes();
+        let Some((ilon, ilat)) = self.celestial_axes() else {
+            return false;
+        };
+        if axes.len() != n {
+            return false;
+        }
+        let Ok(mut world) = self.pixel_to_world(&vec![0.0; n]) else {
+            return false;
+        };
+        world[ilon] = lon;
+        world[ilat] = lat;
+        let Ok(pixel) = self.world_to_pixel(&world) else {
+            return false;
+        };
+        let inside = [ilon, ilat]
+            .iter()
+            .all(|&i| pixel[i] >= -0.5 && pixel[i] <= axes[i] as f64 - 0.5);
+        if !inside {
+            return false;
+        }
+        // Reject positions that do not map back to themselves
+        match self.pixel_to_world(&pixel) {
+            Ok(w) => separation(w[ilon], w[ilat], lon, lat) < 1.0e-6,
+            Err(_) => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_footprint() {
+        let wcs = WCS {
+            ctype: Some(vec!["RA---TAN".into(), "DEC--TAN".into()]),
+            crval: Some(vec![0.5, 0.0]),
+            crpix: Some(vec![50.5, 50.5]),
+            cdelt: Some(vec![-0.01, 0.01]),
+            ..Default::default()
+        };
+        let corners = wcs.footprint(&[100, 100]).unwrap();
+        assert_eq!(corners.len(), 4);
+        // Image spans 1 degree, crossing RA = 0
+        assert!((corners[0].0 - 1.0).abs() < 1.0e-4 && (corners[0].1 + 0.5).abs() < 1.0e-4);
+        assert!(separation(corners[2].0, corners[2].1, 0.0, 0.5) < 1.0e-4);
+
+        assert!(wcs.contains(&[100, 100], 0.5, 0.0));
+        assert!(wcs.contains(&[100, 100], 0.1, 0.4));
+        assert!(!wcs.contains(&[100, 100], 1.2, 0.0));
+        assert!(!wcs.contains(&[100, 100], 180.5, 0.0));
+        assert!(wcs.footprint(&[100]).is_err());
+    }
+
+    #[test]
+    fn test_contains_far_side() {
+        // SIN maps both hemispheres onto the same plane
+        let wcs = WCS {
+            ctype: Some(vec!["RA---SIN".into(), "DEC--SIN".into()]),
+            crval: Some(vec![0.0, 0.0]),
+            crpix: Some(vec![1.0, 1.0]),
+            cdelt: Some(vec![-1.0, 1.0]),
+            ..Default::default()
+        };
+        assert!(wcs.contains(&[10, 10], 0.0, 0.0));
+        assert!(!wcs.contains(&[10, 10], 180.0, 0.0));
+    }
+}
@@ -0,0 +1,362 @@
+use super::split_ctype;
+use crate::FITSError;
+use crate::WCSError;
+use crate::WCS;
+
+use nalgebra::Matrix3;
+use nalgebra::Vector3;
+
+/// Celestial reference frame
+///
+/// See Section 8.3 of FITS standard, version 4, and WCS Paper II
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum CelestialFrame {
+    /// International Celestial Reference System
+    ICRS,
+    /// Mean place, FK5 system, with equinox as Julian epoch
+    FK5(f64),
+    /// Mean place, FK4 system, with equinox as Besselian epoch
+    FK4(f64),
+    /// IAU 1958 galactic coordinates
+    Galactic,
+    /// Mean ecliptic and equinox of J2000
+    Ecliptic,
+}
+
+/// Frame bias matrix from ICRS to mean equator and equinox of J2000
+///
+/// See IERS Conventions (2003), Section 5.4
+const BIAS: [f64; 9] = [
+    0.999_999_999_999_994_2,
+    0.000_000_070_782_797_4,
+    -0.000_000_080_562_171_5,
+    -0.000_000_070_782_794_8,
+    0.999_999_999_999_996_9,
+    0.000_000_033_060_414_5,
+    0.000_000_080_562_173_8,
+    -0.000_000_033_060_408_8,
+    0.999_999_999_999_996_2,
+];
+
+/// Rotation from FK5 J2000 to galactic coordinates
+///
+/// See Hipparcos catalogue, Vol. 1, Section 1.5.3
+const GALACTIC: [f64; 9] = [
+    -0.054_875_560_416_215_4,
+    -0.873_437_090_234_885,
+    -0.483_835_015_548_713_2,
+    0.494_109_427_875_583_7,
+    -0.444_829_629_960_011_2,
+    0.746_982_244_497_219,
+    -0.867_666_149_019_004_7,
+    -0.198_076_373_431_201_5,
+    0.455_983_776_175_066_9,
+];
+
+/// Rotation from FK4 B1950 (E-terms removed) to FK5 J2000,
+/// for positions with zero proper motion
+///
+/// See Standish (1982), A&A 115, 20
+const FK4_TO_FK5: [f64; 9] = [
+    0.999_925_678_2,
+    -0.011_182_061_1,
+    -0.004_857_947_7,
+    0.011_182_061_0,
+    0.999_937_478_4,
+    -0.000_027_176_5,
+    0.004_857_947_9,
+    -0.000_027_147_4,
+    0.999_988_199_7,
+];
+
+/// E-terms of aberration at B1950, in radians
+const ETERMS: [f64; 3] = [-1.62557e-6, -0.31919e-6, -0.13843e-6];
+
+/// Obliquity of the ecliptic at J2000 (IAU 1976), in degrees
+const OBLIQUITY: f64 = 23.439_291_111;
+
+/// Frame rotation of `angle` radians about axis 0 (x), 1 (y), or 2 (z)
+fn rotation(axis: usize, angle: f64) -> Matrix3<f64> {
+    let (s, c) = angle.sin_cos();
+    match axis {
+        0 => Matrix3::new(1.0, 0.0, 0.0, 0.0, c, s, 0.0, -s, c),
+        1 => Matrix3::new(c, 0.0, -s, 0.0, 1.0, 0.0, s, 0.0, c),
+        _ => Matrix3::new(c, s, 0.0, -s, c, 0.0, 0.0, 0.0, 1.0),
+    }
+}
+
+/// IAU 1976 precession matrix from J2000 to the mean equator
+/// and equinox of the given Julian epoch
+///
+/// See Lieske et al. (1977), A&A 58, 1
+fn precession(epoch: f64) -> Matrix3<f64> {
+    let t = (epoch - 2000.0) / 100.0;
+    let arcsec = std::f64::consts::PI / 180.0 / 3600.0;
+    let zeta = (2306.2181 + (0.30188 + 0.017998 * t) * t) * t * arcsec;
+    let z = (2306.2181 + (1.09468 + 0.018203 * t) * t) * t * arcsec;
+    let theta = (2004.3109 - (0.42665 + 0.041833 * t) * t) * t * arcsec;
+    rotation(2, -z) * rotation(1, theta) * rotation(2, -zeta)
+}
+
+/// Unit vector for spherical coordinates in degrees
+fn unit_vector(lon: f64, lat: f64) -> Vector3<f64> {
+    let (slon, clon) = lon.to_radians().sin_cos();
+    let (slat, clat) = lat.to_radians().sin_cos();
+    Vector3::new(clat * clon, clat * slon, slat)
+}
+
+/// Spherical coordinates in degrees for a (not necessarily unit) vector,
+/// with longitude in range [0, 360)
+fn spherical(r: &Vector3<f64>) -> (f64, f64) {
+    let lon = r.y.atan2(r.x).to_degrees().rem_euclid(360.0);
+    let lat = r.z.atan2(r.x.hypot(r.y)).to_degrees();
+    (lon, lat)
+}
+
+impl CelestialFrame {
+    /// Check FK4 positions are at B1950, the only supported equinox
+    fn check_fk4(equinox: f64) -> Result<(), WCSError> {
+        if equinox != 1950.0 {
+            return Err(WCSError::UnsupportedFrame(format!("FK4 B{}", equinox)));
+        }
+        Ok(())
+    }
+
+    /// Rotate a position vector in this frame to ICRS
+    fn rotate_to_icrs(self, r: Vector3<f64>) -> Result<Vector3<f64>, WCSError> {
+        let bias_t = Matrix3::from_row_slice(&BIAS).transpose();
+        Ok(match self {
+            CelestialFrame::ICRS => r,
+            CelestialFrame::FK5(equinox) => bias_t * precession(equinox).transpose() * r,
+            CelestialFrame::Galactic => bias_t * Matrix3::from_row_slice(&GALACTIC).transpose() * r,
+            CelestialFrame::Ecliptic => {
+                bias_t * rotation(0, OBLIQUITY.to_radians()).transpose() * r
+            }
+            CelestialFrame::FK4(equinox) => {
+                Self::check_fk4(equinox)?;
+                let a = Vector3::from_row_slice(&ETERMS);
+                let r = r.normalize();
+                let r = r - a + r.dot(&a) * r;
+                bias_t * Matrix3::from_row_slice(&FK4_TO_FK5) * r
+            }
+        })
+    }
+
+    /// Rotate an ICRS position vector into this frame
+    fn rotate_from_icrs(self, r: Vector3<f64>) -> Result<Vector3<f64>, WCSError> {
+        let bias = Matrix3::from_row_slice(&BIAS);
+        Ok(match self {
+            CelestialFrame::ICRS => r,
+            CelestialFrame::FK5(equinox) => precession(equinox) * bias * r,
+            CelestialFrame::Galactic => Matrix3::from_row_slice(&GALACTIC) * bias * r,
+            CelestialFrame::Ecliptic => rotation(0, OBLIQUITY.to_radians()) * bias * r,
+            CelestialFrame::FK4(equinox) => {
+                Self::check_fk4(equinox)?;
+                let a = Vector3::from_row_slice(&ETERMS);
+                let r = (Matrix3::from_row_slice(&FK4_TO_FK5).transpose() * bias * r).normalize();
+                r + a - r.dot(&a) * r
+            }
+        })
+    }
+
+    /// Convert a position from this frame to another
+    ///
+    /// # Arguments
+    ///
+    /// * `lon` - Longitude (e.g. right ascension) in degrees
+    /// * `lat` - Latitude (e.g. declination) in degrees
+    /// * `to` - Frame to convert to
+    ///
+    /// # Returns
+    ///
+    /// Longitude and latitude in the new frame, in degrees,
+    /// with longitude in range [0, 360)
+    ///
+    /// # Notes
+    ///
+    /// Positions are treated as fixed (zero proper motion and parallax).
+    /// FK4 is supported only for equinox B1950
+    ///
+    pub fn convert(&self, lon: f64, lat: f64, to: &CelestialFrame) -> Result<(f64, f64), WCSError> {
+        if self == to {
+            return Ok((lon, lat));
+        }
+        let r = to.rotate_from_icrs(self.rotate_to_icrs(unit_vector(lon, lat))?)?;
+        Ok(spherical(&r))
+    }
+}
+
+impl WCS {
+    /// Celestial reference frame of the celestial axes
+    ///
+    /// Galactic and ecliptic frames are taken from CTYPE.  For
+    /// equatorial coordinates the frame is given by RADESYSa; if absent,
+    /// it is FK4 if EQUINOXa is before 1984, FK5 if EQUINOXa is
+    /// present, and ICRS otherwise.  The equinox defaults to 2000 for
+    /// FK5 and 1950 for FK4.  See Section 8.3 of FITS standard, version 4
+    ///
+    /// # Returns
+    ///
+    /// The frame, or None if there are no celestial axes
+    /// or the frame is not recognized
+    ///
+    pub fn frame(&self) -> Option<CelestialFrame> {
+        let (lon, _) = self.celestial_axes()?;
+        let ctype = &self.ctype.as_ref()?[lon];
+        match split_ctype(ctype).0 {
+            "GLON" => return Some(CelestialFrame::Galactic),
+            "ELON" => return Some(CelestialFrame::Ecliptic),
+            "RA" => {}
+            _ => return None,
+        }
+        let radesys = match (&self.radesys, self.equinox) {
+            (Some(r), _) => r.trim().to_uppercase(),
+            (None, Some(e)) if e < 1984.0 => "FK4".to_string(),
+            (None, Some(_)) => "FK5".to_string(),
+            (None, None) => "ICRS".to_string(),
+        };
+        match radesys.as_str() {
+            "ICRS" => Some(CelestialFrame::ICRS),
+            "FK5" => Some(CelestialFrame::FK5(self.equinox.unwrap_or(2000.0))),
+            "FK4" => Some(CelestialFrame::FK4(self.equinox.unwrap_or(1950.0))),
+            _ => None,
+        }
+    }
+
+    /// Celestial frame of this WCS, or an error if unknown
+    fn require_frame(&self) -> Result<CelestialFrame, WCSError> {
+        self.frame().ok_or_else(|| {
+            WCSError::UnsupportedFrame(self.radesys.clone().unwrap_or("unknown".to_string()))
+        })
+    }
+
+    /// Convert pixel coordinates to world coordinates in a given frame
+    ///
+    /// As `pixel_to_world`, with the celestial coordinates converted
+    /// from the native frame of the WCS (see `frame`) to `frame`
+    ///
+    /// # Arguments
+    ///
+    /// * `pixel` - Zero-based pixel coordinates, one per axis
+    /// * `frame` - Celestial frame for the returned coordinates
+    ///
+    pub fn pixel_to_world_in(
+        &self,
+        pixel: &[f64],
+        frame: &CelestialFrame,
+    ) -> Result<Vec<f64>, FITSError> {
+        let mut world = self.pixel_to_world(pixel)?;
+        if let Some((lon, lat)) = self.celestial_axes() {
+            (world[lon], world[lat]) = self
+                .require_frame()?
+                .convert(world[lon], world[lat], frame)?;
+        }
+        Ok(world)
+    }
+
+    /// Convert world coordinates in a given frame to pixel coordinates
+    ///
+    /// # Arguments
+    ///
+    /// * `world` - World coordinates, one per axis, with the
+    ///   celestial coordinates in `frame`
+    /// * `frame` - Celestial frame of the given coordinates
+    ///
+    /// # Returns
+    ///
+    /// Zero-based pixel coordinates, one per axis
+    ///
+    pub fn world_to_pixel_in(
+        &self,
+        world: &[f64],
+        frame: &CelestialFrame,
+    ) -> Result<Vec<f64>, FITSError> {
+        let mut world = world.to_vec();
+        if let Some((lon, lat)) = self.celestial_axes() {
+            if world.len() == self.naxes() {
+                (world[lon], world[lat]) =
+                    frame.convert(world[lon], world[lat], &self.require_frame()?)?;
+            }
+        }
+        self.world_to_pixel(&world)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_galactic() {
+        // Galactic center and north galactic pole, FK5 J2000
+        let (ra, dec) = CelestialFrame::Galactic
+            .convert(0.0, 0.0, &CelestialFrame::FK5(2000.0))
+            .unwrap();
+        assert!((ra - 266.404_996).abs() < 1.0e-5);
+        assert!((dec + 28.936_172).abs() < 1.0e-5);
+        let (_, b) = CelestialFrame::FK5(2000.0)
+            .convert(192.859_48, 27.128_25, &CelestialFrame::Galactic)
+            .unwrap();
+        assert!((b - 90.0).abs() < 1.0e-4);
+        // North galactic pole is defined in FK4 B1950
+        let (_, b) = CelestialFrame::FK4(1950.0)
+            .convert(192.25, 27.4, &CelestialFrame::Galactic)
+            .unwrap();
+        assert!((b - 90.0).abs() < 1.0e-3);
+    }
+
+    #[test]
+    fn test_roundtrip() {
+        let frames = [
+            CelestialFrame::ICRS,
+            CelestialFrame::FK5(2000.0),
+            CelestialFrame::FK5(1975.0),
+            CelestialFrame::FK4(1950.0),
+            CelestialFrame::Galactic,
+            CelestialFrame::Ecliptic,
+        ];
+        for from in frames.iter() {
+            for to in frames.iter() {
+                let (lon, lat) = from.convert(123.4, -56.7, to).unwrap();
+                let (lon, lat) = to.convert(lon, lat, from).unwrap();
+                assert!((lon - 123.4).abs() < 1.0e-8, "{:?} -> {:?}", from, to);
+                assert!((lat + 56.7).abs() < 1.0e-8, "{:?} -> {:?}", from, to);
+            }
+        }
+        // ICRS and FK5 J2000 differ by the frame bias, of order 20 mas
+        let (ra, dec) = CelestialFrame::ICRS
+            .convert(10.0, 20.0, &CelestialFrame::FK5(2000.0))
+            .unwrap();
+        assert!((ra - 10.0).abs() < 2.0e-5 && (dec - 20.0).abs() < 2.0e-5);
+        assert!(CelestialFrame::FK4(1900.0)
+            .convert(0.0, 0.0, &CelestialFrame::ICRS)
+            .is_err());
+    }
+
+    #[test]
+    fn test_wcs_frame() {
+        let mut wcs = WCS {
+            ctype: Some(vec!["RA---TAN".into(), "DEC--TAN".into()]),
+            crval: Some(vec![266.404_996, -28.936_172]),
+            crpix: Some(vec![1.0, 1.0]),
+            cdelt: Some(vec![-1.0e-3, 1.0e-3]),
+            ..Default::default()
+        };
+        assert_eq!(wcs.frame(), Some(CelestialFrame::ICRS));
+        wcs.equinox = Some(1950.0);
+        assert_eq!(wcs.frame(), Some(CelestialFrame::FK4(1950.0)));
+        wcs.radesys = Some("FK5".into());
+        wcs.equinox = None;
+        assert_eq!(wcs.frame(), Some(CelestialFrame::FK5(2000.0)));
+
+        let world = wcs
+            .pixel_to_world_in(&[0.0, 0.0], &CelestialFrame::Galactic)
+            .unwrap();
+        assert!(world[0].min(360.0 - world[0]) < 1.0e-5);
+        assert!(world[1].abs() < 1.0e-5);
+        let pixel = wcs
+            .world_to_pixel_in(&world, &CelestialFrame::Galactic)
+            .unwrap();
+        assert!(pixel[0].abs() < 1.0e-6 && pixel[1].abs() < 1.0e-6);
+    }
+}
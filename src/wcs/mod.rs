@@ -1,12 +1,36 @@
+mod footprint;
+mod frame;
+mod projection;
+mod sip;
+mod spectral;
+mod transform;
+
+pub use frame::CelestialFrame;
+pub use projection::Projection;
+pub use projection::ProjectionParams;
+pub use sip::Sip;
+pub use spectral::SpectralType;
+pub use spectral::TabularAxis;
+
+pub(crate) use transform::split_ctype;
+
 use crate::errors::HeaderError;
+use crate::FITSError;
 use crate::Header;
 use crate::KeywordValue;
 use crate::Matrix;
+use crate::Unit;
+
+use std::collections::BTreeMap;
+use std::collections::HashMap;
 
 /// World Coordinate System transformations
 /// See Chapter 8 of FITS standard, version 4
 #[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct WCS {
+    /// Name of the description (WCSNAMEa)
+    pub name: Option<String>,
     pub wcaxes: Option<usize>,
     pub ctype: Option<Vec<String>>,
     pub crval: Option<Vec<f64>>,
@@ -15,17 +39,213 @@ pub struct WCS {
     pub cunit: Option<Vec<String>>,
     pub cd: Option<Matrix>,
     pub pc: Option<Matrix>,
+    /// SIP distortion coefficients, applied if CTYPE ends in "-SIP"
+    pub sip: Option<Sip>,
+    /// Rest frequency in Hz (RESTFRQa)
+    pub restfrq: Option<f64>,
+    /// Rest wavelength in m (RESTWAVa)
+    pub restwav: Option<f64>,
+    /// Lookup tables for axes using the -TAB algorithm, keyed by axis index
+    pub tab: HashMap<usize, TabularAxis>,
+    /// Projection parameters PVi_ma, keyed by (zero-based axis i, m)
+    #[cfg_attr(feature = "serde", serde(with = "pv_entries"))]
+    pub pv: BTreeMap<(usize, usize), f64>,
+    /// Native longitude of the celestial pole, in degrees (LONPOLEa)
+    pub lonpole: Option<f64>,
+    /// Native latitude of the celestial pole, in degrees (LATPOLEa)
+    pub latpole: Option<f64>,
+    /// Equatorial reference system (RADESYSa), e.g. "ICRS", "FK5"
+    pub radesys: Option<String>,
+    /// Equinox of the equatorial reference system, in years (EQUINOXa)
+    pub equinox: Option<f64>,
+}
+
+/// Serialize the PV map as a list of (i, m, value), since formats such
+/// as JSON only allow string map keys
+#[cfg(feature = "serde")]
+mod pv_entries {
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+    use std::collections::BTreeMap;
+
+    pub fn serialize<S: Serializer>(
+        pv: &BTreeMap<(usize, usize), f64>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        let entries: Vec<(usize, usize, f64)> = pv.iter().map(|(&(i, m), &v)| (i, m, v)).collect();
+        entries.serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<BTreeMap<(usize, usize), f64>, D::Error> {
+        let entries = Vec::<(usize, usize, f64)>::deserialize(deserializer)?;
+        Ok(entries.into_iter().map(|(i, m, v)| ((i, m), v)).collect())
+    }
+}
+
+/// Numeric keyword value as float, allowing integer values
+fn float_value(value: Option<&KeywordValue>) -> Option<f64> {
+    match value {
+        Some(KeywordValue::Float(v)) => Some(*v),
+        Some(KeywordValue::Int(v)) => Some(*v as f64),
+        _ => None,
+    }
 }
 
 impl WCS {
-    pub fn from_header(header: &Header) -> Result<Option<Self>, Box<dyn std::error::Error>> {
+    /// Remove an axis from the coordinate description
+    ///
+    /// This is exact only if the removed axis is not coupled to the
+    /// others through the PC or CD matrix, e.g. the spectral axis of a cube
+    ///
+    /// # Arguments
+    ///
+    /// * `axis` - Zero-based axis to remove
+    ///
+    pub(crate) fn remove_axis(&mut self, axis: usize) {
+        fn remove<T>(v: &mut Option<Vec<T>>, axis: usize) {
+            if let Some(v) = v.as_mut() {
+                if axis < v.len() {
+                    v.remove(axis);
+                }
+            }
+        }
+        remove(&mut self.ctype, axis);
+        remove(&mut self.crval, axis);
+        remove(&mut self.crpix, axis);
+        remove(&mut self.cdelt, axis);
+        remove(&mut self.cunit, axis);
+        for m in [&mut self.cd, &mut self.pc] {
+            if let Some(mat) = m.take() {
+                if axis < mat.nrows() && axis < mat.ncols() {
+                    *m = Some(mat.remove_row(axis).remove_column(axis));
+                } else {
+                    *m = Some(mat);
+                }
+            }
+        }
+        self.pv = std::mem::take(&mut self.pv)
+            .into_iter()
+            .filter(|((i, _), _)| *i != axis)
+            .map(|((i, m), v)| (((if i > axis { i - 1 } else { i }), m), v))
+            .collect();
+        if let Some(n) = self.wcaxes.as_mut() {
+            *n = n.saturating_sub(1);
+        }
+    }
+
+    /// Convert legacy CROTAn rotation into an equivalent PC matrix
+    ///
+    /// The rotation is taken from the CROTAn keyword of the latitude axis,
+    /// falling back to CROTA2.  See Section 6.1 of WCS Paper II
+    ///
+    /// # Returns
+    ///
+    /// The PC matrix, or None if there is no (non-zero) rotation
+    ///
+    fn crota_matrix(&self, header: &Header) -> Option<Matrix> {
+        let n = self.naxes();
+        if n < 2 {
+            return None;
+        }
+        let (lon, lat) = self.celestial_axes().unwrap_or((0, 1));
+        let rho = float_value(header.value(format!("CROTA{}", lat + 1).as_str()))
+            .or_else(|| float_value(header.value("CROTA2")))?;
+        if rho == 0.0 {
+            return None;
+        }
+        let cdelt = self.cdelt.clone().unwrap_or_default();
+        let dlon = cdelt.get(lon).copied().unwrap_or(1.0);
+        let dlat = cdelt.get(lat).copied().unwrap_or(1.0);
+        let (sin, cos) = rho.to_radians().sin_cos();
+        let mut pc = Matrix::identity(n, n);
+        pc[(lon, lon)] = cos;
+        pc[(lon, lat)] = -sin * dlat / dlon;
+        pc[(lat, lon)] = sin * dlon / dlat;
+        pc[(lat, lat)] = cos;
+        Some(pc)
+    }
+
+    /// Parse the primary WCS from a header
+    ///
+    /// # Arguments
+    ///
+    /// * `header` - Header containing WCS keywords
+    ///
+    /// # Returns
+    ///
+    /// The WCS, or None if no WCS keywords are present
+    ///
+    pub fn from_header(header: &Header) -> Result<Option<Self>, FITSError> {
+        Self::from_header_alt(header, ' ')
+    }
+
+    /// Unit of the world coordinate of an axis
+    ///
+    /// # Arguments
+    ///
+    /// * `axis` - Zero-based axis index
+    ///
+    /// # Returns
+    ///
+    /// The parsed CUNITia, None if the axis has no unit, or an error if
+    /// CUNITia is not a valid unit string
+    ///
+    pub fn axis_unit(&self, axis: usize) -> Result<Option<Unit>, FITSError> {
+        match self.cunit.as_ref().and_then(|c| c.get(axis)) {
+            Some(cunit) if !cunit.trim().is_empty() => Ok(Some(cunit.parse()?)),
+            _ => Ok(None),
+        }
+    }
+
+    /// Parse all WCS descriptions from a header
+    ///
+    /// # Arguments
+    ///
+    /// * `header` - Header containing WCS keywords
+    ///
+    /// # Returns
+    ///
+    /// Map from alternate WCS key to WCS.  The primary WCS, if
+    /// present, has key ' '; alternates have keys 'A' through 'Z'
+    ///
+    pub fn all_from_header(header: &Header) -> Result<HashMap<char, Self>, FITSError> {
+        let mut all = HashMap::new();
+        for alt in std::iter::once(' ').chain('A'..='Z') {
+            if let Some(wcs) = Self::from_header_alt(header, alt)? {
+                all.insert(alt, wcs);
+            }
+        }
+        Ok(all)
+    }
+
+    /// Parse a primary or alternate WCS from a header
+    ///
+    /// Alternate descriptions use the same keywords as the primary,
+    /// with a single-letter suffix, e.g. CTYPE1A, CRVAL2B
+    ///
+    /// # Arguments
+    ///
+    /// * `header` - Header containing WCS keywords
+    /// * `alt` - Alternate WCS key 'A' through 'Z', or ' ' for the primary
+    ///
+    /// # Returns
+    ///
+    /// The WCS, or None if no WCS keywords with the given key are present
+    ///
+    pub fn from_header_alt(header: &Header, alt: char) -> Result<Option<Self>, FITSError> {
+        let a = alt.to_string();
+        let a = a.trim();
         let mut wcs = WCS::default();
         // See if this is explicitly set
-        if let Some(kw) = header.value("WCSAXES") {
+        if let Some(kw) = header.value(format!("WCSAXES{}", a).as_str()) {
             if let KeywordValue::Int(ax) = kw {
                 wcs.wcaxes = Some(*ax as usize);
             } else {
-                return Err(Box::new(HeaderError::UnexpectedValueType("WCSAXES".into())));
+                return Err(FITSError::from(HeaderError::UnexpectedValueType(format!(
+                    "WCSAXES{}",
+                    a
+                ))));
             }
         } else {
             wcs.wcaxes = None;
@@ -34,7 +254,7 @@ impl WCS {
 
         let mut niaxes = 0;
         while let Some(KeywordValue::String(s)) =
-            header.value(format!("CUNIT{}", niaxes + 1).as_str())
+            header.value(format!("CUNIT{}{}", niaxes + 1, a).as_str())
         {
             if wcs.cunit.is_none() {
                 wcs.cunit = Some(Vec::new())
@@ -44,7 +264,7 @@ impl WCS {
         }
         niaxes = 0;
         while let Some(KeywordValue::String(s)) =
-            header.value(format!("CTYPE{}", niaxes + 1).as_str())
+            header.value(format!("CTYPE{}{}", niaxes + 1, a).as_str())
         {
             if wcs.ctype.is_none() {
                 wcs.ctype = Some(Vec::new());
@@ -53,66 +273,110 @@ impl WCS {
             niaxes += 1;
         }
         niaxes = 0;
-        while let Some(KeywordValue::Float(s)) =
-            header.value(format!("CDELT{}", niaxes + 1).as_str())
+        while let Some(s) = float_value(header.value(format!("CDELT{}{}", niaxes + 1, a).as_str()))
         {
             if wcs.cdelt.is_none() {
                 wcs.cdelt = Some(Vec::new());
             }
-            wcs.cdelt.as_mut().unwrap().push(*s);
+            wcs.cdelt.as_mut().unwrap().push(s);
             niaxes += 1;
         }
         niaxes = 0;
-        while let Some(KeywordValue::Float(s)) =
-            header.value(format!("CRVAL{}", niaxes + 1).as_str())
+        while let Some(s) = float_value(header.value(format!("CRVAL{}{}", niaxes + 1, a).as_str()))
         {
             if wcs.crval.is_none() {
                 wcs.crval = Some(Vec::new());
             }
-            wcs.crval.as_mut().unwrap().push(*s);
+            wcs.crval.as_mut().unwrap().push(s);
             niaxes += 1;
         }
         niaxes = 0;
-        while let Some(KeywordValue::Float(s)) =
-            header.value(format!("CRPIX{}", niaxes + 1).as_str())
+        while let Some(s) = float_value(header.value(format!("CRPIX{}{}", niaxes + 1, a).as_str()))
         {
             if wcs.crpix.is_none() {
                 wcs.crpix = Some(Vec::new());
             }
-            wcs.crpix.as_mut().unwrap().push(*s);
+            wcs.crpix.as_mut().unwrap().push(s);
             niaxes += 1;
         }
 
-        if wcs.crpix.is_some() {
-            let nj = wcs.crpix.as_ref().unwrap().len();
-            let ni = wcs.crpix.as_ref().unwrap().len();
-
-            for i in 0..ni {
-                for j in 0..nj {
-                    if let Some(KeywordValue::Float(s)) =
-                        header.value(format!("CD{}_{}", i + 1, j + 1).as_str())
-                    {
-                        if wcs.cd.is_none() {
-                            wcs.cd = Some(Matrix::identity(ni, nj));
-                        }
-                        wcs.cd.as_mut().unwrap()[(i, j)] = *s;
+        let ni = wcs.naxes();
+        let nj = ni;
+        for i in 0..ni {
+            for j in 0..nj {
+                if let Some(s) =
+                    float_value(header.value(format!("CD{}_{}{}", i + 1, j + 1, a).as_str()))
+                {
+                    // Unspecified CD elements default to zero,
+                    // unlike PC elements which default to identity
+                    if wcs.cd.is_none() {
+                        wcs.cd = Some(Matrix::zeros(ni, nj));
+                    }
+                    wcs.cd.as_mut().unwrap()[(i, j)] = s;
+                }
+            }
+        }
+        for i in 0..ni {
+            for j in 0..nj {
+                if let Some(s) =
+                    float_value(header.value(format!("PC{}_{}{}", i + 1, j + 1, a).as_str()))
+                {
+                    if wcs.pc.is_none() {
+                        wcs.pc = Some(Matrix::identity(ni, nj));
                     }
+                    wcs.pc.as_mut().unwrap()[(i, j)] = s;
+                }
+            }
+        }
+
+        // Legacy rotation keywords apply only if neither CD nor PC is
+        // present, and have no alternate form
+        if wcs.cd.is_none() && wcs.pc.is_none() && alt == ' ' {
+            wcs.pc = wcs.crota_matrix(header);
+        }
+
+        for i in 0..ni {
+            for m in 0..100 {
+                if let Some(v) =
+                    float_value(header.value(format!("PV{}_{}{}", i + 1, m, a).as_str()))
+                {
+                    wcs.pv.insert((i, m), v);
                 }
             }
-            for i in 0..ni {
-                for j in 0..nj {
-                    if let Some(KeywordValue::Float(s)) =
-                        header.value(format!("PC{}_{}", i + 1, j + 1).as_str())
-                    {
-                        if wcs.pc.is_none() {
-                            wcs.pc = Some(Matrix::identity(ni, nj));
-                        }
-                        wcs.pc.as_mut().unwrap()[(i, j)] = *s;
+        }
+        wcs.lonpole = float_value(header.value(format!("LONPOLE{}", a).as_str()));
+        wcs.latpole = float_value(header.value(format!("LATPOLE{}", a).as_str()));
+
+        spectral::parse_rest(&mut wcs, header, a);
+        if let Some(ctype) = &wcs.ctype {
+            for (axis, c) in ctype.iter().enumerate() {
+                if split_ctype(c).1 == Some("TAB") {
+                    if let Some(tab) = TabularAxis::from_header(header, axis, a) {
+                        wcs.tab.insert(axis, tab);
                     }
                 }
             }
         }
 
+        // RADECSYS and EPOCH are deprecated forms of RADESYS and EQUINOX
+        wcs.radesys = match header
+            .value(format!("RADESYS{}", a).as_str())
+            .or_else(|| header.value("RADECSYS"))
+        {
+            Some(KeywordValue::String(s)) => Some(s.trim().to_string()),
+            _ => None,
+        };
+        wcs.equinox = float_value(header.value(format!("EQUINOX{}", a).as_str()))
+            .or_else(|| float_value(header.value("EPOCH")));
+
+        if let Some(KeywordValue::String(name)) = header.value(format!("WCSNAME{}", a).as_str()) {
+            wcs.name = Some(name.clone());
+        }
+        // SIP distortion is only defined for the primary description
+        if alt == ' ' {
+            wcs.sip = Sip::from_header(header)?;
+        }
+
         if wcs.cd.is_none()
             && wcs.pc.is_none()
             && wcs.cdelt.is_none()
@@ -126,3 +390,364 @@ impl WCS {
         Ok(Some(wcs))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testutil::kw;
+
+    fn tan_wcs() -> WCS {
+        WCS {
+            ctype: Some(vec!["RA---TAN".into(), "DEC--TAN".into()]),
+            crval: Some(vec![150.0, 2.0]),
+            crpix: Some(vec![512.0, 512.0]),
+            cd: Some(Matrix::from_row_slice(
+                2,
+                2,
+                &[-1.0e-4, 2.0e-5, 2.0e-5, 1.0e-4],
+            )),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_tan_reference_pixel() {
+        let wcs = tan_wcs();
+        let world = wcs.pixel_to_world(&[511.0, 511.0]).unwrap();
+        assert!((world[0] - 150.0).abs() < 1.0e-12);
+        assert!((world[1] - 2.0).abs() < 1.0e-12);
+    }
+
+    #[test]
+    fn test_tan_roundtrip() {
+        let wcs = tan_wcs();
+        for pixel in [[0.0, 0.0], [1023.0, 17.5], [300.25, 900.0]] {
+            let world = wcs.pixel_to_world(&pixel).unwrap();
+            let back = wcs.world_to_pixel(&world).unwrap();
+            assert!((back[0] - pixel[0]).abs() < 1.0e-8);
+            assert!((back[1] - pixel[1]).abs() < 1.0e-8);
+        }
+        // RA increases to the left with negative CD1_1
+        let world = wcs.pixel_to_world(&[411.0, 511.0]).unwrap();
+        assert!(world[0] > 150.0);
+    }
+
+    #[test]
+    fn test_sexagesimal() {
+        let wcs = tan_wcs();
+        let (ra, dec) = wcs.pixel_to_angles(&[511.0, 511.0]).unwrap().unwrap();
+        assert!((ra.degrees() - 150.0).abs() < 1.0e-12);
+        assert!((dec.radians() - 2.0f64.to_radians()).abs() < 1.0e-12);
+        let (ra, dec) = wcs
+            .pixel_to_sexagesimal(&[511.0, 511.0], 1)
+            .unwrap()
+            .unwrap();
+        assert_eq!(ra, "10:00:00.00");
+        assert_eq!(dec, "+02:00:00.0");
+    }
+
+    #[test]
+    fn test_projections_roundtrip() {
+        for code in [
+            "SIN", "ARC", "STG", "ZEA", "AIT", "CAR", "MOL", "CEA", "TPV", "ZPN",
+        ] {
+            for (crval1, crval2) in [(150.0, 2.0), (10.0, -60.0), (300.0, 85.0)] {
+                let wcs = WCS {
+                    ctype: Some(vec![format!("RA---{}", code), format!("DEC--{}", code)]),
+                    crval: Some(vec![crval1, crval2]),
+                    crpix: Some(vec![100.0, 100.0]),
+                    cdelt: Some(vec![-0.01, 0.01]),
+                    ..Default::default()
+                };
+                let world = wcs.pixel_to_world(&[99.0, 99.0]).unwrap();
+                assert!((world[0] - crval1).abs() < 1.0e-9, "{} {:?}", code, world);
+                assert!((world[1] - crval2).abs() < 1.0e-9, "{} {:?}", code, world);
+                for pixel in [[20.0, 150.0], [180.0, 30.0]] {
+                    let world = wcs.pixel_to_world(&pixel).unwrap();
+                    let back = wcs.world_to_pixel(&world).unwrap();
+                    assert!((back[0] - pixel[0]).abs() < 1.0e-6, "{} {:?}", code, back);
+                    assert!((back[1] - pixel[1]).abs() < 1.0e-6, "{} {:?}", code, back);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_galactic_car() {
+        let wcs = WCS {
+            ctype: Some(vec!["GLON-CAR".into(), "GLAT-CAR".into()]),
+            crval: Some(vec![0.0, 0.0]),
+            crpix: Some(vec![1.0, 1.0]),
+            cdelt: Some(vec![-1.0, 1.0]),
+            ..Default::default()
+        };
+        let world = wcs.pixel_to_world(&[10.0, 20.0]).unwrap();
+        assert!((world[0] - 350.0).abs() < 1.0e-9);
+        assert!((world[1] - 20.0).abs() < 1.0e-9);
+    }
+
+    #[test]
+    fn test_sip() {
+        let mut a = Matrix::zeros(3, 3);
+        a[(2, 0)] = 1.0e-5;
+        a[(1, 1)] = -2.0e-6;
+        let mut b = Matrix::zeros(3, 3);
+        b[(0, 2)] = 3.0e-6;
+        let mut wcs = tan_wcs();
+        wcs.ctype = Some(vec!["RA---TAN-SIP".into(), "DEC--TAN-SIP".into()]);
+        wcs.sip = Some(Sip {
+            a,
+            b,
+            ap: None,
+            bp: None,
+        });
+        let undistorted = tan_wcs().pixel_to_world(&[11.0, 11.0]).unwrap();
+        let world = wcs.pixel_to_world(&[11.0, 11.0]).unwrap();
+        assert!((world[0] - undistorted[0]).abs() > 1.0e-5);
+        let back = wcs.world_to_pixel(&world).unwrap();
+        assert!((back[0] - 11.0).abs() < 1.0e-6);
+        assert!((back[1] - 11.0).abs() < 1.0e-6);
+        // Distortion vanishes at the reference pixel
+        let world = wcs.pixel_to_world(&[511.0, 511.0]).unwrap();
+        assert!((world[0] - 150.0).abs() < 1.0e-12);
+    }
+
+    #[test]
+    fn test_alternates() {
+        let header = Header::new(vec![
+            kw("CTYPE1", KeywordValue::String("RA---TAN".into())),
+            kw("CTYPE2", KeywordValue::String("DEC--TAN".into())),
+            kw("CRVAL1", KeywordValue::Float(10.0)),
+            kw("CRVAL2", KeywordValue::Float(20.0)),
+            kw("WCSNAMEA", KeywordValue::String("PHYSICAL".into())),
+            kw("CTYPE1A", KeywordValue::String("LINEAR".into())),
+            kw("CRVAL1A", KeywordValue::Int(5)),
+            kw("CDELT1A", KeywordValue::Float(2.0)),
+        ]);
+        let all = WCS::all_from_header(&header).unwrap();
+        assert_eq!(all.len(), 2);
+        assert_eq!(all[&' '].crval, Some(vec![10.0, 20.0]));
+        let alt = &all[&'A'];
+        assert_eq!(alt.name.as_deref(), Some("PHYSICAL"));
+        assert_eq!(alt.ctype, Some(vec!["LINEAR".to_string()]));
+        assert_eq!(alt.crval, Some(vec![5.0]));
+        assert_eq!(alt.cdelt, Some(vec![2.0]));
+        assert!(WCS::from_header_alt(&header, 'B').unwrap().is_none());
+    }
+
+    #[test]
+    fn test_crota() {
+        let mut cards = vec![
+            kw("CTYPE1", KeywordValue::String("RA---TAN".into())),
+            kw("CTYPE2", KeywordValue::String("DEC--TAN".into())),
+            kw("CRPIX1", KeywordValue::Float(1.0)),
+            kw("CRPIX2", KeywordValue::Float(1.0)),
+            kw("CDELT1", KeywordValue::Float(-0.001)),
+            kw("CDELT2", KeywordValue::Float(0.002)),
+            kw("CROTA2", KeywordValue::Float(30.0)),
+        ];
+        let wcs = WCS::from_header(&Header::new(cards.clone()))
+            .unwrap()
+            .unwrap();
+        let m = wcs.linear_matrix();
+        let (sin, cos) = 30.0f64.to_radians().sin_cos();
+        assert!((m[(0, 0)] - -0.001 * cos).abs() < 1.0e-15);
+        assert!((m[(0, 1)] - -0.002 * sin).abs() < 1.0e-15);
+        assert!((m[(1, 0)] - -0.001 * sin).abs() < 1.0e-15);
+        assert!((m[(1, 1)] - 0.002 * cos).abs() < 1.0e-15);
+
+        // CD takes precedence over CROTA and CDELT
+        cards.push(kw("CD1_1", KeywordValue::Float(0.5)));
+        cards.push(kw("CD2_2", KeywordValue::Float(0.25)));
+        let wcs = WCS::from_header(&Header::new(cards)).unwrap().unwrap();
+        assert!(wcs.pc.is_none());
+        let m = wcs.linear_matrix();
+        assert_eq!(m[(0, 0)], 0.5);
+        assert_eq!(m[(0, 1)], 0.0);
+        assert_eq!(m[(1, 1)], 0.25);
+    }
+
+    #[test]
+    fn test_pv_lonpole() {
+        let mut cards = vec![
+            kw("CTYPE1", KeywordValue::String("RA---ZPN".into())),
+            kw("CTYPE2", KeywordValue::String("DEC--ZPN".into())),
+            kw("CRVAL1", KeywordValue::Float(10.0)),
+            kw("CRVAL2", KeywordValue::Float(20.0)),
+            kw("CRPIX1", KeywordValue::Float(1.0)),
+            kw("CRPIX2", KeywordValue::Float(1.0)),
+            kw("CDELT1", KeywordValue::Float(-0.01)),
+            kw("CDELT2", KeywordValue::Float(0.01)),
+            kw("PV2_1", KeywordValue::Float(1.0)),
+            kw("PV2_3", KeywordValue::Int(-2)),
+        ];
+        let wcs = WCS::from_header(&Header::new(cards.clone()))
+            .unwrap()
+            .unwrap();
+        assert_eq!(wcs.pv.get(&(1, 3)), Some(&-2.0));
+        assert_eq!(wcs.projection_params().lat.get(&3), Some(&-2.0));
+        let world = wcs.pixel_to_world(&[0.0, 100.0]).unwrap();
+        // One degree north in the plane: solve zeta - 2 zeta^3 = 1 degree
+        let mut zeta = 1.0f64.to_radians();
+        for _ in 0..20 {
+            zeta = 1.0f64.to_radians() + 2.0 * zeta.powi(3);
+        }
+        assert!(zeta.to_degrees() > 1.0);
+        assert!((world[1] - 20.0 - zeta.to_degrees()).abs() < 1.0e-9);
+        let back = wcs.world_to_pixel(&world).unwrap();
+        assert!(back[0].abs() < 1.0e-8 && (back[1] - 100.0).abs() < 1.0e-8);
+
+        // LONPOLE = 0 rotates the native frame by 180 degrees
+        cards.push(kw("LONPOLE", KeywordValue::Float(0.0)));
+        let wcs = WCS::from_header(&Header::new(cards)).unwrap().unwrap();
+        assert_eq!(wcs.lonpole, Some(0.0));
+        let world = wcs.pixel_to_world(&[0.0, 100.0]).unwrap();
+        assert!(world[1] < 20.0);
+    }
+
+    #[test]
+    fn test_tpv_default_linear() {
+        let mut cards = vec![
+            kw("CTYPE1", KeywordValue::String("RA---TPV".into())),
+            kw("CTYPE2", KeywordValue::String("DEC--TPV".into())),
+            kw("CRVAL1", KeywordValue::Float(10.0)),
+            kw("CRVAL2", KeywordValue::Float(20.0)),
+            kw("CRPIX1", KeywordValue::Float(1.0)),
+            kw("CRPIX2", KeywordValue::Float(1.0)),
+            kw("CDELT1", KeywordValue::Float(-0.01)),
+            kw("CDELT2", KeywordValue::Float(0.01)),
+            kw("PV1_4", KeywordValue::Float(0.01)),
+            kw("PV2_5", KeywordValue::Float(-0.02)),
+        ];
+        // PVi_1 is omitted, so it defaults to one
+        let wcs = WCS::from_header(&Header::new(cards.clone()))
+            .unwrap()
+            .unwrap();
+        let world = wcs.pixel_to_world(&[50.0, 80.0]).unwrap();
+        let back = wcs.world_to_pixel(&world).unwrap();
+        assert!((back[0] - 50.0).abs() < 1.0e-8 && (back[1] - 80.0).abs() < 1.0e-8);
+
+        cards.push(kw("PV1_1", KeywordValue::Float(1.0)));
+        cards.push(kw("PV2_1", KeywordValue::Float(1.0)));
+        let explicit = WCS::from_header(&Header::new(cards)).unwrap().unwrap();
+        assert_eq!(explicit.pixel_to_world(&[50.0, 80.0]).unwrap(), world);
+    }
+
+    #[test]
+    fn test_tpv_pole() {
+        // PV1_3 is a distortion coefficient for TPV, not LONPOLE
+        let header = Header::new(vec![
+            kw("CTYPE1", KeywordValue::String("RA---TPV".into())),
+            kw("CTYPE2", KeywordValue::String("DEC--TPV".into())),
+            kw("CRVAL1", KeywordValue::Float(150.0)),
+            kw("CRVAL2", KeywordValue::Float(2.0)),
+            kw("CRPIX1", KeywordValue::Float(100.0)),
+            kw("CRPIX2", KeywordValue::Float(100.0)),
+            kw("CDELT1", KeywordValue::Float(-0.001)),
+            kw("CDELT2", KeywordValue::Float(0.001)),
+            kw("PV1_3", KeywordValue::Float(0.0001)),
+        ]);
+        let wcs = WCS::from_header(&header).unwrap().unwrap();
+        let center = wcs.pixel_to_world(&[99.0, 99.0]).unwrap();
+        assert!((center[0] - 150.0).abs() < 1.0e-9 && (center[1] - 2.0).abs() < 1.0e-9);
+        // RA falls and Dec rises from the reference pixel
+        let world = wcs.pixel_to_world(&[100.0, 100.0]).unwrap();
+        assert!(world[0] < 150.0 && (world[0] - 150.0 + 0.001).abs() < 1.0e-4);
+        assert!(world[1] > 2.0 && (world[1] - 2.001).abs() < 1.0e-4);
+    }
+
+    #[test]
+    fn test_spectral_log() {
+        let wcs = WCS {
+            ctype: Some(vec!["FREQ-LOG".into()]),
+            crval: Some(vec![1.0e9]),
+            crpix: Some(vec![1.0]),
+            cdelt: Some(vec![1.0e7]),
+            cunit: Some(vec!["Hz".into()]),
+            restfrq: Some(1.0e9),
+            ..Default::default()
+        };
+        let f = wcs.spectral_at(10.0).unwrap();
+        assert!((f - 1.0e9 * (0.1f64).exp()).abs() < 1.0e-3);
+        assert!((wcs.world_to_pixel(&[f]).unwrap()[0] - 10.0).abs() < 1.0e-9);
+        let v = wcs.spectral_as(0.0, SpectralType::VRAD).unwrap();
+        assert!(v.abs() < 1.0e-9);
+        let lambda = wcs.spectral_as(0.0, SpectralType::WAVE).unwrap();
+        assert!((lambda - 0.299792458).abs() < 1.0e-12);
+    }
+
+    #[test]
+    fn test_spectral_velocity() {
+        let wcs = WCS {
+            ctype: Some(vec!["RA---TAN".into(), "DEC--TAN".into(), "VRAD".into()]),
+            crval: Some(vec![0.0, 0.0, -10.0]),
+            crpix: Some(vec![1.0, 1.0, 1.0]),
+            cdelt: Some(vec![1.0e-3, 1.0e-3, 2.0]),
+            cunit: Some(vec!["deg".into(), "deg".into(), "km/s".into()]),
+            restfrq: Some(1.420405752e9),
+            ..Default::default()
+        };
+        assert_eq!(wcs.spectral_axis(), Some(2));
+        assert_eq!(wcs.spectral_at(5.0).unwrap(), 0.0);
+        let nu = wcs.spectral_as(0.0, SpectralType::FREQ).unwrap();
+        assert!((nu - 1.420405752e9 * (1.0 + 1.0e4 / 299792458.0)).abs() < 1.0e-3);
+        let unit = wcs.axis_unit(2).unwrap().unwrap();
+        assert_eq!(
+            unit.conversion_factor(&"m/s".parse().unwrap()).unwrap(),
+            1000.0
+        );
+        assert!(wcs.axis_unit(3).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_spectral_tab() {
+        let mut wcs = WCS {
+            ctype: Some(vec!["WAVE-TAB".into()]),
+            crval: Some(vec![1.0]),
+            crpix: Some(vec![1.0]),
+            cdelt: Some(vec![1.0]),
+            ..Default::default()
+        };
+        wcs.tab.insert(
+            0,
+            TabularAxis {
+                coords: Some(vec![1.0e-6, 2.0e-6, 4.0e-6]),
+                ..Default::default()
+            },
+        );
+        // Table indices are one-based, so CRVAL = 1 maps pixel 0 to the first element
+        assert_eq!(wcs.pixel_to_world(&[0.0]).unwrap(), vec![1.0e-6]);
+        assert_eq!(wcs.pixel_to_world(&[1.5]).unwrap(), vec![3.0e-6]);
+        assert!((wcs.world_to_pixel(&[3.0e-6]).unwrap()[0] - 1.5).abs() < 1.0e-12);
+        assert!(wcs.pixel_to_world(&[5.0]).is_err());
+    }
+
+    #[test]
+    fn test_linear() {
+        let wcs = WCS {
+            ctype: Some(vec!["WAVE".into()]),
+            crval: Some(vec![5000.0]),
+            crpix: Some(vec![1.0]),
+            cdelt: Some(vec![2.5]),
+            ..Default::default()
+        };
+        assert_eq!(wcs.pixel_to_world(&[4.0]).unwrap(), vec![5010.0]);
+        assert_eq!(wcs.world_to_pixel(&[5010.0]).unwrap(), vec![4.0]);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde() {
+        let mut wcs = tan_wcs();
+        wcs.pv.insert((1, 2), 0.5);
+        let json = serde_json::to_string(&wcs).unwrap();
+        let back: WCS = serde_json::from_str(&json).unwrap();
+        assert_eq!(back.pv, wcs.pv);
+        assert_eq!(back.cd, wcs.cd);
+        assert_eq!(back.ctype, wcs.ctype);
+        assert_eq!(
+            back.pixel_to_world(&[10.0, 20.0]).unwrap(),
+            wcs.pixel_to_world(&[10.0, 20.0]).unwrap()
+        );
+    }
+}
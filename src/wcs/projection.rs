@@ -0,0 +1,486 @@
+//! Celestial projections
+//!
+//! Conversion between intermediate world coordinates (x, y) and
+//! native spherical coordinates (phi, theta), all in degrees.
+//!
+//! See Calabretta & Greisen (2002), "Representations of celestial
+//! coordinates in FITS" (WCS Paper II)
+
+use std::collections::BTreeMap;
+use std::f64::consts::PI;
+
+/// Celestial projection, from the last three characters of CTYPEn
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Projection {
+    /// Gnomonic
+    TAN,
+    /// Orthographic / synthesis
+    SIN,
+    /// Zenithal equidistant
+    ARC,
+    /// Stereographic
+    STG,
+    /// Zenithal equal-area
+    ZEA,
+    /// Hammer-Aitoff
+    AIT,
+    /// Plate carree
+    CAR,
+    /// Mollweide
+    MOL,
+    /// Cylindrical equal area
+    CEA,
+    /// Gnomonic with polynomial distortion (TPV convention)
+    TPV,
+    /// Zenithal polynomial
+    ZPN,
+}
+
+/// Projection parameters, from the PVi_m keywords
+///
+/// Parameters are stored by the role of the axis they attach to,
+/// indexed by `m`
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ProjectionParams {
+    /// PVi_m where `i` is the longitude axis
+    pub lon: BTreeMap<usize, f64>,
+    /// PVi_m where `i` is the latitude axis
+    pub lat: BTreeMap<usize, f64>,
+}
+
+fn sind(x: f64) -> f64 {
+    x.to_radians().sin()
+}
+
+fn cosd(x: f64) -> f64 {
+    x.to_radians().cos()
+}
+
+fn atan2d(y: f64, x: f64) -> f64 {
+    y.atan2(x).to_degrees()
+}
+
+/// Radians to degrees, as a multiplier
+const R2D: f64 = 180.0 / PI;
+
+/// Monomials of the TPV polynomial, in order of the coefficient index
+///
+/// Terms are grouped by degree, with a radial term `r^d`
+/// following each group of odd degree `d`
+fn tpv_terms(x: f64, y: f64) -> [f64; 40] {
+    let r = x.hypot(y);
+    let mut terms = [0.0; 40];
+    let mut k = 0;
+    for d in 0..=7 {
+        for j in 0..=d {
+            terms[k] = x.powi(d - j) * y.powi(j);
+            k += 1;
+        }
+        if d % 2 == 1 {
+            terms[k] = r.powi(d);
+            k += 1;
+        }
+    }
+    terms
+}
+
+/// Apply TPV polynomial distortion to intermediate world coordinates
+fn tpv_distort(x: f64, y: f64, params: &ProjectionParams) -> (f64, f64) {
+    let eval = |pv: &BTreeMap<usize, f64>, terms: [f64; 40]| {
+        // The linear coefficient PVi_1 defaults to one, so with no
+        // coefficients the distortion is the identity
+        let linear = pv.get(&1).copied().unwrap_or(1.0) * terms[1];
+        linear
+            + pv.iter()
+                .filter(|(m, _)| **m != 1 && **m < 40)
+                .map(|(m, c)| c * terms[*m])
+                .sum::<f64>()
+    };
+    // The latitude polynomial has the roles of x and y interchanged
+    (
+        eval(&params.lon, tpv_terms(x, y)),
+        eval(&params.lat, tpv_terms(y, x)),
+    )
+}
+
+/// Invert TPV polynomial distortion by Newton iteration
+fn tpv_undistort(xp: f64, yp: f64, params: &ProjectionParams) -> Option<(f64, f64)> {
+    let (mut x, mut y) = (xp, yp);
+    let h = 1.0e-7;
+    for _ in 0..50 {
+        let (fx, fy) = tpv_distort(x, y, params);
+        let (ex, ey) = (fx - xp, fy - yp);
+        if ex.abs() < 1.0e-13 && ey.abs() < 1.0e-13 {
+            return Some((x, y));
+        }
+        // Numerical Jacobian
+        let (fxx, fyx) = tpv_distort(x + h, y, params);
+        let (fxy, fyy) = tpv_distort(x, y + h, params);
+        let (j11, j21) = ((fxx - fx) / h, (fyx - fy) / h);
+        let (j12, j22) = ((fxy - fx) / h, (fyy - fy) / h);
+        let det = j11 * j22 - j12 * j21;
+        if det == 0.0 {
+            return None;
+        }
+        x -= (j22 * ex - j12 * ey) / det;
+        y -= (-j21 * ex + j11 * ey) / det;
+    }
+    None
+}
+
+/// ZPN polynomial coefficients PV2_0 ... PV2_20, from the latitude axis
+///
+/// With no coefficients the polynomial is the identity, i.e. ARC
+fn zpn_coefficients(params: &ProjectionParams) -> Vec<f64> {
+    if params.lat.is_empty() {
+        return vec![0.0, 1.0];
+    }
+    let n = params.lat.keys().filter(|m| **m <= 20).max().unwrap_or(&0) + 1;
+    (0..n)
+        .map(|m| params.lat.get(&m).copied().unwrap_or(0.0))
+        .collect()
+}
+
+/// ZPN radial distance (degrees) and its derivative for native
+/// colatitude `zeta` in radians
+fn zpn_eval(coeffs: &[f64], zeta: f64) -> (f64, f64) {
+    let mut r = 0.0;
+    let mut dr = 0.0;
+    for c in coeffs.iter().rev() {
+        dr = dr * zeta + r;
+        r = r * zeta + c;
+    }
+    (R2D * r, R2D * dr)
+}
+
+/// Invert the ZPN polynomial for native colatitude in radians
+///
+/// The solution is sought between zero and the first turning
+/// point of the polynomial (or pi), where it is monotonic
+fn zpn_zeta(coeffs: &[f64], r: f64) -> Option<f64> {
+    let nstep = 1800;
+    let mut zmax = PI;
+    for k in 1..=nstep {
+        let z = PI * k as f64 / nstep as f64;
+        if zpn_eval(coeffs, z).1 <= 0.0 {
+            zmax = z;
+            break;
+        }
+    }
+    let (rmin, _) = zpn_eval(coeffs, 0.0);
+    let (rmax, _) = zpn_eval(coeffs, zmax);
+    if r < rmin || r > rmax + 1.0e-12 {
+        return None;
+    }
+    let (mut lo, mut hi) = (0.0, zmax);
+    for _ in 0..100 {
+        let mid = 0.5 * (lo + hi);
+        if zpn_eval(coeffs, mid).0 < r {
+            lo = mid;
+        } else {
+            hi = mid;
+        }
+    }
+    Some(0.5 * (lo + hi))
+}
+
+impl Projection {
+    /// Projection from its three-letter code, e.g. "TAN"
+    pub fn from_code(code: &str) -> Option<Self> {
+        match code {
+            "TAN" => Some(Projection::TAN),
+            "SIN" => Some(Projection::SIN),
+            "ARC" => Some(Projection::ARC),
+            "STG" => Some(Projection::STG),
+            "ZEA" => Some(Projection::ZEA),
+            "AIT" => Some(Projection::AIT),
+            "CAR" => Some(Projection::CAR),
+            "MOL" => Some(Projection::MOL),
+            "CEA" => Some(Projection::CEA),
+            "TPV" => Some(Projection::TPV),
+            "ZPN" => Some(Projection::ZPN),
+            _ => None,
+        }
+    }
+
+    /// Native coordinates (phi0, theta0) of the fiducial point, in degrees
+    pub fn native_reference(&self) -> (f64, f64) {
+        match self {
+            Projection::TAN
+            | Projection::SIN
+            | Projection::ARC
+            | Projection::STG
+            | Projection::ZEA
+            | Projection::TPV
+            | Projection::ZPN => (0.0, 90.0),
+            Projection::AIT | Projection::CAR | Projection::MOL | Projection::CEA => (0.0, 0.0),
+        }
+    }
+
+    /// Native latitude from radial distance for zenithal projections
+    fn zenithal_theta(&self, r: f64, params: &ProjectionParams) -> Option<f64> {
+        match self {
+            Projection::TAN | Projection::TPV => Some(atan2d(R2D, r)),
+            Projection::SIN => {
+                let s = r / R2D;
+                if s > 1.0 {
+                    return None;
+                }
+                Some(s.acos().to_degrees())
+            }
+            Projection::ARC => Some(90.0 - r),
+            Projection::STG => Some(90.0 - 2.0 * (r / (2.0 * R2D)).atan().to_degrees()),
+            Projection::ZEA => {
+                let s = r / (2.0 * R2D);
+                if s > 1.0 {
+                    return None;
+                }
+                Some(90.0 - 2.0 * s.asin().to_degrees())
+            }
+            Projection::ZPN => Some(90.0 - zpn_zeta(&zpn_coefficients(params), r)?.to_degrees()),
+            _ => None,
+        }
+    }
+
+    /// Radial distance from native latitude for zenithal projections
+    fn zenithal_r(&self, theta: f64, params: &ProjectionParams) -> Option<f64> {
+        match self {
+            Projection::TAN | Projection::TPV => {
+                if sind(theta) <= 0.0 {
+                    return None;
+                }
+                Some(R2D * cosd(theta) / sind(theta))
+            }
+            Projection::SIN => {
+                if theta < 0.0 {
+                    return None;
+                }
+                Some(R2D * cosd(theta))
+            }
+            Projection::ARC => Some(90.0 - theta),
+            Projection::STG => {
+                if theta <= -90.0 {
+                    return None;
+                }
+                Some(2.0 * R2D * ((90.0 - theta) / 2.0).to_radians().tan())
+            }
+            Projection::ZEA => Some(2.0 * R2D * sind((90.0 - theta) / 2.0)),
+            Projection::ZPN => {
+                let coeffs = zpn_coefficients(params);
+                let zeta = (90.0 - theta).to_radians();
+                // Only the monotonic part of the polynomial is invertible
+                let r = zpn_eval(&coeffs, zeta).0;
+                match zpn_zeta(&coeffs, r) {
+                    Some(z) if (z - zeta).abs() < 1.0e-9 => Some(r),
+                    _ => None,
+                }
+            }
+            _ => None,
+        }
+    }
+
+    /// Intermediate world coordinates to native spherical coordinates
+    ///
+    /// # Arguments
+    ///
+    /// * `x` - First intermediate world coordinate, degrees
+    /// * `y` - Second intermediate world coordinate, degrees
+    /// * `params` - Projection parameters
+    ///
+    /// # Returns
+    ///
+    /// Native (phi, theta) in degrees, or None if the point
+    /// lies outside the projection boundary
+    ///
+    pub fn deproject(&self, x: f64, y: f64, params: &ProjectionParams) -> Option<(f64, f64)> {
+        match self {
+            Projection::TAN
+            | Projection::SIN
+            | Projection::ARC
+            | Projection::STG
+            | Projection::ZEA
+            | Projection::TPV
+            | Projection::ZPN => {
+                let (x, y) = match self {
+                    Projection::TPV => tpv_distort(x, y, params),
+                    _ => (x, y),
+                };
+                let r = x.hypot(y);
+                let phi = if r == 0.0 { 0.0 } else { atan2d(x, -y) };
+                Some((phi, self.zenithal_theta(r, params)?))
+            }
+            Projection::CAR => {
+                if y.abs() > 90.0 {
+                    return None;
+                }
+                Some((x, y))
+            }
+            Projection::CEA => {
+                let lambda = params.lat.get(&1).copied().unwrap_or(1.0);
+                let s = lambda * y / R2D;
+                if s.abs() > 1.0 {
+                    return None;
+                }
+                Some((x, s.asin().to_degrees()))
+            }
+            Projection::MOL => {
+                let s = y / (2.0f64.sqrt() * R2D);
+                if s.abs() > 1.0 {
+                    return None;
+                }
+                let gamma = s.asin();
+                let phi = PI * x / (2.0 * 2.0f64.sqrt() * gamma.cos());
+                if phi.abs() > 180.0 + 1.0e-10 {
+                    return None;
+                }
+                let theta = ((2.0 * gamma + (2.0 * gamma).sin()) / PI)
+                    .clamp(-1.0, 1.0)
+                    .asin();
+                Some((phi, theta.to_degrees()))
+            }
+            Projection::AIT => {
+                let z2 = 1.0 - (x / (4.0 * R2D)).powi(2) - (y / (2.0 * R2D)).powi(2);
+                if z2 < 0.5 {
+                    return None;
+                }
+                let z = z2.sqrt();
+                let phi = 2.0 * atan2d(z * x / (2.0 * R2D), 2.0 * z2 - 1.0);
+                let theta = (y * z / R2D).clamp(-1.0, 1.0).asin().to_degrees();
+                Some((phi, theta))
+            }
+        }
+    }
+
+    /// Native spherical coordinates to intermediate world coordinates
+    ///
+    /// # Arguments
+    ///
+    /// * `phi` - Native longitude, degrees
+    /// * `theta` - Native latitude, degrees
+    /// * `params` - Projection parameters
+    ///
+    /// # Returns
+    ///
+    /// Intermediate world coordinates (x, y) in degrees, or None if
+    /// the point cannot be projected
+    ///
+    pub fn project(&self, phi: f64, theta: f64, params: &ProjectionParams) -> Option<(f64, f64)> {
+        match self {
+            Projection::TAN
+            | Projection::SIN
+            | Projection::ARC
+            | Projection::STG
+            | Projection::ZEA
+            | Projection::TPV
+            | Projection::ZPN => {
+                let r = self.zenithal_r(theta, params)?;
+                let (x, y) = (r * sind(phi), -r * cosd(phi));
+                match self {
+                    Projection::TPV => tpv_undistort(x, y, params),
+                    _ => Some((x, y)),
+                }
+            }
+            Projection::CAR => Some((phi, theta)),
+            Projection::CEA => {
+                let lambda = params.lat.get(&1).copied().unwrap_or(1.0);
+                Some((phi, R2D * sind(theta) / lambda))
+            }
+            Projection::MOL => {
+                // Solve gamma + sin(2 gamma) / 2 = pi/2 sin(theta) by Newton iteration
+                let target = PI / 2.0 * sind(theta);
+                let mut gamma = theta.to_radians();
+                for _ in 0..100 {
+                    let f = gamma + 0.5 * (2.0 * gamma).sin() - target;
+                    let df = 1.0 + (2.0 * gamma).cos();
+                    if df.abs() < 1.0e-15 {
+                        break;
+                    }
+                    let step = f / df;
+                    gamma -= step;
+                    if step.abs() < 1.0e-15 {
+                        break;
+                    }
+                }
+                let x = 2.0 * 2.0f64.sqrt() / PI * phi * gamma.cos();
+                let y = 2.0f64.sqrt() * R2D * gamma.sin();
+                Some((x, y))
+            }
+            Projection::AIT => {
+                let gamma = R2D * (2.0 / (1.0 + cosd(theta) * cosd(phi / 2.0))).sqrt();
+                Some((
+                    2.0 * gamma * cosd(theta) * sind(phi / 2.0),
+                    gamma * sind(theta),
+                ))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_roundtrip() {
+        let params = ProjectionParams::default();
+        for proj in [
+            Projection::TAN,
+            Projection::SIN,
+            Projection::ARC,
+            Projection::STG,
+            Projection::ZEA,
+            Projection::AIT,
+            Projection::CAR,
+            Projection::MOL,
+            Projection::CEA,
+            Projection::TPV,
+            Projection::ZPN,
+        ] {
+            for (phi, theta) in [(10.0, 60.0), (-120.0, 45.0), (170.0, 80.0)] {
+                let (x, y) = proj.project(phi, theta, &params).unwrap();
+                let (p2, t2) = proj.deproject(x, y, &params).unwrap();
+                assert!((p2 - phi).abs() < 1.0e-9, "{:?} phi {} {}", proj, phi, p2);
+                assert!(
+                    (t2 - theta).abs() < 1.0e-9,
+                    "{:?} theta {} {}",
+                    proj,
+                    theta,
+                    t2
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_tpv() {
+        let mut params = ProjectionParams::default();
+        params.lon.insert(1, 1.0);
+        params.lon.insert(4, 0.01);
+        params.lat.insert(1, 1.0);
+        params.lat.insert(5, -0.02);
+        let (x, y) = Projection::TPV.project(30.0, 88.0, &params).unwrap();
+        let (phi, theta) = Projection::TPV.deproject(x, y, &params).unwrap();
+        assert!((phi - 30.0).abs() < 1.0e-9);
+        assert!((theta - 88.0).abs() < 1.0e-9);
+        // Distortion changes the projected position
+        let (x0, _) = Projection::TAN.project(30.0, 88.0, &params).unwrap();
+        assert!((x - x0).abs() > 1.0e-6);
+    }
+
+    #[test]
+    fn test_zpn() {
+        let mut params = ProjectionParams::default();
+        params.lat.insert(1, 1.0);
+        params.lat.insert(3, -0.05);
+        for theta in [89.0, 60.0, 30.0] {
+            let (x, y) = Projection::ZPN.project(45.0, theta, &params).unwrap();
+            let zeta = (90.0 - theta).to_radians();
+            let r = R2D * (zeta - 0.05 * zeta.powi(3));
+            assert!((x.hypot(y) - r).abs() < 1.0e-9);
+            let (phi, t) = Projection::ZPN.deproject(x, y, &params).unwrap();
+            assert!((phi - 45.0).abs() < 1.0e-9 && (t - theta).abs() < 1.0e-9);
+        }
+        // Beyond the turning point of the polynomial
+        assert!(Projection::ZPN.project(0.0, -60.0, &params).is_none());
+    }
+}
@@ -0,0 +1,114 @@
+use crate::Header;
+use crate::HeaderError;
+use crate::Matrix;
+
+use super::float_value;
+
+/// Simple Imaging Polynomial (SIP) distortion coefficients
+///
+/// The distortion is applied to pixel offsets (u, v) relative to CRPIX
+/// before the linear transformation:
+/// `u' = u + sum(A_p_q * u^p * v^q)`, and likewise for `v'` with B_p_q.
+/// The optional AP_p_q and BP_p_q coefficients describe the inverse.
+///
+/// See Shupe et al. (2005), "The SIP Convention for Representing
+/// Distortion in FITS Image Headers"
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Sip {
+    /// Forward coefficients for the first axis, indexed by (p, q)
+    pub a: Matrix,
+    /// Forward coefficients for the second axis, indexed by (p, q)
+    pub b: Matrix,
+    /// Inverse coefficients for the first axis, indexed by (p, q)
+    pub ap: Option<Matrix>,
+    /// Inverse coefficients for the second axis, indexed by (p, q)
+    pub bp: Option<Matrix>,
+}
+
+/// Read polynomial coefficients with the given prefix, e.g. "A" for A_ORDER, A_p_q
+fn read_poly(header: &Header, prefix: &str) -> Result<Option<Matrix>, HeaderError> {
+    let key = format!("{}_ORDER", prefix);
+    let order = match header.value(&key) {
+        None => return Ok(None),
+        Some(crate::KeywordValue::Int(v)) if *v >= 0 => *v as usize,
+        Some(_) => return Err(HeaderError::UnexpectedValueType(key)),
+    };
+    let mut m = Matrix::zeros(order + 1, order + 1);
+    for p in 0..=order {
+        for q in 0..=(order - p) {
+            if let Some(c) = float_value(header.value(&format!("{}_{}_{}", prefix, p, q))) {
+                m[(p, q)] = c;
+            }
+        }
+    }
+    Ok(Some(m))
+}
+
+/// Evaluate polynomial sum(c[(p, q)] * u^p * v^q)
+fn eval(c: &Matrix, u: f64, v: f64) -> f64 {
+    let mut sum = 0.0;
+    for p in 0..c.nrows() {
+        for q in 0..c.ncols() {
+            if c[(p, q)] != 0.0 {
+                sum += c[(p, q)] * u.powi(p as i32) * v.powi(q as i32);
+            }
+        }
+    }
+    sum
+}
+
+impl Sip {
+    /// Parse SIP coefficients from a header
+    ///
+    /// # Returns
+    ///
+    /// The coefficients, or None if A_ORDER and B_ORDER are not both present
+    ///
+    pub fn from_header(header: &Header) -> Result<Option<Self>, HeaderError> {
+        let (Some(a), Some(b)) = (read_poly(header, "A")?, read_poly(header, "B")?) else {
+            return Ok(None);
+        };
+        Ok(Some(Sip {
+            a,
+            b,
+            ap: read_poly(header, "AP")?,
+            bp: read_poly(header, "BP")?,
+        }))
+    }
+
+    /// Apply forward distortion to pixel offsets relative to CRPIX
+    pub fn apply(&self, u: f64, v: f64) -> (f64, f64) {
+        (u + eval(&self.a, u, v), v + eval(&self.b, u, v))
+    }
+
+    /// Remove distortion from pixel offsets relative to CRPIX
+    ///
+    /// Uses the AP/BP inverse coefficients as a starting point if present,
+    /// then refines by Newton iteration on the forward polynomial
+    pub fn invert(&self, up: f64, vp: f64) -> (f64, f64) {
+        let (mut u, mut v) = match (&self.ap, &self.bp) {
+            (Some(ap), Some(bp)) => (up + eval(ap, up, vp), vp + eval(bp, up, vp)),
+            _ => (up, vp),
+        };
+        let h = 1.0e-6;
+        for _ in 0..50 {
+            let (fu, fv) = self.apply(u, v);
+            let (eu, ev) = (fu - up, fv - vp);
+            if eu.abs() < 1.0e-12 && ev.abs() < 1.0e-12 {
+                break;
+            }
+            let (fuu, fvu) = self.apply(u + h, v);
+            let (fuv, fvv) = self.apply(u, v + h);
+            let (j11, j21) = ((fuu - fu) / h, (fvu - fv) / h);
+            let (j12, j22) = ((fuv - fu) / h, (fvv - fv) / h);
+            let det = j11 * j22 - j12 * j21;
+            if det == 0.0 {
+                break;
+            }
+            u -= (j22 * eu - j12 * ev) / det;
+            v -= (-j21 * eu + j11 * ev) / det;
+        }
+        (u, v)
+    }
+}
@@ -0,0 +1,468 @@
+//! Spectral coordinates and non-linear axis algorithms
+//!
+//! See Greisen et al. (2006), "Representations of spectral
+//! coordinates in FITS" (WCS Paper III)
+
+use super::float_value;
+use super::split_ctype;
+use crate::BinTableValue;
+use crate::FITSError;
+use crate::HDUData;
+use crate::Header;
+use crate::KeywordValue;
+use crate::WCSError;
+use crate::FITS;
+use crate::WCS;
+
+/// Speed of light, m/s
+const C: f64 = 299792458.0;
+/// Planck constant, J s
+const H: f64 = 6.62607015e-34;
+
+/// Spectral coordinate type, from the first four characters of CTYPEn
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SpectralType {
+    /// Frequency, Hz
+    FREQ,
+    /// Energy, J
+    ENER,
+    /// Wavenumber, 1/m
+    WAVN,
+    /// Radio velocity, m/s
+    VRAD,
+    /// Vacuum wavelength, m
+    WAVE,
+    /// Optical velocity, m/s
+    VOPT,
+    /// Redshift
+    ZOPT,
+    /// Air wavelength, m
+    AWAV,
+    /// Apparent radial velocity, m/s
+    VELO,
+    /// Beta factor (v/c)
+    BETA,
+}
+
+impl SpectralType {
+    /// Spectral type from the axis type portion of CTYPE, e.g. "FREQ"
+    pub fn from_code(code: &str) -> Option<Self> {
+        match code {
+            "FREQ" => Some(SpectralType::FREQ),
+            "ENER" => Some(SpectralType::ENER),
+            "WAVN" => Some(SpectralType::WAVN),
+            "VRAD" => Some(SpectralType::VRAD),
+            "WAVE" => Some(SpectralType::WAVE),
+            "VOPT" => Some(SpectralType::VOPT),
+            "ZOPT" => Some(SpectralType::ZOPT),
+            "AWAV" => Some(SpectralType::AWAV),
+            "VELO" => Some(SpectralType::VELO),
+            "BETA" => Some(SpectralType::BETA),
+            _ => None,
+        }
+    }
+
+    /// True if conversion to or from frequency requires a rest frequency
+    pub fn needs_rest(&self) -> bool {
+        matches!(
+            self,
+            SpectralType::VRAD
+                | SpectralType::VOPT
+                | SpectralType::ZOPT
+                | SpectralType::VELO
+                | SpectralType::BETA
+        )
+    }
+
+    /// Convert a value of this type, in SI units, to frequency in Hz
+    ///
+    /// # Arguments
+    ///
+    /// * `value` - Spectral coordinate value
+    /// * `restfrq` - Rest frequency in Hz, required for velocity and redshift types
+    ///
+    pub fn to_frequency(&self, value: f64, restfrq: Option<f64>) -> Option<f64> {
+        let nu0 = match self.needs_rest() {
+            true => restfrq?,
+            false => 0.0,
+        };
+        Some(match self {
+            SpectralType::FREQ => value,
+            SpectralType::ENER => value / H,
+            SpectralType::WAVN => value * C,
+            SpectralType::VRAD => nu0 * (1.0 - value / C),
+            SpectralType::WAVE => C / value,
+            SpectralType::VOPT => nu0 / (1.0 + value / C),
+            SpectralType::ZOPT => nu0 / (1.0 + value),
+            SpectralType::AWAV => C / (value * air_index(value)),
+            SpectralType::VELO => nu0 * ((C - value) / (C + value)).sqrt(),
+            SpectralType::BETA => nu0 * ((1.0 - value) / (1.0 + value)).sqrt(),
+        })
+    }
+
+    /// Convert a frequency in Hz to a value of this type, in SI units
+    ///
+    /// # Arguments
+    ///
+    /// * `nu` - Frequency, Hz
+    /// * `restfrq` - Rest frequency in Hz, required for velocity and redshift types
+    ///
+    pub fn from_frequency(&self, nu: f64, restfrq: Option<f64>) -> Option<f64> {
+        let nu0 = match self.needs_rest() {
+            true => restfrq?,
+            false => 0.0,
+        };
+        Some(match self {
+            SpectralType::FREQ => nu,
+            SpectralType::ENER => nu * H,
+            SpectralType::WAVN => nu / C,
+            SpectralType::VRAD => C * (nu0 - nu) / nu0,
+            SpectralType::WAVE => C / nu,
+            SpectralType::VOPT => C * (nu0 / nu - 1.0),
+            SpectralType::ZOPT => nu0 / nu - 1.0,
+            SpectralType::AWAV => {
+                // Iterate, as the refractive index depends on air wavelength
+                let lambda = C / nu;
+                let mut air = lambda;
+                for _ in 0..4 {
+                    air = lambda / air_index(air);
+                }
+                air
+            }
+            SpectralType::VELO => C * (nu0 * nu0 - nu * nu) / (nu0 * nu0 + nu * nu),
+            SpectralType::BETA => (nu0 * nu0 - nu * nu) / (nu0 * nu0 + nu * nu),
+        })
+    }
+}
+
+/// Refractive index of air at a given air wavelength in m
+///
+/// See Equation 65 of WCS Paper III
+fn air_index(lambda: f64) -> f64 {
+    let s2 = (1.0e-6 / lambda).powi(2);
+    1.0 + 1.0e-6 * (287.6155 + 1.62887 * s2 + 0.01360 * s2 * s2)
+}
+
+/// Scale factor to SI units for common spectral units (CUNITn)
+fn unit_scale(unit: &str) -> Option<f64> {
+    match unit.trim() {
+        "" | "Hz" | "m" | "m/s" | "J" | "/m" | "m-1" => Some(1.0),
+        "kHz" => Some(1.0e3),
+        "MHz" => Some(1.0e6),
+        "GHz" => Some(1.0e9),
+        "km" => Some(1.0e3),
+        "cm" => Some(1.0e-2),
+        "mm" => Some(1.0e-3),
+        "um" => Some(1.0e-6),
+        "nm" => Some(1.0e-9),
+        "Angstrom" => Some(1.0e-10),
+        "km/s" => Some(1.0e3),
+        "eV" => Some(1.602176634e-19),
+        "keV" => Some(1.602176634e-16),
+        "/cm" | "cm-1" => Some(1.0e2),
+        _ => None,
+    }
+}
+
+/// Lookup table for an axis using the -TAB algorithm
+///
+/// The coordinate and index arrays are held in a binary table
+/// extension, and must be loaded with `WCS::load_tab` before use.
+/// Only one-dimensional tables are supported.
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TabularAxis {
+    /// EXTNAME of the binary table holding the arrays (PSi_0)
+    pub extname: String,
+    /// EXTVER of the binary table (PVi_1)
+    pub extver: i64,
+    /// Column holding the coordinate array (PSi_1)
+    pub coord_column: String,
+    /// Column holding the index vector (PSi_2), if any
+    pub index_column: Option<String>,
+    /// Coordinate array, once loaded
+    pub coords: Option<Vec<f64>>,
+    /// Index vector, once loaded.  If there is no index column,
+    /// the index vector is 1, 2, ..., K
+    pub index: Option<Vec<f64>>,
+}
+
+/// Fractional one-based position of `value` within a monotonic array,
+/// allowing extrapolation by half an element at either end
+fn locate(array: &[f64], value: f64) -> Option<f64> {
+    let k = array.len();
+    if k == 0 {
+        return None;
+    }
+    if k == 1 {
+        return match value == array[0] {
+            true => Some(1.0),
+            false => None,
+        };
+    }
+    let increasing = array[k - 1] >= array[0];
+    for i in 0..k - 1 {
+        let (a, b) = (array[i], array[i + 1]);
+        let inside = match increasing {
+            true => a <= value && value <= b,
+            false => b <= value && value <= a,
+        };
+        if inside && a != b {
+            return Some(i as f64 + 1.0 + (value - a) / (b - a));
+        }
+    }
+    // Extrapolate from the end segments
+    let (pos, a, b) = match (value - array[0]) * (array[1] - array[0]) < 0.0 {
+        true => (1.0, array[0], array[1]),
+        false => (k as f64 - 1.0, array[k - 2], array[k - 1]),
+    };
+    let upsilon = pos + (value - a) / (b - a);
+    match (0.5..=k as f64 + 0.5).contains(&upsilon) {
+        true => Some(upsilon),
+        false => None,
+    }
+}
+
+/// Value of an array at a fractional one-based position, linearly interpolated
+fn interpolate(array: &[f64], upsilon: f64) -> Option<f64> {
+    let k = array.len();
+    if k == 0 || !(0.5..=k as f64 + 0.5).contains(&upsilon) {
+        return None;
+    }
+    if k == 1 {
+        return Some(array[0]);
+    }
+    let i = (upsilon.floor() as usize).clamp(1, k - 1);
+    let (a, b) = (array[i - 1], array[i]);
+    Some(a + (upsilon - i as f64) * (b - a))
+}
+
+/// Flatten a numeric table cell into a vector of floats
+fn cell_values(value: &BinTableValue) -> Vec<f64> {
+    match value {
+        BinTableValue::Array(v) => v.iter().flat_map(cell_values).collect(),
+        v => v.as_f64().into_iter().collect(),
+    }
+}
+
+impl TabularAxis {
+    /// Parse the -TAB parameters for a (zero-based) axis from a header
+    pub(crate) fn from_header(header: &Header, axis: usize, alt: &str) -> Option<Self> {
+        let ps = |m: usize| match header.value(format!("PS{}_{}{}", axis + 1, m, alt).as_str()) {
+            Some(KeywordValue::String(s)) => Some(s.clone()),
+            _ => None,
+        };
+        let extver = match header.value(format!("PV{}_1{}", axis + 1, alt).as_str()) {
+            Some(KeywordValue::Int(v)) => *v,
+            _ => 1,
+        };
+        Some(TabularAxis {
+            extname: ps(0)?,
+            extver,
+            coord_column: ps(1)?,
+            index_column: ps(2),
+            coords: None,
+            index: None,
+        })
+    }
+
+    /// Coordinate value for intermediate coordinate psi
+    fn world(&self, psi: f64) -> Option<f64> {
+        let coords = self.coords.as_ref()?;
+        let upsilon = match &self.index {
+            Some(index) => locate(index, psi)?,
+            None => psi,
+        };
+        interpolate(coords, upsilon)
+    }
+
+    /// Intermediate coordinate psi for a coordinate value
+    fn psi(&self, world: f64) -> Option<f64> {
+        let upsilon = locate(self.coords.as_ref()?, world)?;
+        match &self.index {
+            Some(index) => interpolate(index, upsilon),
+            None => Some(upsilon),
+        }
+    }
+}
+
+impl WCS {
+    /// Index of the spectral axis, if any
+    pub fn spectral_axis(&self) -> Option<usize> {
+        self.ctype
+            .as_ref()?
+            .iter()
+            .position(|c| SpectralType::from_code(split_ctype(c).0).is_some())
+    }
+
+    /// Type of the spectral axis, if any
+    pub fn spectral_type(&self) -> Option<SpectralType> {
+        let axis = self.spectral_axis()?;
+        SpectralType::from_code(split_ctype(&self.ctype.as_ref()?[axis]).0)
+    }
+
+    /// Rest frequency in Hz, from RESTFRQ or RESTWAV
+    pub fn rest_frequency(&self) -> Option<f64> {
+        self.restfrq.or(self.restwav.map(|w| C / w))
+    }
+
+    /// Apply the non-linear algorithm (-LOG or -TAB) of an axis
+    /// to its intermediate world coordinate
+    pub(crate) fn nonlinear_world(&self, axis: usize, x: f64) -> Result<f64, WCSError> {
+        let crval = self
+            .crval
+            .as_ref()
+            .and_then(|v| v.get(axis).copied())
+            .unwrap_or(0.0);
+        let Some(ctype) = self.ctype.as_ref().and_then(|v| v.get(axis)) else {
+            return Ok(x + crval);
+        };
+        match split_ctype(ctype).1 {
+            Some("LOG") => Ok(crval * (x / crval).exp()),
+            Some("TAB") => self
+                .tab
+                .get(&axis)
+                .ok_or_else(|| WCSError::MissingKeyword(format!("PS{}_0", axis + 1)))?
+                .world(x + crval)
+                .ok_or_else(|| WCSError::InvalidCoordinate(format!("{} outside table", x))),
+            _ => Ok(x + crval),
+        }
+    }
+
+    /// Inverse of `nonlinear_world`
+    pub(crate) fn nonlinear_intermediate(&self, axis: usize, s: f64) -> Result<f64, WCSError> {
+        let crval = self
+            .crval
+            .as_ref()
+            .and_then(|v| v.get(axis).copied())
+            .unwrap_or(0.0);
+        let Some(ctype) = self.ctype.as_ref().and_then(|v| v.get(axis)) else {
+            return Ok(s - crval);
+        };
+        match split_ctype(ctype).1 {
+            Some("LOG") => {
+                if s / crval <= 0.0 {
+                    return Err(WCSError::InvalidCoordinate(format!("{} for -LOG axis", s)));
+                }
+                Ok(crval * (s / crval).ln())
+            }
+            Some("TAB") => Ok(self
+                .tab
+                .get(&axis)
+                .ok_or_else(|| WCSError::MissingKeyword(format!("PS{}_0", axis + 1)))?
+                .psi(s)
+                .ok_or_else(|| WCSError::InvalidCoordinate(format!("{} outside table", s)))?
+                - crval),
+            _ => Ok(s - crval),
+        }
+    }
+
+    /// Load coordinate arrays for -TAB axes from binary table extensions
+    ///
+    /// # Arguments
+    ///
+    /// * `fits` - FITS file holding the binary tables referenced by PSi_0
+    ///
+    pub fn load_tab(&mut self, fits: &FITS) -> Result<(), FITSError> {
+        for tab in self.tab.values_mut() {
+            let table = fits
+                .iter()
+                .find(|hdu| {
+                    let extver = match hdu.value("EXTVER") {
+                        Some(KeywordValue::Int(v)) => *v,
+                        _ => 1,
+                    };
+                    hdu.value("EXTNAME") == Some(&KeywordValue::String(tab.extname.clone()))
+                        && extver == tab.extver
+                })
+                .and_then(|hdu| match &hdu.data {
+                    HDUData::BinTable(t) => Some(t),
+                    _ => None,
+                })
+                .ok_or_else(|| WCSError::MissingKeyword(format!("table {}", tab.extname)))?;
+            let column = |name: &str| {
+                table
+                    .ttype
+                    .iter()
+                    .position(|t| t.as_deref().map(|t| t.trim()) == Some(name))
+                    .ok_or_else(|| WCSError::MissingKeyword(format!("column {}", name)))
+            };
+            let col = column(&tab.coord_column)?;
+            tab.coords = Some(cell_values(&table.at(0, col)?));
+            if let Some(name) = &tab.index_column {
+                let col = column(name)?;
+                tab.index = Some(cell_values(&table.at(0, col)?));
+            }
+        }
+        Ok(())
+    }
+
+    /// Spectral coordinate at a pixel index along the spectral axis
+    ///
+    /// Other axes are held at their reference pixel
+    ///
+    /// # Arguments
+    ///
+    /// * `index` - Zero-based pixel index along the spectral axis
+    ///
+    /// # Returns
+    ///
+    /// Spectral coordinate in the type and units given by CTYPE and CUNIT
+    ///
+    pub fn spectral_at(&self, index: f64) -> Result<f64, FITSError> {
+        let axis = self
+            .spectral_axis()
+            .ok_or_else(|| WCSError::MissingKeyword("spectral CTYPE".into()))?;
+        let crpix = self.crpix.clone().unwrap_or_default();
+        let mut pixel: Vec<f64> = (0..self.naxes())
+            .map(|i| crpix.get(i).copied().unwrap_or(0.0) - 1.0)
+            .collect();
+        pixel[axis] = index;
+        Ok(self.pixel_to_world(&pixel)?[axis])
+    }
+
+    /// Spectral coordinate at a pixel index, converted to another spectral type
+    ///
+    /// Velocity and redshift types require a rest frequency
+    /// (RESTFRQ or RESTWAV).
+    ///
+    /// # Arguments
+    ///
+    /// * `index` - Zero-based pixel index along the spectral axis
+    /// * `target` - Desired spectral type
+    ///
+    /// # Returns
+    ///
+    /// Spectral coordinate of the desired type, in SI units
+    /// (Hz, m, m/s, J, or 1/m)
+    ///
+    pub fn spectral_as(&self, index: f64, target: SpectralType) -> Result<f64, FITSError> {
+        let stype = self
+            .spectral_type()
+            .ok_or_else(|| WCSError::MissingKeyword("spectral CTYPE".into()))?;
+        let axis = self.spectral_axis().unwrap();
+        let unit = self
+            .cunit
+            .as_ref()
+            .and_then(|u| u.get(axis))
+            .cloned()
+            .unwrap_or_default();
+        let scale = unit_scale(&unit)
+            .ok_or_else(|| WCSError::InvalidCoordinate(format!("unknown unit {}", unit)))?;
+        let value = self.spectral_at(index)? * scale;
+        let rest = self.rest_frequency();
+        let nu = stype
+            .to_frequency(value, rest)
+            .ok_or_else(|| WCSError::MissingKeyword("RESTFRQ".into()))?;
+        Ok(target
+            .from_frequency(nu, rest)
+            .ok_or_else(|| WCSError::MissingKeyword("RESTFRQ".into()))?)
+    }
+}
+
+/// Parse rest frequency and wavelength keywords into a WCS
+pub(crate) fn parse_rest(wcs: &mut WCS, header: &Header, alt: &str) {
+    wcs.restfrq = float_value(header.value(format!("RESTFRQ{}", alt).as_str()))
+        .or_else(|| float_value(header.value("RESTFREQ")));
+    wcs.restwav = float_value(header.value(format!("RESTWAV{}", alt).as_str()));
+}
@@ -0,0 +1,402 @@
+use super::Projection;
+use super::ProjectionParams;
+use super::Sip;
+use crate::Angle;
+use crate::FITSError;
+use crate::Matrix;
+use crate::WCSError;
+use crate::WCS;
+
+fn sind(x: f64) -> f64 {
+    x.to_radians().sin()
+}
+
+fn cosd(x: f64) -> f64 {
+    x.to_radians().cos()
+}
+
+/// Rotate native spherical coordinates to celestial coordinates
+///
+/// See Equation 2 of WCS Paper II.  All angles in degrees.
+/// Latitude is computed with atan2 rather than asin to retain
+/// precision near the poles
+fn native_to_celestial(phi: f64, theta: f64, pole: (f64, f64, f64)) -> (f64, f64) {
+    let (alpha_p, delta_p, phi_p) = pole;
+    let dphi = phi - phi_p;
+    // Components of cos(delta) * (sin, cos) of (alpha - alpha_p)
+    let y = -cosd(theta) * sind(dphi);
+    let x = sind(theta) * cosd(delta_p) - cosd(theta) * sind(delta_p) * cosd(dphi);
+    let z = sind(theta) * sind(delta_p) + cosd(theta) * cosd(delta_p) * cosd(dphi);
+    let alpha = alpha_p + y.atan2(x).to_degrees();
+    let delta = z.atan2(x.hypot(y)).to_degrees();
+    (alpha.rem_euclid(360.0), delta)
+}
+
+/// Rotate celestial coordinates to native spherical coordinates
+///
+/// See Equation 5 of WCS Paper II.  All angles in degrees
+fn celestial_to_native(alpha: f64, delta: f64, pole: (f64, f64, f64)) -> (f64, f64) {
+    let (alpha_p, delta_p, phi_p) = pole;
+    let dalpha = alpha - alpha_p;
+    // Components of cos(theta) * (sin, cos) of (phi - phi_p)
+    let y = -cosd(delta) * sind(dalpha);
+    let x = sind(delta) * cosd(delta_p) - cosd(delta) * sind(delta_p) * cosd(dalpha);
+    let z = sind(delta) * sind(delta_p) + cosd(delta) * cosd(delta_p) * cosd(dalpha);
+    let phi = phi_p + y.atan2(x).to_degrees();
+    let theta = z.atan2(x.hypot(y)).to_degrees();
+    // Normalize to range [-180, 180)
+    ((phi + 180.0).rem_euclid(360.0) - 180.0, theta)
+}
+
+/// Split CTYPE into axis type and projection code,
+/// e.g. "RA---TAN" -> ("RA", Some("TAN"))
+pub(crate) fn split_ctype(ctype: &str) -> (&str, Option<&str>) {
+    if ctype.len() >= 8 && ctype.is_char_boundary(4) && ctype.as_bytes()[4] == b'-' {
+        (ctype[0..4].trim_end_matches('-'), Some(&ctype[5..8]))
+    } else {
+        (ctype.trim(), None)
+    }
+}
+
+impl WCS {
+    /// Number of world coordinate axes
+    ///
+    /// Taken from WCSAXES if present, otherwise the largest
+    /// number of axes described by any of the indexed keywords
+    pub fn naxes(&self) -> usize {
+        if let Some(n) = self.wcaxes {
+            return n;
+        }
+        [
+            self.ctype.as_ref().map_or(0, |v| v.len()),
+            self.crval.as_ref().map_or(0, |v| v.len()),
+            self.crpix.as_ref().map_or(0, |v| v.len()),
+            self.cdelt.as_ref().map_or(0, |v| v.len()),
+            self.cunit.as_ref().map_or(0, |v| v.len()),
+            self.cd.as_ref().map_or(0, |m| m.nrows()),
+            self.pc.as_ref().map_or(0, |m| m.nrows()),
+        ]
+        .into_iter()
+        .max()
+        .unwrap_or(0)
+    }
+
+    /// Indices of the celestial longitude and latitude axes
+    ///
+    /// # Returns
+    ///
+    /// (longitude axis, latitude axis), or None if the WCS
+    /// does not describe a projected celestial coordinate pair
+    ///
+    pub fn celestial_axes(&self) -> Option<(usize, usize)> {
+        let ctype = self.ctype.as_ref()?;
+        let mut lon = None;
+        let mut lat = None;
+        for (i, c) in ctype.iter().enumerate() {
+            if let (axtype, Some(_)) = split_ctype(c) {
+                if axtype == "RA" || (axtype.len() == 4 && axtype.ends_with("LON")) {
+                    lon = Some(i);
+                } else if axtype == "DEC" || (axtype.len() == 4 && axtype.ends_with("LAT")) {
+                    lat = Some(i);
+                }
+            }
+        }
+        Some((lon?, lat?))
+    }
+
+    /// Celestial projection, from the CTYPE of the longitude axis
+    ///
+    /// # Returns
+    ///
+    /// The projection, None if there are no celestial axes,
+    /// or an error if the projection code is not supported
+    ///
+    pub fn projection(&self) -> Result<Option<Projection>, WCSError> {
+        let Some((lon, _)) = self.celestial_axes() else {
+            return Ok(None);
+        };
+        let ctype = &self.ctype.as_ref().unwrap()[lon];
+        let code = split_ctype(ctype).1.unwrap_or_default();
+        match Projection::from_code(code) {
+            Some(p) => Ok(Some(p)),
+            None => Err(WCSError::UnsupportedProjection(ctype.clone())),
+        }
+    }
+
+    /// Linear transformation matrix from pixel offsets to
+    /// intermediate world coordinates
+    ///
+    /// This is CD if present (any CDELT values are ignored), otherwise
+    /// CDELT * PC, with missing values taking their defaults.  Legacy
+    /// CROTAn rotation is converted to PC when the header is parsed
+    pub(crate) fn linear_matrix(&self) -> Matrix {
+        let n = self.naxes();
+        if let Some(cd) = &self.cd {
+            return cd.clone();
+        }
+        let mut m = self.pc.clone().unwrap_or_else(|| Matrix::identity(n, n));
+        if let Some(cdelt) = &self.cdelt {
+            for (i, c) in cdelt.iter().enumerate().take(m.nrows()) {
+                m.row_mut(i).scale_mut(*c);
+            }
+        }
+        m
+    }
+
+    /// SIP distortion, if present and selected by a "-SIP" CTYPE suffix
+    fn active_sip(&self) -> Option<&Sip> {
+        let sip = self.sip.as_ref()?;
+        if self.naxes() < 2 {
+            return None;
+        }
+        let ctype = self.ctype.as_ref()?;
+        match ctype.iter().any(|c| c.ends_with("-SIP")) {
+            true => Some(sip),
+            false => None,
+        }
+    }
+
+    /// Projection parameters (PVi_m) for the celestial axes
+    pub(crate) fn projection_params(&self) -> ProjectionParams {
+        let mut params = ProjectionParams::default();
+        if let Some((lon, lat)) = self.celestial_axes() {
+            for (&(i, m), &v) in self.pv.iter() {
+                if i == lon {
+                    params.lon.insert(m, v);
+                } else if i == lat {
+                    params.lat.insert(m, v);
+                }
+            }
+        }
+        params
+    }
+
+    /// Native longitude and latitude of the celestial pole from
+    /// LONPOLEa and LATPOLEa, or the equivalent PVi_3a and PVi_4a
+    /// of the longitude axis
+    ///
+    /// Polynomial projections (TPV, and TAN with SIP distortion) use
+    /// PVi_m for distortion coefficients, so they never fall back to
+    /// the PV keywords
+    fn pole_keywords(&self, projection: &Projection, lon: usize) -> (Option<f64>, Option<f64>) {
+        let polynomial = *projection == Projection::TPV || self.active_sip().is_some();
+        let pv = |m| match polynomial {
+            true => None,
+            false => self.pv.get(&(lon, m)).copied(),
+        };
+        (
+            self.lonpole.or_else(|| pv(3)),
+            self.latpole.or_else(|| pv(4)),
+        )
+    }
+
+    /// Celestial coordinates of the native pole, and native
+    /// longitude of the celestial pole: (alpha_p, delta_p, phi_p)
+    ///
+    /// See Section 2.4 of WCS Paper II
+    fn celestial_pole(&self, projection: &Projection, lon: usize, lat: usize) -> (f64, f64, f64) {
+        let crval = self.crval.clone().unwrap_or_default();
+        let alpha0 = crval.get(lon).copied().unwrap_or(0.0);
+        let delta0 = crval.get(lat).copied().unwrap_or(0.0);
+        let (phi0, theta0) = projection.native_reference();
+        let (lonpole, latpole) = self.pole_keywords(projection, lon);
+        let phi_p = lonpole.unwrap_or(if delta0 >= theta0 { phi0 } else { phi0 + 180.0 });
+        let lat_pole = latpole.unwrap_or(90.0);
+
+        // For zenithal projections the fiducial point is the native pole
+        if theta0 == 90.0 {
+            return (alpha0, delta0, phi_p);
+        }
+
+        // Two solutions for the latitude of the native pole;
+        // choose the one closest to LATPOLE
+        let (u, v) = if phi_p == phi0 {
+            (theta0, 90.0 - delta0)
+        } else {
+            let x = cosd(theta0) * cosd(phi_p - phi0);
+            let y = sind(theta0);
+            let z = x.hypot(y);
+            (
+                y.atan2(x).to_degrees(),
+                (sind(delta0) / z).clamp(-1.0, 1.0).acos().to_degrees(),
+            )
+        };
+        let wrap = |lat: f64| {
+            if lat > 90.0 {
+                180.0 - lat
+            } else if lat < -90.0 {
+                -180.0 - lat
+            } else {
+                lat
+            }
+        };
+        let (lat1, lat2) = (wrap(u + v), wrap(u - v));
+        let delta_p = if (lat1 - lat_pole).abs() <= (lat2 - lat_pole).abs() {
+            lat1
+        } else {
+            lat2
+        };
+
+        let alpha_p = if cosd(delta0).abs() < 1.0e-12 {
+            // Celestial pole at the fiducial point
+            alpha0
+        } else if cosd(delta_p).abs() < 1.0e-12 {
+            // Native pole is a celestial pole
+            if delta_p > 0.0 {
+                alpha0 + phi_p - phi0 - 180.0
+            } else {
+                alpha0 - phi_p + phi0
+            }
+        } else {
+            let x = (sind(theta0) - sind(delta_p) * sind(delta0)) / (cosd(delta_p) * cosd(delta0));
+            let y = sind(phi_p - phi0) * cosd(theta0) / cosd(delta0);
+            alpha0 - y.atan2(x).to_degrees()
+        };
+        (alpha_p, delta_p, phi_p)
+    }
+
+    /// Convert pixel coordinates to world coordinates
+    ///
+    /// # Arguments
+    ///
+    /// * `pixel` - Zero-based pixel coordinates, one per axis.  Note that
+    ///   FITS keywords (CRPIXn) use one-based pixel coordinates
+    ///
+    /// # Returns
+    ///
+    /// World coordinates, one per axis.  Celestial coordinates are in
+    /// degrees, with longitude in range [0, 360)
+    ///
+    pub fn pixel_to_world(&self, pixel: &[f64]) -> Result<Vec<f64>, FITSError> {
+        let n = self.naxes();
+        if pixel.len() != n {
+            return Err(FITSError::from(WCSError::DimensionMismatch(n, pixel.len())));
+        }
+        let crpix = self.crpix.clone().unwrap_or_default();
+
+        let mut d = nalgebra::DVector::from_iterator(
+            n,
+            (0..n).map(|i| pixel[i] + 1.0 - crpix.get(i).copied().unwrap_or(0.0)),
+        );
+        if let Some(sip) = self.active_sip() {
+            (d[0], d[1]) = sip.apply(d[0], d[1]);
+        }
+        let q = self.linear_matrix() * d;
+
+        let mut world = (0..n)
+            .map(|i| self.nonlinear_world(i, q[i]))
+            .collect::<Result<Vec<f64>, _>>()?;
+        if let (Some((lon, lat)), Some(projection)) = (self.celestial_axes(), self.projection()?) {
+            let params = self.projection_params();
+            let (phi, theta) = projection
+                .deproject(q[lon], q[lat], &params)
+                .ok_or_else(|| {
+                    WCSError::InvalidCoordinate(format!("pixel {:?} outside projection", pixel))
+                })?;
+            let pole = self.celestial_pole(&projection, lon, lat);
+            let (alpha, delta) = native_to_celestial(phi, theta, pole);
+            world[lon] = alpha;
+            world[lat] = delta;
+        }
+        Ok(world)
+    }
+
+    /// Celestial coordinates of a pixel, as angles
+    ///
+    /// # Arguments
+    ///
+    /// * `pixel` - Zero-based pixel coordinates, one per axis
+    ///
+    /// # Returns
+    ///
+    /// Longitude and latitude, or None if there are no celestial axes
+    ///
+    pub fn pixel_to_angles(&self, pixel: &[f64]) -> Result<Option<(Angle, Angle)>, FITSError> {
+        let world = self.pixel_to_world(pixel)?;
+        Ok(self.celestial_axes().map(|(lon, lat)| {
+            (
+                Angle::from_degrees(world[lon]),
+                Angle::from_degrees(world[lat]),
+            )
+        }))
+    }
+
+    /// Celestial coordinates of a pixel, as sexagesimal text
+    ///
+    /// Right ascension is written in hours ("hh:mm:ss.s"); other
+    /// longitudes, and all latitudes, in signed degrees ("+dd:mm:ss.s")
+    ///
+    /// # Arguments
+    ///
+    /// * `pixel` - Zero-based pixel coordinates, one per axis
+    /// * `precision` - Decimals of the (arc)seconds; right ascension
+    ///   gets one more, as a second of time is 15 arcseconds
+    ///
+    /// # Returns
+    ///
+    /// Longitude and latitude text, or None if there are no celestial axes
+    ///
+    pub fn pixel_to_sexagesimal(
+        &self,
+        pixel: &[f64],
+        precision: usize,
+    ) -> Result<Option<(String, String)>, FITSError> {
+        let Some((lon, lat)) = self.pixel_to_angles(pixel)? else {
+            return Ok(None);
+        };
+        let is_ra = self
+            .celestial_axes()
+            .and_then(|(axis, _)| self.ctype.as_ref()?.get(axis))
+            .is_some_and(|c| c.starts_with("RA"));
+        let lon = match is_ra {
+            true => lon.to_hms(precision + 1),
+            false => lon.to_dms(precision),
+        };
+        Ok(Some((lon, lat.to_dms(precision))))
+    }
+
+    /// Convert world coordinates to pixel coordinates
+    ///
+    /// # Arguments
+    ///
+    /// * `world` - World coordinates, one per axis.  Celestial
+    ///   coordinates are in degrees
+    ///
+    /// # Returns
+    ///
+    /// Zero-based pixel coordinates, one per axis
+    ///
+    pub fn world_to_pixel(&self, world: &[f64]) -> Result<Vec<f64>, FITSError> {
+        let n = self.naxes();
+        if world.len() != n {
+            return Err(FITSError::from(WCSError::DimensionMismatch(n, world.len())));
+        }
+        let crpix = self.crpix.clone().unwrap_or_default();
+
+        let mut q = nalgebra::DVector::zeros(n);
+        for i in 0..n {
+            q[i] = self.nonlinear_intermediate(i, world[i])?;
+        }
+        if let (Some((lon, lat)), Some(projection)) = (self.celestial_axes(), self.projection()?) {
+            let pole = self.celestial_pole(&projection, lon, lat);
+            let (phi, theta) = celestial_to_native(world[lon], world[lat], pole);
+            let params = self.projection_params();
+            let (x, y) = projection.project(phi, theta, &params).ok_or_else(|| {
+                WCSError::InvalidCoordinate(format!("world {:?} cannot be projected", world))
+            })?;
+            q[lon] = x;
+            q[lat] = y;
+        }
+        let minv = self
+            .linear_matrix()
+            .try_inverse()
+            .ok_or(WCSError::SingularMatrix)?;
+        let mut d = minv * q;
+        if let Some(sip) = self.active_sip() {
+            (d[0], d[1]) = sip.invert(d[0], d[1]);
+        }
+        Ok((0..n)
+            .map(|i| d[i] - 1.0 + crpix.get(i).copied().unwrap_or(0.0))
+            .collect())
+    }
+}